@@ -0,0 +1,179 @@
+//! Equivalent classes from before and after Beyond Light
+//!
+//! Beyond Light renumbered most classes, so the same structure has a different class hash in Shadowkeep and earlier
+//! than in Beyond Light and later. The built-in mapping pairs the classes that have the same name in the Shadowkeep and
+//! Beyond Light class lists. More pairs can be added to [`CLASS_MAPPING_PATH`], one `<pre-BL class> <post-BL class>`
+//! pair (in hex) per line.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use arc_swap::ArcSwap;
+use log::{info, warn};
+use rustc_hash::FxHashMap;
+use tiger_pkg::{DestinyVersion, Endian, GameVersion, package_manager_checked};
+
+use crate::classes::{CLASSES_DESTINY_BL, CLASSES_DESTINY_SK};
+use crate::util::u32_from_endian;
+
+/// User-defined class pairs, next to schema.txt
+pub const CLASS_MAPPING_PATH: &str = "class_mapping.txt";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClassEra {
+    /// Destiny 2 up to Shadowkeep
+    PreBeyondLight,
+    /// Destiny 2 from Beyond Light onwards
+    PostBeyondLight,
+}
+
+impl ClassEra {
+    /// None for Destiny 1 and Marathon, which don't share classes with Destiny 2
+    pub fn of_version(version: GameVersion) -> Option<Self> {
+        match version {
+            GameVersion::Destiny(
+                DestinyVersion::Destiny2Beta
+                | DestinyVersion::Destiny2Forsaken
+                | DestinyVersion::Destiny2Shadowkeep,
+            ) => Some(ClassEra::PreBeyondLight),
+            GameVersion::Destiny(
+                DestinyVersion::Destiny2BeyondLight
+                | DestinyVersion::Destiny2WitchQueen
+                | DestinyVersion::Destiny2Lightfall
+                | DestinyVersion::Destiny2TheFinalShape,
+            ) => Some(ClassEra::PostBeyondLight),
+            _ => None,
+        }
+    }
+
+    /// Era of the loaded packages
+    pub fn current() -> Option<Self> {
+        Self::of_version(package_manager_checked().ok()?.version)
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            ClassEra::PreBeyondLight => ClassEra::PostBeyondLight,
+            ClassEra::PostBeyondLight => ClassEra::PreBeyondLight,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ClassEra::PreBeyondLight => "pre-Beyond Light",
+            ClassEra::PostBeyondLight => "post-Beyond Light",
+        }
+    }
+}
+
+#[derive(Default)]
+struct ClassMapping {
+    pre_to_post: FxHashMap<u32, u32>,
+    post_to_pre: FxHashMap<u32, u32>,
+}
+
+impl ClassMapping {
+    fn builtin() -> Self {
+        let mut mapping = Self::default();
+        for pre in CLASSES_DESTINY_SK {
+            if pre.name.starts_with("s_unk") {
+                continue;
+            }
+
+            if let Some(post) = CLASSES_DESTINY_BL.iter().find(|c| c.name == pre.name) {
+                mapping.insert(pre.id, post.id);
+            }
+        }
+
+        mapping
+    }
+
+    fn insert(&mut self, pre: u32, post: u32) {
+        self.pre_to_post.insert(pre, post);
+        self.post_to_pre.insert(post, pre);
+    }
+
+    fn to_era(&self, era: ClassEra) -> &FxHashMap<u32, u32> {
+        match era {
+            ClassEra::PreBeyondLight => &self.post_to_pre,
+            ClassEra::PostBeyondLight => &self.pre_to_post,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CLASS_MAPPING: ArcSwap<ClassMapping> = ArcSwap::new(Arc::new(ClassMapping::builtin()));
+}
+
+/// Reloads the built-in mapping and the pairs from [`CLASS_MAPPING_PATH`]
+pub fn load_class_mapping() {
+    let mut mapping = ClassMapping::builtin();
+    if let Ok(data) = std::fs::read_to_string(CLASS_MAPPING_PATH) {
+        let mut count = 0;
+        for (i, line) in data.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            match parse_pair(line) {
+                Ok((pre, post)) => {
+                    mapping.insert(pre, post);
+                    count += 1;
+                }
+                Err(e) => warn!("Skipping class mapping on line {}: {e}", i + 1),
+            }
+        }
+
+        info!("Loaded {count} class pairs from {CLASS_MAPPING_PATH}");
+    }
+
+    CLASS_MAPPING.store(Arc::new(mapping));
+}
+
+fn parse_pair(line: &str) -> anyhow::Result<(u32, u32)> {
+    let mut parts = line.split_whitespace();
+    let mut parse = |what: &str| -> anyhow::Result<u32> {
+        let s = parts
+            .next()
+            .with_context(|| format!("Missing {what} class"))?;
+        Ok(u32::from_str_radix(s.trim_start_matches("0x"), 16)?)
+    };
+
+    Ok((parse("pre-BL")?, parse("post-BL")?))
+}
+
+/// The class in the given era that is equivalent to a class from the other era
+pub fn equivalent_class(class: u32, era: ClassEra) -> Option<u32> {
+    CLASS_MAPPING.load().to_era(era).get(&class).copied()
+}
+
+/// Translates a class from the other era to the era of the loaded packages. Classes that are already from the current
+/// era, or that aren't in the mapping, are returned as-is
+pub fn to_current_era(class: u32) -> u32 {
+    let Some(era) = ClassEra::current() else {
+        return class;
+    };
+
+    let mapping = CLASS_MAPPING.load();
+    // Some hashes are used by both eras for different classes, those are assumed to be from the current era
+    if mapping.to_era(era.other()).contains_key(&class) {
+        return class;
+    }
+
+    mapping.to_era(era).get(&class).copied().unwrap_or(class)
+}
+
+/// Translates every (aligned) class hash in tag data from the other era to the current one, see [`to_current_era`]
+pub fn translate_classes(data: &mut [u8], endian: Endian) {
+    for chunk in data.chunks_exact_mut(4) {
+        let value = u32_from_endian(endian, chunk.try_into().unwrap());
+        let translated = to_current_era(value);
+        if translated != value {
+            chunk.copy_from_slice(&match endian {
+                Endian::Big => translated.to_be_bytes(),
+                Endian::Little => translated.to_le_bytes(),
+            });
+        }
+    }
+}
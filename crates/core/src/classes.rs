@@ -246,6 +246,7 @@ pub const CLASSES_MARATHON: &[TagClass] = &[];
 lazy_static::lazy_static! {
     static ref CLASS_MAP: ArcSwap<FxHashMap<u32, TagClass>> = ArcSwap::new(Default::default());
     static ref CLASS_MAP_FROM_FILE: ArcSwap<FxHashMap<u32, TagClass>> = ArcSwap::new(Default::default());
+    static ref ENUMS_FROM_FILE: ArcSwap<FxHashMap<u32, Vec<EnumField>>> = ArcSwap::new(Default::default());
     static ref REFRESHED_THIS_FRAME: AtomicBool = AtomicBool::new(false);
 }
 
@@ -263,8 +264,9 @@ pub fn load_schemafile() {
     };
 
     match parse_schemafile(&schemafile) {
-        Ok(o) => {
-            CLASS_MAP_FROM_FILE.store(Arc::new(o));
+        Ok((classes, enums)) => {
+            CLASS_MAP_FROM_FILE.store(Arc::new(classes));
+            ENUMS_FROM_FILE.store(Arc::new(enums));
             REFRESHED_THIS_FRAME.store(true, std::sync::atomic::Ordering::Relaxed);
         }
         Err(e) => {
@@ -277,16 +279,28 @@ pub fn was_schemafile_refreshed() -> bool {
     REFRESHED_THIS_FRAME.load(std::sync::atomic::Ordering::Relaxed)
 }
 
-fn parse_schemafile(s: &str) -> anyhow::Result<FxHashMap<u32, TagClass>> {
+type Schema = (FxHashMap<u32, TagClass>, FxHashMap<u32, Vec<EnumField>>);
+
+fn parse_schemafile(s: &str) -> anyhow::Result<Schema> {
     let mut schema: FxHashMap<u32, TagClass> = Default::default();
+    let mut enums: FxHashMap<u32, Vec<EnumField>> = Default::default();
 
     // schema.txt lines can either be formatted as:
     // 8080XXXX <name>
     // 8080XXXX <name> <size>
+    // enum 8080XXXX <offset> <u8/u16/u32> <value>=<name>...
+    // flags 8080XXXX <offset> <u8/u16/u32> <bit>=<name>...
     for l in s.lines() {
         if l.trim().is_empty() || l.starts_with("#") {
             continue;
         }
+        if is_enum_keyword(l) {
+            let field = EnumField::parse(l).with_context(|| format!("Invalid enum '{l}'"))?;
+            let fields = enums.entry(field.class).or_default();
+            fields.retain(|f| f.offset != field.offset);
+            fields.push(field);
+            continue;
+        }
         let mut parts = l.split_whitespace();
         let id = u32::from_str_radix(parts.next().context("Missing class ID")?, 16)?;
         let name = parts.next().context("Missing name")?;
@@ -307,7 +321,299 @@ fn parse_schemafile(s: &str) -> anyhow::Result<FxHashMap<u32, TagClass>> {
         );
     }
 
-    Ok(schema)
+    for fields in enums.values_mut() {
+        fields.sort_by_key(|f| f.offset);
+    }
+
+    Ok((schema, enums))
+}
+
+/// Width of an enum field
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnumType {
+    U8,
+    U16,
+    U32,
+}
+
+impl EnumType {
+    pub const ALL: [EnumType; 3] = [EnumType::U8, EnumType::U16, EnumType::U32];
+
+    pub fn size(self) -> usize {
+        match self {
+            EnumType::U8 => 1,
+            EnumType::U16 => 2,
+            EnumType::U32 => 4,
+        }
+    }
+
+    pub fn bits(self) -> u32 {
+        self.size() as u32 * 8
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EnumType::U8 => "u8",
+            EnumType::U16 => "u16",
+            EnumType::U32 => "u32",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == s)
+    }
+}
+
+/// Names for the values of a field in a class, declared in schema.txt
+#[derive(Clone, Debug)]
+pub struct EnumField {
+    pub class: u32,
+    /// Offset of the field in the class
+    pub offset: usize,
+    pub ty: EnumType,
+    /// Bit flags, the values are bit numbers instead of whole values
+    pub flags: bool,
+    /// Sorted by value
+    pub values: Vec<(u32, String)>,
+}
+
+impl EnumField {
+    // enum 8080XXXX 0x1C u32 0=none 1=primary
+    // flags 8080XXXX 0x20 u8 0=visible 3=enabled
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        let mut parts = line.split_whitespace();
+        let flags = parts.next() == Some("flags");
+        let class = u32::from_str_radix(parts.next().context("Missing class ID")?, 16)?;
+        let offset = parts.next().context("Missing offset")?;
+        let offset = usize::from_str_radix(offset.trim_start_matches("0x"), 16)?;
+        let ty = parts.next().context("Missing type")?;
+        let ty = EnumType::parse(ty).with_context(|| format!("Unknown enum type '{ty}'"))?;
+
+        let mut values = parts
+            .map(|v| {
+                let (value, name) = v.split_once('=').context("Expected <value>=<name>")?;
+                let value = match value.strip_prefix("0x") {
+                    Some(hex) => u32::from_str_radix(hex, 16)?,
+                    None => value.parse()?,
+                };
+                Ok((value, name.to_string()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        values.sort_by_key(|(value, _)| *value);
+        values.dedup_by_key(|(value, _)| *value);
+        if flags {
+            if let Some((bit, _)) = values.iter().find(|(bit, _)| *bit >= ty.bits()) {
+                anyhow::bail!("Bit {bit} doesn't fit in a {}", ty.name());
+            }
+        }
+
+        Ok(Self {
+            class,
+            offset,
+            ty,
+            flags,
+            values,
+        })
+    }
+
+    pub fn to_schema_line(&self) -> String {
+        let mut line = format!(
+            "{} {:08X} 0x{:X} {}",
+            if self.flags { "flags" } else { "enum" },
+            self.class,
+            self.offset,
+            self.ty.name()
+        );
+        for (value, name) in &self.values {
+            // Names are separated by whitespace in the schema file
+            let name = name.split_whitespace().collect::<Vec<_>>().join("_");
+            line.push_str(&format!(" {value}={name}"));
+        }
+        line
+    }
+
+    /// Reads the value of the field in a struct that starts at `struct_offset`
+    pub fn read(&self, data: &[u8], struct_offset: usize, endian: Endian) -> Option<u32> {
+        let start = struct_offset + self.offset;
+        let bytes = data.get(start..start + self.ty.size())?;
+        Some(match (self.ty, endian) {
+            (EnumType::U8, _) => bytes[0] as u32,
+            (EnumType::U16, Endian::Big) => u16::from_be_bytes(bytes.try_into().ok()?) as u32,
+            (EnumType::U16, Endian::Little) => u16::from_le_bytes(bytes.try_into().ok()?) as u32,
+            (EnumType::U32, Endian::Big) => u32::from_be_bytes(bytes.try_into().ok()?),
+            (EnumType::U32, Endian::Little) => u32::from_le_bytes(bytes.try_into().ok()?),
+        })
+    }
+
+    pub fn name_of(&self, value: u32) -> Option<&str> {
+        self.values
+            .binary_search_by_key(&value, |(v, _)| *v)
+            .ok()
+            .map(|i| self.values[i].1.as_str())
+    }
+
+    /// Name of the value, or the value itself when it doesn't have one. Flags are formatted as the names of the bits
+    /// that are set
+    pub fn format(&self, value: u32) -> String {
+        if self.flags {
+            if value == 0 {
+                return "none".to_string();
+            }
+            return self
+                .set_bits(value)
+                .map(|bit| match self.name_of(bit) {
+                    Some(name) => name.to_string(),
+                    None => format!("bit {bit}"),
+                })
+                .collect::<Vec<_>>()
+                .join(" | ");
+        }
+
+        match self.name_of(value) {
+            Some(name) => name.to_string(),
+            None => format!("{value} (unnamed)"),
+        }
+    }
+
+    pub fn set_bits(&self, value: u32) -> impl Iterator<Item = u32> {
+        (0..self.ty.bits()).filter(move |bit| value & (1 << bit) != 0)
+    }
+
+    /// One line per bit for flags: whether it's set, and its name. Bits without a name are only listed when set
+    pub fn bit_breakdown(&self, value: u32) -> String {
+        (0..self.ty.bits())
+            .rev()
+            .filter_map(|bit| {
+                let set = value & (1 << bit) != 0;
+                let name = self.name_of(bit);
+                if !set && name.is_none() {
+                    return None;
+                }
+
+                Some(format!(
+                    "{} {bit:>2} {}",
+                    if set { "■" } else { "□" },
+                    name.unwrap_or("(unnamed)")
+                ))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Enums declared for a class, sorted by offset
+pub fn get_enums(class: u32) -> Vec<EnumField> {
+    ENUMS_FROM_FILE
+        .load()
+        .get(&class)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub fn get_enum(class: u32, offset: usize) -> Option<EnumField> {
+    ENUMS_FROM_FILE
+        .load()
+        .get(&class)?
+        .iter()
+        .find(|f| f.offset == offset)
+        .cloned()
+}
+
+/// Every declared enum, sorted by class and offset
+pub fn all_enums() -> Vec<EnumField> {
+    let mut enums: Vec<EnumField> = ENUMS_FROM_FILE.load().values().flatten().cloned().collect();
+    enums.sort_by_key(|f| (f.class, f.offset));
+    enums
+}
+
+fn class_schema_line(class: &TagClass) -> String {
+    match class.size {
+        Some(size) => format!("{:08X} {} {size}", class.id, class.name),
+        None => format!("{:08X} {}", class.id, class.name),
+    }
+}
+
+fn is_enum_keyword(line: &str) -> bool {
+    line.starts_with("enum ") || line.starts_with("flags ")
+}
+
+fn is_enum_line(line: &str, class: u32, offset: usize) -> bool {
+    is_enum_keyword(line)
+        && EnumField::parse(line).is_ok_and(|f| f.class == class && f.offset == offset)
+}
+
+/// Writes schema.txt with the enum at (class, offset) replaced by `field`, or removed if it's None
+fn write_enum(class: u32, offset: usize, field: Option<&EnumField>) -> anyhow::Result<()> {
+    let schemafile = std::fs::read_to_string("schema.txt").unwrap_or_default();
+    let mut lines: Vec<String> = schemafile
+        .lines()
+        .filter(|l| !is_enum_line(l, class, offset))
+        .map(str::to_string)
+        .collect();
+    if let Some(field) = field {
+        lines.push(field.to_schema_line());
+    }
+
+    std::fs::write("schema.txt", lines.join("\n") + "\n").context("Failed to write schema.txt")?;
+    load_schemafile();
+    Ok(())
+}
+
+/// Adds an enum to schema.txt, replacing the one at the same class and offset
+pub fn save_enum(field: &EnumField) -> anyhow::Result<()> {
+    write_enum(field.class, field.offset, Some(field))
+}
+
+pub fn remove_enum(class: u32, offset: usize) -> anyhow::Result<()> {
+    write_enum(class, offset, None)
+}
+
+/// Writes the classes and enums from schema.txt to another file, so they can be shared
+pub fn export_schema(path: &std::path::Path) -> anyhow::Result<(usize, usize)> {
+    let class_map = CLASS_MAP_FROM_FILE.load();
+    let enums = all_enums();
+
+    let mut lines = vec!["# quicktag schema".to_string()];
+    let mut classes: Vec<&TagClass> = class_map.values().collect();
+    classes.sort_by_key(|c| c.id);
+    for class in &classes {
+        lines.push(class_schema_line(class));
+    }
+    lines.extend(enums.iter().map(EnumField::to_schema_line));
+
+    std::fs::write(path, lines.join("\n") + "\n")?;
+    Ok((classes.len(), enums.len()))
+}
+
+/// Merges the classes and enums from an exported schema into schema.txt. Classes that are already defined are kept,
+/// enums replace the ones at the same class and offset. Returns the amount of classes and enums that were imported
+pub fn import_schema(path: &std::path::Path) -> anyhow::Result<(usize, usize)> {
+    let (classes, enums) = parse_schemafile(&std::fs::read_to_string(path)?)?;
+    let enums: Vec<EnumField> = enums.into_values().flatten().collect();
+    let existing = CLASS_MAP_FROM_FILE.load();
+
+    let schemafile = std::fs::read_to_string("schema.txt").unwrap_or_default();
+    let mut lines: Vec<String> = schemafile
+        .lines()
+        .filter(|l| !enums.iter().any(|f| is_enum_line(l, f.class, f.offset)))
+        .map(str::to_string)
+        .collect();
+
+    let mut imported_classes = 0;
+    let mut classes: Vec<TagClass> = classes.into_values().collect();
+    classes.sort_by_key(|c| c.id);
+    for class in &classes {
+        if existing.contains_key(&class.id) {
+            continue;
+        }
+        imported_classes += 1;
+        lines.push(class_schema_line(class));
+    }
+    lines.extend(enums.iter().map(EnumField::to_schema_line));
+
+    std::fs::write("schema.txt", lines.join("\n") + "\n").context("Failed to write schema.txt")?;
+    load_schemafile();
+    Ok((imported_classes, enums.len()))
 }
 
 pub fn initialize_reference_names() {
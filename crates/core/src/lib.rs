@@ -1,3 +1,5 @@
+pub mod class_mapping;
 pub mod classes;
+pub mod palette;
 pub mod tagtypes;
 pub mod util;
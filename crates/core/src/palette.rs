@@ -0,0 +1,148 @@
+//! Colors used for highlights and tag types, in a palette picked by the user
+//!
+//! The default palette tells things apart by red/green and gold/gray, which look the same to a lot of people with a
+//! color vision deficiency. The red-green safe palette is based on the Okabe-Ito palette, which stays distinguishable
+//! with deuteranopia and protanopia. The high contrast palette uses bright, saturated colors.
+//!
+//! The colors themselves are only available with the `egui` feature.
+
+use std::sync::RwLock;
+
+#[cfg(feature = "egui")]
+use epaint::Color32;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Palette {
+    #[default]
+    Default,
+    RedGreenSafe,
+    HighContrast,
+}
+
+/// What a color is used for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PaletteColor {
+    /// Tag references in the hex view
+    TagReference,
+    /// Bytes without a known meaning in the hex view
+    RawBytes,
+    AsciiText,
+    UnicodeText,
+    /// Data that differs from a compared version of a tag
+    Difference,
+    /// The offset linked to with `hash@offset`
+    LinkedOffset,
+    Selection,
+    Position,
+    Quaternion,
+
+    Texture,
+    Buffer,
+    Shader,
+    Audio,
+    /// Havok, fonts and videos
+    OtherAsset,
+    TagGlobal,
+    Tag,
+    UnknownType,
+}
+
+static PALETTE: RwLock<Palette> = RwLock::new(Palette::Default);
+
+pub fn set_palette(palette: Palette) {
+    *PALETTE.write().unwrap() = palette;
+}
+
+pub fn palette() -> Palette {
+    *PALETTE.read().unwrap()
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [
+        Palette::Default,
+        Palette::RedGreenSafe,
+        Palette::HighContrast,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Palette::Default => "Default",
+            Palette::RedGreenSafe => "Red-green safe (deuteranopia/protanopia)",
+            Palette::HighContrast => "High contrast",
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    pub fn color(self, color: PaletteColor) -> Color32 {
+        use PaletteColor::*;
+
+        match self {
+            Palette::Default => match color {
+                TagReference => Color32::GOLD,
+                RawBytes => Color32::GRAY,
+                AsciiText => Color32::from_rgb(90, 120, 255),
+                UnicodeText => Color32::from_rgb(120, 220, 160),
+                Difference => Color32::from_rgb(255, 60, 60),
+                LinkedOffset => Color32::LIGHT_RED,
+                Selection => Color32::from_rgb(90, 120, 255),
+                Position => Color32::from_rgb(130, 200, 130),
+                Quaternion => Color32::from_rgb(200, 150, 230),
+                Texture => Color32::GREEN,
+                Buffer => Color32::LIGHT_BLUE,
+                Shader => Color32::from_rgb(249, 168, 71),
+                Audio => Color32::from_rgb(191, 106, 247),
+                OtherAsset => Color32::YELLOW,
+                TagGlobal => Color32::WHITE,
+                Tag => Color32::GRAY,
+                UnknownType => Color32::LIGHT_RED,
+            },
+            Palette::RedGreenSafe => match color {
+                TagReference => Color32::from_rgb(240, 228, 66),
+                RawBytes => Color32::GRAY,
+                AsciiText => Color32::from_rgb(86, 180, 233),
+                UnicodeText => Color32::from_rgb(230, 159, 0),
+                Difference => Color32::from_rgb(213, 94, 0),
+                LinkedOffset => Color32::from_rgb(204, 121, 167),
+                Selection => Color32::from_rgb(0, 114, 178),
+                Position => Color32::from_rgb(86, 180, 233),
+                Quaternion => Color32::from_rgb(230, 159, 0),
+                Texture => Color32::from_rgb(86, 180, 233),
+                Buffer => Color32::from_rgb(0, 114, 178),
+                Shader => Color32::from_rgb(230, 159, 0),
+                Audio => Color32::from_rgb(204, 121, 167),
+                OtherAsset => Color32::from_rgb(240, 228, 66),
+                TagGlobal => Color32::WHITE,
+                Tag => Color32::GRAY,
+                UnknownType => Color32::from_rgb(213, 94, 0),
+            },
+            Palette::HighContrast => match color {
+                TagReference => Color32::from_rgb(255, 255, 0),
+                RawBytes => Color32::from_rgb(220, 220, 220),
+                AsciiText => Color32::from_rgb(0, 255, 255),
+                UnicodeText => Color32::from_rgb(255, 0, 255),
+                Difference => Color32::from_rgb(255, 0, 0),
+                LinkedOffset => Color32::WHITE,
+                Selection => Color32::from_rgb(0, 128, 255),
+                Position => Color32::from_rgb(0, 255, 0),
+                Quaternion => Color32::from_rgb(255, 128, 255),
+                Texture => Color32::from_rgb(0, 255, 0),
+                Buffer => Color32::from_rgb(0, 200, 255),
+                Shader => Color32::from_rgb(255, 160, 0),
+                Audio => Color32::from_rgb(255, 0, 255),
+                OtherAsset => Color32::from_rgb(255, 255, 0),
+                TagGlobal => Color32::WHITE,
+                Tag => Color32::from_rgb(200, 200, 200),
+                UnknownType => Color32::from_rgb(255, 80, 80),
+            },
+        }
+    }
+}
+
+impl PaletteColor {
+    #[cfg(feature = "egui")]
+    /// The color in the current palette
+    pub fn color(self) -> Color32 {
+        palette().color(self)
+    }
+}
@@ -1,8 +1,36 @@
 use std::fmt::Display;
+use std::sync::Arc;
 
+use arc_swap::ArcSwap;
+#[cfg(feature = "egui")]
 use epaint::Color32;
+use rustc_hash::FxHashMap;
 use tiger_pkg::{DestinyVersion, GameVersion, MarathonVersion, package_manager};
 
+#[cfg(feature = "egui")]
+use crate::palette::PaletteColor;
+
+/// User-defined name and color for a type/subtype combination that isn't recognized by quicktag
+#[derive(Clone)]
+pub struct CustomTagType {
+    pub name: String,
+    /// RGB
+    pub color: [u8; 3],
+}
+
+lazy_static::lazy_static! {
+    static ref CUSTOM_TAG_TYPES: ArcSwap<FxHashMap<(u8, u8), CustomTagType>> = ArcSwap::new(Default::default());
+}
+
+/// Replaces the user-defined mappings for unknown type/subtype combinations
+pub fn set_custom_tagtypes(types: FxHashMap<(u8, u8), CustomTagType>) {
+    CUSTOM_TAG_TYPES.store(Arc::new(types));
+}
+
+pub fn get_custom_tagtype(ftype: u8, fsubtype: u8) -> Option<CustomTagType> {
+    CUSTOM_TAG_TYPES.load().get(&(ftype, fsubtype)).cloned()
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum TagType {
     TextureOld,
@@ -79,34 +107,46 @@ impl TagType {
         matches!(self, TagType::WwiseBank | TagType::WwiseStream)
     }
 
+    #[cfg(feature = "egui")]
     pub fn display_color(&self) -> Color32 {
-        match self {
+        let color = match self {
             TagType::TextureOld
             | TagType::Texture2D { .. }
             | TagType::TextureCube { .. }
             | TagType::Texture3D { .. }
             | TagType::TextureSampler { .. }
-            | TagType::TextureLargeBuffer => Color32::GREEN,
+            | TagType::TextureLargeBuffer => PaletteColor::Texture,
 
             TagType::VertexBuffer { .. }
             | TagType::IndexBuffer { .. }
-            | TagType::ConstantBuffer { .. } => Color32::LIGHT_BLUE,
+            | TagType::ConstantBuffer { .. } => PaletteColor::Buffer,
 
             TagType::PixelShader { .. }
             | TagType::VertexShader { .. }
             | TagType::GeometryShader { .. }
-            | TagType::ComputeShader { .. } => Color32::from_rgb(249, 168, 71),
+            | TagType::ComputeShader { .. } => PaletteColor::Shader,
 
             TagType::WwiseInitBank | TagType::WwiseBank | TagType::WwiseStream => {
-                Color32::from_rgb(191, 106, 247)
+                PaletteColor::Audio
+            }
+            TagType::Havok | TagType::OtfFontOrUmbraTome | TagType::CriwareUsm => {
+                PaletteColor::OtherAsset
             }
-            TagType::Havok | TagType::OtfFontOrUmbraTome | TagType::CriwareUsm => Color32::YELLOW,
 
-            TagType::TagGlobal => Color32::WHITE,
-            TagType::Tag => Color32::GRAY,
+            TagType::TagGlobal => PaletteColor::TagGlobal,
+            TagType::Tag => PaletteColor::Tag,
 
-            TagType::Unknown { .. } => Color32::LIGHT_RED,
-        }
+            TagType::Unknown { ftype, fsubtype } => {
+                if let Some(custom) = get_custom_tagtype(*ftype, *fsubtype) {
+                    let [r, g, b] = custom.color;
+                    return Color32::from_rgb(r, g, b);
+                }
+
+                PaletteColor::UnknownType
+            }
+        };
+
+        color.color()
     }
 
     pub fn all_filterable() -> &'static [Self] {
@@ -193,7 +233,11 @@ impl Display for TagType {
             TagType::OtfFontOrUmbraTome => f.write_str("OTF Font / Umbra Tome"),
             TagType::CriwareUsm => f.write_str("CriwareUsm"),
             TagType::Unknown { ftype, fsubtype } => {
-                f.write_fmt(format_args!("Unk{ftype}+{fsubtype}"))
+                if let Some(custom) = get_custom_tagtype(*ftype, *fsubtype) {
+                    f.write_str(&custom.name)
+                } else {
+                    f.write_fmt(format_args!("Unk{ftype}+{fsubtype}"))
+                }
             }
         }
     }
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use tiger_pkg::Endian;
 
 pub const FNV1_BASE: u32 = 0x811c9dc5;
@@ -8,6 +9,39 @@ pub fn fnv1(data: &[u8]) -> u32 {
     })
 }
 
+/// CRC-32 (IEEE), as used by zlib
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |mut crc, b| {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB88320 & (crc & 1).wrapping_neg());
+        }
+        crc
+    })
+}
+
+/// Algorithms that names can be hashed with, see `quicktag_scanner::namespaces`
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    /// The string hash
+    #[default]
+    Fnv1,
+    /// FNV-1 of the lowercase name, used for Wwise IDs
+    Fnv1Lowercase,
+    Crc32,
+}
+
+impl HashAlgorithm {
+    pub fn hash(self, name: &str) -> u32 {
+        match self {
+            HashAlgorithm::Fnv1 => fnv1(name.as_bytes()),
+            HashAlgorithm::Fnv1Lowercase => fnv1(name.to_lowercase().as_bytes()),
+            HashAlgorithm::Crc32 => crc32(name.as_bytes()),
+        }
+    }
+}
+
 #[inline(always)]
 pub fn u64_from_endian(endian: Endian, bytes: [u8; 8]) -> u64 {
     match endian {
@@ -23,3 +57,11 @@ pub fn u32_from_endian(endian: Endian, bytes: [u8; 4]) -> u32 {
         Endian::Little => u32::from_le_bytes(bytes),
     }
 }
+
+#[inline(always)]
+pub fn u16_from_endian(endian: Endian, bytes: [u8; 2]) -> u16 {
+    match endian {
+        Endian::Big => u16::from_be_bytes(bytes),
+        Endian::Little => u16::from_le_bytes(bytes),
+    }
+}
@@ -1,112 +1,265 @@
-use std::{fs::File, io::Read, path::Path, time::SystemTime};
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
-use super::ScanResult;
+use super::{ScanResult, migrations, video::ScannedVideo};
 
 use log::{error, info, warn};
 use rustc_hash::FxHashMap;
-use tiger_pkg::{TagHash, package_manager};
+use tiger_pkg::{TagHash, manager::PackagePath, package_manager};
 
-#[derive(bincode::Encode, bincode::Decode)]
+#[derive(Clone, bincode::Encode, bincode::Decode)]
 pub struct TagCache {
     /// Timestamp of the packages directory
     pub timestamp: u64,
 
     pub version: u32,
 
+    /// Oldest cache version that can read this cache, see [`TagCache::MIN_READER_VERSION`]
+    pub min_reader_version: u32,
+
     pub hashes: FxHashMap<TagHash, ScanResult>,
+
+    /// Bink/CRI USM video streams found while scanning
+    pub videos: Vec<ScannedVideo>,
+
+    /// Package files the cache was built from, by package ID. Used to only rescan the packages that changed when the
+    /// packages directory is updated
+    pub packages: FxHashMap<u16, PackageStamp>,
+
+    /// [`crate::context::ScannerContext::known_hashes_stamp`] of the last scan
+    pub known_hashes_stamp: u64,
+    /// Set when only the changed packages were rescanned after the known string hashes changed. The other packages
+    /// can be missing string hashes until the cache is regenerated
+    pub stale_hashes: bool,
 }
 
 impl TagCache {
-    pub const VERSION: u32 = 7;
+    pub const VERSION: u32 = 15;
+
+    /// Oldest version of quicktag (by [`TagCache::VERSION`]) that can still decode caches written by this one. Only
+    /// keep this below [`TagCache::VERSION`] when a new version appends fields to the end of the cache and leaves the
+    /// existing ones alone, otherwise it must be the same as [`TagCache::VERSION`]
+    pub const MIN_READER_VERSION: u32 = 15;
+
+    /// Modification time of the packages directory in seconds, which is what [`TagCache::timestamp`] is compared to
+    pub fn packages_timestamp() -> u64 {
+        std::fs::metadata(&package_manager().package_dir)
+            .ok()
+            .and_then(|m| {
+                Some(
+                    m.modified()
+                        .ok()?
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .ok()?
+                        .as_secs(),
+                )
+            })
+            .unwrap_or(0)
+    }
+
+    /// Estimated amount of memory used by the cache, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.hashes.capacity() * std::mem::size_of::<(TagHash, ScanResult)>()
+            + self
+                .hashes
+                .values()
+                .map(ScanResult::heap_size)
+                .sum::<usize>()
+            + self.videos.capacity() * std::mem::size_of::<ScannedVideo>()
+    }
+
+    /// Packages that were added or changed since the cache was built, or that have been removed since
+    pub fn changed_packages(&self, current: &FxHashMap<u16, PackageStamp>) -> Vec<u16> {
+        let mut changed: Vec<u16> = current
+            .iter()
+            .filter(|(pkg_id, stamp)| self.packages.get(pkg_id) != Some(stamp))
+            .chain(
+                self.packages
+                    .iter()
+                    .filter(|(pkg_id, _)| !current.contains_key(pkg_id)),
+            )
+            .map(|(pkg_id, _)| *pkg_id)
+            .collect();
+        changed.sort_unstable();
+        changed.dedup();
+        changed
+    }
+
+    /// Removes the tags and videos of the given packages, returning the scanned tags that were removed
+    ///
+    /// References are cleared from the remaining entries and entries that were only referenced are dropped, as
+    /// references are rebuilt after the changed packages have been scanned again
+    pub(crate) fn remove_packages(&mut self, pkg_ids: &[u16]) -> Vec<TagHash> {
+        let mut removed = vec![];
+        self.hashes.retain(|tag, scan| {
+            if !scan.scanned {
+                return false;
+            }
+
+            if pkg_ids.contains(&tag.pkg_id()) {
+                removed.push(*tag);
+                return false;
+            }
+
+            scan.references.clear();
+            true
+        });
+        self.videos
+            .retain(|video| !pkg_ids.contains(&video.tag.pkg_id()));
+        for pkg_id in pkg_ids {
+            self.packages.remove(pkg_id);
+        }
+
+        removed
+    }
+
+    /// Checks whether the packages directory has been modified after the given cache timestamp
+    fn is_outdated(timestamp: u64) -> bool {
+        let current_pkg_timestamp = Self::packages_timestamp();
+
+        if timestamp < current_pkg_timestamp {
+            info!(
+                "Packages have changed since the cache was built (cache: {}, package dir: {})",
+                chrono::DateTime::from_timestamp(timestamp as i64, 0)
+                    .unwrap()
+                    .format("%Y-%m-%d"),
+                chrono::DateTime::from_timestamp(current_pkg_timestamp as i64, 0)
+                    .unwrap()
+                    .format("%Y-%m-%d"),
+            );
+
+            true
+        } else {
+            false
+        }
+    }
 
     pub fn load(path: impl AsRef<Path>) -> anyhow::Result<CacheLoadResult> {
-        if let Ok(cache_file) = File::open(&path) {
-            info!("Existing cache file found, loading");
-
-            let cache_data = zstd::Decoder::new(cache_file).and_then(|mut r| {
-                let mut buf = vec![];
-                r.read_to_end(&mut buf)?;
-                Ok(buf)
-            });
-
-            match cache_data {
-                Ok(cache_data) => {
-                    if let Ok((cache, _)) = bincode::decode_from_slice::<Self, _>(
-                        &cache_data,
-                        bincode::config::standard(),
-                    ) {
-                        match cache.version.cmp(&Self::VERSION) {
-                            std::cmp::Ordering::Equal => {
-                                let current_pkg_timestamp =
-                                    std::fs::metadata(&package_manager().package_dir)
-                                        .ok()
-                                        .and_then(|m| {
-                                            Some(
-                                                m.modified()
-                                                    .ok()?
-                                                    .duration_since(SystemTime::UNIX_EPOCH)
-                                                    .ok()?
-                                                    .as_secs(),
-                                            )
-                                        })
-                                        .unwrap_or(0);
-
-                                if cache.timestamp < current_pkg_timestamp {
-                                    info!(
-                                        "Cache is out of date, rebuilding (cache: {}, package dir: {})",
-                                        chrono::DateTime::from_timestamp(cache.timestamp as i64, 0)
-                                            .unwrap()
-                                            .format("%Y-%m-%d"),
-                                        chrono::DateTime::from_timestamp(
-                                            current_pkg_timestamp as i64,
-                                            0
-                                        )
-                                        .unwrap()
-                                        .format("%Y-%m-%d"),
-                                    );
-
-                                    Ok(CacheLoadResult::Rebuild)
-                                } else {
-                                    Ok(CacheLoadResult::Loaded(cache))
-                                }
-                            }
-                            std::cmp::Ordering::Less => {
-                                info!(
-                                    "Cache is out of date, rebuilding (cache: {}, quicktag: {})",
-                                    cache.version,
-                                    TagCache::default().version
-                                );
-                                Ok(CacheLoadResult::Rebuild)
-                            }
-                            std::cmp::Ordering::Greater => {
-                                error!(
-                                    "Tried to open a future version cache with an old quicktag version (cache: {}, quicktag: {})",
-                                    cache.version,
-                                    TagCache::default().version
-                                );
-
-                                native_dialog::MessageDialog::new()
-                                    .set_type(native_dialog::MessageType::Error)
-                                    .set_title("Future cache")
-                                    .set_text(&format!("Your cache file ({}) is newer than this build of quicktag\n\nCache version: v{}\nExpected version: v{}", path.as_ref().display(), cache.version, Self::default().version))
-                                    .show_alert()
-                                    .unwrap();
-
-                                std::process::exit(21);
-                            }
-                        }
-                    } else {
-                        warn!("Cache file is corrupt, creating a new one");
+        let Ok(cache_file) = File::open(&path) else {
+            return Ok(CacheLoadResult::Rebuild);
+        };
+
+        info!("Existing cache file found, loading");
+        let cache_data = match zstd::Decoder::new(cache_file).and_then(|mut r| {
+            let mut buf = vec![];
+            r.read_to_end(&mut buf)?;
+            Ok(buf)
+        }) {
+            Ok(cache_data) => cache_data,
+            Err(e) => {
+                error!("Failed to load cache file, creating a new one: {e}");
+                return Ok(CacheLoadResult::Rebuild);
+            }
+        };
+
+        let Ok((header, _)) =
+            bincode::decode_from_slice::<CacheHeader, _>(&cache_data, bincode::config::standard())
+        else {
+            warn!("Cache file is corrupt, creating a new one");
+            return Ok(CacheLoadResult::Rebuild);
+        };
+
+        // Packages have been updated since the cache was built. Older caches don't know which packages they were built
+        // from, so there's no point in loading or migrating them
+        let outdated = header.version <= Self::VERSION && Self::is_outdated(header.timestamp);
+        if outdated && header.version < Self::VERSION {
+            return Ok(CacheLoadResult::Rebuild);
+        }
+
+        match header.version.cmp(&Self::VERSION) {
+            std::cmp::Ordering::Equal => {
+                let Ok((cache, _)) =
+                    bincode::decode_from_slice::<Self, _>(&cache_data, bincode::config::standard())
+                else {
+                    warn!("Cache file is corrupt, creating a new one");
+                    return Ok(CacheLoadResult::Rebuild);
+                };
+
+                if outdated {
+                    Ok(CacheLoadResult::Outdated(cache))
+                } else {
+                    Ok(CacheLoadResult::Loaded(cache))
+                }
+            }
+            std::cmp::Ordering::Less if migrations::can_migrate(header.version) => {
+                match migrations::migrate(header.version, cache_data) {
+                    Ok(cache) => Ok(CacheLoadResult::Migrated(cache)),
+                    Err(e) => {
+                        error!("Failed to migrate cache, rebuilding: {e:?}");
                         Ok(CacheLoadResult::Rebuild)
                     }
                 }
-                Err(e) => {
-                    error!("Failed to load cache file, creating a new one: {e}");
-                    Ok(CacheLoadResult::Rebuild)
-                }
             }
-        } else {
-            Ok(CacheLoadResult::Rebuild)
+            std::cmp::Ordering::Less => {
+                info!(
+                    "Cache is out of date, rebuilding (cache: {}, quicktag: {})",
+                    header.version,
+                    Self::VERSION
+                );
+                Ok(CacheLoadResult::Rebuild)
+            }
+            std::cmp::Ordering::Greater => {
+                error!(
+                    "Tried to open a future version cache with an old quicktag version (cache: {}, quicktag: {})",
+                    header.version,
+                    Self::VERSION
+                );
+
+                // Only trust newer caches that say they can still be read by this version, a cache that decodes
+                // fine can still mean something different
+                let compatible = bincode::decode_from_slice::<CompatHeader, _>(
+                    &cache_data,
+                    bincode::config::standard(),
+                )
+                .is_ok_and(|(h, _)| h.min_reader_version <= Self::VERSION);
+                let readable = compatible
+                    .then(|| {
+                        bincode::decode_from_slice::<Self, _>(
+                            &cache_data,
+                            bincode::config::standard(),
+                        )
+                        .ok()
+                    })
+                    .flatten()
+                    .map(|(cache, _)| cache);
+
+                Ok(CacheLoadResult::Future(FutureCache {
+                    path: path.as_ref().to_path_buf(),
+                    version: header.version,
+                    readable,
+                }))
+            }
+        }
+    }
+
+    /// Decodes a compressed cache that didn't come from the local cache file, eg. one shared over the network
+    ///
+    /// Older versions are migrated. Unlike [`TagCache::load`], the timestamp isn't checked against the local packages
+    pub fn decode_compressed(data: &[u8]) -> anyhow::Result<Self> {
+        let cache_data = zstd::decode_all(data)?;
+        let (header, _) =
+            bincode::decode_from_slice::<CacheHeader, _>(&cache_data, bincode::config::standard())?;
+
+        match header.version.cmp(&Self::VERSION) {
+            std::cmp::Ordering::Equal => {
+                let (cache, _) = bincode::decode_from_slice::<Self, _>(
+                    &cache_data,
+                    bincode::config::standard(),
+                )?;
+                Ok(cache)
+            }
+            std::cmp::Ordering::Less if migrations::can_migrate(header.version) => {
+                migrations::migrate(header.version, cache_data)
+            }
+            _ => anyhow::bail!(
+                "Unsupported cache version (cache: {}, quicktag: {})",
+                header.version,
+                Self::VERSION
+            ),
         }
     }
 }
@@ -116,12 +269,91 @@ impl Default for TagCache {
         Self {
             timestamp: 0,
             version: Self::VERSION,
+            min_reader_version: Self::MIN_READER_VERSION,
             hashes: Default::default(),
+            videos: Default::default(),
+            packages: Default::default(),
+            known_hashes_stamp: 0,
+            stale_hashes: false,
         }
     }
 }
 
 pub enum CacheLoadResult {
     Loaded(TagCache),
+    /// An older cache that was upgraded to the current version, and should be written back to disk
+    Migrated(TagCache),
+    /// The packages have been updated since the cache was built, only the packages that changed have to be rescanned
+    Outdated(TagCache),
     Rebuild,
+    /// The cache was written by a newer version of quicktag
+    Future(FutureCache),
+}
+
+/// Leading fields of [`TagCache`], which can be read regardless of the cache version
+#[derive(bincode::Decode)]
+struct CacheHeader {
+    timestamp: u64,
+    version: u32,
+}
+
+/// [`CacheHeader`] for caches from v15 on, which record the oldest version that can read them
+#[derive(bincode::Decode)]
+struct CompatHeader {
+    _timestamp: u64,
+    _version: u32,
+    min_reader_version: u32,
+}
+
+pub struct FutureCache {
+    pub path: PathBuf,
+    pub version: u32,
+    /// The decoded cache, if the newer format is still readable by this version
+    pub readable: Option<TagCache>,
+}
+
+impl FutureCache {
+    /// Moves the cache file out of the way so a new one can be built, returning the new path
+    pub fn rename_aside(&self) -> std::io::Result<PathBuf> {
+        let mut new_path = self.path.clone().into_os_string();
+        new_path.push(format!(".v{}.bak", self.version));
+        let new_path = PathBuf::from(new_path);
+
+        std::fs::rename(&self.path, &new_path)?;
+        Ok(new_path)
+    }
+}
+
+/// Identifies the version of a package file
+#[derive(Clone, PartialEq, Debug, bincode::Encode, bincode::Decode)]
+pub struct PackageStamp {
+    /// File name of the package, which includes the patch ID
+    pub filename: String,
+    /// Modification time in seconds
+    pub modified: u64,
+    pub size: u64,
+}
+
+impl PackageStamp {
+    pub fn read(path: &PackagePath) -> Self {
+        let metadata = std::fs::metadata(&path.path).ok();
+        Self {
+            filename: path.filename.clone(),
+            modified: metadata
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs()),
+            size: metadata.map_or(0, |m| m.len()),
+        }
+    }
+}
+
+/// Stamps of the current package files with the given IDs
+pub fn package_stamps(pkg_ids: impl IntoIterator<Item = u16>) -> FxHashMap<u16, PackageStamp> {
+    let package_paths = &package_manager().package_paths;
+    pkg_ids
+        .into_iter()
+        .filter_map(|pkg_id| Some((pkg_id, PackageStamp::read(package_paths.get(&pkg_id)?))))
+        .collect()
 }
@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use binrw::Endian;
 use itertools::Itertools;
 use log::info;
@@ -5,18 +7,30 @@ use quicktag_strings::{
     localized::{StringCache, create_stringmap},
     wordlist::load_wordlist,
 };
+use rustc_hash::FxHasher;
 use tiger_pkg::{PackageManager, TagHash, TagHash64, Version};
 
-// Shareable read-only context
+use crate::namespaces::{hash_namespaces, load_hash_namespaces};
+
+/// Shareable read-only context, passed to [`crate::scan_file`]
+///
+/// All hash lists are sorted so they can be binary searched
 pub struct ScannerContext {
     pub valid_file_hashes: Vec<TagHash>,
     pub valid_file_hashes64: Vec<TagHash64>,
     pub known_string_hashes: Vec<u32>,
     pub known_wordlist_hashes: Vec<u32>,
+    /// Namespace IDs and their known hashes, see [`crate::namespaces`]
+    pub known_namespace_hashes: Vec<(String, Vec<u32>)>,
     pub endian: Endian,
 }
 
 impl ScannerContext {
+    /// Builds the context for the packages loaded by the given package manager
+    ///
+    /// Known string hashes come from the localized strings of the languages set through
+    /// [`quicktag_strings::localized::set_string_languages`], plus imported cracked hashes. Hash namespaces are
+    /// reloaded as well
     pub fn create(package_manager: &PackageManager) -> anyhow::Result<Self> {
         info!("Creating scanner context");
 
@@ -35,6 +49,8 @@ impl ScannerContext {
             entry.push(s.to_string());
         });
 
+        load_hash_namespaces();
+
         let mut res = Self {
             valid_file_hashes: package_manager
                 .lookup
@@ -56,6 +72,10 @@ impl ScannerContext {
                 .collect(),
             known_string_hashes: stringmap.keys().cloned().collect(),
             known_wordlist_hashes: wordlist.keys().cloned().collect(),
+            known_namespace_hashes: hash_namespaces()
+                .iter()
+                .map(|n| (n.id.clone(), n.known_hashes()))
+                .collect(),
             endian,
         };
 
@@ -66,4 +86,22 @@ impl ScannerContext {
 
         Ok(res)
     }
+
+    /// Order-independent hash of the known string, wordlist and namespace hashes. A cache scanned with a different
+    /// stamp can be missing hashes that are known now
+    pub fn known_hashes_stamp(&self) -> u64 {
+        fn sum(list: &str, hashes: &[u32]) -> u64 {
+            hashes.iter().fold(0u64, |stamp, hash| {
+                let mut hasher = FxHasher::default();
+                (list, hash).hash(&mut hasher);
+                stamp.wrapping_add(hasher.finish())
+            })
+        }
+
+        self.known_namespace_hashes.iter().fold(
+            sum("strings", &self.known_string_hashes)
+                .wrapping_add(sum("wordlist", &self.known_wordlist_hashes)),
+            |stamp, (id, hashes)| stamp.wrapping_add(sum(id, hashes)),
+        )
+    }
 }
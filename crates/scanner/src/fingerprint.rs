@@ -0,0 +1,69 @@
+use quicktag_core::classes::get_class_by_id;
+
+/// Array sequences longer than this are truncated when comparing, to keep comparisons cheap
+const MAX_COMPARED_ARRAYS: usize = 64;
+
+/// Structural fingerprint of a tag, used to find tags with a similar layout
+#[derive(Clone, Default, bincode::Encode, bincode::Decode, Debug)]
+pub struct StructFingerprint {
+    /// Class IDs of the arrays in the tag, in order of appearance
+    pub array_classes: Vec<u32>,
+    /// Element size of each array, or 0 if the class size is unknown
+    pub array_strides: Vec<u32>,
+    /// log2 of the tag size
+    pub size_bucket: u8,
+}
+
+impl StructFingerprint {
+    pub fn new(data_len: usize) -> Self {
+        Self {
+            size_bucket: data_len.max(1).ilog2() as u8,
+            ..Default::default()
+        }
+    }
+
+    pub fn push_array(&mut self, class: u32) {
+        self.array_classes.push(class);
+        self.array_strides
+            .push(get_class_by_id(class).and_then(|c| c.size).unwrap_or(0) as u32);
+    }
+
+    /// Returns a similarity score between 0.0 and 1.0
+    pub fn similarity(&self, other: &Self) -> f32 {
+        let a = &self.array_classes[..self.array_classes.len().min(MAX_COMPARED_ARRAYS)];
+        let b = &other.array_classes[..other.array_classes.len().min(MAX_COMPARED_ARRAYS)];
+
+        let sequence = if a.is_empty() && b.is_empty() {
+            1.0
+        } else {
+            2.0 * longest_common_subsequence(a, b) as f32 / (a.len() + b.len()) as f32
+        };
+
+        let strides = if self.array_strides == other.array_strides {
+            1.0
+        } else {
+            0.0
+        };
+
+        let size = 1.0 - (self.size_bucket.abs_diff(other.size_bucket) as f32 / 4.0).min(1.0);
+
+        sequence * 0.6 + strides * 0.1 + size * 0.3
+    }
+}
+
+fn longest_common_subsequence(a: &[u32], b: &[u32]) -> usize {
+    let mut previous = vec![0; b.len() + 1];
+    let mut current = vec![0; b.len() + 1];
+    for x in a {
+        for (j, y) in b.iter().enumerate() {
+            current[j + 1] = if x == y {
+                previous[j] + 1
+            } else {
+                current[j].max(previous[j + 1])
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
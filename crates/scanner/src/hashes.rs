@@ -0,0 +1,228 @@
+use std::{
+    cmp::Reverse,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use itertools::Itertools;
+use quicktag_core::util::u32_from_endian;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{TagHash, package_manager};
+
+use crate::{ScannedHash, TagCache, context::ScannerContext, find_arrays};
+
+/// FNV-1 offset basis, which is what hashing an empty string results in
+pub const FNV1_EMPTY: u32 = 0x811c9dc5;
+
+/// Checks whether a value could plausibly be an FNV-1 string hash
+///
+/// FNV-1 output is evenly distributed, so this mostly rules out values that are very likely to be something else:
+/// small or negative integers, values with an empty upper or lower half (packed shorts/flags) and floats in a sane range.
+/// This also rejects a fraction of actual hashes, so treat results as an estimate
+pub fn is_hash_candidate(value: u32) -> bool {
+    if value == FNV1_EMPTY || value & 0xFFFF == 0 || value >> 16 == 0 || value >> 16 == 0xFFFF {
+        return false;
+    }
+
+    let float = f32::from_bits(value).abs();
+    if float.is_finite() && (1e-5..=1e7).contains(&float) {
+        return false;
+    }
+
+    true
+}
+
+/// How many hash-looking values in a tag resolve to known strings
+#[derive(Default, Clone, Copy)]
+pub struct StringHashStats {
+    /// Values that are a known string or wordlist hash
+    pub known: u32,
+    /// Hash candidates that aren't known
+    pub unknown: u32,
+}
+
+impl StringHashStats {
+    pub fn total(&self) -> u32 {
+        self.known + self.unknown
+    }
+
+    /// Fraction of candidates that resolved to a known string, 0.0 if there are no candidates
+    pub fn hit_rate(&self) -> f32 {
+        if self.total() == 0 {
+            0.0
+        } else {
+            self.known as f32 / self.total() as f32
+        }
+    }
+
+    pub fn add(&mut self, other: StringHashStats) {
+        self.known += other.known;
+        self.unknown += other.unknown;
+    }
+}
+
+/// Calls `f` for every aligned 32-bit value in the tag that isn't skipped by the scanner (arrays of @block_tags classes)
+pub fn for_each_scanned_value(context: &ScannerContext, data: &[u8], mut f: impl FnMut(u64, u32)) {
+    let blocked_ranges: Vec<_> = find_arrays(context.endian, data)
+        .iter()
+        .filter_map(|a| a.blocked_range())
+        .collect();
+
+    for offset in (0..data.len().saturating_sub(3)).step_by(4) {
+        let offset = offset as u64;
+        if blocked_ranges.iter().any(|range| range.contains(&offset)) {
+            continue;
+        }
+
+        let m: [u8; 4] = data[offset as usize..offset as usize + 4]
+            .try_into()
+            .unwrap();
+        f(offset, u32_from_endian(context.endian, m));
+    }
+}
+
+/// Checks whether the value is a known string or wordlist hash
+pub fn is_known_string_hash(context: &ScannerContext, value: u32) -> bool {
+    value != FNV1_EMPTY
+        && (context.known_string_hashes.binary_search(&value).is_ok()
+            || context.known_wordlist_hashes.binary_search(&value).is_ok())
+}
+
+/// Checks whether a value looks like an FNV-1 hash, but doesn't resolve to a string or tag
+pub fn is_unknown_hash_candidate(context: &ScannerContext, value: u32) -> bool {
+    is_hash_candidate(value)
+        && !is_known_string_hash(context, value)
+        && !(TagHash(value).is_pkg_file()
+            && context
+                .valid_file_hashes
+                .binary_search(&TagHash(value))
+                .is_ok())
+}
+
+pub fn string_hash_stats(context: &ScannerContext, data: &[u8]) -> StringHashStats {
+    let mut stats = StringHashStats::default();
+    for_each_scanned_value(context, data, |_, value| {
+        if is_known_string_hash(context, value) {
+            stats.known += 1;
+        } else if is_unknown_hash_candidate(context, value) {
+            stats.unknown += 1;
+        }
+    });
+
+    stats
+}
+
+static HARVEST_UNKNOWN_HASHES: AtomicBool = AtomicBool::new(false);
+
+/// Enables recording unknown hash candidates while building the tag cache
+pub fn set_harvest_unknown_hashes(enabled: bool) {
+    HARVEST_UNKNOWN_HASHES.store(enabled, Ordering::Relaxed);
+}
+
+pub fn harvest_enabled() -> bool {
+    HARVEST_UNKNOWN_HASHES.load(Ordering::Relaxed)
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct HarvestedHash {
+    /// Total amount of times the value was found
+    pub occurrences: u32,
+    /// Amount of tags the value was found in
+    pub tags: u32,
+}
+
+pub type HashHarvest = FxHashMap<u32, HarvestedHash>;
+
+/// Records every unknown hash candidate in the tag
+pub fn harvest_unknown_hashes(context: &ScannerContext, data: &[u8], harvest: &mut HashHarvest) {
+    let mut found = vec![];
+    for_each_scanned_value(context, data, |_, value| {
+        if is_unknown_hash_candidate(context, value) {
+            found.push(value);
+        }
+    });
+
+    found.sort_unstable();
+    for (value, occurrences) in found.into_iter().dedup_with_count() {
+        let h = harvest.entry(value).or_default();
+        h.occurrences += occurrences as u32;
+        h.tags += 1;
+    }
+}
+
+pub fn merge_harvest(into: &mut HashHarvest, from: HashHarvest) {
+    for (value, h) in from {
+        let entry = into.entry(value).or_default();
+        entry.occurrences += h.occurrences;
+        entry.tags += h.tags;
+    }
+}
+
+/// Writes the harvested hashes as CSV, most common first
+///
+/// Values found in a single tag are more likely to be noise, so they're kept at the bottom of the list
+pub fn write_harvest(path: &Path, harvest: &HashHarvest) -> anyhow::Result<()> {
+    let mut f = BufWriter::new(File::create(path)?);
+    writeln!(f, "hash,occurrences,tags")?;
+    for (value, h) in harvest
+        .iter()
+        .sorted_by_key(|(value, h)| (Reverse(h.tags), Reverse(h.occurrences), **value))
+    {
+        writeln!(f, "{value:08X},{},{}", h.occurrences, h.tags)?;
+    }
+
+    Ok(())
+}
+
+/// Adds string hash matches for newly known hashes to existing scan results, without a full rescan
+///
+/// Only the string hash check is repeated, so this is a lot faster than rebuilding the cache. Returns the amount of new matches
+pub fn reannotate_string_hashes(
+    context: &ScannerContext,
+    cache: &mut TagCache,
+    new_hashes: &[u32],
+) -> usize {
+    let mut new_hashes = new_hashes.to_vec();
+    new_hashes.sort_unstable();
+    new_hashes.dedup();
+    if new_hashes.is_empty() {
+        return 0;
+    }
+
+    cache
+        .hashes
+        .par_iter_mut()
+        .map(|(tag, scan)| {
+            if !scan.successful || !scan.scanned {
+                return 0;
+            }
+
+            let Ok(data) = package_manager().read_tag(*tag) else {
+                return 0;
+            };
+
+            let mut added = 0;
+            for_each_scanned_value(context, &data, |offset, value| {
+                if value != FNV1_EMPTY
+                    && new_hashes.binary_search(&value).is_ok()
+                    && !scan.string_hashes.iter().any(|h| h.offset == offset)
+                {
+                    scan.string_hashes.push(ScannedHash {
+                        offset,
+                        hash: value,
+                    });
+                    added += 1;
+                }
+            });
+
+            if added > 0 {
+                scan.string_hashes.sort_by_key(|h| h.offset);
+            }
+
+            added
+        })
+        .sum()
+}
@@ -1,5 +1,34 @@
+//! Tag scanning engine behind quicktag, usable without the GUI
+//!
+//! Everything here expects the global package manager from `tiger_pkg` to be initialized first
+//! (`tiger_pkg::initialize`), as tags are read through it.
+//!
+//! - [`ScannerContext::create`] builds the lookup tables used for scanning: valid tag hashes, known string and
+//!   wordlist hashes and the hashes of every hash namespace ([`namespaces`]). Creating one reads every
+//!   localized string, so reuse it where possible
+//! - [`scan_file`] scans the data of a single tag for tag references, string hashes and raw strings.
+//!   [`find_arrays`] and [`array_data_layout`] can be used to look at the structure of tag data
+//! - [`load_tag_cache`] loads the cache for the current packages (see [`cache_path`]), building it first if needed.
+//!   [`TagCache::load`] loads a cache from any path without building one, and [`save_tag_cache_to`] writes one
+//! - [`pattern_search`] searches the data of every tag for a byte pattern or value, which the cache doesn't index
+//! - [`merge`] combines partial caches built with a restricted [`scope`] into one
+//! - [`scanner_progress`] reports what the scanner is doing, for showing progress while a cache is built
+//!
+//! Scanner settings (compression, [`string_runs`], [`nested_strings`], [`hashes`], [`namespaces`], [`scope`]) are global
+//! and should be set before building a cache.
+
 pub mod cache;
 pub mod context;
+pub mod fingerprint;
+pub mod hashes;
+pub mod merge;
+mod migrations;
+pub mod namespaces;
+pub mod nested_strings;
+pub mod pattern_search;
+pub mod scope;
+pub mod string_runs;
+pub mod video;
 
 pub use cache::TagCache;
 
@@ -7,55 +36,99 @@ use std::{
     fmt::Display,
     fs::File,
     io::{Cursor, Read, Seek, SeekFrom},
+    ops::Range,
     path::{Path, PathBuf},
     sync::Arc,
-    time::SystemTime,
 };
 
 use binrw::BinReaderExt;
-use cache::CacheLoadResult;
+use cache::{CacheLoadResult, FutureCache};
 use context::ScannerContext;
+use fingerprint::StructFingerprint;
+use hashes::HashHarvest;
 use itertools::Itertools;
-use log::{error, info};
-use parking_lot::RwLock;
+use log::{error, info, warn};
+use parking_lot::{Mutex, RwLock};
 use quicktag_core::{
     classes::get_class_by_id,
     tagtypes::TagType,
     util::{u32_from_endian, u64_from_endian},
 };
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use tiger_pkg::{
-    DestinyVersion, GameVersion, TagHash, TagHash64, Version, package::UEntryHeader,
-    package_manager,
+    DestinyVersion, Endian, GameVersion, Package, TagHash, TagHash64, Version,
+    manager::PackagePath, package::UEntryHeader, package_manager,
 };
+use video::{ScannedVideo, VideoContainer};
 
 #[derive(Clone, bincode::Encode, bincode::Decode, Debug)]
 pub struct ScanResult {
     /// Were we able to read the tag data?
     pub successful: bool,
+    /// Whether the scanner read this tag, rather than only adding it because scanned tags reference it
+    pub scanned: bool,
 
     pub file_hashes: Vec<ScannedHash<TagHash>>,
     pub file_hashes64: Vec<ScannedHash<TagHash64>>,
     pub string_hashes: Vec<ScannedHash<u32>>,
     pub wordlist_hashes: Vec<ScannedHash<u32>>,
-    pub raw_strings: Vec<String>,
+    /// Hashes of names in a hash namespace, by namespace ID
+    pub namespace_hashes: FxHashMap<String, Vec<ScannedHash<u32>>>,
+    pub raw_strings: Vec<ScannedString>,
 
     /// References from other files
     pub references: Vec<TagHash>,
+
+    pub fingerprint: StructFingerprint,
 }
 
 impl Default for ScanResult {
     fn default() -> Self {
         ScanResult {
             successful: true,
+            scanned: false,
             file_hashes: Default::default(),
             file_hashes64: Default::default(),
             string_hashes: Default::default(),
             wordlist_hashes: Default::default(),
+            namespace_hashes: Default::default(),
             raw_strings: Default::default(),
             references: Default::default(),
+            fingerprint: Default::default(),
+        }
+    }
+}
+
+impl ScanResult {
+    /// Estimated amount of heap memory used by the result, in bytes
+    pub fn heap_size(&self) -> usize {
+        fn vec_size<T>(v: &Vec<T>) -> usize {
+            v.capacity() * std::mem::size_of::<T>()
         }
+
+        vec_size(&self.file_hashes)
+            + vec_size(&self.file_hashes64)
+            + vec_size(&self.string_hashes)
+            + vec_size(&self.wordlist_hashes)
+            + self
+                .namespace_hashes
+                .iter()
+                .map(|(id, hashes)| {
+                    std::mem::size_of::<(String, Vec<ScannedHash<u32>>)>()
+                        + id.capacity()
+                        + vec_size(hashes)
+                })
+                .sum::<usize>()
+            + vec_size(&self.raw_strings)
+            + self
+                .raw_strings
+                .iter()
+                .map(|s| s.string.capacity())
+                .sum::<usize>()
+            + vec_size(&self.references)
+            + vec_size(&self.fingerprint.array_classes)
+            + vec_size(&self.fingerprint.array_strides)
     }
 }
 
@@ -65,28 +138,40 @@ pub struct ScannedHash<T: Sized + bincode::Encode + bincode::Decode<()>> {
     pub hash: T,
 }
 
+#[derive(Clone, bincode::Encode, bincode::Decode, Debug)]
+pub struct ScannedString {
+    pub offset: u64,
+    pub string: String,
+}
+
 pub struct ScannedArray {
     pub offset: u64,
     pub count: usize,
     pub class: u32,
 }
 
-pub fn scan_file(context: &ScannerContext, data: &[u8], mode: ScannerMode) -> ScanResult {
-    profiling::scope!(
-        "scan_file",
-        format!("data len = {} bytes", data.len()).as_str()
-    );
+impl ScannedArray {
+    /// The range covered by the array data if its class is marked with @block_tags
+    pub fn blocked_range(&self) -> Option<Range<u64>> {
+        let class = get_class_by_id(self.class)?;
+        if !class.block_tags {
+            return None;
+        }
 
-    let mut r = ScanResult::default();
+        let array_size = class.array_size(self.count).unwrap_or(self.count);
+        Some(self.offset..self.offset + array_size as u64)
+    }
+}
 
-    // Pass 1: find array ranges we should skip (classes marked with @block_tags)
-    let mut blocked_ranges = vec![];
+/// Finds array headers in tag data. The offset of each array points to the data right after the array marker
+pub fn find_arrays(endian: Endian, data: &[u8]) -> Vec<ScannedArray> {
+    let mut arrays = vec![];
     for offset in (0..data.len()).step_by(4) {
         if offset + 4 > data.len() {
             break;
         }
         let m: [u8; 4] = data[offset..offset + 4].try_into().unwrap();
-        let value = u32_from_endian(context.endian, m);
+        let value = u32_from_endian(endian, m);
 
         if matches!(
             value,
@@ -112,16 +197,68 @@ pub fn scan_file(context: &ScannerContext, data: &[u8], mode: ScannerMode) -> Sc
             })();
 
             if let Some((count, class)) = array {
-                if let Some(class) = get_class_by_id(class) {
-                    if class.block_tags {
-                        let array_size = class.array_size(count as usize).unwrap_or(count as usize);
-                        blocked_ranges.push(array_offset..array_offset + array_size as u64);
-                    }
-                }
+                arrays.push(ScannedArray {
+                    offset: array_offset,
+                    count: count as usize,
+                    class,
+                });
             }
         }
     }
 
+    arrays
+}
+
+/// Data offset, element count and element size of `arrays[index]`
+///
+/// The element size comes from the class if it's known, otherwise it's derived from the space between this
+/// array and the next one
+pub fn array_data_layout(
+    data: &[u8],
+    arrays: &[ScannedArray],
+    index: usize,
+) -> Option<(usize, usize, usize)> {
+    let array = &arrays[index];
+    let data_start = array.offset as usize + 16;
+    // The next array starts 4 bytes before its offset (the array marker)
+    let end = arrays
+        .get(index + 1)
+        .map(|a| a.offset as usize - 4)
+        .unwrap_or(data.len())
+        .min(data.len());
+    if array.count == 0 || data_start >= end {
+        return None;
+    }
+
+    let stride = get_class_by_id(array.class)
+        .and_then(|c| c.size)
+        .unwrap_or((end - data_start) / array.count);
+    if stride == 0 || data_start + stride * array.count > end {
+        return None;
+    }
+
+    Some((data_start, array.count, stride))
+}
+
+pub fn scan_file(context: &ScannerContext, data: &[u8], mode: ScannerMode) -> ScanResult {
+    profiling::scope!(
+        "scan_file",
+        format!("data len = {} bytes", data.len()).as_str()
+    );
+
+    let mut r = ScanResult {
+        scanned: true,
+        fingerprint: StructFingerprint::new(data.len()),
+        ..Default::default()
+    };
+
+    // Pass 1: find array ranges we should skip (classes marked with @block_tags)
+    let mut blocked_ranges = vec![];
+    for array in find_arrays(context.endian, data) {
+        r.fingerprint.push_array(array.class);
+        blocked_ranges.extend(array.blocked_range());
+    }
+
     // Pass 2: everything else
     for offset in (0..data.len()).step_by(4) {
         if offset + 4 > data.len() {
@@ -152,7 +289,7 @@ pub fn scan_file(context: &ScannerContext, data: &[u8], mode: ScannerMode) -> Sc
                 r.raw_strings.extend(
                     read_raw_string_blob(data, offset as u64)
                         .into_iter()
-                        .map(|(_, s)| s),
+                        .map(|(offset, string)| ScannedString { offset, string }),
                 );
             }
         }
@@ -171,6 +308,18 @@ pub fn scan_file(context: &ScannerContext, data: &[u8], mode: ScannerMode) -> Sc
             });
         }
 
+        for (namespace, known) in &context.known_namespace_hashes {
+            if known.binary_search(&value).is_ok() {
+                r.namespace_hashes
+                    .entry(namespace.clone())
+                    .or_default()
+                    .push(ScannedHash {
+                        offset: offset as u64,
+                        hash: value,
+                    });
+            }
+        }
+
         if (offset % 8) == 0 && offset + 8 <= data.len() {
             let m: [u8; 8] = data[offset..offset + 8].try_into().unwrap();
             let value64 = u64_from_endian(context.endian, m);
@@ -189,6 +338,18 @@ pub fn scan_file(context: &ScannerContext, data: &[u8], mode: ScannerMode) -> Sc
         }
     }
 
+    if mode != ScannerMode::Tags && string_runs::string_runs_enabled() {
+        profiling::scope!("find string runs");
+        // Blob strings are found again as UTF-8 runs
+        let known: FxHashSet<u64> = r.raw_strings.iter().map(|s| s.offset).collect();
+        r.raw_strings.extend(
+            string_runs::find_string_runs(data)
+                .into_iter()
+                .filter(|(offset, _)| !known.contains(offset))
+                .map(|(offset, string)| ScannedString { offset, string }),
+        );
+    }
+
     if mode == ScannerMode::Hashes {
         r.file_hashes.clear();
         r.file_hashes64.clear();
@@ -255,11 +416,21 @@ pub enum ScanStatus {
     Scanning {
         current_package: usize,
         total_packages: usize,
+        /// Only the packages that changed since the cache was built are being scanned
+        incremental: bool,
+    },
+    NestedStrings {
+        level: u32,
+        tags: usize,
     },
     TransformGathering,
     TransformApplying,
     WritingCache,
     LoadingCache,
+    MigratingCache {
+        from: u32,
+        to: u32,
+    },
 }
 
 impl Display for ScanStatus {
@@ -270,10 +441,22 @@ impl Display for ScanStatus {
             ScanStatus::Scanning {
                 current_package,
                 total_packages,
+                incremental: false,
             } => f.write_fmt(format_args!(
                 "Creating new cache {}/{}",
                 current_package, total_packages
             )),
+            ScanStatus::Scanning {
+                current_package,
+                total_packages,
+                incremental: true,
+            } => f.write_fmt(format_args!(
+                "Updating cache, rescanning changed packages {}/{}",
+                current_package, total_packages
+            )),
+            ScanStatus::NestedStrings { level, tags } => f.write_fmt(format_args!(
+                "Scanning nested string blobs (level {level}, {tags} tags)"
+            )),
             ScanStatus::TransformGathering => {
                 f.write_str("Transforming cache (gathering references)")
             }
@@ -282,46 +465,298 @@ impl Display for ScanStatus {
             }
             ScanStatus::WritingCache => f.write_str("Writing cache"),
             ScanStatus::LoadingCache => f.write_str("Loading cache"),
+            ScanStatus::MigratingCache { from, to } => {
+                f.write_fmt(format_args!("Migrating cache (v{from} to v{to})"))
+            }
+        }
+    }
+}
+
+/// zstd settings used when writing the tag cache
+#[derive(Clone, Copy, Debug)]
+pub struct CacheCompression {
+    pub level: i32,
+    /// Amount of compression worker threads, 0 uses every available core
+    pub threads: u32,
+}
+
+impl Default for CacheCompression {
+    fn default() -> Self {
+        Self {
+            level: 3,
+            threads: 0,
         }
     }
 }
 
+impl CacheCompression {
+    fn worker_count(&self) -> u32 {
+        if self.threads == 0 {
+            std::thread::available_parallelism()
+                .map(|n| n.get() as u32)
+                .unwrap_or(1)
+        } else {
+            self.threads
+        }
+    }
+}
+
+/// Called with a freshly built cache and the path it was written to
+pub type CacheBuiltCallback = Box<dyn Fn(&TagCache, &Path) + Send + Sync>;
+
+/// Called for every tag scanned while building a cache, with the tag, the class of its entry and its data. Called from
+/// the scanner threads, so it has to be cheap to share
+pub type TagAnalyzer = Box<dyn Fn(TagHash, u32, &[u8]) + Send + Sync>;
+
 lazy_static::lazy_static! {
     static ref SCANNER_PROGRESS: RwLock<ScanStatus> = RwLock::new(ScanStatus::None);
+    static ref CACHE_COMPRESSION: RwLock<CacheCompression> = RwLock::new(CacheCompression::default());
+    static ref CACHE_BUILT_CALLBACKS: RwLock<Vec<CacheBuiltCallback>> = RwLock::new(vec![]);
+    static ref TAG_ANALYZER: RwLock<Option<TagAnalyzer>> = RwLock::new(None);
+    static ref CACHE_DIRECTORY: RwLock<Option<PathBuf>> = RwLock::new(None);
 }
 
 pub fn scanner_progress() -> ScanStatus {
     *SCANNER_PROGRESS.read()
 }
 
+/// Non-blocking variant of [`scanner_progress`], returns None if the status is currently being updated
+pub fn try_scanner_progress() -> Option<ScanStatus> {
+    SCANNER_PROGRESS.try_read().map(|s| *s)
+}
+
+pub fn set_cache_compression(compression: CacheCompression) {
+    let range = zstd::compression_level_range();
+    *CACHE_COMPRESSION.write() = CacheCompression {
+        level: compression.level.clamp(*range.start(), *range.end()),
+        ..compression
+    };
+}
+
+/// Registers a function that's called after a new tag cache has been built (or updated) and written to disk
+///
+/// Caches that are loaded or migrated don't trigger the callbacks
+pub fn add_cache_built_callback(callback: impl Fn(&TagCache, &Path) + Send + Sync + 'static) {
+    CACHE_BUILT_CALLBACKS.write().push(Box::new(callback));
+}
+
+/// Sets the function that's run on every tag while building a cache, see [`TagAnalyzer`]
+pub fn set_tag_analyzer(analyzer: Option<TagAnalyzer>) {
+    *TAG_ANALYZER.write() = analyzer;
+}
+
+/// Sets the directory that tag caches (and other files derived from the packages) are stored in. The directory of the
+/// executable is used if none is set
+pub fn set_cache_directory(dir: Option<PathBuf>) {
+    if let Some((dir, Err(e))) = dir.as_ref().map(|d| (d, std::fs::create_dir_all(d))) {
+        warn!("Failed to create cache directory {}: {e}", dir.display());
+    }
+
+    *CACHE_DIRECTORY.write() = dir;
+}
+
+/// Directory that tag caches are stored in, see [`set_cache_directory`]
+pub fn cache_directory() -> PathBuf {
+    CACHE_DIRECTORY.read().clone().unwrap_or_else(|| {
+        std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf()
+    })
+}
+
+/// Path of the cache for the current packages and [`scope::scan_scope`]
 pub fn cache_path() -> PathBuf {
-    let cache_name = format!("tags_{}.cache", package_manager().cache_key());
-    exe_relative_path(&cache_name)
+    let cache_name = match scope::scan_scope().cache_suffix() {
+        Some(suffix) => format!("tags_{}_{suffix}.cache", package_manager().cache_key()),
+        None => format!("tags_{}.cache", package_manager().cache_key()),
+    };
+    cache_relative_path(&cache_name)
 }
 
-pub fn load_tag_cache() -> TagCache {
+/// Loads the tag cache, or builds a new one if it doesn't exist or is outdated
+///
+/// When the packages have been updated since the cache was built, only the packages that changed are scanned again
+/// and merged into the existing cache. Unknown hashes aren't harvested (see [`hashes`]) when updating a cache, as the
+/// harvest covers every package.
+///
+/// Returns an error when the cache was written by a newer version of quicktag, so the user can decide what to do with it
+pub fn load_tag_cache() -> Result<TagCache, FutureCache> {
     let cache_file_path = cache_path();
+    scope::set_loaded_scope(scope::scan_scope());
 
-    if let Ok(CacheLoadResult::Loaded(cache)) = TagCache::load(&cache_file_path) {
-        return cache;
-    }
+    let outdated_cache = match TagCache::load(&cache_file_path) {
+        Ok(CacheLoadResult::Loaded(cache)) => return Ok(cache),
+        Ok(CacheLoadResult::Migrated(cache)) => {
+            write_tag_cache(&cache, &cache_file_path);
+            return Ok(cache);
+        }
+        Ok(CacheLoadResult::Outdated(cache)) => Some(cache),
+        Ok(CacheLoadResult::Future(future_cache)) => return Err(future_cache),
+        _ => None,
+    };
 
     *SCANNER_PROGRESS.write() = ScanStatus::CreatingScanner;
     let scanner_context = Arc::new(
         ScannerContext::create(&package_manager()).expect("Failed to create scanner context"),
     );
 
-    let all_pkgs = package_manager()
+    let scan_scope = scope::scan_scope();
+    let scope_pkgs = package_manager()
         .package_paths
-        .values()
-        .cloned()
+        .iter()
+        .filter(|(_, path)| scan_scope.includes(path))
+        .map(|(pkg_id, path)| (*pkg_id, path.clone()))
+        .collect_vec();
+    if scan_scope.is_partial() {
+        info!(
+            "Building a partial cache of {} out of {} packages",
+            scope_pkgs.len(),
+            package_manager().package_paths.len()
+        );
+    }
+
+    let stamps = cache::package_stamps(scope_pkgs.iter().map(|(pkg_id, _)| *pkg_id));
+    let mut cache: FxHashMap<TagHash, ScanResult> = Default::default();
+    let mut videos = vec![];
+    // Scanned tags of changed packages that aren't scanned again by themselves, like nested string blobs
+    let mut removed_tags = vec![];
+    let mut changed_pkgs = scope_pkgs.iter().map(|(pkg_id, _)| *pkg_id).collect_vec();
+    let known_hashes_stamp = scanner_context.known_hashes_stamp();
+    let mut stale_hashes = false;
+    let incremental = match outdated_cache {
+        Some(mut old_cache) if !old_cache.packages.is_empty() => {
+            changed_pkgs = old_cache.changed_packages(&stamps);
+            info!(
+                "{} of {} packages changed since the cache was built, rescanning only those",
+                changed_pkgs.len(),
+                stamps.len()
+            );
+
+            // Unchanged packages keep the string hashes they were scanned with, hashes that became known since are
+            // only found in them by a full rebuild
+            stale_hashes =
+                old_cache.stale_hashes || old_cache.known_hashes_stamp != known_hashes_stamp;
+            if stale_hashes {
+                warn!(
+                    "Known string hashes changed since the cache was built, unchanged packages may be missing string hashes until the cache is regenerated"
+                );
+            }
+
+            removed_tags = old_cache.remove_packages(&changed_pkgs);
+            cache = old_cache.hashes;
+            videos = old_cache.videos;
+            true
+        }
+        _ => false,
+    };
+
+    let pkgs_to_scan = scope_pkgs
+        .into_iter()
+        .filter(|(pkg_id, _)| changed_pkgs.contains(pkg_id))
+        .map(|(_, path)| path)
         .collect_vec();
 
+    let nested_string_refs: Option<Mutex<FxHashSet<TagHash>>> =
+        (nested_strings::nested_string_depth() > 0).then(Default::default);
+    if incremental && hashes::harvest_enabled() {
+        info!("Skipping the unknown hash harvest, it needs a full rescan");
+    }
+    let harvest: Option<Mutex<HashHarvest>> =
+        (hashes::harvest_enabled() && !incremental).then(Default::default);
+
+    let (scanned, scanned_videos) = scan_packages(
+        &scanner_context,
+        &pkgs_to_scan,
+        incremental,
+        nested_string_refs.as_ref(),
+        harvest.as_ref(),
+    );
+    cache.extend(scanned);
+    videos.extend(scanned_videos);
+
+    if let Some(nested_string_refs) = nested_string_refs {
+        let mut nested_string_refs = nested_string_refs.into_inner();
+        nested_string_refs.extend(
+            removed_tags
+                .into_iter()
+                .filter(|tag| package_manager().get_entry(*tag).is_some()),
+        );
+        nested_strings::scan_nested_strings(&scanner_context, &mut cache, nested_string_refs);
+    }
+
+    let mut cache = transform_tag_cache(cache);
+    cache.videos = videos;
+    cache.videos.sort_by_key(|v| v.tag.0);
+    cache.packages = stamps;
+    cache.known_hashes_stamp = known_hashes_stamp;
+    cache.stale_hashes = stale_hashes;
+
+    if let Some(harvest) = harvest {
+        let harvest = harvest.into_inner();
+        let path = cache_relative_path(format!(
+            "unknown_hashes_{}.csv",
+            package_manager().cache_key()
+        ));
+        match hashes::write_harvest(&path, &harvest) {
+            Ok(()) => info!(
+                "Wrote {} harvested unknown hashes to {}",
+                harvest.len(),
+                path.display()
+            ),
+            Err(e) => error!("Failed to write harvested hashes: {e:?}"),
+        }
+    }
+
+    write_tag_cache(&cache, &cache_file_path);
+
+    for callback in CACHE_BUILT_CALLBACKS.read().iter() {
+        callback(&cache, &cache_file_path);
+    }
+
+    Ok(cache)
+}
+
+/// Entries of a package that are scanned for the tag cache, sorted by starting block index to optimize sequential block
+/// reads
+pub(crate) fn scannable_entries(
+    pkg: &dyn Package,
+    version: GameVersion,
+) -> Vec<(usize, UEntryHeader)> {
+    let mut entries: Vec<(usize, UEntryHeader)> = pkg
+        .entries()
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| {
+            let tagtype =
+                TagType::from_type_subtype_for_version(version, e.file_type, e.file_subtype);
+            matches!(
+                tagtype,
+                TagType::Tag | TagType::TagGlobal | TagType::WwiseInitBank | TagType::WwiseBank // WWise banks are included to allow for reverse hash lookup
+            )
+        })
+        .map(|(i, e)| (i, e.clone()))
+        .collect();
+
+    entries.sort_by_key(|v| v.1.starting_block);
+    entries
+}
+
+/// Scans the tags and videos in the given packages
+fn scan_packages(
+    scanner_context: &Arc<ScannerContext>,
+    packages: &[PackagePath],
+    incremental: bool,
+    nested_string_refs: Option<&Mutex<FxHashSet<TagHash>>>,
+    harvest: Option<&Mutex<HashHarvest>>,
+) -> (FxHashMap<TagHash, ScanResult>, Vec<ScannedVideo>) {
     let version = package_manager().version;
-    let package_count = all_pkgs.len();
-    let cache: FxHashMap<TagHash, ScanResult> = all_pkgs
+    let package_count = packages.len();
+    let videos: Mutex<Vec<ScannedVideo>> = Default::default();
+    let cache: FxHashMap<TagHash, ScanResult> = packages
         .par_iter()
-        .map_with(scanner_context, |context, path| {
+        .map_with(scanner_context.clone(), |context, path| {
             profiling::scope!("scan_pkg", &path.path);
             let current_package = {
                 let mut p = SCANNER_PROGRESS.write();
@@ -337,6 +772,7 @@ pub fn load_tag_cache() -> TagCache {
                 *p = ScanStatus::Scanning {
                     current_package: current_package + 1,
                     total_packages: package_count,
+                    incremental,
                 };
 
                 current_package
@@ -348,31 +784,12 @@ pub fn load_tag_cache() -> TagCache {
                 version.open(&path.path).unwrap()
             };
 
-            let mut all_tags: Vec<(usize, UEntryHeader)> = pkg
-                .entries()
-                .iter()
-                .enumerate()
-                .filter(|(_, e)| {
-                    let tagtype = TagType::from_type_subtype_for_version(
-                        version,
-                        e.file_type,
-                        e.file_subtype,
-                    );
-                    matches!(
-                        tagtype,
-                        TagType::Tag
-                            | TagType::TagGlobal
-                            | TagType::WwiseInitBank
-                            | TagType::WwiseBank // WWise banks are included to allow for reverse hash lookup
-                    )
-                })
-                .map(|(i, e)| (i, e.clone()))
-                .collect();
+            let all_tags = scannable_entries(pkg.as_ref(), version);
 
-            // Sort tags by starting block index to optimize sequential block reads
-            all_tags.sort_by_key(|v| v.1.starting_block);
+            videos.lock().extend(scan_videos(pkg.as_ref(), version));
 
             let mut results = FxHashMap::default();
+            let mut package_harvest = HashHarvest::default();
             for (t, e) in all_tags {
                 let hash = TagHash::new(pkg.pkg_id(), t as u16);
                 profiling::scope!("scan_tag", format!("tag {hash}").as_str());
@@ -385,6 +802,7 @@ pub fn load_tag_cache() -> TagCache {
                             hash,
                             ScanResult {
                                 successful: false,
+                                scanned: true,
                                 ..Default::default()
                             },
                         );
@@ -402,6 +820,24 @@ pub fn load_tag_cache() -> TagCache {
                 };
 
                 let mut scan_result = scan_file(context, &data, scanner_mode);
+                if let Some(analyzer) = TAG_ANALYZER.read().as_ref() {
+                    analyzer(hash, e.reference, &data);
+                }
+                if harvest.is_some() {
+                    hashes::harvest_unknown_hashes(context, &data, &mut package_harvest);
+                }
+
+                if let Some(nested_string_refs) = &nested_string_refs {
+                    let refs = nested_strings::string_class_refs(
+                        context.endian,
+                        &data,
+                        &scan_result.file_hashes,
+                    );
+                    if !refs.is_empty() {
+                        nested_string_refs.lock().extend(refs);
+                    }
+                }
+
                 if let GameVersion::Destiny(v) = version {
                     if v.is_d1() {
                         if let Some(entry) = pkg.entry(t) {
@@ -421,22 +857,100 @@ pub fn load_tag_cache() -> TagCache {
                 results.insert(hash, scan_result);
             }
 
+            if let Some(harvest) = &harvest {
+                hashes::merge_harvest(&mut harvest.lock(), package_harvest);
+            }
+
             results
         })
         .flatten()
         .collect();
 
-    let cache = transform_tag_cache(cache);
+    (cache, videos.into_inner())
+}
+
+/// Writes the tag cache to disk, for when it was modified after loading
+pub fn save_tag_cache(cache: &TagCache) {
+    write_tag_cache(cache, &cache_path());
+}
+
+/// Writes the tag cache to the given path instead of the default one from [`cache_path`]
+pub fn save_tag_cache_to(cache: &TagCache, path: impl AsRef<Path>) {
+    write_tag_cache(cache, path.as_ref());
+}
 
+fn write_tag_cache(cache: &TagCache, path: &Path) {
     *SCANNER_PROGRESS.write() = ScanStatus::WritingCache;
-    info!("Compressing tag cache...");
-    let mut writer = zstd::Encoder::new(File::create(cache_file_path).unwrap(), 3).unwrap();
+    let compression = *CACHE_COMPRESSION.read();
+    let workers = compression.worker_count();
+    info!(
+        "Compressing tag cache (level {}, {workers} threads)...",
+        compression.level
+    );
+    let mut writer = zstd::Encoder::new(File::create(path).unwrap(), compression.level).unwrap();
+    if workers > 1 {
+        writer
+            .multithread(workers)
+            .unwrap_or_else(|e| warn!("Failed to enable multithreaded compression: {e}"));
+    }
 
-    bincode::encode_into_std_write(&cache, &mut writer, bincode::config::standard()).unwrap();
+    bincode::encode_into_std_write(cache, &mut writer, bincode::config::standard()).unwrap();
     writer.finish().unwrap();
     *SCANNER_PROGRESS.write() = ScanStatus::None;
+}
+
+/// Finds Bink/CRI USM video streams in a package
+///
+/// Only USM entries and entries of an unknown type are checked, as videos don't have a dedicated type in every version.
+/// Only the block holding the start of each entry is read, which is enough for detecting the container
+fn scan_videos(pkg: &dyn Package, version: GameVersion) -> Vec<ScannedVideo> {
+    let mut videos = vec![];
+    for (i, e) in pkg.entries().iter().enumerate() {
+        let tagtype = TagType::from_type_subtype_for_version(version, e.file_type, e.file_subtype);
+        if !matches!(tagtype, TagType::CriwareUsm | TagType::Unknown { .. }) || e.file_size < 4 {
+            continue;
+        }
+
+        let tag = TagHash::new(pkg.pkg_id(), i as u16);
+        let header = match read_entry_start(pkg, e, 4) {
+            Ok(d) => d,
+            Err(err) => {
+                error!("Failed to read entry {tag}: {err}");
+                continue;
+            }
+        };
+
+        if let Some(container) = VideoContainer::detect(&header) {
+            videos.push(ScannedVideo {
+                tag,
+                container,
+                size: e.file_size as u64,
+            });
+        }
+    }
+
+    videos
+}
+
+/// Reads the first `len` bytes of an entry, without reading the blocks past them
+fn read_entry_start(pkg: &dyn Package, e: &UEntryHeader, len: usize) -> anyhow::Result<Vec<u8>> {
+    let len = len.min(e.file_size as usize);
+    let mut data = Vec::with_capacity(len);
+    let mut block = e.starting_block as usize;
+    let mut offset = e.starting_block_offset as usize;
+    while data.len() < len {
+        let block_data = pkg.get_block(block)?;
+        anyhow::ensure!(
+            offset < block_data.len(),
+            "Entry starts past the end of its block"
+        );
+        let end = (offset + len - data.len()).min(block_data.len());
+        data.extend_from_slice(&block_data[offset..end]);
+        block += 1;
+        offset = 0;
+    }
 
-    cache
+    Ok(data)
 }
 
 /// Transforms the tag cache to include reference lookup tables
@@ -499,34 +1013,13 @@ fn transform_tag_cache(cache: FxHashMap<TagHash, ScanResult>) -> cache::TagCache
         }
     }
 
-    let timestamp = std::fs::metadata(&package_manager().package_dir)
-        .ok()
-        .and_then(|m| {
-            Some(
-                m.modified()
-                    .ok()?
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .ok()?
-                    .as_secs(),
-            )
-        })
-        .unwrap_or(0);
-
-    new_cache.timestamp = timestamp;
+    new_cache.timestamp = TagCache::packages_timestamp();
 
     new_cache
 }
 
-fn exe_directory() -> PathBuf {
-    std::env::current_exe()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .to_path_buf()
-}
-
-fn exe_relative_path<P: AsRef<Path>>(path: P) -> PathBuf {
-    exe_directory().join(path.as_ref())
+fn cache_relative_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    cache_directory().join(path.as_ref())
 }
 
 #[derive(PartialEq)]
@@ -0,0 +1,156 @@
+//! Merging partial caches (see [`crate::scope`]) into one, so the scan scope can be expanded without rescanning the
+//! packages that were already scanned
+//!
+//! Packages that were scanned for more than one of the caches are taken from the newest cache, by its packages
+//! timestamp. References between tags are rebuilt from the merged scan results, so references across the merged
+//! scopes are found as well.
+
+use std::path::PathBuf;
+
+use itertools::Itertools;
+use log::{info, warn};
+use rustc_hash::{FxHashMap, FxHashSet};
+use tiger_pkg::package_manager;
+
+use crate::{
+    ScanResult, TagCache,
+    cache::CacheLoadResult,
+    cache_directory,
+    scope::{self, ScanScope},
+    transform_tag_cache,
+};
+
+/// Packages that were scanned to build a cache
+pub fn scanned_packages(cache: &TagCache) -> FxHashSet<u16> {
+    cache
+        .hashes
+        .iter()
+        .filter(|(_, scan)| scan.scanned)
+        .map(|(tag, _)| tag.pkg_id())
+        .collect()
+}
+
+/// Partial cache files for the current packages
+pub fn partial_cache_paths() -> Vec<PathBuf> {
+    let prefix = format!("tags_{}_partial_", package_manager().cache_key());
+    std::fs::read_dir(cache_directory())
+        .map(|dir| {
+            dir.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".cache"))
+                })
+                .sorted()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Merges the given caches. Caches are preferred by their timestamp for packages that are in more than one of them
+pub fn merge_caches(mut caches: Vec<TagCache>) -> TagCache {
+    caches.sort_by_key(|c| c.timestamp);
+
+    // Newer caches are visited last, so they take over the packages they share with older ones
+    let mut package_sources: FxHashMap<u16, usize> = FxHashMap::default();
+    for (i, cache) in caches.iter().enumerate() {
+        for pkg_id in scanned_packages(cache) {
+            package_sources.insert(pkg_id, i);
+        }
+    }
+
+    // Packages scanned with different known string hashes than others can be missing some
+    let known_hashes_stamp = caches.first().map_or(0, |c| c.known_hashes_stamp);
+    let stale_hashes = caches
+        .iter()
+        .any(|c| c.stale_hashes || c.known_hashes_stamp != known_hashes_stamp);
+
+    let mut videos = vec![];
+    let mut packages = FxHashMap::default();
+    let mut merged: FxHashMap<_, ScanResult> = FxHashMap::default();
+    for (i, cache) in caches.into_iter().enumerate() {
+        let is_source = |pkg_id: u16| package_sources.get(&pkg_id) == Some(&i);
+        packages.extend(
+            cache
+                .packages
+                .into_iter()
+                .filter(|(pkg_id, _)| is_source(*pkg_id)),
+        );
+        videos.extend(
+            cache
+                .videos
+                .into_iter()
+                .filter(|video| is_source(video.tag.pkg_id())),
+        );
+        merged.extend(
+            cache
+                .hashes
+                .into_iter()
+                .filter(|(tag, scan)| scan.scanned && is_source(tag.pkg_id()))
+                .map(|(tag, scan)| {
+                    (
+                        tag,
+                        ScanResult {
+                            references: vec![],
+                            ..scan
+                        },
+                    )
+                }),
+        );
+    }
+
+    info!(
+        "Merged {} tags from {} packages",
+        merged.len(),
+        package_sources.len()
+    );
+
+    let mut cache = transform_tag_cache(merged);
+    cache.videos = videos;
+    cache.packages = packages;
+    cache.known_hashes_stamp = known_hashes_stamp;
+    cache.stale_hashes = stale_hashes;
+    cache.videos.sort_by_key(|v| v.tag.0);
+    cache
+}
+
+/// Loads and merges partial cache files, returning the merged cache and the scope that covers it
+///
+/// Caches that are outdated or unreadable are skipped
+pub fn merge_cache_files(paths: &[PathBuf]) -> anyhow::Result<(TagCache, ScanScope)> {
+    let mut caches = vec![];
+    for path in paths {
+        match TagCache::load(path)? {
+            CacheLoadResult::Loaded(cache) | CacheLoadResult::Migrated(cache) => caches.push(cache),
+            _ => warn!(
+                "Skipping {}, it's outdated or can't be read by this version",
+                path.display()
+            ),
+        }
+    }
+    anyhow::ensure!(!caches.is_empty(), "None of the caches could be loaded");
+
+    let cache = merge_caches(caches);
+    let packages = scanned_packages(&cache);
+    let scope = if package_manager()
+        .package_paths
+        .keys()
+        .all(|pkg_id| packages.contains(pkg_id))
+    {
+        ScanScope::All
+    } else {
+        ScanScope::Packages(
+            package_manager()
+                .package_paths
+                .iter()
+                .filter(|(pkg_id, _)| packages.contains(pkg_id))
+                .map(|(_, path)| ScanScope::package_name(path))
+                .sorted()
+                .collect(),
+        )
+    };
+    scope::set_loaded_scope(scope.clone());
+
+    Ok((cache, scope))
+}
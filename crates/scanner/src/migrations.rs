@@ -0,0 +1,640 @@
+use anyhow::Context;
+use itertools::Itertools;
+use log::{error, info};
+use quicktag_core::{tagtypes::TagType, util::u32_from_endian};
+use quicktag_strings::wwise::load_wwise_wordlist;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{GameVersion, TagHash, TagHash64, package_manager};
+
+use crate::cache::PackageStamp;
+use crate::{
+    SCANNER_PROGRESS, ScanResult, ScanStatus, ScannedHash, ScannedString, TagCache,
+    cache::package_stamps, find_arrays, fingerprint::StructFingerprint,
+    namespaces::WWISE_NAMESPACE, scan_videos, string_runs::find_string_runs, video::ScannedVideo,
+};
+
+/// Upgrades the decompressed data of a cache from version `from` to `from + 1`
+struct Migration {
+    from: u32,
+    description: &'static str,
+    migrate: fn(&[u8]) -> anyhow::Result<Vec<u8>>,
+}
+
+/// Cache versions that can be upgraded without a full rescan. Versions without a migration are rebuilt from scratch
+///
+/// When bumping [`TagCache::VERSION`], copy the old layout into a `V{n}` struct below and add a migration for it
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 7,
+        description: "scanning packages for video streams",
+        migrate: v7_to_v8,
+    },
+    Migration {
+        from: 8,
+        description: "computing structural fingerprints",
+        migrate: v8_to_v9,
+    },
+    Migration {
+        from: 9,
+        description: "locating raw strings",
+        migrate: v9_to_v10,
+    },
+    Migration {
+        from: 10,
+        description: "finding Wwise IDs",
+        migrate: v10_to_v11,
+    },
+    Migration {
+        from: 11,
+        description: "moving Wwise IDs to hash namespaces",
+        migrate: v11_to_v12,
+    },
+    Migration {
+        from: 12,
+        description: "recording package versions",
+        migrate: v12_to_v13,
+    },
+    Migration {
+        from: 13,
+        description: "marking scanned tags",
+        migrate: v13_to_v14,
+    },
+    Migration {
+        from: 14,
+        description: "recording the oldest compatible version",
+        migrate: v14_to_v15,
+    },
+];
+
+/// Checks whether there's a migration path from the given version to the current one
+pub fn can_migrate(version: u32) -> bool {
+    (version..TagCache::VERSION).all(|v| MIGRATIONS.iter().any(|m| m.from == v))
+}
+
+/// Upgrades raw cache data from the given version to the current one
+pub fn migrate(version: u32, mut data: Vec<u8>) -> anyhow::Result<TagCache> {
+    for from in version..TagCache::VERSION {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.from == from)
+            .with_context(|| format!("No migration from cache v{from}"))?;
+
+        info!(
+            "Migrating cache from v{from} to v{} ({})",
+            from + 1,
+            migration.description
+        );
+        *SCANNER_PROGRESS.write() = ScanStatus::MigratingCache { from, to: from + 1 };
+        let result = (migration.migrate)(&data);
+        *SCANNER_PROGRESS.write() = ScanStatus::None;
+        data = result.with_context(|| format!("Migration from v{from} failed"))?;
+    }
+
+    decode(&data)
+}
+
+fn decode<T: bincode::Decode<()>>(data: &[u8]) -> anyhow::Result<T> {
+    Ok(bincode::decode_from_slice(data, bincode::config::standard())?.0)
+}
+
+fn encode<T: bincode::Encode>(value: &T) -> anyhow::Result<Vec<u8>> {
+    Ok(bincode::encode_to_vec(value, bincode::config::standard())?)
+}
+
+/// [`ScanResult`] up to v8, before structural fingerprints were added
+#[derive(bincode::Encode, bincode::Decode)]
+struct ScanResultV8 {
+    successful: bool,
+    file_hashes: Vec<ScannedHash<TagHash>>,
+    file_hashes64: Vec<ScannedHash<TagHash64>>,
+    string_hashes: Vec<ScannedHash<u32>>,
+    wordlist_hashes: Vec<ScannedHash<u32>>,
+    raw_strings: Vec<String>,
+    references: Vec<TagHash>,
+}
+
+/// [`ScanResult`] in v9, before raw strings had offsets
+#[derive(bincode::Encode, bincode::Decode)]
+struct ScanResultV9 {
+    successful: bool,
+    file_hashes: Vec<ScannedHash<TagHash>>,
+    file_hashes64: Vec<ScannedHash<TagHash64>>,
+    string_hashes: Vec<ScannedHash<u32>>,
+    wordlist_hashes: Vec<ScannedHash<u32>>,
+    raw_strings: Vec<String>,
+    references: Vec<TagHash>,
+    fingerprint: StructFingerprint,
+}
+
+/// [`ScanResult`] in v10, before Wwise IDs were scanned for
+#[derive(bincode::Encode, bincode::Decode)]
+struct ScanResultV10 {
+    successful: bool,
+    file_hashes: Vec<ScannedHash<TagHash>>,
+    file_hashes64: Vec<ScannedHash<TagHash64>>,
+    string_hashes: Vec<ScannedHash<u32>>,
+    wordlist_hashes: Vec<ScannedHash<u32>>,
+    raw_strings: Vec<ScannedString>,
+    references: Vec<TagHash>,
+    fingerprint: StructFingerprint,
+}
+
+/// [`ScanResult`] in v11, when Wwise IDs were the only hash namespace
+#[derive(bincode::Encode, bincode::Decode)]
+struct ScanResultV11 {
+    successful: bool,
+    file_hashes: Vec<ScannedHash<TagHash>>,
+    file_hashes64: Vec<ScannedHash<TagHash64>>,
+    string_hashes: Vec<ScannedHash<u32>>,
+    wordlist_hashes: Vec<ScannedHash<u32>>,
+    wwise_hashes: Vec<ScannedHash<u32>>,
+    raw_strings: Vec<ScannedString>,
+    references: Vec<TagHash>,
+    fingerprint: StructFingerprint,
+}
+
+/// [`ScanResult`] in v12 and v13, before scanned tags were marked
+#[derive(bincode::Encode, bincode::Decode)]
+struct ScanResultV12 {
+    successful: bool,
+    file_hashes: Vec<ScannedHash<TagHash>>,
+    file_hashes64: Vec<ScannedHash<TagHash64>>,
+    string_hashes: Vec<ScannedHash<u32>>,
+    wordlist_hashes: Vec<ScannedHash<u32>>,
+    namespace_hashes: FxHashMap<String, Vec<ScannedHash<u32>>>,
+    raw_strings: Vec<ScannedString>,
+    references: Vec<TagHash>,
+    fingerprint: StructFingerprint,
+}
+
+/// [`ScanResult`] in v14
+#[derive(bincode::Encode, bincode::Decode)]
+struct ScanResultV14 {
+    successful: bool,
+    scanned: bool,
+    file_hashes: Vec<ScannedHash<TagHash>>,
+    file_hashes64: Vec<ScannedHash<TagHash64>>,
+    string_hashes: Vec<ScannedHash<u32>>,
+    wordlist_hashes: Vec<ScannedHash<u32>>,
+    namespace_hashes: FxHashMap<String, Vec<ScannedHash<u32>>>,
+    raw_strings: Vec<ScannedString>,
+    references: Vec<TagHash>,
+    fingerprint: StructFingerprint,
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+struct TagCacheV7 {
+    timestamp: u64,
+    version: u32,
+    hashes: FxHashMap<TagHash, ScanResultV8>,
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+struct TagCacheV8 {
+    timestamp: u64,
+    version: u32,
+    hashes: FxHashMap<TagHash, ScanResultV8>,
+    videos: Vec<ScannedVideo>,
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+struct TagCacheV9 {
+    timestamp: u64,
+    version: u32,
+    hashes: FxHashMap<TagHash, ScanResultV9>,
+    videos: Vec<ScannedVideo>,
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+struct TagCacheV10 {
+    timestamp: u64,
+    version: u32,
+    hashes: FxHashMap<TagHash, ScanResultV10>,
+    videos: Vec<ScannedVideo>,
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+struct TagCacheV11 {
+    timestamp: u64,
+    version: u32,
+    hashes: FxHashMap<TagHash, ScanResultV11>,
+    videos: Vec<ScannedVideo>,
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+struct TagCacheV12 {
+    timestamp: u64,
+    version: u32,
+    hashes: FxHashMap<TagHash, ScanResultV12>,
+    videos: Vec<ScannedVideo>,
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+struct TagCacheV13 {
+    timestamp: u64,
+    version: u32,
+    hashes: FxHashMap<TagHash, ScanResultV12>,
+    videos: Vec<ScannedVideo>,
+    packages: FxHashMap<u16, PackageStamp>,
+}
+
+/// [`TagCache`] in v14, before the oldest version that can read it was recorded
+#[derive(bincode::Encode, bincode::Decode)]
+struct TagCacheV14 {
+    timestamp: u64,
+    version: u32,
+    hashes: FxHashMap<TagHash, ScanResultV14>,
+    videos: Vec<ScannedVideo>,
+    packages: FxHashMap<u16, PackageStamp>,
+    known_hashes_stamp: u64,
+    stale_hashes: bool,
+}
+
+/// Whether the scanner reads the data of this tag, as opposed to only adding it through references
+fn is_scanned_tag(version: GameVersion, tag: TagHash) -> bool {
+    package_manager().get_entry(tag).is_some_and(|e| {
+        matches!(
+            TagType::from_type_subtype_for_version(version, e.file_type, e.file_subtype),
+            TagType::Tag | TagType::TagGlobal | TagType::WwiseInitBank | TagType::WwiseBank
+        )
+    })
+}
+
+/// v8 added video streams, which only requires reading the start of USM and unknown entries
+fn v7_to_v8(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cache: TagCacheV7 = decode(data)?;
+
+    let version = package_manager().version;
+    let mut videos: Vec<ScannedVideo> = package_manager()
+        .package_paths
+        .values()
+        .cloned()
+        .collect_vec()
+        .par_iter()
+        .flat_map_iter(|path| match version.open(&path.path) {
+            Ok(pkg) => scan_videos(pkg.as_ref(), version),
+            Err(e) => {
+                error!("Failed to open package {path}: {e}");
+                vec![]
+            }
+        })
+        .collect();
+    videos.sort_by_key(|v| v.tag.0);
+
+    encode(&TagCacheV8 {
+        timestamp: cache.timestamp,
+        version: 8,
+        hashes: cache.hashes,
+        videos,
+    })
+}
+
+/// v9 added structural fingerprints. These only depend on the arrays in a tag, so the (much slower) hash scan is skipped
+fn v8_to_v9(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cache: TagCacheV8 = decode(data)?;
+
+    let version = package_manager().version;
+    let endian = version.endian();
+    let hashes: FxHashMap<TagHash, ScanResultV9> = cache
+        .hashes
+        .into_par_iter()
+        .map(|(tag, scan)| {
+            // Only entries that are scanned get a fingerprint, entries that were added through references keep an empty one
+            let fingerprint = if scan.successful && is_scanned_tag(version, tag) {
+                package_manager()
+                    .read_tag(tag)
+                    .map(|data| {
+                        let mut fingerprint = StructFingerprint::new(data.len());
+                        for array in find_arrays(endian, &data) {
+                            fingerprint.push_array(array.class);
+                        }
+                        fingerprint
+                    })
+                    .unwrap_or_default()
+            } else {
+                StructFingerprint::default()
+            };
+
+            (
+                tag,
+                ScanResultV9 {
+                    successful: scan.successful,
+                    file_hashes: scan.file_hashes,
+                    file_hashes64: scan.file_hashes64,
+                    string_hashes: scan.string_hashes,
+                    wordlist_hashes: scan.wordlist_hashes,
+                    raw_strings: scan.raw_strings,
+                    references: scan.references,
+                    fingerprint,
+                },
+            )
+        })
+        .collect();
+
+    encode(&TagCacheV9 {
+        timestamp: cache.timestamp,
+        version: 9,
+        hashes,
+        videos: cache.videos,
+    })
+}
+
+/// Finds the offset of a previously scanned raw string, searching from `start` onwards first
+///
+/// Blob strings are stored as-is, strings found as UTF-16 runs have to be found again through the run scanner
+fn locate_raw_string(
+    data: &[u8],
+    string: &str,
+    start: usize,
+    runs: &mut Option<Vec<(u64, String)>>,
+) -> Option<u64> {
+    let needle = string.as_bytes();
+    let find_from = |from: usize| {
+        data.get(from..)?
+            .windows(needle.len())
+            .position(|w| w == needle)
+            .map(|p| (from + p) as u64)
+    };
+
+    find_from(start).or_else(|| find_from(0)).or_else(|| {
+        runs.get_or_insert_with(|| find_string_runs(data))
+            .iter()
+            .find(|(_, s)| s == string)
+            .map(|(offset, _)| *offset)
+    })
+}
+
+/// v10 added offsets to raw strings. Only tags that contain raw strings are read again to look them up
+fn v9_to_v10(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cache: TagCacheV9 = decode(data)?;
+
+    let hashes: FxHashMap<TagHash, ScanResultV10> = cache
+        .hashes
+        .into_par_iter()
+        .map(|(tag, scan)| {
+            let raw_strings = if scan.raw_strings.is_empty() {
+                vec![]
+            } else {
+                match package_manager().read_tag(tag) {
+                    Ok(data) => {
+                        let mut runs = None;
+                        let mut next = 0;
+                        scan.raw_strings
+                            .into_iter()
+                            .filter(|s| !s.is_empty())
+                            .filter_map(|string| {
+                                let offset = locate_raw_string(&data, &string, next, &mut runs)?;
+                                next = offset as usize + string.len();
+                                Some(ScannedString { offset, string })
+                            })
+                            .collect()
+                    }
+                    Err(e) => {
+                        error!("Failed to read tag {tag} while locating raw strings: {e}");
+                        vec![]
+                    }
+                }
+            };
+
+            (
+                tag,
+                ScanResultV10 {
+                    successful: scan.successful,
+                    file_hashes: scan.file_hashes,
+                    file_hashes64: scan.file_hashes64,
+                    string_hashes: scan.string_hashes,
+                    wordlist_hashes: scan.wordlist_hashes,
+                    raw_strings,
+                    references: scan.references,
+                    fingerprint: scan.fingerprint,
+                },
+            )
+        })
+        .collect();
+
+    encode(&TagCacheV10 {
+        timestamp: cache.timestamp,
+        version: 10,
+        hashes,
+        videos: cache.videos,
+    })
+}
+
+/// Finds the Wwise IDs in tag data, skipping arrays that block tag references like [`crate::scan_file`] does
+fn find_wwise_ids(data: &[u8], known_ids: &[u32]) -> Vec<ScannedHash<u32>> {
+    let endian = package_manager().version.endian();
+    let blocked_ranges = find_arrays(endian, data)
+        .iter()
+        .filter_map(|array| array.blocked_range())
+        .collect_vec();
+
+    data.chunks_exact(4)
+        .enumerate()
+        .map(|(i, chunk)| {
+            (
+                (i * 4) as u64,
+                u32_from_endian(endian, chunk.try_into().unwrap()),
+            )
+        })
+        .filter(|(offset, value)| {
+            known_ids.binary_search(value).is_ok()
+                && !blocked_ranges.iter().any(|range| range.contains(offset))
+        })
+        .map(|(offset, hash)| ScannedHash { offset, hash })
+        .collect()
+}
+
+/// v11 added Wwise IDs. Tags are only read again when there's a Wwise wordlist, as nothing can be found without one
+fn v10_to_v11(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cache: TagCacheV10 = decode(data)?;
+
+    let mut known_ids = vec![];
+    load_wwise_wordlist(|_, id| known_ids.push(id));
+    known_ids.sort_unstable();
+    known_ids.dedup();
+
+    let version = package_manager().version;
+    let hashes: FxHashMap<TagHash, ScanResultV11> = cache
+        .hashes
+        .into_par_iter()
+        .map(|(tag, scan)| {
+            let wwise_hashes =
+                if !known_ids.is_empty() && scan.successful && is_scanned_tag(version, tag) {
+                    match package_manager().read_tag(tag) {
+                        Ok(data) => find_wwise_ids(&data, &known_ids),
+                        Err(e) => {
+                            error!("Failed to read tag {tag} while finding Wwise IDs: {e}");
+                            vec![]
+                        }
+                    }
+                } else {
+                    vec![]
+                };
+
+            (
+                tag,
+                ScanResultV11 {
+                    successful: scan.successful,
+                    file_hashes: scan.file_hashes,
+                    file_hashes64: scan.file_hashes64,
+                    string_hashes: scan.string_hashes,
+                    wordlist_hashes: scan.wordlist_hashes,
+                    wwise_hashes,
+                    raw_strings: scan.raw_strings,
+                    references: scan.references,
+                    fingerprint: scan.fingerprint,
+                },
+            )
+        })
+        .collect();
+
+    encode(&TagCacheV11 {
+        timestamp: cache.timestamp,
+        version: 11,
+        hashes,
+        videos: cache.videos,
+    })
+}
+
+/// v12 stores hashes per hash namespace, Wwise IDs are moved to the Wwise namespace
+fn v11_to_v12(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cache: TagCacheV11 = decode(data)?;
+
+    let hashes: FxHashMap<TagHash, ScanResultV12> = cache
+        .hashes
+        .into_iter()
+        .map(|(tag, scan)| {
+            let mut namespace_hashes = FxHashMap::default();
+            if !scan.wwise_hashes.is_empty() {
+                namespace_hashes.insert(WWISE_NAMESPACE.to_string(), scan.wwise_hashes);
+            }
+
+            (
+                tag,
+                ScanResultV12 {
+                    successful: scan.successful,
+                    file_hashes: scan.file_hashes,
+                    file_hashes64: scan.file_hashes64,
+                    string_hashes: scan.string_hashes,
+                    wordlist_hashes: scan.wordlist_hashes,
+                    namespace_hashes,
+                    raw_strings: scan.raw_strings,
+                    references: scan.references,
+                    fingerprint: scan.fingerprint,
+                },
+            )
+        })
+        .collect();
+
+    encode(&TagCacheV12 {
+        timestamp: cache.timestamp,
+        version: 12,
+        hashes,
+        videos: cache.videos,
+    })
+}
+
+/// v13 records the package files a cache was built from. Outdated caches are rebuilt before migrating, so the current
+/// files are the ones the cache was built from
+fn v12_to_v13(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cache: TagCacheV12 = decode(data)?;
+
+    // Scanned tags were recognized by their fingerprint, which is only empty for tags that were added through
+    // references. This misses tiny tags, but only the packages they're in are needed here
+    let packages = package_stamps(
+        cache
+            .hashes
+            .iter()
+            .filter(|(_, scan)| !scan.successful || scan.fingerprint.size_bucket > 0)
+            .map(|(tag, _)| tag.pkg_id())
+            .unique(),
+    );
+
+    encode(&TagCacheV13 {
+        timestamp: cache.timestamp,
+        version: 13,
+        hashes: cache.hashes,
+        videos: cache.videos,
+        packages,
+    })
+}
+
+/// v14 marks the tags that were scanned, instead of guessing from their fingerprint. Scanned tags are the tags in the
+/// recorded packages that the scanner reads. The stamp of the known string hashes is unknown, so the next update
+/// marks the cache as stale
+fn v13_to_v14(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cache: TagCacheV13 = decode(data)?;
+
+    let version = package_manager().version;
+    let hashes: FxHashMap<TagHash, ScanResultV14> = cache
+        .hashes
+        .into_iter()
+        .map(|(tag, scan)| {
+            let scanned = !scan.successful
+                || (cache.packages.contains_key(&tag.pkg_id()) && is_scanned_tag(version, tag));
+
+            (
+                tag,
+                ScanResultV14 {
+                    successful: scan.successful,
+                    scanned,
+                    file_hashes: scan.file_hashes,
+                    file_hashes64: scan.file_hashes64,
+                    string_hashes: scan.string_hashes,
+                    wordlist_hashes: scan.wordlist_hashes,
+                    namespace_hashes: scan.namespace_hashes,
+                    raw_strings: scan.raw_strings,
+                    references: scan.references,
+                    fingerprint: scan.fingerprint,
+                },
+            )
+        })
+        .collect();
+
+    encode(&TagCacheV14 {
+        timestamp: cache.timestamp,
+        version: 14,
+        hashes,
+        videos: cache.videos,
+        packages: cache.packages,
+        known_hashes_stamp: 0,
+        stale_hashes: false,
+    })
+}
+
+/// v15 records the oldest version that can read the cache, so older versions of quicktag know whether they can open it
+fn v14_to_v15(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let cache: TagCacheV14 = decode(data)?;
+
+    encode(&TagCache {
+        timestamp: cache.timestamp,
+        version: 15,
+        min_reader_version: 15,
+        hashes: cache
+            .hashes
+            .into_iter()
+            .map(|(tag, scan)| {
+                (
+                    tag,
+                    ScanResult {
+                        successful: scan.successful,
+                        scanned: scan.scanned,
+                        file_hashes: scan.file_hashes,
+                        file_hashes64: scan.file_hashes64,
+                        string_hashes: scan.string_hashes,
+                        wordlist_hashes: scan.wordlist_hashes,
+                        namespace_hashes: scan.namespace_hashes,
+                        raw_strings: scan.raw_strings,
+                        references: scan.references,
+                        fingerprint: scan.fingerprint,
+                    },
+                )
+            })
+            .collect(),
+        videos: cache.videos,
+        packages: cache.packages,
+        known_hashes_stamp: cache.known_hashes_stamp,
+        stale_hashes: cache.stale_hashes,
+    })
+}
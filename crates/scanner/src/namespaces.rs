@@ -0,0 +1,143 @@
+//! Hash namespaces, for hashes besides the localized string and wordlist hashes
+//!
+//! A namespace is a hash algorithm with a list of names hashed with it, like Wwise event names (lowercase FNV-1) or
+//! community wordlists for other hashes. [`crate::scan_file`] looks for the hashes of every namespace and stores the
+//! matches in [`crate::ScanResult::namespace_hashes`], by namespace ID.
+//!
+//! Namespaces are (re)loaded when a [`crate::context::ScannerContext`] is created, so changes to the wordlists only
+//! apply to caches built after that.
+
+use std::{path::PathBuf, sync::Arc};
+
+use log::{info, warn};
+use parking_lot::RwLock;
+use quicktag_core::util::HashAlgorithm;
+use quicktag_strings::{localized::StringCache, wwise::load_wwise_wordlist};
+
+/// ID of the built-in namespace for Wwise event names, see [`quicktag_strings::wwise`]
+pub const WWISE_NAMESPACE: &str = "wwise";
+
+/// A namespace as it's configured, the wordlist is read by [`load_hash_namespaces`]
+#[derive(Clone, Debug)]
+pub struct HashNamespaceDefinition {
+    /// Stored in the cache, changing it requires the cache to be rebuilt
+    pub id: String,
+    pub name: String,
+    pub algorithm: HashAlgorithm,
+    /// One name per line, empty lines and lines starting with `#` are skipped
+    pub wordlist: PathBuf,
+    pub color: [u8; 3],
+}
+
+pub struct HashNamespace {
+    pub id: String,
+    pub name: String,
+    pub algorithm: HashAlgorithm,
+    /// Highlight color for hashes of this namespace
+    pub color: [u8; 3],
+    pub names: StringCache,
+}
+
+impl HashNamespace {
+    fn new(id: &str, name: &str, algorithm: HashAlgorithm, color: [u8; 3]) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            algorithm,
+            color,
+            names: StringCache::default(),
+        }
+    }
+
+    fn insert(&mut self, name: &str, hash: u32) {
+        let entry = self.names.entry(hash).or_default();
+        if !entry.iter().any(|n| n == name) {
+            entry.push(name.to_string());
+        }
+    }
+
+    /// Sorted hashes of every name in the namespace
+    pub fn known_hashes(&self) -> Vec<u32> {
+        let mut hashes = self.names.keys().copied().collect::<Vec<_>>();
+        hashes.sort_unstable();
+        hashes
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CUSTOM_NAMESPACES: RwLock<Vec<HashNamespaceDefinition>> = RwLock::new(vec![]);
+    static ref NAMESPACES: RwLock<Vec<Arc<HashNamespace>>> = RwLock::new(vec![]);
+}
+
+/// Sets the namespaces that are loaded next to the built-in ones
+pub fn set_custom_hash_namespaces(definitions: Vec<HashNamespaceDefinition>) {
+    *CUSTOM_NAMESPACES.write() = definitions;
+}
+
+/// Reads the wordlists of the built-in and custom namespaces. Namespaces without any names are skipped
+pub fn load_hash_namespaces() {
+    let mut wwise = HashNamespace::new(
+        WWISE_NAMESPACE,
+        "Wwise events",
+        HashAlgorithm::Fnv1Lowercase,
+        [255, 160, 60],
+    );
+    load_wwise_wordlist(|name, id| wwise.insert(name, id));
+
+    let mut namespaces = vec![wwise];
+    for definition in CUSTOM_NAMESPACES.read().iter() {
+        if namespaces.iter().any(|n| n.id == definition.id) {
+            warn!(
+                "Skipping hash namespace '{}', its ID is already in use",
+                definition.id
+            );
+            continue;
+        }
+
+        let wordlist = match std::fs::read_to_string(&definition.wordlist) {
+            Ok(w) => w,
+            Err(e) => {
+                warn!(
+                    "Failed to read wordlist {} for hash namespace '{}': {e}",
+                    definition.wordlist.display(),
+                    definition.id
+                );
+                continue;
+            }
+        };
+
+        let mut namespace = HashNamespace::new(
+            &definition.id,
+            &definition.name,
+            definition.algorithm,
+            definition.color,
+        );
+        for name in wordlist.lines().map(str::trim) {
+            if !name.is_empty() && !name.starts_with('#') {
+                namespace.insert(name, definition.algorithm.hash(name));
+            }
+        }
+
+        info!(
+            "Loaded {} names for hash namespace '{}'",
+            namespace.names.len(),
+            namespace.id
+        );
+        namespaces.push(namespace);
+    }
+
+    *NAMESPACES.write() = namespaces
+        .into_iter()
+        .filter(|n| !n.names.is_empty())
+        .map(Arc::new)
+        .collect();
+}
+
+/// Namespaces loaded by the last [`load_hash_namespaces`]
+pub fn hash_namespaces() -> Vec<Arc<HashNamespace>> {
+    NAMESPACES.read().clone()
+}
+
+pub fn hash_namespace(id: &str) -> Option<Arc<HashNamespace>> {
+    NAMESPACES.read().iter().find(|n| n.id == id).cloned()
+}
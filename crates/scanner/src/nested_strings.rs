@@ -0,0 +1,134 @@
+//! Optional second scan pass for string blobs that are only reachable through references from string-related classes
+//!
+//! Some strings live in tags that aren't scanned themselves (eg. raw data referenced by a string container), so
+//! they never show up in the raw strings list. This pass follows the 32-bit tag references found inside arrays of
+//! string-related classes and scans the referenced tags for strings as well.
+
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use log::{error, info};
+use quicktag_core::classes::get_class_by_id;
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use tiger_pkg::{Endian, TagHash, package_manager};
+
+use crate::{
+    SCANNER_PROGRESS, ScanResult, ScanStatus, ScannedHash, ScannerMode, array_data_layout,
+    context::ScannerContext, find_arrays, scan_file,
+};
+
+/// Following references gets expensive quickly, so the depth is capped
+pub const MAX_NESTED_STRING_DEPTH: u32 = 3;
+
+static NESTED_STRING_DEPTH: AtomicU32 = AtomicU32::new(0);
+
+/// Sets how many levels of references are followed while building the tag cache, 0 disables the pass
+pub fn set_nested_string_depth(depth: u32) {
+    NESTED_STRING_DEPTH.store(depth.min(MAX_NESTED_STRING_DEPTH), Ordering::Relaxed);
+}
+
+pub fn nested_string_depth() -> u32 {
+    NESTED_STRING_DEPTH.load(Ordering::Relaxed)
+}
+
+/// Whether a class is likely to contain string data or point at it, based on its name
+pub fn is_string_class(class: u32) -> bool {
+    get_class_by_id(class).is_some_and(|c| c.name.contains("string"))
+}
+
+/// Tag references that lie inside the data of arrays of string-related classes
+pub fn string_class_refs(
+    endian: Endian,
+    data: &[u8],
+    file_hashes: &[ScannedHash<TagHash>],
+) -> Vec<TagHash> {
+    let arrays = find_arrays(endian, data);
+    let ranges: Vec<Range<u64>> = (0..arrays.len())
+        .filter(|&i| is_string_class(arrays[i].class))
+        .filter_map(|i| array_data_layout(data, &arrays, i))
+        .map(|(start, count, stride)| start as u64..(start + count * stride) as u64)
+        .collect();
+
+    if ranges.is_empty() {
+        return vec![];
+    }
+
+    file_hashes
+        .iter()
+        .filter(|h| ranges.iter().any(|r| r.contains(&h.offset)))
+        .map(|h| h.hash)
+        .collect()
+}
+
+/// Scans the given references (and the references they lead to, up to the configured depth) for strings
+///
+/// Tags that are already in the cache are skipped. The new entries only contain strings and string hashes, their tag
+/// references are only used to find the next level and are not recorded
+pub(crate) fn scan_nested_strings(
+    context: &ScannerContext,
+    cache: &mut FxHashMap<TagHash, ScanResult>,
+    mut pending: FxHashSet<TagHash>,
+) {
+    let mut visited: FxHashSet<TagHash> = Default::default();
+    for level in 1..=nested_string_depth() {
+        pending.retain(|tag| !cache.contains_key(tag) && visited.insert(*tag));
+        if pending.is_empty() {
+            break;
+        }
+
+        *SCANNER_PROGRESS.write() = ScanStatus::NestedStrings {
+            level,
+            tags: pending.len(),
+        };
+        info!(
+            "Scanning {} tags referenced by string classes (level {level})",
+            pending.len()
+        );
+
+        let results: Vec<(TagHash, Option<ScanResult>, Vec<TagHash>)> = pending
+            .par_iter()
+            .filter_map(|&tag| {
+                let data = package_manager()
+                    .read_tag(tag)
+                    .map_err(|e| error!("Failed to read nested string tag {tag}: {e}"))
+                    .ok()?;
+
+                let scan = scan_file(context, &data, ScannerMode::Both);
+                let next = string_class_refs(context.endian, &data, &scan.file_hashes);
+                let has_strings = !scan.raw_strings.is_empty()
+                    || !scan.string_hashes.is_empty()
+                    || !scan.wordlist_hashes.is_empty();
+
+                let scan = has_strings.then(|| ScanResult {
+                    scanned: true,
+                    string_hashes: scan.string_hashes,
+                    wordlist_hashes: scan.wordlist_hashes,
+                    raw_strings: scan.raw_strings,
+                    fingerprint: scan.fingerprint,
+                    ..Default::default()
+                });
+
+                Some((tag, scan, next))
+            })
+            .collect();
+
+        info!(
+            "Found strings in {} of {} nested tags",
+            results.iter().filter(|(_, scan, _)| scan.is_some()).count(),
+            pending.len()
+        );
+
+        pending.clear();
+        for (tag, scan, next) in results {
+            if let Some(scan) = scan {
+                cache.insert(tag, scan);
+            }
+            pending.extend(next);
+        }
+    }
+
+    *SCANNER_PROGRESS.write() = ScanStatus::None;
+}
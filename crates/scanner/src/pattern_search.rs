@@ -0,0 +1,320 @@
+//! Searching the data of every package entry for a byte pattern, text or a number, eg. to find which tags contain a
+//! constant
+//!
+//! The tag cache only indexes tag references and hashes, so this reads the packages again. Packages are searched in
+//! parallel and read in block order like when building the cache, so a search over every package takes about as long
+//! as a cache rebuild without the scanning.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use log::{error, info};
+use parking_lot::Mutex;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use tiger_pkg::{Endian, TagHash, package::UEntryHeader, package_manager};
+
+use crate::scannable_entries;
+use crate::scope::{ScanScope, glob_match};
+
+/// Offsets kept per tag, the total amount of matches in a tag is always counted
+pub const MAX_OFFSETS_PER_TAG: usize = 16;
+/// Searching stops after this many tags matched, so a pattern like `00 00` doesn't fill up memory
+pub const MAX_MATCHING_TAGS: usize = 50_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PatternKind {
+    Hex,
+    Text,
+    U32,
+    U64,
+}
+
+impl PatternKind {
+    pub const ALL: [PatternKind; 4] = [
+        PatternKind::Hex,
+        PatternKind::Text,
+        PatternKind::U32,
+        PatternKind::U64,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PatternKind::Hex => "Hex bytes",
+            PatternKind::Text => "Text",
+            PatternKind::U32 => "u32",
+            PatternKind::U64 => "u64",
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            PatternKind::Hex => "eg. 80 80 9F BD, ?? matches any byte",
+            PatternKind::Text => "ASCII/UTF-8 text",
+            PatternKind::U32 => "eg. 1234, -1 or 0x80809FBD",
+            PatternKind::U64 => "eg. 1234, -1 or 0xB2C3D4E5F6A7B8C9",
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, PatternKind::U32 | PatternKind::U64)
+    }
+}
+
+/// Bytes to search for. `None` matches any byte
+#[derive(Clone, Debug)]
+pub struct BytePattern {
+    bytes: Vec<Option<u8>>,
+    /// Only match at offsets that are a multiple of 4
+    aligned: bool,
+}
+
+impl BytePattern {
+    /// Parses a query of the given kind. Only text is searched for exactly as written, including surrounding
+    /// whitespace. Numbers are written in the given byte order, and only matched at 4-byte
+    /// aligned offsets when `aligned` is set
+    pub fn parse(
+        kind: PatternKind,
+        query: &str,
+        endian: Endian,
+        aligned: bool,
+    ) -> Result<Self, String> {
+        let bytes = match kind {
+            PatternKind::Text => query.bytes().map(Some).collect(),
+            PatternKind::Hex => parse_hex(query)?,
+            PatternKind::U32 => {
+                let query = query.trim();
+                let value =
+                    parse_u32(query).ok_or_else(|| format!("'{query}' isn't a 32-bit integer"))?;
+                let bytes = match endian {
+                    Endian::Big => value.to_be_bytes(),
+                    Endian::Little => value.to_le_bytes(),
+                };
+                bytes.map(Some).to_vec()
+            }
+            PatternKind::U64 => {
+                let query = query.trim();
+                let value =
+                    parse_u64(query).ok_or_else(|| format!("'{query}' isn't a 64-bit integer"))?;
+                let bytes = match endian {
+                    Endian::Big => value.to_be_bytes(),
+                    Endian::Little => value.to_le_bytes(),
+                };
+                bytes.map(Some).to_vec()
+            }
+        };
+
+        if bytes.is_empty() {
+            return Err("The pattern is empty".to_string());
+        }
+
+        Ok(Self {
+            bytes,
+            aligned: aligned && kind.is_number(),
+        })
+    }
+
+    /// Length of a match in bytes
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn matches_at(&self, data: &[u8], offset: usize) -> bool {
+        self.bytes
+            .iter()
+            .zip(&data[offset..])
+            .all(|(p, b)| p.is_none_or(|p| p == *b))
+    }
+
+    /// Start offsets of every occurrence in `data`, in order
+    pub fn find<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let step = if self.aligned { 4 } else { 1 };
+        let starts = (data.len() + 1).saturating_sub(self.bytes.len());
+        (0..starts)
+            .step_by(step)
+            .filter(move |&offset| self.matches_at(data, offset))
+    }
+}
+
+/// Parses a hex pattern like `80 80 ?? BD` or `80809FBD`
+fn parse_hex(query: &str) -> Result<Vec<Option<u8>>, String> {
+    let digits: String = query
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .collect();
+    let digits = digits.strip_prefix("0x").unwrap_or(&digits);
+    if digits.len() % 2 != 0 {
+        return Err("Every byte needs two hex digits".to_string());
+    }
+
+    digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            b"??" => Ok(None),
+            _ => std::str::from_utf8(pair)
+                .ok()
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+                .map(Some)
+                .ok_or_else(|| format!("'{}' isn't a hex byte", String::from_utf8_lossy(pair))),
+        })
+        .collect()
+}
+
+/// Decimal (negative numbers are stored as two's complement) or hex with a `0x` prefix
+fn parse_u32(query: &str) -> Option<u32> {
+    match query.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => query
+            .parse::<u32>()
+            .ok()
+            .or_else(|| query.parse::<i32>().ok().map(|v| v as u32)),
+    }
+}
+
+fn parse_u64(query: &str) -> Option<u64> {
+    match query.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => query
+            .parse::<u64>()
+            .ok()
+            .or_else(|| query.parse::<i64>().ok().map(|v| v as u64)),
+    }
+}
+
+pub struct PatternMatch {
+    pub tag: TagHash,
+    /// Class reference of the tag
+    pub class: u32,
+    /// Offsets of the first [`MAX_OFFSETS_PER_TAG`] matches
+    pub offsets: Vec<u64>,
+    /// Amount of matches in the tag
+    pub count: usize,
+}
+
+#[derive(Default)]
+pub struct PatternSearchProgress {
+    /// Packages searched so far
+    pub current: AtomicUsize,
+    pub total: AtomicUsize,
+    /// Tags that matched so far
+    pub matches: AtomicUsize,
+    pub cancel: AtomicBool,
+}
+
+pub struct PatternSearchResult {
+    /// Sorted by tag
+    pub matches: Vec<PatternMatch>,
+    pub tags_searched: usize,
+    /// Stopped after [`MAX_MATCHING_TAGS`] matching tags
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+/// Searches every entry in the packages for the pattern. `package_filter` is a glob pattern for the package names (see
+/// [`ScanScope::package_name`]), to search only some of the packages. With `tags_only`, only the entries that are
+/// scanned for the tag cache are searched, skipping textures, audio and other data
+pub fn search_packages(
+    pattern: &BytePattern,
+    package_filter: Option<&str>,
+    tags_only: bool,
+    progress: &PatternSearchProgress,
+) -> PatternSearchResult {
+    let version = package_manager().version;
+    let packages = package_manager()
+        .package_paths
+        .values()
+        .filter(|path| {
+            package_filter.is_none_or(|filter| glob_match(filter, &ScanScope::package_name(path)))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    progress.total.store(packages.len(), Ordering::Relaxed);
+    progress.current.store(0, Ordering::Relaxed);
+    progress.matches.store(0, Ordering::Relaxed);
+
+    let tags_searched = AtomicUsize::new(0);
+    let truncated = AtomicBool::new(false);
+    let matches: Mutex<Vec<PatternMatch>> = Default::default();
+    let should_stop =
+        || progress.cancel.load(Ordering::Relaxed) || truncated.load(Ordering::Relaxed);
+
+    packages.par_iter().for_each(|path| {
+        if should_stop() {
+            return;
+        }
+
+        let pkg = match version.open(&path.path) {
+            Ok(pkg) => pkg,
+            Err(e) => {
+                error!("Failed to open package {path}: {e}");
+                progress.current.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+
+        let entries = if tags_only {
+            scannable_entries(pkg.as_ref(), version)
+        } else {
+            let mut entries: Vec<(usize, UEntryHeader)> =
+                pkg.entries().iter().cloned().enumerate().collect();
+            entries.sort_by_key(|v| v.1.starting_block);
+            entries
+        };
+
+        let mut package_matches = vec![];
+        for (t, e) in entries {
+            if should_stop() {
+                break;
+            }
+
+            let data = match pkg.read_entry(t) {
+                Ok(d) => d,
+                Err(e) => {
+                    error!("Failed to read entry {path}:{t}: {e}");
+                    continue;
+                }
+            };
+            tags_searched.fetch_add(1, Ordering::Relaxed);
+
+            let mut offsets = vec![];
+            let mut count = 0;
+            for offset in pattern.find(&data) {
+                if offsets.len() < MAX_OFFSETS_PER_TAG {
+                    offsets.push(offset as u64);
+                }
+                count += 1;
+            }
+            if count == 0 {
+                continue;
+            }
+
+            package_matches.push(PatternMatch {
+                tag: TagHash::new(pkg.pkg_id(), t as u16),
+                class: e.reference,
+                offsets,
+                count,
+            });
+            if progress.matches.fetch_add(1, Ordering::Relaxed) + 1 >= MAX_MATCHING_TAGS {
+                truncated.store(true, Ordering::Relaxed);
+            }
+        }
+
+        matches.lock().extend(package_matches);
+        progress.current.fetch_add(1, Ordering::Relaxed);
+    });
+
+    let mut matches = matches.into_inner();
+    matches.sort_by_key(|m| m.tag.0);
+    let tags_searched = tags_searched.into_inner();
+    info!(
+        "Found the pattern in {} out of {tags_searched} tags",
+        matches.len()
+    );
+
+    PatternSearchResult {
+        matches,
+        tags_searched,
+        truncated: truncated.into_inner(),
+        cancelled: progress.cancel.load(Ordering::Relaxed),
+    }
+}
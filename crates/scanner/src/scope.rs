@@ -0,0 +1,103 @@
+//! Restricts which packages are scanned when building a new cache
+//!
+//! Building a cache for a handful of packages is much faster than scanning the whole game, which is useful when only
+//! researching one content area. Partial caches are written next to the full cache under their own name (see
+//! [`crate::cache_path`]), so switching scopes doesn't throw away the other caches. References from or to tags
+//! outside of the scope are missing from a partial cache.
+
+use std::hash::{Hash, Hasher};
+
+use parking_lot::RwLock;
+use rustc_hash::FxHasher;
+use tiger_pkg::manager::PackagePath;
+
+#[derive(Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub enum ScanScope {
+    #[default]
+    All,
+    /// Packages whose name (eg. `w64_sr_gear_0398`) matches a glob pattern, `*` and `?` are supported and case is
+    /// ignored
+    Matching(String),
+    /// Packages with one of these names
+    Packages(Vec<String>),
+}
+
+impl ScanScope {
+    /// Name of a package as it's matched by the scope, `<name>_<id>`
+    pub fn package_name(path: &PackagePath) -> String {
+        format!("{}_{}", path.name, path.id)
+    }
+
+    pub fn includes(&self, path: &PackagePath) -> bool {
+        match self {
+            ScanScope::All => true,
+            ScanScope::Matching(pattern) => glob_match(pattern, &Self::package_name(path)),
+            ScanScope::Packages(names) => names.contains(&Self::package_name(path)),
+        }
+    }
+
+    pub fn is_partial(&self) -> bool {
+        *self != ScanScope::All
+    }
+
+    /// Identifies the scope in the cache file name, None for a full scan
+    pub(crate) fn cache_suffix(&self) -> Option<String> {
+        if !self.is_partial() {
+            return None;
+        }
+
+        let mut hasher = FxHasher::default();
+        self.hash(&mut hasher);
+        Some(format!("partial_{:016x}", hasher.finish()))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SCAN_SCOPE: RwLock<ScanScope> = RwLock::new(ScanScope::All);
+    static ref LOADED_SCOPE: RwLock<Option<ScanScope>> = RwLock::new(None);
+}
+
+/// Sets the packages to scan when building a new cache
+pub fn set_scan_scope(scope: ScanScope) {
+    *SCAN_SCOPE.write() = scope;
+}
+
+pub fn scan_scope() -> ScanScope {
+    SCAN_SCOPE.read().clone()
+}
+
+/// Scope of the cache that was last loaded or built by [`crate::load_tag_cache`]
+pub fn loaded_scope() -> Option<ScanScope> {
+    LOADED_SCOPE.read().clone()
+}
+
+pub(crate) fn set_loaded_scope(scope: ScanScope) {
+    *LOADED_SCOPE.write() = Some(scope);
+}
+
+/// Case-insensitive glob match, `*` matches any amount of characters and `?` matches a single one
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at, for backtracking
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
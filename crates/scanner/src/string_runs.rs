@@ -0,0 +1,132 @@
+//! Detection of inline UTF-8/UTF-16 strings outside of the raw string blob format
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tiger_pkg::Endian;
+
+/// Runs shorter than this (in characters) are ignored, short runs are mostly coincidental byte patterns
+pub const MIN_RUN_LENGTH: usize = 6;
+/// Longer runs are cut off
+const MAX_RUN_LENGTH: usize = 4096;
+
+static SCAN_STRING_RUNS: AtomicBool = AtomicBool::new(true);
+
+/// Enables looking for inline string runs while building the tag cache
+pub fn set_scan_string_runs(enabled: bool) {
+    SCAN_STRING_RUNS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn string_runs_enabled() -> bool {
+    SCAN_STRING_RUNS.load(Ordering::Relaxed)
+}
+
+fn is_printable(c: char) -> bool {
+    !c.is_control() || c == '\n' || c == '\t'
+}
+
+/// Whether a character belongs to a script that's likely to show up in game text
+fn is_common_script(c: char) -> bool {
+    matches!(c as u32,
+        0x20..=0x7E // ASCII
+        | 0xA0..=0x24F // Latin-1, Latin extended
+        | 0x400..=0x4FF // Cyrillic
+        | 0x3000..=0x30FF // CJK punctuation, hiragana, katakana
+        | 0x4E00..=0x9FFF // CJK unified ideographs
+        | 0xAC00..=0xD7AF // Hangul
+        | 0xFF00..=0xFFEF // Fullwidth forms
+    ) || c == '\n'
+        || c == '\t'
+}
+
+/// Fraction of characters from common scripts, or `None` if the string doesn't look like text at all
+fn text_score(s: &str) -> Option<f32> {
+    let mut chars = 0;
+    let mut common = 0;
+    let mut alphabetic = 0;
+    for c in s.chars() {
+        if !is_printable(c) {
+            return None;
+        }
+
+        chars += 1;
+        common += is_common_script(c) as usize;
+        alphabetic += c.is_alphabetic() as usize;
+    }
+
+    if chars < MIN_RUN_LENGTH || alphabetic * 2 < chars {
+        return None;
+    }
+
+    Some(common as f32 / chars as f32)
+}
+
+/// Null-terminated UTF-8 string starting at `offset`
+fn read_utf8_run(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let end = data[offset..]
+        .iter()
+        .take(MAX_RUN_LENGTH)
+        .position(|&b| b == 0)?;
+    let s = std::str::from_utf8(&data[offset..offset + end]).ok()?;
+    Some((s.to_string(), end + 1))
+}
+
+/// Null-terminated UTF-16 string starting at `offset`
+fn read_utf16_run(data: &[u8], offset: usize, endian: Endian) -> Option<(String, usize)> {
+    let units: Vec<u16> = data[offset..]
+        .chunks_exact(2)
+        .take(MAX_RUN_LENGTH)
+        .map(|b| match endian {
+            Endian::Big => u16::from_be_bytes([b[0], b[1]]),
+            Endian::Little => u16::from_le_bytes([b[0], b[1]]),
+        })
+        .take_while(|&u| u != 0)
+        .collect();
+
+    if units.len() == MAX_RUN_LENGTH {
+        return None;
+    }
+
+    let s = char::decode_utf16(units.iter().copied())
+        .collect::<Result<String, _>>()
+        .ok()?;
+    Some((s, (units.len() + 1) * 2))
+}
+
+/// Finds null-terminated UTF-8 and UTF-16 (little and big endian) strings of at least [`MIN_RUN_LENGTH`] characters
+///
+/// Runs have to start right after a null character (or at the start of the data) and consist mostly of letters from
+/// common scripts. When a run decodes as more than one encoding, the one with the most common characters wins
+pub fn find_string_runs(data: &[u8]) -> Vec<(u64, String)> {
+    let mut runs = vec![];
+    let mut offset = 0;
+    while offset < data.len() {
+        let mut candidates = vec![];
+        let utf8_start = data[offset] != 0 && (offset == 0 || data[offset - 1] == 0);
+        if let Some((s, len)) = utf8_start.then(|| read_utf8_run(data, offset)).flatten() {
+            candidates.extend(text_score(&s).map(|score| (score, s, len)));
+        }
+
+        // Big endian runs of latin text start with a null byte, so these only check the previous code unit
+        if offset & 1 == 0 && (offset == 0 || data[offset - 2..offset] == [0, 0]) {
+            for endian in [Endian::Little, Endian::Big] {
+                if let Some((s, len)) = read_utf16_run(data, offset, endian) {
+                    candidates.extend(text_score(&s).map(|score| (score, s, len)));
+                }
+            }
+        }
+
+        match candidates
+            .into_iter()
+            .filter(|(score, _, _)| *score >= 0.8)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+        {
+            Some((_, s, len)) => {
+                runs.push((offset as u64, s));
+                offset += len;
+            }
+            None => offset += 1,
+        }
+    }
+
+    runs
+}
@@ -0,0 +1,47 @@
+use std::fmt::Display;
+
+use tiger_pkg::TagHash;
+
+#[derive(Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode, Debug)]
+pub enum VideoContainer {
+    Bink1,
+    Bink2,
+    CriwareUsm,
+}
+
+impl VideoContainer {
+    /// Detects the container format from the first bytes of the data
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        match data.get(0..4)? {
+            [b'B', b'I', b'K', _] => Some(Self::Bink1),
+            [b'K', b'B', b'2', _] => Some(Self::Bink2),
+            b"CRID" => Some(Self::CriwareUsm),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VideoContainer::Bink1 => "bik",
+            VideoContainer::Bink2 => "bk2",
+            VideoContainer::CriwareUsm => "usm",
+        }
+    }
+}
+
+impl Display for VideoContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VideoContainer::Bink1 => f.write_str("Bink"),
+            VideoContainer::Bink2 => f.write_str("Bink 2"),
+            VideoContainer::CriwareUsm => f.write_str("CRI USM"),
+        }
+    }
+}
+
+#[derive(Clone, bincode::Encode, bincode::Decode, Debug)]
+pub struct ScannedVideo {
+    pub tag: TagHash,
+    pub container: VideoContainer,
+    pub size: u64,
+}
@@ -0,0 +1,158 @@
+//! Guesses what a raw string is, so the interesting ones (paths, event names) can be told apart from the rest
+//!
+//! The heuristics only look at the shape of the string, they're checked in the order of [`RawStringKind::ALL`] and the
+//! first match wins.
+
+/// Extensions of source files that show up in dev paths
+const DEV_EXTENSIONS: &[&str] = &[
+    "tga", "psd", "png", "dds", "tif", "tiff", "exr", "fbx", "max", "ma", "mb", "obj", "wav",
+    "ogg", "bnk", "wem", "cpp", "h", "txt", "xml", "json", "lua", "py", "tfx", "tfs", "bik", "usm",
+    "ttf", "otf",
+];
+
+const SHADER_EXTENSIONS: &[&str] = &["hlsl", "hlsli", "fx", "fxh", "shader", "cso", "bsl", "tfxs"];
+
+/// Wwise event prefixes
+const EVENT_PREFIXES: &[&str] = &[
+    "play_",
+    "stop_",
+    "pause_",
+    "resume_",
+    "mute_",
+    "unmute_",
+    "set_state_",
+    "set_switch_",
+    "set_rtpc_",
+    "trigger_",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RawStringKind {
+    /// File or directory path, eg. `activities/raid/eclipse/boss_arena.tga`
+    DevPath,
+    /// Shader or shader file name, eg. `ps_terrain_blend` or `lighting.hlsl`
+    ShaderName,
+    /// Audio or script event, eg. `play_weapon_reload`
+    EventName,
+    /// Dot separated or uppercase key, eg. `ui.roster.title` or `STR_PLAYER_NAME`
+    LocalizationKey,
+    /// Single identifier, eg. `boss_phase_2`
+    Identifier,
+    /// Text with spaces, eg. messages and descriptions
+    Text,
+    Other,
+}
+
+impl RawStringKind {
+    pub const ALL: [RawStringKind; 7] = [
+        RawStringKind::DevPath,
+        RawStringKind::ShaderName,
+        RawStringKind::EventName,
+        RawStringKind::LocalizationKey,
+        RawStringKind::Identifier,
+        RawStringKind::Text,
+        RawStringKind::Other,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RawStringKind::DevPath => "Dev paths",
+            RawStringKind::ShaderName => "Shader names",
+            RawStringKind::EventName => "Event names",
+            RawStringKind::LocalizationKey => "Localization keys",
+            RawStringKind::Identifier => "Identifiers",
+            RawStringKind::Text => "Text",
+            RawStringKind::Other => "Other",
+        }
+    }
+
+    pub fn classify(s: &str) -> Self {
+        let s = s.trim();
+        let lower = s.to_lowercase();
+        let has_whitespace = s.chars().any(char::is_whitespace);
+        let extension = lower
+            .rsplit_once('.')
+            .map(|(_, ext)| ext)
+            .filter(|ext| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        if extension.is_some_and(|ext| SHADER_EXTENSIONS.contains(&ext))
+            || (!has_whitespace
+                && (["ps_", "vs_", "cs_", "gs_"]
+                    .iter()
+                    .any(|p| lower.starts_with(p))
+                    || ["_ps", "_vs", "_cs", "_gs", "_shader"]
+                        .iter()
+                        .any(|p| lower.ends_with(p))))
+        {
+            return RawStringKind::ShaderName;
+        }
+
+        if is_path(s, &lower) || extension.is_some_and(|ext| DEV_EXTENSIONS.contains(&ext)) {
+            return RawStringKind::DevPath;
+        }
+
+        if has_whitespace {
+            return if s.chars().filter(|c| c.is_alphabetic()).count() * 2 >= s.chars().count() {
+                RawStringKind::Text
+            } else {
+                RawStringKind::Other
+            };
+        }
+
+        if EVENT_PREFIXES.iter().any(|p| lower.starts_with(p)) && is_identifier(s) {
+            return RawStringKind::EventName;
+        }
+
+        let segments = s.split('.').collect::<Vec<_>>();
+        if segments.len() >= 2
+            && segments.iter().all(|seg| is_identifier(seg))
+            && s.chars().any(|c| c.is_ascii_alphabetic())
+        {
+            return RawStringKind::LocalizationKey;
+        }
+
+        if is_identifier(s)
+            && s.chars().any(|c| c.is_ascii_uppercase())
+            && !s.chars().any(|c| c.is_ascii_lowercase())
+            && s.matches('_').count() >= 2
+        {
+            return RawStringKind::LocalizationKey;
+        }
+
+        if is_identifier(s) && s.chars().any(|c| c.is_ascii_alphabetic()) {
+            return RawStringKind::Identifier;
+        }
+
+        RawStringKind::Other
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// At least two non-empty segments separated by slashes, or a drive letter
+fn is_path(s: &str, lower: &str) -> bool {
+    let bytes = lower.as_bytes();
+    if bytes.len() > 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/')
+    {
+        return true;
+    }
+
+    if s.contains("://") || s.chars().any(|c| c.is_whitespace() && c != ' ') {
+        return false;
+    }
+
+    let segments = s
+        .split(['/', '\\'])
+        .filter(|seg| !seg.is_empty())
+        .collect::<Vec<_>>();
+    segments.len() >= 2
+        && segments.iter().all(|seg| {
+            seg.chars()
+                .all(|c| c.is_alphanumeric() || "_-. ".contains(c))
+        })
+}
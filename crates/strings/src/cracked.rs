@@ -0,0 +1,107 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Instant;
+
+use log::{info, warn};
+
+use crate::localized::StringCache;
+
+/// Strings for hashes that were cracked outside of quicktag, merged into the string cache on startup
+pub const CRACKED_HASHES_PATH: &str = "cracked_hashes.txt";
+
+static CRACKED_HASHES_DIRECTORY: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Sets the directory that [`CRACKED_HASHES_PATH`] and [`crate::wwise::WWISE_WORDLIST_PATH`] are in, the working
+/// directory is used if none is set
+pub fn set_cracked_hashes_directory(dir: Option<PathBuf>) {
+    *CRACKED_HASHES_DIRECTORY.write().unwrap() = dir;
+}
+
+pub(crate) fn data_file_path(name: &str) -> PathBuf {
+    match CRACKED_HASHES_DIRECTORY.read().unwrap().as_ref() {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+fn cracked_hashes_path() -> PathBuf {
+    data_file_path(CRACKED_HASHES_PATH)
+}
+
+/// Parses a list of `hash=string` lines. Hashes are hexadecimal, with an optional 0x prefix
+///
+/// Empty lines and lines starting with `#` are skipped
+pub fn parse_cracked_hashes(s: &str) -> Vec<(u32, String)> {
+    let mut hashes = vec![];
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((hash, string)) = line.split_once('=') else {
+            warn!("Skipping cracked hash on line {}: missing '='", i + 1);
+            continue;
+        };
+
+        let hash = hash.trim();
+        let hash = hash
+            .strip_prefix("0x")
+            .or_else(|| hash.strip_prefix("0X"))
+            .unwrap_or(hash);
+        match u32::from_str_radix(hash, 16) {
+            Ok(hash) => hashes.push((hash, string.to_string())),
+            Err(e) => warn!("Skipping cracked hash on line {}: {e}", i + 1),
+        }
+    }
+
+    hashes
+}
+
+/// Adds a string to the cache, unless the hash already has that exact string
+///
+/// Returns true if the string was added
+pub fn insert_string(cache: &mut StringCache, hash: u32, string: &str) -> bool {
+    let entry = cache.entry(hash).or_default();
+    if entry.iter().any(|s| s == string) {
+        return false;
+    }
+
+    entry.push(string.to_string());
+    true
+}
+
+/// Merges previously imported cracked hashes into the string cache
+pub fn load_cracked_hashes(cache: &mut StringCache) {
+    let load_start = Instant::now();
+    let path = cracked_hashes_path();
+    let Ok(data) = std::fs::read_to_string(&path) else {
+        return;
+    };
+
+    let hashes = parse_cracked_hashes(&data);
+    for (hash, string) in &hashes {
+        insert_string(cache, *hash, string);
+    }
+
+    info!(
+        "Loaded {} cracked hashes from {} in {}ms",
+        hashes.len(),
+        path.display(),
+        load_start.elapsed().as_millis()
+    );
+}
+
+/// Appends cracked hashes to the on-disk list, so they're included in the string cache from now on
+pub fn append_cracked_hashes(hashes: &[(u32, String)]) -> anyhow::Result<()> {
+    let mut f = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(cracked_hashes_path())?;
+    for (hash, string) in hashes {
+        writeln!(f, "{hash:08X}={string}")?;
+    }
+
+    Ok(())
+}
@@ -1,2 +1,5 @@
+pub mod classify;
+pub mod cracked;
 pub mod localized;
 pub mod wordlist;
+pub mod wwise;
@@ -4,9 +4,10 @@ use std::fmt::{Debug, Formatter, Write};
 use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::ops::Deref;
 use std::slice::Iter;
+use std::sync::{Arc, LazyLock, RwLock};
 
 use binrw::{BinRead, BinReaderExt, BinResult, Endian, VecArgs};
-use log::error;
+use log::{error, info};
 use rustc_hash::{FxHashMap, FxHashSet};
 use tiger_pkg::{DestinyVersion, GameVersion, MarathonVersion, TagHash, package_manager};
 
@@ -213,6 +214,78 @@ pub struct StringContainer {
     pub language_russian: TagHash,
 }
 
+/// Codes of the languages in a [`StringContainer`], in the same order as [`StringContainer::all_languages`]
+pub const LANGUAGE_CODES: &[&str] = &[
+    "en", "jp", "de", "fr", "es", "es_latam", "it", "ko", "zh_t", "zh_s", "pt", "pl", "ru",
+];
+
+static STRING_LANGUAGES: RwLock<Vec<&'static str>> = RwLock::new(Vec::new());
+static STRING_HASH_LANGUAGES: LazyLock<RwLock<FxHashMap<u32, &'static str>>> =
+    LazyLock::new(Default::default);
+/// Strings of the languages that have been read, by language code. Only the selected languages are kept resident
+static LANGUAGE_STRINGS: LazyLock<RwLock<FxHashMap<&'static str, LanguageSlot>>> =
+    LazyLock::new(Default::default);
+
+enum LanguageSlot {
+    Loading,
+    Loaded(Arc<StringCache>),
+    Failed(String),
+}
+
+/// Whether the strings of a language are in memory, see [`language_status`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum LanguageStatus {
+    NotLoaded,
+    Loading,
+    Loaded { strings: usize },
+    Failed(String),
+}
+
+/// Sets the languages that string hashes are resolved from. Unknown codes are ignored, an empty selection falls back
+/// to English
+///
+/// Only affects string maps created afterwards. Versions before Rise of Iron only have English strings
+pub fn set_string_languages(languages: &[String]) {
+    *STRING_LANGUAGES.write().unwrap() = LANGUAGE_CODES
+        .iter()
+        .copied()
+        .filter(|code| languages.iter().any(|l| l == code))
+        .collect();
+}
+
+/// The selected languages, in [`LANGUAGE_CODES`] order
+pub fn string_languages() -> Vec<&'static str> {
+    let languages = STRING_LANGUAGES.read().unwrap();
+    if languages.is_empty() {
+        vec!["en"]
+    } else {
+        languages.clone()
+    }
+}
+
+/// Whether the strings of a language are currently loaded
+pub fn language_status(language: &str) -> LanguageStatus {
+    match LANGUAGE_STRINGS.read().unwrap().get(language) {
+        None => LanguageStatus::NotLoaded,
+        Some(LanguageSlot::Loading) => LanguageStatus::Loading,
+        Some(LanguageSlot::Loaded(strings)) => LanguageStatus::Loaded {
+            strings: strings.len(),
+        },
+        Some(LanguageSlot::Failed(e)) => LanguageStatus::Failed(e.clone()),
+    }
+}
+
+/// Drops the strings of every language, so they're read again by the next [`create_stringmap`]. Needed when the
+/// packages have changed
+pub fn unload_languages() {
+    LANGUAGE_STRINGS.write().unwrap().clear();
+}
+
+/// The language a string hash was resolved through, for hashes that weren't resolved through English
+pub fn string_hash_language(hash: u32) -> Option<&'static str> {
+    STRING_HASH_LANGUAGES.read().unwrap().get(&hash).copied()
+}
+
 impl StringContainer {
     pub fn all_languages(&self) -> Vec<(&'static str, TagHash)> {
         vec![
@@ -392,9 +465,10 @@ pub fn decode_text(data: &[u8], cipher: u16) -> String {
     String::from_utf8_lossy(&data_clone).to_string()
 }
 
+/// Reads every localized string for the current game version, along with imported cracked hashes
 pub fn create_stringmap() -> anyhow::Result<StringCache> {
     // TODO: Change this match to use ordered version checking after destiny-pkg 0.11
-    match package_manager().version {
+    let mut cache = match package_manager().version {
         // cohae: Rise of Iron uses the same string format as D2
         GameVersion::Destiny(DestinyVersion::DestinyRiseOfIron)
         | GameVersion::Destiny(DestinyVersion::Destiny2Beta)
@@ -412,7 +486,10 @@ pub fn create_stringmap() -> anyhow::Result<StringCache> {
         GameVersion::Destiny(DestinyVersion::DestinyInternalAlpha) => {
             create_stringmap_d1_devalpha()
         }
-    }
+    }?;
+
+    crate::cracked::load_cracked_hashes(&mut cache);
+    Ok(cache)
 }
 
 pub fn create_stringmap_d2() -> anyhow::Result<StringCache> {
@@ -438,20 +515,98 @@ pub fn create_stringmap_d2() -> anyhow::Result<StringCache> {
 
     let old_format = matches!(package_manager().version, GameVersion::Destiny(v) if v <= DestinyVersion::Destiny2BeyondLight);
 
-    let mut tmp_map: FxHashMap<u32, FxHashSet<String>> = Default::default();
-    for (t, _) in package_manager()
-        .get_all_by_reference(reference_type)
-        .into_iter()
-    {
-        let Ok(textset_header) = package_manager().read_tag_binrw::<StringContainer>(t) else {
+    let languages = string_languages();
+    // Languages that aren't selected anymore are dropped, the ones that are still selected are kept as they are
+    LANGUAGE_STRINGS.write().unwrap().retain(|language, slot| {
+        languages.contains(language) && !matches!(slot, LanguageSlot::Failed(_))
+    });
+
+    let missing: Vec<&'static str> = {
+        let mut loaded = LANGUAGE_STRINGS.write().unwrap();
+        languages
+            .iter()
+            .copied()
+            .filter(|language| !loaded.contains_key(language))
+            .inspect(|language| {
+                loaded.insert(language, LanguageSlot::Loading);
+            })
+            .collect()
+    };
+
+    if !missing.is_empty() {
+        let containers: Vec<StringContainer> = package_manager()
+            .get_all_by_reference(reference_type)
+            .into_iter()
+            .filter_map(|(t, _)| package_manager().read_tag_binrw::<StringContainer>(t).ok())
+            .collect();
+
+        for language in missing {
+            let slot = match read_language_d2(&containers, language, old_format) {
+                Ok(strings) => {
+                    info!("Read {} {language} strings", strings.len());
+                    LanguageSlot::Loaded(Arc::new(strings))
+                }
+                Err(e) => {
+                    error!("Failed to read {language} strings: {e:?}");
+                    LanguageSlot::Failed(e.to_string())
+                }
+            };
+            LANGUAGE_STRINGS.write().unwrap().insert(language, slot);
+        }
+    }
+
+    // Earlier languages take priority. Hashes only take strings from the first language that resolved them, so
+    // hashes that exist in every language don't end up with a translation for each of them
+    let mut tmp_map = StringCache::default();
+    let mut resolved_by: FxHashMap<u32, &'static str> = Default::default();
+    let loaded = LANGUAGE_STRINGS.read().unwrap();
+    for &language in &languages {
+        let Some(LanguageSlot::Loaded(strings)) = loaded.get(language) else {
             continue;
         };
 
-        let Ok(data) = package_manager().read_tag(textset_header.language_english) else {
+        for (hash, language_strings) in strings.iter() {
+            if *resolved_by.entry(*hash).or_insert(language) == language {
+                tmp_map.insert(*hash, language_strings.clone());
+            }
+        }
+    }
+
+    resolved_by.retain(|_, language| *language != "en");
+    info!(
+        "Resolved string hashes from {} languages, {} hashes only through a language other than English",
+        languages.len(),
+        resolved_by.len()
+    );
+    *STRING_HASH_LANGUAGES.write().unwrap() = resolved_by;
+
+    Ok(tmp_map)
+}
+
+/// Reads the strings of a single language from every string container
+fn read_language_d2(
+    containers: &[StringContainer],
+    language: &'static str,
+    old_format: bool,
+) -> anyhow::Result<StringCache> {
+    let Some(language_index) = LANGUAGE_CODES.iter().position(|&l| l == language) else {
+        anyhow::bail!("Unknown language '{language}'");
+    };
+
+    let mut tmp_map: FxHashMap<u32, FxHashSet<String>> = Default::default();
+    for textset_header in containers {
+        let language_tag = textset_header.all_languages()[language_index].1;
+        let Ok(data) = package_manager().read_tag(language_tag) else {
             continue;
         };
         let mut cur = Cursor::new(&data);
-        let text_data: StringData = cur.read_le_args((old_format,))?;
+        let text_data: StringData = match cur.read_le_args((old_format,)) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("Failed to read {language} string data {language_tag}: {e:?}");
+                continue;
+            }
+        };
 
         for (combination, hash) in text_data
             .string_combinations
@@ -0,0 +1,49 @@
+//! Wwise object IDs (events, busses, states, ...) are the FNV-1 hash of the lowercase object name
+//!
+//! They're a separate namespace from the string hashes, names are read from a user-supplied wordlist
+//! ([`WWISE_WORDLIST_PATH`]) with one name per line.
+
+use std::time::Instant;
+
+use log::info;
+use quicktag_core::util::{FNV1_BASE, HashAlgorithm};
+
+use crate::cracked::data_file_path;
+
+/// Event (and other Wwise object) names, read from the same directory as the cracked hashes
+pub const WWISE_WORDLIST_PATH: &str = "wwise_wordlist.txt";
+
+/// Hashes a name the way Wwise does
+pub fn wwise_id(name: &str) -> u32 {
+    HashAlgorithm::Fnv1Lowercase.hash(name)
+}
+
+/// Calls `callback` with every name in the Wwise wordlist and its ID
+///
+/// Empty lines and lines starting with `#` are skipped
+pub fn load_wwise_wordlist<F: FnMut(&str, u32)>(mut callback: F) {
+    let load_start = Instant::now();
+    let Ok(wordlist) = std::fs::read_to_string(data_file_path(WWISE_WORDLIST_PATH)) else {
+        return;
+    };
+
+    let mut count = 0;
+    for name in wordlist.lines().map(str::trim) {
+        if name.is_empty() || name.starts_with('#') {
+            continue;
+        }
+
+        let id = wwise_id(name);
+        if id == FNV1_BASE {
+            continue;
+        }
+
+        callback(name, id);
+        count += 1;
+    }
+
+    info!(
+        "Loaded {count} names from Wwise wordlist in {}ms",
+        load_start.elapsed().as_millis()
+    );
+}
@@ -0,0 +1,355 @@
+//! Sandboxed scan-time analyzers: WebAssembly modules that are run on every tag while a tag cache is built, so
+//! community-made analyzers can be shared without having to trust them
+//!
+//! Analyzers are opt-in. When enabled (`[analyzers]` in the config), modules are loaded from the `analyzers` directory
+//! when quicktag starts, and run with wasmi without any imports. They can't reach anything but the tag data they're
+//! given, every call is limited in instructions (fuel) and every instance in memory. Instances are reused for the tags
+//! scanned after each other (one per scanner thread), so an analyzer shouldn't expect its memory to be reset between
+//! tags. An instance that fails is thrown away.
+//!
+//! A module has to export:
+//! - `memory`
+//! - `alloc(len: i32) -> i32`: returns a pointer to `len` bytes, which the tag data is copied into
+//! - `analyze(ptr: i32, len: i32, tag: i32, class: i32) -> i64`: returns the pointer (upper 32 bits) and length
+//!   (lower 32 bits) of a UTF-8 JSON value describing what was found, or 0 if there's nothing to report
+//! - optionally `wants(class: i32) -> i32`: returning 0 skips tags of the class, without copying their data
+//!
+//! Findings are written next to the tag cache (`tags_<key>.analyzers.json`) and shown in the tag view. When only the
+//! changed packages are rescanned, the findings of the other packages are kept.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Context;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use parking_lot::{Mutex, RwLock};
+use quicktag_scanner::TagCache;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use tiger_pkg::TagHash;
+use wasmi::{Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use crate::config;
+
+/// JSON output larger than this is discarded
+const MAX_OUTPUT_SIZE: usize = 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Finding {
+    /// File name of the analyzer, without the extension
+    pub analyzer: String,
+    pub output: serde_json::Value,
+}
+
+struct Analyzer {
+    name: String,
+    engine: Engine,
+    module: Module,
+    fuel: u64,
+    memory_limit: usize,
+    /// Calls that failed during the current cache build. Only the first error is logged
+    failures: AtomicUsize,
+    /// Idle instances, one for every scanner thread that's run the analyzer. Cleared after every cache build
+    instances: Mutex<Vec<Instance>>,
+}
+
+/// An instantiated analyzer module
+struct Instance {
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    analyze: TypedFunc<(i32, i32, i32, i32), i64>,
+    wants: Option<TypedFunc<i32, i32>>,
+}
+
+impl Analyzer {
+    fn load(path: &Path, fuel: u64, memory_limit: usize) -> anyhow::Result<Self> {
+        let mut engine_config = wasmi::Config::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let bytes = std::fs::read(path)?;
+        let module = Module::new(&engine, &bytes[..])?;
+
+        // Checked up front, so a module that can't work is reported once instead of for every tag
+        let imports = module
+            .imports()
+            .map(|i| format!("{}::{}", i.module(), i.name()))
+            .collect_vec();
+        anyhow::ensure!(
+            imports.is_empty(),
+            "Analyzers can't import anything, the module imports {}",
+            imports.join(", ")
+        );
+        for export in ["memory", "alloc", "analyze"] {
+            anyhow::ensure!(
+                module.exports().any(|e| e.name() == export),
+                "The module doesn't export '{export}'"
+            );
+        }
+
+        Ok(Self {
+            name: path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            engine,
+            module,
+            fuel,
+            memory_limit,
+            failures: AtomicUsize::new(0),
+            instances: Mutex::new(vec![]),
+        })
+    }
+
+    fn instantiate(&self) -> anyhow::Result<Instance> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.memory_limit)
+            .build();
+        let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel)?;
+
+        let instance = Linker::<StoreLimits>::new(&self.engine)
+            .instantiate(&mut store, &self.module)?
+            .start(&mut store)?;
+
+        Ok(Instance {
+            memory: instance
+                .get_memory(&store, "memory")
+                .context("The module doesn't export its memory")?,
+            alloc: instance.get_typed_func::<i32, i32>(&store, "alloc")?,
+            analyze: instance.get_typed_func::<(i32, i32, i32, i32), i64>(&store, "analyze")?,
+            wants: instance.get_typed_func::<i32, i32>(&store, "wants").ok(),
+            store,
+        })
+    }
+
+    /// Runs the analyzer on a tag, with an idle instance if there is one
+    fn run(
+        &self,
+        tag: TagHash,
+        class: u32,
+        data: &[u8],
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        let idle = self.instances.lock().pop();
+        let mut instance = match idle {
+            Some(instance) => instance,
+            None => self.instantiate()?,
+        };
+
+        // A failed call can leave the instance in any state (eg. out of memory halfway through), so it's not reused
+        let result = self.call(&mut instance, tag, class, data);
+        if result.is_ok() {
+            self.instances.lock().push(instance);
+        }
+
+        result
+    }
+
+    fn call(
+        &self,
+        instance: &mut Instance,
+        tag: TagHash,
+        class: u32,
+        data: &[u8],
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        let store = &mut instance.store;
+        store.set_fuel(self.fuel)?;
+
+        if let Some(wants) = &instance.wants {
+            if wants.call(&mut *store, class as i32)? == 0 {
+                return Ok(None);
+            }
+        }
+
+        let ptr = instance.alloc.call(&mut *store, data.len() as i32)?;
+        instance
+            .memory
+            .write(&mut *store, ptr as u32 as usize, data)?;
+        let result = instance.analyze.call(
+            &mut *store,
+            (ptr, data.len() as i32, tag.0 as i32, class as i32),
+        )? as u64;
+        if result == 0 {
+            return Ok(None);
+        }
+
+        let (ptr, len) = ((result >> 32) as usize, (result & 0xFFFF_FFFF) as usize);
+        anyhow::ensure!(len <= MAX_OUTPUT_SIZE, "Output is too large ({len} bytes)");
+        let mut output = vec![0; len];
+        instance.memory.read(&*store, ptr, &mut output)?;
+        let value: serde_json::Value =
+            serde_json::from_slice(&output).context("Output isn't valid JSON")?;
+
+        Ok((!value.is_null()).then_some(value))
+    }
+}
+
+/// Findings of the cache build in progress
+#[derive(Default)]
+struct PendingFindings {
+    /// Packages that were scanned, their old findings are replaced
+    packages: FxHashSet<u16>,
+    findings: FxHashMap<TagHash, Vec<Finding>>,
+}
+
+lazy_static! {
+    static ref ANALYZERS: RwLock<Vec<Analyzer>> = RwLock::new(vec![]);
+    static ref PENDING: Mutex<PendingFindings> = Mutex::new(PendingFindings::default());
+    /// Findings of the current cache, with the path of the cache they were loaded for
+    static ref FINDINGS: RwLock<Option<(PathBuf, FxHashMap<TagHash, Vec<Finding>>)>> =
+        RwLock::new(None);
+}
+
+/// `tags_<key>.cache` -> `tags_<key>.analyzers.json`
+fn findings_path(cache_path: &Path) -> PathBuf {
+    cache_path.with_extension("analyzers.json")
+}
+
+fn load_findings(cache_path: &Path) -> FxHashMap<TagHash, Vec<Finding>> {
+    let path = findings_path(cache_path);
+    let Ok(data) = std::fs::read(&path) else {
+        return FxHashMap::default();
+    };
+
+    match serde_json::from_slice::<FxHashMap<String, Vec<Finding>>>(&data) {
+        Ok(findings) => findings
+            .into_iter()
+            .filter_map(|(tag, findings)| {
+                Some((TagHash(u32::from_str_radix(&tag, 16).ok()?), findings))
+            })
+            .collect(),
+        Err(e) => {
+            error!(
+                "Failed to read analyzer findings from {}: {e}",
+                path.display()
+            );
+            FxHashMap::default()
+        }
+    }
+}
+
+fn write_findings(
+    cache_path: &Path,
+    findings: &FxHashMap<TagHash, Vec<Finding>>,
+) -> anyhow::Result<()> {
+    let findings: FxHashMap<String, &Vec<Finding>> = findings
+        .iter()
+        .map(|(tag, f)| (tag.to_string(), f))
+        .collect();
+    std::fs::write(findings_path(cache_path), serde_json::to_vec(&findings)?)?;
+    Ok(())
+}
+
+/// Loads the analyzers and hooks them into the scanner. Does nothing if there are no analyzers, or they're disabled
+pub fn register() {
+    let settings = config::with(|c| c.analyzers.clone());
+    if !settings.enabled {
+        return;
+    }
+
+    let directory = config::data_path(&settings.directory);
+    let Ok(entries) = std::fs::read_dir(&directory) else {
+        return;
+    };
+
+    let mut analyzers = vec![];
+    for path in entries.filter_map(|e| Some(e.ok()?.path())).filter(|p| {
+        p.extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("wasm"))
+    }) {
+        match Analyzer::load(
+            &path,
+            settings.fuel_per_tag,
+            settings.memory_limit_mb as usize * 1024 * 1024,
+        ) {
+            Ok(analyzer) => analyzers.push(analyzer),
+            Err(e) => error!("Failed to load analyzer {}: {e:?}", path.display()),
+        }
+    }
+    if analyzers.is_empty() {
+        return;
+    }
+
+    info!(
+        "Loaded {} analyzers: {}",
+        analyzers.len(),
+        analyzers.iter().map(|a| a.name.as_str()).join(", ")
+    );
+    *ANALYZERS.write() = analyzers;
+    quicktag_scanner::set_tag_analyzer(Some(Box::new(analyze_tag)));
+    quicktag_scanner::add_cache_built_callback(|cache, path| {
+        if let Err(e) = save_findings(cache, path) {
+            error!("Failed to save analyzer findings: {e:?}");
+        }
+    });
+}
+
+fn analyze_tag(tag: TagHash, class: u32, data: &[u8]) {
+    let mut findings = vec![];
+    for analyzer in ANALYZERS.read().iter() {
+        match analyzer.run(tag, class, data) {
+            Ok(Some(output)) => findings.push(Finding {
+                analyzer: analyzer.name.clone(),
+                output,
+            }),
+            Ok(None) => {}
+            Err(e) => {
+                if analyzer.failures.fetch_add(1, Ordering::Relaxed) == 0 {
+                    warn!("Analyzer {} failed on tag {tag}: {e}", analyzer.name);
+                }
+            }
+        }
+    }
+
+    let mut pending = PENDING.lock();
+    pending.packages.insert(tag.pkg_id());
+    if !findings.is_empty() {
+        pending.findings.insert(tag, findings);
+    }
+}
+
+/// Merges the findings of the build that just finished with the findings of the packages that weren't scanned again
+fn save_findings(cache: &TagCache, cache_path: &Path) -> anyhow::Result<()> {
+    let pending = std::mem::take(&mut *PENDING.lock());
+    for analyzer in ANALYZERS.read().iter() {
+        // Frees the memory of the instances until the next build
+        analyzer.instances.lock().clear();
+        let failures = analyzer.failures.swap(0, Ordering::Relaxed);
+        if failures > 0 {
+            warn!("Analyzer {} failed on {failures} tags", analyzer.name);
+        }
+    }
+
+    let mut findings = load_findings(cache_path);
+    findings.retain(|tag, _| {
+        !pending.packages.contains(&tag.pkg_id()) && cache.hashes.contains_key(tag)
+    });
+    findings.extend(pending.findings);
+    info!("Analyzers reported findings for {} tags", findings.len());
+
+    write_findings(cache_path, &findings)?;
+    *FINDINGS.write() = Some((cache_path.to_path_buf(), findings));
+    Ok(())
+}
+
+/// Findings for a tag in the current cache
+pub fn findings(tag: TagHash) -> Vec<Finding> {
+    let cache_path = quicktag_scanner::cache_path();
+    if FINDINGS
+        .read()
+        .as_ref()
+        .is_none_or(|(path, _)| *path != cache_path)
+    {
+        let findings = load_findings(&cache_path);
+        *FINDINGS.write() = Some((cache_path, findings));
+    }
+
+    FINDINGS
+        .read()
+        .as_ref()
+        .and_then(|(_, findings)| findings.get(&tag).cloned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,160 @@
+//! Three-way merge for importing someone else's annotation database
+//!
+//! The version of a file that was imported last is kept in [`BASE_DIR`], and is used as the common ancestor the next
+//! time a file with the same name is imported. Entries that only changed on one side are merged automatically,
+//! entries that changed on both sides are conflicts that the user has to resolve. Without a previous import every
+//! difference with an existing annotation is a conflict
+
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools;
+use log::{error, info};
+use tiger_pkg::TagHash;
+
+use crate::annotations::{self, Annotations, TagAnnotation};
+use crate::config;
+
+/// Previously imported versions of annotation files, keyed by file name
+pub const BASE_DIR: &str = "annotation_imports";
+
+pub struct MergeEntry {
+    pub tag: TagHash,
+    /// The annotation in the previously imported version of the file, if there was one
+    pub base: Option<TagAnnotation>,
+    pub mine: Option<TagAnnotation>,
+    pub theirs: Option<TagAnnotation>,
+    /// Whether their version is used. Defaults to true for automatic changes and false for conflicts
+    pub accept: bool,
+}
+
+pub struct MergePlan {
+    pub source: PathBuf,
+    /// Whether the file was imported before, so there's a common ancestor to compare against
+    pub has_base: bool,
+    /// Entries that only they changed
+    pub automatic: Vec<MergeEntry>,
+    /// Entries that both sides changed differently
+    pub conflicts: Vec<MergeEntry>,
+    theirs: Annotations,
+}
+
+fn base_path(source: &Path) -> PathBuf {
+    let name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "annotations.json".to_string());
+    config::data_path(BASE_DIR).join(name)
+}
+
+fn non_empty(annotation: Option<&TagAnnotation>) -> Option<TagAnnotation> {
+    annotation.filter(|a| !a.is_empty()).cloned()
+}
+
+/// Compares the file against the current annotations, without changing anything yet
+pub fn plan(source: &Path) -> anyhow::Result<MergePlan> {
+    let theirs = annotations::read_file(source)?;
+    let base = annotations::read_file(base_path(source)).ok();
+
+    let mut automatic = vec![];
+    let mut conflicts = vec![];
+    annotations::with(|mine| {
+        let tags = theirs
+            .tags
+            .keys()
+            .chain(base.iter().flat_map(|b| b.tags.keys()))
+            .unique()
+            .sorted_by_key(|t| t.0);
+
+        for &tag in tags {
+            let mine = non_empty(mine.get(tag));
+            let theirs = non_empty(theirs.get(tag));
+            if mine == theirs {
+                continue;
+            }
+
+            let entry = |base, accept| MergeEntry {
+                tag,
+                base,
+                mine: mine.clone(),
+                theirs: theirs.clone(),
+                accept,
+            };
+
+            match &base {
+                Some(base) => {
+                    let base = non_empty(base.get(tag));
+                    if theirs == base {
+                        // Only changed on our side
+                        continue;
+                    }
+
+                    if mine == base {
+                        automatic.push(entry(base, true));
+                    } else {
+                        conflicts.push(entry(base, false));
+                    }
+                }
+                // Without a previous import, entries they don't have aren't deletions
+                None if theirs.is_none() => {}
+                None if mine.is_none() => automatic.push(entry(None, true)),
+                None => conflicts.push(entry(None, false)),
+            }
+        }
+    });
+
+    Ok(MergePlan {
+        source: source.to_path_buf(),
+        has_base: base.is_some(),
+        automatic,
+        conflicts,
+        theirs,
+    })
+}
+
+impl MergePlan {
+    /// Applies the accepted entries (as a single undoable edit), and stores their file as the base for the next import
+    pub fn apply(self) -> usize {
+        let name = self
+            .source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let accepted = self
+            .automatic
+            .iter()
+            .chain(&self.conflicts)
+            .filter(|e| e.accept)
+            .collect_vec();
+        annotations::with_mut(&format!("Import annotations from {name}"), |a| {
+            for entry in &accepted {
+                match &entry.theirs {
+                    Some(annotation) => {
+                        a.tags.insert(entry.tag, annotation.clone());
+                    }
+                    None => {
+                        a.tags.remove(&entry.tag);
+                    }
+                }
+            }
+        });
+        info!(
+            "Imported {} annotations from {}",
+            accepted.len(),
+            self.source.display()
+        );
+
+        let base_path = base_path(&self.source);
+        if let Err(e) = std::fs::create_dir_all(config::data_path(BASE_DIR))
+            .map_err(anyhow::Error::from)
+            .and_then(|_| annotations::write_file(&base_path, &self.theirs))
+        {
+            error!(
+                "Failed to store {} for the next import: {e:?}",
+                base_path.display()
+            );
+        }
+
+        accepted.len()
+    }
+}
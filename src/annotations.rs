@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::{DateTime, Local};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use log::{error, info};
+use parking_lot::{Mutex, RwLock};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use tiger_pkg::TagHash;
+
+use crate::backups;
+use crate::config;
+use crate::shutdown::DirtyFlag;
+
+pub const ANNOTATIONS_PATH: &str = "annotations.json";
+
+lazy_static! {
+    static ref ANNOTATIONS: RwLock<Annotations> = RwLock::new(Annotations::default());
+    static ref HISTORY: Mutex<EditHistory> = Mutex::new(EditHistory::default());
+}
+
+/// Maximum amount of edits that can be undone
+const MAX_UNDO: usize = 256;
+
+/// Incremented every time the annotations are loaded or modified
+static GENERATION: AtomicU64 = AtomicU64::new(1);
+
+pub static DIRTY: DirtyFlag = DirtyFlag::new();
+
+/// User-made names, labels and notes for tags
+#[derive(Default)]
+pub struct Annotations {
+    pub tags: FxHashMap<TagHash, TagAnnotation>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct TagAnnotation {
+    pub name: Option<String>,
+    pub labels: Vec<String>,
+    pub notes: String,
+}
+
+impl TagAnnotation {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.labels.is_empty() && self.notes.is_empty()
+    }
+}
+
+/// The annotations changed by a single call to [`with_mut`]
+pub struct AnnotationEdit {
+    pub description: String,
+    pub time: DateTime<Local>,
+    /// (tag, annotation before, annotation after). Empty annotations are stored as None
+    changes: Vec<(TagHash, Option<TagAnnotation>, Option<TagAnnotation>)>,
+}
+
+impl AnnotationEdit {
+    pub fn tag_count(&self) -> usize {
+        self.changes.len()
+    }
+}
+
+/// Edits made this session
+#[derive(Default)]
+pub struct EditHistory {
+    /// Applied edits, oldest first
+    pub undo: Vec<AnnotationEdit>,
+    /// Undone edits, the most recently undone one last. Cleared by new edits
+    pub redo: Vec<AnnotationEdit>,
+}
+
+/// On-disk format, keyed by hex tag hashes so the file stays readable
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct AnnotationsFile {
+    tags: BTreeMap<String, TagAnnotation>,
+}
+
+impl Annotations {
+    pub fn get(&self, tag: TagHash) -> Option<&TagAnnotation> {
+        self.tags.get(&tag)
+    }
+
+    pub fn entry(&mut self, tag: TagHash) -> &mut TagAnnotation {
+        self.tags.entry(tag).or_default()
+    }
+
+    pub fn add_label(&mut self, tag: TagHash, label: &str) {
+        let annotation = self.entry(tag);
+        if !annotation.labels.iter().any(|l| l == label) {
+            annotation.labels.push(label.to_string());
+        }
+    }
+
+    fn to_file(&self) -> AnnotationsFile {
+        AnnotationsFile {
+            tags: self
+                .tags
+                .iter()
+                .filter(|(_, a)| !a.is_empty())
+                .map(|(t, a)| (format!("{:08X}", t.0), a.clone()))
+                .collect(),
+        }
+    }
+
+    fn from_file(file: AnnotationsFile) -> Self {
+        Self {
+            tags: file
+                .tags
+                .into_iter()
+                .filter_map(|(t, a)| Some((TagHash(u32::from_str_radix(&t, 16).ok()?), a)))
+                .collect(),
+        }
+    }
+}
+
+/// Reads an annotation database file, eg. one shared by someone else
+pub fn read_file(path: impl AsRef<Path>) -> anyhow::Result<Annotations> {
+    let file = serde_json::from_str::<AnnotationsFile>(&std::fs::read_to_string(path)?)?;
+    Ok(Annotations::from_file(file))
+}
+
+pub fn write_file(path: impl AsRef<Path>, annotations: &Annotations) -> anyhow::Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(&annotations.to_file())?)?;
+    Ok(())
+}
+
+/// Loads the annotation database (if it exists)
+pub fn load() {
+    let path = config::data_path(ANNOTATIONS_PATH);
+    if !path.exists() {
+        return;
+    }
+
+    match read_file(&path) {
+        Ok(annotations) => {
+            info!(
+                "Loaded {} tag annotations from {}",
+                annotations.tags.len(),
+                path.display()
+            );
+            *ANNOTATIONS.write() = annotations;
+            GENERATION.fetch_add(1, Ordering::Relaxed);
+            // Edits made before (re)loading can't be undone on top of the loaded annotations
+            *HISTORY.lock() = EditHistory::default();
+        }
+        Err(e) => {
+            error!("Failed to load annotations: {e:?}");
+        }
+    }
+}
+
+/// Reads the annotation database
+pub fn with<R>(f: impl FnOnce(&Annotations) -> R) -> R {
+    f(&ANNOTATIONS.read())
+}
+
+/// Changes whenever the annotations change, so derived data (eg. the [`crate::text_index`]) knows when to update
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+fn non_empty(annotation: Option<&TagAnnotation>) -> Option<TagAnnotation> {
+    annotation.filter(|a| !a.is_empty()).cloned()
+}
+
+/// Saves the annotations after they were changed
+fn commit(annotations: &Annotations) {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    save(annotations);
+}
+
+fn save(annotations: &Annotations) {
+    backups::backup_before_write(ANNOTATIONS_PATH);
+    DIRTY.record(
+        "annotations",
+        write_file(config::data_path(ANNOTATIONS_PATH), annotations),
+    );
+}
+
+/// Saves the annotations as they are now, see [`crate::shutdown::flush`]
+pub fn save_current() {
+    save(&ANNOTATIONS.read());
+}
+
+/// Modifies the annotation database, then saves it. The changes can be undone with [`undo`], the description is shown
+/// in the edit history (eg. "Label 12 tags as 'Foo'")
+pub fn with_mut<R>(description: &str, f: impl FnOnce(&mut Annotations) -> R) -> R {
+    let mut annotations = ANNOTATIONS.write();
+    let before = annotations.tags.clone();
+    let result = f(&mut annotations);
+
+    let changes = before
+        .keys()
+        .chain(annotations.tags.keys())
+        .unique()
+        .filter_map(|tag| {
+            let old = non_empty(before.get(tag));
+            let new = non_empty(annotations.tags.get(tag));
+            (old != new).then_some((*tag, old, new))
+        })
+        .collect_vec();
+    if changes.is_empty() {
+        return result;
+    }
+
+    commit(&annotations);
+    drop(annotations);
+
+    info!("Annotation edit: {description} ({} tags)", changes.len());
+    let mut history = HISTORY.lock();
+    history.redo.clear();
+    history.undo.push(AnnotationEdit {
+        description: description.to_string(),
+        time: Local::now(),
+        changes,
+    });
+    if history.undo.len() > MAX_UNDO {
+        history.undo.remove(0);
+    }
+
+    result
+}
+
+/// Sets every changed tag to its annotation before (`undo`) or after the edit
+fn apply_edit(edit: &AnnotationEdit, undo: bool) {
+    let mut annotations = ANNOTATIONS.write();
+    for (tag, old, new) in &edit.changes {
+        let annotation = if undo { old } else { new };
+        match annotation {
+            Some(annotation) => {
+                annotations.tags.insert(*tag, annotation.clone());
+            }
+            None => {
+                annotations.tags.remove(tag);
+            }
+        }
+    }
+
+    commit(&annotations);
+}
+
+/// Reverts the last edit, returning its description
+pub fn undo() -> Option<String> {
+    let edit = HISTORY.lock().undo.pop()?;
+    apply_edit(&edit, true);
+    info!("Undid annotation edit: {}", edit.description);
+
+    let description = edit.description.clone();
+    HISTORY.lock().redo.push(edit);
+    Some(description)
+}
+
+/// Applies the last undone edit again, returning its description
+pub fn redo() -> Option<String> {
+    let edit = HISTORY.lock().redo.pop()?;
+    apply_edit(&edit, false);
+    info!("Redid annotation edit: {}", edit.description);
+
+    let description = edit.description.clone();
+    HISTORY.lock().undo.push(edit);
+    Some(description)
+}
+
+/// Reads the edits made this session
+pub fn with_history<R>(f: impl FnOnce(&EditHistory) -> R) -> R {
+    f(&HISTORY.lock())
+}
@@ -0,0 +1,119 @@
+//! Rotating backups of the user databases (annotations, watch list and hex view sidecars)
+//!
+//! Before one of them is written, the file on disk is copied to `backups/<file>.<timestamp>.bak` in the data
+//! directory. A new backup is made at most once per [`BackupSettings::interval_minutes`], and only the newest
+//! [`BackupSettings::count`] backups of every file are kept. Backups can be restored from the edit menu, restoring backs
+//! up the current file first so it can be undone.
+//!
+//! Files are identified by their path relative to the data directory (eg. `annotations.json`), backups of files in a
+//! subdirectory go into the same subdirectory of `backups`.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use log::{error, info};
+
+use crate::config::{self, BackupSettings};
+
+pub const BACKUPS_DIR: &str = "backups";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+pub struct Backup {
+    pub path: PathBuf,
+    pub time: DateTime<Local>,
+    pub size: u64,
+}
+
+fn backup_dir(name: &Path) -> PathBuf {
+    let dir = config::data_path(BACKUPS_DIR);
+    match name.parent() {
+        Some(parent) => dir.join(parent),
+        None => dir,
+    }
+}
+
+/// Backups of a file, newest first
+pub fn list(name: impl AsRef<Path>) -> Vec<Backup> {
+    let name = name.as_ref();
+    let Some(file_name) = name.file_name().map(|f| f.to_string_lossy().to_string()) else {
+        return vec![];
+    };
+    let Ok(dir) = std::fs::read_dir(backup_dir(name)) else {
+        return vec![];
+    };
+
+    let mut backups: Vec<Backup> = dir
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let entry_name = entry.file_name().to_string_lossy().to_string();
+            let timestamp = entry_name
+                .strip_prefix(&file_name)?
+                .strip_prefix('.')?
+                .strip_suffix(".bak")?;
+            let time = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+
+            Some(Backup {
+                path: entry.path(),
+                time: Local.from_local_datetime(&time).single()?,
+                size: entry.metadata().ok()?.len(),
+            })
+        })
+        .collect();
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.time));
+    backups
+}
+
+/// Backs up a file from the data directory before it's overwritten, if the last backup is old enough
+pub fn backup_before_write(name: impl AsRef<Path>) {
+    let settings = config::with(|c| c.backups.clone());
+    if let Err(e) = backup(name.as_ref(), &settings, false) {
+        error!("Failed to back up {}: {e:?}", name.as_ref().display());
+    }
+}
+
+fn backup(name: &Path, settings: &BackupSettings, force: bool) -> anyhow::Result<()> {
+    let source = config::data_path(name);
+    if settings.count == 0 || !source.exists() {
+        return Ok(());
+    }
+
+    let backups = list(name);
+    let interval = chrono::Duration::minutes(settings.interval_minutes as i64);
+    if !force
+        && backups
+            .first()
+            .is_some_and(|b| Local::now() - b.time < interval)
+    {
+        return Ok(());
+    }
+
+    let dir = backup_dir(name);
+    std::fs::create_dir_all(&dir)?;
+    let file_name = name.file_name().unwrap_or_default().to_string_lossy();
+    let path = dir.join(format!(
+        "{file_name}.{}.bak",
+        Local::now().format(TIMESTAMP_FORMAT)
+    ));
+    std::fs::copy(&source, &path)?;
+
+    // The new backup isn't in the list yet, so one less of the existing ones is kept
+    for old in backups.iter().skip(settings.count as usize - 1) {
+        std::fs::remove_file(&old.path).ok();
+    }
+
+    Ok(())
+}
+
+/// Replaces a file in the data directory with one of its backups. The current file is backed up first
+pub fn restore(name: impl AsRef<Path>, backup: &Backup) -> anyhow::Result<()> {
+    let name = name.as_ref();
+    // Read before backing up the current file, which might rotate out the backup that's being restored
+    let data = std::fs::read(&backup.path)?;
+    let settings = config::with(|c| c.backups.clone());
+    self::backup(name, &settings, true)?;
+
+    std::fs::write(config::data_path(name), data)?;
+    info!("Restored {} from {}", name.display(), backup.path.display());
+    Ok(())
+}
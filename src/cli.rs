@@ -0,0 +1,715 @@
+//! Command line subcommands that print information from the tag cache or extract entries instead of opening the GUI
+//!
+//! Output goes to stdout (text or JSON), logs go to stderr, so the output can be piped into other tools. A tag that
+//! doesn't exist or an extraction that fails exits with an error. `hash` is the only command that doesn't need the
+//! packages
+//!
+//! With `--json`, every command prints a single JSON object with a `schema` field (see [`JSON_SCHEMA_VERSION`]). The
+//! fields of each command are documented in the README, and on the output structs below
+//!
+//! `--headless` builds or refreshes the tag cache without a window, and answers the queries passed with it
+//! (`--refs`, `--search-string`, `--search-bytes`, `--export`). Meant for servers and scripts that process game updates
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use itertools::Itertools;
+use log::{error, info};
+use quicktag_core::{tagtypes::TagType, util::fnv1};
+use quicktag_scanner::{
+    cache_path, load_tag_cache,
+    pattern_search::{search_packages, BytePattern, PatternKind, PatternSearchProgress},
+    scope::{glob_match, ScanScope},
+    TagCache,
+};
+use quicktag_strings::localized::{create_stringmap, set_string_languages, LANGUAGE_CODES};
+use quicktag_strings::wwise::wwise_id;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use tiger_pkg::{package_manager, TagHash};
+
+use crate::gui::{
+    extract_package, parse_tag64, tag_names, EntryCategory, ExtractOptions, ExtractProgress,
+};
+use crate::tag_data;
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Prints the tags referenced by a tag
+    Refs(RefsArgs),
+    /// Prints the tags that reference a tag
+    Refsto(RefsArgs),
+    /// Extracts package entries to a directory, like "Extract entire package" in the GUI
+    Extract(ExtractArgs),
+    /// Prints localized strings with their hash and the tags that use them
+    Strings(StringsArgs),
+    /// Prints the FNV1 hash of a string, as used for string and wordlist hashes
+    Hash(HashArgs),
+}
+
+/// Version of the JSON output. Only increased when a field is removed or changes meaning, new fields can be added
+/// without changing it
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(clap::Args, Debug)]
+pub struct RefsArgs {
+    /// 32-bit tag hash (eg. `80A1B2C3`), or a 64-bit hash as shown in the tag view
+    hash: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExtractArgs {
+    /// Only extract entries of these types, can be given more than once. Extracts everything if omitted
+    #[arg(long = "type", value_enum)]
+    types: Vec<EntryCategory>,
+
+    /// Only extract packages whose name matches this pattern (eg. `europa*`), `*` and `?` are supported
+    #[arg(long)]
+    pkg: Option<String>,
+
+    /// Output directory, every package is extracted to its own subdirectory
+    #[arg(long, default_value = "extracted")]
+    out: PathBuf,
+
+    /// Write textures as DDS files instead of separate header and data entries
+    #[arg(long)]
+    dds: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct StringsArgs {
+    /// Language code (eg. `en`, `de`), defaults to the languages in the settings
+    #[arg(long)]
+    lang: Option<String>,
+
+    /// Only print strings containing this text, ignoring case
+    #[arg(long)]
+    contains: Option<String>,
+}
+
+/// Flags of `--headless`, which are only accepted together with it
+#[derive(clap::Args, Debug)]
+pub struct HeadlessArgs {
+    /// Builds or updates the tag cache and answers the queries below without opening the GUI
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Prints the tags referenced by a tag, like `refs`
+    #[arg(long, requires = "headless", value_name = "TAGHASH")]
+    refs: Option<String>,
+
+    /// Prints the localized strings containing this text and the tags that use them, like `strings --contains`
+    #[arg(long, requires = "headless", value_name = "TEXT")]
+    search_string: Option<String>,
+
+    /// Prints the tags whose data contains a byte pattern (eg. `80 80 ?? BD`), with the offsets of the matches
+    #[arg(long, requires = "headless", value_name = "PATTERN")]
+    search_bytes: Option<String>,
+
+    /// How the `--search-bytes` pattern is read. Numbers are searched for in the game's byte order, at 4-byte aligned
+    /// offsets
+    #[arg(long, requires = "search_bytes", value_enum, default_value = "hex")]
+    pattern_kind: PatternKindArg,
+
+    /// Only search the entries that are scanned for the tag cache with `--search-bytes`, instead of every entry
+    #[arg(long, requires = "search_bytes")]
+    tags_only: bool,
+
+    /// Writes the data of a tag to `<export-dir>/<tag>.bin`, can be given more than once
+    #[arg(long, requires = "headless", value_name = "TAGHASH")]
+    export: Vec<String>,
+
+    /// Directory to export tags to
+    #[arg(long, requires = "export", default_value = "exported")]
+    export_dir: PathBuf,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum PatternKindArg {
+    Hex,
+    Text,
+    U32,
+    U64,
+}
+
+impl From<PatternKindArg> for PatternKind {
+    fn from(kind: PatternKindArg) -> Self {
+        match kind {
+            PatternKindArg::Hex => PatternKind::Hex,
+            PatternKindArg::Text => PatternKind::Text,
+            PatternKindArg::U32 => PatternKind::U32,
+            PatternKindArg::U64 => PatternKind::U64,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct HashArgs {
+    string: String,
+
+    /// Hash the string as a Wwise object name (FNV-1 of the lowercase string)
+    #[arg(long)]
+    wwise: bool,
+}
+
+/// Wraps the output of every command, so scripts can check which version of the output they're reading
+#[derive(Serialize)]
+struct JsonOutput<T: Serialize> {
+    schema: u32,
+    #[serde(flatten)]
+    output: T,
+}
+
+fn print_json(output: impl Serialize) -> anyhow::Result<()> {
+    let output = JsonOutput {
+        schema: JSON_SCHEMA_VERSION,
+        output,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// `refs` and `refsto`
+#[derive(Serialize)]
+struct ReferencesOutput {
+    tag: String,
+    name: Option<String>,
+    /// `outgoing` for `refs`, `incoming` for `refsto`
+    direction: &'static str,
+    references: Vec<Reference>,
+}
+
+#[derive(Serialize)]
+struct Reference {
+    tag: String,
+    hash64: Option<String>,
+    /// Offset of the reference in the referencing tag, only for outgoing references
+    offset: Option<u64>,
+    tag_type: String,
+    name: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ExtractOutput {
+    out_dir: String,
+    packages: Vec<ExtractedPackage>,
+}
+
+#[derive(Serialize)]
+struct ExtractedPackage {
+    name: String,
+    /// Directory the package was extracted to, containing a manifest.json. None if extracting failed
+    path: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct StringsOutput {
+    strings: Vec<LocalizedString>,
+}
+
+#[derive(Serialize)]
+struct LocalizedString {
+    hash: String,
+    /// Every string with this hash, there can be more than one
+    strings: Vec<String>,
+    /// Tags that contain the hash
+    tags: Vec<String>,
+}
+
+/// `--headless`, queries that weren't passed are `null`
+#[derive(Serialize)]
+struct HeadlessOutput {
+    cache: CacheSummary,
+    refs: Option<ReferencesOutput>,
+    search_string: Option<StringsOutput>,
+    search_bytes: Option<PatternSearchOutput>,
+    export: Option<ExportOutput>,
+}
+
+#[derive(Serialize)]
+struct CacheSummary {
+    /// Path of the cache file
+    path: String,
+    tags: usize,
+    /// Packages the cache was built from
+    packages: usize,
+}
+
+/// `--search-bytes`
+#[derive(Serialize)]
+struct PatternSearchOutput {
+    tags_searched: usize,
+    /// Set when searching stopped early because too many tags matched
+    truncated: bool,
+    matches: Vec<PatternMatchOutput>,
+}
+
+#[derive(Serialize)]
+struct PatternMatchOutput {
+    tag: String,
+    class: String,
+    /// Amount of matches in the tag
+    count: usize,
+    /// Offsets of the first matches, see `count` for the total
+    offsets: Vec<u64>,
+}
+
+#[derive(Serialize)]
+struct ExportOutput {
+    out_dir: String,
+    tags: Vec<ExportedTag>,
+}
+
+#[derive(Serialize)]
+struct ExportedTag {
+    tag: String,
+    /// None if reading or writing the tag failed
+    path: Option<String>,
+    size: Option<usize>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct HashOutput {
+    string: String,
+    hash: String,
+}
+
+/// Parses a 32-bit or 64-bit tag hash, see [`parse_tag64`]
+fn parse_tag(input: &str) -> anyhow::Result<TagHash> {
+    let input = input.trim().trim_start_matches("0x");
+    if input.len() >= 16 {
+        return parse_tag64(input);
+    }
+
+    Ok(TagHash(u32::from_str_radix(input, 16)?))
+}
+
+fn reference(tag: TagHash, offset: Option<u64>, names: &FxHashMap<TagHash, String>) -> Reference {
+    Reference {
+        tag: tag.to_string(),
+        hash64: package_manager()
+            .get_tag64_for_tag32(tag)
+            .map(|h| format!("{:016X}", h.0)),
+        offset,
+        tag_type: package_manager()
+            .get_entry(tag)
+            .map(|e| TagType::from_type_subtype(e.file_type, e.file_subtype).to_string())
+            .unwrap_or_else(|| "Unknown".to_string()),
+        name: names.get(&tag).cloned(),
+    }
+}
+
+/// Runs a subcommand, printing JSON instead of text if `json` is set. Expects the package manager, config and
+/// annotations to be loaded
+pub fn run(command: Command, json: bool) -> anyhow::Result<()> {
+    match command {
+        Command::Refs(args) => print_references(&args, true, json),
+        Command::Refsto(args) => print_references(&args, false, json),
+        Command::Extract(args) => extract(args, json),
+        Command::Strings(args) => print_strings(args, json),
+        Command::Hash(args) => print_hash(&args, json),
+    }
+}
+
+/// Prints the error and exits with a non-zero exit code if the command failed
+pub fn exit_on_error(result: anyhow::Result<()>) {
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn load_cache() -> anyhow::Result<TagCache> {
+    match load_tag_cache() {
+        Ok(cache) => Ok(cache),
+        Err(future_cache) => future_cache.readable.ok_or_else(|| {
+            anyhow::anyhow!(
+                "The tag cache was written by a newer version of quicktag (v{})",
+                future_cache.version
+            )
+        }),
+    }
+}
+
+fn print_references(args: &RefsArgs, outgoing: bool, json: bool) -> anyhow::Result<()> {
+    let tag = parse_tag(&args.hash)?;
+    anyhow::ensure!(
+        package_manager().get_entry(tag).is_some(),
+        "Tag {tag} doesn't exist"
+    );
+
+    let output = references(&load_cache()?, tag, outgoing);
+    if json {
+        return print_json(output);
+    }
+
+    print_references_text(&output);
+    Ok(())
+}
+
+fn references(cache: &TagCache, tag: TagHash, outgoing: bool) -> ReferencesOutput {
+    let names = tag_names();
+    let references = if outgoing {
+        outgoing_references(cache, tag, &names)
+    } else {
+        cache
+            .hashes
+            .get(&tag)
+            .map(|scan| {
+                scan.references
+                    .iter()
+                    .map(|&t| reference(t, None, &names))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    ReferencesOutput {
+        tag: tag.to_string(),
+        name: names.get(&tag).cloned(),
+        direction: if outgoing { "outgoing" } else { "incoming" },
+        references,
+    }
+}
+
+fn print_references_text(output: &ReferencesOutput) {
+    for r in &output.references {
+        let offset = r.offset.map(|o| format!("@{o:X}")).unwrap_or_default();
+        println!(
+            "{}{offset}\t{}\t{}",
+            r.tag,
+            r.tag_type,
+            r.name.as_deref().unwrap_or_default()
+        );
+    }
+}
+
+/// Tags referenced by 32-bit and 64-bit hashes, in order of their offset
+fn outgoing_references(
+    cache: &TagCache,
+    tag: TagHash,
+    names: &FxHashMap<TagHash, String>,
+) -> Vec<Reference> {
+    let Some(scan) = cache.hashes.get(&tag) else {
+        return vec![];
+    };
+
+    let mut references: Vec<(u64, TagHash)> = scan
+        .file_hashes
+        .iter()
+        .map(|h| (h.offset, h.hash))
+        .chain(scan.file_hashes64.iter().filter_map(|h| {
+            let entry = package_manager().lookup.tag64_entries.get(&h.hash.0)?;
+            Some((h.offset, entry.hash32))
+        }))
+        .collect();
+    references.sort_by_key(|(offset, _)| *offset);
+
+    references
+        .into_iter()
+        .map(|(offset, t)| reference(t, (offset != u64::MAX).then_some(offset), names))
+        .collect()
+}
+
+fn extract(args: ExtractArgs, json: bool) -> anyhow::Result<()> {
+    let packages = package_manager()
+        .package_paths
+        .iter()
+        .map(|(id, path)| (*id, ScanScope::package_name(path)))
+        .filter(|(_, name)| args.pkg.as_ref().is_none_or(|p| glob_match(p, name)))
+        .sorted_by(|a, b| a.1.cmp(&b.1))
+        .collect_vec();
+    anyhow::ensure!(!packages.is_empty(), "No packages match the pattern");
+
+    let options = ExtractOptions {
+        categories: args.types,
+        textures_as_dds: args.dds,
+    };
+
+    let mut extracted = vec![];
+    for (i, (pkg_id, name)) in packages.iter().enumerate() {
+        info!("Extracting {name} ({}/{})", i + 1, packages.len());
+        let result = extract_package(*pkg_id, &args.out, &options, &ExtractProgress::default());
+        if let Err(e) = &result {
+            error!("Failed to extract {name}: {e:?}");
+        }
+
+        extracted.push(ExtractedPackage {
+            name: name.clone(),
+            path: result
+                .as_ref()
+                .ok()
+                .map(|p| p.to_string_lossy().to_string()),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+
+    let failed = extracted.iter().filter(|p| p.error.is_some()).count();
+    if json {
+        print_json(ExtractOutput {
+            out_dir: args.out.to_string_lossy().to_string(),
+            packages: extracted,
+        })?;
+    } else {
+        println!(
+            "Extracted {} packages to {}",
+            packages.len() - failed,
+            args.out.display()
+        );
+    }
+    anyhow::ensure!(failed == 0, "{failed} packages failed to extract");
+
+    Ok(())
+}
+
+fn print_strings(args: StringsArgs, json: bool) -> anyhow::Result<()> {
+    if let Some(lang) = &args.lang {
+        anyhow::ensure!(
+            LANGUAGE_CODES.contains(&lang.as_str()),
+            "Unknown language '{lang}', expected one of {}",
+            LANGUAGE_CODES.join(", ")
+        );
+        set_string_languages(std::slice::from_ref(lang));
+    }
+
+    let output = find_strings(&load_cache()?, args.contains.as_deref())?;
+    if json {
+        return print_json(output);
+    }
+
+    print_strings_text(&output);
+    Ok(())
+}
+
+/// Localized strings containing `contains` (ignoring case), or every string
+fn find_strings(cache: &TagCache, contains: Option<&str>) -> anyhow::Result<StringsOutput> {
+    let strings = create_stringmap()?;
+    let contains = contains.map(|c| c.to_lowercase());
+    let matches: FxHashMap<u32, &Vec<String>> = strings
+        .iter()
+        .filter(|(_, strings)| {
+            contains
+                .as_ref()
+                .is_none_or(|c| strings.iter().any(|s| s.to_lowercase().contains(c)))
+        })
+        .map(|(hash, strings)| (*hash, strings))
+        .collect();
+
+    let mut tags: FxHashMap<u32, Vec<TagHash>> = FxHashMap::default();
+    for (tag, scan) in &cache.hashes {
+        for hash in scan.string_hashes.iter().map(|h| h.hash).unique() {
+            if matches.contains_key(&hash) {
+                tags.entry(hash).or_default().push(*tag);
+            }
+        }
+    }
+
+    let strings = matches
+        .into_iter()
+        .sorted_by_key(|(hash, _)| *hash)
+        .map(|(hash, strings)| LocalizedString {
+            hash: format!("{hash:08X}"),
+            strings: strings.clone(),
+            tags: tags
+                .remove(&hash)
+                .unwrap_or_default()
+                .into_iter()
+                .sorted_by_key(|t| t.0)
+                .map(|t| t.to_string())
+                .collect_vec(),
+        })
+        .collect();
+
+    Ok(StringsOutput { strings })
+}
+
+fn print_strings_text(output: &StringsOutput) {
+    for s in &output.strings {
+        for string in &s.strings {
+            println!(
+                "{}\t{}\t{}",
+                s.hash,
+                string.replace('\n', "\\n"),
+                s.tags.join(",")
+            );
+        }
+    }
+}
+
+/// Searches the data of every entry (or every tag with `tags_only`) for the pattern
+fn search_bytes(
+    query: &str,
+    kind: PatternKind,
+    tags_only: bool,
+) -> anyhow::Result<PatternSearchOutput> {
+    let pattern = BytePattern::parse(kind, query, package_manager().version.endian(), true)
+        .map_err(|e| anyhow::anyhow!("Invalid pattern: {e}"))?;
+    let result = search_packages(&pattern, None, tags_only, &PatternSearchProgress::default());
+
+    Ok(PatternSearchOutput {
+        tags_searched: result.tags_searched,
+        truncated: result.truncated,
+        matches: result
+            .matches
+            .into_iter()
+            .map(|m| PatternMatchOutput {
+                tag: m.tag.to_string(),
+                class: format!("{:08X}", m.class),
+                count: m.count,
+                offsets: m.offsets,
+            })
+            .collect(),
+    })
+}
+
+/// Writes the data of each tag to `<out_dir>/<tag>.bin`. Tags that fail are reported in the output rather than
+/// stopping the export
+fn export_tags(hashes: &[String], out_dir: &Path) -> anyhow::Result<ExportOutput> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    let tags = hashes
+        .iter()
+        .map(|hash| {
+            let result = parse_tag(hash).and_then(|tag| {
+                let data =
+                    tag_data::read_tag(tag).with_context(|| format!("Failed to read tag {tag}"))?;
+                let path = out_dir.join(format!("{tag}.bin"));
+                std::fs::write(&path, &data)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                Ok((tag, path, data.len()))
+            });
+
+            match result {
+                Ok((tag, path, size)) => ExportedTag {
+                    tag: tag.to_string(),
+                    path: Some(path.to_string_lossy().to_string()),
+                    size: Some(size),
+                    error: None,
+                },
+                Err(e) => {
+                    error!("Failed to export {hash}: {e:?}");
+                    ExportedTag {
+                        tag: hash.clone(),
+                        path: None,
+                        size: None,
+                        error: Some(format!("{e:#}")),
+                    }
+                }
+            }
+        })
+        .collect();
+
+    Ok(ExportOutput {
+        out_dir: out_dir.to_string_lossy().to_string(),
+        tags,
+    })
+}
+
+/// Builds or updates the tag cache, then answers the queries in `args`. With `--json` everything is printed as a
+/// single object, otherwise the output of each query is printed in the order of the flags
+pub fn run_headless(args: HeadlessArgs, json: bool) -> anyhow::Result<()> {
+    info!("Loading the tag cache");
+    let cache = load_cache()?;
+    let summary = CacheSummary {
+        path: cache_path().to_string_lossy().to_string(),
+        tags: cache.hashes.len(),
+        packages: cache.packages.len(),
+    };
+    info!(
+        "Tag cache is up to date ({} tags from {} packages)",
+        summary.tags, summary.packages
+    );
+
+    let refs = match &args.refs {
+        Some(hash) => {
+            let tag = parse_tag(hash)?;
+            anyhow::ensure!(
+                package_manager().get_entry(tag).is_some(),
+                "Tag {tag} doesn't exist"
+            );
+            Some(references(&cache, tag, true))
+        }
+        None => None,
+    };
+    let search_string = args
+        .search_string
+        .as_deref()
+        .map(|text| find_strings(&cache, Some(text)))
+        .transpose()?;
+    let search_bytes = args
+        .search_bytes
+        .as_deref()
+        .map(|query| {
+            info!("Searching every entry for {query}");
+            search_bytes(query, args.pattern_kind.into(), args.tags_only)
+        })
+        .transpose()?;
+    let export = if args.export.is_empty() {
+        None
+    } else {
+        Some(export_tags(&args.export, &args.export_dir)?)
+    };
+    let failed = export
+        .as_ref()
+        .map_or(0, |e| e.tags.iter().filter(|t| t.error.is_some()).count());
+
+    if json {
+        print_json(HeadlessOutput {
+            cache: summary,
+            refs,
+            search_string,
+            search_bytes,
+            export,
+        })?;
+    } else {
+        if let Some(refs) = &refs {
+            print_references_text(refs);
+        }
+        if let Some(strings) = &search_string {
+            print_strings_text(strings);
+        }
+        if let Some(search) = &search_bytes {
+            for m in &search.matches {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    m.tag,
+                    m.class,
+                    m.count,
+                    m.offsets.iter().map(|o| format!("0x{o:X}")).join(",")
+                );
+            }
+        }
+        if let Some(export) = &export {
+            for tag in &export.tags {
+                if let Some(path) = &tag.path {
+                    println!("{}\t{path}", tag.tag);
+                }
+            }
+        }
+    }
+    anyhow::ensure!(failed == 0, "{failed} tags failed to export");
+
+    Ok(())
+}
+
+pub fn print_hash(args: &HashArgs, json: bool) -> anyhow::Result<()> {
+    let hash = if args.wwise {
+        wwise_id(&args.string)
+    } else {
+        fnv1(args.string.as_bytes())
+    };
+    let hash = format!("{hash:08X}");
+    if json {
+        return print_json(HashOutput {
+            string: args.string.clone(),
+            hash,
+        });
+    }
+
+    println!("{hash}");
+    Ok(())
+}
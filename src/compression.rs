@@ -0,0 +1,270 @@
+//! Compressed blobs embedded in tag data
+//!
+//! zlib, gzip and zstd streams are found by their headers and checked by decompressing the start of the stream. Oodle
+//! doesn't have a real header, blocks that start like an Oodle block are only listed as candidates. Oodle is
+//! decompressed with the game's own `oo2core` library, which is looked up next to the packages (`bin/x64`), next to
+//! the executable or in the working directory, or at `general.oodle_library` in the config.
+
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use tiger_pkg::package_manager;
+
+use crate::config;
+
+/// Decompressed output is cut off at this size, so a corrupt stream can't take all memory
+const MAX_DECOMPRESSED_SIZE: usize = 256 * 1024 * 1024;
+/// Bytes decompressed when checking whether a header is really the start of a stream
+const PROBE_SIZE: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Compression {
+    Zlib,
+    Gzip,
+    Zstd,
+    /// Oodle block, with the name of the compressor
+    Oodle(&'static str),
+}
+
+impl Compression {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Zlib => "zlib",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Oodle(_) => "Oodle",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CompressedBlob {
+    pub offset: usize,
+    pub compression: Compression,
+    /// Decompressed size stored right before the blob, if there seems to be one. Needed for Oodle
+    pub size_hint: Option<usize>,
+}
+
+fn probe(reader: impl Read) -> bool {
+    let mut buf = [0u8; PROBE_SIZE];
+    let mut reader = reader.take(PROBE_SIZE as u64);
+    let mut read = 0;
+    loop {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => return read > 0,
+            Ok(n) => read += n,
+            Err(_) => return false,
+        }
+        if read == PROBE_SIZE {
+            return true;
+        }
+    }
+}
+
+fn oodle_compressor(data: &[u8]) -> Option<&'static str> {
+    // Oodle LZ block headers: 0x8C/0xCC, followed by the compressor
+    if !matches!(data.first()?, 0x8C | 0xCC) {
+        return None;
+    }
+
+    Some(match data.get(1)? {
+        0x06 => "Kraken",
+        0x0A => "Mermaid",
+        0x0C => "Leviathan",
+        _ => return None,
+    })
+}
+
+/// Finds the start of compressed streams in `data`
+pub fn find_compressed_blobs(data: &[u8], endian: tiger_pkg::Endian) -> Vec<CompressedBlob> {
+    let mut blobs = vec![];
+    let mut offset = 0;
+    while offset + 4 <= data.len() {
+        let rest = &data[offset..];
+        let compression = match rest {
+            [0x78, 0x01 | 0x5E | 0x9C | 0xDA, ..] if probe(ZlibDecoder::new(rest)) => {
+                Some(Compression::Zlib)
+            }
+            [0x1F, 0x8B, 0x08, ..] if probe(GzDecoder::new(rest)) => Some(Compression::Gzip),
+            [0x28, 0xB5, 0x2F, 0xFD, ..]
+                if zstd::stream::read::Decoder::new(rest)
+                    .is_ok_and(|d| probe(d.single_frame())) =>
+            {
+                Some(Compression::Zstd)
+            }
+            // Oodle blocks are only looked for at aligned offsets, the header is too short to be reliable otherwise
+            _ if offset % 4 == 0 => oodle_compressor(rest).map(Compression::Oodle),
+            _ => None,
+        };
+
+        match compression {
+            Some(compression) => {
+                let size_hint = offset
+                    .checked_sub(4)
+                    .map(|o| <[u8; 4]>::try_from(&data[o..o + 4]).unwrap())
+                    .map(|b| quicktag_core::util::u32_from_endian(endian, b) as usize)
+                    .filter(|&s| s > 0 && s <= MAX_DECOMPRESSED_SIZE);
+                blobs.push(CompressedBlob {
+                    offset,
+                    compression,
+                    size_hint,
+                });
+                // Streams don't overlap, and the header of a zlib stream can't be found again in its own data
+                offset += 4;
+            }
+            None => offset += 1,
+        }
+    }
+
+    blobs
+}
+
+fn read_limited(reader: impl Read) -> anyhow::Result<Vec<u8>> {
+    let mut out = vec![];
+    reader
+        .take(MAX_DECOMPRESSED_SIZE as u64)
+        .read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Decompresses the stream that starts at the beginning of `data`. Trailing data after the stream is ignored.
+/// `size` is the decompressed size, which is only needed (and required) for Oodle
+pub fn decompress(
+    compression: Compression,
+    data: &[u8],
+    size: Option<usize>,
+) -> anyhow::Result<Vec<u8>> {
+    match compression {
+        Compression::Zlib => read_limited(ZlibDecoder::new(data)),
+        Compression::Gzip => read_limited(GzDecoder::new(data)),
+        Compression::Zstd => read_limited(zstd::stream::read::Decoder::new(data)?.single_frame()),
+        Compression::Oodle(_) => {
+            let size = size.context("Oodle needs the decompressed size")?;
+            anyhow::ensure!(
+                size <= MAX_DECOMPRESSED_SIZE,
+                "The decompressed size is too large"
+            );
+            oodle_decompress(data, size)
+        }
+    }
+}
+
+type OodleLzDecompress = unsafe extern "C" fn(
+    comp_buf: *const u8,
+    comp_buf_size: isize,
+    raw_buf: *mut u8,
+    raw_len: isize,
+    fuzz_safe: i32,
+    check_crc: i32,
+    verbosity: i32,
+    dec_buf_base: *mut u8,
+    dec_buf_size: isize,
+    fp_callback: *const std::ffi::c_void,
+    callback_user_data: *const std::ffi::c_void,
+    decoder_memory: *mut u8,
+    decoder_memory_size: isize,
+    thread_phase: i32,
+) -> isize;
+
+const OODLE_LIBRARY_NAMES: &[&str] = &[
+    "oo2core_9_win64.dll",
+    "oo2core_8_win64.dll",
+    "oo2core_7_win64.dll",
+    "oo2core_3_win64.dll",
+    "liboo2corelinux64.so.9",
+    "liboo2corelinux64.so",
+];
+
+fn oodle_library_candidates() -> Vec<PathBuf> {
+    let mut candidates = vec![];
+    if let Some(path) = config::with(|c| c.general.oodle_library.clone()) {
+        candidates.push(PathBuf::from(path));
+    }
+
+    let mut directories = vec![];
+    if let Some(packages) = package_manager().package_dir.parent() {
+        directories.push(packages.join("bin").join("x64"));
+    }
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+    {
+        directories.push(exe_dir);
+    }
+    directories.push(PathBuf::from("."));
+
+    for directory in directories {
+        candidates.extend(OODLE_LIBRARY_NAMES.iter().map(|n| directory.join(n)));
+    }
+
+    candidates
+}
+
+/// Loaded once, the library stays loaded until quicktag exits
+fn oodle_library() -> Result<&'static libloading::Library, String> {
+    static LIBRARY: OnceLock<Result<libloading::Library, String>> = OnceLock::new();
+    LIBRARY
+        .get_or_init(|| {
+            let candidates = oodle_library_candidates();
+            for path in candidates.iter().filter(|p| p.exists()) {
+                // SAFETY: oo2core doesn't run anything on load
+                match unsafe { libloading::Library::new(path) } {
+                    Ok(library) => {
+                        log::info!("Loaded Oodle from {}", path.display());
+                        return Ok(library);
+                    }
+                    Err(e) => log::warn!("Failed to load Oodle from {}: {e}", path.display()),
+                }
+            }
+
+            Err(
+                "Couldn't find the Oodle library (oo2core), set general.oodle_library in the config"
+                    .to_string(),
+            )
+        })
+        .as_ref()
+        .map_err(|e| e.clone())
+}
+
+/// Whether the Oodle library is available, or why it isn't
+pub fn oodle_available() -> Result<(), String> {
+    oodle_library().map(|_| ())
+}
+
+fn oodle_decompress(data: &[u8], size: usize) -> anyhow::Result<Vec<u8>> {
+    let library = oodle_library().map_err(|e| anyhow::anyhow!(e))?;
+    // SAFETY: the signature matches OodleLZ_Decompress from oo2core 3 through 9
+    let decompress: libloading::Symbol<OodleLzDecompress> =
+        unsafe { library.get(b"OodleLZ_Decompress\0")? };
+
+    let mut out = vec![0u8; size];
+    // SAFETY: both buffers are valid for the given sizes, fuzz safety makes Oodle check the input instead of trusting it
+    let written = unsafe {
+        decompress(
+            data.as_ptr(),
+            data.len() as isize,
+            out.as_mut_ptr(),
+            size as isize,
+            1,
+            0,
+            0,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null_mut(),
+            0,
+            3,
+        )
+    };
+
+    anyhow::ensure!(
+        written > 0,
+        "Oodle failed to decompress the block, the size might be wrong"
+    );
+    out.truncate(written as usize);
+    Ok(out)
+}
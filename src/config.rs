@@ -0,0 +1,693 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use parking_lot::RwLock;
+use quicktag_core::palette::{set_palette, Palette};
+use quicktag_core::tagtypes::{set_custom_tagtypes, CustomTagType};
+use quicktag_core::util::HashAlgorithm;
+use quicktag_scanner::{
+    hashes::set_harvest_unknown_hashes,
+    namespaces::{set_custom_hash_namespaces, HashNamespaceDefinition},
+    nested_strings::set_nested_string_depth,
+    scope::{set_scan_scope, ScanScope},
+    set_cache_compression, set_cache_directory,
+    string_runs::set_scan_string_runs,
+    CacheCompression,
+};
+use quicktag_strings::{
+    cracked::set_cracked_hashes_directory,
+    localized::{set_string_languages, LANGUAGE_CODES},
+};
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n;
+use crate::shutdown::DirtyFlag;
+use crate::tag_data::{set_prefetch_count, set_tag_data_cache_size};
+use crate::texture::cache::set_texture_cache_count;
+
+pub const CONFIG_PATH: &str = "quicktag.toml";
+
+/// Environment variables starting with this override config values. Sections and keys are separated by a double
+/// underscore, eg. `QUICKTAG_CACHE__COMPRESSION_LEVEL=3` sets `compression_level` in the `[cache]` section and
+/// `QUICKTAG_VIDEO_PLAYER=mpv` sets `video_player`
+pub const ENV_PREFIX: &str = "QUICKTAG_";
+
+/// Environment variable with the path of the config file, used when `--config` isn't passed
+pub const CONFIG_PATH_ENV: &str = "QUICKTAG_CONFIG";
+
+pub static DIRTY: DirtyFlag = DirtyFlag::new();
+
+lazy_static! {
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
+    static ref CONFIG_FILE: RwLock<PathBuf> = RwLock::new(PathBuf::from(CONFIG_PATH));
+    static ref OVERRIDES: RwLock<Vec<Override>> = RwLock::new(vec![]);
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub general: GeneralSettings,
+
+    /// User-defined names and colors for unknown type/subtype combinations
+    pub tag_types: Vec<TagTypeMapping>,
+
+    /// Command used to preview exported videos (eg. `ffplay`). The path to the video is appended as the last argument
+    pub video_player: Option<String>,
+
+    /// Program run after a new tag cache has been built, see [`crate::post_scan_hook`]. The path is used as-is, so it
+    /// can contain spaces
+    pub post_scan_hook: Option<String>,
+    /// Arguments for the post-scan hook. The paths to the cache and a JSON summary of it are appended after these
+    pub post_scan_hook_args: Vec<String>,
+
+    pub cache: CacheSettings,
+
+    pub hex_view: HexViewSettings,
+
+    pub tag_view: TagViewSettings,
+
+    pub strings: StringSettings,
+
+    pub watchdog: WatchdogSettings,
+
+    pub analyzers: AnalyzerSettings,
+
+    pub share: ShareSettings,
+
+    pub open_api: OpenApiSettings,
+
+    pub backups: BackupSettings,
+
+    pub tag_data: TagDataSettings,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct GeneralSettings {
+    /// Packages directory that's used when none is passed on the command line. The Destiny 2 installation is looked up
+    /// if this isn't set either
+    pub packages_path: Option<String>,
+    /// Game version of the packages, as it's passed to `-v` (eg. `d2_tfs`)
+    pub game_version: Option<String>,
+    /// Directory that tag caches are stored in, the directory of the executable if not set
+    pub cache_dir: Option<String>,
+    /// Directory for the annotations, watch list, saved queries and cracked hashes, the working directory if not set
+    pub data_dir: Option<String>,
+    /// Amount of threads used for scanning and searching, 0 uses every available core. Only read on startup
+    pub threads: u32,
+    /// Name of this setup (eg. "Pre-patch"), shown in the window title. See [`crate::gui::profile`]
+    pub profile: Option<String>,
+    /// Color of the bar along the top of the window. Picked from the profile name or game version if not set
+    pub accent_color: Option<[u8; 3]>,
+    /// Language of the UI, see [`crate::i18n`]. English if not set
+    pub ui_language: Option<String>,
+    /// Colors for highlights and tag types, see [`quicktag_core::palette`]
+    pub palette: Palette,
+    /// Path to the Oodle library (oo2core), for decompressing Oodle blocks in tags. Looked up in the game's `bin/x64`,
+    /// next to the executable and in the working directory if not set
+    pub oodle_library: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// zstd compression level for the tag cache. Lower levels build faster, higher levels produce smaller files
+    pub compression_level: i32,
+    /// Amount of threads used to compress the tag cache, 0 uses every available core
+    pub compression_threads: u32,
+    /// Record values that look like string hashes but aren't known while building the cache, see [`quicktag_scanner::hashes`]
+    pub harvest_unknown_hashes: bool,
+    /// Look for inline UTF-8/UTF-16 strings in addition to raw string blobs, see [`quicktag_scanner::string_runs`]
+    pub scan_string_runs: bool,
+    /// Levels of references from string classes to follow for nested string blobs, see [`quicktag_scanner::nested_strings`]
+    pub nested_string_depth: u32,
+    /// Which packages are scanned when building a new cache, see [`quicktag_scanner::scope`]
+    pub scan_scope: ScanScopeMode,
+    /// Package name pattern for [`ScanScopeMode::Matching`]
+    pub scan_pattern: String,
+    /// Package names for [`ScanScopeMode::Selected`], picked in the package list
+    pub scan_packages: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum ScanScopeMode {
+    #[default]
+    All,
+    Matching,
+    Selected,
+}
+
+impl ScanScopeMode {
+    pub const ALL: [ScanScopeMode; 3] = [
+        ScanScopeMode::All,
+        ScanScopeMode::Matching,
+        ScanScopeMode::Selected,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ScanScopeMode::All => "All packages",
+            ScanScopeMode::Matching => "Matching a pattern",
+            ScanScopeMode::Selected => "Selected packages",
+        }
+    }
+}
+
+impl CacheSettings {
+    pub fn scan_scope(&self) -> ScanScope {
+        match self.scan_scope {
+            ScanScopeMode::All => ScanScope::All,
+            ScanScopeMode::Matching => ScanScope::Matching(self.scan_pattern.clone()),
+            ScanScopeMode::Selected => {
+                let mut packages = self.scan_packages.clone();
+                packages.sort();
+                ScanScope::Packages(packages)
+            }
+        }
+    }
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        let default = CacheCompression::default();
+        Self {
+            compression_level: default.level,
+            compression_threads: default.threads,
+            harvest_unknown_hashes: false,
+            scan_string_runs: true,
+            nested_string_depth: 0,
+            scan_scope: ScanScopeMode::All,
+            scan_pattern: String::new(),
+            scan_packages: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct HexViewSettings {
+    /// Show 4-byte lanes that look like floats as floats instead of raw bytes
+    pub detect_floats: bool,
+    /// Smallest magnitude (as a power of 10) considered a float. Small integers and most hashes fall below this
+    pub min_float_exponent: i32,
+    /// Largest magnitude (as a power of 10) considered a float
+    pub max_float_exponent: i32,
+    /// Mark differences, tag references, selections and vector rows with shapes and labels as well as colors
+    pub non_color_markers: bool,
+    /// Show the data inspector next to the hex view
+    pub data_inspector: bool,
+}
+
+impl Default for HexViewSettings {
+    fn default() -> Self {
+        Self {
+            detect_floats: true,
+            min_float_exponent: -10,
+            max_float_exponent: 7,
+            non_color_markers: false,
+            data_inspector: false,
+        }
+    }
+}
+
+/// Views of the tag view
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TagViewMode {
+    Traversal,
+    Hex,
+    HexReferenced,
+    Float,
+    Search,
+    Similar,
+    TextureUsage,
+    Geometry,
+    Rig,
+    Placements,
+}
+
+impl TagViewMode {
+    pub const ALL: [TagViewMode; 10] = [
+        TagViewMode::Traversal,
+        TagViewMode::Hex,
+        TagViewMode::HexReferenced,
+        TagViewMode::Float,
+        TagViewMode::Search,
+        TagViewMode::Similar,
+        TagViewMode::TextureUsage,
+        TagViewMode::Geometry,
+        TagViewMode::Rig,
+        TagViewMode::Placements,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TagViewMode::Traversal => "Traversal",
+            TagViewMode::Hex => "Hex",
+            TagViewMode::HexReferenced => "Hex (referenced data)",
+            TagViewMode::Float => "Floating point",
+            TagViewMode::Search => "Search",
+            TagViewMode::Similar => "Similar tags",
+            TagViewMode::TextureUsage => "Texture usage",
+            TagViewMode::Geometry => "3D preview",
+            TagViewMode::Rig => "Rig",
+            TagViewMode::Placements => "Placements",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct TagViewSettings {
+    /// View that tags of a class open in, by class hash in hex (eg. `"80809AD8" = "hex"`). Tags of other classes open
+    /// in the view that was open before. Views that aren't available for a tag fall back to the traversal view
+    pub class_views: BTreeMap<String, TagViewMode>,
+}
+
+impl TagViewSettings {
+    pub fn class_view(&self, class: u32) -> Option<TagViewMode> {
+        self.class_views
+            .iter()
+            .find(|(key, _)| u32::from_str_radix(key.trim(), 16) == Ok(class))
+            .map(|(_, view)| *view)
+    }
+
+    /// Sets the view for a class, or removes it when `view` is None
+    pub fn set_class_view(&mut self, class: u32, view: Option<TagViewMode>) {
+        self.class_views
+            .retain(|key, _| u32::from_str_radix(key.trim(), 16) != Ok(class));
+        if let Some(view) = view {
+            self.class_views.insert(format!("{class:08X}"), view);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct StringSettings {
+    /// Languages that string hashes are resolved from, see [`quicktag_strings::localized::LANGUAGE_CODES`]
+    ///
+    /// Earlier languages take priority, so hashes only fall back to other languages when they aren't in English
+    pub languages: Vec<String>,
+    /// Extra hash namespaces, next to the built-in Wwise one. See [`quicktag_scanner::namespaces`]
+    pub hash_namespaces: Vec<HashNamespaceSettings>,
+}
+
+impl Default for StringSettings {
+    fn default() -> Self {
+        Self {
+            languages: LANGUAGE_CODES.iter().map(|l| l.to_string()).collect(),
+            hash_namespaces: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HashNamespaceSettings {
+    /// Stored in the cache, changing it requires the cache to be rebuilt
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    /// Path of the wordlist, relative to the data directory
+    pub wordlist: String,
+    pub color: [u8; 3],
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WatchdogSettings {
+    /// Periodically check the packages directory for changes and rebuild the cache, see [`crate::watchdog`]
+    pub enabled: bool,
+    pub interval_minutes: u32,
+    /// Directory that patch reports (and reports of regenerated caches) are written to, relative to the data directory
+    pub output_dir: String,
+    /// Reports of patches and regenerated caches are posted here as a Discord embed, if set. See [`crate::webhook`]
+    pub webhook_url: Option<String>,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 30,
+            output_dir: "patch_reports".to_string(),
+            webhook_url: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AnalyzerSettings {
+    /// Run the WebAssembly analyzers in `directory` on every tag while building the cache, see [`crate::analyzers`].
+    /// Off by default, as analyzers are third-party code that slows down building the cache
+    pub enabled: bool,
+    /// Directory containing `.wasm` analyzers, relative to the data directory
+    pub directory: String,
+    /// Instructions an analyzer may run per tag, calls that run out are aborted
+    pub fuel_per_tag: u64,
+    /// Memory an analyzer instance may use, in megabytes. Every scanner thread gets its own instance
+    pub memory_limit_mb: u32,
+}
+
+impl Default for AnalyzerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: "analyzers".to_string(),
+            fuel_per_tag: 100_000_000,
+            memory_limit_mb: 64,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ShareSettings {
+    /// Serve the tag cache to other quicktag instances on the network, see [`crate::lan_share`]
+    pub host: bool,
+    /// Address of the network interface to share on, eg. the machine's LAN address. Defaults to only this machine,
+    /// `0.0.0.0` shares on every interface
+    pub bind_address: String,
+    pub port: u16,
+    /// Address of another instance to browse together with, eg. one with the packages from before a patch. Opening a
+    /// tag opens the corresponding tag in the linked instance, and the hex view highlights the differences
+    pub link: Option<String>,
+    /// Open the tags that linked instances open. Only works while hosting
+    pub follow_links: bool,
+}
+
+impl Default for ShareSettings {
+    fn default() -> Self {
+        Self {
+            host: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 7331,
+            link: None,
+            follow_links: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BackupSettings {
+    /// Backups kept per file, 0 disables backups. See [`crate::backups`]
+    pub count: u32,
+    /// Minimum time between two backups of the same file
+    pub interval_minutes: u32,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            count: 10,
+            interval_minutes: 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct OpenApiSettings {
+    /// Let other programs on this machine open tags, see [`crate::open_api`]
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for OpenApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7332,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TagDataSettings {
+    /// Size of the in-memory cache for decompressed tag data in megabytes, see [`crate::tag_data`]
+    pub cache_size_mb: u32,
+    /// Amount of referenced tags that are read into the cache in the background when a tag is opened, 0 disables
+    /// prefetching
+    pub prefetch_count: u32,
+    /// Maximum amount of textures kept loaded for previews, see [`crate::texture::cache`]
+    pub texture_cache_count: u32,
+}
+
+impl Default for TagDataSettings {
+    fn default() -> Self {
+        Self {
+            cache_size_mb: 256,
+            prefetch_count: 16,
+            texture_cache_count: 2048,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TagTypeMapping {
+    pub ftype: u8,
+    pub fsubtype: u8,
+    pub name: String,
+    pub color: [u8; 3],
+}
+
+/// A config value set from the environment or the command line. Overrides take priority over the config file and
+/// aren't written back to it, the value from the file is saved instead
+pub struct Override {
+    /// Name of the environment variable or command line flag, for logging
+    source: String,
+    /// Section and key, eg. `["cache", "compression_level"]`
+    key: Vec<String>,
+    value: toml::Value,
+    /// Value in the config file before it was overridden
+    original: Option<toml::Value>,
+}
+
+impl Override {
+    /// `key` is the section and key separated by a dot, eg. `general.cache_dir`
+    pub fn new(source: &str, key: &str, value: impl Into<toml::Value>) -> Self {
+        Self {
+            source: source.to_string(),
+            key: key.split('.').map(str::to_string).collect(),
+            value: value.into(),
+            original: None,
+        }
+    }
+
+    /// Parses a `QUICKTAG_` environment variable, see [`ENV_PREFIX`]
+    fn from_env(name: &str, value: &str) -> Option<Self> {
+        let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+        if key.is_empty() || name == CONFIG_PATH_ENV {
+            return None;
+        }
+
+        // Values are parsed as TOML so numbers, booleans and lists work, anything else is taken as a string
+        let value = toml::from_str::<toml::Table>(&format!("value = {value}"))
+            .ok()
+            .and_then(|mut t| t.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(value.to_string()));
+
+        Some(Self::new(name, &key.replace("__", "."), value))
+    }
+
+    /// Sets the value in the config table, returning the value it replaced
+    fn set(&self, table: &mut toml::Table, value: toml::Value) -> Option<toml::Value> {
+        let (key, sections) = self.key.split_last()?;
+        let mut table = table;
+        for section in sections {
+            table = table
+                .entry(section.as_str())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()?;
+        }
+
+        table.insert(key.clone(), value)
+    }
+
+    /// Puts the value from the config file back
+    fn restore(&self, table: &mut toml::Table) {
+        match &self.original {
+            Some(original) => {
+                self.set(table, original.clone());
+            }
+            None => {
+                let (key, sections) = self.key.split_last().unwrap();
+                let mut table = Some(table);
+                for section in sections {
+                    table = table.and_then(|t| t.get_mut(section)?.as_table_mut());
+                }
+
+                if let Some(table) = table {
+                    table.remove(key);
+                }
+            }
+        }
+    }
+}
+
+/// Overrides from `QUICKTAG_` environment variables, see [`ENV_PREFIX`]
+fn env_overrides() -> Vec<Override> {
+    std::env::vars()
+        .filter_map(|(name, value)| Override::from_env(&name, &value))
+        .collect()
+}
+
+/// Loads the config file (if it exists), applies overrides from the environment and the given overrides from the
+/// command line on top of it, then applies it
+///
+/// The config file is read from `path`, the path in [`CONFIG_PATH_ENV`] or [`CONFIG_PATH`], in that order
+pub fn load(path: Option<PathBuf>, cli_overrides: Vec<Override>) {
+    let path = path
+        .or_else(|| std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(CONFIG_PATH));
+
+    let mut table = toml::Table::new();
+    if path.exists() {
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| {
+                let table = toml::from_str::<toml::Table>(&s)?;
+                // Catches invalid values, so a broken file falls back to the defaults as a whole
+                Config::deserialize(toml::Value::Table(table.clone()))?;
+                Ok(table)
+            }) {
+            Ok(t) => {
+                info!("Loaded config from {}", path.display());
+                table = t;
+            }
+            Err(e) => {
+                error!("Failed to load config file: {e:?}");
+            }
+        }
+    }
+
+    let mut overrides = vec![];
+    for mut o in env_overrides().into_iter().chain(cli_overrides) {
+        let mut overridden = table.clone();
+        o.original = o.set(&mut overridden, o.value.clone());
+        match Config::deserialize(toml::Value::Table(overridden.clone())) {
+            Ok(_) => {
+                info!("Config value {} set by {}", o.key.join("."), o.source);
+                table = overridden;
+                overrides.push(o);
+            }
+            Err(e) => warn!("Ignoring {}: {e}", o.source),
+        }
+    }
+
+    // Overrides of the same key are applied in order, so only the first one knows the value from the file
+    let mut seen = FxHashSet::default();
+    overrides.retain(|o| seen.insert(o.key.clone()));
+
+    *CONFIG.write() = Config::deserialize(toml::Value::Table(table)).unwrap_or_default();
+    *CONFIG_FILE.write() = path;
+    *OVERRIDES.write() = overrides;
+
+    apply();
+}
+
+fn save(config: &Config) -> anyhow::Result<()> {
+    let mut table = toml::Table::try_from(config)?;
+    for o in OVERRIDES.read().iter() {
+        o.restore(&mut table);
+    }
+
+    std::fs::write(&*CONFIG_FILE.read(), toml::to_string_pretty(&table)?)?;
+    Ok(())
+}
+
+/// Path of a file in the data directory (see [`GeneralSettings::data_dir`])
+pub fn data_path(name: impl AsRef<Path>) -> PathBuf {
+    match with(|c| c.general.data_dir.clone()) {
+        Some(dir) => PathBuf::from(dir).join(name),
+        None => name.as_ref().to_path_buf(),
+    }
+}
+
+/// Pushes config values that live outside of the GUI (eg. in quicktag-core or the scanner) to where they're used
+fn apply() {
+    let config = CONFIG.read();
+    set_custom_tagtypes(
+        config
+            .tag_types
+            .iter()
+            .map(|m| {
+                (
+                    (m.ftype, m.fsubtype),
+                    CustomTagType {
+                        name: m.name.clone(),
+                        color: m.color,
+                    },
+                )
+            })
+            .collect(),
+    );
+
+    set_cache_compression(CacheCompression {
+        level: config.cache.compression_level,
+        threads: config.cache.compression_threads,
+    });
+    set_harvest_unknown_hashes(config.cache.harvest_unknown_hashes);
+    set_scan_string_runs(config.cache.scan_string_runs);
+    set_nested_string_depth(config.cache.nested_string_depth);
+    set_scan_scope(config.cache.scan_scope());
+    set_string_languages(&config.strings.languages);
+    set_cache_directory(config.general.cache_dir.as_ref().map(PathBuf::from));
+    set_cracked_hashes_directory(config.general.data_dir.as_ref().map(PathBuf::from));
+    set_palette(config.general.palette);
+    set_tag_data_cache_size(config.tag_data.cache_size_mb);
+    set_prefetch_count(config.tag_data.prefetch_count as usize);
+    set_texture_cache_count(config.tag_data.texture_cache_count as usize);
+
+    // Wordlists and translations are read from the data directory, which needs the config lock
+    let hash_namespaces = config.strings.hash_namespaces.clone();
+    let ui_language = config.general.ui_language.clone();
+    drop(config);
+    set_custom_hash_namespaces(
+        hash_namespaces
+            .into_iter()
+            .map(|n| HashNamespaceDefinition {
+                wordlist: data_path(&n.wordlist),
+                id: n.id,
+                name: n.name,
+                algorithm: n.algorithm,
+                color: n.color,
+            })
+            .collect(),
+    );
+    i18n::set_language(ui_language.as_deref().unwrap_or(i18n::SOURCE_LANGUAGE));
+}
+
+/// Reads the current config
+pub fn with<R>(f: impl FnOnce(&Config) -> R) -> R {
+    f(&CONFIG.read())
+}
+
+/// Modifies the config, then saves and applies it
+pub fn with_mut<R>(f: impl FnOnce(&mut Config) -> R) -> R {
+    let result = {
+        let mut config = CONFIG.write();
+        let result = f(&mut config);
+        save_or_mark_dirty(&config);
+        result
+    };
+
+    apply();
+    result
+}
+
+fn save_or_mark_dirty(config: &Config) {
+    DIRTY.record("config file", save(config));
+}
+
+/// Saves the config as it is now, see [`crate::shutdown::flush`]
+pub fn save_current() {
+    save_or_mark_dirty(&CONFIG.read());
+}
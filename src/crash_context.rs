@@ -0,0 +1,125 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+type ContextProvider = Box<dyn Fn() -> Option<String> + Send + Sync>;
+
+/// Context about what the application was doing, written to panic.log when we crash
+struct ContextEntry {
+    id: u64,
+    label: &'static str,
+    value: String,
+    /// Thread that pushed the entry, None for values that aren't tied to a thread
+    thread: Option<String>,
+}
+
+lazy_static! {
+    static ref CONTEXT_STACK: Mutex<Vec<ContextEntry>> = Mutex::new(vec![]);
+    static ref CONTEXT_PROVIDERS: Mutex<Vec<(&'static str, ContextProvider)>> = Mutex::new(vec![]);
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Label for background jobs (scans, searches, imports, exports), which the user is asked about before quicktag is
+/// closed (see [`crate::shutdown`])
+pub const JOB: &str = "Job";
+
+/// Removes its context entry when dropped
+#[must_use = "The context is removed as soon as the guard is dropped"]
+pub struct ContextGuard(u64);
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        CONTEXT_STACK.lock().retain(|e| e.id != self.0);
+    }
+}
+
+/// Pushes an operation onto the context stack for the duration of the returned guard
+pub fn push(label: &'static str, value: impl Into<String>) -> ContextGuard {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let this_thread = std::thread::current();
+    CONTEXT_STACK.lock().push(ContextEntry {
+        id,
+        label,
+        value: value.into(),
+        thread: Some(
+            this_thread
+                .name()
+                .map(|name| name.to_string())
+                .unwrap_or(format!("{:?}", this_thread.id())),
+        ),
+    });
+
+    ContextGuard(id)
+}
+
+/// Sets a long-lived value (eg. the currently opened tag), replacing the previous value with the same label
+pub fn set(label: &'static str, value: impl Into<String>) {
+    let mut stack = CONTEXT_STACK.lock();
+    let value = value.into();
+    if let Some(entry) = stack
+        .iter_mut()
+        .find(|e| e.label == label && e.thread.is_none())
+    {
+        entry.value = value;
+    } else {
+        stack.push(ContextEntry {
+            id: 0,
+            label,
+            value,
+            thread: None,
+        });
+    }
+}
+
+pub fn clear(label: &'static str) {
+    CONTEXT_STACK
+        .lock()
+        .retain(|e| e.label != label || e.thread.is_some());
+}
+
+/// Values of the operations with the given label that are currently running
+pub fn running(label: &'static str) -> Vec<String> {
+    CONTEXT_STACK
+        .lock()
+        .iter()
+        .filter(|e| e.label == label && e.thread.is_some())
+        .map(|e| e.value.clone())
+        .collect()
+}
+
+/// Registers a function that is queried for context when a crash happens. Returning None omits the value
+pub fn add_provider(
+    label: &'static str,
+    provider: impl Fn() -> Option<String> + Send + Sync + 'static,
+) {
+    CONTEXT_PROVIDERS.lock().push((label, Box::new(provider)));
+}
+
+/// Formats the current context, one entry per line
+///
+/// Uses `try_lock` so a crash while the context is being modified can't deadlock the panic hook
+pub fn format_context() -> String {
+    let mut out = String::new();
+    if let Some(stack) = CONTEXT_STACK.try_lock() {
+        for e in stack.iter() {
+            match &e.thread {
+                Some(thread) => writeln!(out, "  {}: {} (thread '{thread}')", e.label, e.value),
+                None => writeln!(out, "  {}: {}", e.label, e.value),
+            }
+            .ok();
+        }
+    }
+
+    if let Some(providers) = CONTEXT_PROVIDERS.try_lock() {
+        for (label, provider) in providers.iter() {
+            if let Some(value) = provider() {
+                writeln!(out, "  {label}: {value}").ok();
+            }
+        }
+    }
+
+    out
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use eframe::egui::{self, ViewportBuilder, ViewportCommand};
+use log::{error, warn};
+
+/// Shows an error message box. Falls back to an egui window when no native dialog is available (eg. Linux without zenity/kdialog)
+///
+/// The fallback creates its own event loop, so this may only be called from the main thread before the main window has been created
+pub fn show_error(title: &str, text: &str) {
+    if let Err(e) = show_native_error(title, text) {
+        warn!("Failed to show native error dialog ({e}), falling back to an egui window");
+        if let Err(e) = show_message_window(title, text) {
+            error!("Failed to show error window: {e}");
+        }
+    }
+}
+
+/// Shows an error and exits. Meant for errors during startup that we can't recover from
+pub fn fatal_error(title: &str, text: &str) -> ! {
+    error!("{text}");
+    show_error(title, text);
+    std::process::exit(1);
+}
+
+pub fn show_native_error(title: &str, text: &str) -> Result<(), native_dialog::Error> {
+    native_dialog::MessageDialog::new()
+        .set_type(native_dialog::MessageType::Error)
+        .set_title(title)
+        .set_text(text)
+        .show_alert()
+}
+
+/// Relaunches quicktag to show a crash log, for when the current process can't open a window anymore (ie. from the panic hook)
+pub fn spawn_crash_dialog(log_path: &Path) -> std::io::Result<()> {
+    std::process::Command::new(std::env::current_exe()?)
+        .arg("--crash-dialog")
+        .arg(log_path)
+        .spawn()?;
+
+    Ok(())
+}
+
+pub fn show_message_window(title: &str, text: &str) -> eframe::Result<()> {
+    let native_options = eframe::NativeOptions {
+        renderer: eframe::Renderer::Wgpu,
+        viewport: ViewportBuilder::default()
+            .with_title(title)
+            .with_inner_size([560.0, 360.0]),
+        follow_system_theme: false,
+        default_theme: eframe::Theme::Dark,
+        ..Default::default()
+    };
+
+    let text = text.to_string();
+    eframe::run_native(
+        title,
+        native_options,
+        Box::new(|_cc| Ok(Box::new(MessageWindow { text }))),
+    )
+}
+
+struct MessageWindow {
+    text: String,
+}
+
+impl eframe::App for MessageWindow {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::bottom("message_buttons").show(ctx, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if ui.button("Copy to clipboard").clicked() {
+                    ui.output_mut(|o| o.copied_text = self.text.clone());
+                }
+
+                if ui.button("Close").clicked() {
+                    ctx.send_viewport_cmd(ViewportCommand::Close);
+                }
+            });
+            ui.add_space(4.0);
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::both()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::Label::new(egui::RichText::new(&self.text).monospace())
+                            .selectable(true),
+                    );
+                });
+        });
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::Context;
+use binrw::BinRead;
+use itertools::Itertools;
+use quicktag_core::{
+    tagtypes::TagType,
+    util::{u16_from_endian, u32_from_endian},
+};
+use quicktag_scanner::TagCache;
+use tiger_pkg::{package_manager, Endian, TagHash};
+
+#[derive(BinRead, Debug)]
+pub struct VertexBufferHeader {
+    pub data_size: u32,
+    pub stride: u16,
+    pub vertex_type: u16,
+}
+
+#[derive(BinRead, Debug)]
+pub struct IndexBufferHeader {
+    pub _unk0: i8,
+    #[br(map(|v: u8| v != 0))]
+    pub is_32bit: bool,
+    pub _unk2: u16,
+    pub _unk4: u32,
+    pub data_size: u64,
+}
+
+fn buffer_type(tag: TagHash) -> Option<TagType> {
+    package_manager()
+        .get_entry(tag)
+        .map(|e| TagType::from_type_subtype(e.file_type, e.file_subtype))
+}
+
+/// Vertex/index buffer and texture headers referenced by a tag, up to two levels deep (mesh -> mesh data -> buffers)
+#[derive(Default, Clone)]
+pub struct GeometryBuffers {
+    pub vertex: Vec<TagHash>,
+    pub index: Vec<TagHash>,
+    pub textures: Vec<TagHash>,
+}
+
+impl GeometryBuffers {
+    pub fn find(cache: &TagCache, tag: TagHash) -> Self {
+        let mut buffers = GeometryBuffers::default();
+        let mut visit = |t: TagHash| match buffer_type(t) {
+            Some(TagType::VertexBuffer { is_header: true }) => buffers.vertex.push(t),
+            Some(TagType::IndexBuffer { is_header: true }) => buffers.index.push(t),
+            Some(tag_type) if tag_type.is_texture() && tag_type.is_header() => {
+                buffers.textures.push(t)
+            }
+            _ => {}
+        };
+
+        visit(tag);
+        let direct = cache
+            .hashes
+            .get(&tag)
+            .map(|s| s.file_hashes.iter().map(|h| h.hash).collect_vec())
+            .unwrap_or_default();
+        for &child in &direct {
+            visit(child);
+        }
+        for &child in &direct {
+            if let Some(scan) = cache.hashes.get(&child) {
+                for h in &scan.file_hashes {
+                    visit(h.hash);
+                }
+            }
+        }
+
+        buffers.vertex = buffers.vertex.into_iter().unique().collect();
+        buffers.index = buffers.index.into_iter().unique().collect();
+        buffers.textures = buffers.textures.into_iter().unique().collect();
+        buffers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertex.is_empty()
+    }
+}
+
+/// Positions and indices of a single vertex/index buffer pair
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub indices: Vec<u32>,
+    pub stride: u16,
+    pub vertex_type: u16,
+    /// Center of the bounding box
+    pub center: [f32; 3],
+    /// Distance from the center to the furthest vertex
+    pub radius: f32,
+}
+
+impl Mesh {
+    pub fn load(
+        vertex_buffer: TagHash,
+        index_buffer: Option<TagHash>,
+        endian: Endian,
+    ) -> anyhow::Result<Self> {
+        let header: VertexBufferHeader = package_manager().read_tag_binrw(vertex_buffer)?;
+        anyhow::ensure!(
+            (6..=128).contains(&header.stride),
+            "Unexpected vertex stride {}",
+            header.stride
+        );
+        let data = read_buffer_data(vertex_buffer, header.data_size as usize)?;
+        let positions = decode_positions(&data, header.stride as usize, endian);
+
+        let indices = if let Some(index_buffer) = index_buffer {
+            let header: IndexBufferHeader = package_manager().read_tag_binrw(index_buffer)?;
+            let data = read_buffer_data(index_buffer, header.data_size as usize)?;
+            if header.is_32bit {
+                data.chunks_exact(4)
+                    .map(|c| u32_from_endian(endian, c.try_into().unwrap()))
+                    .collect()
+            } else {
+                data.chunks_exact(2)
+                    .map(|c| {
+                        let i = u16_from_endian(endian, c.try_into().unwrap());
+                        // Keep strip restarts recognizable
+                        if i == u16::MAX {
+                            u32::MAX
+                        } else {
+                            i as u32
+                        }
+                    })
+                    .collect()
+            }
+        } else {
+            vec![]
+        };
+
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in &positions {
+            for ((min, max), v) in min.iter_mut().zip(max.iter_mut()).zip(p) {
+                *min = min.min(*v);
+                *max = max.max(*v);
+            }
+        }
+        let center: [f32; 3] = std::array::from_fn(|i| (min[i] + max[i]) / 2.0);
+        let radius = positions
+            .iter()
+            .map(|p| {
+                ((p[0] - center[0]).powi(2)
+                    + (p[1] - center[1]).powi(2)
+                    + (p[2] - center[2]).powi(2))
+                .sqrt()
+            })
+            .fold(0.0, f32::max)
+            .max(f32::EPSILON);
+
+        Ok(Self {
+            positions,
+            indices,
+            stride: header.stride,
+            vertex_type: header.vertex_type,
+            center,
+            radius,
+        })
+    }
+
+    /// Triangles from the index buffer, skipping any that reference vertices outside of the vertex buffer
+    pub fn triangles(&self, strip: bool, limit: usize) -> Vec<[u32; 3]> {
+        let valid = |t: &[u32; 3]| {
+            t.iter().all(|&i| (i as usize) < self.positions.len())
+                && t[0] != t[1]
+                && t[1] != t[2]
+                && t[0] != t[2]
+        };
+
+        if strip {
+            self.indices
+                .split(|&i| i == u32::MAX)
+                .flat_map(|run| {
+                    run.windows(3).enumerate().map(|(n, w)| {
+                        // Every other triangle in a strip has flipped winding
+                        if n & 1 == 1 {
+                            [w[1], w[0], w[2]]
+                        } else {
+                            [w[0], w[1], w[2]]
+                        }
+                    })
+                })
+                .filter(valid)
+                .take(limit)
+                .collect()
+        } else {
+            self.indices
+                .chunks_exact(3)
+                .map(|c| [c[0], c[1], c[2]])
+                .filter(valid)
+                .take(limit)
+                .collect()
+        }
+    }
+}
+
+/// Buffer headers only describe the data, the data itself lives in the referenced tag
+pub fn read_buffer_data(header: TagHash, data_size: usize) -> anyhow::Result<Vec<u8>> {
+    let entry = package_manager()
+        .get_entry(header)
+        .context("Buffer header entry not found")?;
+    let mut data = package_manager()
+        .read_tag(TagHash(entry.reference))
+        .context("Failed to read buffer data")?;
+    data.truncate(data_size);
+    Ok(data)
+}
+
+/// Reads the first 3 components of every vertex, either as floats or as normalized shorts
+///
+/// Most static geometry uses 16-bit normalized positions, the float check is only a fallback for strides that
+/// can't be explained otherwise
+pub fn decode_positions(data: &[u8], stride: usize, endian: Endian) -> Vec<[f32; 3]> {
+    let count = data.len() / stride;
+    let read_f32 = |offset: usize| {
+        f32::from_bits(u32_from_endian(
+            endian,
+            data[offset..offset + 4].try_into().unwrap(),
+        ))
+    };
+    let read_snorm16 = |offset: usize| read_snorm16(endian, data, offset);
+
+    let is_float = stride >= 12
+        && (0..count.min(64)).all(|i| {
+            (0..3).all(|c| {
+                let v = read_f32(i * stride + c * 4);
+                v.is_finite() && v.abs() < 1e5 && (v == 0.0 || v.abs() > 1e-6)
+            })
+        });
+
+    (0..count)
+        .map(|i| {
+            let base = i * stride;
+            if is_float {
+                [read_f32(base), read_f32(base + 4), read_f32(base + 8)]
+            } else {
+                [
+                    read_snorm16(base),
+                    read_snorm16(base + 2),
+                    read_snorm16(base + 4),
+                ]
+            }
+        })
+        .collect()
+}
+
+fn read_snorm16(endian: Endian, data: &[u8], offset: usize) -> f32 {
+    u16_from_endian(endian, data[offset..offset + 2].try_into().unwrap()) as i16 as f32
+        / i16::MAX as f32
+}
+
+/// Normals and texture coordinates found in the vertex buffers next to the position buffer
+#[derive(Default)]
+pub struct VertexAttributes {
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub uvs: Option<Vec<[f32; 2]>>,
+}
+
+impl VertexAttributes {
+    /// Guesses attributes from the stride of buffers with the same amount of vertices as the position buffer
+    ///
+    /// 4-byte vertices are read as 16-bit normalized UVs, 8-byte vertices as 16-bit normalized normals. UV scale/offset
+    /// from the mesh isn't applied, so texture coordinates might need to be adjusted after importing
+    pub fn guess(
+        vertex_buffers: &[TagHash],
+        position_buffer: TagHash,
+        vertex_count: usize,
+        endian: Endian,
+    ) -> Self {
+        let mut attributes = VertexAttributes::default();
+        for &buffer in vertex_buffers {
+            if buffer == position_buffer {
+                continue;
+            }
+
+            let Ok(header) = package_manager().read_tag_binrw::<VertexBufferHeader>(buffer) else {
+                continue;
+            };
+            if header.stride == 0
+                || header.data_size as usize / header.stride as usize != vertex_count
+            {
+                continue;
+            }
+            let Ok(data) = read_buffer_data(buffer, header.data_size as usize) else {
+                continue;
+            };
+
+            let stride = header.stride as usize;
+            match stride {
+                4 if attributes.uvs.is_none() => {
+                    attributes.uvs = Some(
+                        data.chunks_exact(stride)
+                            .map(|v| [read_snorm16(endian, v, 0), read_snorm16(endian, v, 2)])
+                            .collect(),
+                    );
+                }
+                8 if attributes.normals.is_none() => {
+                    attributes.normals = Some(
+                        data.chunks_exact(stride)
+                            .map(|v| {
+                                let n = [
+                                    read_snorm16(endian, v, 0),
+                                    read_snorm16(endian, v, 2),
+                                    read_snorm16(endian, v, 4),
+                                ];
+                                let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                                if length > f32::EPSILON {
+                                    n.map(|c| c / length)
+                                } else {
+                                    [0.0, 0.0, 1.0]
+                                }
+                            })
+                            .collect(),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        attributes
+    }
+}
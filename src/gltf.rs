@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use eframe::egui_wgpu::RenderState;
+use serde_json::{json, Value};
+use tiger_pkg::{package_manager, TagHash};
+
+use crate::crash_context;
+use crate::geometry::{GeometryBuffers, Mesh, VertexAttributes};
+use crate::texture::export::{
+    export_texture, ColorSpace, CubemapLayout, TextureExportFormat, TextureExportOptions,
+};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+const MODE_POINTS: u32 = 0;
+const MODE_TRIANGLES: u32 = 4;
+
+/// Textures beyond this are skipped, large models can reference hundreds of them through their materials
+const MAX_TEXTURES: usize = 32;
+
+pub struct GltfExportSummary {
+    pub path: PathBuf,
+    pub textures: usize,
+    pub failed_textures: usize,
+}
+
+/// Binary buffer with the buffer views and accessors pointing into it
+#[derive(Default)]
+struct GltfBuffer {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl GltfBuffer {
+    /// Appends the values as a new buffer view + accessor, returns the accessor index
+    fn push<const N: usize>(
+        &mut self,
+        values: &[[f32; N]],
+        kind: &str,
+        with_bounds: bool,
+    ) -> usize {
+        let offset = self.data.len();
+        for v in values.iter().flatten() {
+            self.data.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let mut accessor = json!({
+            "bufferView": self.views.len(),
+            "componentType": COMPONENT_FLOAT,
+            "count": values.len(),
+            "type": kind,
+        });
+
+        // Positions require bounds
+        if with_bounds {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+            for v in values {
+                for (c, &value) in v.iter().enumerate() {
+                    min[c] = min[c].min(value);
+                    max[c] = max[c].max(value);
+                }
+            }
+            accessor["min"] = json!(min.to_vec());
+            accessor["max"] = json!(max.to_vec());
+        }
+
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": self.data.len() - offset,
+            "target": ARRAY_BUFFER,
+        }));
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_indices(&mut self, triangles: &[[u32; 3]]) -> usize {
+        let offset = self.data.len();
+        for i in triangles.iter().flatten() {
+            self.data.extend_from_slice(&i.to_le_bytes());
+        }
+
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": self.data.len() - offset,
+            "target": ELEMENT_ARRAY_BUFFER,
+        }));
+        self.accessors.push(json!({
+            "bufferView": self.views.len() - 1,
+            "componentType": COMPONENT_UNSIGNED_INT,
+            "count": triangles.len() * 3,
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+}
+
+/// Destiny is Z-up, glTF is Y-up
+fn to_y_up(v: [f32; 3]) -> [f32; 3] {
+    [v[0], v[2], -v[1]]
+}
+
+/// Exports a vertex/index buffer pair with guessed normals and UVs, plus the textures found next to the buffers
+///
+/// Writes `{tag}.gltf`, `{tag}.bin` and a `textures` directory into `out_dir`. The first texture is used as the
+/// base color of a single material, the others are only included as images so they can be assigned by hand
+pub fn export_gltf(
+    rs: &RenderState,
+    tag: TagHash,
+    buffers: &GeometryBuffers,
+    vertex_buffer: TagHash,
+    index_buffer: Option<TagHash>,
+    triangle_strip: bool,
+    out_dir: &Path,
+) -> anyhow::Result<GltfExportSummary> {
+    let _context = crash_context::push(crash_context::JOB, "Exporting glTF");
+    let endian = package_manager().version.endian();
+    let mesh = Mesh::load(vertex_buffer, index_buffer, endian)?;
+    let attributes =
+        VertexAttributes::guess(&buffers.vertex, vertex_buffer, mesh.positions.len(), endian);
+
+    std::fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+
+    let mut buffer = GltfBuffer::default();
+    let positions: Vec<_> = mesh.positions.iter().map(|&p| to_y_up(p)).collect();
+    let mut primitive_attributes = json!({
+        "POSITION": buffer.push(&positions, "VEC3", true),
+    });
+    if let Some(normals) = &attributes.normals {
+        let normals: Vec<_> = normals.iter().map(|&n| to_y_up(n)).collect();
+        primitive_attributes["NORMAL"] = json!(buffer.push(&normals, "VEC3", false));
+    }
+    if let Some(uvs) = &attributes.uvs {
+        primitive_attributes["TEXCOORD_0"] = json!(buffer.push(uvs, "VEC2", false));
+    }
+
+    let mut primitive = json!({
+        "attributes": primitive_attributes,
+        "mode": MODE_POINTS,
+    });
+    if index_buffer.is_some() {
+        let triangles = mesh.triangles(triangle_strip, usize::MAX);
+        anyhow::ensure!(
+            !triangles.is_empty(),
+            "Index buffer does not contain any triangles"
+        );
+        primitive["indices"] = json!(buffer.push_indices(&triangles));
+        primitive["mode"] = json!(MODE_TRIANGLES);
+    }
+
+    let texture_options = TextureExportOptions {
+        format: TextureExportFormat::Png,
+        mip: 0,
+        cubemap_layout: CubemapLayout::Faces,
+        color_space: ColorSpace::AsStored,
+    };
+    let texture_dir = out_dir.join("textures");
+    let mut images = vec![];
+    let mut failed_textures = 0;
+    if !buffers.textures.is_empty() {
+        std::fs::create_dir_all(&texture_dir).context("Failed to create texture directory")?;
+    }
+    for &texture in buffers.textures.iter().take(MAX_TEXTURES) {
+        match export_texture(rs, texture, texture_options, &texture_dir) {
+            Ok(files) => {
+                // Only the first layer of array textures/cubemaps is referenced
+                if let Some(file_name) = files.first().and_then(|f| f.file_name()) {
+                    images.push(json!({
+                        "uri": format!("textures/{}", file_name.to_string_lossy()),
+                        "name": texture.to_string(),
+                    }));
+                }
+            }
+            Err(e) => {
+                log::warn!("Failed to export texture {texture} for glTF: {e:?}");
+                failed_textures += 1;
+            }
+        }
+    }
+
+    let mut material = json!({
+        "name": tag.to_string(),
+        "pbrMetallicRoughness": {
+            "metallicFactor": 0.0,
+            "roughnessFactor": 1.0,
+        },
+    });
+    if !images.is_empty() {
+        material["pbrMetallicRoughness"]["baseColorTexture"] = json!({ "index": 0 });
+    }
+    primitive["material"] = json!(0);
+
+    let bin_name = format!("{tag}.bin");
+    let mut gltf = json!({
+        "asset": {
+            "version": "2.0",
+            "generator": format!("quicktag {}", env!("CARGO_PKG_VERSION")),
+        },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0, "name": tag.to_string() }],
+        "meshes": [{ "name": vertex_buffer.to_string(), "primitives": [primitive] }],
+        "materials": [material],
+        "buffers": [{ "uri": bin_name, "byteLength": buffer.data.len() }],
+        "bufferViews": buffer.views,
+        "accessors": buffer.accessors,
+    });
+
+    // glTF doesn't allow empty arrays
+    if !images.is_empty() {
+        gltf["samplers"] = json!([{}]);
+        gltf["textures"] = (0..images.len())
+            .map(|i| json!({ "sampler": 0, "source": i }))
+            .collect();
+        gltf["images"] = json!(images);
+    }
+
+    std::fs::write(out_dir.join(&bin_name), &buffer.data)?;
+    let path = out_dir.join(format!("{tag}.gltf"));
+    std::fs::write(&path, serde_json::to_string_pretty(&gltf)?)?;
+
+    Ok(GltfExportSummary {
+        path,
+        textures: images.len(),
+        failed_textures,
+    })
+}
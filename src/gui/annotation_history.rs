@@ -0,0 +1,111 @@
+//! Undo/redo for annotation edits, see [`crate::annotations::with_mut`]
+
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers, RichText};
+
+use super::{backup_restore, TOASTS};
+use crate::annotations;
+
+const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+const REDO_SHORTCUT_ALT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Y);
+
+/// Amount of edits listed in the history menu
+const HISTORY_LENGTH: usize = 32;
+
+fn undo() {
+    if let Some(description) = annotations::undo() {
+        TOASTS.lock().info(format!("Undid '{description}'"));
+    }
+}
+
+fn redo() {
+    if let Some(description) = annotations::redo() {
+        TOASTS.lock().info(format!("Redid '{description}'"));
+    }
+}
+
+/// Handles the undo/redo shortcuts. Text fields have their own undo, so nothing happens while one is focused
+pub fn handle_shortcuts(ctx: &egui::Context) {
+    if ctx.memory(|m| m.focused().is_some()) {
+        return;
+    }
+
+    // Redo is checked first, as the undo shortcut also matches when shift is held
+    if ctx
+        .input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT) || i.consume_shortcut(&REDO_SHORTCUT_ALT))
+    {
+        redo();
+    } else if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+        undo();
+    }
+}
+
+/// Contents of the edit menu
+pub fn edit_menu(ui: &mut egui::Ui) {
+    let (next_undo, next_redo) = annotations::with_history(|h| {
+        (
+            h.undo.last().map(|e| e.description.clone()),
+            h.redo.last().map(|e| e.description.clone()),
+        )
+    });
+
+    let undo_label = match &next_undo {
+        Some(description) => format!("Undo '{description}'"),
+        None => "Undo".to_string(),
+    };
+    if ui
+        .add_enabled(
+            next_undo.is_some(),
+            egui::Button::new(undo_label).shortcut_text(ui.ctx().format_shortcut(&UNDO_SHORTCUT)),
+        )
+        .clicked()
+    {
+        undo();
+        ui.close_menu();
+    }
+
+    let redo_label = match &next_redo {
+        Some(description) => format!("Redo '{description}'"),
+        None => "Redo".to_string(),
+    };
+    if ui
+        .add_enabled(
+            next_redo.is_some(),
+            egui::Button::new(redo_label).shortcut_text(ui.ctx().format_shortcut(&REDO_SHORTCUT)),
+        )
+        .clicked()
+    {
+        redo();
+        ui.close_menu();
+    }
+
+    ui.separator();
+    ui.menu_button("Annotation history", |ui| {
+        annotations::with_history(|h| {
+            if h.undo.is_empty() && h.redo.is_empty() {
+                ui.weak("No annotations were edited this session");
+                return;
+            }
+
+            // Newest first: undone edits, then the applied ones
+            let undone = h.redo.iter().map(|e| (e, true));
+            let applied = h.undo.iter().rev().map(|e| (e, false));
+            for (edit, is_undone) in undone.chain(applied).take(HISTORY_LENGTH) {
+                let text = RichText::new(format!(
+                    "{} {} ({} tags)",
+                    edit.time.format("%H:%M:%S"),
+                    edit.description,
+                    edit.tag_count()
+                ));
+                if is_undone {
+                    ui.label(text.weak().strikethrough())
+                        .on_hover_text("Undone");
+                } else {
+                    ui.label(text);
+                }
+            }
+        });
+    });
+    ui.menu_button("Restore backup", backup_restore::restore_menu);
+}
@@ -0,0 +1,231 @@
+use std::path::Path;
+
+use eframe::egui::{self, RichText};
+
+use super::{common::ResponseExt, file_picker::FilePicker, TOASTS};
+use crate::annotation_merge::{self, MergeEntry, MergePlan};
+use crate::annotations::TagAnnotation;
+
+/// Notes are shortened to this many characters in the merge tables
+const MAX_NOTES_LENGTH: usize = 48;
+
+fn annotation_summary(annotation: &Option<TagAnnotation>) -> String {
+    let Some(annotation) = annotation else {
+        return "(none)".to_string();
+    };
+
+    let mut parts = vec![];
+    parts.extend(annotation.name.clone());
+    if !annotation.labels.is_empty() {
+        parts.push(format!("[{}]", annotation.labels.join(", ")));
+    }
+    if !annotation.notes.is_empty() {
+        let mut notes: String = annotation.notes.chars().take(MAX_NOTES_LENGTH).collect();
+        if notes.len() < annotation.notes.len() {
+            notes.push('…');
+        }
+        parts.push(notes);
+    }
+
+    parts.join(" ")
+}
+
+fn annotation_label(ui: &mut egui::Ui, annotation: &Option<TagAnnotation>) {
+    let label = ui.label(annotation_summary(annotation));
+    if let Some(annotation) = annotation {
+        if !annotation.notes.is_empty() {
+            label.on_hover_text(&annotation.notes);
+        }
+    }
+}
+
+/// Imports someone else's annotations, letting the user pick between their and our version for every conflict
+pub struct AnnotationMergeDialog {
+    plan: Option<MergePlan>,
+    file_picker: Option<FilePicker>,
+}
+
+impl AnnotationMergeDialog {
+    pub fn new() -> Self {
+        Self {
+            plan: None,
+            file_picker: None,
+        }
+    }
+
+    /// Asks for the file to import, using the built-in file picker if the native dialog isn't available
+    pub fn pick_file(&mut self) {
+        match native_dialog::FileDialog::new()
+            .add_filter("Annotations", &["json"])
+            .show_open_single_file()
+        {
+            Ok(Some(path)) => self.open(&path),
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("Native file dialog unavailable ({e}), using the built-in file picker");
+                self.file_picker = Some(FilePicker::new("Import annotations"));
+            }
+        }
+    }
+
+    fn open(&mut self, path: &Path) {
+        match annotation_merge::plan(path) {
+            Ok(plan) if plan.automatic.is_empty() && plan.conflicts.is_empty() => {
+                TOASTS
+                    .lock()
+                    .info("The annotations are already up to date, nothing to import");
+            }
+            Ok(plan) => self.plan = Some(plan),
+            Err(e) => {
+                log::error!("Failed to read annotations from {}: {e:?}", path.display());
+                TOASTS
+                    .lock()
+                    .error(format!("Failed to read annotations: {e}"));
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if let Some(picker) = self.file_picker.as_mut() {
+            let selected = picker.show(ctx);
+            if !picker.is_open() {
+                self.file_picker = None;
+            }
+
+            if let Some(path) = selected {
+                self.open(&path);
+            }
+        }
+
+        let Some(plan) = self.plan.as_mut() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut apply = false;
+        egui::Window::new("Import annotations")
+            .open(&mut open)
+            .collapsible(false)
+            .default_size([720.0, 480.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{}: {} changes can be merged automatically, {} conflict with your annotations",
+                    plan.source.display(),
+                    plan.automatic.len(),
+                    plan.conflicts.len()
+                ));
+                if !plan.has_base {
+                    ui.weak(
+                        "This file wasn't imported before, so every difference with one of your annotations is a conflict",
+                    );
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height() - 32.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        if !plan.conflicts.is_empty() {
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new("Conflicts").strong());
+                                if ui.small_button("Keep all mine").clicked() {
+                                    plan.conflicts.iter_mut().for_each(|e| e.accept = false);
+                                }
+                                if ui.small_button("Take all theirs").clicked() {
+                                    plan.conflicts.iter_mut().for_each(|e| e.accept = true);
+                                }
+                            });
+                            conflicts_ui(ui, &mut plan.conflicts, plan.has_base);
+                            ui.separator();
+                        }
+
+                        if !plan.automatic.is_empty() {
+                            egui::CollapsingHeader::new(format!(
+                                "Automatic changes ({})",
+                                plan.automatic.len()
+                            ))
+                            .default_open(plan.conflicts.is_empty())
+                            .show(ui, |ui| automatic_ui(ui, &mut plan.automatic));
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let accepted = plan
+                        .automatic
+                        .iter()
+                        .chain(&plan.conflicts)
+                        .filter(|e| e.accept)
+                        .count();
+                    if ui
+                        .button(format!("Apply {accepted} changes"))
+                        .on_hover_text("Can be undone from the Edit menu")
+                        .clicked()
+                    {
+                        apply = true;
+                    }
+                });
+            });
+
+        if apply {
+            let plan = self.plan.take().unwrap();
+            let count = plan.apply();
+            TOASTS
+                .lock()
+                .success(format!("Imported {count} annotations"));
+        } else if !open {
+            self.plan = None;
+        }
+    }
+}
+
+fn conflicts_ui(ui: &mut egui::Ui, conflicts: &mut [MergeEntry], has_base: bool) {
+    egui::Grid::new("annotation_merge_conflicts")
+        .num_columns(if has_base { 5 } else { 4 })
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Tag");
+            if has_base {
+                ui.strong("Previous");
+            }
+            ui.strong("Mine");
+            ui.strong("Theirs");
+            ui.strong("Use");
+            ui.end_row();
+
+            for entry in conflicts {
+                ui.label(entry.tag.to_string()).tag_context(entry.tag);
+                if has_base {
+                    annotation_label(ui, &entry.base);
+                }
+                annotation_label(ui, &entry.mine);
+                annotation_label(ui, &entry.theirs);
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut entry.accept, false, "Mine");
+                    ui.radio_value(&mut entry.accept, true, "Theirs");
+                });
+                ui.end_row();
+            }
+        });
+}
+
+fn automatic_ui(ui: &mut egui::Ui, automatic: &mut [MergeEntry]) {
+    egui::Grid::new("annotation_merge_automatic")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Tag");
+            ui.strong("Mine");
+            ui.strong("Theirs");
+            ui.strong("Accept");
+            ui.end_row();
+
+            for entry in automatic {
+                ui.label(entry.tag.to_string()).tag_context(entry.tag);
+                annotation_label(ui, &entry.mine);
+                annotation_label(ui, &entry.theirs);
+                ui.checkbox(&mut entry.accept, "");
+                ui.end_row();
+            }
+        });
+}
@@ -0,0 +1,98 @@
+//! Restoring the user databases from their backups, see [`crate::backups`]
+
+use std::path::{Path, PathBuf};
+
+use eframe::egui;
+use itertools::Itertools;
+use log::error;
+
+use super::hex_sidecar::SIDECARS_DIR;
+use super::TOASTS;
+use crate::annotations::{self, ANNOTATIONS_PATH};
+use crate::backups::{self, BACKUPS_DIR};
+use crate::config;
+use crate::watchlist::{self, WATCHLIST_PATH};
+
+/// Contents of the restore backup menu
+pub fn restore_menu(ui: &mut egui::Ui) {
+    ui.menu_button("Annotations", |ui| {
+        backup_list(ui, Path::new(ANNOTATIONS_PATH), annotations::load)
+    });
+    ui.menu_button("Watch list", |ui| {
+        backup_list(ui, Path::new(WATCHLIST_PATH), watchlist::load)
+    });
+    ui.menu_button("Hex view sidecars", |ui| {
+        let sidecars = sidecar_backups();
+        if sidecars.is_empty() {
+            ui.weak("No backups");
+        }
+
+        for name in sidecars {
+            let label = name
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            // Sidecars are read whenever a tag is opened, there's nothing to reload
+            ui.menu_button(label, |ui| backup_list(ui, &name, || {}));
+        }
+    });
+}
+
+fn backup_list(ui: &mut egui::Ui, name: &Path, reload: impl Fn()) {
+    let backups = backups::list(name);
+    if backups.is_empty() {
+        ui.weak("No backups");
+        return;
+    }
+
+    for backup in backups {
+        let label = format!(
+            "{} ({} KiB)",
+            backup.time.format("%Y-%m-%d %H:%M:%S"),
+            backup.size.div_ceil(1024)
+        );
+        if ui
+            .button(label)
+            .on_hover_text(
+                "Replaces the current file with this backup. The current file is backed up first",
+            )
+            .clicked()
+        {
+            match backups::restore(name, &backup) {
+                Ok(()) => {
+                    reload();
+                    TOASTS.lock().success(format!(
+                        "Restored {} from {}",
+                        name.display(),
+                        backup.time.format("%Y-%m-%d %H:%M:%S")
+                    ));
+                }
+                Err(e) => {
+                    error!("Failed to restore {}: {e:?}", name.display());
+                    TOASTS
+                        .lock()
+                        .error(format!("Failed to restore {}", name.display()));
+                }
+            }
+            ui.close_menu();
+        }
+    }
+}
+
+/// Sidecars that have backups, relative to the data directory
+fn sidecar_backups() -> Vec<PathBuf> {
+    let Ok(dir) = std::fs::read_dir(config::data_path(BACKUPS_DIR).join(SIDECARS_DIR)) else {
+        return vec![];
+    };
+
+    dir.filter_map(|entry| {
+        let file_name = entry.ok()?.file_name().to_string_lossy().to_string();
+        // <sidecar>.<timestamp>.bak
+        let (name, _) = file_name.strip_suffix(".bak")?.rsplit_once('.')?;
+        Some(Path::new(SIDECARS_DIR).join(name))
+    })
+    .unique()
+    .sorted()
+    .collect()
+}
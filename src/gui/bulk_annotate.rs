@@ -0,0 +1,195 @@
+//! Names and labels every tag in a set of search results in one go
+
+use eframe::egui::{self, RichText};
+use tiger_pkg::TagHash;
+
+use super::{
+    search_results::{class_name, package_name},
+    tag_search::TagSearchResult,
+    TOASTS,
+};
+use crate::annotations;
+
+/// Amount of tags shown in the preview
+const PREVIEW_COUNT: usize = 16;
+
+const PLACEHOLDER_HELP: &str = "Placeholders:\n\
+    {index} - position in the results, starting at 0\n\
+    {tag} - tag hash\n\
+    {package} - package name\n\
+    {class} - class name or reference\n\
+    {type} - tag type\n\
+    {name} - current name, empty if the tag doesn't have one";
+
+/// A search result, with the values for the placeholders
+struct BulkTarget {
+    tag: TagHash,
+    package: String,
+    class: String,
+    tag_type: String,
+    name: Option<String>,
+}
+
+impl BulkTarget {
+    fn new(result: &TagSearchResult) -> Self {
+        Self {
+            tag: result.tag,
+            package: package_name(result),
+            class: class_name(result),
+            tag_type: result.tag_type.to_string(),
+            name: result.name.clone(),
+        }
+    }
+
+    fn expand(&self, pattern: &str, index: usize) -> String {
+        pattern
+            .replace("{index}", &index.to_string())
+            .replace("{tag}", &self.tag.to_string())
+            .replace("{package}", &self.package)
+            .replace("{class}", &self.class)
+            .replace("{type}", &self.tag_type)
+            .replace("{name}", self.name.as_deref().unwrap_or_default())
+    }
+}
+
+pub struct BulkAnnotateDialog {
+    pub open: bool,
+    /// Snapshot of the results when the dialog was opened, so a new search doesn't change what gets annotated
+    targets: Vec<BulkTarget>,
+    name_pattern: String,
+    label: String,
+    skip_named: bool,
+}
+
+impl BulkAnnotateDialog {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            targets: vec![],
+            name_pattern: String::new(),
+            label: String::new(),
+            skip_named: true,
+        }
+    }
+
+    pub fn open_for(&mut self, results: &[TagSearchResult]) {
+        self.targets = results.iter().map(BulkTarget::new).collect();
+        self.open = true;
+    }
+
+    /// New name for the target at the given index, or None if it's left as-is
+    fn new_name(&self, index: usize) -> Option<String> {
+        let target = &self.targets[index];
+        if self.name_pattern.trim().is_empty() || (self.skip_named && target.name.is_some()) {
+            return None;
+        }
+
+        Some(target.expand(self.name_pattern.trim(), index))
+    }
+
+    fn apply(&self) {
+        let label = self.label.trim();
+        let renamed = (0..self.targets.len())
+            .filter_map(|i| Some((self.targets[i].tag, self.new_name(i)?)))
+            .collect::<Vec<_>>();
+        if renamed.is_empty() && label.is_empty() {
+            TOASTS.lock().warning("All tags already have a name");
+            return;
+        }
+
+        let description = match (renamed.is_empty(), label.is_empty()) {
+            (false, true) => format!("Name {} tags '{}'", renamed.len(), self.name_pattern.trim()),
+            (true, false) => format!("Label {} tags as '{label}'", self.targets.len()),
+            _ => format!(
+                "Name {} tags '{}' and label {} tags as '{label}'",
+                renamed.len(),
+                self.name_pattern.trim(),
+                self.targets.len()
+            ),
+        };
+
+        annotations::with_mut(&description, |a| {
+            for (tag, name) in renamed {
+                a.entry(tag).name = Some(name);
+            }
+
+            if !label.is_empty() {
+                for target in &self.targets {
+                    a.add_label(target.tag, label);
+                }
+            }
+        });
+
+        TOASTS.lock().success(description);
+    }
+
+    /// Shows the dialog while it's open, returns true when the annotations were changed
+    pub fn show(&mut self, ctx: &egui::Context) -> bool {
+        let mut open = self.open;
+        let mut applied = false;
+        egui::Window::new("Annotate results")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("bulk_annotate_fields")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.name_pattern)
+                                .hint_text("eg. Cinematic_Audio_{index}, empty keeps the names"),
+                        )
+                        .on_hover_text(PLACEHOLDER_HELP);
+                        ui.end_row();
+
+                        ui.label("Label");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.label)
+                                .hint_text("Added to every tag, empty adds no label"),
+                        );
+                        ui.end_row();
+                    });
+                ui.checkbox(&mut self.skip_named, "Keep existing names");
+
+                ui.separator();
+                ui.label(RichText::new("Preview").strong());
+                egui::Grid::new("bulk_annotate_preview")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for i in 0..self.targets.len().min(PREVIEW_COUNT) {
+                            let target = &self.targets[i];
+                            ui.label(target.tag.to_string());
+                            ui.label(target.name.as_deref().unwrap_or("-"));
+                            match self.new_name(i) {
+                                Some(name) => ui.label(format!("→ {name}")),
+                                None => ui.weak("unchanged"),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                if self.targets.len() > PREVIEW_COUNT {
+                    ui.weak(format!("and {} more", self.targets.len() - PREVIEW_COUNT));
+                }
+
+                ui.separator();
+                let has_changes =
+                    !self.name_pattern.trim().is_empty() || !self.label.trim().is_empty();
+                if ui
+                    .add_enabled(
+                        has_changes,
+                        egui::Button::new(format!("Apply to {} tags", self.targets.len())),
+                    )
+                    .on_hover_text("Can be undone from the Edit menu")
+                    .clicked()
+                {
+                    self.apply();
+                    applied = true;
+                }
+            });
+
+        self.open = open && !applied;
+        applied
+    }
+}
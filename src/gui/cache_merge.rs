@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use eframe::egui::{self, RichText};
+use quicktag_scanner::{merge, save_tag_cache, scope::ScanScope, TagCache};
+
+use crate::config::{self, ScanScopeMode};
+use crate::util::format_file_size;
+
+struct PartialCacheFile {
+    path: PathBuf,
+    size: u64,
+    modified: Option<DateTime<Local>>,
+    selected: bool,
+}
+
+/// Picks partial caches (built with a scan scope) to merge into one
+pub struct CacheMergeDialog {
+    pub open: bool,
+    caches: Vec<PartialCacheFile>,
+}
+
+impl CacheMergeDialog {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            caches: vec![],
+        }
+    }
+
+    /// Opens the dialog with the partial caches that currently exist, all of them selected
+    pub fn open(&mut self) {
+        self.caches = merge::partial_cache_paths()
+            .into_iter()
+            .map(|path| {
+                let metadata = std::fs::metadata(&path).ok();
+                PartialCacheFile {
+                    size: metadata.as_ref().map(|m| m.len()).unwrap_or_default(),
+                    modified: metadata
+                        .and_then(|m| m.modified().ok())
+                        .map(DateTime::<Local>::from),
+                    path,
+                    selected: true,
+                }
+            })
+            .collect();
+        self.open = true;
+    }
+
+    /// Returns the caches to merge once the user confirms
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Vec<PathBuf>> {
+        if !self.open {
+            return None;
+        }
+
+        let mut open = true;
+        let mut merge = false;
+        egui::Window::new("Merge partial caches")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if self.caches.is_empty() {
+                    ui.label(
+                        RichText::new(
+                            "There are no partial caches for this game version. \
+                            Build one by picking a scan scope in the settings",
+                        )
+                        .italics(),
+                    );
+                    return;
+                }
+
+                ui.label(
+                    "The merged cache replaces the scan scope with the packages of every selected cache. \
+                    Packages that are in more than one cache are taken from the newest one",
+                );
+                ui.separator();
+
+                egui::Grid::new("cache_merge_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for cache in &mut self.caches {
+                            let name = cache
+                                .path
+                                .file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_default();
+                            ui.checkbox(&mut cache.selected, name);
+                            ui.label(format_file_size(cache.size as usize));
+                            ui.label(
+                                cache
+                                    .modified
+                                    .map(|m| m.format("%Y-%m-%d %H:%M").to_string())
+                                    .unwrap_or_default(),
+                            );
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                let selected = self.caches.iter().filter(|c| c.selected).count();
+                if ui
+                    .add_enabled(selected >= 2, egui::Button::new(format!("Merge {selected} caches")))
+                    .on_disabled_hover_text("Select at least two caches")
+                    .clicked()
+                {
+                    merge = true;
+                }
+            });
+
+        if merge {
+            self.open = false;
+            return Some(
+                self.caches
+                    .iter()
+                    .filter(|c| c.selected)
+                    .map(|c| c.path.clone())
+                    .collect(),
+            );
+        }
+
+        self.open &= open;
+        None
+    }
+}
+
+/// Merges the caches, switches the scan scope to the merged packages and saves the merged cache as the cache for that
+/// scope. Runs on the cache loading thread
+pub fn merge_partial_caches(paths: &[PathBuf]) -> anyhow::Result<TagCache> {
+    let (cache, scope) = merge::merge_cache_files(paths)?;
+    config::with_mut(|c| match scope {
+        ScanScope::Packages(packages) => {
+            c.cache.scan_scope = ScanScopeMode::Selected;
+            c.cache.scan_packages = packages;
+        }
+        _ => c.cache.scan_scope = ScanScopeMode::All,
+    });
+
+    save_tag_cache(&cache);
+    Ok(cache)
+}
@@ -0,0 +1,238 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use eframe::egui::{self, collapsing_header::CollapsingState, RichText};
+use itertools::Itertools;
+use log::error;
+use poll_promise::Promise;
+use quicktag_core::classes::get_class_by_id;
+use quicktag_scanner::TagCache;
+use rustc_hash::{FxHashMap, FxHashSet};
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{common::ResponseExt, tag::format_tag_entry, View, ViewAction, TOASTS};
+use crate::{annotations, crash_context};
+
+/// Clusters with fewer tags than this are not shown
+const MIN_CLUSTER_SIZE: usize = 2;
+/// Maximum amount of tags listed per cluster
+const MAX_LISTED_TAGS: usize = 128;
+
+pub struct TagCluster {
+    /// Class reference shared by every tag in the cluster
+    pub reference: u32,
+    pub array_classes: Vec<u32>,
+    pub tags: Vec<TagHash>,
+}
+
+/// Groups tags without a known class or label by their structural signature (class reference and array class sequence)
+pub fn cluster_unlabeled_tags(cache: &TagCache) -> Vec<TagCluster> {
+    let _context = crash_context::push(crash_context::JOB, "Clustering unlabeled tags");
+    let labeled = annotations::with(|a| {
+        a.tags
+            .iter()
+            .filter(|(_, a)| !a.labels.is_empty())
+            .map(|(t, _)| *t)
+            .collect::<FxHashSet<_>>()
+    });
+
+    let mut groups: FxHashMap<(u32, &[u32]), Vec<TagHash>> = FxHashMap::default();
+    for (tag, scan) in cache.hashes.iter() {
+        if !scan.successful {
+            continue;
+        }
+
+        let Some(entry) = package_manager().get_entry(*tag) else {
+            continue;
+        };
+
+        if get_class_by_id(entry.reference).is_some() || labeled.contains(tag) {
+            continue;
+        }
+
+        groups
+            .entry((entry.reference, &scan.fingerprint.array_classes))
+            .or_default()
+            .push(*tag);
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, tags)| tags.len() >= MIN_CLUSTER_SIZE)
+        .map(|((reference, array_classes), tags)| TagCluster {
+            reference,
+            array_classes: array_classes.to_vec(),
+            tags: tags.into_iter().sorted_by_key(|t| t.0).collect(),
+        })
+        .sorted_by_key(|c| std::cmp::Reverse(c.tags.len()))
+        .collect()
+}
+
+/// Adds a class name to schema.txt. The schema file watcher takes care of reloading it
+fn append_schema_class(reference: u32, name: &str) -> anyhow::Result<()> {
+    let mut f = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open("schema.txt")?;
+    writeln!(f, "{reference:08X} {name}")?;
+    Ok(())
+}
+
+pub struct ClustersView {
+    cache: Arc<TagCache>,
+    clusters: Option<Promise<Vec<TagCluster>>>,
+    /// Class name being entered per cluster (by index)
+    label_input: FxHashMap<usize, String>,
+    name_class: bool,
+}
+
+impl ClustersView {
+    pub fn new(cache: Arc<TagCache>) -> Self {
+        Self {
+            cache,
+            clusters: None,
+            label_input: Default::default(),
+            name_class: true,
+        }
+    }
+
+    fn label_cluster(cluster: &TagCluster, name: &str, name_class: bool) {
+        let description = format!("Label {} tags as '{name}'", cluster.tags.len());
+        annotations::with_mut(&description, |a| {
+            for &tag in &cluster.tags {
+                a.add_label(tag, name);
+            }
+        });
+
+        if name_class {
+            if let Err(e) = append_schema_class(cluster.reference, name) {
+                error!("Failed to write class name to schema.txt: {e:?}");
+                TOASTS
+                    .lock()
+                    .error("Failed to write class name to schema.txt");
+                return;
+            }
+        }
+
+        TOASTS
+            .lock()
+            .success(format!("Labeled {} tags as '{name}'", cluster.tags.len()));
+    }
+}
+
+impl View for ClustersView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.clusters
+                        .as_ref()
+                        .map(|p| p.ready().is_some())
+                        .unwrap_or(true),
+                    egui::Button::new("Cluster unlabeled tags"),
+                )
+                .on_hover_text(
+                    "Groups tags with an unknown class by their structure (class reference and array classes)",
+                )
+                .clicked()
+            {
+                let cache = self.cache.clone();
+                self.label_input.clear();
+                self.clusters = Some(Promise::spawn_thread("cluster_unlabeled_tags", move || {
+                    cluster_unlabeled_tags(&cache)
+                }));
+            }
+
+            ui.checkbox(&mut self.name_class, "Also add class names to schema.txt");
+        });
+        ui.separator();
+
+        let Some(clusters) = self.clusters.as_ref() else {
+            ui.label(RichText::new("Tags have not been clustered yet").italics());
+            return None;
+        };
+
+        let Some(clusters) = clusters.ready() else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Clustering tags");
+            });
+            return None;
+        };
+
+        ui.label(format!("{} clusters", clusters.len()));
+
+        let mut result = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (i, cluster) in clusters.iter().enumerate() {
+                    CollapsingState::load_with_default_open(
+                        ui.ctx(),
+                        egui::Id::new(("tag_cluster", i)),
+                        false,
+                    )
+                    .show_header(ui, |ui| {
+                        ui.label(format!(
+                            "{:08X} - {} tags, {} arrays",
+                            cluster.reference,
+                            cluster.tags.len(),
+                            cluster.array_classes.len()
+                        ));
+
+                        let input = self.label_input.entry(i).or_default();
+                        ui.add(
+                            egui::TextEdit::singleline(input)
+                                .hint_text("Class name")
+                                .desired_width(160.0),
+                        );
+
+                        let name = input.trim().to_string();
+                        if ui
+                            .add_enabled(
+                                !name.is_empty() && !name.contains(char::is_whitespace),
+                                egui::Button::new("Label cluster"),
+                            )
+                            .clicked()
+                        {
+                            Self::label_cluster(cluster, &name, self.name_class);
+                        }
+                    })
+                    .body(|ui| {
+                        if !cluster.array_classes.is_empty() {
+                            ui.weak(format!(
+                                "Arrays: {}",
+                                cluster
+                                    .array_classes
+                                    .iter()
+                                    .map(|c| get_class_by_id(*c)
+                                        .map(|c| c.name.to_string())
+                                        .unwrap_or_else(|| format!("{c:08X}")))
+                                    .join(", ")
+                            ));
+                        }
+
+                        for &tag in cluster.tags.iter().take(MAX_LISTED_TAGS) {
+                            let entry = package_manager().get_entry(tag);
+                            if ui
+                                .selectable_label(false, format_tag_entry(tag, entry.as_ref()))
+                                .tag_context(tag)
+                                .clicked()
+                            {
+                                result = Some(ViewAction::OpenTag(tag));
+                            }
+                        }
+
+                        if cluster.tags.len() > MAX_LISTED_TAGS {
+                            ui.weak(format!(
+                                "...and {} more",
+                                cluster.tags.len() - MAX_LISTED_TAGS
+                            ));
+                        }
+                    });
+                }
+            });
+
+        result
+    }
+}
@@ -1,22 +1,75 @@
 use std::fs::File;
+use std::path::{Path, PathBuf};
 
 use eframe::egui;
 use eframe::egui::RichText;
-use image::{DynamicImage, GenericImage, ImageFormat};
+use image::ImageFormat;
 use lazy_static::lazy_static;
 use log::{error, info, warn};
+use parking_lot::Mutex;
 use quicktag_core::tagtypes::TagType;
+use rustc_hash::FxHashMap;
 use std::io::{Cursor, Write};
 use std::num::NonZeroU32;
 use tiger_pkg::{package_manager, TagHash};
 
+use crate::tag_data;
 use crate::texture::{cache::TextureCache, Texture};
+use crate::watchlist;
 
-use super::TOASTS;
+use super::{tag_clipboard, TOASTS};
 
 lazy_static! {
     static ref CF_PNG: NonZeroU32 = clipboard_win::register_format("PNG").unwrap();
     static ref CF_FILENAME: NonZeroU32 = clipboard_win::register_format("FileNameW").unwrap();
+
+    /// Files on disk that tags were last exported to
+    static ref EXPORTED_FILES: Mutex<FxHashMap<TagHash, PathBuf>> = Mutex::new(Default::default());
+    /// Exported file that should be opened in the external file view on the next frame
+    static ref PENDING_EXTERNAL_SCAN: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// String containers that should be added to the watch list on the next frame
+    static ref PENDING_CONTAINER_WATCHES: Mutex<Vec<TagHash>> = Mutex::new(vec![]);
+    /// Tags that should be added to the watch list on the next frame
+    static ref PENDING_TAG_WATCHES: Mutex<Vec<TagHash>> = Mutex::new(vec![]);
+    /// Textures that should be opened in the export dialog on the next frame
+    static ref PENDING_TEXTURE_EXPORT: Mutex<Option<Vec<TagHash>>> = Mutex::new(None);
+}
+
+/// Remembers where a tag was exported to, so the exported copy can be scanned again later
+pub fn register_exported_file(tag: TagHash, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    EXPORTED_FILES.lock().insert(tag, path);
+}
+
+/// File that a tag was last exported to
+pub fn exported_file(tag: TagHash) -> Option<PathBuf> {
+    EXPORTED_FILES.lock().get(&tag).cloned()
+}
+
+/// Takes the exported file that was requested to be opened in the external file view, if any
+pub fn take_pending_external_scan() -> Option<PathBuf> {
+    PENDING_EXTERNAL_SCAN.lock().take()
+}
+
+/// Takes the string containers that were requested to be watched
+pub fn take_pending_container_watches() -> Vec<TagHash> {
+    std::mem::take(&mut PENDING_CONTAINER_WATCHES.lock())
+}
+
+/// Takes the tags that were requested to be watched
+pub fn take_pending_tag_watches() -> Vec<TagHash> {
+    std::mem::take(&mut PENDING_TAG_WATCHES.lock())
+}
+
+/// Opens the texture export dialog for the given textures on the next frame
+pub fn request_texture_export(tags: Vec<TagHash>) {
+    *PENDING_TEXTURE_EXPORT.lock() = Some(tags);
+}
+
+/// Takes the textures that were requested to be exported, if any
+pub fn take_pending_texture_export() -> Option<Vec<TagHash>> {
+    PENDING_TEXTURE_EXPORT.lock().take()
 }
 
 pub trait ResponseExt {
@@ -84,32 +137,11 @@ impl ResponseExt for egui::Response {
                 }
 
                 if ui
-                    .selectable_label(false, "📷 Save texture")
-                    .on_hover_text("Texture(s) will be saved to the textures/ directory")
+                    .selectable_label(false, "📷 Export texture...")
+                    .on_hover_text("Export as DDS, PNG or TGA")
                     .clicked()
                 {
-                    match Texture::load(&texture_cache.render_state, tag, false) {
-                        Ok(o) => {
-                            std::fs::create_dir_all("textures/").unwrap();
-                            let mut images = vec![];
-                            for layer in 0..(o.desc.array_size.max(o.desc.depth)) {
-                                let image = o.to_image(&texture_cache.render_state, layer).unwrap();
-                                image.save(format!("textures/{tag}_{layer}.png")).unwrap();
-                                images.push(image);
-                            }
-
-                            if images.len() == 6 {
-                                let cubemap_image = assemble_cubemap(images);
-                                cubemap_image
-                                    .save(format!("textures/{tag}_cubemap.png"))
-                                    .unwrap();
-                            }
-                            TOASTS.lock().success("Texture saved");
-                        }
-                        Err(e) => {
-                            error!("Failed to load texture: {e}");
-                        }
-                    }
+                    request_texture_export(vec![tag]);
                     ui.close_menu();
                 }
             }
@@ -221,6 +253,15 @@ pub fn tag_context(ui: &mut egui::Ui, tag: TagHash) {
         ui.close_menu();
     }
 
+    if ui
+        .selectable_label(false, "📋 Copy tag with annotation")
+        .on_hover_text("Can be pasted into another quicktag instance with Ctrl+V")
+        .clicked()
+    {
+        tag_clipboard::copy_tag(ui, tag);
+        ui.close_menu();
+    }
+
     if let Some(tag64) = package_manager().get_tag64_for_tag32(tag) {
         if ui.selectable_label(false, format!("📋 Copy 64-bit tag{flipped_postfix}")).clicked() {
             ui.output_mut(|o| {
@@ -254,6 +295,41 @@ pub fn tag_context(ui: &mut egui::Ui, tag: TagHash) {
             open_audio_file_in_default_application(tag, "wem");
             ui.close_menu();
         }
+
+        if watchlist::is_string_container(entry.reference) {
+            if watchlist::with(|w| w.is_container_watched(tag)) {
+                if ui
+                    .selectable_label(false, "👁 Stop watching string container")
+                    .clicked()
+                {
+                    watchlist::with_mut(|w| w.string_containers.retain(|c| c.tag != tag.0));
+                    ui.close_menu();
+                }
+            } else if ui
+                .selectable_label(false, "👁 Watch string container")
+                .on_hover_text("Get notified when strings in this container change")
+                .clicked()
+            {
+                PENDING_CONTAINER_WATCHES.lock().push(tag);
+                ui.close_menu();
+            }
+        }
+    }
+
+    if watchlist::with(|w| w.is_tag_watched(tag)) {
+        if ui.selectable_label(false, "👁 Stop watching tag").clicked() {
+            watchlist::with_mut(|w| w.tags.retain(|t| t.tag != tag.0));
+            ui.close_menu();
+        }
+    } else if ui
+        .selectable_label(false, "👁 Watch tag")
+        .on_hover_text(
+            "Get notified when this tag's data or references change after a cache rebuild",
+        )
+        .clicked()
+    {
+        PENDING_TAG_WATCHES.lock().push(tag);
+        ui.close_menu();
     }
 
     if ui
@@ -271,10 +347,24 @@ pub fn tag_context(ui: &mut egui::Ui, tag: TagHash) {
         open_tag_in_default_application(tag);
         ui.close_menu();
     }
+
+    if let Some(path) = exported_file(tag) {
+        if ui
+            .selectable_label(false, "📥 Open exported copy")
+            .on_hover_text(format!(
+                "Scans {} from disk, including any changes made to it by other tools",
+                path.display()
+            ))
+            .clicked()
+        {
+            *PENDING_EXTERNAL_SCAN.lock() = Some(path);
+            ui.close_menu();
+        }
+    }
 }
 
 pub fn open_tag_in_default_application(tag: TagHash) {
-    let data = package_manager().read_tag(tag).unwrap();
+    let data = tag_data::read_tag(tag).unwrap();
     let entry = package_manager().get_entry(tag).unwrap();
 
     let filename = format!(
@@ -285,7 +375,9 @@ pub fn open_tag_in_default_application(tag: TagHash) {
     );
 
     let path = std::env::temp_dir().join(filename);
-    std::fs::write(&path, data).ok();
+    if std::fs::write(&path, data).is_ok() {
+        register_exported_file(tag, &path);
+    }
 
     opener::open(path).ok();
 }
@@ -379,30 +471,3 @@ pub fn dump_wwise_info(package_id: u16) {
         info!("dump_wwise_info: Done");
     });
 }
-
-#[allow(clippy::erasing_op)]
-fn assemble_cubemap(images: Vec<DynamicImage>) -> DynamicImage {
-    let tile_w = images[0].width();
-    let tile_h = images[0].height();
-
-    let mut cubemap = DynamicImage::new_rgba8(tile_w * 4, tile_h * 3);
-
-    let x_pos = images[0].rotate90();
-    let x_neg = images[1].rotate270();
-    let y_pos = images[2].rotate180();
-    let y_neg = images[3].clone();
-    let z_pos = images[4].rotate90();
-    let z_neg = images[5].rotate90();
-
-    // -- Z+ -- --
-    // Y- X+ Y+ X-
-    // -- Z- -- --
-    let _ = cubemap.copy_from(&z_pos, tile_w, tile_h * 0);
-    let _ = cubemap.copy_from(&y_neg, tile_w * 0, tile_h);
-    let _ = cubemap.copy_from(&x_pos, tile_w, tile_h);
-    let _ = cubemap.copy_from(&y_pos, tile_w * 2, tile_h);
-    let _ = cubemap.copy_from(&x_neg, tile_w * 3, tile_h);
-    let _ = cubemap.copy_from(&z_neg, tile_w, tile_h * 2);
-
-    cubemap
-}
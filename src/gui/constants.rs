@@ -0,0 +1,454 @@
+//! Values that many tags of a class share at the same offset, with a guess at what they are. Constant fields point at
+//! enums, shared IDs and hashes, values that show up at several offsets are likely IDs that tie fields together
+//!
+//! Values are counted with a small heavy hitters summary per offset (Misra-Gries), so the counts are a lower bound
+//! that can be off by up to [`MAX_COUNT_ERROR`] of the tags. Offsets where every tag has the same value are exact.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use eframe::egui::{self, RichText};
+use egui_extras::{Column, TableBuilder};
+use itertools::Itertools;
+use poll_promise::Promise;
+use quicktag_core::classes::get_class_by_id;
+use quicktag_scanner::context::ScannerContext;
+use quicktag_scanner::hashes::{is_hash_candidate, FNV1_EMPTY};
+use quicktag_scanner::namespaces::hash_namespaces;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::StringCache;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, Endian, TagHash};
+
+use super::{View, ViewAction};
+use crate::crash_context;
+
+/// Offsets past this are ignored, constants are mostly in the fixed part of a tag
+const MAX_OFFSET: usize = 0x1000;
+/// Counters kept per offset. Values in more than 1/(n+1) of the tags are always found
+const SUMMARY_SIZE: usize = 16;
+/// Largest fraction of the tags that a count can be short by
+pub const MAX_COUNT_ERROR: f32 = 1.0 / (SUMMARY_SIZE as f32 + 1.0);
+
+/// Misra-Gries summary of the values at one offset
+#[derive(Default, Clone)]
+struct ValueSummary {
+    counters: Vec<(u32, u32)>,
+    /// Tags that are large enough to have this offset
+    tags: u32,
+}
+
+impl ValueSummary {
+    fn add(&mut self, value: u32) {
+        self.tags += 1;
+        if let Some((_, count)) = self.counters.iter_mut().find(|(v, _)| *v == value) {
+            *count += 1;
+        } else if self.counters.len() < SUMMARY_SIZE {
+            self.counters.push((value, 1));
+        } else {
+            for (_, count) in &mut self.counters {
+                *count -= 1;
+            }
+            self.counters.retain(|(_, count)| *count > 0);
+        }
+    }
+
+    fn merge(mut self, other: ValueSummary) -> ValueSummary {
+        self.tags += other.tags;
+        for (value, count) in other.counters {
+            match self.counters.iter_mut().find(|(v, _)| *v == value) {
+                Some((_, c)) => *c += count,
+                None => self.counters.push((value, count)),
+            }
+        }
+
+        if self.counters.len() > SUMMARY_SIZE {
+            self.counters
+                .sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            let cutoff = self.counters[SUMMARY_SIZE].1;
+            self.counters.truncate(SUMMARY_SIZE);
+            for (_, count) in &mut self.counters {
+                *count -= cutoff;
+            }
+            self.counters.retain(|(_, count)| *count > 0);
+        }
+
+        self
+    }
+}
+
+fn merge_summaries(mut a: Vec<ValueSummary>, b: Vec<ValueSummary>) -> Vec<ValueSummary> {
+    if a.len() < b.len() {
+        a.resize(b.len(), ValueSummary::default());
+    }
+    for (i, summary) in b.into_iter().enumerate() {
+        a[i] = std::mem::take(&mut a[i]).merge(summary);
+    }
+    a
+}
+
+pub struct SharedConstant {
+    pub offset: usize,
+    pub value: u32,
+    /// Tags that have this value at the offset (lower bound)
+    pub count: u32,
+    /// Tags that are large enough to have the offset
+    pub tags: u32,
+    pub meaning: String,
+    /// Other offsets in the report with the same value
+    pub also_at: Vec<usize>,
+}
+
+#[derive(Default)]
+pub struct ConstantsProgress {
+    pub current: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancel: AtomicBool,
+}
+
+pub struct ConstantsReport {
+    class: u32,
+    tags: usize,
+    constants: Vec<SharedConstant>,
+}
+
+/// Best guess at what a value is
+pub fn guess_meaning(value: u32, context: &ScannerContext, strings: &StringCache) -> String {
+    match value {
+        0 => return "Zero".to_string(),
+        u32::MAX => return "-1 (none/invalid index?)".to_string(),
+        FNV1_EMPTY => return "Hash of an empty string".to_string(),
+        _ => {}
+    }
+
+    if let Some(s) = strings.get(&value).and_then(|s| s.first()) {
+        return format!("String hash '{s}'");
+    }
+    for namespace in hash_namespaces() {
+        if let Some(name) = namespace.names.get(&value).and_then(|n| n.first()) {
+            return format!("{} hash '{name}'", namespace.name);
+        }
+    }
+    if context.known_wordlist_hashes.binary_search(&value).is_ok() {
+        return "Wordlist hash".to_string();
+    }
+    if let Some(entry) = package_manager().get_entry(TagHash(value)) {
+        let class = get_class_by_id(entry.reference)
+            .map(|c| c.name.to_string())
+            .unwrap_or_else(|| format!("{:08X}", entry.reference));
+        return format!("Tag {} ({class})", TagHash(value));
+    }
+    if let Some(class) = get_class_by_id(value) {
+        return format!("Class {}", class.name);
+    }
+
+    if value < 0x100 {
+        return "Small integer (enum or index?)".to_string();
+    }
+    if value.is_power_of_two() {
+        return format!("Single bit {} (flag?)", value.trailing_zeros());
+    }
+    if value < 0x10000 {
+        return "Integer (count or index?)".to_string();
+    }
+
+    let float = f32::from_bits(value);
+    if float.is_finite() && (1e-4..=1e6).contains(&float.abs()) {
+        return format!("Float {float}");
+    }
+
+    let (high, low) = (value >> 16, value & 0xFFFF);
+    if high < 0x1000 && low < 0x1000 {
+        return format!("Packed 16-bit pair? ({high}, {low})");
+    }
+    if is_hash_candidate(value) {
+        return "Unknown hash?".to_string();
+    }
+
+    "Unknown".to_string()
+}
+
+/// Finds values that at least `min_share` of the tags of a class have at the same offset
+pub fn find_shared_constants(
+    context: &ScannerContext,
+    strings: &StringCache,
+    cache: &TagCache,
+    class: u32,
+    min_share: f32,
+    ignore_zero: bool,
+    progress: &ConstantsProgress,
+) -> ConstantsReport {
+    let _context = crash_context::push(
+        crash_context::JOB,
+        format!("Finding constants in {class:08X}"),
+    );
+    let tags = cache
+        .hashes
+        .iter()
+        .map(|(tag, _)| *tag)
+        .filter(|tag| {
+            package_manager()
+                .get_entry(*tag)
+                .is_some_and(|e| e.reference == class)
+        })
+        .collect_vec();
+
+    progress.total.store(tags.len(), Ordering::Relaxed);
+    progress.current.store(0, Ordering::Relaxed);
+
+    let endian = context.endian;
+    let summaries = tags
+        .par_iter()
+        .fold(Vec::<ValueSummary>::new, |mut summaries, &tag| {
+            if progress.cancel.load(Ordering::Relaxed) {
+                return summaries;
+            }
+
+            progress.current.fetch_add(1, Ordering::Relaxed);
+            let Ok(data) = package_manager().read_tag(tag) else {
+                return summaries;
+            };
+
+            let fields = data.len().min(MAX_OFFSET) / 4;
+            if summaries.len() < fields {
+                summaries.resize(fields, ValueSummary::default());
+            }
+            for (i, chunk) in data[..fields * 4].chunks_exact(4).enumerate() {
+                let bytes: [u8; 4] = chunk.try_into().unwrap();
+                summaries[i].add(match endian {
+                    Endian::Big => u32::from_be_bytes(bytes),
+                    Endian::Little => u32::from_le_bytes(bytes),
+                });
+            }
+
+            summaries
+        })
+        .reduce(Vec::new, merge_summaries);
+
+    let mut constants = vec![];
+    for (i, summary) in summaries.iter().enumerate() {
+        for &(value, count) in &summary.counters {
+            if (ignore_zero && value == 0) || (count as f32) < min_share * summary.tags as f32 {
+                continue;
+            }
+
+            constants.push(SharedConstant {
+                offset: i * 4,
+                value,
+                count,
+                tags: summary.tags,
+                meaning: guess_meaning(value, context, strings),
+                also_at: vec![],
+            });
+        }
+    }
+
+    let mut offsets_by_value: FxHashMap<u32, Vec<usize>> = FxHashMap::default();
+    for constant in &constants {
+        offsets_by_value
+            .entry(constant.value)
+            .or_default()
+            .push(constant.offset);
+    }
+    for constant in &mut constants {
+        constant.also_at = offsets_by_value[&constant.value]
+            .iter()
+            .copied()
+            .filter(|o| *o != constant.offset)
+            .collect();
+    }
+
+    ConstantsReport {
+        class,
+        tags: tags.len(),
+        constants,
+    }
+}
+
+pub struct ConstantsView {
+    context: Arc<ScannerContext>,
+    cache: Arc<TagCache>,
+    strings: Arc<StringCache>,
+    class_input: String,
+    /// Percentage of the tags that need to share a value
+    min_share: f32,
+    ignore_zero: bool,
+    progress: Arc<ConstantsProgress>,
+    report: Option<Promise<ConstantsReport>>,
+}
+
+impl ConstantsView {
+    pub fn new(
+        context: Arc<ScannerContext>,
+        cache: Arc<TagCache>,
+        strings: Arc<StringCache>,
+    ) -> Self {
+        Self {
+            context,
+            cache,
+            strings,
+            class_input: String::new(),
+            min_share: 50.0,
+            ignore_zero: true,
+            progress: Default::default(),
+            report: None,
+        }
+    }
+
+    pub fn set_strings(&mut self, strings: Arc<StringCache>) {
+        self.strings = strings;
+    }
+
+    fn start(&mut self, class: u32) {
+        self.progress = Default::default();
+        let progress = self.progress.clone();
+        let context = self.context.clone();
+        let cache = self.cache.clone();
+        let strings = self.strings.clone();
+        let min_share = self.min_share / 100.0;
+        let ignore_zero = self.ignore_zero;
+        self.report = Some(Promise::spawn_thread("find_shared_constants", move || {
+            find_shared_constants(
+                &context,
+                &strings,
+                &cache,
+                class,
+                min_share,
+                ignore_zero,
+                &progress,
+            )
+        }));
+    }
+}
+
+impl View for ConstantsView {
+    fn view(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let is_running = self.report.as_ref().is_some_and(|p| p.ready().is_none());
+        let class = u32::from_str_radix(self.class_input.trim(), 16).ok();
+
+        ui.horizontal(|ui| {
+            ui.label("Class");
+            ui.add(egui::TextEdit::singleline(&mut self.class_input).desired_width(80.0))
+                .on_hover_text("Class reference of the tags, in hex");
+            if let Some(class) = class.and_then(get_class_by_id) {
+                ui.weak(class.name.to_string());
+            }
+            ui.add(
+                egui::Slider::new(&mut self.min_share, 10.0..=100.0)
+                    .suffix("%")
+                    .text("of the tags"),
+            );
+            ui.checkbox(&mut self.ignore_zero, "Ignore zeroes");
+
+            if ui
+                .add_enabled(
+                    !is_running && class.is_some(),
+                    egui::Button::new("Find constants"),
+                )
+                .on_hover_text("Reads every tag of the class, so this can take a while")
+                .on_disabled_hover_text("Enter a class in hex")
+                .clicked()
+            {
+                if let Some(class) = class {
+                    self.start(class);
+                }
+            }
+
+            if is_running && ui.button("Cancel").clicked() {
+                self.progress.cancel.store(true, Ordering::Relaxed);
+            }
+        });
+        ui.weak(format!(
+            "Counts are a lower bound and can be short by up to {:.0}% of the tags. Click an offset for its histogram",
+            MAX_COUNT_ERROR * 100.0
+        ));
+        ui.separator();
+
+        let Some(report) = self.report.as_ref() else {
+            ui.label(RichText::new("No constants searched yet").italics());
+            return None;
+        };
+
+        let Some(report) = report.ready() else {
+            let current = self.progress.current.load(Ordering::Relaxed);
+            let total = self.progress.total.load(Ordering::Relaxed).max(1);
+            ui.add(
+                egui::ProgressBar::new(current as f32 / total as f32)
+                    .animate(true)
+                    .text(format!("Reading tags {current}/{total}")),
+            );
+            ctx.request_repaint();
+            return None;
+        };
+
+        ui.label(format!(
+            "{} shared values in {} tags{}",
+            report.constants.len(),
+            report.tags,
+            if self.progress.cancel.load(Ordering::Relaxed) {
+                " (cancelled, results are incomplete)"
+            } else {
+                ""
+            }
+        ));
+
+        let mut result = None;
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto().at_least(64.0))
+            .column(Column::auto().at_least(80.0))
+            .column(Column::auto().at_least(96.0))
+            .column(Column::remainder().at_least(192.0))
+            .column(Column::auto().at_least(96.0))
+            .min_scrolled_height(0.0)
+            .header(20.0, |mut header| {
+                for title in ["Offset", "Value", "Tags", "Meaning", "Also at"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, report.constants.len(), |mut row| {
+                    let constant = &report.constants[row.index()];
+                    row.col(|ui| {
+                        if ui
+                            .link(RichText::new(format!("0x{:X}", constant.offset)).monospace())
+                            .on_hover_text("Show the histogram of this field")
+                            .clicked()
+                        {
+                            result = Some(ViewAction::FieldHistogram {
+                                class: report.class,
+                                offset: constant.offset,
+                            });
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.monospace(format!("{:08X}", constant.value));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!(
+                            "{} ({:.0}%)",
+                            constant.count,
+                            constant.count as f32 / constant.tags.max(1) as f32 * 100.0
+                        ));
+                    });
+                    row.col(|ui| {
+                        ui.label(&constant.meaning);
+                    });
+                    row.col(|ui| {
+                        ui.label(
+                            constant
+                                .also_at
+                                .iter()
+                                .map(|o| format!("0x{o:X}"))
+                                .join(", "),
+                        );
+                    });
+                });
+            });
+
+        result
+    }
+}
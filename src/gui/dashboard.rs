@@ -0,0 +1,187 @@
+//! Pinned saved queries, each shown as a match count and a preview of the first matches. Queries are refreshed
+//! whenever a cache is loaded
+
+use std::sync::Arc;
+
+use eframe::egui::{self, RichText};
+use poll_promise::Promise;
+use quicktag_scanner::TagCache;
+
+use super::{
+    common::ResponseExt,
+    tag_search::{search_tags, EntropyCache, TagSearchResult},
+    View, ViewAction,
+};
+use crate::saved_queries::{self, SavedQuery};
+
+/// Amount of matches listed for every query
+const PREVIEW_COUNT: usize = 10;
+const CARD_WIDTH: f32 = 320.0;
+
+struct DashboardCard {
+    query: SavedQuery,
+    results: Promise<Vec<TagSearchResult>>,
+}
+
+pub struct DashboardView {
+    cache: Arc<TagCache>,
+    entropy_cache: EntropyCache,
+    /// One card for every pinned query, in the order they were saved
+    cards: Vec<DashboardCard>,
+}
+
+impl DashboardView {
+    pub fn new() -> Self {
+        Self {
+            cache: Default::default(),
+            entropy_cache: Default::default(),
+            cards: vec![],
+        }
+    }
+
+    /// Runs every query again against the new cache
+    pub fn set_cache(&mut self, cache: Arc<TagCache>) {
+        self.cache = cache;
+        self.cards.clear();
+    }
+
+    fn run(&self, query: SavedQuery) -> DashboardCard {
+        let filter = query.filter.clone();
+        let cache = self.cache.clone();
+        let entropy_cache = self.entropy_cache.clone();
+        DashboardCard {
+            query,
+            results: Promise::spawn_thread("dashboard_query", move || {
+                search_tags(&filter, &cache, &entropy_cache)
+            }),
+        }
+    }
+
+    /// Runs queries that were pinned or changed since the last frame, keeping the results of the others
+    fn sync_cards(&mut self) {
+        let pinned = saved_queries::with(|q| q.pinned());
+        if pinned.len() == self.cards.len()
+            && pinned.iter().zip(&self.cards).all(|(q, c)| *q == c.query)
+        {
+            return;
+        }
+
+        let mut old = std::mem::take(&mut self.cards);
+        let cards = pinned
+            .into_iter()
+            .map(|query| match old.iter().position(|c| c.query == query) {
+                Some(i) => old.swap_remove(i),
+                None => self.run(query),
+            })
+            .collect();
+        self.cards = cards;
+    }
+
+    fn card_ui(
+        ui: &mut egui::Ui,
+        card: &DashboardCard,
+        refresh: &mut bool,
+        action: &mut Option<ViewAction>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.heading(&card.query.name);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("📌").on_hover_text("Unpin").clicked() {
+                    saved_queries::with_mut(|q| q.set_pinned(&card.query.name, false));
+                }
+
+                if ui
+                    .small_button("🔍")
+                    .on_hover_text("Open in the search tab")
+                    .clicked()
+                {
+                    *action = Some(ViewAction::Search(card.query.filter.clone()));
+                }
+
+                if ui.small_button("⟳").on_hover_text("Refresh").clicked() {
+                    *refresh = true;
+                }
+            });
+        });
+
+        let Some(results) = card.results.ready() else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Searching...");
+            });
+            return;
+        };
+
+        ui.label(RichText::new(format!("{} tags", results.len())).size(18.0));
+        for r in results.iter().take(PREVIEW_COUNT) {
+            let label = match &r.name {
+                Some(name) => format!("{} {name}", r.tag),
+                None => r.tag.to_string(),
+            };
+            if ui
+                .selectable_label(
+                    false,
+                    RichText::new(label).color(r.tag_type.display_color()),
+                )
+                .tag_context(r.tag)
+                .clicked()
+            {
+                *action = Some(ViewAction::OpenTag(r.tag));
+            }
+        }
+
+        if results.len() > PREVIEW_COUNT {
+            ui.weak(format!("and {} more", results.len() - PREVIEW_COUNT));
+        }
+    }
+}
+
+impl View for DashboardView {
+    fn view(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        self.sync_cards();
+        if self.cards.is_empty() {
+            ui.label(
+                RichText::new(
+                    "No pinned queries. Save a query in the Search tab and pin it (📌) to show it here",
+                )
+                .italics(),
+            );
+            return None;
+        }
+
+        let mut refresh_all = ui.button("Refresh all").clicked();
+        ui.separator();
+
+        let mut action = None;
+        let mut refresh = vec![false; self.cards.len()];
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for (card, refresh) in self.cards.iter().zip(&mut refresh) {
+                        ui.allocate_ui(egui::vec2(CARD_WIDTH, 0.0), |ui| {
+                            ui.group(|ui| {
+                                ui.set_width(CARD_WIDTH);
+                                ui.vertical(|ui| Self::card_ui(ui, card, refresh, &mut action));
+                            });
+                        });
+                    }
+                });
+            });
+
+        if self.cards.iter().any(|c| c.results.ready().is_none()) {
+            ctx.request_repaint();
+            // Refreshing while a query is still running would only run it twice
+            refresh_all = false;
+        }
+
+        for (i, refresh) in refresh.into_iter().enumerate() {
+            if refresh_all || refresh {
+                let query = self.cards[i].query.clone();
+                self.cards[i] = self.run(query);
+            }
+        }
+
+        action
+    }
+}
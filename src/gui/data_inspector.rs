@@ -0,0 +1,178 @@
+//! Data inspector next to the hex view, shows the bytes at the cursor as every common type at once, in both byte
+//! orders
+
+use eframe::egui::{self, RichText};
+use quicktag_scanner::namespaces::hash_namespaces;
+use quicktag_strings::localized::StringCache;
+use tiger_pkg::{package_manager, Endian, TagHash};
+
+use crate::gui::tag::format_tag_entry;
+
+/// Byte orders shown side by side, one column each
+const ENDIANS: [Endian; 2] = [Endian::Little, Endian::Big];
+
+/// Reads `N` bytes at `offset` as a big endian array, so every type can be built with `from_be_bytes`
+fn read<const N: usize>(data: &[u8], offset: usize, endian: Endian) -> Option<[u8; N]> {
+    let mut bytes: [u8; N] = data.get(offset..offset + N)?.try_into().ok()?;
+    if endian == Endian::Little {
+        bytes.reverse();
+    }
+    Some(bytes)
+}
+
+fn endian_name(endian: Endian) -> &'static str {
+    match endian {
+        Endian::Little => "Little endian",
+        Endian::Big => "Big endian",
+    }
+}
+
+fn tag_cell(ui: &mut egui::Ui, tag: Option<TagHash>) -> Option<TagHash> {
+    let entry = tag.and_then(|t| Some((t, package_manager().get_entry(t)?)));
+    match entry {
+        Some((tag, entry)) => ui
+            .link(format_tag_entry(tag, Some(&entry)))
+            .on_hover_text("Open tag")
+            .clicked()
+            .then_some(tag),
+        None => {
+            ui.weak("not a tag");
+            None
+        }
+    }
+}
+
+/// Draws the values at `offset`, with a column for each byte order. The column of `endian`, the byte order of the
+/// game, is highlighted. Returns a tag to open when one of the tag hashes was clicked
+pub fn inspector_ui(
+    ui: &mut egui::Ui,
+    data: &[u8],
+    offset: usize,
+    endian: Endian,
+    strings: &StringCache,
+) -> Option<TagHash> {
+    ui.strong(format!("Offset 0x{offset:X}"));
+    ui.separator();
+
+    let mut open_tag = None;
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        egui::Grid::new("data_inspector")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                for e in ENDIANS {
+                    if e == endian {
+                        ui.strong(endian_name(e))
+                            .on_hover_text("Byte order of the game");
+                    } else {
+                        ui.weak(endian_name(e));
+                    }
+                }
+                ui.end_row();
+
+                let mut row = |label: &str, value: &dyn Fn(Endian) -> Option<String>| {
+                    ui.label(label);
+                    for e in ENDIANS {
+                        match value(e) {
+                            Some(value) => ui.monospace(value),
+                            None => ui.weak("-"),
+                        };
+                    }
+                    ui.end_row();
+                };
+
+                row("i8", &|e| {
+                    read::<1>(data, offset, e).map(|b| i8::from_be_bytes(b).to_string())
+                });
+                row("u8", &|e| {
+                    read::<1>(data, offset, e).map(|b| format!("{} (0x{:02X})", b[0], b[0]))
+                });
+                row("i16", &|e| {
+                    read::<2>(data, offset, e).map(|b| i16::from_be_bytes(b).to_string())
+                });
+                row("u16", &|e| {
+                    read::<2>(data, offset, e).map(|b| u16::from_be_bytes(b).to_string())
+                });
+                row("i32", &|e| {
+                    read::<4>(data, offset, e).map(|b| i32::from_be_bytes(b).to_string())
+                });
+                row("u32", &|e| {
+                    read::<4>(data, offset, e).map(|b| u32::from_be_bytes(b).to_string())
+                });
+                row("i64", &|e| {
+                    read::<8>(data, offset, e).map(|b| i64::from_be_bytes(b).to_string())
+                });
+                row("u64", &|e| {
+                    read::<8>(data, offset, e).map(|b| u64::from_be_bytes(b).to_string())
+                });
+                row("f32", &|e| {
+                    read::<4>(data, offset, e).map(|b| f32::from_be_bytes(b).to_string())
+                });
+                row("f64", &|e| {
+                    read::<8>(data, offset, e).map(|b| f64::from_be_bytes(b).to_string())
+                });
+                row("binary", &|e| {
+                    read::<4>(data, offset, e).map(|b| format!("{:032b}", u32::from_be_bytes(b)))
+                });
+
+                let value32 = ENDIANS.map(|e| read::<4>(data, offset, e).map(u32::from_be_bytes));
+                let tag64 = ENDIANS.map(|e| {
+                    let value = u64::from_be_bytes(read::<8>(data, offset, e)?);
+                    package_manager()
+                        .lookup
+                        .tag64_entries
+                        .get(&value)
+                        .map(|entry| entry.hash32)
+                });
+
+                ui.label("TagHash");
+                for value in value32 {
+                    open_tag = open_tag.or(tag_cell(ui, value.map(TagHash)));
+                }
+                ui.end_row();
+
+                ui.label("TagHash64");
+                for tag in tag64 {
+                    open_tag = open_tag.or(tag_cell(ui, tag));
+                }
+                ui.end_row();
+
+                ui.label("String hash");
+                for value in value32 {
+                    match value.and_then(|v| strings.get(&v)) {
+                        Some(s) if s.len() > 1 => {
+                            ui.label(format!("'{}' (+{} more)", s[0], s.len() - 1))
+                        }
+                        Some(s) => ui.label(format!("'{}'", s[0])),
+                        None => ui.weak("unknown"),
+                    };
+                }
+                ui.end_row();
+
+                for namespace in hash_namespaces() {
+                    let names = value32.map(|v| v.and_then(|v| namespace.names.get(&v)));
+                    if names.iter().all(Option::is_none) {
+                        continue;
+                    }
+                    ui.label(&namespace.name);
+                    for names in names {
+                        match names {
+                            Some(names) => ui.label(names.join(", ")),
+                            None => ui.weak("-"),
+                        };
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+
+    ui.add_space(4.0);
+    ui.label(
+        RichText::new("Move the cursor with the arrow keys, or click a value")
+            .weak()
+            .small(),
+    );
+
+    open_tag
+}
@@ -0,0 +1,171 @@
+use eframe::egui::{self, Color32, RichText};
+use itertools::Itertools;
+use quicktag_core::palette::PaletteColor;
+use quicktag_core::tagtypes::{get_custom_tagtype, TagType};
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{common::ResponseExt, View, ViewAction};
+use crate::config::{self, TagTypeMapping};
+
+const MAX_EXAMPLES: usize = 8;
+
+struct UnknownTagType {
+    ftype: u8,
+    fsubtype: u8,
+    count: usize,
+    examples: Vec<TagHash>,
+
+    /// Name/color being edited for the user-defined mapping
+    name: String,
+    color: Color32,
+}
+
+pub struct DiagnosticsView {
+    unknown_types: Vec<UnknownTagType>,
+}
+
+impl DiagnosticsView {
+    pub fn new() -> Self {
+        let mut combos: FxHashMap<(u8, u8), (usize, Vec<TagHash>)> = Default::default();
+        for (pkg_id, entries) in package_manager().lookup.tag32_entries_by_pkg.iter() {
+            for (i, e) in entries.iter().enumerate() {
+                if !matches!(
+                    TagType::from_type_subtype(e.file_type, e.file_subtype),
+                    TagType::Unknown { .. }
+                ) {
+                    continue;
+                }
+
+                let (count, examples) = combos.entry((e.file_type, e.file_subtype)).or_default();
+                *count += 1;
+                if examples.len() < MAX_EXAMPLES {
+                    examples.push(TagHash::new(*pkg_id, i as u16));
+                }
+            }
+        }
+
+        let unknown_types = combos
+            .into_iter()
+            .map(|((ftype, fsubtype), (count, examples))| {
+                let custom = get_custom_tagtype(ftype, fsubtype);
+                UnknownTagType {
+                    ftype,
+                    fsubtype,
+                    count,
+                    examples,
+                    name: custom.as_ref().map(|c| c.name.clone()).unwrap_or_default(),
+                    color: custom
+                        .map(|c| Color32::from_rgb(c.color[0], c.color[1], c.color[2]))
+                        .unwrap_or_else(|| PaletteColor::UnknownType.color()),
+                }
+            })
+            .sorted_by_key(|u| std::cmp::Reverse(u.count))
+            .collect();
+
+        Self { unknown_types }
+    }
+}
+
+impl View for DiagnosticsView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        ui.heading("Unknown type/subtype combinations");
+        ui.label(
+            RichText::new(
+                "Entry types that quicktag doesn't recognize. Names and colors assigned here are saved to the config file.",
+            )
+            .italics(),
+        );
+        ui.separator();
+
+        if self.unknown_types.is_empty() {
+            ui.label(RichText::new("No unknown type/subtype combinations found").italics());
+            return None;
+        }
+
+        let mut result = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("unknown_tagtypes_grid")
+                    .striped(true)
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        ui.strong("Type+Subtype");
+                        ui.strong("Count");
+                        ui.strong("Mapping");
+                        ui.strong("Examples");
+                        ui.end_row();
+
+                        for u in &mut self.unknown_types {
+                            let tagtype = TagType::Unknown {
+                                ftype: u.ftype,
+                                fsubtype: u.fsubtype,
+                            };
+                            ui.label(
+                                RichText::new(format!("{}+{} ({tagtype})", u.ftype, u.fsubtype))
+                                    .color(tagtype.display_color()),
+                            );
+                            ui.label(u.count.to_string());
+
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut u.name)
+                                        .hint_text("Name")
+                                        .desired_width(160.0),
+                                );
+                                ui.color_edit_button_srgba(&mut u.color);
+
+                                if ui
+                                    .add_enabled(
+                                        !u.name.trim().is_empty(),
+                                        egui::Button::new("Save"),
+                                    )
+                                    .clicked()
+                                {
+                                    let mapping = TagTypeMapping {
+                                        ftype: u.ftype,
+                                        fsubtype: u.fsubtype,
+                                        name: u.name.trim().to_string(),
+                                        color: [u.color.r(), u.color.g(), u.color.b()],
+                                    };
+                                    config::with_mut(|c| {
+                                        c.tag_types.retain(|m| {
+                                            (m.ftype, m.fsubtype) != (u.ftype, u.fsubtype)
+                                        });
+                                        c.tag_types.push(mapping);
+                                    });
+                                }
+
+                                if get_custom_tagtype(u.ftype, u.fsubtype).is_some()
+                                    && ui.button("Remove").clicked()
+                                {
+                                    config::with_mut(|c| {
+                                        c.tag_types.retain(|m| {
+                                            (m.ftype, m.fsubtype) != (u.ftype, u.fsubtype)
+                                        });
+                                    });
+                                    u.name.clear();
+                                    u.color = PaletteColor::UnknownType.color();
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                for &tag in &u.examples {
+                                    if ui
+                                        .selectable_label(false, tag.to_string())
+                                        .tag_context(tag)
+                                        .clicked()
+                                    {
+                                        result = Some(ViewAction::OpenTag(tag));
+                                    }
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        result
+    }
+}
@@ -0,0 +1,339 @@
+use std::sync::Arc;
+
+use eframe::egui::{self, collapsing_header::CollapsingState, Color32, RichText};
+use itertools::Itertools;
+use log::error;
+use poll_promise::Promise;
+use quicktag_core::classes::get_class_by_id;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::StringCache;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde_json::json;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{
+    common::{open_audio_file_in_default_application, ResponseExt},
+    voice_lines::{find_voice_lines, VoiceLine},
+    View, ViewAction, TOASTS,
+};
+
+/// Maximum distance (in bytes) between a line's audio reference and the speaker string hash
+const MAX_SPEAKER_DISTANCE: u64 = 0x40;
+
+pub struct DialogueLine {
+    pub offset: u64,
+    pub text_hash: u32,
+    pub speaker_hash: Option<u32>,
+    pub streams: Vec<TagHash>,
+}
+
+pub struct DialogueNode {
+    pub tag: TagHash,
+    pub lines: Vec<DialogueLine>,
+    pub children: Vec<DialogueNode>,
+}
+
+impl DialogueNode {
+    pub fn line_count(&self) -> usize {
+        self.lines.len() + self.children.iter().map(|c| c.line_count()).sum::<usize>()
+    }
+
+    fn to_json(&self, strings: &StringCache) -> serde_json::Value {
+        let string = |h: u32| strings.get(&h).and_then(|s| s.first().cloned());
+        let class = package_manager()
+            .get_entry(self.tag)
+            .and_then(|e| get_class_by_id(e.reference).map(|c| c.name.to_string()));
+
+        json!({
+            "tag": self.tag.to_string(),
+            "class": class,
+            "lines": self.lines.iter().map(|l| json!({
+                "offset": l.offset,
+                "text_hash": format!("{:08X}", l.text_hash),
+                "text": string(l.text_hash),
+                "speaker_hash": l.speaker_hash.map(|h| format!("{h:08X}")),
+                "speaker": l.speaker_hash.and_then(string),
+                "audio": l.streams.iter().map(|t| t.to_string()).collect_vec(),
+            })).collect_vec(),
+            "children": self.children.iter().map(|c| c.to_json(strings)).collect_vec(),
+        })
+    }
+}
+
+/// Builds dialogue trees from the voice lines found in dialogue tables
+///
+/// Speakers are guessed from localized string hashes that are referenced close to the audio reference of a line,
+/// and that are used more than once within the same table
+pub fn build_dialogue_trees(cache: &TagCache, strings: &StringCache) -> Vec<DialogueNode> {
+    let voice_lines = find_voice_lines(cache, strings);
+    let mut lines_by_table: FxHashMap<TagHash, Vec<VoiceLine>> = FxHashMap::default();
+    for line in voice_lines {
+        lines_by_table.entry(line.table).or_default().push(line);
+    }
+
+    // Tables referenced by another table are nested under it instead of being shown at the root
+    let mut child_tables = FxHashSet::default();
+    for &table in lines_by_table.keys() {
+        if let Some(scan) = cache.hashes.get(&table) {
+            for h in &scan.file_hashes {
+                if h.hash != table && lines_by_table.contains_key(&h.hash) {
+                    child_tables.insert(h.hash);
+                }
+            }
+        }
+    }
+
+    let tables = lines_by_table
+        .keys()
+        .copied()
+        .sorted_by_key(|t| t.0)
+        .collect_vec();
+    let mut visited = FxHashSet::default();
+    let mut roots = tables
+        .iter()
+        .filter(|t| !child_tables.contains(t))
+        .filter_map(|&t| build_node(cache, strings, &lines_by_table, t, &mut visited))
+        .collect_vec();
+
+    // Tables that only reference each other in a cycle are all children, so the first table of every cycle that wasn't
+    // reached from a root becomes a root itself
+    for &table in &tables {
+        if !visited.contains(&table) {
+            roots.extend(build_node(
+                cache,
+                strings,
+                &lines_by_table,
+                table,
+                &mut visited,
+            ));
+        }
+    }
+
+    roots
+}
+
+fn build_node(
+    cache: &TagCache,
+    strings: &StringCache,
+    lines_by_table: &FxHashMap<TagHash, Vec<VoiceLine>>,
+    table: TagHash,
+    visited: &mut FxHashSet<TagHash>,
+) -> Option<DialogueNode> {
+    if !visited.insert(table) {
+        return None;
+    }
+
+    let scan = cache.hashes.get(&table)?;
+    let localized = scan
+        .string_hashes
+        .iter()
+        .filter(|s| strings.contains_key(&s.hash))
+        .collect_vec();
+    let occurrences = localized.iter().counts_by(|s| s.hash);
+
+    let lines = lines_by_table
+        .get(&table)
+        .map(|lines| {
+            lines
+                .iter()
+                .map(|l| DialogueLine {
+                    offset: l.string_offset,
+                    text_hash: l.string_hash,
+                    speaker_hash: localized
+                        .iter()
+                        .filter(|s| {
+                            s.hash != l.string_hash
+                                && occurrences[&s.hash] > 1
+                                && s.offset.abs_diff(l.audio_offset) <= MAX_SPEAKER_DISTANCE
+                        })
+                        .min_by_key(|s| s.offset.abs_diff(l.audio_offset))
+                        .map(|s| s.hash),
+                    streams: l.streams.clone(),
+                })
+                .collect_vec()
+        })
+        .unwrap_or_default();
+
+    let children = scan
+        .file_hashes
+        .iter()
+        .map(|h| h.hash)
+        .filter(|h| lines_by_table.contains_key(h))
+        .unique()
+        .filter_map(|h| build_node(cache, strings, lines_by_table, h, visited))
+        .collect_vec();
+
+    Some(DialogueNode {
+        tag: table,
+        lines,
+        children,
+    })
+}
+
+pub struct DialogueView {
+    cache: Arc<TagCache>,
+    strings: Arc<StringCache>,
+    trees: Option<Promise<Vec<DialogueNode>>>,
+}
+
+impl DialogueView {
+    pub fn new(cache: Arc<TagCache>, strings: Arc<StringCache>) -> Self {
+        Self {
+            cache,
+            strings,
+            trees: None,
+        }
+    }
+
+    fn string_text(&self, hash: u32) -> String {
+        self.strings
+            .get(&hash)
+            .and_then(|s| s.first().cloned())
+            .unwrap_or_else(|| format!("{hash:08X}"))
+    }
+
+    fn export_json(&self, trees: &[DialogueNode]) -> anyhow::Result<()> {
+        let json =
+            serde_json::Value::Array(trees.iter().map(|t| t.to_json(&self.strings)).collect());
+        std::fs::write("dialogue.json", serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+
+    fn node_ui(&self, ui: &mut egui::Ui, node: &DialogueNode) -> Option<TagHash> {
+        let mut open_tag = None;
+        let entry = package_manager().get_entry(node.tag);
+        let class_label = entry
+            .and_then(|e| get_class_by_id(e.reference))
+            .map(|c| format!(" ({})", c.name))
+            .unwrap_or_default();
+
+        CollapsingState::load_with_default_open(
+            ui.ctx(),
+            egui::Id::new(format!("dialogue_node_{}", node.tag)),
+            false,
+        )
+        .show_header(ui, |ui| {
+            if ui
+                .selectable_label(
+                    false,
+                    RichText::new(format!(
+                        "{}{class_label} - {} lines",
+                        node.tag,
+                        node.line_count()
+                    ))
+                    .strong(),
+                )
+                .tag_context(node.tag)
+                .clicked()
+            {
+                open_tag = Some(node.tag);
+            }
+        })
+        .body(|ui| {
+            for line in &node.lines {
+                ui.horizontal(|ui| {
+                    ui.weak(format!("0x{:X}", line.offset));
+                    if let Some(speaker) = line.speaker_hash {
+                        ui.label(
+                            RichText::new(format!("{}:", self.string_text(speaker)))
+                                .color(Color32::from_rgb(90, 160, 255)),
+                        );
+                    }
+                    ui.label(self.string_text(line.text_hash));
+
+                    for &stream in &line.streams {
+                        if ui.small_button("▶").clicked() {
+                            open_audio_file_in_default_application(stream, "wem");
+                        }
+                        if ui
+                            .selectable_label(false, stream.to_string())
+                            .tag_context(stream)
+                            .clicked()
+                        {
+                            open_tag = Some(stream);
+                        }
+                    }
+                });
+            }
+
+            for child in &node.children {
+                open_tag = open_tag.or(self.node_ui(ui, child));
+            }
+        });
+
+        open_tag
+    }
+}
+
+impl View for DialogueView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.trees
+                        .as_ref()
+                        .map(|p| p.ready().is_some())
+                        .unwrap_or(true),
+                    egui::Button::new("Build dialogue trees"),
+                )
+                .clicked()
+            {
+                let cache = self.cache.clone();
+                let strings = self.strings.clone();
+                self.trees = Some(Promise::spawn_thread("build_dialogue_trees", move || {
+                    build_dialogue_trees(&cache, &strings)
+                }));
+            }
+
+            if let Some(trees) = self.trees.as_ref().and_then(|p| p.ready()) {
+                if ui
+                    .button("Export to JSON")
+                    .on_hover_text("Exports all dialogue trees to dialogue.json")
+                    .clicked()
+                {
+                    match self.export_json(trees) {
+                        Ok(_) => {
+                            TOASTS
+                                .lock()
+                                .success("Dialogue trees exported to dialogue.json");
+                        }
+                        Err(e) => {
+                            error!("Failed to export dialogue trees: {e:?}");
+                            TOASTS.lock().error("Failed to export dialogue trees");
+                        }
+                    }
+                }
+            }
+        });
+        ui.separator();
+
+        let Some(trees) = self.trees.as_ref() else {
+            ui.label(RichText::new("Dialogue trees have not been built yet").italics());
+            return None;
+        };
+
+        let Some(trees) = trees.ready() else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Building dialogue trees");
+            });
+            return None;
+        };
+
+        if trees.is_empty() {
+            ui.label(RichText::new("No dialogue tables found").italics());
+            return None;
+        }
+
+        let mut open_tag = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for tree in trees {
+                    open_tag = open_tag.or(self.node_ui(ui, tree));
+                }
+            });
+
+        open_tag.map(ViewAction::OpenTag)
+    }
+}
@@ -0,0 +1,304 @@
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use eframe::egui::{self, collapsing_header::CollapsingState, RichText};
+use itertools::Itertools;
+use poll_promise::Promise;
+use quicktag_core::{classes::get_class_by_id, tagtypes::TagType};
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHasher};
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{common::ResponseExt, View, ViewAction};
+use crate::crash_context;
+
+/// Minimum fraction of identical 16-byte chunks for two tags to be considered near-identical
+const NEAR_IDENTICAL_THRESHOLD: f32 = 0.9;
+/// Groups of same-class, same-size tags larger than this are not compared chunk by chunk
+const MAX_NEAR_GROUP_SIZE: usize = 256;
+
+pub struct DuplicateCluster {
+    pub tags: Vec<TagHash>,
+    pub size: usize,
+    pub reference: u32,
+    /// Lowest chunk similarity between the first tag and the others, 1.0 for byte-identical clusters
+    pub similarity: f32,
+}
+
+impl DuplicateCluster {
+    pub fn is_exact(&self) -> bool {
+        self.similarity >= 1.0
+    }
+
+    /// Amount of bytes taken up by the copies
+    pub fn redundant_bytes(&self) -> usize {
+        self.size * (self.tags.len() - 1)
+    }
+
+    pub fn spans_packages(&self) -> bool {
+        self.tags.iter().map(|t| t.pkg_id()).unique().count() > 1
+    }
+}
+
+#[derive(Default)]
+pub struct DuplicateProgress {
+    pub current: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancel: AtomicBool,
+}
+
+struct TagDigest {
+    tag: TagHash,
+    reference: u32,
+    size: usize,
+    hash: u64,
+}
+
+fn hash_data(data: &[u8]) -> u64 {
+    let mut hasher = FxHasher::default();
+    hasher.write(data);
+    hasher.finish()
+}
+
+fn chunk_similarity(a: &[u8], b: &[u8]) -> f32 {
+    let chunks = a.len().div_ceil(16).max(1);
+    let equal = a
+        .chunks(16)
+        .zip(b.chunks(16))
+        .filter(|(a, b)| a == b)
+        .count();
+    equal as f32 / chunks as f32
+}
+
+/// Hashes the contents of every tag and clusters byte-identical tags
+///
+/// Tags with the same class and size that aren't byte-identical are compared chunk by chunk to find near-identical tags
+pub fn find_duplicates(progress: &DuplicateProgress) -> Vec<DuplicateCluster> {
+    let _context = crash_context::push(crash_context::JOB, "Finding duplicate tags");
+    let tags = package_manager()
+        .lookup
+        .tag32_entries_by_pkg
+        .iter()
+        .flat_map(|(pkg_id, entries)| {
+            entries.iter().enumerate().filter_map(|(i, e)| {
+                matches!(
+                    TagType::from_type_subtype(e.file_type, e.file_subtype),
+                    TagType::Tag | TagType::TagGlobal
+                )
+                .then(|| TagHash::new(*pkg_id, i as u16))
+            })
+        })
+        .collect_vec();
+
+    progress.total.store(tags.len(), Ordering::Relaxed);
+    progress.current.store(0, Ordering::Relaxed);
+
+    let digests: Vec<TagDigest> = tags
+        .par_iter()
+        .filter_map(|&tag| {
+            if progress.cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            progress.current.fetch_add(1, Ordering::Relaxed);
+            let entry = package_manager().get_entry(tag)?;
+            let data = package_manager().read_tag(tag).ok()?;
+            Some(TagDigest {
+                tag,
+                reference: entry.reference,
+                size: data.len(),
+                hash: hash_data(&data),
+            })
+        })
+        .collect();
+
+    let mut clusters = vec![];
+    let mut by_layout: FxHashMap<(u32, usize), Vec<&TagDigest>> = FxHashMap::default();
+    for d in &digests {
+        by_layout.entry((d.reference, d.size)).or_default().push(d);
+    }
+
+    for ((reference, size), group) in by_layout {
+        if group.len() < 2 || size == 0 || progress.cancel.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        // Exact duplicates
+        let by_hash = group.iter().into_group_map_by(|d| d.hash);
+        let mut unique = vec![];
+        for tags in by_hash.into_values() {
+            if tags.len() > 1 {
+                clusters.push(DuplicateCluster {
+                    tags: tags.iter().map(|d| d.tag).sorted_by_key(|t| t.0).collect(),
+                    size,
+                    reference,
+                    similarity: 1.0,
+                });
+            }
+
+            unique.push(tags[0].tag);
+        }
+
+        // Near-identical tags, greedily clustered around the first tag that didn't fit in an existing cluster
+        if unique.len() < 2 || unique.len() > MAX_NEAR_GROUP_SIZE {
+            continue;
+        }
+
+        let data = unique
+            .iter()
+            .filter_map(|&t| Some((t, package_manager().read_tag(t).ok()?)))
+            .collect_vec();
+        let mut near: Vec<(usize, Vec<TagHash>, f32)> = vec![];
+        for (i, (tag, tag_data)) in data.iter().enumerate() {
+            let fits = near.iter_mut().find_map(|(rep, tags, similarity)| {
+                let s = chunk_similarity(&data[*rep].1, tag_data);
+                (s >= NEAR_IDENTICAL_THRESHOLD).then(|| {
+                    tags.push(*tag);
+                    *similarity = similarity.min(s);
+                })
+            });
+
+            if fits.is_none() {
+                near.push((i, vec![*tag], 1.0));
+            }
+        }
+
+        for (_, tags, similarity) in near {
+            if tags.len() > 1 {
+                clusters.push(DuplicateCluster {
+                    tags,
+                    size,
+                    reference,
+                    similarity,
+                });
+            }
+        }
+    }
+
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.redundant_bytes()));
+    clusters
+}
+
+pub struct DuplicatesView {
+    progress: Arc<DuplicateProgress>,
+    clusters: Option<Promise<Vec<DuplicateCluster>>>,
+    show_near_identical: bool,
+    only_across_packages: bool,
+}
+
+impl DuplicatesView {
+    pub fn new() -> Self {
+        Self {
+            progress: Default::default(),
+            clusters: None,
+            show_near_identical: true,
+            only_across_packages: false,
+        }
+    }
+}
+
+impl View for DuplicatesView {
+    fn view(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let is_running = self.clusters.as_ref().is_some_and(|p| p.ready().is_none());
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!is_running, egui::Button::new("Find duplicates"))
+                .on_hover_text("Reads every tag, so this can take a while")
+                .clicked()
+            {
+                self.progress = Default::default();
+                let progress = self.progress.clone();
+                self.clusters = Some(Promise::spawn_thread("find_duplicates", move || {
+                    find_duplicates(&progress)
+                }));
+            }
+
+            if is_running && ui.button("Cancel").clicked() {
+                self.progress.cancel.store(true, Ordering::Relaxed);
+            }
+
+            ui.checkbox(&mut self.show_near_identical, "Show near-identical");
+            ui.checkbox(&mut self.only_across_packages, "Only across packages");
+        });
+        ui.separator();
+
+        let Some(clusters) = self.clusters.as_ref() else {
+            ui.label(RichText::new("Duplicates have not been analyzed yet").italics());
+            return None;
+        };
+
+        let Some(clusters) = clusters.ready() else {
+            let current = self.progress.current.load(Ordering::Relaxed);
+            let total = self.progress.total.load(Ordering::Relaxed).max(1);
+            ui.add(
+                egui::ProgressBar::new(current as f32 / total as f32)
+                    .animate(true)
+                    .text(format!("Hashing tags {current}/{total}")),
+            );
+            ctx.request_repaint();
+            return None;
+        };
+
+        let filtered = clusters
+            .iter()
+            .filter(|c| self.show_near_identical || c.is_exact())
+            .filter(|c| !self.only_across_packages || c.spans_packages())
+            .collect_vec();
+
+        ui.label(format!(
+            "{} clusters, {} redundant bytes{}",
+            filtered.len(),
+            filtered.iter().map(|c| c.redundant_bytes()).sum::<usize>(),
+            if self.progress.cancel.load(Ordering::Relaxed) {
+                " (cancelled, results are incomplete)"
+            } else {
+                ""
+            }
+        ));
+
+        let mut result = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (i, cluster) in filtered.iter().enumerate() {
+                    let class = get_class_by_id(cluster.reference)
+                        .map(|c| c.name.to_string())
+                        .unwrap_or_else(|| format!("{:08X}", cluster.reference));
+                    let kind = if cluster.is_exact() {
+                        "identical".to_string()
+                    } else {
+                        format!("{:.0}% similar", cluster.similarity * 100.0)
+                    };
+
+                    CollapsingState::load_with_default_open(
+                        ui.ctx(),
+                        egui::Id::new(("duplicate_cluster", i)),
+                        false,
+                    )
+                    .show_header(ui, |ui| {
+                        ui.label(format!(
+                            "{} tags, {class}, {} bytes ({kind})",
+                            cluster.tags.len(),
+                            cluster.size
+                        ));
+                    })
+                    .body(|ui| {
+                        for &tag in &cluster.tags {
+                            let path = &package_manager().package_paths[&tag.pkg_id()];
+                            if ui
+                                .selectable_label(false, format!("{tag} ({})", path.filename))
+                                .tag_context(tag)
+                                .clicked()
+                            {
+                                result = Some(ViewAction::OpenTag(tag));
+                            }
+                        }
+                    });
+                }
+            });
+
+        result
+    }
+}
@@ -0,0 +1,369 @@
+//! Declaring enums: names for the values of a field in a class. Enums are stored in schema.txt next to the class
+//! names, and are shown in the hex view and when comparing structs
+//!
+//! Flags are enums that name bits instead of values.
+
+use eframe::egui::{self, RichText};
+use egui_extras::{Column, TableBuilder};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use quicktag_core::classes::{
+    all_enums, export_schema, get_class_by_id, get_enum, import_schema, remove_enum, save_enum,
+    EnumField, EnumType,
+};
+
+use super::TOASTS;
+
+lazy_static! {
+    static ref EDITOR: Mutex<EnumEditor> = Mutex::new(EnumEditor::new());
+}
+
+/// Opens the editor for the enum at `offset` in `class`. Values in `seen_values` that don't have a name yet are
+/// listed so they can be named
+pub fn declare_enum(class: u32, offset: usize, seen_values: Vec<u32>) {
+    EDITOR.lock().edit(class, offset, seen_values);
+}
+
+/// Opens the editor without selecting an enum, to see the declared ones
+pub fn open() {
+    EDITOR.lock().open = true;
+}
+
+/// Returns the class and offset of a field to show the field histogram for
+pub fn show(ctx: &egui::Context) -> Option<(u32, usize)> {
+    EDITOR.lock().show(ctx)
+}
+
+struct EnumEditor {
+    open: bool,
+    class_input: String,
+    offset_input: String,
+    ty: EnumType,
+    flags: bool,
+    /// (value, name) as entered, values are bit numbers for flags
+    values: Vec<(String, String)>,
+    /// Values of the field seen in the data, listed until they're named
+    seen_values: Vec<u32>,
+}
+
+impl EnumEditor {
+    fn new() -> Self {
+        Self {
+            open: false,
+            class_input: String::new(),
+            offset_input: String::new(),
+            ty: EnumType::U32,
+            flags: false,
+            values: vec![],
+            seen_values: vec![],
+        }
+    }
+
+    fn edit(&mut self, class: u32, offset: usize, mut seen_values: Vec<u32>) {
+        let existing = get_enum(class, offset);
+        self.class_input = format!("{class:08X}");
+        self.offset_input = format!("{offset:X}");
+        self.ty = existing.as_ref().map_or(EnumType::U32, |f| f.ty);
+        self.flags = existing.as_ref().is_some_and(|f| f.flags);
+        self.values = existing
+            .as_ref()
+            .map(|f| {
+                f.values
+                    .iter()
+                    .map(|(value, name)| (value.to_string(), name.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        seen_values.sort_unstable();
+        seen_values.dedup();
+        self.seen_values = seen_values;
+        self.add_seen_values();
+
+        self.open = true;
+    }
+
+    /// Adds rows for the seen values (or the bits set in them, for flags) that aren't listed yet
+    fn add_seen_values(&mut self) {
+        let mut seen = self.seen_values.clone();
+        if self.flags {
+            seen = (0..self.ty.bits())
+                .filter(|bit| self.seen_values.iter().any(|v| v & (1 << bit) != 0))
+                .collect();
+        }
+
+        for value in seen {
+            let value = value.to_string();
+            if !self.values.iter().any(|(v, _)| v.trim() == value) {
+                self.values.push((value, String::new()));
+            }
+        }
+    }
+
+    /// Builds the enum from the inputs. Values without a name are left out
+    fn parse(&self) -> anyhow::Result<EnumField> {
+        let class = u32::from_str_radix(self.class_input.trim(), 16)
+            .map_err(|_| anyhow::anyhow!("The class should be a hex hash"))?;
+        let offset = usize::from_str_radix(self.offset_input.trim().trim_start_matches("0x"), 16)
+            .map_err(|_| anyhow::anyhow!("The offset should be in hex"))?;
+
+        let mut values = vec![];
+        for (value, name) in &self.values {
+            if name.trim().is_empty() {
+                continue;
+            }
+
+            let value = value.trim();
+            let parsed = match value.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => value.parse(),
+            };
+            let parsed = parsed.map_err(|_| anyhow::anyhow!("'{value}' is not a number"))?;
+            values.push((parsed, name.trim().to_string()));
+        }
+        values.sort_by_key(|(value, _)| *value);
+        values.dedup_by_key(|(value, _)| *value);
+        if self.flags {
+            if let Some((bit, _)) = values.iter().find(|(bit, _)| *bit >= self.ty.bits()) {
+                anyhow::bail!("Bit {bit} doesn't fit in a {}", self.ty.name());
+            }
+        }
+
+        Ok(EnumField {
+            class,
+            offset,
+            ty: self.ty,
+            flags: self.flags,
+            values,
+        })
+    }
+
+    fn save(&self) {
+        let result = self.parse().and_then(|field| {
+            save_enum(&field)?;
+            Ok(field)
+        });
+        match result {
+            Ok(field) => TOASTS.lock().success(format!(
+                "Saved enum at {:08X} @ 0x{:X} ({} names)",
+                field.class,
+                field.offset,
+                field.values.len()
+            )),
+            Err(e) => {
+                log::error!("Failed to save enum: {e:?}");
+                TOASTS.lock().error(format!("Failed to save enum: {e}"))
+            }
+        };
+    }
+
+    fn remove(&self) {
+        let result = self
+            .parse()
+            .and_then(|field| remove_enum(field.class, field.offset));
+        match result {
+            Ok(()) => TOASTS.lock().success("Removed the enum"),
+            Err(e) => {
+                log::error!("Failed to remove enum: {e:?}");
+                TOASTS.lock().error(format!("Failed to remove enum: {e}"))
+            }
+        };
+    }
+
+    fn show(&mut self, ctx: &egui::Context) -> Option<(u32, usize)> {
+        let mut open = self.open;
+        let mut histogram = None;
+        egui::Window::new("Enums")
+            .open(&mut open)
+            .default_size([480.0, 480.0])
+            .show(ctx, |ui| {
+                histogram = self.editor_ui(ui);
+                ui.separator();
+                self.declared_ui(ui);
+            });
+        self.open = open;
+        histogram
+    }
+
+    fn editor_ui(&mut self, ui: &mut egui::Ui) -> Option<(u32, usize)> {
+        ui.horizontal(|ui| {
+            ui.label("Class");
+            ui.add(egui::TextEdit::singleline(&mut self.class_input).desired_width(80.0));
+            if let Some(class) = u32::from_str_radix(self.class_input.trim(), 16)
+                .ok()
+                .and_then(get_class_by_id)
+            {
+                ui.weak(class.name.to_string());
+            }
+            ui.label("Offset 0x");
+            ui.add(egui::TextEdit::singleline(&mut self.offset_input).desired_width(48.0));
+            egui::ComboBox::from_id_source("enum_type")
+                .selected_text(self.ty.name())
+                .show_ui(ui, |ui| {
+                    for ty in EnumType::ALL {
+                        ui.selectable_value(&mut self.ty, ty, ty.name());
+                    }
+                });
+            if ui
+                .checkbox(&mut self.flags, "Flags")
+                .on_hover_text("Name the bits of the field instead of its values")
+                .changed()
+            {
+                // Unnamed rows were seen values, swap them for bits or values
+                self.values.retain(|(_, name)| !name.trim().is_empty());
+                self.add_seen_values();
+            }
+        });
+
+        let mut remove = None;
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::exact(96.0))
+            .column(Column::remainder().at_least(160.0))
+            .column(Column::exact(24.0))
+            .max_scroll_height(240.0)
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong(if self.flags { "Bit" } else { "Value" });
+                });
+                header.col(|ui| {
+                    ui.strong("Name");
+                });
+                header.col(|_| {});
+            })
+            .body(|body| {
+                body.rows(22.0, self.values.len(), |mut row| {
+                    let i = row.index();
+                    let (value, name) = &mut self.values[i];
+                    row.col(|ui| {
+                        ui.text_edit_singleline(value);
+                    });
+                    row.col(|ui| {
+                        ui.add(egui::TextEdit::singleline(name).hint_text("unnamed"));
+                    });
+                    row.col(|ui| {
+                        if ui.small_button("🗙").on_hover_text("Remove").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                });
+            });
+        if let Some(i) = remove {
+            self.values.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Add value").clicked() {
+                self.values.push((String::new(), String::new()));
+            }
+            if ui
+                .button("Save")
+                .on_hover_text("Values without a name are left out")
+                .clicked()
+            {
+                self.save();
+            }
+            if ui.button("Remove enum").clicked() {
+                self.remove();
+            }
+        });
+
+        let field = self.parse().ok()?;
+        let label = if self.flags {
+            "Bits set across the class"
+        } else {
+            "Values across the class"
+        };
+        ui.button(label)
+            .on_hover_text("Opens the field histogram for this field, with a count of every bit")
+            .clicked()
+            .then_some((field.class, field.offset))
+    }
+
+    fn declared_ui(&mut self, ui: &mut egui::Ui) {
+        let enums = all_enums();
+        ui.horizontal(|ui| {
+            ui.strong(format!("{} declared enums", enums.len()));
+            if ui
+                .button("Export schema...")
+                .on_hover_text("Writes the classes and enums from schema.txt to a file")
+                .clicked()
+            {
+                export();
+            }
+            if ui
+                .button("Import schema...")
+                .on_hover_text("Adds the classes and enums from an exported schema to schema.txt")
+                .clicked()
+            {
+                import();
+            }
+        });
+
+        egui::ScrollArea::vertical()
+            .id_source("declared_enums")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                for field in enums {
+                    let class = get_class_by_id(field.class)
+                        .map(|c| c.name.to_string())
+                        .unwrap_or_else(|| format!("{:08X}", field.class));
+                    if ui
+                        .selectable_label(
+                            false,
+                            RichText::new(format!(
+                                "{class} @ 0x{:X} ({} {}, {} names)",
+                                field.offset,
+                                field.ty.name(),
+                                if field.flags { "flags" } else { "enum" },
+                                field.values.len()
+                            )),
+                        )
+                        .clicked()
+                    {
+                        self.edit(field.class, field.offset, vec![]);
+                    }
+                }
+            });
+    }
+}
+
+fn export() {
+    let Ok(Some(path)) = native_dialog::FileDialog::new()
+        .add_filter("Schema", &["txt"])
+        .set_filename("schema_export.txt")
+        .show_save_single_file()
+    else {
+        return;
+    };
+
+    match export_schema(&path) {
+        Ok((classes, enums)) => TOASTS.lock().success(format!(
+            "Exported {classes} classes and {enums} enums to {}",
+            path.display()
+        )),
+        Err(e) => {
+            log::error!("Failed to export schema: {e:?}");
+            TOASTS.lock().error(format!("Failed to export schema: {e}"))
+        }
+    };
+}
+
+fn import() {
+    let Ok(Some(path)) = native_dialog::FileDialog::new()
+        .add_filter("Schema", &["txt"])
+        .show_open_single_file()
+    else {
+        return;
+    };
+
+    match import_schema(&path) {
+        Ok((classes, enums)) => TOASTS
+            .lock()
+            .success(format!("Imported {classes} classes and {enums} enums")),
+        Err(e) => {
+            log::error!("Failed to import schema from {}: {e:?}", path.display());
+            TOASTS.lock().error(format!("Failed to import schema: {e}"))
+        }
+    };
+}
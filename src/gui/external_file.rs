@@ -1,27 +1,47 @@
+use std::path::{Path, PathBuf};
+
+use crate::crash_context;
 use crate::gui::common::ResponseExt;
+use crate::gui::hex_sidecar::{self, HexSidecar};
 use crate::gui::tag::{
     format_tag_entry, ExtendedScanResult, ExtendedTagHash, ScannedHashWithEntry,
 };
-use crate::gui::ViewAction;
+use crate::gui::{ViewAction, TOASTS};
 use crate::texture::cache::TextureCache;
 use eframe::egui;
+use log::error;
 use quicktag_core::tagtypes::TagType;
 use quicktag_scanner::{context::ScannerContext, ScannerMode};
 
 pub struct ExternalFileScanView {
     pub filename: String,
+    /// Path of the scanned file, used to rescan it after it has been modified
+    path: PathBuf,
     file_hashes: Vec<ScannedHashWithEntry<ExtendedTagHash>>,
+    /// Sidecar next to the file, used when its tag is opened
+    sidecar: Option<HexSidecar>,
 }
 
 impl ExternalFileScanView {
-    pub fn new(filename: String, scancontext: &ScannerContext, data: &[u8]) -> Self {
-        let scanresult = quicktag_scanner::scan_file(scancontext, data, ScannerMode::Tags);
+    pub fn new(path: &Path, scancontext: &ScannerContext) -> anyhow::Result<Self> {
+        let filename = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let _context = crash_context::push(
+            crash_context::JOB,
+            format!("Scanning file {}", path.display()),
+        );
+        let data = std::fs::read(path)?;
+        let scanresult = quicktag_scanner::scan_file(scancontext, &data, ScannerMode::Tags);
         let scanresult_ext = ExtendedScanResult::from_scanresult(scanresult);
 
-        Self {
+        Ok(Self {
             filename,
+            path: path.to_path_buf(),
             file_hashes: scanresult_ext.file_hashes,
-        }
+            sidecar: hex_sidecar::register_external(path),
+        })
     }
 
     pub fn view(
@@ -29,9 +49,46 @@ impl ExternalFileScanView {
         _ctx: &egui::Context,
         ui: &mut egui::Ui,
         texture_cache: &TextureCache,
+        scancontext: &ScannerContext,
     ) -> Option<ViewAction> {
         let mut result = None;
 
+        ui.horizontal(|ui| {
+            ui.label(self.path.display().to_string());
+            if ui
+                .button("Rescan")
+                .on_hover_text("Reads the file from disk and scans it again")
+                .clicked()
+            {
+                match Self::new(&self.path, scancontext) {
+                    Ok(v) => *self = v,
+                    Err(e) => {
+                        error!("Failed to rescan {}: {e:?}", self.path.display());
+                        TOASTS.lock().error("Failed to rescan file");
+                    }
+                }
+            }
+        });
+
+        if let Some(sidecar) = &self.sidecar {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Sidecar with {} bookmarks and {} region labels",
+                    sidecar.bookmarks.len(),
+                    sidecar.regions.len()
+                ));
+                if let Some(tag) = sidecar.tag() {
+                    if ui
+                        .button(format!("Open {tag}"))
+                        .on_hover_text("Opens the tag the sidecar was made for, with its markup")
+                        .clicked()
+                    {
+                        result = Some(ViewAction::OpenTag(tag));
+                    }
+                }
+            });
+        }
+
         if ui.button("Copy tag list").clicked() {
             let mut taglist = String::new();
 
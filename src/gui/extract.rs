@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use anyhow::Context;
+use log::error;
+use quicktag_core::{classes::get_class_by_id, tagtypes::TagType};
+use serde::Serialize;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::common::register_exported_file;
+use crate::crash_context;
+use crate::texture::export::export_dds;
+
+#[derive(Default)]
+pub struct ExtractProgress {
+    pub current: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancel: AtomicBool,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    hash: String,
+    hash64: Option<String>,
+    index: usize,
+    #[serde(rename = "type")]
+    tag_type: String,
+    file_type: u8,
+    file_subtype: u8,
+    reference: String,
+    class: Option<String>,
+    size: u32,
+    /// Path relative to the manifest, or None if the entry couldn't be read
+    path: Option<String>,
+}
+
+/// Kinds of entries, each is extracted to its own subfolder
+#[derive(Clone, Copy, PartialEq, Debug, clap::ValueEnum)]
+pub enum EntryCategory {
+    #[value(alias = "texture")]
+    Textures,
+    #[value(alias = "shader")]
+    Shaders,
+    #[value(alias = "buffer")]
+    Buffers,
+    #[value(alias = "constant-buffer")]
+    ConstantBuffers,
+    #[value(alias = "tag")]
+    Tags,
+    Audio,
+    #[value(alias = "soundbank")]
+    Soundbanks,
+    Havok,
+    #[value(alias = "font")]
+    FontsTomes,
+    #[value(alias = "video")]
+    Videos,
+    Other,
+}
+
+impl EntryCategory {
+    pub fn of(tag_type: TagType) -> Self {
+        match tag_type {
+            t if t.is_texture() => EntryCategory::Textures,
+            t if t.is_shader() => EntryCategory::Shaders,
+            TagType::VertexBuffer { .. } | TagType::IndexBuffer { .. } => EntryCategory::Buffers,
+            TagType::ConstantBuffer { .. } => EntryCategory::ConstantBuffers,
+            TagType::Tag | TagType::TagGlobal => EntryCategory::Tags,
+            TagType::WwiseStream => EntryCategory::Audio,
+            TagType::WwiseBank | TagType::WwiseInitBank => EntryCategory::Soundbanks,
+            TagType::Havok => EntryCategory::Havok,
+            TagType::OtfFontOrUmbraTome => EntryCategory::FontsTomes,
+            TagType::CriwareUsm => EntryCategory::Videos,
+            _ => EntryCategory::Other,
+        }
+    }
+
+    /// Subfolder entries of this category are extracted to
+    fn folder(self) -> &'static str {
+        match self {
+            EntryCategory::Textures => "textures",
+            EntryCategory::Shaders => "shaders",
+            EntryCategory::Buffers => "buffers",
+            EntryCategory::ConstantBuffers => "constant_buffers",
+            EntryCategory::Tags => "tags",
+            EntryCategory::Audio => "audio",
+            EntryCategory::Soundbanks => "soundbanks",
+            EntryCategory::Havok => "havok",
+            EntryCategory::FontsTomes => "fonts_tomes",
+            EntryCategory::Videos => "videos",
+            EntryCategory::Other => "other",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ExtractOptions {
+    /// Categories to extract, every entry is extracted if this is empty
+    pub categories: Vec<EntryCategory>,
+    /// Writes texture headers as DDS files with their data, instead of extracting headers and data separately
+    pub textures_as_dds: bool,
+}
+
+impl ExtractOptions {
+    fn includes(&self, tag_type: TagType) -> bool {
+        if self.textures_as_dds && tag_type.is_texture() && !tag_type.is_header() {
+            return false;
+        }
+
+        self.categories.is_empty() || self.categories.contains(&EntryCategory::of(tag_type))
+    }
+}
+
+fn type_extension(tag_type: TagType) -> &'static str {
+    match tag_type {
+        TagType::WwiseStream => "wem",
+        TagType::WwiseBank | TagType::WwiseInitBank => "bnk",
+        TagType::CriwareUsm => "usm",
+        _ => "bin",
+    }
+}
+
+/// Extracts the entries of a package to `<out_root>/<package name>`, sorted into subfolders by type, along with a
+/// manifest.json
+pub fn extract_package(
+    pkg_id: u16,
+    out_root: &Path,
+    options: &ExtractOptions,
+    progress: &ExtractProgress,
+) -> anyhow::Result<PathBuf> {
+    let _context = crash_context::push(
+        crash_context::JOB,
+        format!("Extracting package {pkg_id:04x}"),
+    );
+    let path = package_manager()
+        .package_paths
+        .get(&pkg_id)
+        .cloned()
+        .context("Package not found")?;
+    let pkg = package_manager().version.open(&path.path)?;
+
+    let out_dir = out_root.join(format!("{}_{}", path.name, path.id));
+    std::fs::create_dir_all(&out_dir)?;
+
+    let entries = pkg.entries().to_vec();
+    progress.total.store(entries.len(), Ordering::Relaxed);
+
+    let mut manifest = vec![];
+    for (i, e) in entries.iter().enumerate() {
+        if progress.cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        progress.current.store(i + 1, Ordering::Relaxed);
+
+        let tag = TagHash::new(pkg_id, i as u16);
+        let tag_type = TagType::from_type_subtype(e.file_type, e.file_subtype);
+        if !options.includes(tag_type) {
+            continue;
+        }
+
+        let as_dds = options.textures_as_dds && tag_type.is_texture();
+        let extension = if as_dds {
+            "dds"
+        } else {
+            type_extension(tag_type)
+        };
+        let relative_path =
+            Path::new(EntryCategory::of(tag_type).folder()).join(format!("{tag}.{extension}"));
+
+        let file_path = out_dir.join(&relative_path);
+        let written = std::fs::create_dir_all(file_path.parent().unwrap())
+            .map_err(anyhow::Error::from)
+            .and_then(|_| {
+                if as_dds {
+                    export_dds(tag, 0, &file_path)
+                } else {
+                    std::fs::write(&file_path, pkg.read_entry(i)?)?;
+                    Ok(())
+                }
+            })
+            .map(|_| register_exported_file(tag, &file_path));
+
+        if let Err(err) = &written {
+            error!("Failed to extract {tag}: {err:?}");
+        }
+
+        manifest.push(ManifestEntry {
+            hash: format!("{:08X}", tag.0),
+            hash64: package_manager()
+                .get_tag64_for_tag32(tag)
+                .map(|t| format!("{:016X}", t.0)),
+            index: i,
+            tag_type: tag_type.to_string(),
+            file_type: e.file_type,
+            file_subtype: e.file_subtype,
+            reference: format!("{:08X}", e.reference),
+            class: get_class_by_id(e.reference).map(|c| c.name.to_string()),
+            size: e.file_size,
+            path: written
+                .is_ok()
+                .then(|| relative_path.to_string_lossy().replace('\\', "/")),
+        });
+    }
+
+    std::fs::write(
+        out_dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    Ok(out_dir)
+}
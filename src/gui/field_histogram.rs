@@ -0,0 +1,596 @@
+//! Distribution of the values of one field across every tag of a class, to find out what a field means (flags,
+//! enums, counts, floats) and which tags are the odd ones out
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use eframe::egui::{self, Color32, RichText, Sense};
+use itertools::Itertools;
+use poll_promise::Promise;
+use quicktag_core::classes::{get_class_by_id, get_enum, EnumType};
+use quicktag_scanner::TagCache;
+use rayon::prelude::*;
+use tiger_pkg::{package_manager, Endian, TagHash, Version};
+
+use super::{View, ViewAction};
+use crate::crash_context;
+
+/// Fields with at most this many distinct values get a bar per value instead of ranges
+const MAX_DISTINCT_BUCKETS: usize = 48;
+const RANGE_BUCKETS: usize = 32;
+/// Values further than this many interquartile ranges outside of the middle half are outliers
+const OUTLIER_IQR_FACTOR: f64 = 3.0;
+/// Values that less than this fraction of the tags share are outliers, for fields with few distinct values
+const RARE_VALUE_FRACTION: f64 = 0.01;
+/// Tags listed when drilling down into a bucket
+const MAX_LISTED_TAGS: usize = 512;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FieldType {
+    U8,
+    U16,
+    U32,
+    I32,
+    F32,
+}
+
+impl FieldType {
+    const ALL: [FieldType; 5] = [
+        FieldType::U8,
+        FieldType::U16,
+        FieldType::U32,
+        FieldType::I32,
+        FieldType::F32,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            FieldType::U8 => "u8",
+            FieldType::U16 => "u16",
+            FieldType::U32 => "u32",
+            FieldType::I32 => "i32",
+            FieldType::F32 => "f32",
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            FieldType::U8 => 1,
+            FieldType::U16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+        }
+    }
+
+    fn read(self, data: &[u8], offset: usize, endian: Endian) -> Option<f64> {
+        let bytes = data.get(offset..offset + self.size())?;
+        let mut buf = [0u8; 4];
+        match endian {
+            Endian::Little => buf[..bytes.len()].copy_from_slice(bytes),
+            Endian::Big => buf[4 - bytes.len()..].copy_from_slice(bytes),
+        }
+        let raw = match endian {
+            Endian::Little => u32::from_le_bytes(buf),
+            Endian::Big => u32::from_be_bytes(buf),
+        };
+
+        Some(match self {
+            FieldType::U8 | FieldType::U16 | FieldType::U32 => raw as f64,
+            FieldType::I32 => raw as i32 as f64,
+            FieldType::F32 => f32::from_bits(raw) as f64,
+        })
+    }
+
+    fn format(self, value: f64) -> String {
+        match self {
+            FieldType::F32 => format!("{value:.4}"),
+            FieldType::U32 => format!("{value} (0x{:X})", value as u32),
+            _ => value.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HistogramProgress {
+    pub current: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancel: AtomicBool,
+}
+
+struct Bucket {
+    label: String,
+    tags: Vec<TagHash>,
+}
+
+pub struct FieldHistogram {
+    class: u32,
+    offset: usize,
+    field_type: FieldType,
+    /// Tags of the class that were read
+    tags: usize,
+    /// Tags that are too small for the field, or have a NaN/infinite float
+    invalid: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    distinct: usize,
+    buckets: Vec<Bucket>,
+    outliers: Vec<(TagHash, f64)>,
+    /// Tags with each bit set, for integer fields
+    bit_counts: Vec<usize>,
+}
+
+/// Reads a field from every tag of a class in the cache
+pub fn compute_field_histogram(
+    cache: &TagCache,
+    class: u32,
+    offset: usize,
+    field_type: FieldType,
+    progress: &HistogramProgress,
+) -> FieldHistogram {
+    let _context = crash_context::push(
+        crash_context::JOB,
+        format!("Computing a histogram of {class:08X} @ 0x{offset:X}"),
+    );
+    let tags = cache
+        .hashes
+        .iter()
+        .map(|(tag, _)| *tag)
+        .filter(|tag| {
+            package_manager()
+                .get_entry(*tag)
+                .is_some_and(|e| e.reference == class)
+        })
+        .collect_vec();
+
+    progress.total.store(tags.len(), Ordering::Relaxed);
+    progress.current.store(0, Ordering::Relaxed);
+
+    let endian = package_manager().version.endian();
+    let values: Vec<(TagHash, Option<f64>)> = tags
+        .par_iter()
+        .filter_map(|&tag| {
+            if progress.cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            progress.current.fetch_add(1, Ordering::Relaxed);
+            let data = package_manager().read_tag(tag).ok()?;
+            let value = field_type
+                .read(&data, offset, endian)
+                .filter(|v| v.is_finite());
+            Some((tag, value))
+        })
+        .collect();
+
+    FieldHistogram::new(class, offset, field_type, values)
+}
+
+impl FieldHistogram {
+    fn new(
+        class: u32,
+        offset: usize,
+        field_type: FieldType,
+        values: Vec<(TagHash, Option<f64>)>,
+    ) -> Self {
+        let tags = values.len();
+        let mut values = values
+            .into_iter()
+            .filter_map(|(tag, v)| Some((tag, v?)))
+            .collect_vec();
+        values.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        let invalid = tags - values.len();
+        let bit_counts = match field_type {
+            FieldType::F32 => vec![],
+            _ => (0..field_type.size() * 8)
+                .map(|bit| {
+                    values
+                        .iter()
+                        .filter(|(_, v)| (*v as i64 as u32) & (1 << bit) != 0)
+                        .count()
+                })
+                .collect(),
+        };
+        let distinct = values.iter().dedup_by(|(_, a), (_, b)| a == b).count();
+        let (Some((_, min)), Some((_, max))) = (values.first().copied(), values.last().copied())
+        else {
+            return Self {
+                class,
+                offset,
+                field_type,
+                tags,
+                invalid,
+                min: 0.0,
+                max: 0.0,
+                mean: 0.0,
+                distinct,
+                buckets: vec![],
+                outliers: vec![],
+                bit_counts,
+            };
+        };
+        let mean = values.iter().map(|(_, v)| v).sum::<f64>() / values.len() as f64;
+
+        let (buckets, outliers) = if distinct <= MAX_DISTINCT_BUCKETS {
+            let groups = values
+                .iter()
+                .chunk_by(|(_, v)| *v)
+                .into_iter()
+                .map(|(value, group)| (value, group.map(|(tag, _)| *tag).collect_vec()))
+                .collect_vec();
+
+            let rare_count = (values.len() as f64 * RARE_VALUE_FRACTION).floor() as usize;
+            let outliers = groups
+                .iter()
+                .filter(|(_, tags)| tags.len() <= rare_count)
+                .flat_map(|(value, tags)| tags.iter().map(|tag| (*tag, *value)))
+                .collect();
+            let buckets = groups
+                .into_iter()
+                .map(|(value, tags)| Bucket {
+                    label: field_type.format(value),
+                    tags,
+                })
+                .collect();
+            (buckets, outliers)
+        } else {
+            let quartile = |q: f64| values[((values.len() - 1) as f64 * q) as usize].1;
+            let (q1, q3) = (quartile(0.25), quartile(0.75));
+            let iqr = q3 - q1;
+            let (low, high) = if iqr > 0.0 {
+                (
+                    (q1 - iqr * OUTLIER_IQR_FACTOR).max(min),
+                    (q3 + iqr * OUTLIER_IQR_FACTOR).min(max),
+                )
+            } else {
+                (min, max)
+            };
+
+            let outliers = values
+                .iter()
+                .filter(|(_, v)| *v < low || *v > high)
+                .copied()
+                .collect();
+
+            let width = ((high - low) / RANGE_BUCKETS as f64).max(f64::MIN_POSITIVE);
+            let mut buckets = (0..RANGE_BUCKETS)
+                .map(|i| {
+                    let start = low + width * i as f64;
+                    Bucket {
+                        label: format!(
+                            "{} to {}",
+                            field_type.format(start),
+                            field_type.format(start + width)
+                        ),
+                        tags: vec![],
+                    }
+                })
+                .collect_vec();
+            for (tag, v) in values.iter().filter(|(_, v)| *v >= low && *v <= high) {
+                let index = (((v - low) / width) as usize).min(RANGE_BUCKETS - 1);
+                buckets[index].tags.push(*tag);
+            }
+
+            (buckets, outliers)
+        };
+
+        Self {
+            class,
+            offset,
+            field_type,
+            tags,
+            invalid,
+            min,
+            max,
+            mean,
+            distinct,
+            buckets,
+            outliers,
+            bit_counts,
+        }
+    }
+}
+
+pub struct FieldHistogramView {
+    cache: Arc<TagCache>,
+    class_input: String,
+    offset_input: String,
+    field_type: FieldType,
+    progress: Arc<HistogramProgress>,
+    histogram: Option<Promise<FieldHistogram>>,
+    selected_bucket: Option<usize>,
+}
+
+impl FieldHistogramView {
+    pub fn new(cache: Arc<TagCache>) -> Self {
+        Self {
+            cache,
+            class_input: String::new(),
+            offset_input: String::new(),
+            field_type: FieldType::U32,
+            progress: Default::default(),
+            histogram: None,
+            selected_bucket: None,
+        }
+    }
+
+    /// Fills in the field and computes its histogram, eg. from the sibling comparison
+    pub fn compute_for(&mut self, class: u32, offset: usize) {
+        self.class_input = format!("{class:08X}");
+        self.offset_input = format!("{offset:X}");
+        if let Some(field) = get_enum(class, offset) {
+            self.field_type = match field.ty {
+                EnumType::U8 => FieldType::U8,
+                EnumType::U16 => FieldType::U16,
+                EnumType::U32 => FieldType::U32,
+            };
+        }
+        self.start();
+    }
+
+    fn parsed_input(&self) -> Option<(u32, usize)> {
+        let class = u32::from_str_radix(self.class_input.trim(), 16).ok()?;
+        let offset = self.offset_input.trim();
+        let offset = offset.strip_prefix("0x").unwrap_or(offset);
+        Some((class, usize::from_str_radix(offset, 16).ok()?))
+    }
+
+    fn start(&mut self) {
+        let Some((class, offset)) = self.parsed_input() else {
+            return;
+        };
+
+        self.progress = Default::default();
+        self.selected_bucket = None;
+        let progress = self.progress.clone();
+        let cache = self.cache.clone();
+        let field_type = self.field_type;
+        self.histogram = Some(Promise::spawn_thread(
+            "compute_field_histogram",
+            move || compute_field_histogram(&cache, class, offset, field_type, &progress),
+        ));
+    }
+
+    /// Lists how many tags have each bit set, named after the flags declared for the field
+    fn bits(ui: &mut egui::Ui, histogram: &FieldHistogram) {
+        let flags = get_enum(histogram.class, histogram.offset).filter(|f| f.flags);
+        let valid = (histogram.tags - histogram.invalid).max(1);
+        let never_set = histogram.bit_counts.iter().filter(|&&c| c == 0).count();
+        egui::CollapsingHeader::new(format!(
+            "Bits ({} of {} never set)",
+            never_set,
+            histogram.bit_counts.len()
+        ))
+        .id_source("field_histogram_bits")
+        .show(ui, |ui| {
+            egui::Grid::new("field_histogram_bits_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (bit, &count) in histogram.bit_counts.iter().enumerate().rev() {
+                        let name = flags
+                            .as_ref()
+                            .and_then(|f| f.name_of(bit as u32))
+                            .unwrap_or_default();
+                        let text =
+                            format!("{count} tags ({:.1}%)", count as f32 / valid as f32 * 100.0);
+                        if count == 0 {
+                            ui.weak(format!("Bit {bit}"));
+                            ui.weak(name);
+                            ui.weak(text);
+                        } else {
+                            ui.monospace(format!("Bit {bit}"));
+                            ui.label(name);
+                            ui.label(text);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// Draws the bars, returns the index of the clicked bucket
+    fn bars(
+        ui: &mut egui::Ui,
+        histogram: &FieldHistogram,
+        selected: Option<usize>,
+    ) -> Option<usize> {
+        let max_count = histogram
+            .buckets
+            .iter()
+            .map(|b| b.tags.len())
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let (rect, _) =
+            ui.allocate_exact_size(egui::vec2(ui.available_width(), 160.0), Sense::hover());
+        let bar_width = rect.width() / histogram.buckets.len().max(1) as f32;
+
+        let mut clicked = None;
+        for (i, bucket) in histogram.buckets.iter().enumerate() {
+            let height = if bucket.tags.is_empty() {
+                0.0
+            } else {
+                (bucket.tags.len() as f32 / max_count as f32).max(0.02) * rect.height()
+            };
+            let column = egui::Rect::from_min_size(
+                egui::pos2(rect.left() + bar_width * i as f32, rect.top()),
+                egui::vec2(bar_width, rect.height()),
+            );
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(column.left() + 1.0, column.bottom() - height),
+                egui::pos2(column.right() - 1.0, column.bottom()),
+            );
+
+            let response = ui
+                .interact(column, ui.id().with(("histogram_bar", i)), Sense::click())
+                .on_hover_text(format!("{}\n{} tags", bucket.label, bucket.tags.len()));
+            let color = if selected == Some(i) {
+                Color32::from_rgb(255, 200, 80)
+            } else if response.hovered() {
+                Color32::from_rgb(120, 180, 255)
+            } else {
+                Color32::from_rgb(80, 140, 220)
+            };
+            ui.painter().rect_filled(bar, 0.0, color);
+
+            if response.clicked() {
+                clicked = Some(i);
+            }
+        }
+
+        clicked
+    }
+}
+
+fn tag_list(
+    ui: &mut egui::Ui,
+    id: &str,
+    tags: impl Iterator<Item = (TagHash, Option<String>)>,
+) -> Option<TagHash> {
+    let mut result = None;
+    egui::ScrollArea::vertical()
+        .id_source(id)
+        .max_height(240.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            for (tag, value) in tags.take(MAX_LISTED_TAGS) {
+                let label = match value {
+                    Some(value) => format!("{tag} = {value}"),
+                    None => tag.to_string(),
+                };
+                if ui.selectable_label(false, label).clicked() {
+                    result = Some(tag);
+                }
+            }
+        });
+
+    result
+}
+
+impl View for FieldHistogramView {
+    fn view(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let is_running = self.histogram.as_ref().is_some_and(|p| p.ready().is_none());
+
+        ui.horizontal(|ui| {
+            ui.label("Class");
+            ui.add(egui::TextEdit::singleline(&mut self.class_input).desired_width(80.0))
+                .on_hover_text("Class reference of the tags, in hex");
+            ui.label("Offset");
+            ui.add(egui::TextEdit::singleline(&mut self.offset_input).desired_width(64.0))
+                .on_hover_text("Offset of the field in the tag, in hex");
+            egui::ComboBox::from_id_source("field_histogram_type")
+                .selected_text(self.field_type.label())
+                .show_ui(ui, |ui| {
+                    for field_type in FieldType::ALL {
+                        ui.selectable_value(&mut self.field_type, field_type, field_type.label());
+                    }
+                });
+
+            let valid = self.parsed_input().is_some();
+            if ui
+                .add_enabled(!is_running && valid, egui::Button::new("Compute"))
+                .on_hover_text("Reads every tag of the class, so this can take a while")
+                .on_disabled_hover_text("Enter a class and offset in hex")
+                .clicked()
+            {
+                self.start();
+            }
+
+            if is_running && ui.button("Cancel").clicked() {
+                self.progress.cancel.store(true, Ordering::Relaxed);
+            }
+
+            if let Some((class, _)) = self.parsed_input() {
+                if let Some(class) = get_class_by_id(class) {
+                    ui.weak(class.name.to_string());
+                }
+            }
+        });
+        ui.separator();
+
+        let Some(histogram) = self.histogram.as_ref() else {
+            ui.label(RichText::new("No histogram computed yet").italics());
+            return None;
+        };
+
+        let Some(histogram) = histogram.ready() else {
+            let current = self.progress.current.load(Ordering::Relaxed);
+            let total = self.progress.total.load(Ordering::Relaxed).max(1);
+            ui.add(
+                egui::ProgressBar::new(current as f32 / total as f32)
+                    .animate(true)
+                    .text(format!("Reading tags {current}/{total}")),
+            );
+            ctx.request_repaint();
+            return None;
+        };
+
+        if histogram.buckets.is_empty() {
+            ui.label(format!(
+                "None of the {} tags of this class have a value at this offset",
+                histogram.tags
+            ));
+            return None;
+        }
+
+        let field_type = histogram.field_type;
+        ui.label(format!(
+            "{} tags, {} distinct values. Min {}, max {}, mean {:.4}{}{}",
+            histogram.tags,
+            histogram.distinct,
+            field_type.format(histogram.min),
+            field_type.format(histogram.max),
+            histogram.mean,
+            if histogram.invalid > 0 {
+                format!(", {} tags without a valid value", histogram.invalid)
+            } else {
+                String::new()
+            },
+            if self.progress.cancel.load(Ordering::Relaxed) {
+                " (cancelled, results are incomplete)"
+            } else {
+                ""
+            }
+        ));
+
+        if let Some(clicked) = Self::bars(ui, histogram, self.selected_bucket) {
+            self.selected_bucket = (self.selected_bucket != Some(clicked)).then_some(clicked);
+        }
+        ui.weak("Click a bar to list its tags");
+        ui.separator();
+
+        if !histogram.bit_counts.is_empty() {
+            Self::bits(ui, histogram);
+            ui.separator();
+        }
+
+        let mut open_tag = None;
+        ui.columns(2, |columns| {
+            match self.selected_bucket.and_then(|i| histogram.buckets.get(i)) {
+                Some(bucket) => {
+                    columns[0].strong(format!("{} ({} tags)", bucket.label, bucket.tags.len()));
+                    open_tag = open_tag.or(tag_list(
+                        &mut columns[0],
+                        "field_histogram_bucket",
+                        bucket.tags.iter().map(|t| (*t, None)),
+                    ));
+                }
+                None => {
+                    columns[0].weak("No bar selected");
+                }
+            }
+
+            columns[1].strong(format!("Outliers ({})", histogram.outliers.len()));
+            open_tag = open_tag.or(tag_list(
+                &mut columns[1],
+                "field_histogram_outliers",
+                histogram
+                    .outliers
+                    .iter()
+                    .map(|(t, v)| (*t, Some(field_type.format(*v)))),
+            ));
+        });
+
+        open_tag.map(|tag| ViewAction::OpenTagAtOffset(tag, histogram.offset as u64))
+    }
+}
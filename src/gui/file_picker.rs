@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+
+use eframe::egui::{self, RichText};
+use itertools::Itertools;
+
+use crate::util::resolve_user_path;
+
+/// Built-in file picker, used when the native file dialog isn't available (eg. Linux without zenity/kdialog)
+pub struct FilePicker {
+    title: String,
+    directory: PathBuf,
+    /// (name, is directory)
+    entries: Vec<(String, bool)>,
+    path_input: String,
+    open: bool,
+}
+
+impl FilePicker {
+    pub fn new(title: &str) -> Self {
+        let mut picker = Self {
+            title: title.to_string(),
+            directory: PathBuf::new(),
+            entries: vec![],
+            path_input: String::new(),
+            open: true,
+        };
+
+        picker.navigate(std::env::current_dir().unwrap_or_default());
+        picker
+    }
+
+    fn navigate(&mut self, directory: PathBuf) {
+        self.entries = std::fs::read_dir(&directory)
+            .map(|rd| {
+                rd.flatten()
+                    .map(|e| {
+                        (
+                            e.file_name().to_string_lossy().to_string(),
+                            e.path().is_dir(),
+                        )
+                    })
+                    .sorted_by_key(|(name, is_dir)| (!is_dir, name.to_lowercase()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        self.path_input = directory.to_string_lossy().to_string();
+        self.directory = directory;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Returns the selected file once the user confirms their selection
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<PathBuf> {
+        let mut open = self.open;
+        let mut navigate_to = None;
+        let mut selected = None;
+
+        egui::Window::new(&self.title)
+            .open(&mut open)
+            .collapsible(false)
+            .default_size([480.0, 360.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.directory.parent().is_some(), egui::Button::new("⬆"))
+                        .on_hover_text("Parent directory")
+                        .clicked()
+                    {
+                        navigate_to = self.directory.parent().map(|p| p.to_path_buf());
+                    }
+
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.path_input)
+                            .desired_width(f32::INFINITY),
+                    );
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        let path = resolve_user_path(&self.path_input);
+                        if path.is_dir() {
+                            navigate_to = Some(path);
+                        } else if path.is_file() {
+                            selected = Some(path);
+                        }
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .max_height(ui.available_height() - 32.0)
+                    .show(ui, |ui| {
+                        if self.entries.is_empty() {
+                            ui.label(RichText::new("Directory is empty or unreadable").italics());
+                        }
+
+                        let input_path = PathBuf::from(self.path_input.trim());
+                        for (name, is_dir) in &self.entries {
+                            let path = self.directory.join(name);
+                            let icon = if *is_dir { "🗀" } else { "🗋" };
+                            let response =
+                                ui.selectable_label(input_path == path, format!("{icon} {name}"));
+
+                            if *is_dir {
+                                if response.clicked() {
+                                    navigate_to = Some(path);
+                                }
+                            } else if response.double_clicked() {
+                                selected = Some(path);
+                            } else if response.clicked() {
+                                self.path_input = path.to_string_lossy().to_string();
+                            }
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let path = resolve_user_path(&self.path_input);
+                    if ui
+                        .add_enabled(path.is_file(), egui::Button::new("Open"))
+                        .clicked()
+                    {
+                        selected = Some(path);
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        self.open = false;
+                    }
+                });
+            });
+
+        if let Some(directory) = navigate_to {
+            self.navigate(directory);
+        }
+
+        self.open &= open && selected.is_none();
+        selected
+    }
+}
@@ -0,0 +1,398 @@
+use std::path::PathBuf;
+
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use anyhow::Context;
+use eframe::egui::{self, pos2, vec2, Color32, RichText, TextureHandle, TextureOptions};
+use image::{Rgba, RgbaImage};
+use itertools::Itertools;
+use log::error;
+use poll_promise::Promise;
+use quicktag_core::tagtypes::TagType;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{common::ResponseExt, View, ViewAction, TOASTS};
+
+const ATLAS_COLUMNS: usize = 64;
+const ATLAS_CELL_SIZE: u32 = 32;
+const ATLAS_FONT_SIZE: f32 = 26.0;
+/// Keeps the atlas within the maximum texture size (4096px high at 64 columns)
+const MAX_ATLAS_GLYPHS: usize = 8192;
+
+/// System fonts used as a fallback for Chinese/Japanese/Korean text, egui doesn't ship with any CJK glyphs
+const CJK_FALLBACK_FONTS: &[&str] = &[
+    "C:/Windows/Fonts/msyh.ttc",
+    "C:/Windows/Fonts/malgun.ttf",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+];
+
+/// Adds any installed CJK fallback fonts to the end of both font families
+pub fn add_cjk_fallback_fonts(fonts: &mut egui::FontDefinitions) {
+    for path in CJK_FALLBACK_FONTS {
+        let Ok(data) = std::fs::read(path) else {
+            continue;
+        };
+
+        let name = format!("cjk_fallback_{path}");
+        fonts
+            .font_data
+            .insert(name.clone(), egui::FontData::from_owned(data));
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            fonts.families.entry(family).or_default().push(name.clone());
+        }
+    }
+}
+
+/// Returns true if the data starts with a TrueType/OpenType signature
+fn is_font_data(data: &[u8]) -> bool {
+    matches!(
+        data.get(0..4),
+        Some(b"OTTO" | b"true" | b"ttcf" | [0x00, 0x01, 0x00, 0x00])
+    )
+}
+
+pub struct FontInfo {
+    pub tag: TagHash,
+    pub font: FontVec,
+    /// Character mapped to each glyph (if any)
+    pub glyph_chars: FxHashMap<u16, char>,
+}
+
+impl FontInfo {
+    fn load(tag: TagHash) -> anyhow::Result<Option<Self>> {
+        let data = package_manager().read_tag(tag)?;
+        if !is_font_data(&data) {
+            return Ok(None);
+        }
+
+        let font = FontVec::try_from_vec(data).context("Failed to parse font")?;
+        let glyph_chars = font
+            .codepoint_ids()
+            .map(|(id, c)| (id.0, c))
+            .collect::<FxHashMap<_, _>>();
+
+        Ok(Some(Self {
+            tag,
+            font,
+            glyph_chars,
+        }))
+    }
+
+    pub fn atlas_glyph_count(&self) -> usize {
+        self.font.glyph_count().min(MAX_ATLAS_GLYPHS)
+    }
+
+    /// Rasterizes every glyph in the font into a grid, in glyph index order
+    pub fn render_atlas(&self) -> RgbaImage {
+        let glyph_count = self.atlas_glyph_count();
+        let rows = glyph_count.div_ceil(ATLAS_COLUMNS).max(1);
+        let mut image = RgbaImage::new(
+            ATLAS_COLUMNS as u32 * ATLAS_CELL_SIZE,
+            rows as u32 * ATLAS_CELL_SIZE,
+        );
+
+        let scaled = self.font.as_scaled(PxScale::from(ATLAS_FONT_SIZE));
+        for i in 0..glyph_count {
+            let cell_x = (i % ATLAS_COLUMNS) as u32 * ATLAS_CELL_SIZE;
+            let cell_y = (i / ATLAS_COLUMNS) as u32 * ATLAS_CELL_SIZE;
+
+            let id = GlyphId(i as u16);
+            let advance = scaled.h_advance(id);
+            let x = cell_x as f32 + ((ATLAS_CELL_SIZE as f32 - advance) / 2.0).max(0.0);
+            let y = cell_y as f32 + (ATLAS_CELL_SIZE as f32 - scaled.height()) / 2.0;
+            let glyph = id.with_scale_and_position(ATLAS_FONT_SIZE, point(x, y + scaled.ascent()));
+
+            if let Some(outlined) = self.font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    let px = bounds.min.x as i32 + gx as i32;
+                    let py = bounds.min.y as i32 + gy as i32;
+                    // Clip glyphs to their own cell
+                    if px < cell_x as i32
+                        || py < cell_y as i32
+                        || px >= (cell_x + ATLAS_CELL_SIZE) as i32
+                        || py >= (cell_y + ATLAS_CELL_SIZE) as i32
+                    {
+                        return;
+                    }
+
+                    let alpha = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+                    let pixel = image.get_pixel_mut(px as u32, py as u32);
+                    *pixel = Rgba([255, 255, 255, pixel.0[3].max(alpha)]);
+                });
+            }
+        }
+
+        image
+    }
+}
+
+pub struct FontsView {
+    fonts: Option<Promise<Vec<FontInfo>>>,
+    selected: Option<usize>,
+    atlas: Option<(TagHash, TextureHandle)>,
+    sample_text: String,
+}
+
+impl FontsView {
+    pub fn new() -> Self {
+        Self {
+            fonts: None,
+            selected: None,
+            atlas: None,
+            sample_text: "The quick brown fox jumps over the lazy dog 0123456789".to_string(),
+        }
+    }
+
+    /// Finds all font tags (OTF/TTF data) in the loaded packages
+    fn find_fonts() -> Vec<FontInfo> {
+        let mut tags = vec![];
+        for (pkg_id, entries) in package_manager().lookup.tag32_entries_by_pkg.iter() {
+            for (i, e) in entries.iter().enumerate() {
+                if TagType::from_type_subtype(e.file_type, e.file_subtype)
+                    == TagType::OtfFontOrUmbraTome
+                {
+                    tags.push(TagHash::new(*pkg_id, i as u16));
+                }
+            }
+        }
+
+        tags.into_iter()
+            .sorted_by_key(|t| t.0)
+            .filter_map(|tag| match FontInfo::load(tag) {
+                Ok(f) => f,
+                Err(e) => {
+                    error!("Failed to load font {tag}: {e:?}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn export_font(font: &FontInfo) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all("fonts")?;
+        let ext = if font.font.as_slice().starts_with(b"OTTO") {
+            "otf"
+        } else {
+            "ttf"
+        };
+        let path = PathBuf::from(format!("fonts/{}.{ext}", font.tag));
+        std::fs::write(&path, font.font.as_slice())?;
+        Ok(path)
+    }
+
+    fn export_atlas(font: &FontInfo) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all("fonts")?;
+        let path = PathBuf::from(format!("fonts/{}_atlas.png", font.tag));
+        font.render_atlas().save(&path)?;
+        Ok(path)
+    }
+
+    fn font_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, font: &FontInfo) {
+        let units_per_em = font.font.units_per_em().unwrap_or(1000.0);
+        egui::Grid::new("font_metrics_grid")
+            .striped(true)
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.strong("Glyphs");
+                ui.label(font.font.glyph_count().to_string());
+                ui.end_row();
+                ui.strong("Mapped characters");
+                ui.label(font.glyph_chars.len().to_string());
+                ui.end_row();
+                ui.strong("Units per em");
+                ui.label(units_per_em.to_string());
+                ui.end_row();
+                ui.strong("Ascent");
+                ui.label(font.font.ascent_unscaled().to_string());
+                ui.end_row();
+                ui.strong("Descent");
+                ui.label(font.font.descent_unscaled().to_string());
+                ui.end_row();
+                ui.strong("Line gap");
+                ui.label(font.font.line_gap_unscaled().to_string());
+                ui.end_row();
+            });
+
+        ui.horizontal(|ui| {
+            if ui.button("Export font").clicked() {
+                match Self::export_font(font) {
+                    Ok(path) => {
+                        TOASTS
+                            .lock()
+                            .success(format!("Font exported to {}", path.display()));
+                    }
+                    Err(e) => {
+                        error!("Failed to export font {}: {e:?}", font.tag);
+                        TOASTS.lock().error("Failed to export font");
+                    }
+                }
+            }
+
+            if ui.button("Export atlas").clicked() {
+                match Self::export_atlas(font) {
+                    Ok(path) => {
+                        TOASTS
+                            .lock()
+                            .success(format!("Atlas exported to {}", path.display()));
+                    }
+                    Err(e) => {
+                        error!("Failed to export font atlas {}: {e:?}", font.tag);
+                        TOASTS.lock().error("Failed to export font atlas");
+                    }
+                }
+            }
+        });
+
+        ui.separator();
+        ui.add(egui::TextEdit::singleline(&mut self.sample_text).desired_width(f32::INFINITY));
+        ui.label(self.sample_glyphs(font));
+        ui.separator();
+
+        if self.atlas.as_ref().map(|(t, _)| *t) != Some(font.tag) {
+            let atlas = font.render_atlas();
+            let image = egui::ColorImage::from_rgba_unmultiplied(
+                [atlas.width() as usize, atlas.height() as usize],
+                atlas.as_raw(),
+            );
+            self.atlas = Some((
+                font.tag,
+                ctx.load_texture(
+                    format!("font_atlas_{}", font.tag),
+                    image,
+                    TextureOptions::LINEAR,
+                ),
+            ));
+        }
+
+        if font.font.glyph_count() > MAX_ATLAS_GLYPHS {
+            ui.label(
+                RichText::new(format!(
+                    "Only the first {MAX_ATLAS_GLYPHS} glyphs are shown in the atlas"
+                ))
+                .italics(),
+            );
+        }
+
+        let Some((_, texture)) = &self.atlas else {
+            return;
+        };
+
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                let size = texture.size_vec2();
+                let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+                painter.rect_filled(response.rect, 0.0, Color32::from_gray(20));
+                painter.image(
+                    texture.id(),
+                    response.rect,
+                    egui::Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+                    Color32::WHITE,
+                );
+
+                if let Some(pos) = response.hover_pos() {
+                    let cell = ((pos - response.rect.min) / ATLAS_CELL_SIZE as f32).floor();
+                    let index = cell.y as usize * ATLAS_COLUMNS + cell.x as usize;
+                    if index < font.atlas_glyph_count() {
+                        let cell_rect = egui::Rect::from_min_size(
+                            response.rect.min + cell * ATLAS_CELL_SIZE as f32,
+                            vec2(ATLAS_CELL_SIZE as f32, ATLAS_CELL_SIZE as f32),
+                        );
+                        painter.rect_stroke(cell_rect, 0.0, (1.0, Color32::LIGHT_RED));
+
+                        let id = GlyphId(index as u16);
+                        let character = font
+                            .glyph_chars
+                            .get(&id.0)
+                            .map(|c| format!("'{c}' (U+{:04X})", *c as u32))
+                            .unwrap_or_else(|| "unmapped".to_string());
+                        response.on_hover_text(format!(
+                            "Glyph {index}\n{character}\nAdvance: {}\nSide bearing: {}",
+                            font.font.h_advance_unscaled(id),
+                            font.font.h_side_bearing_unscaled(id),
+                        ));
+                    }
+                }
+            });
+    }
+
+    /// Describes how the sample text maps to glyphs, flagging characters that aren't in the font
+    fn sample_glyphs(&self, font: &FontInfo) -> String {
+        let missing = self
+            .sample_text
+            .chars()
+            .filter(|c| !c.is_whitespace() && font.font.glyph_id(*c).0 == 0)
+            .unique()
+            .collect::<String>();
+
+        if missing.is_empty() {
+            "All sample characters are present in this font".to_string()
+        } else {
+            format!("Missing characters: {missing}")
+        }
+    }
+}
+
+impl View for FontsView {
+    fn view(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let mut result = None;
+
+        let fonts = self
+            .fonts
+            .get_or_insert_with(|| Promise::spawn_thread("find_fonts", Self::find_fonts));
+        let Some(fonts) = fonts.ready() else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Searching for fonts");
+            });
+            return None;
+        };
+
+        if fonts.is_empty() {
+            ui.label(RichText::new("No font tags found").italics());
+            return None;
+        }
+
+        // Taking the promise out of self so we can borrow the selected font while mutating the view
+        let promise = self.fonts.take().unwrap();
+        let fonts = promise.ready().unwrap();
+
+        egui::SidePanel::left("fonts_left_panel")
+            .resizable(true)
+            .min_width(192.0)
+            .show_inside(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for (i, font) in fonts.iter().enumerate() {
+                            let response = ui
+                                .selectable_label(
+                                    self.selected == Some(i),
+                                    format!("{} ({} glyphs)", font.tag, font.font.glyph_count()),
+                                )
+                                .tag_context(font.tag);
+
+                            if response.clicked() {
+                                self.selected = Some(i);
+                            }
+                            if response.double_clicked() {
+                                result = Some(ViewAction::OpenTag(font.tag));
+                            }
+                        }
+                    });
+            });
+
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            if let Some(font) = self.selected.and_then(|i| fonts.get(i)) {
+                self.font_ui(ctx, ui, font);
+            } else {
+                ui.label(RichText::new("No font selected").italics());
+            }
+        });
+
+        self.fonts = Some(promise);
+        result
+    }
+}
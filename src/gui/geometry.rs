@@ -0,0 +1,302 @@
+use std::path::PathBuf;
+
+use eframe::egui::{self, pos2, vec2, Color32, RichText, Sense, Stroke};
+use eframe::egui_wgpu::RenderState;
+use itertools::Itertools;
+use poll_promise::Promise;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::tag::format_tag_entry;
+use super::TOASTS;
+use crate::geometry::{GeometryBuffers, Mesh};
+use crate::gltf::{export_gltf, GltfExportSummary};
+
+/// Triangles beyond this are not drawn, egui isn't made for drawing millions of lines
+const MAX_TRIANGLES: usize = 65536;
+const MAX_POINTS: usize = 32768;
+
+/// Untextured wireframe/point preview of vertex and index buffers referenced by a tag
+pub struct GeometryPreview {
+    tag: TagHash,
+    render_state: RenderState,
+    pub buffers: GeometryBuffers,
+    selected_vertex: usize,
+    selected_index: Option<usize>,
+    mesh: Option<Result<Mesh, String>>,
+    triangle_strip: bool,
+    show_points: bool,
+    yaw: f32,
+    pitch: f32,
+    zoom: f32,
+    export_job: Option<Promise<anyhow::Result<GltfExportSummary>>>,
+}
+
+impl GeometryPreview {
+    pub fn new(tag: TagHash, buffers: GeometryBuffers, render_state: RenderState) -> Self {
+        Self {
+            tag,
+            render_state,
+            selected_index: if buffers.index.is_empty() {
+                None
+            } else {
+                Some(0)
+            },
+            buffers,
+            selected_vertex: 0,
+            mesh: None,
+            triangle_strip: false,
+            show_points: false,
+            yaw: 45f32.to_radians(),
+            pitch: 20f32.to_radians(),
+            zoom: 1.0,
+            export_job: None,
+        }
+    }
+
+    fn start_export(&mut self) {
+        let render_state = self.render_state.clone();
+        let tag = self.tag;
+        let buffers = self.buffers.clone();
+        let vertex_buffer = self.buffers.vertex[self.selected_vertex];
+        let index_buffer = self.selected_index.map(|i| self.buffers.index[i]);
+        let triangle_strip = self.triangle_strip;
+        self.export_job = Some(Promise::spawn_thread("export_gltf", move || {
+            export_gltf(
+                &render_state,
+                tag,
+                &buffers,
+                vertex_buffer,
+                index_buffer,
+                triangle_strip,
+                &PathBuf::from("gltf").join(tag.to_string()),
+            )
+        }));
+    }
+
+    fn poll_export(&mut self) {
+        let Some(job) = self.export_job.take() else {
+            return;
+        };
+
+        match job.try_take() {
+            Ok(Ok(summary)) => {
+                if summary.failed_textures == 0 {
+                    TOASTS.lock().success(format!(
+                        "Exported glTF with {} textures to {}",
+                        summary.textures,
+                        summary.path.display()
+                    ));
+                } else {
+                    TOASTS.lock().warning(format!(
+                        "Exported glTF to {}, {} textures failed (see log)",
+                        summary.path.display(),
+                        summary.failed_textures
+                    ));
+                }
+            }
+            Ok(Err(e)) => {
+                log::error!("Failed to export glTF: {e:?}");
+                TOASTS.lock().error(format!("Failed to export glTF: {e}"));
+            }
+            Err(job) => self.export_job = Some(job),
+        }
+    }
+
+    fn buffer_label(tag: TagHash) -> String {
+        format_tag_entry(tag, package_manager().get_entry(tag).as_ref())
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        if self.buffers.is_empty() {
+            ui.label(RichText::new("No vertex buffers found").italics());
+            return;
+        }
+
+        self.poll_export();
+
+        let mut reload = false;
+        ui.horizontal(|ui| {
+            ui.label("Vertices");
+            egui::ComboBox::from_id_source("geometry_vertex_buffer")
+                .selected_text(Self::buffer_label(
+                    self.buffers.vertex[self.selected_vertex],
+                ))
+                .show_ui(ui, |ui| {
+                    for (i, &tag) in self.buffers.vertex.iter().enumerate() {
+                        reload |= ui
+                            .selectable_value(&mut self.selected_vertex, i, Self::buffer_label(tag))
+                            .changed();
+                    }
+                });
+
+            ui.label("Indices");
+            egui::ComboBox::from_id_source("geometry_index_buffer")
+                .selected_text(
+                    self.selected_index
+                        .map(|i| Self::buffer_label(self.buffers.index[i]))
+                        .unwrap_or_else(|| "None".to_string()),
+                )
+                .show_ui(ui, |ui| {
+                    reload |= ui
+                        .selectable_value(&mut self.selected_index, None, "None")
+                        .changed();
+                    for (i, &tag) in self.buffers.index.iter().enumerate() {
+                        reload |= ui
+                            .selectable_value(
+                                &mut self.selected_index,
+                                Some(i),
+                                Self::buffer_label(tag),
+                            )
+                            .changed();
+                    }
+                });
+
+            ui.checkbox(&mut self.triangle_strip, "Triangle strip");
+            ui.checkbox(&mut self.show_points, "Points");
+            if ui.button("Reset view").clicked() {
+                self.yaw = 45f32.to_radians();
+                self.pitch = 20f32.to_radians();
+                self.zoom = 1.0;
+            }
+
+            ui.separator();
+            if self.export_job.is_some() {
+                ui.spinner();
+                ui.label("Exporting glTF...");
+                ui.ctx().request_repaint();
+            } else if ui
+                .button("Export glTF")
+                .on_hover_text(
+                    "Exports the selected buffers and the textures referenced by this tag to gltf/<tag>/",
+                )
+                .clicked()
+            {
+                self.start_export();
+            }
+        });
+
+        if reload {
+            self.mesh = None;
+        }
+
+        let vertex_buffer = self.buffers.vertex[self.selected_vertex];
+        let index_buffer = self.selected_index.map(|i| self.buffers.index[i]);
+        let mesh = self.mesh.get_or_insert_with(|| {
+            Mesh::load(
+                vertex_buffer,
+                index_buffer,
+                package_manager().version.endian(),
+            )
+            .map_err(|e| format!("{e:?}"))
+        });
+
+        let mesh = match mesh {
+            Ok(mesh) => mesh,
+            Err(e) => {
+                ui.colored_label(Color32::RED, format!("Failed to load geometry: {e}"));
+                return;
+            }
+        };
+
+        let triangles = mesh.triangles(self.triangle_strip, MAX_TRIANGLES);
+        ui.label(format!(
+            "{} vertices (stride {}, type {}), {} indices, {} triangles{}",
+            mesh.positions.len(),
+            mesh.stride,
+            mesh.vertex_type,
+            mesh.indices.len(),
+            triangles.len(),
+            if triangles.len() == MAX_TRIANGLES {
+                " (truncated)"
+            } else {
+                ""
+            }
+        ));
+        ui.weak("Drag to rotate, scroll to zoom");
+
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        if response.dragged() {
+            let delta = response.drag_delta();
+            self.yaw += delta.x * 0.01;
+            self.pitch = (self.pitch + delta.y * 0.01).clamp(-1.5, 1.5);
+        }
+        if response.hovered() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            self.zoom = (self.zoom * (1.0 + scroll * 0.002)).clamp(0.1, 20.0);
+        }
+
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        let scale = rect.size().min_elem() * 0.45 * self.zoom / mesh.radius;
+        // Destiny is Z-up
+        let project = |p: [f32; 3]| {
+            let [x, y, z] = [
+                p[0] - mesh.center[0],
+                p[1] - mesh.center[1],
+                p[2] - mesh.center[2],
+            ];
+            let rx = x * cos_yaw - y * sin_yaw;
+            let ry = x * sin_yaw + y * cos_yaw;
+            let sy = z * cos_pitch - ry * sin_pitch;
+            let depth = z * sin_pitch + ry * cos_pitch;
+            (
+                rect.center() + vec2(rx, -sy) * scale,
+                (depth / mesh.radius * 0.5 + 0.5).clamp(0.0, 1.0),
+            )
+        };
+        let shade = |depth: f32| {
+            Color32::from_rgb(
+                (80.0 + 100.0 * depth) as u8,
+                (140.0 + 80.0 * depth) as u8,
+                (200.0 + 55.0 * depth) as u8,
+            )
+        };
+
+        let painter = painter.with_clip_rect(rect);
+        let mut shapes = vec![];
+        if !self.show_points && !triangles.is_empty() {
+            let projected = mesh.positions.iter().map(|&p| project(p)).collect_vec();
+            for t in &triangles {
+                for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])] {
+                    let (pa, da) = projected[a as usize];
+                    let (pb, db) = projected[b as usize];
+                    shapes.push(egui::Shape::line_segment(
+                        [pa, pb],
+                        Stroke::new(1.0, shade((da + db) / 2.0)),
+                    ));
+                }
+            }
+        } else {
+            let step = (mesh.positions.len() / MAX_POINTS).max(1);
+            for &p in mesh.positions.iter().step_by(step) {
+                let (pos, depth) = project(p);
+                shapes.push(egui::Shape::rect_filled(
+                    egui::Rect::from_center_size(pos, vec2(2.0, 2.0)),
+                    0.0,
+                    shade(depth),
+                ));
+            }
+        }
+        painter.extend(shapes);
+
+        // Axis gizmo
+        let origin = pos2(rect.left() + 32.0, rect.bottom() - 32.0);
+        for (axis, color) in [
+            ([1.0, 0.0, 0.0], Color32::RED),
+            ([0.0, 1.0, 0.0], Color32::GREEN),
+            ([0.0, 0.0, 1.0], Color32::LIGHT_BLUE),
+        ] {
+            let [x, y, z]: [f32; 3] = axis;
+            let rx = x * cos_yaw - y * sin_yaw;
+            let ry = x * sin_yaw + y * cos_yaw;
+            let sy = z * cos_pitch - ry * sin_pitch;
+            painter.line_segment(
+                [origin, origin + vec2(rx, -sy) * 20.0],
+                Stroke::new(2.0, color),
+            );
+        }
+    }
+}
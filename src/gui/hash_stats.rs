@@ -0,0 +1,273 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use eframe::egui::{self, RichText};
+use egui_extras::{Column, TableBuilder};
+use itertools::Itertools;
+use poll_promise::Promise;
+use quicktag_core::{classes::get_class_by_id, tagtypes::TagType};
+use quicktag_scanner::{
+    context::ScannerContext,
+    hashes::{string_hash_stats, StringHashStats},
+    TagCache,
+};
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use tiger_pkg::package_manager;
+
+use super::{View, ViewAction};
+use crate::crash_context;
+
+#[derive(Default)]
+pub struct HashStatsProgress {
+    pub current: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancel: AtomicBool,
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct GroupStats {
+    pub tags: usize,
+    pub hashes: StringHashStats,
+}
+
+#[derive(Default)]
+pub struct HashHitRates {
+    pub total: StringHashStats,
+    pub by_package: FxHashMap<u16, GroupStats>,
+    /// Keyed by class reference
+    pub by_class: FxHashMap<u32, GroupStats>,
+}
+
+/// Counts known and unknown string hash candidates in every scanned tag, grouped by package and class
+pub fn compute_hash_hit_rates(
+    context: &ScannerContext,
+    cache: &TagCache,
+    progress: &HashStatsProgress,
+) -> HashHitRates {
+    let _context = crash_context::push(crash_context::JOB, "Computing string hash hit rates");
+    let tags = cache
+        .hashes
+        .iter()
+        .filter(|(_, scan)| scan.successful)
+        .map(|(tag, _)| *tag)
+        .collect_vec();
+
+    progress.total.store(tags.len(), Ordering::Relaxed);
+    progress.current.store(0, Ordering::Relaxed);
+
+    let per_tag: Vec<_> = tags
+        .par_iter()
+        .filter_map(|&tag| {
+            if progress.cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            progress.current.fetch_add(1, Ordering::Relaxed);
+            let entry = package_manager().get_entry(tag)?;
+            if !matches!(
+                TagType::from_type_subtype(entry.file_type, entry.file_subtype),
+                TagType::Tag | TagType::TagGlobal
+            ) {
+                return None;
+            }
+
+            let data = package_manager().read_tag(tag).ok()?;
+            Some((tag, entry.reference, string_hash_stats(context, &data)))
+        })
+        .collect();
+
+    let mut rates = HashHitRates::default();
+    for (tag, reference, stats) in per_tag {
+        rates.total.add(stats);
+        for group in [
+            rates.by_package.entry(tag.pkg_id()).or_default(),
+            rates.by_class.entry(reference).or_default(),
+        ] {
+            group.tags += 1;
+            group.hashes.add(stats);
+        }
+    }
+
+    rates
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum GroupBy {
+    Package,
+    Class,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum SortBy {
+    Unknown,
+    HitRate,
+}
+
+pub struct HashStatsView {
+    context: Arc<ScannerContext>,
+    cache: Arc<TagCache>,
+    progress: Arc<HashStatsProgress>,
+    rates: Option<Promise<HashHitRates>>,
+    group_by: GroupBy,
+    sort_by: SortBy,
+}
+
+impl HashStatsView {
+    pub fn new(context: Arc<ScannerContext>, cache: Arc<TagCache>) -> Self {
+        Self {
+            context,
+            cache,
+            progress: Default::default(),
+            rates: None,
+            group_by: GroupBy::Class,
+            sort_by: SortBy::Unknown,
+        }
+    }
+
+    fn group_name(&self, key: u32) -> String {
+        match self.group_by {
+            GroupBy::Package => package_manager()
+                .package_paths
+                .get(&(key as u16))
+                .map(|p| p.filename.clone())
+                .unwrap_or_else(|| format!("{key:04X}")),
+            GroupBy::Class => get_class_by_id(key)
+                .map(|c| format!("{} ({key:08X})", c.name))
+                .unwrap_or_else(|| format!("{key:08X}")),
+        }
+    }
+}
+
+impl View for HashStatsView {
+    fn view(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let is_running = self.rates.as_ref().is_some_and(|p| p.ready().is_none());
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!is_running, egui::Button::new("Compute hit rates"))
+                .on_hover_text("Reads every scanned tag, so this can take a while")
+                .clicked()
+            {
+                self.progress = Default::default();
+                let progress = self.progress.clone();
+                let context = self.context.clone();
+                let cache = self.cache.clone();
+                self.rates = Some(Promise::spawn_thread("compute_hash_hit_rates", move || {
+                    compute_hash_hit_rates(&context, &cache, &progress)
+                }));
+            }
+
+            if is_running && ui.button("Cancel").clicked() {
+                self.progress.cancel.store(true, Ordering::Relaxed);
+            }
+
+            ui.separator();
+            ui.label("Group by");
+            ui.selectable_value(&mut self.group_by, GroupBy::Class, "Class");
+            ui.selectable_value(&mut self.group_by, GroupBy::Package, "Package");
+            ui.separator();
+            ui.label("Sort by");
+            ui.selectable_value(&mut self.sort_by, SortBy::Unknown, "Unknown hashes");
+            ui.selectable_value(&mut self.sort_by, SortBy::HitRate, "Hit rate");
+        });
+        ui.weak("Unknown hashes are values that look like string hashes, but aren't in the string cache or wordlist. This is a heuristic, so expect some noise");
+        ui.separator();
+
+        let Some(rates) = self.rates.as_ref() else {
+            ui.label(RichText::new("Hit rates have not been computed yet").italics());
+            return None;
+        };
+
+        let Some(rates) = rates.ready() else {
+            let current = self.progress.current.load(Ordering::Relaxed);
+            let total = self.progress.total.load(Ordering::Relaxed).max(1);
+            ui.add(
+                egui::ProgressBar::new(current as f32 / total as f32)
+                    .animate(true)
+                    .text(format!("Scanning tags {current}/{total}")),
+            );
+            ctx.request_repaint();
+            return None;
+        };
+
+        ui.label(format!(
+            "{} known, {} unknown, {:.1}% hit rate{}",
+            rates.total.known,
+            rates.total.unknown,
+            rates.total.hit_rate() * 100.0,
+            if self.progress.cancel.load(Ordering::Relaxed) {
+                " (cancelled, results are incomplete)"
+            } else {
+                ""
+            }
+        ));
+
+        let groups = match self.group_by {
+            GroupBy::Package => rates
+                .by_package
+                .iter()
+                .map(|(k, v)| (*k as u32, *v))
+                .collect_vec(),
+            GroupBy::Class => rates.by_class.iter().map(|(k, v)| (*k, *v)).collect_vec(),
+        };
+        let groups = match self.sort_by {
+            SortBy::Unknown => groups
+                .into_iter()
+                .sorted_by_key(|(_, g)| std::cmp::Reverse(g.hashes.unknown))
+                .collect_vec(),
+            SortBy::HitRate => groups
+                .into_iter()
+                .filter(|(_, g)| g.hashes.total() > 0)
+                .sorted_by(|(_, a), (_, b)| a.hashes.hit_rate().total_cmp(&b.hashes.hit_rate()))
+                .collect_vec(),
+        };
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::remainder().at_least(256.0))
+            .column(Column::auto().at_least(64.0))
+            .column(Column::auto().at_least(80.0))
+            .column(Column::auto().at_least(80.0))
+            .column(Column::auto().at_least(64.0))
+            .min_scrolled_height(0.0)
+            .header(20.0, |mut header| {
+                for title in [
+                    match self.group_by {
+                        GroupBy::Package => "Package",
+                        GroupBy::Class => "Class",
+                    },
+                    "Tags",
+                    "Known",
+                    "Unknown",
+                    "Hit rate",
+                ] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, groups.len(), |mut row| {
+                    let (key, group) = groups[row.index()];
+                    row.col(|ui| {
+                        ui.label(self.group_name(key));
+                    });
+                    row.col(|ui| {
+                        ui.label(group.tags.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(group.hashes.known.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(group.hashes.unknown.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.1}%", group.hashes.hit_rate() * 100.0));
+                    });
+                });
+            });
+
+        None
+    }
+}
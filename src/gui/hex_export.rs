@@ -0,0 +1,263 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use anyhow::Context;
+use eframe::egui::{self, Color32};
+use image::{Rgba, RgbaImage};
+use itertools::Itertools;
+use quicktag_core::palette::PaletteColor;
+use quicktag_core::tagtypes::TagType;
+use tiger_pkg::{package_manager, TagHash};
+
+use crate::gui::tag::{format_tag_entry, ExtendedScanResult};
+
+const FONT_SIZE: f32 = 16.0;
+const PADDING: u32 = 12;
+const BACKGROUND: Color32 = Color32::from_rgb(27, 27, 27);
+
+/// A single line of colored text segments
+type Line = Vec<(String, Color32)>;
+
+/// Renders the given (16-byte aligned) region of tag data to an image, including reference highlights and labels
+pub fn render_region_image(
+    tag: TagHash,
+    data: &[u8],
+    range: Range<u64>,
+    scan: &ExtendedScanResult,
+    highlight: Option<u64>,
+) -> anyhow::Result<RgbaImage> {
+    let fonts = egui::FontDefinitions::default();
+    let font_data = fonts
+        .font_data
+        .get("Hack")
+        .context("Monospace font not found")?;
+    let font = FontRef::try_from_slice(&font_data.font).context("Failed to load font")?;
+    let scaled = font.as_scaled(PxScale::from(FONT_SIZE));
+    let char_width = scaled.h_advance(font.glyph_id('0'));
+    let line_height = scaled.height().ceil() + 2.0;
+
+    let lines = region_lines(tag, data, range, scan, highlight);
+    let max_chars = lines
+        .iter()
+        .map(|(l, _)| l.iter().map(|(s, _)| s.chars().count()).sum::<usize>())
+        .max()
+        .unwrap_or_default();
+
+    let width = (max_chars as f32 * char_width).ceil() as u32 + PADDING * 2;
+    let height = (lines.len() as f32 * line_height).ceil() as u32 + PADDING * 2;
+    let mut image = RgbaImage::from_pixel(width, height, Rgba(BACKGROUND.to_array()));
+
+    for (i, (line, highlighted_chars)) in lines.iter().enumerate() {
+        let y = PADDING as f32 + i as f32 * line_height;
+
+        if let Some(chars) = highlighted_chars {
+            let x0 = PADDING as f32 + chars.start as f32 * char_width;
+            let x1 = PADDING as f32 + chars.end as f32 * char_width;
+            draw_rect_outline(
+                &mut image,
+                x0 - 2.0,
+                y - 1.0,
+                x1 + 2.0,
+                y + line_height - 1.0,
+            );
+        }
+
+        let mut x = PADDING as f32;
+        for (text, color) in line {
+            for c in text.chars() {
+                let glyph = font
+                    .glyph_id(c)
+                    .with_scale_and_position(FONT_SIZE, point(x, y + scaled.ascent()));
+                if let Some(outlined) = font.outline_glyph(glyph) {
+                    let bounds = outlined.px_bounds();
+                    outlined.draw(|gx, gy, coverage| {
+                        let px = bounds.min.x as i32 + gx as i32;
+                        let py = bounds.min.y as i32 + gy as i32;
+                        blend_pixel(&mut image, px, py, *color, coverage);
+                    });
+                }
+
+                x += char_width;
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+/// Builds the lines for a region, along with the character range of the highlighted chunk (if any)
+fn region_lines(
+    tag: TagHash,
+    data: &[u8],
+    range: Range<u64>,
+    scan: &ExtendedScanResult,
+    highlight: Option<u64>,
+) -> Vec<(Line, Option<Range<usize>>)> {
+    let entry = package_manager().get_entry(tag);
+    let mut lines = vec![
+        (
+            vec![(format_tag_entry(tag, entry.as_ref()), Color32::WHITE)],
+            None,
+        ),
+        (vec![], None),
+    ];
+
+    let start = (range.start as usize).min(data.len()) & !0xf;
+    let end = (range.end as usize).min(data.len());
+    for (i, row) in data[start..end].chunks(16).enumerate() {
+        let offset = start + i * 16;
+        let mut line: Line = vec![(format!("{offset:08X}:  "), Color32::WHITE)];
+        let mut highlighted_chars = None;
+        let mut labels = vec![];
+        // Length of "00000000:  "
+        let mut column = 11;
+
+        for (ci, chunk) in row.chunks(4).enumerate() {
+            let chunk_offset = offset + ci * 4;
+            let hash = scan
+                .file_hashes
+                .iter()
+                .find(|v| v.offset == chunk_offset as u64);
+
+            let color = if hash.is_some() {
+                PaletteColor::TagReference.color()
+            } else {
+                PaletteColor::RawBytes.color()
+            };
+
+            let text = chunk.iter().map(|b| format!("{b:02X}")).join(" ");
+            if highlight.is_some_and(|h| (chunk_offset..chunk_offset + 4).contains(&(h as usize))) {
+                highlighted_chars = Some(column..column + text.len());
+            }
+            column += text.len() + 2;
+            line.push((text, color));
+            line.push(("  ".to_string(), color));
+
+            if let Some(e) = hash {
+                let tagline_color = e
+                    .entry
+                    .as_ref()
+                    .map(|e| {
+                        TagType::from_type_subtype(e.file_type, e.file_subtype).display_color()
+                    })
+                    .unwrap_or(Color32::GRAY);
+
+                labels.push((
+                    format!(
+                        "+{:X}: {}",
+                        chunk_offset - offset,
+                        format_tag_entry(e.hash.hash32(), e.entry.as_ref())
+                    ),
+                    tagline_color,
+                ));
+            }
+        }
+
+        let ascii: String = row
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+            .collect();
+        line.push((ascii, PaletteColor::AsciiText.color()));
+
+        for (i, label) in labels.into_iter().enumerate() {
+            line.push((
+                if i == 0 { "  ; " } else { " | " }.to_string(),
+                Color32::DARK_GRAY,
+            ));
+            line.push(label);
+        }
+
+        lines.push((line, highlighted_chars));
+    }
+
+    lines
+}
+
+/// Formats the given region of tag data as an xxd-style text dump (offset, hex, ascii)
+///
+/// If `annotate` is set, tag references within each row are appended as comments
+pub fn format_region_xxd(
+    tag: TagHash,
+    data: &[u8],
+    range: Range<u64>,
+    scan: &ExtendedScanResult,
+    annotate: bool,
+) -> String {
+    let mut out = String::new();
+    if annotate {
+        let entry = package_manager().get_entry(tag);
+        writeln!(out, "# {}", format_tag_entry(tag, entry.as_ref())).ok();
+    }
+
+    let start = (range.start as usize).min(data.len()) & !0xf;
+    let end = (range.end as usize).min(data.len());
+    for (i, row) in data[start..end].chunks(16).enumerate() {
+        let offset = start + i * 16;
+        let hex = row
+            .chunks(2)
+            .map(|pair| pair.iter().map(|b| format!("{b:02x}")).join(""))
+            .join(" ");
+        let ascii: String = row
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        write!(out, "{offset:08x}: {hex:<39}  {ascii:<16}").ok();
+
+        if annotate {
+            let labels = scan
+                .file_hashes
+                .iter()
+                .filter(|v| (offset as u64..offset as u64 + 16).contains(&v.offset))
+                .map(|v| {
+                    format!(
+                        "+{:X}: {}",
+                        v.offset - offset as u64,
+                        format_tag_entry(v.hash.hash32(), v.entry.as_ref())
+                    )
+                })
+                .join(" | ");
+
+            if !labels.is_empty() {
+                write!(out, "  # {labels}").ok();
+            }
+        }
+
+        writeln!(out).ok();
+    }
+
+    out
+}
+
+fn blend_pixel(image: &mut RgbaImage, x: i32, y: i32, color: Color32, coverage: f32) {
+    if x < 0 || y < 0 || x as u32 >= image.width() || y as u32 >= image.height() {
+        return;
+    }
+
+    let coverage = coverage.clamp(0.0, 1.0);
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    let src = color.to_array();
+    for c in 0..3 {
+        pixel.0[c] = (pixel.0[c] as f32 * (1.0 - coverage) + src[c] as f32 * coverage) as u8;
+    }
+}
+
+fn draw_rect_outline(image: &mut RgbaImage, x0: f32, y0: f32, x1: f32, y1: f32) {
+    let (x0, y0, x1, y1) = (x0 as i32, y0 as i32, x1 as i32, y1 as i32);
+    let color = PaletteColor::LinkedOffset.color();
+    for x in x0..=x1 {
+        blend_pixel(image, x, y0, color, 1.0);
+        blend_pixel(image, x, y1, color, 1.0);
+    }
+    for y in y0..=y1 {
+        blend_pixel(image, x0, y, color, 1.0);
+        blend_pixel(image, x1, y, color, 1.0);
+    }
+}
@@ -0,0 +1,283 @@
+//! Find bar of the hex view, searches the data of the open tag for a byte pattern, text or a number
+
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers, RichText};
+use quicktag_scanner::pattern_search::{BytePattern, PatternKind};
+use tiger_pkg::Endian;
+
+pub const FIND_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::F);
+
+/// Searching stops after this many matches, so a pattern like `00` doesn't take forever to highlight
+const MAX_MATCHES: usize = 10_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SearchKind {
+    Hex,
+    Text,
+    U32,
+    F32,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 4] = [
+        SearchKind::Hex,
+        SearchKind::Text,
+        SearchKind::U32,
+        SearchKind::F32,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchKind::Hex => "Hex bytes",
+            SearchKind::Text => "Text",
+            SearchKind::U32 => "u32",
+            SearchKind::F32 => "f32",
+        }
+    }
+
+    fn hint(&self) -> &'static str {
+        match self.pattern_kind() {
+            Some(kind) => kind.hint(),
+            None => "eg. 0.5, matches values that are very close",
+        }
+    }
+
+    /// Floats are matched with a tolerance, everything else is an exact [`BytePattern`]
+    fn pattern_kind(&self) -> Option<PatternKind> {
+        match self {
+            SearchKind::Hex => Some(PatternKind::Hex),
+            SearchKind::Text => Some(PatternKind::Text),
+            SearchKind::U32 => Some(PatternKind::U32),
+            SearchKind::F32 => None,
+        }
+    }
+}
+
+/// Floats are compared with a small relative tolerance, as values are rarely typed in with every digit
+fn find_f32(data: &[u8], value: f32, endian: Endian, aligned: bool) -> Vec<usize> {
+    let tolerance = (value.abs() * 1e-5).max(1e-6);
+    let step = if aligned { 4 } else { 1 };
+    (0..data.len().saturating_sub(3))
+        .step_by(step)
+        .filter(|&offset| {
+            let bytes: [u8; 4] = data[offset..offset + 4].try_into().unwrap();
+            let found = f32::from_bits(match endian {
+                Endian::Big => u32::from_be_bytes(bytes),
+                Endian::Little => u32::from_le_bytes(bytes),
+            });
+            (found - value).abs() <= tolerance
+        })
+        .take(MAX_MATCHES)
+        .collect()
+}
+
+pub struct HexSearch {
+    pub open: bool,
+    kind: SearchKind,
+    query: String,
+    /// Search in the other byte order than the game's
+    swap_endian: bool,
+    /// Only match numbers at offsets that are a multiple of 4
+    aligned: bool,
+    focus_query: bool,
+
+    /// Start offsets of the matches, sorted
+    matches: Vec<usize>,
+    /// Length of every match
+    match_len: usize,
+    /// Index of the match that was jumped to
+    current: Option<usize>,
+    error: Option<String>,
+}
+
+impl Default for HexSearch {
+    fn default() -> Self {
+        Self {
+            open: false,
+            kind: SearchKind::Hex,
+            query: String::new(),
+            swap_endian: false,
+            aligned: true,
+            focus_query: false,
+            matches: vec![],
+            match_len: 0,
+            current: None,
+            error: None,
+        }
+    }
+}
+
+impl HexSearch {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.focus_query = true;
+    }
+
+    fn endian(&self, game_endian: Endian) -> Endian {
+        match (game_endian, self.swap_endian) {
+            (endian, false) => endian,
+            (Endian::Little, true) => Endian::Big,
+            (Endian::Big, true) => Endian::Little,
+        }
+    }
+
+    fn search(&mut self, data: &[u8], game_endian: Endian) {
+        self.current = None;
+        self.error = None;
+        self.matches.clear();
+        if self.query.trim().is_empty() {
+            return;
+        }
+
+        let endian = self.endian(game_endian);
+        let Some(kind) = self.kind.pattern_kind() else {
+            match self.query.trim().parse::<f32>() {
+                Ok(value) => {
+                    self.matches = find_f32(data, value, endian, self.aligned);
+                    self.match_len = 4;
+                }
+                Err(_) => self.error = Some(format!("'{}' isn't a number", self.query.trim())),
+            }
+            return;
+        };
+
+        match BytePattern::parse(kind, &self.query, endian, self.aligned) {
+            Ok(pattern) => {
+                self.matches = pattern.find(data).take(MAX_MATCHES).collect();
+                self.match_len = pattern.size();
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Whether any match overlaps the given bytes
+    pub fn is_match(&self, offset: usize, len: usize) -> bool {
+        // Every match has the same length, so the match that starts last before the end is the only one to check
+        let i = self.matches.partition_point(|&m| m < offset + len);
+        i > 0 && self.matches[i - 1] + self.match_len > offset
+    }
+
+    /// Moves to the next (or previous) match after `from`, returning its offset
+    fn step(&mut self, forward: bool, from: Option<usize>) -> Option<u64> {
+        if self.matches.is_empty() {
+            return None;
+        }
+
+        let len = self.matches.len();
+        let next = match (self.current, from) {
+            (Some(current), _) if forward => (current + 1) % len,
+            (Some(current), _) => (current + len - 1) % len,
+            // Starts at the cursor, so searching continues from where the user is looking
+            (None, Some(from)) if forward => self.matches.partition_point(|&m| m < from) % len,
+            (None, Some(from)) => (self.matches.partition_point(|&m| m < from) + len - 1) % len,
+            (None, None) if forward => 0,
+            (None, None) => len - 1,
+        };
+        self.current = Some(next);
+        Some(self.matches[next] as u64)
+    }
+
+    /// Draws the find bar. Returns the offset of the match to jump to. `from` is where searching starts when no match
+    /// has been jumped to yet, eg. the cursor
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        data: &[u8],
+        game_endian: Endian,
+        from: Option<u64>,
+    ) -> Option<u64> {
+        let mut jump = None;
+        ui.horizontal(|ui| {
+            let previous = (
+                self.kind,
+                self.query.clone(),
+                self.swap_endian,
+                self.aligned,
+            );
+            egui::ComboBox::from_id_source("hexview_search_kind")
+                .selected_text(self.kind.label())
+                .show_ui(ui, |ui| {
+                    for kind in SearchKind::ALL {
+                        ui.selectable_value(&mut self.kind, kind, kind.label());
+                    }
+                });
+
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text(self.kind.hint())
+                    .desired_width(220.0),
+            );
+            if std::mem::take(&mut self.focus_query) {
+                response.request_focus();
+            }
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            let backwards = ui.input(|i| i.modifiers.shift);
+
+            if matches!(self.kind, SearchKind::U32 | SearchKind::F32) {
+                ui.checkbox(&mut self.aligned, "Aligned")
+                    .on_hover_text("Only match values at offsets that are a multiple of 4");
+                ui.checkbox(&mut self.swap_endian, "Swap byte order")
+                    .on_hover_text("Search for the value in the other byte order than the game's");
+            }
+
+            if previous
+                != (
+                    self.kind,
+                    self.query.clone(),
+                    self.swap_endian,
+                    self.aligned,
+                )
+            {
+                self.search(data, game_endian);
+            }
+
+            let from = from.map(|f| f as usize);
+            if ui
+                .add_enabled(!self.matches.is_empty(), egui::Button::new("⏶"))
+                .on_hover_text("Previous match (shift+enter)")
+                .clicked()
+            {
+                jump = self.step(false, from);
+            }
+            if ui
+                .add_enabled(!self.matches.is_empty(), egui::Button::new("⏷"))
+                .on_hover_text("Next match (enter)")
+                .clicked()
+            {
+                jump = self.step(true, from);
+            }
+            if submitted {
+                jump = self.step(!backwards, from);
+                response.request_focus();
+            }
+
+            match (&self.error, self.current) {
+                (Some(e), _) => {
+                    ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+                }
+                _ if self.query.trim().is_empty() => {}
+                (None, _) if self.matches.is_empty() => {
+                    ui.weak("No matches");
+                }
+                (None, current) => {
+                    let more = if self.matches.len() == MAX_MATCHES {
+                        "+"
+                    } else {
+                        ""
+                    };
+                    match current {
+                        Some(i) => ui.label(format!("{}/{}{more}", i + 1, self.matches.len())),
+                        None => ui.label(format!("{}{more} matches", self.matches.len())),
+                    };
+                }
+            }
+
+            if ui.button("✖").on_hover_text("Close (escape)").clicked()
+                || (response.lost_focus() && ui.input(|i| i.key_pressed(Key::Escape)))
+            {
+                self.open = false;
+            }
+        });
+
+        jump
+    }
+}
@@ -0,0 +1,184 @@
+//! Sidecar files with the hex view markup of a single tag (row display overrides, bookmarks and region labels)
+//!
+//! Sidecars are saved to `sidecars/<tag>.quicktag.json` in the data directory, and next to the exported binary
+//! (`<file>.quicktag.json`) if the tag was exported. They're loaded again when the tag is opened, either from the data
+//! directory or from next to a binary that was opened as an external file.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use lazy_static::lazy_static;
+use log::warn;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use tiger_pkg::TagHash;
+
+use super::common::exported_file;
+use super::hexview::RowMode;
+use super::virtual_tag::VirtualAddress;
+use crate::backups;
+use crate::config;
+
+pub const SIDECARS_DIR: &str = "sidecars";
+const SIDECAR_EXTENSION: &str = "quicktag.json";
+
+lazy_static! {
+    /// Sidecars found next to external files, used over the one in the data directory
+    static ref EXTERNAL_SIDECARS: Mutex<FxHashMap<TagHash, PathBuf>> = Mutex::new(Default::default());
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bookmark {
+    pub offset: u64,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RegionLabel {
+    pub start: u64,
+    pub end: u64,
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RowOverride {
+    pub start: u64,
+    pub end: u64,
+    pub mode: RowMode,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct HexSidecar {
+    /// Tag the markup was made for, as a hex hash
+    pub tag: Option<String>,
+    /// Address of the sub-view the markup was made for, see [`VirtualAddress`]
+    pub address: Option<String>,
+    /// Size of the tag data, to warn about sidecars for a different version of the tag
+    pub size: u64,
+    pub row_overrides: Vec<RowOverride>,
+    pub bookmarks: Vec<Bookmark>,
+    pub regions: Vec<RegionLabel>,
+}
+
+impl HexSidecar {
+    pub fn tag(&self) -> Option<TagHash> {
+        u32::from_str_radix(self.tag.as_deref()?, 16)
+            .ok()
+            .map(TagHash)
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data).with_context(|| format!("Invalid sidecar {}", path.display()))
+    }
+
+    fn write(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Path of the sidecar next to a binary file
+pub fn sidecar_path(binary: &Path) -> PathBuf {
+    let mut name = binary.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{SIDECAR_EXTENSION}"));
+    binary.with_file_name(name)
+}
+
+/// Path of the sidecar of a tag, relative to the data directory
+pub fn tag_sidecar_name(tag: TagHash) -> PathBuf {
+    Path::new(SIDECARS_DIR).join(format!("{:08X}.{SIDECAR_EXTENSION}", tag.0))
+}
+
+fn tag_sidecar_path(tag: TagHash) -> PathBuf {
+    config::data_path(tag_sidecar_name(tag))
+}
+
+/// Path of the sidecar of a sub-view, relative to the data directory
+fn virtual_sidecar_name(address: &VirtualAddress) -> PathBuf {
+    Path::new(SIDECARS_DIR).join(format!("{address}.{SIDECAR_EXTENSION}"))
+}
+
+/// Saves the sidecar of a tag, returning the paths it was written to
+pub fn export(tag: TagHash, sidecar: &HexSidecar) -> anyhow::Result<Vec<PathBuf>> {
+    backups::backup_before_write(tag_sidecar_name(tag));
+    let mut paths = vec![tag_sidecar_path(tag)];
+    if let Some(binary) = exported_file(tag) {
+        paths.push(sidecar_path(&binary));
+    }
+
+    for path in &paths {
+        sidecar.write(path)?;
+    }
+
+    Ok(paths)
+}
+
+/// Saves the sidecar of a sub-view, returning the path it was written to
+pub fn export_virtual(
+    address: &VirtualAddress,
+    sidecar: &HexSidecar,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let name = virtual_sidecar_name(address);
+    backups::backup_before_write(&name);
+    let path = config::data_path(name);
+    sidecar.write(&path)?;
+    Ok(vec![path])
+}
+
+/// Loads the sidecar of a sub-view
+pub fn load_virtual(address: &VirtualAddress) -> Option<HexSidecar> {
+    let path = config::data_path(virtual_sidecar_name(address));
+    if !path.exists() {
+        return None;
+    }
+
+    HexSidecar::read(&path)
+        .inspect_err(|e| warn!("Failed to read sidecar {}: {e:?}", path.display()))
+        .ok()
+}
+
+/// Loads the sidecar for a tag, preferring one next to an external file over the one in the data directory
+pub fn load(tag: TagHash) -> Option<HexSidecar> {
+    let external = EXTERNAL_SIDECARS.lock().get(&tag).cloned();
+    let candidates = external
+        .into_iter()
+        .chain(exported_file(tag).map(|binary| sidecar_path(&binary)))
+        .chain([tag_sidecar_path(tag)]);
+
+    for path in candidates.filter(|p| p.exists()) {
+        match HexSidecar::read(&path) {
+            Ok(sidecar) => return Some(sidecar),
+            Err(e) => warn!("Failed to read sidecar {}: {e:?}", path.display()),
+        }
+    }
+
+    None
+}
+
+/// Looks for a sidecar next to an external file, so it's used when its tag is opened. Returns the sidecar if found
+pub fn register_external(binary: &Path) -> Option<HexSidecar> {
+    let path = sidecar_path(binary);
+    if !path.exists() {
+        return None;
+    }
+
+    match HexSidecar::read(&path) {
+        Ok(sidecar) => {
+            if let Some(tag) = sidecar.tag() {
+                EXTERNAL_SIDECARS.lock().insert(tag, path);
+            }
+            Some(sidecar)
+        }
+        Err(e) => {
+            warn!("Failed to read sidecar {}: {e:?}", path.display());
+            None
+        }
+    }
+}
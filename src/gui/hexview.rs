@@ -1,23 +1,57 @@
+use crate::compression::{self, CompressedBlob, Compression};
+use crate::config::{self, HexViewSettings};
 use crate::gui::common::ResponseExt;
+use crate::gui::data_inspector;
+use crate::gui::enum_editor;
+use crate::gui::hex_export::{format_region_xxd, render_region_image};
+use crate::gui::hex_search::{HexSearch, FIND_SHORTCUT};
+use crate::gui::hex_sidecar::{self, Bookmark, HexSidecar, RegionLabel, RowOverride};
+use crate::gui::region_hashes::RegionHashWindow;
+use crate::gui::struct_stubs::{self, StructSample, StubLanguage};
 use crate::gui::tag::{format_tag_entry, ExtendedScanResult};
+use crate::gui::virtual_tag::{VirtualAddress, VirtualTag};
+use crate::gui::TOASTS;
 use crate::swap_to_ne;
 use binrw::{binread, BinReaderExt, Endian};
 use eframe::egui;
 use eframe::egui::{
-    collapsing_header::CollapsingState, vec2, Color32, CursorIcon, Rgba, RichText, ScrollArea,
-    Sense, Stroke, Ui,
+    collapsing_header::CollapsingState, vec2, Align, Color32, CursorIcon, EventFilter, Key, Rgba,
+    RichText, ScrollArea, Sense, Shape, Stroke, Ui, WidgetInfo, WidgetType,
 };
 use itertools::Itertools;
-use log::warn;
-use quicktag_core::classes::get_class_by_id;
+use log::{error, warn};
+use quicktag_core::classes::{get_class_by_id, get_enums, EnumField};
+use quicktag_core::palette::PaletteColor;
 use quicktag_core::tagtypes::TagType;
+use quicktag_core::util::u32_from_endian;
+use quicktag_strings::localized::StringCache;
+use serde::{Deserialize, Serialize};
 use std::io::{Cursor, Seek, SeekFrom};
+use std::ops::Range;
+use std::sync::Arc;
 use tiger_pkg::package_manager;
 use tiger_pkg::{DestinyVersion, GameVersion, TagHash, Version};
 
 pub struct TagHexView {
+    tag: TagHash,
+    /// Class of the tag's own struct, for the enums declared on it
+    class: Option<u32>,
+    /// For string hashes in the data inspector
+    strings: Arc<StringCache>,
     data: Vec<u8>,
+    /// Length of the data before it was padded to whole rows
+    data_len: usize,
     rows: Vec<DataRow>,
+    /// Float detection settings the rows were built with
+    row_settings: HexViewSettings,
+    /// Regions forced to a display mode, later entries take precedence
+    row_overrides: Vec<(Range<u64>, RowMode)>,
+    /// Named offsets, sorted by offset
+    bookmarks: Vec<Bookmark>,
+    /// Labeled regions, sorted by start offset
+    region_labels: Vec<RegionLabel>,
+    /// Text for the next region label
+    region_label_input: String,
     array_ranges: Vec<ArrayRange>,
     refresh_collapsible_states: bool,
 
@@ -25,28 +59,870 @@ pub struct TagHexView {
     // detect_floats: bool,
     split_arrays: bool,
     raw_array_data: bool,
+    /// Show rows that look like positions/quaternions as vectors in an extra column
+    show_vectors: bool,
+    text_encoding: TextEncoding,
+    /// Decoded character for every byte of the data, in the current text encoding
+    text_cells: Vec<TextCell>,
+
+    /// Offset highlighted through `hash@offset` links
+    highlight_offset: Option<u64>,
+    scroll_to_highlight: bool,
+
+    /// Selected rows (start..end), selected by clicking/shift-clicking row offsets
+    selection: Option<(u64, u64)>,
+    /// Offset of the 4-byte lane under the keyboard cursor, moved with the arrow keys
+    cursor: Option<u64>,
+    /// Set when the cursor moved, so its lane takes focus (and is read out by screen readers)
+    cursor_moved: bool,
+    /// Widget of the lane under the cursor in the last frame
+    cursor_focus: Option<egui::Id>,
+    /// Include tag reference comments when copying a region as text
+    annotate_text_dump: bool,
+    search: HexSearch,
+
+    region_hashes: Option<RegionHashWindow>,
+    /// Compressed streams found in the data, with the decompressed size entered for each
+    compressed_blobs: Vec<(CompressedBlob, String)>,
+    /// Name of the data in labels of virtual tags opened from this view, the tag hash unless this is a virtual tag
+    label: String,
+    /// Address of the data, unless it's derived from a tag (eg. decompressed). See [`VirtualAddress`]
+    base_address: Option<VirtualAddress>,
+    /// Decompressed blob or sub-view to be opened as a virtual tag by the owner of the view
+    virtual_tag: Option<VirtualTag>,
+
+    /// Data of the corresponding tag in a linked instance, bytes that differ from it are highlighted
+    compare_data: Option<Vec<u8>>,
+    /// Describes where the compared data came from
+    compare_label: String,
+    /// Offsets of the rows that differ from the compared data
+    diff_rows: Vec<u64>,
 }
 
 impl TagHexView {
-    pub fn new(mut data: Vec<u8>) -> Self {
+    pub fn new(tag: TagHash, mut data: Vec<u8>, strings: Arc<StringCache>) -> Self {
+        let data_len = data.len();
         // Pad data to an alignment of 16 bytes
         let remainder = data.len() % 16;
         if remainder != 0 {
             data.extend(vec![0; 16 - remainder]);
         }
 
-        Self {
-            rows: data
-                .chunks_exact(16)
-                .map(|chunk| DataRow::from(<[u8; 16]>::try_from(chunk).unwrap()))
-                .collect(),
+        let row_settings = config::with(|c| c.hex_view.clone());
+        let text_cells = decode_text_cells(&data, TextEncoding::Ascii);
+        let compressed_blobs = compression::find_compressed_blobs(
+            &data[..data_len],
+            package_manager().version.endian(),
+        )
+        .into_iter()
+        .map(|b| {
+            let size = b.size_hint.map(|s| s.to_string()).unwrap_or_default();
+            (b, size)
+        })
+        .collect();
+        let mut view = Self {
+            rows: build_rows(&data, &row_settings, &[]),
+            row_settings,
+            row_overrides: vec![],
+            bookmarks: vec![],
+            region_labels: vec![],
+            region_label_input: String::new(),
             array_ranges: find_all_array_ranges(&data),
             refresh_collapsible_states: true,
+            class: package_manager().get_entry(tag).map(|e| e.reference),
+            strings,
+            tag,
             data,
+            data_len,
             // mode: DataViewMode::Auto,
             // detect_floats: true,
             split_arrays: true,
             raw_array_data: false,
+            show_vectors: false,
+            text_encoding: TextEncoding::Ascii,
+            text_cells,
+            highlight_offset: None,
+            scroll_to_highlight: false,
+            selection: None,
+            cursor: None,
+            cursor_moved: false,
+            cursor_focus: None,
+            search: HexSearch::default(),
+            annotate_text_dump: true,
+            region_hashes: None,
+            compressed_blobs,
+            label: tag.to_string(),
+            base_address: (tag != TagHash::NONE).then_some(VirtualAddress {
+                tag,
+                range: 0..data_len,
+            }),
+            virtual_tag: None,
+            compare_data: None,
+            compare_label: String::new(),
+            diff_rows: vec![],
+        };
+
+        if let Some(sidecar) = (tag != TagHash::NONE)
+            .then(|| hex_sidecar::load(tag))
+            .flatten()
+        {
+            view.apply_sidecar(sidecar);
+        }
+
+        view
+    }
+
+    /// Replaces the row overrides, bookmarks and region labels with the ones from a sidecar
+    fn apply_sidecar(&mut self, sidecar: HexSidecar) {
+        if sidecar.size != 0 && sidecar.size > self.data.len() as u64 {
+            warn!(
+                "Sidecar for {} was made for 0x{:X} bytes of data, the tag only has 0x{:X}",
+                self.tag,
+                sidecar.size,
+                self.data.len()
+            );
+        }
+
+        self.row_overrides = sidecar
+            .row_overrides
+            .into_iter()
+            .map(|o| (o.start..o.end, o.mode))
+            .collect();
+        self.bookmarks = sidecar.bookmarks;
+        self.bookmarks.sort_by_key(|b| b.offset);
+        self.region_labels = sidecar.regions;
+        self.region_labels.sort_by_key(|r| r.start);
+        self.rebuild_rows();
+    }
+
+    fn to_sidecar(&self) -> HexSidecar {
+        let tag = self.base_address.as_ref().map_or(self.tag, |a| a.tag);
+        HexSidecar {
+            tag: Some(format!("{:08X}", tag.0)),
+            address: self
+                .base_address
+                .as_ref()
+                .filter(|_| self.tag == TagHash::NONE)
+                .map(|a| a.to_string()),
+            size: self.data.len() as u64,
+            row_overrides: self
+                .row_overrides
+                .iter()
+                .map(|(range, mode)| RowOverride {
+                    start: range.start,
+                    end: range.end,
+                    mode: *mode,
+                })
+                .collect(),
+            bookmarks: self.bookmarks.clone(),
+            regions: self.region_labels.clone(),
+        }
+    }
+
+    fn export_sidecar(&self) {
+        let result = match &self.base_address {
+            Some(address) if self.tag == TagHash::NONE => {
+                hex_sidecar::export_virtual(address, &self.to_sidecar())
+            }
+            _ => hex_sidecar::export(self.tag, &self.to_sidecar()),
+        };
+        match result {
+            Ok(paths) => {
+                let paths = paths.iter().map(|p| p.display().to_string()).join(", ");
+                TOASTS.lock().success(format!("Sidecar saved to {paths}"));
+            }
+            Err(e) => {
+                error!("Failed to save sidecar: {e:?}");
+                TOASTS.lock().error("Failed to save sidecar");
+            }
+        }
+    }
+
+    fn add_bookmark(&mut self, offset: u64) {
+        if self.bookmarks.iter().any(|b| b.offset == offset) {
+            return;
+        }
+
+        self.bookmarks.push(Bookmark {
+            offset,
+            name: format!("0x{offset:X}"),
+        });
+        self.bookmarks.sort_by_key(|b| b.offset);
+    }
+
+    /// Bookmarks, region labels and saving them to a sidecar file
+    fn markup_toolbar(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            let mut jump_to = None;
+            let mut remove_bookmark = None;
+            ui.add_enabled_ui(!self.bookmarks.is_empty(), |ui| {
+                ui.menu_button(format!("🔖 Bookmarks ({})", self.bookmarks.len()), |ui| {
+                    for (i, bookmark) in self.bookmarks.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.button(format!("0x{:X}", bookmark.offset)).clicked() {
+                                jump_to = Some(bookmark.offset);
+                                ui.close_menu();
+                            }
+                            ui.add(
+                                egui::TextEdit::singleline(&mut bookmark.name)
+                                    .desired_width(160.0),
+                            );
+                            if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                                remove_bookmark = Some(i);
+                            }
+                        });
+                    }
+                });
+            });
+
+            let mut remove_region = None;
+            ui.add_enabled_ui(!self.region_labels.is_empty(), |ui| {
+                ui.menu_button(format!("Regions ({})", self.region_labels.len()), |ui| {
+                    for (i, region) in self.region_labels.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button(format!("0x{:X}..0x{:X}", region.start, region.end))
+                                .clicked()
+                            {
+                                jump_to = Some(region.start);
+                                ui.close_menu();
+                            }
+                            ui.add(
+                                egui::TextEdit::singleline(&mut region.label)
+                                    .desired_width(160.0),
+                            );
+                            if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                                remove_region = Some(i);
+                            }
+                        });
+                    }
+                });
+            });
+
+            if let Some(i) = remove_bookmark {
+                self.bookmarks.remove(i);
+            }
+            if let Some(i) = remove_region {
+                self.region_labels.remove(i);
+            }
+            if let Some(offset) = jump_to {
+                self.highlight_offset(offset);
+            }
+
+            if !self.row_overrides.is_empty() && ui.button("Clear overrides").clicked() {
+                self.row_overrides.clear();
+                self.rebuild_rows();
+            }
+
+            if ui
+                .add_enabled(
+                    self.base_address.is_some(),
+                    egui::Button::new("Save sidecar"),
+                )
+                .on_disabled_hover_text("Decompressed data doesn't have an address to save the sidecar for")
+                .on_hover_text(
+                    "Saves the row modes, bookmarks and region labels of this tag to a sidecar file, next to the \
+                    exported tag data if it was exported. They're loaded again when the tag is opened",
+                )
+                .clicked()
+            {
+                self.export_sidecar();
+            }
+        });
+    }
+
+    /// Every instance of the struct at `offset`: the elements of the array it's in, or the tag's own struct, which is
+    /// assumed to end before the first array unless the class has a known size
+    fn struct_sample(&self, offset: u64) -> Option<StructSample<'_>> {
+        let (class, instances, size) = match self
+            .array_ranges
+            .iter()
+            .find(|a| (a.start..a.end).contains(&offset))
+        {
+            Some(array) => {
+                let stride = get_class_by_id(array.class)?.size.filter(|&s| s > 0)?;
+                let instances = (0..array.length as usize)
+                    .map(|i| array.data_start as usize + i * stride)
+                    .take_while(|&o| o + stride <= self.data_len)
+                    .collect_vec();
+                (array.class, instances, stride)
+            }
+            None => {
+                let class = self.class?;
+                let size = get_class_by_id(class)
+                    .and_then(|c| c.size)
+                    .unwrap_or_else(|| {
+                        self.array_ranges
+                            .first()
+                            .map_or(self.data_len, |a| (a.start as usize).saturating_sub(4))
+                    })
+                    .min(self.data_len);
+                (class, vec![0], size)
+            }
+        };
+        if instances.is_empty() || size == 0 {
+            return None;
+        }
+
+        let field_names = self
+            .bookmarks
+            .iter()
+            .filter_map(|b| {
+                let offset = b.offset as usize;
+                let start = instances
+                    .iter()
+                    .find(|&&i| (i..i + size).contains(&offset))?;
+                Some((offset - start, b.name.clone()))
+            })
+            .collect();
+
+        Some(StructSample {
+            tag: self.tag,
+            class,
+            data: &self.data[..self.data_len],
+            instances,
+            size,
+            arrays: self
+                .array_ranges
+                .iter()
+                .map(|a| (a.start, a.class))
+                .collect(),
+            field_names,
+        })
+    }
+
+    fn struct_stub_ui(&self, ui: &mut Ui, scan: &ExtendedScanResult) {
+        // Falls back to the start of the selection, and then to the tag's own struct
+        let offset = self
+            .cursor
+            .or(self.selection.map(|(start, _)| start))
+            .unwrap_or_default();
+        let Some(sample) = self.struct_sample(offset) else {
+            ui.weak("The class (or size) of the struct at the cursor isn't known");
+            return;
+        };
+
+        ui.label(format!(
+            "{}, 0x{:X} bytes",
+            struct_stubs::class_name(sample.class),
+            sample.size
+        ));
+        if sample.instances.len() > 1 {
+            ui.weak(format!(
+                "Field types are guessed from {} array elements",
+                sample.instances.len()
+            ));
+        }
+        for language in StubLanguage::ALL {
+            if ui.button(format!("Copy as {}", language.label())).clicked() {
+                let stub = struct_stubs::generate(language, &sample, scan, &self.strings);
+                ui.output_mut(|o| o.copied_text = stub);
+                TOASTS.lock().success("Struct copied to clipboard");
+                ui.close_menu();
+            }
+        }
+    }
+
+    /// Labels of the regions that contain the offset
+    fn region_labels_at(&self, offset: u64) -> impl Iterator<Item = &RegionLabel> {
+        self.region_labels
+            .iter()
+            .filter(move |r| (r.start..r.end).contains(&offset))
+    }
+
+    /// Highlights the bytes that differ from the given data
+    pub fn set_compare_data(&mut self, label: String, mut data: Vec<u8>) {
+        // Padded the same way as our data, so the padding doesn't show up as a difference
+        let remainder = data.len() % 16;
+        if remainder != 0 {
+            data.extend(vec![0; 16 - remainder]);
+        }
+
+        let rows = self.data.len().max(data.len()) / 16;
+        self.diff_rows = (0..rows)
+            .filter(|row| {
+                self.data.get(row * 16..row * 16 + 16) != data.get(row * 16..row * 16 + 16)
+            })
+            .map(|row| row as u64 * 16)
+            .collect();
+        self.compare_label = label;
+        self.compare_data = Some(data);
+    }
+
+    fn differs(&self, offset: usize, len: usize) -> bool {
+        self.compare_data.as_ref().is_some_and(|compare| {
+            self.data.get(offset..offset + len) != compare.get(offset..offset + len)
+        })
+    }
+
+    fn paint_diff(&self, ui: &Ui, response: &egui::Response) {
+        ui.painter().rect_filled(
+            response.rect.expand(1.0),
+            2.0,
+            PaletteColor::Difference.color().gamma_multiply(0.2),
+        );
+
+        if self.row_settings.non_color_markers {
+            // A notch in the corner, so differences don't rely on the red tint alone
+            let corner = response.rect.expand(1.0).left_top();
+            ui.painter().add(Shape::convex_polygon(
+                vec![corner, corner + vec2(6.0, 0.0), corner + vec2(0.0, 6.0)],
+                Color32::WHITE,
+                Stroke::NONE,
+            ));
+        }
+    }
+
+    fn paint_search_match(&self, ui: &Ui, response: &egui::Response) {
+        ui.painter().rect_filled(
+            response.rect.expand(1.0),
+            2.0,
+            ui.visuals().selection.bg_fill.gamma_multiply(0.35),
+        );
+    }
+
+    fn paint_cursor(&self, ui: &Ui, response: &egui::Response) {
+        let rect = response.rect.expand(3.0);
+        let stroke = Stroke::new(2.0, ui.visuals().selection.stroke.color);
+        ui.painter().rect_stroke(rect, 0.0, stroke);
+        // The underline keeps the cursor distinguishable from the highlight outline without relying on color
+        ui.painter()
+            .hline(rect.x_range(), rect.bottom() + 2.0, stroke);
+    }
+
+    fn rebuild_rows(&mut self) {
+        self.rows = build_rows(&self.data, &self.row_settings, &self.row_overrides);
+    }
+
+    /// Shows the rows in the range in the given mode. Overrides overlapping the range are cut back to the parts outside
+    /// of it, so [`RowMode::Auto`] only removes overrides
+    fn set_row_override(&mut self, range: Range<u64>, mode: RowMode) {
+        let mut overrides = Vec::with_capacity(self.row_overrides.len() + 1);
+        for (r, m) in self.row_overrides.drain(..) {
+            if r.start >= range.end || r.end <= range.start {
+                overrides.push((r, m));
+                continue;
+            }
+
+            if r.start < range.start {
+                overrides.push((r.start..range.start, m));
+            }
+            if r.end > range.end {
+                overrides.push((range.end..r.end, m));
+            }
+        }
+
+        if mode != RowMode::Auto {
+            overrides.push((range, mode));
+        }
+        self.row_overrides = overrides;
+        self.rebuild_rows();
+    }
+
+    /// Makes this the view of a virtual tag, loading the sidecar for its address if there is one
+    pub fn set_virtual(&mut self, label: String, address: Option<VirtualAddress>) {
+        self.label = label;
+        if let Some(sidecar) = address.as_ref().and_then(hex_sidecar::load_virtual) {
+            self.apply_sidecar(sidecar);
+        }
+        self.base_address = address;
+    }
+
+    /// Size of the data, without the padding
+    pub fn data_len(&self) -> usize {
+        self.data_len
+    }
+
+    /// Takes the decompressed blob or sub-view that was opened from this view, if any
+    pub fn take_virtual_tag(&mut self) -> Option<VirtualTag> {
+        self.virtual_tag.take()
+    }
+
+    fn open_sub_view(&mut self, range: Range<usize>) {
+        let range = range.start.min(self.data_len)..range.end.min(self.data_len);
+        let address = self
+            .base_address
+            .as_ref()
+            .map(|a| a.sub_range(range.clone()));
+        self.virtual_tag = Some(VirtualTag {
+            label: address.as_ref().map_or_else(
+                || format!("{} 0x{:X}..0x{:X}", self.label, range.start, range.end),
+                |a| a.to_string(),
+            ),
+            address,
+            data: self.data[range].to_vec(),
+        });
+    }
+
+    fn compressed_blobs_ui(&mut self, ui: &mut Ui) {
+        let oodle = compression::oodle_available();
+        let mut jump_to = None;
+        let mut decompress = None;
+        egui::Grid::new("hexview_compressed_blobs")
+            .num_columns(4)
+            .show(ui, |ui| {
+                for (i, (blob, size)) in self.compressed_blobs.iter_mut().enumerate() {
+                    if ui
+                        .link(RichText::new(format!("0x{:X}", blob.offset)).monospace())
+                        .clicked()
+                    {
+                        jump_to = Some(blob.offset as u64);
+                    }
+
+                    match blob.compression {
+                        Compression::Oodle(compressor) => {
+                            ui.label(format!("Oodle {compressor}")).on_hover_text(
+                                "Oodle blocks don't have a real header, this might not be one",
+                            );
+                            ui.add(
+                                egui::TextEdit::singleline(size)
+                                    .hint_text("Size")
+                                    .desired_width(72.0),
+                            )
+                            .on_hover_text(
+                                "Decompressed size in bytes, Oodle needs to know it up front",
+                            );
+                        }
+                        compression => {
+                            ui.label(compression.name());
+                            ui.label("");
+                        }
+                    }
+
+                    let enabled = match blob.compression {
+                        Compression::Oodle(_) => {
+                            oodle.is_ok() && size.trim().parse::<usize>().is_ok()
+                        }
+                        _ => true,
+                    };
+                    let response =
+                        ui.add_enabled(enabled, egui::Button::new("Decompress and open"));
+                    let response = match &oodle {
+                        Err(e) if matches!(blob.compression, Compression::Oodle(_)) => {
+                            response.on_disabled_hover_text(e)
+                        }
+                        _ => response.on_disabled_hover_text("Enter the decompressed size"),
+                    };
+                    if response.clicked() {
+                        decompress = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(offset) = jump_to {
+            self.highlight_offset(offset);
+        }
+
+        if let Some(i) = decompress {
+            let (blob, size) = &self.compressed_blobs[i];
+            match compression::decompress(
+                blob.compression,
+                &self.data[blob.offset..self.data_len],
+                size.trim().parse().ok(),
+            ) {
+                Ok(data) => {
+                    TOASTS.lock().success(format!(
+                        "Decompressed {} bytes from 0x{:X}",
+                        data.len(),
+                        blob.offset
+                    ));
+                    self.virtual_tag = Some(VirtualTag {
+                        label: format!(
+                            "{} {} @ 0x{:X}",
+                            self.label,
+                            blob.compression.name(),
+                            blob.offset
+                        ),
+                        address: None,
+                        data,
+                    });
+                    ui.close_menu();
+                }
+                Err(e) => {
+                    error!("Failed to decompress blob at 0x{:X}: {e:?}", blob.offset);
+                    TOASTS.lock().error(format!("Failed to decompress: {e}"));
+                }
+            }
+        }
+    }
+
+    /// Highlights the given offset and scrolls to it the next time the view is shown
+    pub fn highlight_offset(&mut self, offset: u64) {
+        self.highlight_offset = Some(offset);
+        self.scroll_to_highlight = true;
+    }
+
+    fn is_highlighted(&self, offset: usize, len: usize) -> bool {
+        self.highlight_offset
+            .is_some_and(|h| (offset..offset + len).contains(&(h as usize)))
+    }
+
+    fn paint_highlight(&self, ui: &Ui, response: &egui::Response) {
+        ui.painter().rect_stroke(
+            response.rect.expand(2.0),
+            2.0,
+            Stroke::new(1.5, PaletteColor::LinkedOffset.color()),
+        );
+
+        if self.scroll_to_highlight {
+            response.scroll_to_me(Some(Align::Center));
+        }
+    }
+
+    /// Expands the collapsed array that contains the offset, if there is one
+    fn expand_array_at(&self, ui: &Ui, offset: u64) {
+        for (i, array) in self.array_ranges.iter().enumerate() {
+            if (array.start..array.end).contains(&offset) {
+                let mut state = CollapsingState::load_with_default_open(
+                    ui.ctx(),
+                    egui::Id::new(format!("hexview_array_{i}",)),
+                    false,
+                );
+                state.set_open(true);
+                state.store(ui.ctx());
+            }
+        }
+    }
+
+    fn set_cursor(&mut self, offset: u64) {
+        let last_lane = (self.data.len() as u64).saturating_sub(4);
+        self.cursor = Some((offset & !3).min(last_lane));
+        self.cursor_moved = true;
+    }
+
+    /// Moves the cursor with the arrow keys (a lane), page up/down (16 rows) and home/end (start and end of the row).
+    /// Enter opens the tag referenced at the cursor, escape hides the cursor
+    ///
+    /// Keys are only handled while the cursor's lane has focus, so they don't interfere with text fields
+    fn handle_cursor_keys(
+        &mut self,
+        ui: &Ui,
+        cursor_focus: Option<egui::Id>,
+        scan: &ExtendedScanResult,
+    ) -> Option<TagHash> {
+        let cursor = self.cursor?;
+        let focused = ui.memory(|m| m.focused());
+        if focused.is_some() && focused != cursor_focus {
+            return None;
+        }
+
+        let (moves, open, escape) = ui.input(|i| {
+            let moves = [
+                (Key::ArrowLeft, -4),
+                (Key::ArrowRight, 4),
+                (Key::ArrowUp, -16),
+                (Key::ArrowDown, 16),
+                (Key::PageUp, -16 * 16),
+                (Key::PageDown, 16 * 16),
+                (Key::Home, -(cursor as i64 % 16)),
+                (Key::End, 12 - cursor as i64 % 16),
+            ]
+            .into_iter()
+            .filter(|(key, _)| i.key_pressed(*key))
+            .map(|(_, delta)| delta)
+            .sum::<i64>();
+            (moves, i.key_pressed(Key::Enter), i.key_pressed(Key::Escape))
+        });
+
+        if escape {
+            self.cursor = None;
+            if let Some(id) = cursor_focus {
+                ui.memory_mut(|m| m.surrender_focus(id));
+            }
+            return None;
+        }
+
+        if moves != 0 {
+            let offset = (cursor as i64 + moves).max(0) as u64;
+            self.set_cursor(offset);
+            self.expand_array_at(ui, offset);
+        }
+
+        if open {
+            return scan
+                .file_hashes
+                .iter()
+                .find(|v| v.offset == cursor)
+                .map(|v| v.hash.hash32());
+        }
+
+        None
+    }
+
+    /// Class, struct start and offset within the struct of the field at `offset`. Offsets outside of arrays are in the
+    /// tag's own struct, array elements need a class with a known size
+    fn struct_offset(&self, offset: u64) -> Option<(u32, usize, usize)> {
+        if let Some(array) = self
+            .array_ranges
+            .iter()
+            .find(|a| (a.start..a.end).contains(&offset))
+        {
+            if offset < array.data_start {
+                return None;
+            }
+
+            let stride = get_class_by_id(array.class)?.size.filter(|&s| s > 0)? as u64;
+            let struct_start = array.data_start + (offset - array.data_start) / stride * stride;
+            return Some((
+                array.class,
+                struct_start as usize,
+                (offset - struct_start) as usize,
+            ));
+        }
+
+        Some((self.class?, 0, offset as usize))
+    }
+
+    /// Enums declared in the 4-byte lane at `offset`, with their values
+    fn lane_enums(&self, offset: usize) -> Vec<(EnumField, u32)> {
+        let Some((class, struct_start, field_offset)) = self.struct_offset(offset as u64) else {
+            return vec![];
+        };
+
+        let endian = package_manager().version.endian();
+        get_enums(class)
+            .into_iter()
+            .filter(|f| (field_offset..field_offset + 4).contains(&f.offset))
+            .filter_map(|f| {
+                let value = f.read(&self.data, struct_start, endian)?;
+                Some((f, value))
+            })
+            .collect()
+    }
+
+    /// Values of the 4-byte field at `offset` in every element of its array, or only its own value outside of arrays
+    fn field_values(&self, offset: u64) -> Vec<u32> {
+        let endian = package_manager().version.endian();
+        let read = |o: usize| {
+            self.data
+                .get(o..o + 4)
+                .map(|b| u32_from_endian(endian, b.try_into().unwrap()))
+        };
+
+        let array = self
+            .array_ranges
+            .iter()
+            .find(|a| (a.data_start..a.end).contains(&offset));
+        match (array, self.struct_offset(offset)) {
+            // Only arrays of classes with a known size have a struct offset
+            (Some(array), Some((_, _, field_offset))) => {
+                let stride = get_class_by_id(array.class)
+                    .and_then(|c| c.size)
+                    .unwrap_or_default();
+                (0..array.length as usize)
+                    .map_while(|i| read(array.data_start as usize + i * stride + field_offset))
+                    .collect()
+            }
+            _ => read(offset as usize).into_iter().collect(),
+        }
+    }
+
+    /// Spoken description of a 4-byte lane, also shown below the toolbar for the lane under the cursor
+    fn describe_lane(&self, offset: usize, scan: &ExtendedScanResult) -> String {
+        let Some(bytes) = self.data.get(offset..offset + 4) else {
+            return format!("Offset {offset:X}");
+        };
+        let bytes: [u8; 4] = bytes.try_into().unwrap();
+        let value = u32_from_endian(package_manager().version.endian(), bytes);
+
+        let mut parts = vec![
+            format!("Offset {offset:X}"),
+            format!(
+                "bytes {:02X} {:02X} {:02X} {:02X}",
+                bytes[0], bytes[1], bytes[2], bytes[3]
+            ),
+            format!("integer {value}"),
+            format!("float {}", fmt_short_float(f32::from_bits(value))),
+        ];
+
+        if let Some(e) = scan.file_hashes.iter().find(|v| v.offset == offset as u64) {
+            parts.push(format!(
+                "reference to {}",
+                format_tag_entry(e.hash.hash32(), e.entry.as_ref())
+            ));
+        }
+
+        if let Some(description) = self
+            .rows
+            .get(offset / 16)
+            .and_then(|r| r.kind.description())
+        {
+            parts.push(description.to_lowercase());
+        }
+
+        if let Some(h) = scan
+            .namespace_hashes
+            .iter()
+            .find(|h| h.offset == offset as u64)
+        {
+            parts.push(format!("{} hash {}", h.namespace.name, h.names()));
+        }
+
+        for (field, value) in self.lane_enums(offset) {
+            parts.push(format!("enum value {}", field.format(value)));
+        }
+
+        if self.is_highlighted(offset, 4) {
+            parts.push("linked offset".to_string());
+        }
+
+        if self.differs(offset, 4) {
+            parts.push(format!("differs from {}", self.compare_label));
+        }
+
+        if self
+            .selection
+            .is_some_and(|(start, end)| (start..end).contains(&(offset as u64)))
+        {
+            parts.push("selected".to_string());
+        }
+
+        parts.join(", ")
+    }
+
+    /// Makes a lane clickable and readable by screen readers, and draws the cursor on it. `len` is the amount of bytes
+    /// the response covers, multiple lanes for rows that are shown as floats
+    fn lane_interaction(
+        &self,
+        ui: &Ui,
+        response: &egui::Response,
+        offset: usize,
+        len: usize,
+        scan: &ExtendedScanResult,
+        interaction: &mut RowInteraction,
+    ) {
+        let cursor_lane = self
+            .cursor
+            .filter(|&c| (offset..offset + len).contains(&(c as usize)));
+        let described = cursor_lane.map(|c| c as usize).unwrap_or(offset);
+        response.widget_info(|| {
+            WidgetInfo::labeled(WidgetType::Label, true, self.describe_lane(described, scan))
+        });
+
+        // Lanes can also be reached with tab, the cursor follows the focus
+        if response.clicked() || (response.gained_focus() && cursor_lane.is_none()) {
+            interaction.cursor = Some(offset as u64);
+        }
+
+        if cursor_lane.is_some() {
+            self.paint_cursor(ui, response);
+            interaction.cursor_focus = Some(response.id);
+            if self.cursor_moved {
+                response.scroll_to_me(None);
+                ui.memory_mut(|m| {
+                    m.request_focus(response.id);
+                    m.set_focus_lock_filter(
+                        response.id,
+                        EventFilter {
+                            tab: false,
+                            horizontal_arrows: true,
+                            vertical_arrows: true,
+                            escape: true,
+                        },
+                    );
+                });
+            }
         }
     }
 
@@ -69,14 +945,286 @@ impl TagHexView {
             self.refresh_collapsible_states = false;
         }
 
+        if self.scroll_to_highlight {
+            if let Some(highlight) = self.highlight_offset {
+                // Make sure the array containing the highlighted offset is expanded
+                self.expand_array_at(ui, highlight);
+
+                if highlight >= self.data.len() as u64 {
+                    warn!(
+                        "Highlighted offset 0x{highlight:X} is beyond the end of the tag data (0x{:X})",
+                        self.data.len()
+                    );
+                }
+            }
+        }
+
         if quicktag_core::classes::was_schemafile_refreshed() {
             self.array_ranges = find_all_array_ranges(&self.data);
         }
 
-        ui.checkbox(&mut self.raw_array_data, "Show raw array data");
+        let row_settings = config::with(|c| c.hex_view.clone());
+        if row_settings != self.row_settings {
+            self.row_settings = row_settings;
+            self.rebuild_rows();
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.raw_array_data, "Show raw array data");
+            let previous_encoding = self.text_encoding;
+            egui::ComboBox::from_id_source("hexview_text_encoding")
+                .selected_text(self.text_encoding.label())
+                .show_ui(ui, |ui| {
+                    for encoding in [
+                        TextEncoding::Ascii,
+                        TextEncoding::Utf8,
+                        TextEncoding::Utf16,
+                    ] {
+                        ui.selectable_value(&mut self.text_encoding, encoding, encoding.label());
+                    }
+                })
+                .response
+                .on_hover_text("Encoding used for the text column next to the hex data");
+            if self.text_encoding != previous_encoding {
+                self.text_cells = decode_text_cells(&self.data, self.text_encoding);
+            }
+
+            ui.checkbox(&mut self.show_vectors, "Show vectors").on_hover_text(
+                "Shows rows that look like world positions or rotation quaternions as vectors (quaternions as euler angles)",
+            );
+
+            let mut data_inspector = self.row_settings.data_inspector;
+            if ui
+                .checkbox(&mut data_inspector, "Data inspector")
+                .on_hover_text("Shows the bytes at the cursor as every type at once")
+                .changed()
+            {
+                config::with_mut(|c| c.hex_view.data_inspector = data_inspector);
+            }
+
+            if ui
+                .selectable_label(self.search.open, "🔍 Find")
+                .on_hover_text(format!(
+                    "Search the tag for bytes, text or a number ({})",
+                    ui.ctx().format_shortcut(&FIND_SHORTCUT)
+                ))
+                .clicked()
+            {
+                if self.search.open {
+                    self.search.open = false;
+                } else {
+                    self.search.open();
+                }
+            }
+
+            ui.menu_button("Struct stub", |ui| self.struct_stub_ui(ui, scan))
+                .response
+                .on_hover_text(
+                    "C and Rust definitions of the struct at the cursor, with the fields that could be identified",
+                );
+
+            if !self.compressed_blobs.is_empty() {
+                ui.menu_button(
+                    format!("{} compressed blobs", self.compressed_blobs.len()),
+                    |ui| self.compressed_blobs_ui(ui),
+                )
+                .response
+                .on_hover_text("zlib, gzip, zstd and Oodle streams inside the tag");
+            }
+
+            if self.compare_data.is_some() {
+                ui.separator();
+                ui.label(
+                    RichText::new(format!(
+                        "{} rows differ from {}",
+                        self.diff_rows.len(),
+                        self.compare_label
+                    ))
+                    .color(PaletteColor::Difference.color()),
+                );
+
+                let current = self.highlight_offset.unwrap_or_default();
+                let next = self
+                    .diff_rows
+                    .iter()
+                    .find(|&&o| self.highlight_offset.is_none() || o > current)
+                    .or(self.diff_rows.first())
+                    .copied();
+                if ui
+                    .add_enabled(next.is_some(), egui::Button::new("Next difference"))
+                    .clicked()
+                {
+                    if let Some(offset) = next {
+                        self.highlight_offset(offset);
+                    }
+                }
+            }
+
+            if let Some((start, end)) = self.selection {
+                ui.separator();
+                ui.label(format!(
+                    "Selected 0x{start:X}..0x{end:X} ({} bytes)",
+                    end - start
+                ));
+
+                if ui
+                    .button("Export region as image")
+                    .on_hover_text("Region images will be saved to the regions/ directory")
+                    .clicked()
+                {
+                    self.export_region_image(scan, start..end);
+                }
+
+                if ui
+                    .button("Copy region as text")
+                    .on_hover_text("Copies the region as an xxd-style hex dump")
+                    .clicked()
+                {
+                    let dump = format_region_xxd(
+                        self.tag,
+                        &self.data,
+                        start..end,
+                        scan,
+                        self.annotate_text_dump,
+                    );
+                    ui.output_mut(|o| o.copied_text = dump);
+                    TOASTS.lock().success("Region copied to clipboard");
+                }
+                ui.checkbox(&mut self.annotate_text_dump, "Annotate");
+
+                if ui
+                    .button("Hashes...")
+                    .on_hover_text("FNV1, CRC32, MD5, SHA-1 and xxHash of the region")
+                    .clicked()
+                {
+                    self.region_hashes = Some(RegionHashWindow::new(
+                        self.tag,
+                        &self.data[..self.data_len],
+                        start as usize..end as usize,
+                    ));
+                }
+
+                if ui
+                    .button("Open as sub-view")
+                    .on_hover_text(
+                        "Opens the region in its own hex view, with its own array detection, scan and sidecar",
+                    )
+                    .clicked()
+                {
+                    self.open_sub_view(start as usize..end as usize);
+                }
+                if let Some(base) = &self.base_address {
+                    if ui
+                        .button("Copy address")
+                        .on_hover_text("Copies the address of the region (hash!offset..len), which can be opened from the tag box")
+                        .clicked()
+                    {
+                        let address = base.sub_range(start as usize..end as usize);
+                        ui.output_mut(|o| o.copied_text = address.to_string());
+                        TOASTS.lock().success("Address copied to clipboard");
+                    }
+                }
+
+                ui.label("Show as");
+                for (mode, label) in [
+                    (RowMode::Auto, "Auto"),
+                    (RowMode::Raw, "Raw"),
+                    (RowMode::Float, "Float"),
+                ] {
+                    if ui.button(label).clicked() {
+                        self.set_row_override(start..end, mode);
+                    }
+                }
+                if ui
+                    .add_enabled(
+                        !self.row_overrides.is_empty(),
+                        egui::Button::new("Clear overrides"),
+                    )
+                    .on_hover_text("Shows every row in its detected mode again")
+                    .clicked()
+                {
+                    self.row_overrides.clear();
+                    self.rebuild_rows();
+                }
+
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.region_label_input)
+                        .hint_text("Region label")
+                        .desired_width(120.0),
+                );
+                if ui
+                    .add_enabled(
+                        !self.region_label_input.trim().is_empty(),
+                        egui::Button::new("Label region"),
+                    )
+                    .clicked()
+                {
+                    self.region_labels.push(RegionLabel {
+                        start,
+                        end,
+                        label: self.region_label_input.trim().to_string(),
+                    });
+                    self.region_labels.sort_by_key(|r| r.start);
+                    self.region_label_input.clear();
+                }
+
+                if ui.button("Clear selection").clicked() {
+                    self.selection = None;
+                }
+            } else {
+                ui.weak("Click a row offset to select it, shift+click to extend the selection");
+            }
+        });
+
+        if ui.input_mut(|i| i.consume_shortcut(&FIND_SHORTCUT)) {
+            self.search.open();
+        }
+        if self.search.open {
+            if let Some(offset) = self.search.ui(
+                ui,
+                &self.data[..self.data_len],
+                package_manager().version.endian(),
+                self.cursor.or(self.highlight_offset),
+            ) {
+                self.highlight_offset(offset);
+            }
+        }
+
+        self.markup_toolbar(ui);
+
+        let mut open_tag = self.handle_cursor_keys(ui, self.cursor_focus, scan);
+        if let Some(cursor) = self.cursor {
+            ui.label(self.describe_lane(cursor as usize, scan));
+        } else {
+            ui.weak("Click a value to move the keyboard cursor to it, then use the arrow keys to move it");
+        }
         ui.separator();
 
-        let mut open_tag = None;
+        if self.row_settings.data_inspector {
+            egui::SidePanel::right("hexview_data_inspector")
+                .resizable(true)
+                .default_width(440.0)
+                .show_inside(ui, |ui| {
+                    // Falls back to the start of the selection when the cursor isn't placed
+                    let offset = self.cursor.or(self.selection.map(|(start, _)| start));
+                    match offset {
+                        Some(offset) => {
+                            open_tag = open_tag.or(data_inspector::inspector_ui(
+                                ui,
+                                &self.data,
+                                offset as usize,
+                                package_manager().version.endian(),
+                                &self.strings,
+                            ));
+                        }
+                        None => {
+                            ui.weak("Place the cursor on a value to inspect it");
+                        }
+                    }
+                });
+        }
+
+        let mut interaction = RowInteraction::default();
         ScrollArea::vertical()
             .auto_shrink([false, false])
             .show(ui, |ui| {
@@ -87,6 +1235,7 @@ impl TagHexView {
                         &self.rows[..first_array_offset / 16],
                         0,
                         scan,
+                        &mut interaction,
                     ));
 
                     for (i, array) in self.array_ranges.iter().enumerate() {
@@ -118,13 +1267,28 @@ impl TagHexView {
                                 let class_size = get_class_by_id(array.class).and_then(|c| c.size);
                                 for (i, row) in array.pretty_rows.iter().enumerate() {
                                     ui.horizontal(|ui| {
+                                        let mut highlighted = false;
+                                        let mut changed = false;
+                                        let mut matched = false;
                                         if let Some(class_size) = class_size {
                                             let offset = array.data_start as usize + i * class_size;
                                             ui.strong(format!("{:08X}:", offset));
+                                            highlighted = self.is_highlighted(offset, class_size);
+                                            changed = self.differs(offset, class_size);
+                                            matched = self.search.is_match(offset, class_size);
                                         }
                                         ui.strong(format!("[{i}]"));
                                         ui.style_mut().spacing.item_spacing.x = 14.0;
-                                        ui.monospace(row);
+                                        let response = ui.monospace(row);
+                                        if highlighted {
+                                            self.paint_highlight(ui, &response);
+                                        }
+                                        if changed {
+                                            self.paint_diff(ui, &response);
+                                        }
+                                        if matched {
+                                            self.paint_search_match(ui, &response);
+                                        }
                                     });
                                 }
                             } else {
@@ -134,15 +1298,41 @@ impl TagHexView {
                                         [array.data_start as usize / 16..array.end as usize / 16],
                                     array.data_start as usize,
                                     scan,
+                                    &mut interaction,
                                 ));
                             }
                         });
                     }
                 } else {
-                    open_tag = open_tag.or(self.show_row_block(ui, &self.rows, 0, scan));
+                    open_tag =
+                        open_tag.or(self.show_row_block(ui, &self.rows, 0, scan, &mut interaction));
                 }
             });
 
+        self.scroll_to_highlight = false;
+        self.cursor_moved = false;
+        self.cursor_focus = interaction.cursor_focus;
+        if let Some(offset) = interaction.cursor {
+            self.set_cursor(offset);
+        }
+        if interaction.select_row.is_some() {
+            self.selection = interaction.select_row;
+        }
+        if let Some(offset) = interaction.add_bookmark {
+            self.add_bookmark(offset);
+        }
+        if let Some(offset) = interaction.remove_region_labels {
+            self.region_labels
+                .retain(|r| !(r.start..r.end).contains(&offset));
+        }
+
+        if let Some(window) = self.region_hashes.as_mut() {
+            window.show(ui.ctx(), &self.data[..self.data_len]);
+            if !window.open {
+                self.region_hashes = None;
+            }
+        }
+
         open_tag
     }
 
@@ -153,109 +1343,306 @@ impl TagHexView {
         rows: &[DataRow],
         base_offset: usize,
         scan: &ExtendedScanResult,
+        interaction: &mut RowInteraction,
     ) -> Option<TagHash> {
         let mut open_tag = None;
         for (i, row) in rows.iter().enumerate() {
             let offset = base_offset + i * 16;
-            ui.horizontal(|ui| {
-                ui.strong(format!("{:08X}:", base_offset + i * 16));
+            let row_range = offset as u64..offset as u64 + 16;
+            for region in self
+                .region_labels
+                .iter()
+                .filter(|r| row_range.contains(&r.start))
+            {
+                ui.label(
+                    RichText::new(format!(
+                        "▼ {} (0x{:X}..0x{:X})",
+                        region.label, region.start, region.end
+                    ))
+                    .color(PaletteColor::Selection.color())
+                    .strong(),
+                );
+            }
+            for bookmark in self
+                .bookmarks
+                .iter()
+                .filter(|b| row_range.contains(&b.offset))
+            {
+                ui.label(
+                    RichText::new(format!("🔖 {} (0x{:X})", bookmark.name, bookmark.offset))
+                        .strong(),
+                );
+            }
+
+            let row_response = ui.horizontal(|ui| {
+                let mut offset_response = ui
+                    .strong(format!("{:08X}:", offset))
+                    .interact(Sense::click())
+                    .on_hover_cursor(CursorIcon::PointingHand);
+                let regions = self
+                    .region_labels_at(offset as u64)
+                    .map(|r| r.label.as_str())
+                    .join(" > ");
+                if !regions.is_empty() {
+                    offset_response = offset_response.on_hover_text(regions);
+                }
+
+                if offset_response.clicked() {
+                    let (start, end) = (offset as u64, offset as u64 + 16);
+                    interaction.select_row = Some(match self.selection {
+                        Some((s, e)) if ui.input(|i| i.modifiers.shift) => {
+                            (s.min(start), e.max(end))
+                        }
+                        _ => (start, end),
+                    });
+                    interaction.cursor = Some(start);
+                }
+
+                offset_response.context_menu(|ui| {
+                    if ui
+                        .selectable_label(false, "🔗 Copy link to offset")
+                        .clicked()
+                    {
+                        ui.output_mut(|o| {
+                            o.copied_text = format!("{:08X}@0x{offset:X}", self.tag.0)
+                        });
+                        ui.close_menu();
+                    }
+
+                    if ui.selectable_label(false, "🔖 Bookmark offset").clicked() {
+                        interaction.add_bookmark = Some(offset as u64);
+                        ui.close_menu();
+                    }
+
+                    if self.region_labels_at(offset as u64).next().is_some()
+                        && ui
+                            .selectable_label(false, "Remove region labels here")
+                            .clicked()
+                    {
+                        interaction.remove_region_labels = Some(offset as u64);
+                        ui.close_menu();
+                    }
+                });
                 ui.style_mut().spacing.item_spacing.x = 14.0;
-                match row {
-                    DataRow::Raw(data) => {
-                        for (bi, b) in data.chunks_exact(4).enumerate() {
-                            let chunk_offset = offset + bi * 4;
-                            let hash = scan
-                                .file_hashes
-                                .iter()
-                                .find(|v| v.offset == chunk_offset as u64);
-                            let color = if hash.is_some() {
-                                Color32::GOLD
-                            } else {
-                                Color32::GRAY
-                            };
-
-                            let response = ui.monospace(
-                                RichText::new(format!(
-                                    "{:02X} {:02X} {:02X} {:02X}",
-                                    b[0], b[1], b[2], b[3]
-                                ))
-                                .color(color),
-                            );
-                            if let Some(e) = hash {
-                                let hash32 = e.hash.hash32();
-                                let tagline_color = e
-                                    .entry
-                                    .as_ref()
-                                    .map(|e| {
-                                        TagType::from_type_subtype(e.file_type, e.file_subtype)
-                                            .display_color()
-                                    })
-                                    .unwrap_or(Color32::GRAY);
-                                let response = response
-                                    .on_hover_text(
-                                        RichText::new(format_tag_entry(hash32, e.entry.as_ref()))
-                                            .color(tagline_color),
-                                    )
-                                    .tag_context(hash32)
-                                    .interact(Sense::click())
-                                    .on_hover_cursor(CursorIcon::PointingHand);
-
-                                if response.hovered() {
-                                    ui.painter().rect(
-                                        response.rect,
-                                        0.0,
-                                        Color32::from_white_alpha(30),
-                                        Stroke::NONE,
-                                    );
-                                }
+                // Lanes with a tag reference are always shown as bytes, so they can be clicked
+                let lane_hashes: [_; 4] = std::array::from_fn(|lane| {
+                    scan.file_hashes
+                        .iter()
+                        .find(|v| v.offset == (offset + lane * 4) as u64)
+                });
+                let float_lanes: [bool; 4] = std::array::from_fn(|lane| {
+                    row.float_lanes[lane] && lane_hashes[lane].is_none()
+                });
 
-                                if response.clicked() {
-                                    open_tag = Some(hash32);
-                                }
-                            }
+                if float_lanes.iter().all(|&f| f) {
+                    let data = &row.floats;
+                    let kind = row.kind;
+                    let string = data.iter().map(|f| fmt_short_float(*f)).join("  ");
+                    let mut text = RichText::new(string);
+                    if let Some(color) = kind.color() {
+                        text = text.color(color);
+                    }
+                    let mut response = ui.monospace(text).interact(Sense::click());
+                    if let Some(description) = kind.description() {
+                        response = response.on_hover_text(description);
+                    }
+                    if self.is_highlighted(offset, 16) {
+                        self.paint_highlight(ui, &response);
+                    }
+                    if self.differs(offset, 16) {
+                        self.paint_diff(ui, &response);
+                    }
+                    if self.search.is_match(offset, 16) {
+                        self.paint_search_match(ui, &response);
+                    }
+                    self.lane_interaction(ui, &response, offset, 16, scan, interaction);
+                    if let Some(marker) = kind
+                        .marker()
+                        .filter(|_| self.row_settings.non_color_markers)
+                    {
+                        ui.label(RichText::new(marker).small().strong());
+                    }
+                    ui.add_space(16.0);
+
+                    if data.iter().all(|&v| v >= 0.0) {
+                        let needs_normalization = data.iter().any(|&v| v > 1.0);
+                        let floats = if needs_normalization {
+                            let factor = (*data).into_iter().reduce(f32::max).unwrap();
+                            [
+                                data[0] / factor,
+                                data[1] / factor,
+                                data[2] / factor,
+                                data[3] / factor,
+                            ]
+                        } else {
+                            *data
+                        };
+
+                        let color =
+                            Rgba::from_rgb(floats[0].abs(), floats[1].abs(), floats[2].abs());
+
+                        let (response, painter) =
+                            ui.allocate_painter(vec2(16.0, 16.0), Sense::hover());
+
+                        painter.rect_filled(response.rect, 0.0, color);
+                    }
+
+                    if self.show_vectors {
+                        if let Some(vector) = kind.format_vector(data) {
+                            ui.add_space(16.0);
+                            ui.monospace(
+                                RichText::new(vector).color(kind.color().unwrap_or(Color32::GRAY)),
+                            );
                         }
                     }
-                    DataRow::Float(data) => {
-                        let string = data.iter().map(|f| fmt_short_float(*f)).join("  ");
-                        ui.monospace(string);
-                        ui.add_space(16.0);
+                } else {
+                    for (bi, b) in row.bytes.chunks_exact(4).enumerate() {
+                        let chunk_offset = offset + bi * 4;
+                        if float_lanes[bi] {
+                            let response = ui
+                                .monospace(fmt_short_float(row.floats[bi]))
+                                .interact(Sense::click());
+                            if self.is_highlighted(chunk_offset, 4) {
+                                self.paint_highlight(ui, &response);
+                            }
+                            if self.differs(chunk_offset, 4) {
+                                self.paint_diff(ui, &response);
+                            }
+                            if self.search.is_match(chunk_offset, 4) {
+                                self.paint_search_match(ui, &response);
+                            }
+                            self.lane_interaction(
+                                ui,
+                                &response,
+                                chunk_offset,
+                                4,
+                                scan,
+                                interaction,
+                            );
+                            continue;
+                        }
 
-                        if data.iter().all(|&v| v >= 0.0) {
-                            let needs_normalization = data.iter().any(|&v| v > 1.0);
-                            let floats = if needs_normalization {
-                                let factor = (*data).into_iter().reduce(f32::max).unwrap();
-                                [
-                                    data[0] / factor,
-                                    data[1] / factor,
-                                    data[2] / factor,
-                                    data[3] / factor,
-                                ]
-                            } else {
-                                *data
-                            };
+                        let hash = lane_hashes[bi];
+                        let namespace_hash = scan
+                            .namespace_hashes
+                            .iter()
+                            .find(|h| h.offset == chunk_offset as u64);
+                        let color = if hash.is_some() {
+                            PaletteColor::TagReference.color()
+                        } else if let Some(h) = namespace_hash {
+                            let [red, green, blue] = h.namespace.color;
+                            Color32::from_rgb(red, green, blue)
+                        } else {
+                            PaletteColor::RawBytes.color()
+                        };
+
+                        let mut text = RichText::new(format!(
+                            "{:02X} {:02X} {:02X} {:02X}",
+                            b[0], b[1], b[2], b[3]
+                        ))
+                        .color(color);
+                        if (hash.is_some() || namespace_hash.is_some())
+                            && self.row_settings.non_color_markers
+                        {
+                            text = text.underline();
+                        }
+
+                        let mut response = ui.monospace(text).interact(Sense::click());
+                        if let Some(h) = namespace_hash.filter(|_| hash.is_none()) {
+                            response = response.on_hover_text(format!(
+                                "{}: {}",
+                                h.namespace.name,
+                                h.names()
+                            ));
+                        }
+                        if self.is_highlighted(chunk_offset, 4) {
+                            self.paint_highlight(ui, &response);
+                        }
+                        if self.differs(chunk_offset, 4) {
+                            self.paint_diff(ui, &response);
+                        }
+                        if self.search.is_match(chunk_offset, 4) {
+                            self.paint_search_match(ui, &response);
+                        }
+                        self.lane_interaction(ui, &response, chunk_offset, 4, scan, interaction);
+
+                        if hash.is_none() {
+                            let enums = self.lane_enums(chunk_offset);
+                            if !enums.is_empty() {
+                                response = response.on_hover_text(
+                                    enums
+                                        .iter()
+                                        .map(|(f, v)| enum_hover_text(f, *v))
+                                        .join("\n\n"),
+                                );
+                            }
+                            if let Some((class, _, field_offset)) =
+                                self.struct_offset(chunk_offset as u64)
+                            {
+                                response.context_menu(|ui| {
+                                    if ui.selectable_label(false, "Declare enum...").clicked() {
+                                        enum_editor::declare_enum(
+                                            class,
+                                            field_offset,
+                                            self.field_values(chunk_offset as u64),
+                                        );
+                                        ui.close_menu();
+                                    }
+                                });
+                            }
+                        }
 
-                            let color =
-                                Rgba::from_rgb(floats[0].abs(), floats[1].abs(), floats[2].abs());
+                        if let Some(e) = hash {
+                            let hash32 = e.hash.hash32();
+                            let tagline_color = e
+                                .entry
+                                .as_ref()
+                                .map(|e| {
+                                    TagType::from_type_subtype(e.file_type, e.file_subtype)
+                                        .display_color()
+                                })
+                                .unwrap_or(Color32::GRAY);
+                            let response = response
+                                .on_hover_text(
+                                    RichText::new(format_tag_entry(hash32, e.entry.as_ref()))
+                                        .color(tagline_color),
+                                )
+                                .tag_context(hash32)
+                                .interact(Sense::click())
+                                .on_hover_cursor(CursorIcon::PointingHand);
 
-                            let (response, painter) =
-                                ui.allocate_painter(vec2(16.0, 16.0), Sense::hover());
+                            if response.hovered() {
+                                ui.painter().rect(
+                                    response.rect,
+                                    0.0,
+                                    Color32::from_white_alpha(30),
+                                    Stroke::NONE,
+                                );
+                            }
 
-                            painter.rect_filled(response.rect, 0.0, color);
+                            if response.clicked() {
+                                open_tag = Some(hash32);
+                            }
                         }
                     }
                 }
 
-                if let Some(bytes) = row.as_raw() {
+                if !float_lanes.iter().all(|&f| f) {
                     ui.add_space(16.0);
                     let (_response, painter) =
                         ui.allocate_painter(vec2(16.0 * 16.0, 16.0), Sense::hover());
 
                     ui.style_mut().spacing.item_spacing.x = 4.0;
-                    for (i, &b) in bytes.iter().enumerate() {
-                        let (c, color) = if b.is_ascii_graphic() {
-                            (b as char, Color32::from_rgb(90, 120, 255))
-                        } else {
-                            ('.', Color32::DARK_GRAY)
+                    let cells = self.text_cells.get(offset..offset + 16).unwrap_or_default();
+                    for (i, cell) in cells.iter().enumerate() {
+                        let (c, color) = match *cell {
+                            TextCell::Char(c) if c.is_ascii() => {
+                                (c, PaletteColor::AsciiText.color())
+                            }
+                            TextCell::Char(c) => (c, PaletteColor::UnicodeText.color()),
+                            // Covered by the character in a previous cell
+                            TextCell::Continuation => continue,
+                            TextCell::Invalid => ('.', Color32::DARK_GRAY),
                         };
 
                         let pos = painter.clip_rect().min + vec2(i as f32 * 12.0, 0.0);
@@ -268,11 +1655,124 @@ impl TagHexView {
                         );
                     }
                 }
+
+                let row_enums = (0..4)
+                    .flat_map(|lane| self.lane_enums(offset + lane * 4))
+                    .collect_vec();
+                if !row_enums.is_empty() {
+                    ui.add_space(16.0);
+                    ui.label(
+                        RichText::new(
+                            row_enums
+                                .iter()
+                                .map(|(f, v)| format!("0x{:X}: {}", f.offset, f.format(*v)))
+                                .join("  "),
+                        )
+                        .italics(),
+                    );
+                }
             });
+
+            if self
+                .selection
+                .is_some_and(|(start, end)| (start..end).contains(&(offset as u64)))
+            {
+                ui.painter().rect_filled(
+                    row_response.response.rect,
+                    0.0,
+                    PaletteColor::Selection.color().gamma_multiply(0.1),
+                );
+
+                if self.row_settings.non_color_markers {
+                    let rect = row_response.response.rect;
+                    ui.painter().vline(
+                        rect.left() - 4.0,
+                        rect.y_range(),
+                        Stroke::new(2.0, Color32::WHITE),
+                    );
+                }
+            }
         }
 
         open_tag
     }
+
+    fn export_region_image(&self, scan: &ExtendedScanResult, range: std::ops::Range<u64>) {
+        let result = render_region_image(
+            self.tag,
+            &self.data,
+            range.clone(),
+            scan,
+            self.highlight_offset,
+        )
+        .and_then(|image| {
+            std::fs::create_dir_all("regions/")?;
+            let path = format!(
+                "regions/{:08X}_{:X}-{:X}.png",
+                self.tag.0, range.start, range.end
+            );
+            image.save(&path)?;
+            Ok(path)
+        });
+
+        match result {
+            Ok(path) => {
+                TOASTS.lock().success(format!("Region exported to {path}"));
+            }
+            Err(e) => {
+                error!("Failed to export region image: {e:?}");
+                TOASTS.lock().error("Failed to export region image");
+            }
+        }
+    }
+}
+
+/// Changes made by clicking or focusing rows while they're drawn, applied afterwards
+#[derive(Default)]
+struct RowInteraction {
+    select_row: Option<(u64, u64)>,
+    add_bookmark: Option<u64>,
+    /// Removes every region label containing the offset
+    remove_region_labels: Option<u64>,
+    /// New position of the keyboard cursor
+    cursor: Option<u64>,
+    cursor_focus: Option<egui::Id>,
+}
+
+/// Name of an enum value, or a per-bit breakdown for flags
+fn enum_hover_text(field: &EnumField, value: u32) -> String {
+    if !field.flags {
+        return format!("0x{:X}: {}", field.offset, field.format(value));
+    }
+
+    format!(
+        "0x{:X}: {:#0width$b}\n{}",
+        field.offset,
+        value,
+        field.bit_breakdown(value),
+        width = field.ty.bits() as usize + 2
+    )
+}
+
+fn build_rows(
+    data: &[u8],
+    settings: &HexViewSettings,
+    overrides: &[(Range<u64>, RowMode)],
+) -> Vec<DataRow> {
+    data.chunks_exact(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let offset = i as u64 * 16;
+            // Selections don't have to start or end on a row boundary
+            let mode = overrides
+                .iter()
+                .rev()
+                .find(|(range, _)| range.start < offset + 16 && offset < range.end)
+                .map(|(_, mode)| *mode)
+                .unwrap_or(RowMode::Auto);
+            DataRow::new(chunk.try_into().unwrap(), settings, mode)
+        })
+        .collect()
 }
 
 // #[derive(Copy, Clone)]
@@ -283,51 +1783,258 @@ impl TagHexView {
 //     U32,
 // }
 
-#[derive(Clone, Copy)]
-enum DataRow {
-    Raw([u8; 16]),
-    Float([f32; 4]),
-    // U32([u32; 4]),
+/// What a row of floats most likely represents
+#[derive(Clone, Copy, PartialEq)]
+enum FloatKind {
+    Plain,
+    /// XYZ within world-scale range, with a W of 0 or 1
+    Position,
+    /// Unit length with at least two non-zero components
+    Quaternion,
 }
 
-impl DataRow {
-    fn as_raw(&self) -> Option<&[u8; 16]> {
+impl FloatKind {
+    /// Positions beyond this are not considered world coordinates
+    const MAX_COORDINATE: f32 = 100_000.0;
+
+    fn detect(v: &[f32; 4]) -> Self {
+        let length = v.iter().map(|c| c * c).sum::<f32>().sqrt();
+        let non_zero = v.iter().filter(|&&c| c != 0.0).count();
+        if (length - 1.0).abs() < 1e-3 && non_zero >= 2 {
+            return FloatKind::Quaternion;
+        }
+
+        let xyz = &v[..3];
+        let largest = xyz.iter().fold(0f32, |a, c| a.max(c.abs()));
+        let xyz_length = xyz.iter().map(|c| c * c).sum::<f32>().sqrt();
+        if (v[3] == 0.0 || v[3] == 1.0)
+            && (0.01..Self::MAX_COORDINATE).contains(&largest)
+            // Unit vectors are directions/normals, not positions
+            && (xyz_length - 1.0).abs() > 1e-3
+        {
+            return FloatKind::Position;
+        }
+
+        FloatKind::Plain
+    }
+
+    fn color(&self) -> Option<Color32> {
         match self {
-            DataRow::Raw(data) => Some(data),
-            _ => None,
+            FloatKind::Plain => None,
+            FloatKind::Position => Some(PaletteColor::Position.color()),
+            FloatKind::Quaternion => Some(PaletteColor::Quaternion.color()),
         }
     }
+
+    fn description(&self) -> Option<&'static str> {
+        match self {
+            FloatKind::Plain => None,
+            FloatKind::Position => Some("Possibly a position (XYZ + W)"),
+            FloatKind::Quaternion => Some("Possibly a rotation quaternion (XYZW)"),
+        }
+    }
+
+    /// Shown next to the row when non-color markers are enabled
+    fn marker(&self) -> Option<&'static str> {
+        match self {
+            FloatKind::Plain => None,
+            FloatKind::Position => Some("POS"),
+            FloatKind::Quaternion => Some("ROT"),
+        }
+    }
+
+    fn format_vector(&self, v: &[f32; 4]) -> Option<String> {
+        match self {
+            FloatKind::Plain => None,
+            FloatKind::Position => Some(format!("pos({:.2}, {:.2}, {:.2})", v[0], v[1], v[2])),
+            FloatKind::Quaternion => {
+                let [roll, pitch, yaw] = quat_to_euler(*v);
+                Some(format!("rot({roll:.1}°, {pitch:.1}°, {yaw:.1}°)"))
+            }
+        }
+    }
+}
+
+/// XYZW quaternion to roll/pitch/yaw in degrees
+fn quat_to_euler([x, y, z, w]: [f32; 4]) -> [f32; 3] {
+    let roll = (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y));
+    let pitch = (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin();
+    let yaw = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+    [roll.to_degrees(), pitch.to_degrees(), yaw.to_degrees()]
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TextEncoding {
+    Ascii,
+    Utf8,
+    /// In the endianness of the current game version
+    Utf16,
+}
+
+impl TextEncoding {
+    fn label(&self) -> &'static str {
+        match self {
+            TextEncoding::Ascii => "ASCII",
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16 => "UTF-16",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TextCell {
+    Char(char),
+    /// Part of a multi-byte character that starts in an earlier cell
+    Continuation,
+    Invalid,
+}
+
+/// Decodes the data into one cell per byte. Multi-byte characters are shown in the cell of their first byte, and
+/// can span across rows
+fn decode_text_cells(data: &[u8], encoding: TextEncoding) -> Vec<TextCell> {
+    let printable = |c: char| !c.is_control() && (c == ' ' || !c.is_whitespace());
+    let mut cells = vec![TextCell::Invalid; data.len()];
+    match encoding {
+        TextEncoding::Ascii => {
+            for (cell, &b) in cells.iter_mut().zip(data) {
+                if b.is_ascii_graphic() {
+                    *cell = TextCell::Char(b as char);
+                }
+            }
+        }
+        TextEncoding::Utf8 => {
+            let mut i = 0;
+            while i < data.len() {
+                let len = match data[i] {
+                    0x20..=0x7E => 1,
+                    0xC2..=0xDF => 2,
+                    0xE0..=0xEF => 3,
+                    0xF0..=0xF4 => 4,
+                    _ => 0,
+                };
+
+                let c = (len != 0)
+                    .then(|| data.get(i..i + len))
+                    .flatten()
+                    .and_then(|b| std::str::from_utf8(b).ok())
+                    .and_then(|s| s.chars().next())
+                    .filter(|&c| printable(c));
+                match c {
+                    Some(c) => {
+                        cells[i] = TextCell::Char(c);
+                        for cell in &mut cells[i + 1..i + len] {
+                            *cell = TextCell::Continuation;
+                        }
+                        i += len;
+                    }
+                    None => i += 1,
+                }
+            }
+        }
+        TextEncoding::Utf16 => {
+            let endian = package_manager().version.endian();
+            let unit = |i: usize| -> Option<u16> {
+                let b: [u8; 2] = data.get(i..i + 2)?.try_into().ok()?;
+                Some(if endian == Endian::Big {
+                    u16::from_be_bytes(b)
+                } else {
+                    u16::from_le_bytes(b)
+                })
+            };
+
+            let mut i = 0;
+            while let Some(first) = unit(i) {
+                let units = match first {
+                    0xD800..=0xDBFF => unit(i + 2).map(|second| vec![first, second]),
+                    _ => Some(vec![first]),
+                };
+
+                let c = units.as_ref().and_then(|u| {
+                    char::decode_utf16(u.iter().copied())
+                        .next()?
+                        .ok()
+                        .filter(|&c| printable(c))
+                });
+                match (c, units) {
+                    (Some(c), Some(units)) => {
+                        let len = units.len() * 2;
+                        cells[i] = TextCell::Char(c);
+                        for cell in &mut cells[i + 1..i + len] {
+                            *cell = TextCell::Continuation;
+                        }
+                        i += len;
+                    }
+                    _ => i += 2,
+                }
+            }
+        }
+    }
+
+    cells
 }
 
-impl From<[u8; 16]> for DataRow {
-    fn from(data: [u8; 16]) -> Self {
+/// Display mode for a row, overridable per selected region
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowMode {
+    Auto,
+    Raw,
+    Float,
+}
+
+#[derive(Clone, Copy)]
+struct DataRow {
+    bytes: [u8; 16],
+    floats: [f32; 4],
+    /// 4-byte lanes shown as floats
+    float_lanes: [bool; 4],
+    /// Only detected for rows that are floats in every lane
+    kind: FloatKind,
+}
+
+impl DataRow {
+    fn new(bytes: [u8; 16], settings: &HexViewSettings, mode: RowMode) -> Self {
         let from_xe_bytes = if package_manager().version.endian() == Endian::Big {
             f32::from_be_bytes
         } else {
             f32::from_le_bytes
         };
 
-        let floats = [
-            from_xe_bytes(data[0..4].try_into().unwrap()),
-            from_xe_bytes(data[4..8].try_into().unwrap()),
-            from_xe_bytes(data[8..12].try_into().unwrap()),
-            from_xe_bytes(data[12..16].try_into().unwrap()),
-        ];
+        let floats: [f32; 4] =
+            std::array::from_fn(|i| from_xe_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap()));
 
-        let mut all_valid_floats = floats.iter().all(|&v| {
-            (v.is_normal() && v.abs() < 1e7 && v.abs() > 1e-10)
-                || v == 0.0
-                || v == f32::MAX
-                || v == f32::MIN
-        });
-        if floats.iter().all(|&v| v == 0.0) {
-            all_valid_floats = false;
-        }
+        let float_lanes = match mode {
+            RowMode::Raw => [false; 4],
+            RowMode::Float => [true; 4],
+            RowMode::Auto if !settings.detect_floats => [false; 4],
+            RowMode::Auto => {
+                let min = 10f32.powi(settings.min_float_exponent);
+                let max = 10f32.powi(settings.max_float_exponent);
+                let valid = floats.map(|v| {
+                    (v.is_normal() && v.abs() < max && v.abs() > min)
+                        || v == 0.0
+                        || v == f32::MAX
+                        || v == f32::MIN
+                });
 
-        if all_valid_floats {
-            DataRow::Float(floats)
-        } else {
-            DataRow::Raw(data)
+                // Zero lanes are only floats when they're next to actual floats, empty rows stay raw
+                let any_float = floats
+                    .iter()
+                    .zip(valid)
+                    .any(|(&v, valid)| valid && v != 0.0);
+                std::array::from_fn(|i| valid[i] && (floats[i] != 0.0 || any_float))
+            }
+        };
+
+        Self {
+            bytes,
+            floats,
+            float_lanes,
+            kind: if float_lanes.iter().all(|&f| f) {
+                FloatKind::detect(&floats)
+            } else {
+                FloatKind::Plain
+            },
         }
     }
 }
@@ -0,0 +1,161 @@
+use std::fmt::Write;
+
+use eframe::egui::{self, RichText};
+use quicktag_scanner::TagCache;
+use tiger_pkg::package_manager;
+
+use super::TOASTS;
+use crate::logging;
+
+const ISSUES_URL: &str = "https://github.com/v4nguard/quicktag/issues/new";
+/// Browsers and GitHub start rejecting URLs around this length, so the included log is trimmed to fit
+const MAX_URL_LENGTH: usize = 8000;
+
+pub struct IssueReporter {
+    pub open: bool,
+    title: String,
+    description: String,
+    include_logs: bool,
+    log_lines: usize,
+}
+
+impl IssueReporter {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            title: String::new(),
+            description: String::new(),
+            include_logs: true,
+            log_lines: 50,
+        }
+    }
+
+    fn environment(cache_version: u32) -> String {
+        format!(
+            "- Quicktag version: {}\n- Game version: {}\n- Cache version: v{} (expected v{})\n- OS: {} ({})",
+            env!("CARGO_PKG_VERSION"),
+            package_manager().version.name(),
+            cache_version,
+            TagCache::default().version,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+        )
+    }
+
+    fn report_body(&self, cache_version: u32, log_lines: usize) -> String {
+        let mut body = String::new();
+        writeln!(body, "{}\n", self.description.trim()).ok();
+        writeln!(body, "### Environment").ok();
+        writeln!(body, "{}", Self::environment(cache_version)).ok();
+
+        if self.include_logs && log_lines > 0 {
+            writeln!(body, "\n### Log\n```").ok();
+            for line in logging::recent_lines(log_lines) {
+                writeln!(body, "{line}").ok();
+            }
+            writeln!(body, "```").ok();
+        }
+
+        body
+    }
+
+    fn issue_url(&self, cache_version: u32) -> String {
+        // Drop log lines until the URL is short enough
+        let mut log_lines = self.log_lines;
+        loop {
+            let url = format!(
+                "{ISSUES_URL}?title={}&body={}",
+                url_encode(self.title.trim()),
+                url_encode(&self.report_body(cache_version, log_lines))
+            );
+
+            if url.len() <= MAX_URL_LENGTH || log_lines == 0 {
+                return url;
+            }
+
+            log_lines /= 2;
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, cache_version: u32) {
+        let mut open = self.open;
+        egui::Window::new("Report a problem")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.title)
+                        .hint_text("Title")
+                        .desired_width(f32::INFINITY),
+                );
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.description)
+                        .hint_text("What happened, and what did you expect to happen?")
+                        .desired_rows(6)
+                        .desired_width(f32::INFINITY),
+                );
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.include_logs, "Include the last");
+                    ui.add_enabled(
+                        self.include_logs,
+                        egui::DragValue::new(&mut self.log_lines).range(1..=256),
+                    );
+                    ui.label("log lines");
+                });
+
+                ui.separator();
+                ui.label(RichText::new("Environment").strong());
+                ui.label(RichText::new(Self::environment(cache_version)).monospace());
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !self.title.trim().is_empty(),
+                            egui::Button::new("Open GitHub issue"),
+                        )
+                        .on_disabled_hover_text("Enter a title first")
+                        .clicked()
+                    {
+                        if let Err(e) = opener::open_browser(self.issue_url(cache_version)) {
+                            log::error!("Failed to open issue URL: {e:?}");
+                            TOASTS.lock().error("Failed to open the browser");
+                        }
+                    }
+
+                    if ui
+                        .button("Copy report")
+                        .on_hover_text(
+                            "Copies the report as markdown, for pasting it somewhere else",
+                        )
+                        .clicked()
+                    {
+                        let report = format!(
+                            "## {}\n\n{}",
+                            self.title.trim(),
+                            self.report_body(cache_version, self.log_lines)
+                        );
+                        ui.output_mut(|o| o.copied_text = report);
+                        TOASTS.lock().success("Copied report to clipboard");
+                    }
+                });
+            });
+
+        self.open = open;
+    }
+}
+
+fn url_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            _ => write!(out, "%{b:02X}").unwrap(),
+        }
+    }
+    out
+}
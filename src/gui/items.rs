@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use eframe::egui::{self, pos2, vec2, Color32, RichText, Stroke};
+use itertools::Itertools;
+use poll_promise::Promise;
+use quicktag_core::tagtypes::TagType;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::StringCache;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{common::ResponseExt, tag::format_tag_entry, View, ViewAction};
+use crate::texture::cache::TextureCache;
+
+/// Tags with more localized strings than this are most likely string tables rather than item definitions
+const MAX_ITEM_STRINGS: usize = 16;
+const ICON_SIZE: f32 = 96.0;
+
+#[derive(Clone)]
+pub struct ItemDefinition {
+    pub tag: TagHash,
+    /// The first localized string referenced by the definition, usually the display name
+    pub name_hash: u32,
+    /// Remaining localized strings (description, flavor text, etc.)
+    pub other_strings: Vec<u32>,
+    pub icon: Option<TagHash>,
+}
+
+fn is_texture_header(tag: TagHash) -> bool {
+    package_manager()
+        .get_entry(tag)
+        .map(|e| {
+            let st = TagType::from_type_subtype(e.file_type, e.file_subtype);
+            st.is_texture() && st.is_header()
+        })
+        .unwrap_or_default()
+}
+
+/// Resolves the icon texture a tag points to, either directly or through a single intermediate tag (eg. an icon container)
+fn resolve_icon(
+    cache: &TagCache,
+    tag: TagHash,
+    memo: &mut FxHashMap<TagHash, Option<TagHash>>,
+) -> Option<TagHash> {
+    if let Some(icon) = memo.get(&tag) {
+        return *icon;
+    }
+
+    let icon = if is_texture_header(tag) {
+        Some(tag)
+    } else {
+        cache.hashes.get(&tag).and_then(|scan| {
+            scan.file_hashes
+                .iter()
+                .map(|h| h.hash)
+                .find(|&t| is_texture_header(t))
+        })
+    };
+
+    memo.insert(tag, icon);
+    icon
+}
+
+/// Finds tags that look like inventory item definitions: tags referencing a small number of localized strings along with an icon texture
+pub fn find_item_definitions(cache: &TagCache, strings: &StringCache) -> Vec<ItemDefinition> {
+    let mut memo = FxHashMap::default();
+    let mut items = vec![];
+
+    for (&tag, scan) in cache.hashes.iter() {
+        let localized = scan
+            .string_hashes
+            .iter()
+            .filter(|s| strings.contains_key(&s.hash))
+            .sorted_by_key(|s| s.offset)
+            .map(|s| s.hash)
+            .unique()
+            .collect_vec();
+        if localized.is_empty() || localized.len() > MAX_ITEM_STRINGS {
+            continue;
+        }
+
+        let icon = scan
+            .file_hashes
+            .iter()
+            .sorted_by_key(|h| h.offset)
+            .find_map(|h| resolve_icon(cache, h.hash, &mut memo));
+        if icon.is_none() {
+            continue;
+        }
+
+        items.push(ItemDefinition {
+            tag,
+            name_hash: localized[0],
+            other_strings: localized[1..].to_vec(),
+            icon,
+        });
+    }
+
+    items.sort_by_key(|i| i.tag.0);
+    items
+}
+
+pub struct ItemsView {
+    cache: Arc<TagCache>,
+    strings: Arc<StringCache>,
+    texture_cache: TextureCache,
+    items: Option<Promise<Vec<ItemDefinition>>>,
+    filter: String,
+}
+
+impl ItemsView {
+    pub fn new(
+        cache: Arc<TagCache>,
+        strings: Arc<StringCache>,
+        texture_cache: TextureCache,
+    ) -> Self {
+        Self {
+            cache,
+            strings,
+            texture_cache,
+            items: None,
+            filter: String::new(),
+        }
+    }
+
+    fn string_text(&self, hash: u32) -> String {
+        self.strings
+            .get(&hash)
+            .and_then(|s| s.first().cloned())
+            .unwrap_or_else(|| format!("{hash:08X}"))
+    }
+}
+
+impl View for ItemsView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let mut result = None;
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.items.as_ref().map(|p| p.ready().is_some()).unwrap_or(true),
+                    egui::Button::new("Find items"),
+                )
+                .on_hover_text(
+                    "Finds tags that reference both a localized name and an icon texture (eg. inventory item definitions)",
+                )
+                .clicked()
+            {
+                let cache = self.cache.clone();
+                let strings = self.strings.clone();
+                self.items = Some(Promise::spawn_thread("find_item_definitions", move || {
+                    find_item_definitions(&cache, &strings)
+                }));
+            }
+
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        ui.separator();
+
+        let Some(items) = self.items.as_ref() else {
+            ui.label(RichText::new("Items have not been analyzed yet").italics());
+            return None;
+        };
+
+        let Some(items) = items.ready() else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Resolving item names and icons");
+            });
+            return None;
+        };
+
+        let filter = self.filter.to_lowercase();
+        let filtered = items
+            .iter()
+            .filter(|i| {
+                filter.is_empty()
+                    || self
+                        .string_text(i.name_hash)
+                        .to_lowercase()
+                        .contains(&filter)
+                    || i.tag.to_string().to_lowercase().contains(&filter)
+            })
+            .collect_vec();
+
+        ui.label(format!("{} items", filtered.len()));
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for item in filtered {
+                        let name = self.string_text(item.name_hash);
+                        ui.vertical(|ui| {
+                            ui.set_width(ICON_SIZE);
+
+                            let icon_response = ui.allocate_response(
+                                vec2(ICON_SIZE, ICON_SIZE),
+                                egui::Sense::click(),
+                            );
+                            let rect = icon_response.rect;
+                            if ui.is_rect_visible(rect) {
+                                let painter = ui.painter_at(rect);
+                                painter.rect_filled(rect, 4.0, Color32::BLACK);
+                                if let Some(icon) = item.icon {
+                                    let (_, tid) = self.texture_cache.get_or_default(icon);
+                                    painter.image(
+                                        tid,
+                                        rect,
+                                        egui::Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+                                        Color32::WHITE,
+                                    );
+                                }
+
+                                if icon_response.hovered() {
+                                    ui.painter().rect_stroke(
+                                        rect,
+                                        4.0,
+                                        Stroke::new(1.0, Color32::WHITE),
+                                    );
+                                }
+                            }
+
+                            let entry = package_manager().get_entry(item.tag);
+                            let mut icon_response = icon_response.on_hover_ui(|ui| {
+                                ui.strong(&name);
+                                for &s in &item.other_strings {
+                                    ui.label(self.string_text(s));
+                                }
+                                ui.weak(format_tag_entry(item.tag, entry.as_ref()));
+                            });
+                            if let Some(icon) = item.icon {
+                                icon_response = icon_response.tag_context_with_texture(
+                                    icon,
+                                    &self.texture_cache,
+                                    true,
+                                );
+                            }
+                            if icon_response.clicked() {
+                                result = Some(ViewAction::OpenTag(item.tag));
+                            }
+
+                            if ui
+                                .add(
+                                    egui::Label::new(&name)
+                                        .truncate()
+                                        .sense(egui::Sense::click()),
+                                )
+                                .tag_context(item.tag)
+                                .clicked()
+                            {
+                                result = Some(ViewAction::OpenTag(item.tag));
+                            }
+                        });
+                    }
+                });
+            });
+
+        result
+    }
+}
@@ -0,0 +1,157 @@
+//! Memory usage of the caches that quicktag keeps around, shown in the diagnostics panel
+//!
+//! Sizes are estimates from the capacity of the containers, allocator overhead isn't included. The tag cache and
+//! string caches are only measured when they change (or on refresh), as measuring them goes over every tag.
+
+use std::sync::Arc;
+
+use eframe::egui;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::{RawStringHashCache, StringCache};
+use rustc_hash::FxHashMap;
+
+use crate::config;
+use crate::tag_data;
+use crate::texture::cache::TextureCache;
+
+/// Pointers to the measured caches, to notice when they're replaced
+type CacheIdentity = (
+    *const TagCache,
+    *const StringCache,
+    *const RawStringHashCache,
+);
+
+#[derive(Default)]
+pub struct MemoryUsage {
+    measured: Option<CacheIdentity>,
+    tag_cache: usize,
+    strings: usize,
+    raw_strings: usize,
+}
+
+fn string_map_size<T>(map: &FxHashMap<u32, Vec<T>>, item_size: impl Fn(&T) -> usize) -> usize {
+    map.capacity() * std::mem::size_of::<(u32, Vec<T>)>()
+        + map
+            .values()
+            .map(|v| {
+                v.capacity() * std::mem::size_of::<T>() + v.iter().map(&item_size).sum::<usize>()
+            })
+            .sum::<usize>()
+}
+
+fn format_size(bytes: usize) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    let mib = bytes as f64 / MIB;
+    if mib >= 1024.0 {
+        format!("{:.2} GiB", mib / 1024.0)
+    } else {
+        format!("{mib:.1} MiB")
+    }
+}
+
+impl MemoryUsage {
+    fn measure(
+        &mut self,
+        cache: &Arc<TagCache>,
+        strings: &Arc<StringCache>,
+        raw_strings: &Arc<RawStringHashCache>,
+    ) {
+        self.tag_cache = cache.memory_usage();
+        self.strings = string_map_size(strings, |s| s.capacity());
+        self.raw_strings = string_map_size(raw_strings, |(s, _)| s.capacity());
+        self.measured = Some((
+            Arc::as_ptr(cache),
+            Arc::as_ptr(strings),
+            Arc::as_ptr(raw_strings),
+        ));
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        cache: &Arc<TagCache>,
+        strings: &Arc<StringCache>,
+        raw_strings: &Arc<RawStringHashCache>,
+        texture_cache: &TextureCache,
+    ) {
+        let identity = (
+            Arc::as_ptr(cache),
+            Arc::as_ptr(strings),
+            Arc::as_ptr(raw_strings),
+        );
+        if self.measured != Some(identity) {
+            self.measure(cache, strings, raw_strings);
+        }
+
+        ui.horizontal(|ui| {
+            ui.heading("Memory usage");
+            if ui.button("Refresh").clicked() {
+                self.measure(cache, strings, raw_strings);
+            }
+        });
+
+        match memory_stats::memory_stats() {
+            Some(stats) => ui.label(format!(
+                "quicktag is using {} of memory",
+                format_size(stats.physical_mem)
+            )),
+            None => ui.weak("The memory used by quicktag can't be read on this system"),
+        };
+
+        let (tag_data_count, tag_data_size) = tag_data::usage();
+        let (texture_count, texture_size) = texture_cache.memory_usage();
+        let mut evict_tag_data = false;
+        let mut evict_textures = false;
+        egui::Grid::new("memory_usage_grid")
+            .striped(true)
+            .num_columns(3)
+            .show(ui, |ui| {
+                ui.label(format!("Tag cache ({} tags)", cache.hashes.len()));
+                ui.label(format_size(self.tag_cache));
+                ui.weak(
+                    "Needed while the cache is loaded. Narrow the scan scope to make it smaller",
+                );
+                ui.end_row();
+
+                ui.label(format!("Localized strings ({})", strings.len()));
+                ui.label(format_size(self.strings));
+                ui.weak("Only the languages picked in the settings are loaded");
+                ui.end_row();
+
+                ui.label(format!("Raw strings ({})", raw_strings.len()));
+                ui.label(format_size(self.raw_strings));
+                ui.label("");
+                ui.end_row();
+
+                ui.label(format!("Tag data ({tag_data_count} tags)"));
+                ui.label(format!(
+                    "{} of {}",
+                    format_size(tag_data_size),
+                    format_size(tag_data::capacity())
+                ));
+                evict_tag_data = ui.button("Clear").clicked();
+                ui.end_row();
+
+                ui.label(format!("Textures ({texture_count} loaded)"))
+                    .on_hover_text(
+                        "Texture previews and thumbnails, most of this lives in GPU memory",
+                    );
+                ui.label(format!("at least {}", format_size(texture_size)));
+                evict_textures = ui.button("Unload").clicked();
+                ui.end_row();
+            });
+
+        if evict_tag_data {
+            tag_data::clear();
+        }
+        if evict_textures {
+            texture_cache.clear();
+        }
+
+        ui.weak(format!(
+            "The tag data and texture limits can be changed in the settings (currently {} and {} textures)",
+            format_size(tag_data::capacity()),
+            config::with(|c| c.tag_data.texture_cache_count)
+        ));
+    }
+}
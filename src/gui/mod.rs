@@ -1,17 +1,64 @@
+mod annotation_history;
+mod annotation_merge;
 #[cfg(feature = "audio")]
 mod audio;
 #[cfg(feature = "audio")]
 mod audio_list;
+mod backup_restore;
+mod bulk_annotate;
+mod cache_merge;
+mod clusters;
 mod common;
+mod constants;
+mod dashboard;
+mod data_inspector;
+mod diagnostics;
+mod dialogue;
+mod duplicates;
+mod enum_editor;
 mod external_file;
+mod extract;
+mod field_histogram;
+mod file_picker;
+mod fonts;
+mod geometry;
+mod hash_stats;
+mod hex_export;
+mod hex_search;
+mod hex_sidecar;
 mod hexview;
+mod issue_reporter;
+mod items;
+mod memory;
 mod named_tags;
 mod packages;
+mod path_tree;
+mod pattern_search;
+mod placements;
+pub mod profile;
 mod raw_strings;
+mod region_hashes;
+mod rig;
+mod search_results;
+mod settings;
+mod sibling_diff;
+mod similar;
+mod string_languages;
 mod strings;
+mod struct_stubs;
 mod style;
 mod tag;
+mod tag_clipboard;
+mod tag_search;
+mod texture_channels;
+mod texture_export;
+mod texture_usage;
 mod texturelist;
+mod type_matrix;
+mod videos;
+mod virtual_tag;
+mod voice_lines;
+mod watchlist;
 
 use std::cell::RefCell;
 use std::path::Path;
@@ -19,6 +66,7 @@ use std::rc::Rc;
 use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 
+use anyhow::Context;
 use eframe::egui::{PointerButton, TextEdit, Widget};
 use eframe::egui_wgpu::RenderState;
 use eframe::{
@@ -33,32 +81,80 @@ use notify::Watcher;
 use parking_lot::Mutex;
 use poll_promise::Promise;
 use quicktag_core::util::fnv1;
+use quicktag_scanner::cache::FutureCache;
 use quicktag_scanner::context::ScannerContext;
 use quicktag_scanner::{load_tag_cache, scanner_progress, ScanStatus, TagCache};
+use quicktag_scanner::namespaces::hash_namespaces;
+use quicktag_strings::cracked::insert_string;
 use quicktag_strings::localized::{create_stringmap, RawStringHashCache, StringCache};
 use rustc_hash::FxHashSet;
 use strings::StringViewVariant;
 use tiger_pkg::{package_manager, TagHash};
 
+use self::annotation_merge::AnnotationMergeDialog;
+use self::cache_merge::CacheMergeDialog;
+use self::clusters::ClustersView;
+use self::dashboard::DashboardView;
+use self::diagnostics::DiagnosticsView;
+use self::constants::ConstantsView;
+use self::dialogue::DialogueView;
+use self::duplicates::DuplicatesView;
+use self::file_picker::FilePicker;
+use self::fonts::FontsView;
+use self::field_histogram::FieldHistogramView;
+use self::virtual_tag::{VirtualAddress, VirtualTag, VirtualTagWindow};
+use self::hash_stats::HashStatsView;
+use self::issue_reporter::IssueReporter;
+use self::items::ItemsView;
 use self::named_tags::NamedTagView;
 use self::packages::PackagesView;
+use self::path_tree::PathTreeView;
+use self::pattern_search::PatternSearchView;
 use self::raw_strings::RawStringsView;
 use self::strings::StringsView;
 use self::tag::TagView;
+pub use self::extract::{extract_package, EntryCategory, ExtractOptions, ExtractProgress};
+pub use self::tag_search::{tag_names, TagFilter};
+use self::tag_search::TagSearchView;
+use self::texture_export::TextureExportDialog;
 use self::texturelist::TexturesView;
+use self::type_matrix::TypeMatrixView;
+use self::videos::VideosView;
+use self::voice_lines::VoiceLinesView;
+use self::watchlist::WatchListView;
+use crate::config;
+use crate::crash_context;
 use crate::gui::external_file::ExternalFileScanView;
 use crate::gui::tag::TagHistory;
+use crate::hash_import::{import_cracked_hashes, HashImport};
+use crate::i18n::{tr, tr_args};
+use crate::lan_share;
+use crate::open_api;
+use crate::session::{self, Session};
+use crate::shutdown;
+use crate::tag_identity;
+use crate::text_index;
 use crate::texture::cache::TextureCache;
+use crate::watchdog::{self, PatchSnapshot};
+use crate::webhook;
 
 #[derive(PartialEq)]
 pub enum Panel {
     Tag,
     NamedTags,
     Packages,
+    Search,
+    Dashboard,
     Textures,
     #[cfg(feature = "audio")]
     Audio,
+    Videos,
     Strings,
+    Items,
+    Fonts,
+    Analysis,
+    WatchList,
+    Diagnostics,
     ExternalFile,
 }
 
@@ -66,7 +162,23 @@ pub enum Panel {
 pub enum StringsPanel {
     Localized,
     Raw,
+    PathTree,
     Hashes,
+    /// Index into the hash namespace views
+    HashNamespace(usize),
+    VoiceLines,
+    Dialogue,
+}
+
+#[derive(PartialEq)]
+pub enum AnalysisPanel {
+    Duplicates,
+    Clusters,
+    HashStats,
+    TypeMatrix,
+    FieldHistogram,
+    Constants,
+    PatternSearch,
 }
 
 lazy_static! {
@@ -74,11 +186,26 @@ lazy_static! {
 }
 
 pub struct QuickTagApp {
-    scanner_context: ScannerContext,
-    cache_load: Option<Promise<TagCache>>,
+    scanner_context: Arc<ScannerContext>,
+    cache_load: Option<Promise<Result<TagCache, FutureCache>>>,
     reload_cache: bool,
+    /// Cache written by a newer quicktag version, waiting for the user to decide what to do with it
+    future_cache: Option<FutureCache>,
+    /// Set when using a future cache as-is or a cache shared over the network, which shouldn't be deleted or overwritten
+    cache_read_only: bool,
+    /// The cache from before it was rebuilt (after a patch or with "Regenerate Cache"), compared against the rebuilt
+    /// cache once it's loaded. Comes with the title of the report notification
+    diff_snapshot: Option<(PatchSnapshot, &'static str)>,
+    /// Shown when the window is closed while a background job is running or something couldn't be saved
+    close_dialog: bool,
+    /// Set when the user chose to close anyway
+    close_confirmed: bool,
     cache: Arc<TagCache>,
     tag_history: Rc<RefCell<TagHistory>>,
+    /// Set once the tag history of the last session has been reopened, see [`session`]
+    session_restored: bool,
+    /// Length, position and open tag of the tag history when the session was last saved
+    session_stamp: (usize, usize, Option<TagHash>),
     strings: Arc<StringCache>,
     raw_strings: Arc<RawStringHashCache>,
 
@@ -91,18 +218,51 @@ pub struct QuickTagApp {
 
     open_panel: Panel,
     strings_panel: StringsPanel,
+    analysis_panel: AnalysisPanel,
 
     tag_view: Option<TagView>,
     external_file_view: Option<ExternalFileScanView>,
+    /// Windows for decompressed blobs and other data that isn't a tag in the packages
+    virtual_tags: Vec<VirtualTagWindow>,
+    file_picker: Option<FilePicker>,
+    hash_import: Option<Promise<anyhow::Result<Option<HashImport>>>>,
+    /// Corresponding tag and its data in the linked instance, for the tag that's being opened
+    linked_data: Option<(TagHash, Promise<anyhow::Result<(TagHash, Vec<u8>)>>)>,
+    /// Tag that's being read from the host, for tags that aren't in the local packages
+    remote_tag: Option<(TagHash, Promise<Option<Vec<u8>>>)>,
+    issue_reporter: IssueReporter,
+    texture_export: TextureExportDialog,
+    annotation_merge: AnnotationMergeDialog,
+    cache_merge: CacheMergeDialog,
 
     named_tags_view: NamedTagView,
     packages_view: PackagesView,
+    search_view: TagSearchView,
+    dashboard_view: DashboardView,
     textures_view: TexturesView,
     #[cfg(feature = "audio")]
     audio_view: audio_list::AudioView,
+    videos_view: VideosView,
     strings_view: StringsView,
     raw_strings_view: RawStringsView,
+    path_tree_view: PathTreeView,
     raw_string_hashes_view: StringsView,
+    /// Name and view of every hash namespace with hashes in the cache
+    namespace_views: Vec<(String, StringsView)>,
+    voice_lines_view: VoiceLinesView,
+    dialogue_view: DialogueView,
+    items_view: ItemsView,
+    fonts_view: FontsView,
+    duplicates_view: DuplicatesView,
+    clusters_view: ClustersView,
+    hash_stats_view: HashStatsView,
+    field_histogram_view: FieldHistogramView,
+    constants_view: ConstantsView,
+    pattern_search_view: PatternSearchView,
+    type_matrix_view: TypeMatrixView,
+    watchlist_view: WatchListView,
+    diagnostics_view: DiagnosticsView,
+    memory_usage: memory::MemoryUsage,
 
     _schemafile_watcher: notify::RecommendedWatcher,
     schemafile_update_rx: Receiver<Result<notify::Event, notify::Error>>,
@@ -124,6 +284,7 @@ impl QuickTagApp {
             .entry(egui::FontFamily::Proportional)
             .or_default()
             .insert(1, "Destiny_Keys".to_owned());
+        fonts::add_cjk_fallback_fonts(&mut fonts);
 
         cc.egui_ctx.set_fonts(fonts);
 
@@ -140,16 +301,39 @@ impl QuickTagApp {
             .unwrap();
 
         quicktag_core::classes::load_schemafile();
+        quicktag_core::class_mapping::load_class_mapping();
+        watchdog::spawn(cc.egui_ctx.clone());
+        open_api::set_context(cc.egui_ctx.clone());
+
+        let scanner_context = Arc::new(
+            ScannerContext::create(&package_manager()).expect("Failed to create scanner context"),
+        );
 
         QuickTagApp {
-            scanner_context: ScannerContext::create(&package_manager())
-                .expect("Failed to create scanner context"),
+            scanner_context: scanner_context.clone(),
             cache_load: None,
             reload_cache: true,
+            future_cache: None,
+            // Caches shared by another instance are never written to disk or rebuilt
+            cache_read_only: lan_share::remote().is_some(),
+            diff_snapshot: None,
+            close_dialog: false,
+            close_confirmed: false,
             tag_history: Rc::new(RefCell::new(TagHistory::default())),
+            session_restored: false,
+            session_stamp: (0, 0, None),
             cache: Default::default(),
             tag_view: None,
             external_file_view: None,
+            virtual_tags: vec![],
+            file_picker: None,
+            hash_import: None,
+            linked_data: None,
+            remote_tag: None,
+            issue_reporter: IssueReporter::new(),
+            texture_export: TextureExportDialog::new(texture_cache.render_state.clone()),
+            annotation_merge: AnnotationMergeDialog::new(),
+            cache_merge: CacheMergeDialog::new(),
             tag_input: String::new(),
             tag_split: false,
             tag_split_input: (String::new(), String::new()),
@@ -158,23 +342,47 @@ impl QuickTagApp {
 
             open_panel: Panel::Tag,
             strings_panel: StringsPanel::Localized,
+            analysis_panel: AnalysisPanel::Duplicates,
 
             named_tags_view: NamedTagView::new(),
             packages_view: PackagesView::new(texture_cache.clone()),
-            textures_view: TexturesView::new(texture_cache),
+            search_view: TagSearchView::new(),
+            dashboard_view: DashboardView::new(),
+            textures_view: TexturesView::new(texture_cache.clone()),
             #[cfg(feature = "audio")]
             audio_view: audio_list::AudioView::new(),
+            videos_view: VideosView::new(Default::default()),
             strings_view: StringsView::new(
                 strings.clone(),
                 Default::default(),
                 StringViewVariant::LocalizedStrings,
             ),
             raw_strings_view: RawStringsView::new(Default::default()),
+            path_tree_view: PathTreeView::new(Default::default()),
             raw_string_hashes_view: StringsView::new(
                 Arc::new(Default::default()),
                 Default::default(),
                 StringViewVariant::RawWordlist,
             ),
+            namespace_views: vec![],
+            voice_lines_view: VoiceLinesView::new(Default::default(), strings.clone()),
+            dialogue_view: DialogueView::new(Default::default(), strings.clone()),
+            items_view: ItemsView::new(Default::default(), strings.clone(), texture_cache),
+            fonts_view: FontsView::new(),
+            duplicates_view: DuplicatesView::new(),
+            clusters_view: ClustersView::new(Default::default()),
+            hash_stats_view: HashStatsView::new(scanner_context.clone(), Default::default()),
+            field_histogram_view: FieldHistogramView::new(Default::default()),
+            constants_view: ConstantsView::new(
+                scanner_context.clone(),
+                Default::default(),
+                strings.clone(),
+            ),
+            pattern_search_view: PatternSearchView::new(),
+            type_matrix_view: TypeMatrixView::new(),
+            watchlist_view: WatchListView::new(strings.clone()),
+            diagnostics_view: DiagnosticsView::new(),
+            memory_usage: Default::default(),
 
             strings,
             raw_strings: Default::default(),
@@ -189,10 +397,40 @@ impl QuickTagApp {
 
 impl eframe::App for QuickTagApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.reload_cache {
-            self.cache_load = Some(Promise::spawn_thread("load_cache", move || {
+        if ctx.input(|i| i.viewport().close_requested())
+            && !self.close_confirmed
+            && (!shutdown::running_jobs().is_empty() || !shutdown::unsaved().is_empty())
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.close_dialog = true;
+        }
+
+        // Missed detections (eg. while a cache is already loading) are picked up again on the next check
+        if watchdog::take_patch_detected() && self.cache_load.is_none() && !self.cache_read_only {
+            self.diff_snapshot = Some((
+                PatchSnapshot::new(&self.cache, self.strings.clone()),
+                "Patch detected",
+            ));
+            self.cache_load = Some(Promise::spawn_thread("watchdog_rebuild", || {
+                watchdog::reload_packages();
                 load_tag_cache()
             }));
+        }
+
+        if self.reload_cache {
+            self.cache_load = Some(if let Some(address) = lan_share::remote() {
+                Promise::spawn_thread("load_remote_cache", move || {
+                    Ok(lan_share::fetch_cache(&address).unwrap_or_else(|e| {
+                        log::error!("Failed to load the cache shared by {address}: {e:?}");
+                        TOASTS
+                            .lock()
+                            .error(format!("Failed to load the cache shared by {address}: {e}"));
+                        TagCache::default()
+                    }))
+                })
+            } else {
+                Promise::spawn_thread("load_cache", load_tag_cache)
+            });
             self.reload_cache = false;
         }
 
@@ -222,6 +460,7 @@ impl eframe::App for QuickTagApp {
                         let progress = if let ScanStatus::Scanning {
                             current_package,
                             total_packages,
+                            ..
                         } = scanner_progress()
                         {
                             current_package as f32 / total_packages as f32
@@ -249,22 +488,109 @@ impl eframe::App for QuickTagApp {
             .unwrap_or_default()
         {
             let c = self.cache_load.take().unwrap();
-            let cache = c.try_take().unwrap_or_default();
+            let cache = match c.try_take() {
+                Ok(Ok(cache)) => {
+                    watchdog::set_cache_timestamp(cache.timestamp);
+                    cache
+                }
+                Ok(Err(future_cache)) => {
+                    self.future_cache = Some(future_cache);
+                    TagCache::default()
+                }
+                Err(_) => TagCache::default(),
+            };
             self.cache = Arc::new(cache);
+            if !self.session_restored && !self.cache.hashes.is_empty() {
+                self.restore_session();
+            }
+            if self.cache.stale_hashes {
+                TOASTS.lock().warning(tr(
+                    "Only the changed packages were rescanned after new strings became known, other packages can be missing string hashes until the cache is regenerated",
+                ));
+            }
+
+            if let Some(strings) = watchdog::take_rebuilt_strings() {
+                self.strings = Arc::new(strings);
+                self.watchlist_view.set_strings(self.strings.clone());
+            }
+
+            if let Some((snapshot, title)) = self.diff_snapshot.take() {
+                let report = snapshot.report(&self.cache, &self.strings);
+                info!("{title}: {}", report.summary());
+                tag_identity::follow_moved_tags(&report.identity);
+                match report.write() {
+                    Ok(path) => {
+                        TOASTS.lock().info(format!(
+                            "{title}: {}. Report written to {}",
+                            report.summary(),
+                            path.display()
+                        ));
+
+                        if let Some(url) = config::with(|c| c.watchdog.webhook_url.clone()) {
+                            webhook::post(
+                                url,
+                                webhook::patch_report_payload(&report, &path, title),
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Failed to write patch report: {e:?}");
+                        TOASTS
+                            .lock()
+                            .error(format!("Failed to write patch report: {e}"));
+                    }
+                }
+            }
 
+            text_index::rebuild(self.cache.clone(), self.strings.clone());
+            self.search_view.set_cache(self.cache.clone());
+            self.dashboard_view.set_cache(self.cache.clone());
             self.strings_view = StringsView::new(
                 self.strings.clone(),
                 self.cache.clone(),
                 StringViewVariant::LocalizedStrings,
             );
             self.raw_strings_view = RawStringsView::new(self.cache.clone());
+            self.path_tree_view = PathTreeView::new(self.cache.clone());
+            self.videos_view = VideosView::new(self.cache.clone());
+            self.clusters_view = ClustersView::new(self.cache.clone());
+            self.hash_stats_view =
+                HashStatsView::new(self.scanner_context.clone(), self.cache.clone());
+            self.field_histogram_view = FieldHistogramView::new(self.cache.clone());
+            self.constants_view = ConstantsView::new(
+                self.scanner_context.clone(),
+                self.cache.clone(),
+                self.strings.clone(),
+            );
+
+            let changed_tags = self.watchlist_view.check_tags(self.cache.clone());
+            if changed_tags > 0 {
+                TOASTS.lock().info(tr_args(
+                    "{count} watched tags changed, see the Watch list tab",
+                    &[("count", &changed_tags)],
+                ));
+            }
+
+            let changed_strings = self.watchlist_view.check_strings();
+            if changed_strings > 0 {
+                TOASTS.lock().info(format!(
+                    "{changed_strings} watched string containers/strings changed, see the Watch list tab"
+                ));
+            }
+            self.voice_lines_view = VoiceLinesView::new(self.cache.clone(), self.strings.clone());
+            self.dialogue_view = DialogueView::new(self.cache.clone(), self.strings.clone());
+            self.items_view = ItemsView::new(
+                self.cache.clone(),
+                self.strings.clone(),
+                self.texture_cache.clone(),
+            );
 
             let mut new_rsh_cache = RawStringHashCache::default();
             for s in self
                 .cache
                 .hashes
                 .iter()
-                .flat_map(|(_, sc)| sc.raw_strings.iter().cloned())
+                .flat_map(|(_, sc)| sc.raw_strings.iter().map(|s| s.string.clone()))
             {
                 let h = fnv1(s.as_bytes());
                 let entry = new_rsh_cache.entry(h).or_default();
@@ -284,6 +610,41 @@ impl eframe::App for QuickTagApp {
                 entry.push((s.to_string(), true));
             });
 
+            // Namespace hashes are resolved like wordlist hashes, so they show up in the tag view as well
+            self.namespace_views.clear();
+            for namespace in hash_namespaces() {
+                let found_hashes: FxHashSet<u32> = self
+                    .cache
+                    .hashes
+                    .iter()
+                    .filter_map(|(_, scan)| scan.namespace_hashes.get(&namespace.id))
+                    .flat_map(|hashes| hashes.iter().map(|h| h.hash))
+                    .collect();
+                if found_hashes.is_empty() {
+                    continue;
+                }
+
+                let mut names: StringCache = Default::default();
+                for hash in found_hashes {
+                    for name in namespace.names.get(&hash).into_iter().flatten() {
+                        insert_string(&mut names, hash, name);
+                        let entry = new_rsh_cache.entry(hash).or_default();
+                        if !entry.iter().any(|(s2, _)| s2 == name) {
+                            entry.push((name.clone(), true));
+                        }
+                    }
+                }
+
+                self.namespace_views.push((
+                    namespace.name.clone(),
+                    StringsView::new(
+                        Arc::new(names),
+                        self.cache.clone(),
+                        StringViewVariant::HashNamespace(namespace.id.clone()),
+                    ),
+                ));
+            }
+
             let mut filtered_wordlist_hashes: StringCache = Default::default();
             let found_hashes: FxHashSet<u32> = self
                 .cache
@@ -329,37 +690,92 @@ impl eframe::App for QuickTagApp {
             self.raw_strings = Arc::new(new_rsh_cache);
         }
 
+        profile::accent_bar(ctx);
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.add_enabled_ui(!is_loading_cache, |ui| {
                 egui::menu::bar(ui, |ui| {
-                    ui.menu_button("File", |ui| {
-                        if ui.button("Scan file").clicked() {
-                            if let Ok(Some(selected_file)) = native_dialog::FileDialog::new()
+                    ui.menu_button(tr("File"), |ui| {
+                        if ui.button(tr("Scan file")).clicked() {
+                            match native_dialog::FileDialog::new()
                                 .add_filter("All files", &["*"])
                                 .show_open_single_file()
                             {
-                                let filename = selected_file
-                                    .file_name()
-                                    .unwrap()
-                                    .to_string_lossy()
-                                    .to_string();
-                                let data = std::fs::read(&selected_file).unwrap();
-                                self.external_file_view = Some(ExternalFileScanView::new(
-                                    filename,
-                                    &self.scanner_context,
-                                    &data,
-                                ));
-
-                                self.open_panel = Panel::ExternalFile;
+                                Ok(Some(selected_file)) => {
+                                    self.open_external_file(&selected_file);
+                                }
+                                Ok(None) => {}
+                                Err(e) => {
+                                    log::warn!("Native file dialog unavailable ({e}), using the built-in file picker");
+                                    self.file_picker = Some(FilePicker::new("Scan file"));
+                                }
                             }
 
                             ui.close_menu();
                         }
 
-                        if ui.button("Regenerate Cache").clicked() {
+                        if ui
+                            .add_enabled(
+                                !self.cache_read_only && self.hash_import.is_none(),
+                                egui::Button::new(tr("Import cracked hashes...")),
+                            )
+                            .on_hover_text(
+                                tr("Adds strings from a list of hash=string lines, and finds them in the existing cache without rebuilding it"),
+                            )
+                            .clicked()
+                        {
+                            self.start_hash_import();
+                            ui.close_menu();
+                        }
+
+                        if ui
+                            .button(tr("Import annotations..."))
+                            .on_hover_text(
+                                tr("Merges an annotations.json from someone else into your annotations, showing conflicts before anything is changed"),
+                            )
+                            .clicked()
+                        {
+                            self.annotation_merge.pick_file();
+                            ui.close_menu();
+                        }
+
+                        if ui
+                            .add_enabled(
+                                !self.cache_read_only,
+                                egui::Button::new(tr("Merge partial caches...")),
+                            )
+                            .on_hover_text(
+                                tr("Combines caches built with different scan scopes into one, without rescanning"),
+                            )
+                            .clicked()
+                        {
+                            self.cache_merge.open();
+                            ui.close_menu();
+                        }
+
+                        if ui
+                            .add_enabled(
+                                !self.cache_read_only,
+                                egui::Button::new(tr("Regenerate Cache")),
+                            )
+                            .on_hover_text(if self.cache.stale_hashes {
+                                tr("Some packages were scanned before the latest strings were known, regenerating finds their string hashes")
+                            } else {
+                                tr("Rescans every package")
+                            })
+                            .on_disabled_hover_text(
+                                tr("The cache was created by a newer version of quicktag or is shared by another instance, and is opened read-only"),
+                            )
+                            .clicked()
+                        {
                             if let Err(e) = std::fs::remove_file(quicktag_scanner::cache_path()) {
                                 log::error!("Failed to remove cache file: {}", e);
                             } else {
+                                if !self.cache.hashes.is_empty() {
+                                    self.diff_snapshot = Some((
+                                        PatchSnapshot::new(&self.cache, self.strings.clone()),
+                                        "Cache regenerated",
+                                    ));
+                                }
                                 self.tag_view = None;
                                 self.open_panel = Panel::Tag;
 
@@ -369,6 +785,30 @@ impl eframe::App for QuickTagApp {
                         }
                     });
 
+                    ui.menu_button(tr("Edit"), |ui| {
+                        annotation_history::edit_menu(ui);
+                        ui.separator();
+                        if ui.button(tr("Enums...")).clicked() {
+                            enum_editor::open();
+                            ui.close_menu();
+                        }
+                    });
+
+                    ui.menu_button(tr("Settings"), |ui| {
+                        if settings::settings_menu(ui) {
+                            self.tag_view = None;
+                            self.open_panel = Panel::Tag;
+                            self.reload_cache = true;
+                        }
+                    });
+
+                    ui.menu_button(tr("Help"), |ui| {
+                        if ui.button(tr("Report a problem")).clicked() {
+                            self.issue_reporter.open = true;
+                            ui.close_menu();
+                        }
+                    });
+
                     // ui.with_layout(egui::Layout::right_to_left(egui::Align::Max), |ui| {
                     //     egui::global_dark_light_mode_switch(ui);
                     // });
@@ -376,7 +816,7 @@ impl eframe::App for QuickTagApp {
                 ui.separator();
 
                 ui.horizontal(|ui| {
-                    ui.label("Tag:");
+                    ui.label(tr("Tag:"));
                     let mut submitted = false;
 
                     if self.tag_split {
@@ -395,15 +835,23 @@ impl eframe::App for QuickTagApp {
                             && ui.input(|i| i.key_pressed(egui::Key::Enter));
                     } else {
                         submitted |= TextEdit::singleline(&mut self.tag_input)
-                            .hint_text("32/64-bit hex tag")
+                            .hint_text("32/64-bit hex tag[@offset] or tag!offset..len")
                             .desired_width(128. + 8.)
                             .ui(ui)
                             .lost_focus()
                             && ui.input(|i| i.key_pressed(egui::Key::Enter));
                     }
 
-                    if ui.button("Open").clicked() || submitted {
-                        let tag_input_trimmed = self.tag_input.trim();
+                    let open = ui.button(tr("Open")).clicked() || submitted;
+                    let virtual_address = VirtualAddress::parse(&self.tag_input).filter(|_| !self.tag_split);
+                    if let Some(address) = virtual_address.filter(|_| open) {
+                        self.open_virtual_address(address);
+                    } else if open {
+                        let (tag_input_trimmed, offset) = if self.tag_split {
+                            (self.tag_input.trim(), None)
+                        } else {
+                            split_tag_offset(self.tag_input.trim())
+                        };
                         let tag = if self.tag_split {
                             let pkg_id = self.tag_split_input.0.trim();
                             let entry_index = self.tag_split_input.1.trim();
@@ -417,17 +865,7 @@ impl eframe::App for QuickTagApp {
                                 TagHash::new(pkg_id, entry_index)
                             }
                         } else if tag_input_trimmed.len() >= 16 {
-                            let hash =
-                                u64::from_str_radix(tag_input_trimmed, 16).unwrap_or_default();
-                            if let Some(t) = package_manager()
-                                .lookup
-                                .tag64_entries
-                                .get(&u64::from_be(hash))
-                            {
-                                t.hash32
-                            } else {
-                                TagHash::NONE
-                            }
+                            parse_tag64(tag_input_trimmed).unwrap_or(TagHash::NONE)
                         } else if tag_input_trimmed.len() > 8
                             && tag_input_trimmed.chars().all(char::is_numeric)
                         {
@@ -451,24 +889,35 @@ impl eframe::App for QuickTagApp {
                         };
 
                         self.open_tag(tag, true);
+                        if let Some(offset) = offset {
+                            self.jump_to_offset(tag, offset);
+                        }
                     }
 
-                    ui.checkbox(&mut self.tag_split, "Split pkg/entry");
+                    ui.checkbox(&mut self.tag_split, tr("Split pkg/entry"));
                 });
 
                 ui.horizontal(|ui| {
-                    ui.selectable_value(&mut self.open_panel, Panel::Tag, "Tag");
-                    ui.selectable_value(&mut self.open_panel, Panel::NamedTags, "Named tags");
-                    ui.selectable_value(&mut self.open_panel, Panel::Packages, "Packages");
-                    ui.selectable_value(&mut self.open_panel, Panel::Textures, "Textures");
+                    ui.selectable_value(&mut self.open_panel, Panel::Tag, tr("Tag"));
+                    ui.selectable_value(&mut self.open_panel, Panel::NamedTags, tr("Named tags"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Packages, tr("Packages"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Search, tr("Search"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Dashboard, tr("Dashboard"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Textures, tr("Textures"));
                     #[cfg(feature = "audio")]
-                    ui.selectable_value(&mut self.open_panel, Panel::Audio, "Audio");
-                    ui.selectable_value(&mut self.open_panel, Panel::Strings, "Strings");
+                    ui.selectable_value(&mut self.open_panel, Panel::Audio, tr("Audio"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Videos, tr("Videos"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Strings, tr("Strings"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Items, tr("Items"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Fonts, tr("Fonts"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Analysis, tr("Analysis"));
+                    ui.selectable_value(&mut self.open_panel, Panel::WatchList, tr("Watch list"));
+                    ui.selectable_value(&mut self.open_panel, Panel::Diagnostics, tr("Diagnostics"));
                     if let Some(external_file_view) = &self.external_file_view {
                         ui.selectable_value(
                             &mut self.open_panel,
                             Panel::ExternalFile,
-                            format!("File {}", external_file_view.filename),
+                            tr_args("File {name}", &[("name", &external_file_view.filename)]),
                         );
                     }
                 });
@@ -477,9 +926,28 @@ impl eframe::App for QuickTagApp {
 
                 if self.open_panel == Panel::Strings {
                     ui.horizontal(|ui| {
-                        ui.selectable_value(&mut self.strings_panel, StringsPanel::Localized, "Localized");
-                        ui.selectable_value(&mut self.strings_panel, StringsPanel::Raw, "Raw Strings");
-                        ui.selectable_value(&mut self.strings_panel, StringsPanel::Hashes, "Hashes");
+                        ui.selectable_value(&mut self.strings_panel, StringsPanel::Localized, tr("Localized"));
+                        ui.selectable_value(&mut self.strings_panel, StringsPanel::Raw, tr("Raw Strings"));
+                        ui.selectable_value(&mut self.strings_panel, StringsPanel::PathTree, tr("Path tree"));
+                        ui.selectable_value(&mut self.strings_panel, StringsPanel::Hashes, tr("Hashes"));
+                        for (i, (name, _)) in self.namespace_views.iter().enumerate() {
+                            ui.selectable_value(&mut self.strings_panel, StringsPanel::HashNamespace(i), name);
+                        }
+                        ui.selectable_value(&mut self.strings_panel, StringsPanel::VoiceLines, tr("Voice Lines"));
+                        ui.selectable_value(&mut self.strings_panel, StringsPanel::Dialogue, tr("Dialogue"));
+                    });
+                    ui.separator();
+                }
+
+                if self.open_panel == Panel::Analysis {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.analysis_panel, AnalysisPanel::Duplicates, tr("Duplicates"));
+                        ui.selectable_value(&mut self.analysis_panel, AnalysisPanel::Clusters, tr("Clusters"));
+                        ui.selectable_value(&mut self.analysis_panel, AnalysisPanel::HashStats, tr("String hash hit rate"));
+                        ui.selectable_value(&mut self.analysis_panel, AnalysisPanel::TypeMatrix, tr("Type matrix"));
+                        ui.selectable_value(&mut self.analysis_panel, AnalysisPanel::FieldHistogram, tr("Field histogram"));
+                        ui.selectable_value(&mut self.analysis_panel, AnalysisPanel::Constants, tr("Shared constants"));
+                        ui.selectable_value(&mut self.analysis_panel, AnalysisPanel::PatternSearch, tr("Byte search"));
                     });
                     ui.separator();
                 }
@@ -488,6 +956,12 @@ impl eframe::App for QuickTagApp {
                     Panel::Tag => {
                         if let Some(tagview) = &mut self.tag_view {
                             tagview.view(ctx, ui)
+                        } else if let Some((tag, _)) = &self.remote_tag {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(format!("Reading {tag} from the host..."));
+                            });
+                            None
                         } else {
                             ui.label("No tag loaded");
                             None
@@ -495,17 +969,55 @@ impl eframe::App for QuickTagApp {
                     }
                     Panel::NamedTags => self.named_tags_view.view(ctx, ui),
                     Panel::Packages => self.packages_view.view(ctx, ui),
+                    Panel::Search => self.search_view.view(ctx, ui),
+                    Panel::Dashboard => self.dashboard_view.view(ctx, ui),
                     Panel::Textures => self.textures_view.view(ctx, ui),
                     #[cfg(feature = "audio")]
                     Panel::Audio => self.audio_view.view(ctx, ui),
+                    Panel::Videos => self.videos_view.view(ctx, ui),
                     Panel::Strings => match self.strings_panel {
                         StringsPanel::Localized => self.strings_view.view(ctx, ui),
                         StringsPanel::Raw => self.raw_strings_view.view(ctx, ui),
+                        StringsPanel::PathTree => self.path_tree_view.view(ctx, ui),
                         StringsPanel::Hashes => self.raw_string_hashes_view.view(ctx, ui),
+                        StringsPanel::HashNamespace(i) => self
+                            .namespace_views
+                            .get_mut(i)
+                            .and_then(|(_, view)| view.view(ctx, ui)),
+                        StringsPanel::VoiceLines => self.voice_lines_view.view(ctx, ui),
+                        StringsPanel::Dialogue => self.dialogue_view.view(ctx, ui),
+                    },
+                    Panel::Items => self.items_view.view(ctx, ui),
+                    Panel::Fonts => self.fonts_view.view(ctx, ui),
+                    Panel::Analysis => match self.analysis_panel {
+                        AnalysisPanel::Duplicates => self.duplicates_view.view(ctx, ui),
+                        AnalysisPanel::Clusters => self.clusters_view.view(ctx, ui),
+                        AnalysisPanel::HashStats => self.hash_stats_view.view(ctx, ui),
+                        AnalysisPanel::TypeMatrix => self.type_matrix_view.view(ctx, ui),
+                        AnalysisPanel::FieldHistogram => self.field_histogram_view.view(ctx, ui),
+                        AnalysisPanel::Constants => self.constants_view.view(ctx, ui),
+                        AnalysisPanel::PatternSearch => self.pattern_search_view.view(ctx, ui),
                     },
+                    Panel::WatchList => self.watchlist_view.view(ctx, ui),
+                    Panel::Diagnostics => {
+                        self.memory_usage.show(
+                            ui,
+                            &self.cache,
+                            &self.strings,
+                            &self.raw_strings,
+                            &self.texture_cache,
+                        );
+                        ui.separator();
+                        self.diagnostics_view.view(ctx, ui)
+                    }
                     Panel::ExternalFile => {
                         if let Some(external_file_view) = &mut self.external_file_view {
-                            external_file_view.view(ctx, ui, &self.texture_cache)
+                            external_file_view.view(
+                                ctx,
+                                ui,
+                                &self.texture_cache,
+                                &self.scanner_context,
+                            )
                         } else {
                             self.open_panel = Panel::Tag;
                             None
@@ -530,24 +1042,212 @@ impl eframe::App for QuickTagApp {
                 }
 
                 if let Some(action) = action {
-                    match action {
-                        ViewAction::OpenTag(t) => self.open_tag(t, true),
-                    }
+                    self.handle_action(action);
                 }
             });
         });
 
+        let virtual_tag_actions: Vec<_> = self
+            .virtual_tags
+            .iter_mut()
+            .filter_map(|w| w.show(ctx))
+            .collect();
+        self.virtual_tags.retain(|w| w.open);
+        for action in virtual_tag_actions {
+            self.handle_action(action);
+        }
+
+        self.future_cache_ui(ctx);
+        self.close_dialog_ui(ctx);
+
+        if self.issue_reporter.open {
+            self.issue_reporter.show(ctx, self.cache.version);
+        }
+
+        if let Some(tags) = common::take_pending_texture_export() {
+            self.texture_export.open_for(tags);
+        }
+        self.texture_export.show(ctx);
+        self.annotation_merge.show(ctx);
+        if let Some((class, offset)) = enum_editor::show(ctx) {
+            self.field_histogram_view.compute_for(class, offset);
+            self.open_panel = Panel::Analysis;
+            self.analysis_panel = AnalysisPanel::FieldHistogram;
+        }
+
+        if let Some(paths) = self.cache_merge.show(ctx) {
+            self.tag_view = None;
+            self.open_panel = Panel::Tag;
+            self.cache_load = Some(Promise::spawn_thread("merge_caches", move || {
+                cache_merge::merge_partial_caches(&paths).or_else(|e| {
+                    log::error!("Failed to merge caches: {e:?}");
+                    TOASTS.lock().error(format!("Failed to merge caches: {e}"));
+                    load_tag_cache()
+                })
+            }));
+        }
+
+        if let Some(picker) = self.file_picker.as_mut() {
+            let selected = picker.show(ctx);
+            if !picker.is_open() {
+                self.file_picker = None;
+            }
+
+            if let Some(path) = selected {
+                self.open_external_file(&path);
+            }
+        }
+
+        self.poll_hash_import();
+
+        if let Some(strings) = string_languages::take_reloaded_strings() {
+            self.strings = Arc::new(strings);
+            text_index::rebuild(self.cache.clone(), self.strings.clone());
+            self.watchlist_view.set_strings(self.strings.clone());
+            self.constants_view.set_strings(self.strings.clone());
+            self.strings_view = StringsView::new(
+                self.strings.clone(),
+                self.cache.clone(),
+                StringViewVariant::LocalizedStrings,
+            );
+            self.voice_lines_view = VoiceLinesView::new(self.cache.clone(), self.strings.clone());
+            self.dialogue_view = DialogueView::new(self.cache.clone(), self.strings.clone());
+            self.items_view = ItemsView::new(
+                self.cache.clone(),
+                self.strings.clone(),
+                self.texture_cache.clone(),
+            );
+        }
+
+        if let Some((tag, offset)) = open_api::take_pending_open() {
+            self.open_panel = Panel::Tag;
+            self.open_tag(tag, true);
+            if let Some(offset) = offset {
+                self.jump_to_offset(tag, offset);
+            }
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+
+        if let Some((tag, linked_tag)) = lan_share::take_linked_open() {
+            self.open_tag_from_link(tag, linked_tag);
+        }
+        self.poll_remote_tag();
+        self.poll_linked_data();
+        if self.linked_data.is_some() || self.remote_tag.is_some() {
+            ctx.request_repaint();
+        } else if lan_share::is_following_links() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+
+        for tag in common::take_pending_container_watches() {
+            self.watchlist_view.watch_container(tag);
+        }
+        for tag in common::take_pending_tag_watches() {
+            self.watchlist_view.watch_tag(tag);
+        }
+
+        if let Some(path) = common::take_pending_external_scan() {
+            self.open_external_file(&path);
+        }
+
+        annotation_history::handle_shortcuts(ctx);
+        if let Some(tag) = tag_clipboard::handle_paste(ctx) {
+            self.open_tag(tag, true);
+        }
+
         TOASTS.lock().show(ctx);
+        self.update_session();
 
         // Redraw the window while we're loading textures. This prevents loading textures from seeming "stuck"
         if self.texture_cache.is_loading_textures() {
             ctx.request_repaint();
         }
     }
+
+    fn on_exit(&mut self) {
+        shutdown::flush();
+    }
 }
 
 impl QuickTagApp {
+    /// Reopens the tag history of the last session, and the tag that was open
+    fn restore_session(&mut self) {
+        self.session_restored = true;
+        let session = session::get();
+        let tags = session.tags();
+        if tags.is_empty() {
+            return;
+        }
+
+        let current = session.current.min(tags.len() - 1);
+        {
+            let mut history = self.tag_history.borrow_mut();
+            for &tag in &tags {
+                history.push(tag);
+            }
+            history.current = current;
+        }
+
+        if self.cache.hashes.contains_key(&tags[current]) {
+            self.open_tag_view(tags[current], false);
+        }
+        info!("Restored a session with {} tags", tags.len());
+    }
+
+    /// Saves the tag history as the session when it changed
+    fn update_session(&mut self) {
+        if !self.session_restored {
+            return;
+        }
+
+        let history = self.tag_history.borrow();
+        let stamp = (
+            history.tags.len(),
+            history.current,
+            history.tags.get(history.current).map(|t| t.0),
+        );
+        if stamp != self.session_stamp {
+            self.session_stamp = stamp;
+            session::set(Session::new(
+                history.tags.iter().map(|t| t.0),
+                history.current,
+            ));
+        }
+    }
+
     fn open_tag(&mut self, tag: TagHash, push_history: bool) {
+        if !self.open_tag_view(tag, push_history) {
+            return;
+        }
+
+        if let Some(address) = lan_share::linked_address() {
+            self.linked_data = Some((
+                tag,
+                Promise::spawn_thread("open_linked", move || {
+                    lan_share::open_linked(&address, tag)
+                }),
+            ));
+        }
+    }
+
+    /// Opens a tag that was opened in a linked instance, without opening it there again
+    fn open_tag_from_link(&mut self, tag: TagHash, linked_tag: TagHash) {
+        if !self.open_tag_view(tag, true) {
+            return;
+        }
+
+        if let Some(address) = lan_share::linked_address() {
+            self.linked_data = Some((
+                tag,
+                Promise::spawn_thread("read_linked", move || {
+                    Ok((linked_tag, lan_share::read_linked_tag(&address, linked_tag)?))
+                }),
+            ));
+        }
+    }
+
+    /// Returns whether the tag could be opened
+    fn open_tag_view(&mut self, tag: TagHash, push_history: bool) -> bool {
         let new_view = TagView::create(
             self.cache.clone(),
             self.tag_history.clone(),
@@ -557,8 +1257,20 @@ impl QuickTagApp {
             self.wgpu_state.clone(),
             self.texture_cache.clone(),
         );
-        if new_view.is_some() {
+        let opened = new_view.is_some();
+        if opened {
+            crash_context::set("Open tag", tag.to_string());
             self.tag_view = new_view;
+            self.remote_tag = None;
+            self.open_panel = Panel::Tag;
+        } else if package_manager().get_entry(tag).is_none() && lan_share::remote().is_some() {
+            self.tag_view = None;
+            self.remote_tag = Some((
+                tag,
+                Promise::spawn_thread("read_remote_tag", move || {
+                    lan_share::read_remote_tag(tag)
+                }),
+            ));
             self.open_panel = Panel::Tag;
         } else if package_manager().get_entry(tag).is_some() {
             TOASTS.lock().warning(format!(
@@ -574,11 +1286,359 @@ impl QuickTagApp {
         if push_history {
             self.tag_history.borrow_mut().push(tag);
         }
+
+        opened
     }
+
+    /// Opens the tag that was read from the host once it's arrived
+    fn poll_remote_tag(&mut self) {
+        let Some((tag, promise)) = self.remote_tag.take() else {
+            return;
+        };
+
+        let data = match promise.try_take() {
+            Ok(data) => data,
+            Err(promise) => {
+                self.remote_tag = Some((tag, promise));
+                return;
+            }
+        };
+
+        let new_view = data.and_then(|data| {
+            TagView::from_data(
+                self.cache.clone(),
+                self.tag_history.clone(),
+                self.strings.clone(),
+                self.raw_strings.clone(),
+                tag,
+                data,
+                self.wgpu_state.clone(),
+                self.texture_cache.clone(),
+            )
+        });
+
+        if new_view.is_some() {
+            crash_context::set("Open tag", tag.to_string());
+            self.tag_view = new_view;
+        } else {
+            TOASTS.lock().error(format!("Could not read tag {tag} from the host"));
+        }
+    }
+
+    /// Passes the data of the corresponding tag in the linked instance to the tag view once it's been read
+    fn poll_linked_data(&mut self) {
+        let Some((tag, promise)) = self.linked_data.take() else {
+            return;
+        };
+
+        match promise.try_take() {
+            Ok(Ok((linked_tag, data))) => {
+                if let Some(view) = self.tag_view.as_mut().filter(|v| v.tag() == tag) {
+                    view.set_linked_data(linked_tag, data);
+                }
+            }
+            Ok(Err(e)) => {
+                log::warn!("Linked instance: failed to open {tag}: {e:?}");
+                TOASTS
+                    .lock()
+                    .warning(format!("Linked instance: failed to open {tag}: {e}"));
+            }
+            Err(promise) => self.linked_data = Some((tag, promise)),
+        }
+    }
+
+    /// Lists what would be lost by closing the window, and lets the user close anyway
+    fn close_dialog_ui(&mut self, ctx: &egui::Context) {
+        if !self.close_dialog {
+            return;
+        }
+
+        let jobs = shutdown::running_jobs();
+        let unsaved = shutdown::unsaved();
+        egui::Window::new("Close quicktag?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                if !jobs.is_empty() {
+                    ui.label("These jobs are still running and will be interrupted:");
+                    for job in &jobs {
+                        ui.label(format!(" • {job}"));
+                    }
+                    ui.add_space(4.0);
+                }
+
+                if !unsaved.is_empty() {
+                    ui.label(format!(
+                        "Changes to the {} couldn't be saved, see the log for details",
+                        unsaved.join(", ")
+                    ));
+                    if ui.button("Retry saving").clicked() {
+                        shutdown::flush();
+                    }
+                    ui.add_space(4.0);
+                }
+
+                if jobs.is_empty() && unsaved.is_empty() {
+                    ui.label("Everything is done and saved");
+                }
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Close anyway").clicked() {
+                        self.close_confirmed = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.close_dialog = false;
+                    }
+                });
+            });
+
+        // Keep the job list up to date
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+    }
+
+    /// Asks the user what to do with a cache written by a newer version of quicktag
+    fn future_cache_ui(&mut self, ctx: &egui::Context) {
+        let Some(future_cache) = self.future_cache.as_mut() else {
+            return;
+        };
+
+        let mut resolved = false;
+        egui::Window::new("Future cache")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Your cache file ({}) was created by a newer version of quicktag\n\nCache version: v{}\nExpected version: v{}",
+                    future_cache.path.display(),
+                    future_cache.version,
+                    TagCache::default().version
+                ));
+                ui.add_space(8.0);
+
+                if ui
+                    .button("Rename cache and rebuild")
+                    .on_hover_text("Keeps the newer cache next to the new one, so the newer version of quicktag can still use it")
+                    .clicked()
+                {
+                    match future_cache.rename_aside() {
+                        Ok(new_path) => {
+                            info!("Moved future cache to {}", new_path.display());
+                            self.reload_cache = true;
+                            resolved = true;
+                        }
+                        Err(e) => {
+                            log::error!("Failed to rename cache file: {e}");
+                            TOASTS.lock().error(format!("Failed to rename cache file: {e}"));
+                        }
+                    }
+                }
+
+                if ui
+                    .add_enabled(
+                        future_cache.readable.is_some(),
+                        egui::Button::new("Continue read-only"),
+                    )
+                    .on_hover_text("Uses the cache as-is without modifying it")
+                    .on_disabled_hover_text("The cache format is not compatible with this version")
+                    .clicked()
+                {
+                    if let Some(cache) = future_cache.readable.take() {
+                        self.cache_read_only = true;
+                        self.cache_load = Some(Promise::from_ready(Ok(cache)));
+                        resolved = true;
+                    }
+                }
+
+                if ui.button("Quit").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+
+        if resolved {
+            self.future_cache = None;
+        }
+    }
+
+    fn start_hash_import(&mut self) {
+        let path = match native_dialog::FileDialog::new()
+            .add_filter("Text files", &["txt"])
+            .add_filter("All files", &["*"])
+            .show_open_single_file()
+        {
+            Ok(Some(path)) => path,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Failed to open file dialog: {e}");
+                TOASTS.lock().error("Failed to open file dialog");
+                return;
+            }
+        };
+
+        let strings = self.strings.clone();
+        let cache = self.cache.clone();
+        self.hash_import = Some(Promise::spawn_thread("import_cracked_hashes", move || {
+            import_cracked_hashes(&path, &strings, &cache)
+        }));
+        TOASTS
+            .lock()
+            .info("Importing cracked hashes, this may take a moment");
+    }
+
+    fn poll_hash_import(&mut self) {
+        let Some(promise) = self.hash_import.take() else {
+            return;
+        };
+
+        match promise.try_take() {
+            Ok(Ok(Some(import))) => {
+                TOASTS.lock().success(format!(
+                    "Imported {} new strings, found {} new string hashes in tags",
+                    import.imported, import.new_matches
+                ));
+                self.strings = Arc::new(import.strings);
+                self.scanner_context = Arc::new(import.context);
+                // Refreshes every view that uses the cache or strings
+                self.cache_load = Some(Promise::from_ready(Ok(import.cache)));
+            }
+            Ok(Ok(None)) => {
+                TOASTS
+                    .lock()
+                    .info("The list did not contain any new strings");
+            }
+            Ok(Err(e)) => {
+                log::error!("Failed to import cracked hashes: {e:?}");
+                TOASTS
+                    .lock()
+                    .error(format!("Failed to import cracked hashes: {e}"));
+            }
+            Err(promise) => self.hash_import = Some(promise),
+        }
+    }
+
+    /// Scans a file from disk and shows it in the external file view
+    fn handle_action(&mut self, action: ViewAction) {
+        match action {
+            ViewAction::OpenTag(t) => self.open_tag(t, true),
+            ViewAction::OpenTagAtOffset(t, offset) => {
+                self.open_tag(t, true);
+                self.jump_to_offset(t, offset);
+            }
+            ViewAction::SearchRawType {
+                file_type,
+                file_subtype,
+            } => {
+                self.search_view.search_raw_type(file_type, file_subtype);
+                self.open_panel = Panel::Search;
+            }
+            ViewAction::Search(filter) => {
+                self.search_view.search_filter(filter);
+                self.open_panel = Panel::Search;
+            }
+            ViewAction::FieldHistogram { class, offset } => {
+                self.field_histogram_view.compute_for(class, offset);
+                self.open_panel = Panel::Analysis;
+                self.analysis_panel = AnalysisPanel::FieldHistogram;
+            }
+            ViewAction::OpenVirtualTag(tag) => {
+                self.virtual_tags.push(VirtualTagWindow::new(
+                    tag,
+                    &self.scanner_context,
+                    self.strings.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Opens a range of a tag as a sub-view, see [`VirtualAddress`]
+    fn open_virtual_address(&mut self, address: VirtualAddress) {
+        match VirtualTag::open(address.clone()) {
+            Ok(tag) => self.handle_action(ViewAction::OpenVirtualTag(tag)),
+            Err(e) => {
+                log::error!("Failed to open {address}: {e:?}");
+                TOASTS.lock().error(format!("Failed to open {address}: {e}"));
+            }
+        }
+    }
+
+    fn open_external_file(&mut self, path: &Path) {
+        match ExternalFileScanView::new(path, &self.scanner_context) {
+            Ok(v) => {
+                self.external_file_view = Some(v);
+                self.open_panel = Panel::ExternalFile;
+            }
+            Err(e) => {
+                log::error!("Failed to open {}: {e:?}", path.display());
+                TOASTS
+                    .lock()
+                    .error(format!("Failed to open {}", path.display()));
+            }
+        }
+    }
+
+    /// Scrolls the hex view of the currently opened tag to the given offset
+    fn jump_to_offset(&mut self, tag: TagHash, offset: u64) {
+        if let Some(tag_view) = self.tag_view.as_mut() {
+            if tag_view.tag() == tag {
+                tag_view.jump_to_offset(offset);
+            }
+        }
+    }
+}
+
+/// Splits a `hash@offset` string (optionally prefixed with `quicktag://`) into the tag and offset parts
+/// The offset is always parsed as hexadecimal, with or without a `0x` prefix
+pub(crate) fn split_tag_offset(input: &str) -> (&str, Option<u64>) {
+    let input = input
+        .strip_prefix("quicktag://")
+        .unwrap_or(input)
+        .trim_end_matches('/');
+
+    match input.split_once('@') {
+        Some((tag, offset)) => {
+            let offset = offset.trim();
+            let offset = offset
+                .strip_prefix("0x")
+                .or_else(|| offset.strip_prefix("0X"))
+                .unwrap_or(offset);
+
+            (tag.trim(), u64::from_str_radix(offset, 16).ok())
+        }
+        None => (input, None),
+    }
+}
+
+/// Looks up a 64-bit tag hash. Hashes are accepted in either byte order, as they're copied both as shown in the tag
+/// view and as stored in the packages
+pub(crate) fn parse_tag64(input: &str) -> anyhow::Result<TagHash> {
+    let hash = u64::from_be(u64::from_str_radix(input, 16).context("Invalid 64-bit tag hash")?);
+    let pm = package_manager();
+    let tag64_entries = &pm.lookup.tag64_entries;
+    tag64_entries
+        .get(&hash)
+        .or_else(|| tag64_entries.get(&hash.swap_bytes()))
+        .map(|e| e.hash32)
+        .with_context(|| format!("Unknown 64-bit tag hash {input}"))
 }
 
 pub enum ViewAction {
     OpenTag(TagHash),
+    /// Opens a tag with the hex view scrolled to the given offset
+    OpenTagAtOffset(TagHash, u64),
+    /// Lists every tag with a file type/subtype combination in the search panel
+    SearchRawType {
+        file_type: u8,
+        file_subtype: u8,
+    },
+    /// Searches with the given filters in the search panel
+    Search(TagFilter),
+    /// Shows the distribution of a field across every tag of a class in the analysis panel
+    FieldHistogram { class: u32, offset: usize },
+    /// Opens data that isn't a tag in the packages (eg. a decompressed blob) in a hex view window
+    OpenVirtualTag(VirtualTag),
 }
 
 pub trait View {
@@ -1,12 +1,21 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
 use eframe::egui::{self, RichText};
+use log::error;
+use poll_promise::Promise;
 use quicktag_core::tagtypes::TagType;
+use quicktag_scanner::scope::ScanScope;
 use tiger_pkg::{manager::PackagePath, package::UEntryHeader, package_manager, TagHash, Version};
 
 use super::{
     common::{dump_wwise_info, ResponseExt},
+    extract::{extract_package, ExtractOptions, ExtractProgress},
     tag::format_tag_entry,
-    View, ViewAction,
+    View, ViewAction, TOASTS,
 };
+use crate::config::{self, ScanScopeMode};
 use crate::gui::common::open_audio_file_in_default_application;
 use crate::texture::cache::TextureCache;
 use crate::util::format_file_size;
@@ -20,6 +29,8 @@ pub struct PackagesView {
     sorted_package_paths: Vec<(u16, PackagePath)>,
     show_only_hash64: bool,
     sort_by_size: bool,
+
+    extraction: Option<(Arc<ExtractProgress>, Promise<anyhow::Result<PathBuf>>)>,
 }
 
 impl PackagesView {
@@ -41,6 +52,69 @@ impl PackagesView {
             sorted_package_paths,
             show_only_hash64: false,
             sort_by_size: false,
+            extraction: None,
+        }
+    }
+
+    fn extraction_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        if let Some((progress, promise)) = &self.extraction {
+            if let Some(result) = promise.ready() {
+                let cancelled = progress.cancel.load(Ordering::Relaxed);
+                match result {
+                    Ok(path) if cancelled => {
+                        TOASTS.lock().warning(format!(
+                            "Package extraction cancelled, partial output in {}",
+                            path.display()
+                        ));
+                    }
+                    Ok(path) => {
+                        TOASTS
+                            .lock()
+                            .success(format!("Package extracted to {}", path.display()));
+                    }
+                    Err(e) => {
+                        error!("Failed to extract package: {e:?}");
+                        TOASTS.lock().error("Failed to extract package");
+                    }
+                }
+                self.extraction = None;
+            } else {
+                let current = progress.current.load(Ordering::Relaxed);
+                let total = progress.total.load(Ordering::Relaxed).max(1);
+                ui.add(
+                    egui::ProgressBar::new(current as f32 / total as f32)
+                        .desired_width(192.0)
+                        .text(format!("Extracting {current}/{total}")),
+                );
+                if ui.button("Cancel").clicked() {
+                    progress.cancel.store(true, Ordering::Relaxed);
+                }
+                ctx.request_repaint();
+                return;
+            }
+        }
+
+        if ui
+            .button("Extract entire package")
+            .on_hover_text(
+                "Extracts every entry to extracted/<package>, sorted by type, with a manifest.json",
+            )
+            .clicked()
+        {
+            let pkg_id = self.selected_package;
+            let progress = Arc::new(ExtractProgress::default());
+            let progress_thread = progress.clone();
+            self.extraction = Some((
+                progress,
+                Promise::spawn_thread("extract_package", move || {
+                    extract_package(
+                        pkg_id,
+                        Path::new("extracted"),
+                        &ExtractOptions::default(),
+                        &progress_thread,
+                    )
+                }),
+            ));
         }
     }
 
@@ -71,12 +145,25 @@ impl View for PackagesView {
                     ui.label("Search:");
                     ui.text_edit_singleline(&mut self.package_filter);
                 });
+                // Packages in the scan scope get a checkbox when the scope is picked by hand
+                let mut scan_packages = config::with(|c| {
+                    (c.cache.scan_scope == ScanScopeMode::Selected)
+                        .then(|| c.cache.scan_packages.clone())
+                });
+                let mut scan_packages_changed = false;
+                if let Some(scan_packages) = &scan_packages {
+                    ui.label(format!("{} packages selected for scanning", scan_packages.len()))
+                        .on_hover_text(
+                            "The next cache is built from only these packages, see the scan scope in the settings",
+                        );
+                }
+
                 egui::ScrollArea::vertical()
                     .max_width(f32::INFINITY)
                     .show(ui, |ui| {
                         let mut sort_entries = false;
                         for (id, path) in self.sorted_package_paths.iter() {
-                            let package_name = format!("{}_{}", path.name, path.id);
+                            let package_name = ScanScope::package_name(path);
                             if !self.package_filter.is_empty()
                                 && !package_name
                                     .to_lowercase()
@@ -91,14 +178,29 @@ impl View for PackagesView {
                                 ""
                             };
 
-                            if ui
-                                .selectable_value(
-                                    &mut self.selected_package,
-                                    *id,
-                                    format!("{id:04x}: {redacted}{package_name}"),
-                                )
-                                .changed()
-                            {
+                            let selected = ui
+                                .horizontal(|ui| {
+                                    if let Some(scan_packages) = &mut scan_packages {
+                                        let mut scanned = scan_packages.contains(&package_name);
+                                        if ui.checkbox(&mut scanned, "").changed() {
+                                            if scanned {
+                                                scan_packages.push(package_name.clone());
+                                            } else {
+                                                scan_packages.retain(|p| *p != package_name);
+                                            }
+                                            scan_packages_changed = true;
+                                        }
+                                    }
+
+                                    ui.selectable_value(
+                                        &mut self.selected_package,
+                                        *id,
+                                        format!("{id:04x}: {redacted}{package_name}"),
+                                    )
+                                })
+                                .inner;
+
+                            if selected.changed() {
                                 self.package_entry_search_cache = vec![];
                                 if let Ok(p) = package_manager().version.open(&path.path) {
                                     for (i, e) in p.entries().iter().enumerate() {
@@ -121,6 +223,10 @@ impl View for PackagesView {
                             self.sort_entries();
                         }
                     });
+
+                if let Some(scan_packages) = scan_packages.filter(|_| scan_packages_changed) {
+                    config::with_mut(|c| c.cache.scan_packages = scan_packages);
+                }
             });
 
         egui::CentralPanel::default()
@@ -140,6 +246,8 @@ impl View for PackagesView {
                             dump_wwise_info(self.selected_package);
                         }
 
+                        self.extraction_ui(ctx, ui);
+
                         ui.checkbox(&mut self.show_only_hash64, "★ Only show hash64");
                         if ui
                             .checkbox(&mut self.sort_by_size, "Sort by size descending")
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use eframe::egui::{self, collapsing_header::CollapsingState, RichText};
+use itertools::Itertools;
+use quicktag_core::tagtypes::TagType;
+use quicktag_scanner::TagCache;
+use quicktag_strings::classify::RawStringKind;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{common::ResponseExt, tag::format_tag_entry, View, ViewAction};
+
+#[derive(Default)]
+struct PathNode {
+    /// Segment name as it was first seen
+    name: String,
+    /// Keyed by the lowercase segment name, so paths that only differ in case end up in the same folder
+    children: BTreeMap<String, PathNode>,
+    /// Index of the path string ending at this node
+    path: Option<usize>,
+    /// Amount of paths in this node and all of its children
+    count: usize,
+}
+
+impl PathNode {
+    fn insert(&mut self, segments: &[&str], path: usize) {
+        self.count += 1;
+        let Some((first, rest)) = segments.split_first() else {
+            self.path = Some(path);
+            return;
+        };
+
+        self.children
+            .entry(first.to_lowercase())
+            .or_insert_with(|| PathNode {
+                name: first.to_string(),
+                ..Default::default()
+            })
+            .insert(rest, path);
+    }
+}
+
+/// Virtual folder tree of every path-like raw string in the cache
+pub struct PathTreeView {
+    /// Path string and the tags and offsets it was found at
+    paths: Vec<(String, Vec<(TagHash, u64)>)>,
+    root: PathNode,
+
+    filter: String,
+    selected: Option<usize>,
+}
+
+impl PathTreeView {
+    pub fn new(cache: Arc<TagCache>) -> Self {
+        let mut paths: FxHashMap<String, Vec<(TagHash, u64)>> = Default::default();
+        for (tag, scan) in cache.hashes.iter() {
+            for s in &scan.raw_strings {
+                if RawStringKind::classify(&s.string) == RawStringKind::DevPath {
+                    paths
+                        .entry(s.string.trim().to_string())
+                        .or_default()
+                        .push((*tag, s.offset));
+                }
+            }
+        }
+
+        let mut view = Self {
+            paths: paths
+                .into_iter()
+                .sorted_by(|(a, _), (b, _)| a.cmp(b))
+                .collect(),
+            root: PathNode::default(),
+            filter: String::new(),
+            selected: None,
+        };
+        view.rebuild_tree();
+        view
+    }
+
+    fn rebuild_tree(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.root = PathNode::default();
+        for (i, (path, _)) in self.paths.iter().enumerate() {
+            if !filter.is_empty() && !path.to_lowercase().contains(&filter) {
+                continue;
+            }
+
+            let segments = path
+                .split(['/', '\\'])
+                .filter(|s| !s.is_empty())
+                .collect_vec();
+            self.root.insert(&segments, i);
+        }
+    }
+
+    fn show_node(
+        ui: &mut egui::Ui,
+        node: &PathNode,
+        id: egui::Id,
+        paths: &[(String, Vec<(TagHash, u64)>)],
+        selected: &mut Option<usize>,
+    ) {
+        for (key, child) in &node.children {
+            let child_id = id.with(key);
+            if child.children.is_empty() {
+                Self::path_label(ui, &child.name, child.path, paths, selected);
+                continue;
+            }
+
+            // Folders with a single path in them are opened by default, there's nothing to lose
+            CollapsingState::load_with_default_open(ui.ctx(), child_id, child.count == 1)
+                .show_header(ui, |ui| {
+                    if child.path.is_some() {
+                        Self::path_label(ui, &child.name, child.path, paths, selected);
+                    } else {
+                        ui.label(&child.name);
+                    }
+                    ui.weak(format!("({})", child.count));
+                })
+                .body(|ui| Self::show_node(ui, child, child_id, paths, selected));
+        }
+    }
+
+    fn path_label(
+        ui: &mut egui::Ui,
+        name: &str,
+        path: Option<usize>,
+        paths: &[(String, Vec<(TagHash, u64)>)],
+        selected: &mut Option<usize>,
+    ) {
+        let Some(path) = path else {
+            return;
+        };
+
+        let (string, tags) = &paths[path];
+        let label = if tags.len() > 1 {
+            format!("{name} ({} occurrences)", tags.len())
+        } else {
+            name.to_string()
+        };
+
+        let response = ui
+            .selectable_label(*selected == Some(path), RichText::new(label).strong())
+            .on_hover_text(string);
+        if response.clicked() {
+            *selected = Some(path);
+        }
+
+        response.context_menu(|ui| {
+            if ui.selectable_label(false, "Copy path").clicked() {
+                ui.output_mut(|o| o.copied_text = string.clone());
+                ui.close_menu();
+            }
+        });
+    }
+}
+
+impl View for PathTreeView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let result = if let Some((_, tags)) = self.selected.and_then(|i| self.paths.get(i)) {
+            egui::SidePanel::right("path_tree_right_panel")
+                .show_inside(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_width(f32::INFINITY)
+                        .show(ui, |ui| {
+                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
+                            for &(tag, offset) in tags {
+                                if let Some(e) = package_manager().get_entry(tag) {
+                                    let label = format!(
+                                        "{} @ 0x{offset:X}",
+                                        format_tag_entry(tag, Some(&e))
+                                    );
+                                    let tag_type =
+                                        TagType::from_type_subtype(e.file_type, e.file_subtype);
+                                    if ui
+                                        .add(egui::SelectableLabel::new(
+                                            false,
+                                            RichText::new(label).color(tag_type.display_color()),
+                                        ))
+                                        .tag_context(tag)
+                                        .clicked()
+                                    {
+                                        return Some(ViewAction::OpenTagAtOffset(tag, offset));
+                                    }
+                                }
+                            }
+
+                            None
+                        })
+                        .inner
+                })
+                .inner
+        } else {
+            None
+        };
+
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Filter:");
+                if ui.text_edit_singleline(&mut self.filter).changed() {
+                    self.rebuild_tree();
+                }
+                ui.weak(format!("{} of {} paths", self.root.count, self.paths.len()));
+            });
+            ui.separator();
+
+            if self.paths.is_empty() {
+                ui.label(RichText::new("No path-like strings were found in the cache").italics());
+                return;
+            }
+
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
+                    Self::show_node(
+                        ui,
+                        &self.root,
+                        egui::Id::new("path_tree"),
+                        &self.paths,
+                        &mut self.selected,
+                    );
+                });
+        });
+
+        result
+    }
+}
@@ -0,0 +1,222 @@
+//! Searches every entry in the packages for a byte pattern or value, see [`quicktag_scanner::pattern_search`]
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use eframe::egui::{self, RichText};
+use egui_extras::{Column, TableBuilder};
+use poll_promise::Promise;
+use quicktag_core::classes::get_class_by_id;
+use quicktag_scanner::pattern_search::{
+    search_packages, BytePattern, PatternKind, PatternSearchProgress, PatternSearchResult,
+    MAX_MATCHING_TAGS,
+};
+use tiger_pkg::{package_manager, Endian};
+
+use super::{common::ResponseExt, tag::format_tag_entry, View, ViewAction};
+use crate::crash_context;
+
+pub struct PatternSearchView {
+    kind: PatternKind,
+    query: String,
+    /// Search for numbers in the other byte order than the game's
+    swap_endian: bool,
+    aligned: bool,
+    /// Glob pattern for the package names, empty searches every package
+    package_filter: String,
+    /// Only search the entries that are scanned for the tag cache
+    tags_only: bool,
+    progress: Arc<PatternSearchProgress>,
+    result: Option<Promise<PatternSearchResult>>,
+}
+
+impl PatternSearchView {
+    pub fn new() -> Self {
+        Self {
+            kind: PatternKind::Hex,
+            query: String::new(),
+            swap_endian: false,
+            aligned: true,
+            package_filter: String::new(),
+            tags_only: false,
+            progress: Default::default(),
+            result: None,
+        }
+    }
+
+    fn pattern(&self) -> Result<BytePattern, String> {
+        let endian = match (package_manager().version.endian(), self.swap_endian) {
+            (endian, false) => endian,
+            (Endian::Little, true) => Endian::Big,
+            (Endian::Big, true) => Endian::Little,
+        };
+        BytePattern::parse(self.kind, &self.query, endian, self.aligned)
+    }
+
+    fn start(&mut self, pattern: BytePattern) {
+        self.progress = Default::default();
+        let progress = self.progress.clone();
+        let package_filter = Some(self.package_filter.trim().to_string()).filter(|f| !f.is_empty());
+        let tags_only = self.tags_only;
+        self.result = Some(Promise::spawn_thread("pattern_search", move || {
+            let _context = crash_context::push(crash_context::JOB, "Searching tags for a pattern");
+            search_packages(&pattern, package_filter.as_deref(), tags_only, &progress)
+        }));
+    }
+}
+
+impl View for PatternSearchView {
+    fn view(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let is_running = self.result.as_ref().is_some_and(|p| p.ready().is_none());
+        let pattern = self.pattern();
+
+        let mut submitted = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("pattern_search_kind")
+                .selected_text(self.kind.label())
+                .show_ui(ui, |ui| {
+                    for kind in PatternKind::ALL {
+                        ui.selectable_value(&mut self.kind, kind, kind.label());
+                    }
+                });
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text(self.kind.hint())
+                    .desired_width(260.0),
+            );
+            submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            if self.kind.is_number() {
+                ui.checkbox(&mut self.aligned, "Aligned")
+                    .on_hover_text("Only match values at offsets that are a multiple of 4");
+                ui.checkbox(&mut self.swap_endian, "Swap byte order")
+                    .on_hover_text("Search for the value in the other byte order than the game's");
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Packages");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.package_filter)
+                    .hint_text("eg. w64_sr_gear_*, every package if empty")
+                    .desired_width(200.0),
+            );
+            ui.checkbox(&mut self.tags_only, "Only tags").on_hover_text(
+                "Only search the entries that are scanned for the tag cache, skipping textures, audio and other data",
+            );
+
+            let search = ui
+                .add_enabled(!is_running && pattern.is_ok(), egui::Button::new("Search"))
+                .on_hover_text("Reads every entry in the packages, so this can take a while");
+            if (search.clicked() || submitted) && !is_running {
+                if let Ok(pattern) = &pattern {
+                    self.start(pattern.clone());
+                }
+            }
+
+            if is_running && ui.button("Cancel").clicked() {
+                self.progress.cancel.store(true, Ordering::Relaxed);
+            }
+
+            if let Err(e) = &pattern {
+                if !self.query.is_empty() {
+                    ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+                }
+            }
+        });
+        ui.separator();
+
+        let Some(result) = self.result.as_ref() else {
+            ui.label(RichText::new("No pattern searched yet").italics());
+            return None;
+        };
+
+        let Some(result) = result.ready() else {
+            let current = self.progress.current.load(Ordering::Relaxed);
+            let total = self.progress.total.load(Ordering::Relaxed).max(1);
+            let matches = self.progress.matches.load(Ordering::Relaxed);
+            ui.add(
+                egui::ProgressBar::new(current as f32 / total as f32)
+                    .animate(true)
+                    .text(format!(
+                        "Searching packages {current}/{total}, {matches} tags found"
+                    )),
+            );
+            ctx.request_repaint();
+            return None;
+        };
+
+        ui.label(format!(
+            "Found in {} out of {} tags{}",
+            result.matches.len(),
+            result.tags_searched,
+            if result.cancelled {
+                " (cancelled, results are incomplete)"
+            } else if result.truncated {
+                " (stopped after the first matches, narrow the search down for the rest)"
+            } else {
+                ""
+            }
+        ))
+        .on_hover_text(format!("Searching stops after {MAX_MATCHING_TAGS} tags"));
+
+        let mut action = None;
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto().at_least(192.0))
+            .column(Column::auto().at_least(160.0))
+            .column(Column::auto().at_least(64.0))
+            .column(Column::remainder().at_least(192.0))
+            .min_scrolled_height(0.0)
+            .header(20.0, |mut header| {
+                for title in ["Tag", "Class", "Matches", "Offsets"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, result.matches.len(), |mut row| {
+                    let m = &result.matches[row.index()];
+                    row.col(|ui| {
+                        let entry = package_manager().get_entry(m.tag);
+                        if ui
+                            .selectable_label(false, format_tag_entry(m.tag, entry.as_ref()))
+                            .tag_context(m.tag)
+                            .clicked()
+                        {
+                            action = Some(ViewAction::OpenTagAtOffset(m.tag, m.offsets[0]));
+                        }
+                    });
+                    row.col(|ui| match get_class_by_id(m.class) {
+                        Some(class) => {
+                            ui.label(class.name.to_string());
+                        }
+                        None => {
+                            ui.monospace(format!("{:08X}", m.class));
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(m.count.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.horizontal(|ui| {
+                            for &offset in &m.offsets {
+                                if ui
+                                    .link(RichText::new(format!("0x{offset:X}")).monospace())
+                                    .on_hover_text("Open the tag at this offset")
+                                    .clicked()
+                                {
+                                    action = Some(ViewAction::OpenTagAtOffset(m.tag, offset));
+                                }
+                            }
+                            if m.count > m.offsets.len() {
+                                ui.weak(format!("+{} more", m.count - m.offsets.len()));
+                            }
+                        });
+                    });
+                });
+            });
+
+        action
+    }
+}
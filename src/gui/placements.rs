@@ -0,0 +1,458 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use eframe::egui::{self, pos2, vec2, Color32, RichText, Sense};
+use egui_extras::{Column, TableBuilder};
+use itertools::Itertools;
+use quicktag_core::{
+    classes::get_class_by_id,
+    util::{u16_from_endian, u32_from_endian},
+};
+use quicktag_scanner::{array_data_layout, find_arrays, ScannedArray};
+use tiger_pkg::{package_manager, Endian, TagHash};
+
+use super::{common::ResponseExt, tag::format_tag_entry, TOASTS};
+
+/// Rotation quaternion, translation and scale
+const MIN_TRANSFORM_SIZE: usize = 28;
+/// Positions beyond this are not considered world coordinates
+const MAX_COORDINATE: f32 = 100_000.0;
+
+pub struct Placement {
+    pub index: usize,
+    pub rotation: [f32; 4],
+    pub translation: [f32; 3],
+    pub scale: [f32; 3],
+    pub model: Option<TagHash>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SortColumn {
+    Index,
+    X,
+    Y,
+    Z,
+    Scale,
+    Model,
+}
+
+fn read_f32(data: &[u8], offset: usize, endian: Endian) -> f32 {
+    f32::from_bits(u32_from_endian(
+        endian,
+        data[offset..offset + 4].try_into().unwrap(),
+    ))
+}
+
+fn read_u16(data: &[u8], offset: usize, endian: Endian) -> u16 {
+    u16_from_endian(endian, data[offset..offset + 2].try_into().unwrap())
+}
+
+fn is_transform_array(
+    data: &[u8],
+    array: &ScannedArray,
+    start: usize,
+    stride: usize,
+    endian: Endian,
+) -> bool {
+    if stride < MIN_TRANSFORM_SIZE {
+        return false;
+    }
+
+    if get_class_by_id(array.class).is_some_and(|c| c.name.contains("transform")) {
+        return true;
+    }
+
+    // Unnamed classes need (nearly) all of their elements to start with a unit quaternion followed by a position
+    let valid = (0..array.count)
+        .filter(|i| {
+            let offset = start + i * stride;
+            let q: [f32; 4] = std::array::from_fn(|c| read_f32(data, offset + c * 4, endian));
+            let length = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+            let position_valid = (0..3).all(|c| {
+                let v = read_f32(data, offset + 16 + c * 4, endian);
+                v.is_finite() && v.abs() < MAX_COORDINATE
+            });
+
+            (length - 1.0).abs() < 0.01 && position_valid
+        })
+        .count();
+
+    valid * 10 >= array.count * 9
+}
+
+/// Tags referenced through an array of 32-bit tag hashes, in array order
+fn find_models(data: &[u8], arrays: &[ScannedArray], endian: Endian) -> Vec<TagHash> {
+    for i in 0..arrays.len() {
+        let Some((start, count, stride)) = array_data_layout(data, arrays, i) else {
+            continue;
+        };
+        if stride != 4 {
+            continue;
+        }
+
+        let tags = (0..count)
+            .map(|t| {
+                TagHash(u32_from_endian(
+                    endian,
+                    data[start + t * 4..start + t * 4 + 4].try_into().unwrap(),
+                ))
+            })
+            .collect_vec();
+        if tags
+            .iter()
+            .all(|&t| package_manager().get_entry(t).is_some())
+        {
+            return tags;
+        }
+    }
+
+    vec![]
+}
+
+/// Looks for a (instance count, first instance, model index) u16 table that covers every transform exactly once,
+/// and returns the model of each transform
+fn find_model_mapping(
+    data: &[u8],
+    arrays: &[ScannedArray],
+    endian: Endian,
+    transform_count: usize,
+    models: &[TagHash],
+) -> Option<Vec<Option<TagHash>>> {
+    if models.is_empty() {
+        return None;
+    }
+
+    for i in 0..arrays.len() {
+        let Some((start, count, stride)) = array_data_layout(data, arrays, i) else {
+            continue;
+        };
+        if stride < 6 {
+            continue;
+        }
+
+        let groups = (0..count)
+            .map(|g| {
+                let offset = start + g * stride;
+                (
+                    read_u16(data, offset, endian) as usize,
+                    read_u16(data, offset + 2, endian) as usize,
+                    read_u16(data, offset + 4, endian) as usize,
+                )
+            })
+            .collect_vec();
+
+        let covers_all = groups.iter().map(|g| g.0).sum::<usize>() == transform_count
+            && groups
+                .iter()
+                .all(|&(n, first, model)| first + n <= transform_count && model < models.len());
+        if !covers_all {
+            continue;
+        }
+
+        let mut mapping = vec![None; transform_count];
+        for (n, first, model) in groups {
+            for m in &mut mapping[first..first + n] {
+                *m = Some(models[model]);
+            }
+        }
+        return Some(mapping);
+    }
+
+    None
+}
+
+/// Sortable table of the transforms in a map placement tag, with an optional top-down plot
+///
+/// Transform arrays are recognized by their class name or by their elements starting with a unit quaternion.
+/// Models are only resolved if the tag also contains a tag hash array and an instance group table
+pub struct PlacementTable {
+    tag: TagHash,
+    array_offset: u64,
+    stride: usize,
+    placements: Vec<Placement>,
+    sort: SortColumn,
+    sort_descending: bool,
+    show_plot: bool,
+}
+
+impl PlacementTable {
+    pub fn new(tag: TagHash, data: &[u8]) -> Option<Self> {
+        let endian = package_manager().version.endian();
+        let arrays = find_arrays(endian, data);
+
+        let (array_index, start, count, stride) = (0..arrays.len())
+            .filter_map(|i| {
+                let (start, count, stride) = array_data_layout(data, &arrays, i)?;
+                is_transform_array(data, &arrays[i], start, stride, endian)
+                    .then_some((i, start, count, stride))
+            })
+            .max_by_key(|(_, _, count, _)| *count)?;
+
+        let models = find_models(data, &arrays, endian);
+        let mapping = find_model_mapping(data, &arrays, endian, count, &models)
+            .unwrap_or_else(|| vec![None; count]);
+
+        let placements = (0..count)
+            .zip(mapping)
+            .map(|(index, model)| {
+                let offset = start + index * stride;
+                let read = |o: usize| read_f32(data, offset + o, endian);
+                let scale = if stride >= 40 {
+                    [read(28), read(32), read(36)]
+                } else if stride >= 32 {
+                    [read(28); 3]
+                } else {
+                    [1.0; 3]
+                };
+
+                Placement {
+                    index,
+                    rotation: [read(0), read(4), read(8), read(12)],
+                    translation: [read(16), read(20), read(24)],
+                    scale,
+                    model,
+                }
+            })
+            .collect();
+
+        Some(Self {
+            tag,
+            array_offset: arrays[array_index].offset,
+            stride,
+            placements,
+            sort: SortColumn::Index,
+            sort_descending: false,
+            show_plot: false,
+        })
+    }
+
+    fn apply_sorting(&mut self) {
+        let key = |p: &Placement| -> f32 {
+            match self.sort {
+                SortColumn::Index => p.index as f32,
+                SortColumn::X => p.translation[0],
+                SortColumn::Y => p.translation[1],
+                SortColumn::Z => p.translation[2],
+                SortColumn::Scale => p.scale[0],
+                SortColumn::Model => p.model.map(|t| t.0 as f32).unwrap_or(f32::MAX),
+            }
+        };
+
+        self.placements.sort_by(|a, b| key(a).total_cmp(&key(b)));
+        if self.sort_descending {
+            self.placements.reverse();
+        }
+    }
+
+    /// Writes the placements to `placements/{tag}.csv`, in the current sort order
+    fn export_csv(&self) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all("placements")?;
+        let path = PathBuf::from("placements").join(format!("{}.csv", self.tag));
+        let mut f = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        writeln!(f, "index,x,y,z,qx,qy,qz,qw,sx,sy,sz,model")?;
+        for p in &self.placements {
+            let [x, y, z] = p.translation;
+            let [qx, qy, qz, qw] = p.rotation;
+            let [sx, sy, sz] = p.scale;
+            writeln!(
+                f,
+                "{},{x},{y},{z},{qx},{qy},{qz},{qw},{sx},{sy},{sz},{}",
+                p.index,
+                p.model.map(|t| t.to_string()).unwrap_or_default()
+            )?;
+        }
+
+        Ok(path)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<TagHash> {
+        let mut open_tag = None;
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} placements (array at 0x{:X}, stride 0x{:X})",
+                self.placements.len(),
+                self.array_offset,
+                self.stride
+            ));
+            ui.checkbox(&mut self.show_plot, "Top-down plot");
+            if ui.button("Export CSV").clicked() {
+                match self.export_csv() {
+                    Ok(path) => {
+                        TOASTS
+                            .lock()
+                            .success(format!("Exported placements to {}", path.display()));
+                    }
+                    Err(e) => {
+                        log::error!("Failed to export placements: {e:?}");
+                        TOASTS
+                            .lock()
+                            .error(format!("Failed to export placements: {e}"));
+                    }
+                }
+            }
+        });
+
+        if self.show_plot {
+            self.plot_ui(ui);
+        }
+
+        let mut sort_changed = false;
+        let text_height = egui::TextStyle::Body
+            .resolve(ui.style())
+            .size
+            .max(ui.spacing().interact_size.y);
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto().at_least(48.0))
+            .columns(Column::auto().at_least(96.0), 3)
+            .column(Column::auto().at_least(224.0))
+            .column(Column::auto().at_least(160.0))
+            .column(Column::remainder())
+            .min_scrolled_height(0.0)
+            .header(20.0, |mut header| {
+                for (column, label) in [
+                    (Some(SortColumn::Index), "#"),
+                    (Some(SortColumn::X), "X"),
+                    (Some(SortColumn::Y), "Y"),
+                    (Some(SortColumn::Z), "Z"),
+                    (None, "Rotation"),
+                    (Some(SortColumn::Scale), "Scale"),
+                    (Some(SortColumn::Model), "Model"),
+                ] {
+                    header.col(|ui| {
+                        let Some(column) = column else {
+                            ui.strong(label);
+                            return;
+                        };
+
+                        let arrow = match (self.sort == column, self.sort_descending) {
+                            (false, _) => "",
+                            (true, false) => " ⏶",
+                            (true, true) => " ⏷",
+                        };
+                        if ui
+                            .selectable_label(
+                                self.sort == column,
+                                RichText::new(format!("{label}{arrow}")).strong(),
+                            )
+                            .clicked()
+                        {
+                            if self.sort == column {
+                                self.sort_descending = !self.sort_descending;
+                            } else {
+                                self.sort = column;
+                                self.sort_descending = false;
+                            }
+                            sort_changed = true;
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(text_height, self.placements.len(), |mut row| {
+                    let p = &self.placements[row.index()];
+                    row.col(|ui| {
+                        ui.label(p.index.to_string());
+                    });
+                    for v in p.translation {
+                        row.col(|ui| {
+                            ui.monospace(format!("{v:.3}"));
+                        });
+                    }
+                    row.col(|ui| {
+                        let [x, y, z, w] = p.rotation;
+                        ui.monospace(format!("{x:.3} {y:.3} {z:.3} {w:.3}"));
+                    });
+                    row.col(|ui| {
+                        let [x, y, z] = p.scale;
+                        if x == y && y == z {
+                            ui.monospace(format!("{x:.3}"));
+                        } else {
+                            ui.monospace(format!("{x:.3} {y:.3} {z:.3}"));
+                        }
+                    });
+                    row.col(|ui| {
+                        if let Some(model) = p.model {
+                            let entry = package_manager().get_entry(model);
+                            if ui
+                                .selectable_label(false, format_tag_entry(model, entry.as_ref()))
+                                .tag_context(model)
+                                .clicked()
+                            {
+                                open_tag = Some(model);
+                            }
+                        } else {
+                            ui.weak("Unknown");
+                        }
+                    });
+                });
+            });
+
+        if sort_changed {
+            self.apply_sorting();
+        }
+
+        open_tag
+    }
+
+    /// X/Y scatter plot, Destiny is Z-up
+    fn plot_ui(&self, ui: &mut egui::Ui) {
+        let (response, painter) =
+            ui.allocate_painter(vec2(ui.available_width(), 320.0), Sense::hover());
+        let rect = response.rect.shrink(8.0);
+        painter.rect_filled(response.rect, 0.0, ui.visuals().extreme_bg_color);
+        if self.placements.is_empty() {
+            return;
+        }
+
+        let (min_x, max_x) = self
+            .placements
+            .iter()
+            .map(|p| p.translation[0])
+            .minmax()
+            .into_option()
+            .unwrap_or_default();
+        let (min_y, max_y) = self
+            .placements
+            .iter()
+            .map(|p| p.translation[1])
+            .minmax()
+            .into_option()
+            .unwrap_or_default();
+
+        // Keep the aspect ratio, so distances look the same in both directions
+        let extent = (max_x - min_x).max(max_y - min_y).max(f32::EPSILON);
+        let scale = rect.width().min(rect.height()) / extent;
+        let to_screen = |p: &Placement| {
+            pos2(
+                rect.center().x + (p.translation[0] - (min_x + max_x) / 2.0) * scale,
+                rect.center().y - (p.translation[1] - (min_y + max_y) / 2.0) * scale,
+            )
+        };
+
+        for p in &self.placements {
+            painter.circle_filled(to_screen(p), 1.5, Color32::LIGHT_BLUE);
+        }
+
+        if let Some(hover) = response.hover_pos() {
+            let nearest = self
+                .placements
+                .iter()
+                .map(|p| (p, to_screen(p).distance(hover)))
+                .filter(|(_, d)| *d < 8.0)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            if let Some((p, _)) = nearest {
+                painter.circle_stroke(to_screen(p), 4.0, (1.0, Color32::YELLOW));
+                response.on_hover_text(format!(
+                    "#{} ({:.2}, {:.2}, {:.2}){}",
+                    p.index,
+                    p.translation[0],
+                    p.translation[1],
+                    p.translation[2],
+                    p.model.map(|m| format!("\n{m}")).unwrap_or_default()
+                ));
+            }
+        }
+    }
+}
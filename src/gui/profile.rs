@@ -0,0 +1,51 @@
+//! Tells quicktag windows apart when more than one is open, eg. to compare the packages of two game versions
+//!
+//! The window title contains the profile name and the game version, and a bar in the accent color is drawn along the
+//! top of the window. The accent color is picked from the profile name (or the game version without one), so every
+//! profile keeps the same color between runs unless one is set in the config.
+
+use std::hash::{Hash, Hasher};
+
+use eframe::egui::{self, ecolor::Hsva, Color32};
+use rustc_hash::FxHasher;
+use tiger_pkg::{package_manager, Version};
+
+use crate::config;
+
+const ACCENT_BAR_HEIGHT: f32 = 3.0;
+
+pub fn window_title() -> String {
+    let version = package_manager().version;
+    match config::with(|c| c.general.profile.clone()) {
+        Some(profile) => format!("Quicktag - {profile} ({})", version.name()),
+        None => format!("Quicktag - {}", version.name()),
+    }
+}
+
+pub fn accent_color() -> Color32 {
+    let (profile, accent) = config::with(|c| (c.general.profile.clone(), c.general.accent_color));
+    if let Some([r, g, b]) = accent {
+        return Color32::from_rgb(r, g, b);
+    }
+
+    let mut hasher = FxHasher::default();
+    profile
+        .unwrap_or_else(|| package_manager().version.name().to_string())
+        .hash(&mut hasher);
+    let hue = (hasher.finish() % 360) as f32 / 360.0;
+    Hsva::new(hue, 0.6, 0.85, 1.0).into()
+}
+
+/// Draws the accent bar along the top of the window. Has to be shown before the central panel
+pub fn accent_bar(ctx: &egui::Context) {
+    egui::TopBottomPanel::top("profile_accent_bar")
+        .exact_height(ACCENT_BAR_HEIGHT)
+        .show_separator_line(false)
+        .frame(egui::Frame::none().fill(accent_color()))
+        .show(ctx, |_| {});
+}
+
+/// Updates the title of the window after the profile has changed
+pub fn update_window_title(ctx: &egui::Context) {
+    ctx.send_viewport_cmd(egui::ViewportCommand::Title(window_title()));
+}
@@ -4,40 +4,54 @@ use eframe::egui::{self, RichText};
 use itertools::Itertools;
 use quicktag_core::{tagtypes::TagType, util::fnv1};
 use quicktag_scanner::TagCache;
+use quicktag_strings::classify::RawStringKind;
 use rustc_hash::FxHashMap;
 use tiger_pkg::{package_manager, TagHash};
 
 use super::{common::ResponseExt, tag::format_tag_entry, View, ViewAction};
 
 pub struct RawStringsView {
-    strings: Vec<(String, Vec<TagHash>, u32)>,
-    strings_vec_filtered: Vec<(usize, String, Vec<TagHash>, u32)>,
+    /// String, the tags and offsets it was found at, and its FNV1 hash
+    strings: Vec<(String, Vec<(TagHash, u64)>, u32)>,
+    /// Kind of every string in `strings`
+    kinds: Vec<RawStringKind>,
+    /// Amount of strings of every kind
+    kind_counts: FxHashMap<RawStringKind, usize>,
+    strings_vec_filtered: Vec<(usize, String, Vec<(TagHash, u64)>, u32)>,
 
     string_filter: String,
+    kind_filter: Option<RawStringKind>,
     selected_stringset: usize,
 }
 
 impl RawStringsView {
     pub fn new(cache: Arc<TagCache>) -> Self {
-        let mut strings: FxHashMap<String, (Vec<TagHash>, u32)> = Default::default();
+        let mut strings: FxHashMap<String, (Vec<(TagHash, u64)>, u32)> = Default::default();
 
         for (t, s) in cache
             .hashes
             .iter()
-            .flat_map(|(t, sc)| sc.raw_strings.iter().map(|s| (*t, s.clone())))
+            .flat_map(|(t, sc)| sc.raw_strings.iter().map(|s| (*t, s)))
         {
-            let h = fnv1(s.as_bytes());
-            match strings.entry(s) {
-                std::collections::hash_map::Entry::Occupied(mut o) => o.get_mut().0.push(t),
+            match strings.entry(s.string.clone()) {
+                std::collections::hash_map::Entry::Occupied(mut o) => {
+                    o.get_mut().0.push((t, s.offset))
+                }
                 std::collections::hash_map::Entry::Vacant(v) => {
-                    v.insert((vec![t], h));
+                    v.insert((vec![(t, s.offset)], fnv1(s.string.as_bytes())));
                 }
             };
         }
 
         let strings = strings.into_iter().collect_vec();
+        let kinds = strings
+            .iter()
+            .map(|(s, _)| RawStringKind::classify(s))
+            .collect_vec();
 
         Self {
+            kind_counts: kinds.iter().copied().counts().into_iter().collect(),
+            kinds,
             strings_vec_filtered: strings
                 .iter()
                 .enumerate()
@@ -48,9 +62,24 @@ impl RawStringsView {
                 .map(|(v0, (v1, v2))| (v0, v1, v2))
                 .collect(),
             string_filter: String::new(),
+            kind_filter: None,
             selected_stringset: usize::MAX,
         }
     }
+
+    fn update_filter(&mut self) {
+        let filter = self.string_filter.to_lowercase();
+        self.strings_vec_filtered = self
+            .strings
+            .iter()
+            .enumerate()
+            .filter(|(i, (s, _, _))| {
+                self.kind_filter.is_none_or(|kind| self.kinds[*i] == kind)
+                    && (filter.is_empty() || s.to_lowercase().contains(&filter))
+            })
+            .map(|(i, (k, v, h))| (i, k.clone(), v.clone(), *h))
+            .collect();
+    }
 }
 
 impl View for RawStringsView {
@@ -63,24 +92,39 @@ impl View for RawStringsView {
             ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
             ui.horizontal(|ui| {
                 ui.label("Search:");
-                if ui.text_edit_singleline(&mut self.string_filter).changed() {
-                    self.strings_vec_filtered = if !self.string_filter.is_empty() {
-                        self.strings
-                            .iter()
-                            .enumerate()
-                            .filter(|(_, (s, _, _))| {
-                                s.to_lowercase()
-                                    .contains(&self.string_filter.to_lowercase())
-                            })
-                            .map(|(i, (k, v, h))| (i, k.clone(), v.clone(), *h))
-                            .collect()
-                    } else {
-                        self.strings
-                            .iter()
-                            .enumerate()
-                            .map(|(i, (k, v, h))| (i, k.clone(), v.clone(), *h))
-                            .collect_vec()
-                    };
+                let mut changed = ui.text_edit_singleline(&mut self.string_filter).changed();
+
+                let kind_label = |kind: Option<RawStringKind>| match kind {
+                    Some(kind) => format!(
+                        "{} ({})",
+                        kind.label(),
+                        self.kind_counts.get(&kind).copied().unwrap_or_default()
+                    ),
+                    None => format!("All kinds ({})", self.strings.len()),
+                };
+                egui::ComboBox::from_id_source("raw_strings_kind")
+                    .selected_text(kind_label(self.kind_filter))
+                    .show_ui(ui, |ui| {
+                        changed |= ui
+                            .selectable_value(&mut self.kind_filter, None, kind_label(None))
+                            .changed();
+                        for kind in RawStringKind::ALL {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.kind_filter,
+                                    Some(kind),
+                                    kind_label(Some(kind)),
+                                )
+                                .changed();
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Kind of string, guessed from what it looks like (eg. slashes for paths, play_ for events)",
+                    );
+
+                if changed {
+                    self.update_filter();
                 }
             });
 
@@ -97,7 +141,9 @@ impl View for RawStringsView {
                         writeln!(
                             &mut f,
                             "'{string}' - [{}] (fnv1=0x{hash:08X})",
-                            tags.iter().join(", ")
+                            tags.iter()
+                                .map(|(tag, offset)| format!("{tag}@0x{offset:X}"))
+                                .join(", ")
                         )
                         .ok();
                     }
@@ -124,6 +170,8 @@ impl View for RawStringsView {
                                     }
                                 ),
                             );
+                            let response =
+                                response.on_hover_text(self.kinds[*i].label().trim_end_matches('s'));
 
                             if response.clicked() {
                                 self.selected_stringset = *i;
@@ -147,9 +195,12 @@ impl View for RawStringsView {
                         .max_width(f32::INFINITY)
                         .show(ui, |ui| {
                             ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
-                            for tag in &self.strings[self.selected_stringset].1 {
-                                if let Some(e) = package_manager().get_entry(*tag) {
-                                    let label = format_tag_entry(*tag, Some(&e));
+                            for &(tag, offset) in &self.strings[self.selected_stringset].1 {
+                                if let Some(e) = package_manager().get_entry(tag) {
+                                    let label = format!(
+                                        "{} @ 0x{offset:X}",
+                                        format_tag_entry(tag, Some(&e))
+                                    );
                                     let tag_type =
                                         TagType::from_type_subtype(e.file_type, e.file_subtype);
                                     if ui
@@ -157,10 +208,10 @@ impl View for RawStringsView {
                                             false,
                                             RichText::new(label).color(tag_type.display_color()),
                                         ))
-                                        .tag_context(*tag)
+                                        .tag_context(tag)
                                         .clicked()
                                     {
-                                        return Some(ViewAction::OpenTag(*tag));
+                                        return Some(ViewAction::OpenTagAtOffset(tag, offset));
                                     }
                                 }
                             }
@@ -0,0 +1,142 @@
+//! Checksums of a region of a tag, for matching blobs against other tools and checking exports
+
+use std::ops::Range;
+
+use eframe::egui::{self, RichText};
+use md5::{Digest, Md5};
+use quicktag_core::util::{crc32, fnv1};
+use sha1::Sha1;
+use tiger_pkg::TagHash;
+
+use super::TOASTS;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// (algorithm, hash) for every supported algorithm
+fn region_hashes(data: &[u8]) -> Vec<(&'static str, String)> {
+    vec![
+        ("FNV1", format!("{:08X}", fnv1(data))),
+        ("CRC32", format!("{:08X}", crc32(data))),
+        ("MD5", hex(&Md5::digest(data))),
+        ("SHA-1", hex(&Sha1::digest(data))),
+        (
+            "XXH64",
+            format!("{:016x}", xxhash_rust::xxh64::xxh64(data, 0)),
+        ),
+        (
+            "XXH3-64",
+            format!("{:016x}", xxhash_rust::xxh3::xxh3_64(data)),
+        ),
+    ]
+}
+
+pub struct RegionHashWindow {
+    pub open: bool,
+    tag: TagHash,
+    start_input: String,
+    end_input: String,
+    /// Range the hashes were computed for
+    range: Range<usize>,
+    hashes: Vec<(&'static str, String)>,
+}
+
+impl RegionHashWindow {
+    pub fn new(tag: TagHash, data: &[u8], range: Range<usize>) -> Self {
+        let mut window = Self {
+            open: true,
+            tag,
+            start_input: format!("{:X}", range.start),
+            end_input: format!("{:X}", range.end),
+            range: 0..0,
+            hashes: vec![],
+        };
+        window.compute(data, range);
+        window
+    }
+
+    fn compute(&mut self, data: &[u8], range: Range<usize>) {
+        let range = range.start.min(data.len())..range.end.min(data.len());
+        self.hashes = region_hashes(&data[range.clone()]);
+        self.range = range;
+    }
+
+    fn parsed_range(&self) -> Option<Range<usize>> {
+        let parse = |s: &str| usize::from_str_radix(s.trim().trim_start_matches("0x"), 16).ok();
+        let (start, end) = (parse(&self.start_input)?, parse(&self.end_input)?);
+        (start < end).then_some(start..end)
+    }
+
+    /// `data` is the tag data, without the padding added by the hex view
+    pub fn show(&mut self, ctx: &egui::Context, data: &[u8]) {
+        let mut open = self.open;
+        egui::Window::new(format!("Region hashes of {}", self.tag))
+            .id(egui::Id::new("region_hashes"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("From 0x");
+                    ui.add(egui::TextEdit::singleline(&mut self.start_input).desired_width(64.0));
+                    ui.label("to 0x");
+                    ui.add(egui::TextEdit::singleline(&mut self.end_input).desired_width(64.0));
+
+                    let range = self.parsed_range();
+                    if ui
+                        .add_enabled(
+                            range.as_ref().is_some_and(|r| *r != self.range),
+                            egui::Button::new("Compute"),
+                        )
+                        .on_disabled_hover_text("Enter a range in hex, the end is exclusive")
+                        .clicked()
+                    {
+                        if let Some(range) = range {
+                            self.compute(data, range);
+                        }
+                    }
+                });
+                ui.weak(format!(
+                    "0x{:X}..0x{:X} ({} bytes)",
+                    self.range.start,
+                    self.range.end,
+                    self.range.len()
+                ));
+                ui.separator();
+
+                egui::Grid::new("region_hashes_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (algorithm, hash) in &self.hashes {
+                            ui.strong(*algorithm);
+                            ui.label(RichText::new(hash).monospace());
+                            if ui.small_button("Copy").clicked() {
+                                ui.output_mut(|o| o.copied_text = hash.clone());
+                                TOASTS
+                                    .lock()
+                                    .success(format!("{algorithm} copied to clipboard"));
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                if ui.button("Copy all").clicked() {
+                    let text = self
+                        .hashes
+                        .iter()
+                        .map(|(algorithm, hash)| format!("{algorithm}: {hash}"))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    ui.output_mut(|o| {
+                        o.copied_text = format!(
+                            "{} 0x{:X}..0x{:X}\n{text}",
+                            self.tag, self.range.start, self.range.end
+                        )
+                    });
+                    TOASTS.lock().success("Hashes copied to clipboard");
+                }
+            });
+        self.open = open;
+    }
+}
@@ -0,0 +1,512 @@
+use std::path::PathBuf;
+
+use eframe::egui::{self, Color32, RichText};
+use itertools::Itertools;
+use quicktag_core::{
+    classes::get_class_by_id,
+    tagtypes::TagType,
+    util::{u16_from_endian, u32_from_endian},
+};
+use quicktag_scanner::{array_data_layout, find_arrays, ScannedArray, TagCache};
+use quicktag_strings::localized::RawStringHashCache;
+use serde_json::json;
+use tiger_pkg::{package_manager, Endian, TagHash};
+
+use super::{common::ResponseExt, tag::format_tag_entry, TOASTS};
+
+/// Hierarchies with more bones than this are ignored, they're most likely index buffers in disguise
+const MAX_BONES: usize = 4096;
+/// Only floats within the first bytes of a tag (before any arrays) are considered when guessing durations
+const HEADER_SCAN_SIZE: usize = 0x80;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RigKind {
+    Skeleton,
+    Animation,
+}
+
+impl RigKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RigKind::Skeleton => "Skeleton",
+            RigKind::Animation => "Animation",
+        }
+    }
+
+    /// Guesses the kind from the class name, which only works for classes named by the built-in list or a schema file
+    pub fn from_class(class: u32) -> Option<Self> {
+        let name = get_class_by_id(class)?.name.to_lowercase();
+        if name.contains("skeleton") || name.contains("bone") {
+            Some(RigKind::Skeleton)
+        } else if name.contains("animation") || name.contains("anim_") {
+            Some(RigKind::Animation)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct Bone {
+    pub parent: Option<usize>,
+    pub name_hash: Option<u32>,
+    pub name: Option<String>,
+}
+
+/// Location of the parent indices in the tag data
+struct HierarchySource {
+    array_offset: u64,
+    field_offset: usize,
+    stride: usize,
+}
+
+fn read_i16(data: &[u8], offset: usize, endian: Endian) -> i16 {
+    u16_from_endian(endian, data[offset..offset + 2].try_into().unwrap()) as i16
+}
+
+/// Looks for a 16-bit field in an array that forms a valid parent index list: the first element is the root (-1)
+/// and every other element points to an earlier one
+fn find_hierarchy(
+    data: &[u8],
+    arrays: &[ScannedArray],
+    endian: Endian,
+) -> Option<(HierarchySource, Vec<Option<usize>>)> {
+    for i in 0..arrays.len() {
+        let Some((data_start, count, stride)) = array_data_layout(data, arrays, i) else {
+            continue;
+        };
+        if !(2..=MAX_BONES).contains(&count) || stride > 256 {
+            continue;
+        }
+
+        for field_offset in (0..stride.saturating_sub(1)).step_by(2) {
+            let parents = (0..count)
+                .map(|b| read_i16(data, data_start + b * stride + field_offset, endian))
+                .collect_vec();
+
+            let valid = parents[0] == -1
+                && parents
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .all(|(b, &p)| p >= -1 && (p as isize) < b as isize)
+                // A list full of roots is more likely padding than a hierarchy
+                && parents.iter().filter(|&&p| p >= 0).count() >= count / 2;
+
+            if valid {
+                return Some((
+                    HierarchySource {
+                        array_offset: arrays[i].offset,
+                        field_offset,
+                        stride,
+                    },
+                    parents
+                        .into_iter()
+                        .map(|p| (p >= 0).then_some(p as usize))
+                        .collect(),
+                ));
+            }
+        }
+    }
+
+    None
+}
+
+/// Finds a 32-bit field holding a known string hash for most elements of an array with `count` elements
+fn find_bone_names(
+    data: &[u8],
+    arrays: &[ScannedArray],
+    endian: Endian,
+    count: usize,
+    raw_strings: &RawStringHashCache,
+) -> Vec<Option<u32>> {
+    let mut best: Option<(usize, Vec<Option<u32>>)> = None;
+    for i in 0..arrays.len() {
+        let Some((data_start, array_count, stride)) = array_data_layout(data, arrays, i) else {
+            continue;
+        };
+        if array_count != count || stride < 4 {
+            continue;
+        }
+
+        for field_offset in (0..=stride - 4).step_by(4) {
+            let hashes = (0..count)
+                .map(|b| {
+                    let offset = data_start + b * stride + field_offset;
+                    let hash =
+                        u32_from_endian(endian, data[offset..offset + 4].try_into().unwrap());
+                    raw_strings.contains_key(&hash).then_some(hash)
+                })
+                .collect_vec();
+
+            let found = hashes.iter().flatten().count();
+            if found * 2 >= count && best.as_ref().is_none_or(|(n, _)| found > *n) {
+                best = Some((found, hashes));
+            }
+        }
+    }
+
+    best.map(|(_, hashes)| hashes)
+        .unwrap_or_else(|| vec![None; count])
+}
+
+/// First float in the tag header that looks like a duration in seconds
+fn guess_duration(data: &[u8], arrays: &[ScannedArray], endian: Endian) -> Option<(u64, f32)> {
+    let header_end = arrays
+        .first()
+        .map(|a| a.offset as usize - 4)
+        .unwrap_or(data.len())
+        .min(HEADER_SCAN_SIZE)
+        .min(data.len());
+
+    (0..header_end.saturating_sub(3))
+        .step_by(4)
+        .map(|offset| {
+            let bits = u32_from_endian(endian, data[offset..offset + 4].try_into().unwrap());
+            (offset as u64, f32::from_bits(bits))
+        })
+        .find(|(_, v)| v.is_normal() && (0.01..=3600.0).contains(v) && v.fract() != 0.0)
+}
+
+fn bone_count(tag: TagHash, endian: Endian) -> Option<usize> {
+    let data = package_manager().read_tag(tag).ok()?;
+    let arrays = find_arrays(endian, &data);
+    find_hierarchy(&data, &arrays, endian).map(|(_, parents)| parents.len())
+}
+
+/// Bone/track counts, durations and related rigs of a skeleton or animation tag
+///
+/// None of the skeleton or animation formats are mapped out, so everything except the array list is a guess.
+/// Skeletons are recognized by a parent index list, bone names by known (raw string/wordlist) hashes
+pub struct RigSummary {
+    tag: TagHash,
+    pub kind: RigKind,
+    class: Option<String>,
+    arrays: Vec<ScannedArray>,
+    hierarchy: Option<HierarchySource>,
+    bones: Vec<Bone>,
+    /// Arrays with a class named after tracks/curves
+    track_count: Option<usize>,
+    duration: Option<(u64, f32)>,
+    /// Skeletons referenced by this animation, with their bone count
+    rigs: Vec<(TagHash, Option<usize>)>,
+    /// Animations referencing this skeleton
+    animations: Vec<TagHash>,
+}
+
+impl RigSummary {
+    pub fn new(
+        cache: &TagCache,
+        raw_strings: &RawStringHashCache,
+        tag: TagHash,
+        data: &[u8],
+    ) -> Option<Self> {
+        let endian = package_manager().version.endian();
+        let entry = package_manager().get_entry(tag)?;
+        if !TagType::from_type_subtype(entry.file_type, entry.file_subtype).is_tag() {
+            return None;
+        }
+
+        let arrays = find_arrays(endian, data);
+        let hierarchy = find_hierarchy(data, &arrays, endian);
+        let kind = match RigKind::from_class(entry.reference) {
+            Some(kind) => kind,
+            None if hierarchy.is_some() => RigKind::Skeleton,
+            None => return None,
+        };
+
+        let (hierarchy, bones) = match hierarchy {
+            Some((source, parents)) => {
+                let names = find_bone_names(data, &arrays, endian, parents.len(), raw_strings);
+                let bones = parents
+                    .into_iter()
+                    .zip(names)
+                    .map(|(parent, name_hash)| Bone {
+                        parent,
+                        name_hash,
+                        name: name_hash
+                            .and_then(|h| raw_strings.get(&h))
+                            .and_then(|s| s.first())
+                            .map(|(s, _)| s.clone()),
+                    })
+                    .collect();
+                (Some(source), bones)
+            }
+            None => (None, vec![]),
+        };
+
+        let track_count = arrays
+            .iter()
+            .filter(|a| {
+                get_class_by_id(a.class).is_some_and(|c| {
+                    let name = c.name.to_lowercase();
+                    name.contains("track") || name.contains("curve")
+                })
+            })
+            .map(|a| a.count)
+            .reduce(|a, b| a + b);
+
+        let scan = cache.hashes.get(&tag);
+        let rigs = if kind == RigKind::Animation {
+            scan.map(|s| s.file_hashes.iter().map(|h| h.hash).unique().collect_vec())
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|&t| t != tag)
+                .filter_map(|t| {
+                    let entry = package_manager().get_entry(t)?;
+                    if !TagType::from_type_subtype(entry.file_type, entry.file_subtype).is_tag() {
+                        return None;
+                    }
+
+                    let bones = bone_count(t, endian);
+                    (RigKind::from_class(entry.reference) == Some(RigKind::Skeleton)
+                        || bones.is_some())
+                    .then_some((t, bones))
+                })
+                .collect_vec()
+        } else {
+            vec![]
+        };
+
+        let animations = scan
+            .map(|s| s.references.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|&t| {
+                package_manager()
+                    .get_entry(t)
+                    .is_some_and(|e| RigKind::from_class(e.reference) == Some(RigKind::Animation))
+            })
+            .unique()
+            .collect_vec();
+
+        Some(Self {
+            tag,
+            kind,
+            class: get_class_by_id(entry.reference).map(|c| c.name.to_string()),
+            duration: if kind == RigKind::Animation {
+                guess_duration(data, &arrays, endian)
+            } else {
+                None
+            },
+            arrays,
+            hierarchy,
+            bones,
+            track_count,
+            rigs,
+            animations,
+        })
+    }
+
+    fn bone_label(&self, index: usize) -> String {
+        let bone = &self.bones[index];
+        match (&bone.name, bone.name_hash) {
+            (Some(name), _) => format!("{index}: {name}"),
+            (None, Some(hash)) => format!("{index}: {hash:08X}"),
+            (None, None) => format!("Bone {index}"),
+        }
+    }
+
+    fn bone_json(&self, index: usize) -> serde_json::Value {
+        let bone = &self.bones[index];
+        json!({
+            "index": index,
+            "name": bone.name,
+            "name_hash": bone.name_hash.map(|h| format!("{h:08X}")),
+            "children": self.children(index).map(|c| self.bone_json(c)).collect_vec(),
+        })
+    }
+
+    fn children(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.bones
+            .iter()
+            .enumerate()
+            .filter(move |(_, b)| b.parent == Some(index))
+            .map(|(i, _)| i)
+    }
+
+    /// Writes the bone list and the nested hierarchy to `skeletons/{tag}.json`
+    fn export_json(&self) -> anyhow::Result<PathBuf> {
+        let roots = self
+            .bones
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.parent.is_none())
+            .map(|(i, _)| self.bone_json(i))
+            .collect_vec();
+
+        let value = json!({
+            "tag": self.tag.to_string(),
+            "class": self.class,
+            "bone_count": self.bones.len(),
+            "bones": self.bones.iter().enumerate().map(|(i, b)| json!({
+                "index": i,
+                "parent": b.parent,
+                "name": b.name,
+                "name_hash": b.name_hash.map(|h| format!("{h:08X}")),
+            })).collect_vec(),
+            "hierarchy": roots,
+        });
+
+        std::fs::create_dir_all("skeletons")?;
+        let path = PathBuf::from("skeletons").join(format!("{}.json", self.tag));
+        std::fs::write(&path, serde_json::to_string_pretty(&value)?)?;
+        Ok(path)
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) -> Option<TagHash> {
+        let mut open_tag = None;
+        ui.label(
+            RichText::new("Skeleton and animation layouts are guessed from the tag data, verify anything important in the hex view")
+                .italics(),
+        );
+
+        egui::Grid::new("rig_summary")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Kind");
+                ui.label(self.kind.label());
+                ui.end_row();
+
+                ui.label("Class");
+                ui.label(self.class.as_deref().unwrap_or("Unknown"));
+                ui.end_row();
+
+                ui.label("Bones");
+                match &self.hierarchy {
+                    Some(source) => ui.label(format!(
+                        "{} (parent indices at array 0x{:X}, field +0x{:X}, stride 0x{:X})",
+                        self.bones.len(),
+                        source.array_offset,
+                        source.field_offset,
+                        source.stride
+                    )),
+                    None => ui.weak("No hierarchy found"),
+                };
+                ui.end_row();
+
+                if self.kind == RigKind::Animation {
+                    ui.label("Tracks");
+                    match self.track_count {
+                        Some(count) => ui.label(count.to_string()),
+                        None => ui.weak("Unknown"),
+                    };
+                    ui.end_row();
+
+                    ui.label("Duration");
+                    match self.duration {
+                        Some((offset, seconds)) => ui
+                            .label(format!("{seconds:.3}s (guessed)"))
+                            .on_hover_text(format!("Float at offset 0x{offset:X}")),
+                        None => ui.weak("Unknown"),
+                    };
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+        if !self.rigs.is_empty() {
+            ui.heading("Referenced skeletons");
+            for &(tag, bones) in &self.rigs {
+                let entry = package_manager().get_entry(tag);
+                ui.horizontal(|ui| {
+                    if ui
+                        .selectable_label(false, format_tag_entry(tag, entry.as_ref()))
+                        .tag_context(tag)
+                        .clicked()
+                    {
+                        open_tag = Some(tag);
+                    }
+                    if let Some(bones) = bones {
+                        ui.weak(format!("{bones} bones"));
+                    }
+                });
+            }
+            ui.separator();
+        }
+
+        if !self.animations.is_empty() {
+            egui::CollapsingHeader::new(format!("Used by {} animations", self.animations.len()))
+                .id_source("rig_animations")
+                .show(ui, |ui| {
+                    for &tag in &self.animations {
+                        let entry = package_manager().get_entry(tag);
+                        if ui
+                            .selectable_label(false, format_tag_entry(tag, entry.as_ref()))
+                            .tag_context(tag)
+                            .clicked()
+                        {
+                            open_tag = Some(tag);
+                        }
+                    }
+                });
+            ui.separator();
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                if !self.bones.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.heading("Hierarchy");
+                        if ui.button("Export JSON").clicked() {
+                            match self.export_json() {
+                                Ok(path) => {
+                                    TOASTS.lock().success(format!(
+                                        "Exported skeleton to {}",
+                                        path.display()
+                                    ));
+                                }
+                                Err(e) => {
+                                    log::error!("Failed to export skeleton: {e:?}");
+                                    TOASTS
+                                        .lock()
+                                        .error(format!("Failed to export skeleton: {e}"));
+                                }
+                            }
+                        }
+                    });
+
+                    for root in self
+                        .bones
+                        .iter()
+                        .positions(|b| b.parent.is_none())
+                        .collect_vec()
+                    {
+                        self.bone_ui(ui, root);
+                    }
+                    ui.separator();
+                }
+
+                ui.heading("Arrays");
+                for array in &self.arrays {
+                    let class = get_class_by_id(array.class)
+                        .map(|c| c.name.to_string())
+                        .unwrap_or_else(|| format!("{:08X}", array.class));
+                    ui.label(
+                        RichText::new(format!("0x{:X}: {class} x{}", array.offset, array.count))
+                            .monospace()
+                            .color(Color32::GRAY),
+                    );
+                }
+            });
+
+        open_tag
+    }
+
+    fn bone_ui(&self, ui: &mut egui::Ui, index: usize) {
+        let children = self.children(index).collect_vec();
+        if children.is_empty() {
+            ui.label(self.bone_label(index));
+            return;
+        }
+
+        egui::CollapsingHeader::new(self.bone_label(index))
+            .id_source(("rig_bone", index))
+            .default_open(true)
+            .show(ui, |ui| {
+                for child in children {
+                    self.bone_ui(ui, child);
+                }
+            });
+    }
+}
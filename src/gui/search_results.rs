@@ -0,0 +1,383 @@
+//! Virtualized table for tag search results, with sortable and hideable columns that can be grouped by package,
+//! class or type
+
+use eframe::egui::{self, RichText};
+use egui_extras::{Column, TableBuilder};
+use itertools::Itertools;
+use quicktag_core::classes::get_class_by_id;
+use tiger_pkg::package_manager;
+
+use super::{common::ResponseExt, tag_search::TagSearchResult, ViewAction};
+use crate::util::format_file_size;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ResultColumn {
+    Tag,
+    Name,
+    Package,
+    Type,
+    Class,
+    Size,
+    RefsIn,
+    RefsOut,
+    Entropy,
+}
+
+impl ResultColumn {
+    pub const ALL: [ResultColumn; 9] = [
+        ResultColumn::Tag,
+        ResultColumn::Name,
+        ResultColumn::Package,
+        ResultColumn::Type,
+        ResultColumn::Class,
+        ResultColumn::Size,
+        ResultColumn::RefsIn,
+        ResultColumn::RefsOut,
+        ResultColumn::Entropy,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ResultColumn::Tag => "Tag",
+            ResultColumn::Name => "Name",
+            ResultColumn::Package => "Package",
+            ResultColumn::Type => "Type",
+            ResultColumn::Class => "Class",
+            ResultColumn::Size => "Size",
+            ResultColumn::RefsIn => "Refs in",
+            ResultColumn::RefsOut => "Refs out",
+            ResultColumn::Entropy => "Entropy",
+        }
+    }
+
+    /// Numeric columns are sorted largest first when they're selected
+    fn descending_by_default(self) -> bool {
+        matches!(
+            self,
+            ResultColumn::Size
+                | ResultColumn::RefsIn
+                | ResultColumn::RefsOut
+                | ResultColumn::Entropy
+        )
+    }
+
+    fn column(self) -> Column {
+        match self {
+            ResultColumn::Tag => Column::initial(96.0),
+            ResultColumn::Name | ResultColumn::Package | ResultColumn::Class => {
+                Column::initial(192.0)
+            }
+            ResultColumn::Type => Column::initial(128.0),
+            _ => Column::initial(72.0),
+        }
+        .at_least(48.0)
+        .clip(true)
+    }
+}
+
+pub fn package_name(result: &TagSearchResult) -> String {
+    package_manager()
+        .package_paths
+        .get(&result.tag.pkg_id())
+        .map(|p| p.filename.clone())
+        .unwrap_or_else(|| format!("{:04X}", result.tag.pkg_id()))
+}
+
+pub fn class_name(result: &TagSearchResult) -> String {
+    get_class_by_id(result.class)
+        .map(|c| c.name.to_string())
+        .unwrap_or_else(|| format!("{:08X}", result.class))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    None,
+    Package,
+    Class,
+    Type,
+}
+
+impl GroupBy {
+    pub const ALL: [GroupBy; 4] = [
+        GroupBy::None,
+        GroupBy::Package,
+        GroupBy::Class,
+        GroupBy::Type,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GroupBy::None => "None",
+            GroupBy::Package => "Package",
+            GroupBy::Class => "Class",
+            GroupBy::Type => "Type",
+        }
+    }
+
+    fn group_name(self, result: &TagSearchResult) -> String {
+        match self {
+            GroupBy::None => String::new(),
+            GroupBy::Package => package_name(result),
+            GroupBy::Class => class_name(result),
+            GroupBy::Type => result.tag_type.to_string(),
+        }
+    }
+}
+
+struct ResultGroup {
+    name: String,
+    /// Indices into the results, in sorted order
+    rows: Vec<usize>,
+}
+
+pub struct ResultsTable {
+    /// Visible columns, in the order of [`ResultColumn::ALL`]
+    visible: Vec<ResultColumn>,
+    sort_column: ResultColumn,
+    sort_descending: bool,
+    /// Whether the results still need to be sorted (and grouped)
+    pub needs_sort: bool,
+    group_by: GroupBy,
+    /// Largest groups first, only valid when the results are sorted
+    groups: Vec<ResultGroup>,
+}
+
+impl ResultsTable {
+    pub fn new() -> Self {
+        Self {
+            visible: ResultColumn::ALL.to_vec(),
+            sort_column: ResultColumn::Tag,
+            sort_descending: false,
+            needs_sort: false,
+            group_by: GroupBy::None,
+            groups: vec![],
+        }
+    }
+
+    pub fn sort(&mut self, results: &mut [TagSearchResult]) {
+        match self.sort_column {
+            ResultColumn::Tag => results.sort_by_key(|r| r.tag.0),
+            ResultColumn::Name => results.sort_by_cached_key(|r| {
+                (r.name.is_none(), r.name.as_ref().map(|n| n.to_lowercase()))
+            }),
+            ResultColumn::Package => results.sort_by_key(|r| (r.tag.pkg_id(), r.tag.0)),
+            ResultColumn::Type => results.sort_by_cached_key(|r| (r.tag_type.to_string(), r.tag.0)),
+            ResultColumn::Class => results.sort_by_key(|r| (r.class, r.tag.0)),
+            ResultColumn::Size => results.sort_by_key(|r| r.size),
+            ResultColumn::RefsIn => results.sort_by_key(|r| r.refs_in),
+            ResultColumn::RefsOut => results.sort_by_key(|r| r.refs_out),
+            ResultColumn::Entropy => results.sort_by(|a, b| {
+                a.entropy
+                    .unwrap_or_default()
+                    .total_cmp(&b.entropy.unwrap_or_default())
+            }),
+        }
+
+        if self.sort_descending {
+            results.reverse();
+        }
+        self.needs_sort = false;
+        self.regroup(results);
+    }
+
+    fn regroup(&mut self, results: &[TagSearchResult]) {
+        if self.group_by == GroupBy::None {
+            self.groups.clear();
+            return;
+        }
+
+        // Rows keep their sorted order within each group
+        self.groups = results
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (self.group_by.group_name(r), i))
+            .into_group_map()
+            .into_iter()
+            .map(|(name, rows)| ResultGroup { name, rows })
+            .sorted_by(|a, b| {
+                b.rows
+                    .len()
+                    .cmp(&a.rows.len())
+                    .then_with(|| a.name.cmp(&b.name))
+            })
+            .collect();
+    }
+
+    pub fn columns_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Columns", |ui| {
+            for column in ResultColumn::ALL {
+                let mut visible = self.visible.contains(&column);
+                if ui.checkbox(&mut visible, column.label()).changed() {
+                    self.visible = ResultColumn::ALL
+                        .into_iter()
+                        .filter(|&c| {
+                            if c == column {
+                                visible
+                            } else {
+                                self.visible.contains(&c)
+                            }
+                        })
+                        .collect();
+                }
+            }
+        });
+    }
+
+    pub fn group_menu(&mut self, ui: &mut egui::Ui) {
+        ui.label("Group by");
+        egui::ComboBox::from_id_source("search_results_group_by")
+            .selected_text(self.group_by.label())
+            .show_ui(ui, |ui| {
+                for group_by in GroupBy::ALL {
+                    if ui
+                        .selectable_value(&mut self.group_by, group_by, group_by.label())
+                        .changed()
+                    {
+                        self.needs_sort = true;
+                    }
+                }
+            });
+    }
+
+    /// Shows the results, only the visible rows are laid out. Clicking a header sorts by that column
+    ///
+    /// Groups are shown as collapsible sections once the results are sorted, until then the results are shown as a
+    /// single table
+    pub fn show(&mut self, ui: &mut egui::Ui, results: &[TagSearchResult]) -> Option<ViewAction> {
+        let mut action = None;
+        let mut clicked_column = None;
+
+        if self.group_by == GroupBy::None || self.needs_sort {
+            self.show_table(ui, results, None, &mut action, &mut clicked_column);
+        } else {
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    for group in &self.groups {
+                        egui::CollapsingHeader::new(format!(
+                            "{} ({})",
+                            group.name,
+                            group.rows.len()
+                        ))
+                        .id_source(&group.name)
+                        .show(ui, |ui| {
+                            self.show_table(
+                                ui,
+                                results,
+                                Some(&group.rows),
+                                &mut action,
+                                &mut clicked_column,
+                            );
+                        });
+                    }
+                });
+        }
+
+        if let Some(column) = clicked_column {
+            if column == self.sort_column {
+                self.sort_descending = !self.sort_descending;
+            } else {
+                self.sort_column = column;
+                self.sort_descending = column.descending_by_default();
+            }
+            self.needs_sort = true;
+        }
+
+        action
+    }
+
+    /// Shows either all results or the given rows of a group. Group tables don't scroll by themselves, they're inside
+    /// the scroll area of the groups
+    fn show_table(
+        &self,
+        ui: &mut egui::Ui,
+        results: &[TagSearchResult],
+        rows: Option<&[usize]>,
+        action: &mut Option<ViewAction>,
+        clicked_column: &mut Option<ResultColumn>,
+    ) {
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .vscroll(rows.is_none())
+            .min_scrolled_height(0.0);
+        for column in &self.visible {
+            table = table.column(column.column());
+        }
+
+        table
+            .header(20.0, |mut header| {
+                for &column in &self.visible {
+                    header.col(|ui| {
+                        let label = if column == self.sort_column {
+                            let arrow = if self.sort_descending { "⏷" } else { "⏶" };
+                            format!("{} {arrow}", column.label())
+                        } else {
+                            column.label().to_string()
+                        };
+
+                        if ui
+                            .add(egui::Button::new(RichText::new(label).strong()).frame(false))
+                            .clicked()
+                        {
+                            *clicked_column = Some(column);
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                let row_count = rows.map_or(results.len(), |rows| rows.len());
+                body.rows(20.0, row_count, |mut row| {
+                    let r = &results[rows.map_or(row.index(), |rows| rows[row.index()])];
+                    for &column in &self.visible {
+                        row.col(|ui| match column {
+                            ResultColumn::Tag => {
+                                if ui
+                                    .selectable_label(
+                                        false,
+                                        RichText::new(r.tag.to_string())
+                                            .color(r.tag_type.display_color()),
+                                    )
+                                    .tag_context(r.tag)
+                                    .clicked()
+                                {
+                                    *action = Some(ViewAction::OpenTag(r.tag));
+                                }
+                            }
+                            ResultColumn::Name => {
+                                ui.label(r.name.as_deref().unwrap_or_default());
+                            }
+                            ResultColumn::Package => {
+                                ui.label(package_name(r));
+                            }
+                            ResultColumn::Type => {
+                                ui.label(
+                                    RichText::new(r.tag_type.to_string())
+                                        .color(r.tag_type.display_color()),
+                                );
+                            }
+                            ResultColumn::Class => {
+                                ui.label(class_name(r));
+                            }
+                            ResultColumn::Size => {
+                                ui.label(format_file_size(r.size as usize));
+                            }
+                            ResultColumn::RefsIn => {
+                                ui.label(r.refs_in.to_string());
+                            }
+                            ResultColumn::RefsOut => {
+                                ui.label(r.refs_out.to_string());
+                            }
+                            ResultColumn::Entropy => {
+                                ui.label(
+                                    r.entropy
+                                        .map(|e| format!("{e:.2}"))
+                                        .unwrap_or_else(|| "-".to_string()),
+                                );
+                            }
+                        });
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,571 @@
+use eframe::egui::{self, RichText};
+
+use quicktag_core::classes::get_class_by_id;
+use quicktag_core::palette::Palette;
+use quicktag_scanner::{nested_strings::MAX_NESTED_STRING_DEPTH, scope};
+use quicktag_strings::localized::LANGUAGE_CODES;
+use tiger_pkg::package_manager;
+
+use super::TOASTS;
+use crate::config::{self, ScanScopeMode, TagViewMode};
+use crate::gui::profile;
+use crate::gui::string_languages;
+use crate::i18n::{self, tr};
+use crate::lan_share;
+use crate::open_api;
+
+/// Contents of the settings menu. Changes are saved to the config immediately
+///
+/// Returns true when the cache should be reloaded, eg. to switch to the cache of another scan scope
+pub fn settings_menu(ui: &mut egui::Ui) -> bool {
+    let mut reload_cache = false;
+    ui.label(RichText::new(tr("Tag cache")).strong());
+
+    let mut cache = config::with(|c| c.cache.clone());
+    let max_threads = std::thread::available_parallelism()
+        .map(|n| n.get() as u32)
+        .unwrap_or(1);
+
+    let level = ui
+        .add(egui::Slider::new(&mut cache.compression_level, 1..=19).text("Compression level"))
+        .on_hover_text(
+            "Lower levels make rebuilding the cache faster, higher levels produce a smaller cache file",
+        );
+    let threads = ui
+        .add(
+            egui::Slider::new(&mut cache.compression_threads, 0..=max_threads)
+                .text("Compression threads"),
+        )
+        .on_hover_text("0 uses every available core");
+
+    let harvest = ui
+        .checkbox(
+            &mut cache.harvest_unknown_hashes,
+            "Harvest unknown string hashes",
+        )
+        .on_hover_text(
+            "Records values that look like string hashes but aren't in the string cache, \
+            and writes them to unknown_hashes_<version>.csv sorted by frequency",
+        );
+
+    let string_runs = ui
+        .checkbox(&mut cache.scan_string_runs, "Scan inline strings")
+        .on_hover_text(
+            "Finds null-terminated UTF-8/UTF-16 strings outside of raw string blobs. \
+            Makes building the cache slower",
+        );
+
+    let nested = ui
+        .add(
+            egui::Slider::new(&mut cache.nested_string_depth, 0..=MAX_NESTED_STRING_DEPTH)
+                .text("Nested string depth"),
+        )
+        .on_hover_text(
+            "Follows tag references from string classes this many levels deep, and scans the referenced tags \
+            for strings as well. 0 disables this. Every level makes building the cache slower",
+        );
+
+    let mut scope_changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Scan scope:");
+        egui::ComboBox::from_id_source("settings_scan_scope")
+            .selected_text(cache.scan_scope.label())
+            .show_ui(ui, |ui| {
+                for mode in ScanScopeMode::ALL {
+                    scope_changed |= ui
+                        .selectable_value(&mut cache.scan_scope, mode, mode.label())
+                        .changed();
+                }
+            })
+            .response
+            .on_hover_text(
+                "Only scans some of the packages, which builds a much smaller cache in a fraction of the time. \
+                References from or to other packages are missing. Each scope has its own cache file",
+            );
+    });
+
+    match cache.scan_scope {
+        ScanScopeMode::All => {}
+        ScanScopeMode::Matching => {
+            ui.horizontal(|ui| {
+                ui.label("Package pattern:");
+                scope_changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut cache.scan_pattern)
+                            .hint_text("w64_sr_gear_*")
+                            .desired_width(160.0),
+                    )
+                    .on_hover_text("* matches anything, ? matches a single character")
+                    .changed();
+            });
+        }
+        ScanScopeMode::Selected => {
+            ui.weak("Pick packages with the checkboxes in the Packages tab");
+        }
+    }
+
+    if cache.scan_scope != ScanScopeMode::All {
+        let scope = cache.scan_scope();
+        let matched = package_manager()
+            .package_paths
+            .values()
+            .filter(|path| scope.includes(path))
+            .count();
+        ui.label(format!(
+            "{matched} of {} packages will be scanned",
+            package_manager().package_paths.len()
+        ));
+    }
+
+    if level.changed()
+        || threads.changed()
+        || harvest.changed()
+        || string_runs.changed()
+        || nested.changed()
+        || scope_changed
+    {
+        config::with_mut(|c| c.cache = cache.clone());
+    }
+
+    ui.horizontal(|ui| {
+        ui.weak("Applies the next time the cache is rebuilt");
+        if Some(cache.scan_scope()) != scope::loaded_scope()
+            && ui
+                .button("Load cache for this scope")
+                .on_hover_text("Loads the cache for the selected scan scope, building it if needed")
+                .clicked()
+        {
+            reload_cache = true;
+            ui.close_menu();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Post-scan hook:");
+        let mut program = config::with(|c| c.post_scan_hook.clone()).unwrap_or_default();
+        if ui
+            .add(
+                egui::TextEdit::singleline(&mut program)
+                    .hint_text("None")
+                    .desired_width(192.0),
+            )
+            .on_hover_text(
+                "Program to run after a new cache has been built. \
+                The cache path and the path to a JSON summary are appended to its arguments",
+            )
+            .changed()
+        {
+            // Not trimmed here, otherwise spaces can't be typed
+            config::with_mut(|c| {
+                c.post_scan_hook = (!program.trim().is_empty()).then_some(program)
+            });
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Arguments:");
+        let mut args = config::with(|c| c.post_scan_hook_args.join("\n"));
+        if ui
+            .add(
+                egui::TextEdit::multiline(&mut args)
+                    .hint_text("One argument per line")
+                    .desired_rows(2)
+                    .desired_width(192.0),
+            )
+            .changed()
+        {
+            config::with_mut(|c| {
+                c.post_scan_hook_args = args
+                    .lines()
+                    .filter(|a| !a.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            });
+        }
+    });
+
+    ui.separator();
+    ui.label(RichText::new(tr("Patch watchdog")).strong());
+
+    let mut watchdog = config::with(|c| c.watchdog.clone());
+    let enabled = ui
+        .checkbox(&mut watchdog.enabled, "Watch for patches")
+        .on_hover_text(
+        "Checks the packages directory for changes, rebuilds the cache when the game was patched \
+            and writes a report of new tags, strings and classes",
+    );
+    let interval = ui.add_enabled(
+        watchdog.enabled,
+        egui::Slider::new(&mut watchdog.interval_minutes, 1..=24 * 60)
+            .logarithmic(true)
+            .text("Check interval (minutes)"),
+    );
+    let output_dir = ui
+        .horizontal(|ui| {
+            ui.label("Report directory:");
+            ui.add(egui::TextEdit::singleline(&mut watchdog.output_dir).desired_width(192.0))
+                .on_hover_text("Reports are also written when the cache is regenerated")
+        })
+        .inner;
+
+    let mut webhook_url = watchdog.webhook_url.clone().unwrap_or_default();
+    let webhook = ui
+        .horizontal(|ui| {
+            ui.label("Webhook URL:");
+            ui.add(
+                egui::TextEdit::singleline(&mut webhook_url)
+                    .hint_text("None")
+                    .password(true)
+                    .desired_width(192.0),
+            )
+            .on_hover_text(
+                "Reports of patches and regenerated caches are posted here as a Discord embed",
+            )
+        })
+        .inner;
+    if webhook.changed() {
+        watchdog.webhook_url =
+            (!webhook_url.trim().is_empty()).then(|| webhook_url.trim().to_string());
+    }
+
+    if enabled.changed() || interval.changed() || output_dir.changed() || webhook.changed() {
+        config::with_mut(|c| c.watchdog = watchdog);
+    }
+
+    ui.separator();
+    ui.label(RichText::new(tr("Tag data")).strong());
+
+    let mut tag_data = config::with(|c| c.tag_data.clone());
+    let cache_size = ui
+        .add(
+            egui::Slider::new(&mut tag_data.cache_size_mb, 0..=4096)
+                .logarithmic(true)
+                .text("Memory cache (MB)"),
+        )
+        .on_hover_text(
+            "Recently opened tags are kept in memory, which makes navigating back and forth instant. 0 disables the cache",
+        );
+    let prefetch = ui
+        .add_enabled(
+            tag_data.cache_size_mb > 0,
+            egui::Slider::new(&mut tag_data.prefetch_count, 0..=64).text("Prefetched references"),
+        )
+        .on_hover_text(
+            "Reads the first references of an opened tag in the background, so clicking through is immediate. \
+            0 disables prefetching, which saves memory",
+        );
+
+    let textures = ui
+        .add(
+            egui::Slider::new(&mut tag_data.texture_cache_count, 16..=8192)
+                .logarithmic(true)
+                .text("Loaded textures"),
+        )
+        .on_hover_text(
+            "Texture previews and thumbnails that are kept loaded. Lower this if quicktag uses too much (GPU) memory",
+        );
+
+    if cache_size.changed() || prefetch.changed() || textures.changed() {
+        config::with_mut(|c| c.tag_data = tag_data);
+    }
+
+    ui.separator();
+    ui.label(RichText::new(tr("Cache sharing")).strong());
+
+    let mut share = config::with(|c| c.share.clone());
+    let host = ui
+        .checkbox(&mut share.host, "Share cache on the local network")
+        .on_hover_text(
+            "Other quicktag instances can load this cache with --connect <address>:<port>. \
+            Anyone that can reach the address can read the cache and tag data while this is enabled",
+        );
+    let address = ui
+        .horizontal(|ui| {
+            ui.label("Address:");
+            ui.add(
+                egui::TextEdit::singleline(&mut share.bind_address)
+                    .hint_text("127.0.0.1")
+                    .desired_width(120.0),
+            )
+            .on_hover_text(
+                "Network interface to share on, eg. this machine's LAN address. \
+                127.0.0.1 only shares with this machine, 0.0.0.0 shares on every interface",
+            )
+        })
+        .inner;
+    let port = ui
+        .horizontal(|ui| {
+            ui.label("Port:");
+            ui.add(egui::DragValue::new(&mut share.port).range(1024..=u16::MAX))
+        })
+        .inner;
+
+    let mut link_address = share.link.clone().unwrap_or_default();
+    let link = ui
+        .horizontal(|ui| {
+            ui.label("Linked instance:");
+            ui.add(
+                egui::TextEdit::singleline(&mut link_address)
+                    .hint_text("None")
+                    .desired_width(160.0),
+            )
+            .on_hover_text(
+                "<address>:<port> of another instance that shares its cache, eg. one with the packages from before a patch. \
+                Opening a tag here opens the corresponding tag there, and the hex view highlights the differences",
+            )
+        })
+        .inner;
+    if link.changed() {
+        share.link = (!link_address.trim().is_empty()).then(|| link_address.trim().to_string());
+    }
+    let follow = ui
+        .checkbox(
+            &mut share.follow_links,
+            "Open tags opened by linked instances",
+        )
+        .on_hover_text("Only works while the cache is shared");
+
+    if host.changed() || address.changed() || port.changed() || link.changed() || follow.changed() {
+        config::with_mut(|c| c.share = share.clone());
+    }
+    // Changing the address or port restarts the host, so that's only done once the value is settled
+    if host.changed() || address.lost_focus() || port.drag_stopped() || port.lost_focus() {
+        lan_share::update_host(&share);
+    }
+
+    if let Some(remote) = lan_share::remote() {
+        ui.weak(format!("Using the cache shared by {remote} (read-only)"));
+    }
+
+    let mut open_api_settings = config::with(|c| c.open_api.clone());
+    let enabled = ui
+        .checkbox(
+            &mut open_api_settings.enabled,
+            "Let other programs open tags",
+        )
+        .on_hover_text(
+            "Editors and scripts on this machine can open tags by connecting to 127.0.0.1:<port>, \
+            see the README for the protocol",
+        );
+    let port = ui
+        .horizontal(|ui| {
+            ui.label("Port:");
+            ui.add(egui::DragValue::new(&mut open_api_settings.port).range(1024..=u16::MAX))
+        })
+        .inner;
+
+    if enabled.changed() || port.changed() {
+        config::with_mut(|c| c.open_api = open_api_settings.clone());
+    }
+    if enabled.changed() || port.drag_stopped() || port.lost_focus() {
+        open_api::update_listener(&open_api_settings);
+    }
+
+    ui.separator();
+    ui.label(RichText::new(tr("Interface")).strong());
+
+    let mut general = config::with(|c| c.general.clone());
+    let mut profile = general.profile.clone().unwrap_or_default();
+    let profile_name = ui
+        .horizontal(|ui| {
+            ui.label("Profile name:");
+            ui.add(egui::TextEdit::singleline(&mut profile).desired_width(160.0))
+        })
+        .inner
+        .on_hover_text("Shown in the window title, to tell windows for different builds apart");
+
+    let mut custom_accent = general.accent_color.is_some();
+    let mut accent = general.accent_color.unwrap_or_else(|| {
+        let [r, g, b, _] = profile::accent_color().to_array();
+        [r, g, b]
+    });
+    let accent_changed = ui
+        .horizontal(|ui| {
+            let toggled = ui.checkbox(&mut custom_accent, "Accent color").changed();
+            let picked = ui
+                .add_enabled_ui(custom_accent, |ui| ui.color_edit_button_srgb(&mut accent))
+                .inner
+                .changed();
+            toggled || picked
+        })
+        .inner;
+
+    let mut ui_language = general
+        .ui_language
+        .clone()
+        .unwrap_or_else(|| i18n::SOURCE_LANGUAGE.to_string());
+    let mut language_changed = false;
+    ui.horizontal(|ui| {
+        ui.label(tr("Language:"));
+        egui::ComboBox::from_id_source("settings_ui_language")
+            .selected_text(&ui_language)
+            .show_ui(ui, |ui| {
+                for language in i18n::available_languages() {
+                    language_changed |= ui
+                        .selectable_value(&mut ui_language, language.clone(), language)
+                        .changed();
+                }
+            })
+            .response
+            .on_hover_text(format!(
+                "Translations are read from {}, see the README to add one",
+                config::data_path(i18n::LOCALES_DIR).display()
+            ));
+
+        if ui
+            .button(tr("Export strings..."))
+            .on_hover_text(
+                "Writes every UI string that was shown so far, with its translation in the current language, \
+                as a starting point for a new translation",
+            )
+            .clicked()
+        {
+            export_translation_template();
+        }
+    });
+
+    let mut palette_changed = false;
+    ui.horizontal(|ui| {
+        ui.label(tr("Color palette:"));
+        egui::ComboBox::from_id_source("settings_palette")
+            .selected_text(general.palette.label())
+            .show_ui(ui, |ui| {
+                for palette in Palette::ALL {
+                    palette_changed |= ui
+                        .selectable_value(&mut general.palette, palette, palette.label())
+                        .changed();
+                }
+            })
+            .response
+            .on_hover_text(
+                "Colors used for tag types and highlights in the hex view. \
+                Custom tag type colors are kept as they are",
+            );
+    });
+
+    if profile_name.changed() || accent_changed || language_changed || palette_changed {
+        general.profile = Some(profile.trim().to_string()).filter(|p| !p.is_empty());
+        general.accent_color = custom_accent.then_some(accent);
+        general.ui_language = Some(ui_language).filter(|l| l != i18n::SOURCE_LANGUAGE);
+        config::with_mut(|c| c.general = general);
+        profile::update_window_title(ui.ctx());
+    }
+
+    ui.separator();
+    ui.label(RichText::new(tr("Hex view")).strong());
+
+    let mut hex_view = config::with(|c| c.hex_view.clone());
+    let detect = ui.checkbox(&mut hex_view.detect_floats, "Detect floats");
+    let min = ui
+        .add_enabled(
+            hex_view.detect_floats,
+            egui::Slider::new(&mut hex_view.min_float_exponent, -38..=0)
+                .text("Smallest float (10^n)"),
+        )
+        .on_hover_text("Raising this hides tiny floats, which are usually integers or hashes");
+    let max = ui.add_enabled(
+        hex_view.detect_floats,
+        egui::Slider::new(&mut hex_view.max_float_exponent, 0..=38).text("Largest float (10^n)"),
+    );
+
+    let markers = ui
+        .checkbox(&mut hex_view.non_color_markers, "Non-color markers")
+        .on_hover_text(
+            "Marks differences with a notch, tag references with an underline, selected rows with a bar \
+            and position/rotation rows with a label, in addition to their colors",
+        );
+
+    if detect.changed() || min.changed() || max.changed() || markers.changed() {
+        config::with_mut(|c| c.hex_view = hex_view);
+    }
+
+    ui.separator();
+    ui.label(RichText::new(tr("Default views")).strong());
+
+    let class_views = config::with(|c| c.tag_view.class_views.clone());
+    if class_views.is_empty() {
+        ui.weak("Use \"📌 Default for class\" in the tag view to open tags of a class in a specific view");
+    }
+    for (key, mut view) in class_views {
+        let class = u32::from_str_radix(key.trim(), 16).ok();
+        ui.horizontal(|ui| {
+            let name = class
+                .and_then(get_class_by_id)
+                .map(|c| c.name.to_string())
+                .unwrap_or_else(|| key.clone());
+            ui.label(name);
+            let Some(class) = class else {
+                ui.weak("invalid class hash");
+                return;
+            };
+
+            let mut changed = false;
+            egui::ComboBox::from_id_source(("settings_class_view", class))
+                .selected_text(view.label())
+                .show_ui(ui, |ui| {
+                    for mode in TagViewMode::ALL {
+                        changed |= ui.selectable_value(&mut view, mode, mode.label()).changed();
+                    }
+                });
+            if changed {
+                config::with_mut(|c| c.tag_view.set_class_view(class, Some(view)));
+            }
+            if ui.small_button("🗙").on_hover_text("Remove").clicked() {
+                config::with_mut(|c| c.tag_view.set_class_view(class, None));
+            }
+        });
+    }
+
+    ui.separator();
+    ui.label(RichText::new(tr("String languages")).strong());
+
+    let mut strings = config::with(|c| c.strings.clone());
+    let mut languages_changed = false;
+    ui.horizontal_wrapped(|ui| {
+        for &code in LANGUAGE_CODES {
+            let mut enabled = strings.languages.iter().any(|l| l == code);
+            string_languages::status_indicator(ui, code);
+            if ui.checkbox(&mut enabled, code).changed() {
+                languages_changed = true;
+                if enabled {
+                    strings.languages.push(code.to_string());
+                } else {
+                    strings.languages.retain(|l| l != code);
+                }
+            }
+        }
+    });
+
+    if languages_changed {
+        config::with_mut(|c| c.strings = strings);
+        string_languages::reload_strings(ui.ctx());
+    }
+
+    ui.weak(
+        "String hashes are resolved from these languages, English first. Languages are loaded when selected \
+        and unloaded when deselected. Applies to the tag cache the next time it's rebuilt",
+    );
+
+    reload_cache
+}
+
+fn export_translation_template() {
+    let Ok(Some(path)) = native_dialog::FileDialog::new()
+        .add_filter("TOML", &["toml"])
+        .set_filename("template.toml")
+        .show_save_single_file()
+    else {
+        return;
+    };
+
+    match i18n::export_template(&path) {
+        Ok(count) => TOASTS
+            .lock()
+            .success(format!("Exported {count} strings to {}", path.display())),
+        Err(e) => {
+            log::error!("Failed to export UI strings: {e:?}");
+            TOASTS
+                .lock()
+                .error(format!("Failed to export UI strings: {e}"));
+        }
+    }
+}
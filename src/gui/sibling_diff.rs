@@ -0,0 +1,336 @@
+//! Field-by-field comparison of sibling structs, eg. tags of the same class or the elements of one array. Fields that
+//! differ between the siblings are highlighted, which helps with working out what they mean
+//!
+//! Structs are compared as 4-byte fields. The struct size comes from the class (see [`get_class_by_id`]) when its size
+//! is known, otherwise it's inferred from the data (the space between arrays, or the size of the smallest tag).
+
+use eframe::egui::{self, Color32, RichText};
+use egui_extras::{Column, TableBuilder};
+use quicktag_core::classes::{get_class_by_id, get_enums, EnumField};
+use rustc_hash::FxHashSet;
+use tiger_pkg::{package_manager, DestinyVersion, Endian, GameVersion, TagHash, Version};
+
+use super::{enum_editor, ViewAction};
+
+/// Larger structs are cut off, comparing more than this isn't useful in a table
+const MAX_STRUCT_SIZE: usize = 0x1000;
+/// Maximum amount of siblings that are compared at once
+pub const MAX_SIBLINGS: usize = 64;
+
+pub struct Sibling {
+    pub label: String,
+    /// Tag the sibling is in, opened by clicking its column header
+    pub tag: Option<TagHash>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FieldDisplay {
+    Hex,
+    Integer,
+    Float,
+}
+
+struct FieldRow {
+    offset: usize,
+    /// None for siblings that are too short for this field
+    values: Vec<Option<u32>>,
+    distinct: usize,
+    /// Enum declared within the field, shown instead of the raw values
+    enum_field: Option<EnumField>,
+}
+
+pub struct SiblingDiffWindow {
+    pub open: bool,
+    title: String,
+    siblings: Vec<Sibling>,
+    size: usize,
+    /// Where the struct size came from, shown above the table
+    size_source: String,
+    rows: Vec<FieldRow>,
+    only_varying: bool,
+    display: FieldDisplay,
+    /// Class of the siblings if they're whole tags, for the field histogram
+    tag_class: Option<u32>,
+    class: u32,
+}
+
+impl SiblingDiffWindow {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            title: String::new(),
+            siblings: vec![],
+            size: 0,
+            size_source: String::new(),
+            rows: vec![],
+            only_varying: false,
+            display: FieldDisplay::Hex,
+            tag_class: None,
+            class: 0,
+        }
+    }
+
+    /// Compares the given siblings, which are all instances of `class`. `inferred_size` is used when the class doesn't
+    /// have a known size
+    pub fn open_for(
+        &mut self,
+        title: impl Into<String>,
+        siblings: Vec<Sibling>,
+        class: u32,
+        inferred_size: usize,
+    ) {
+        self.tag_class = siblings.iter().all(|s| s.tag.is_some()).then_some(class);
+        self.class = class;
+        let class = get_class_by_id(class);
+        let (size, size_source) = match class.as_ref().and_then(|c| Some((c.name.clone(), c.size?)))
+        {
+            Some((name, size)) if size > 0 => (size, format!("size of {name}")),
+            _ => (inferred_size, "inferred from the data".to_string()),
+        };
+
+        self.title = title.into();
+        self.size = size.min(MAX_STRUCT_SIZE);
+        self.size_source = size_source;
+        self.siblings = siblings;
+        self.rows = self.build_rows(package_manager().version.endian());
+        self.open = true;
+    }
+
+    fn build_rows(&self, endian: Endian) -> Vec<FieldRow> {
+        let enums = get_enums(self.class);
+        (0..self.size / 4)
+            .map(|field| {
+                let offset = field * 4;
+                let values: Vec<Option<u32>> = self
+                    .siblings
+                    .iter()
+                    .map(|s| {
+                        let bytes: [u8; 4] = s.data.get(offset..offset + 4)?.try_into().ok()?;
+                        Some(match endian {
+                            Endian::Big => u32::from_be_bytes(bytes),
+                            Endian::Little => u32::from_le_bytes(bytes),
+                        })
+                    })
+                    .collect();
+                let distinct = values.iter().collect::<FxHashSet<_>>().len();
+
+                FieldRow {
+                    offset,
+                    values,
+                    distinct,
+                    enum_field: enums
+                        .iter()
+                        .find(|f| (offset..offset + 4).contains(&f.offset))
+                        .cloned(),
+                }
+            })
+            .collect()
+    }
+
+    fn format_value(&self, value: u32) -> String {
+        match self.display {
+            FieldDisplay::Hex => format!("{value:08X}"),
+            FieldDisplay::Integer => (value as i32).to_string(),
+            FieldDisplay::Float => {
+                let f = f32::from_bits(value);
+                if f.is_finite() && (f == 0.0 || f.abs() >= 1e-6) && f.abs() < 1e9 {
+                    format!("{f:.4}")
+                } else {
+                    "-".to_string()
+                }
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<ViewAction> {
+        let mut open = self.open;
+        let mut action = None;
+        egui::Window::new(format!("Compare {}", self.title))
+            .id(egui::Id::new("sibling_diff"))
+            .open(&mut open)
+            .default_size([720.0, 480.0])
+            .show(ctx, |ui| {
+                let varying = self.rows.iter().filter(|r| r.distinct > 1).count();
+                ui.label(format!(
+                    "{} siblings, 0x{:X} bytes ({}). {varying} of {} fields vary",
+                    self.siblings.len(),
+                    self.size,
+                    self.size_source,
+                    self.rows.len()
+                ));
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.only_varying, "Only fields that vary");
+                    ui.separator();
+                    ui.selectable_value(&mut self.display, FieldDisplay::Hex, "Hex");
+                    ui.selectable_value(&mut self.display, FieldDisplay::Integer, "Integer");
+                    ui.selectable_value(&mut self.display, FieldDisplay::Float, "Float");
+                });
+                ui.separator();
+
+                let rows: Vec<&FieldRow> = self
+                    .rows
+                    .iter()
+                    .filter(|r| !self.only_varying || r.distinct > 1)
+                    .collect();
+
+                let endian = package_manager().version.endian();
+                egui::ScrollArea::horizontal().show(ui, |ui| {
+                    TableBuilder::new(ui)
+                        .striped(true)
+                        .column(Column::exact(64.0))
+                        .column(Column::exact(56.0))
+                        .columns(Column::auto().at_least(80.0), self.siblings.len())
+                        .min_scrolled_height(0.0)
+                        .header(20.0, |mut header| {
+                            header.col(|ui| {
+                                ui.strong("Offset");
+                            });
+                            header.col(|ui| {
+                                ui.strong("Distinct");
+                            });
+                            for sibling in &self.siblings {
+                                header.col(|ui| {
+                                    let label = RichText::new(&sibling.label).strong();
+                                    match sibling.tag {
+                                        Some(tag) => {
+                                            if ui
+                                                .add(
+                                                    egui::Label::new(label)
+                                                        .sense(egui::Sense::click()),
+                                                )
+                                                .on_hover_text("Click to open")
+                                                .clicked()
+                                            {
+                                                action = Some(ViewAction::OpenTag(tag));
+                                            }
+                                        }
+                                        None => {
+                                            ui.label(label);
+                                        }
+                                    }
+                                });
+                            }
+                        })
+                        .body(|body| {
+                            body.rows(18.0, rows.len(), |mut row| {
+                                let field = rows[row.index()];
+                                let varies = field.distinct > 1;
+                                let first = field.values.first().copied().flatten();
+                                row.col(|ui| {
+                                    let text =
+                                        RichText::new(format!("0x{:X}", field.offset)).monospace();
+                                    let response = ui.add(
+                                        egui::Label::new(if varies {
+                                            text.strong()
+                                        } else {
+                                            text.weak()
+                                        })
+                                        .sense(egui::Sense::click()),
+                                    );
+                                    response.context_menu(|ui| {
+                                        if let Some(class) = self.tag_class {
+                                            if ui.button("Histogram across the class").clicked() {
+                                                action = Some(ViewAction::FieldHistogram {
+                                                    class,
+                                                    offset: field.offset,
+                                                });
+                                                ui.close_menu();
+                                            }
+                                        }
+                                        if ui.button("Declare enum...").clicked() {
+                                            enum_editor::declare_enum(
+                                                self.class,
+                                                field.offset,
+                                                field.values.iter().flatten().copied().collect(),
+                                            );
+                                            ui.close_menu();
+                                        }
+                                    });
+                                });
+                                row.col(|ui| {
+                                    ui.label(field.distinct.to_string());
+                                });
+                                for (value, sibling) in field.values.iter().zip(&self.siblings) {
+                                    row.col(|ui| match value {
+                                        Some(v) => {
+                                            let formatted = match &field.enum_field {
+                                                Some(e) => {
+                                                    e.read(&sibling.data, 0, endian).map_or_else(
+                                                        || "-".to_string(),
+                                                        |v| e.format(v),
+                                                    )
+                                                }
+                                                None => self.format_value(*v),
+                                            };
+                                            let text = RichText::new(formatted).monospace();
+                                            if Some(*v) != first {
+                                                ui.label(
+                                                    text.color(Color32::from_rgb(255, 200, 80)),
+                                                );
+                                            } else if varies {
+                                                ui.label(text);
+                                            } else {
+                                                ui.label(text.weak());
+                                            }
+                                        }
+                                        None => {
+                                            ui.weak("-");
+                                        }
+                                    });
+                                }
+                            });
+                        });
+                });
+            });
+
+        self.open = open;
+        action
+    }
+}
+
+/// Reads the elements of the array at `offset` in a tag. The element size comes from the class if it's known,
+/// otherwise it's inferred from the space between the array and the next one. Returns the elements and the inferred
+/// element size
+pub fn array_elements(
+    data: &[u8],
+    offset: u64,
+    count: u64,
+    class: u32,
+    next_array: Option<u64>,
+) -> (Vec<Sibling>, usize) {
+    // Same as the array headers read by the tag view
+    let header_size = if matches!(
+        package_manager().version,
+        GameVersion::Destiny(DestinyVersion::DestinyInternalAlpha)
+            | GameVersion::Destiny(DestinyVersion::DestinyTheTakenKing)
+    ) {
+        8
+    } else {
+        16
+    };
+    let start = offset as usize + header_size;
+    let end = next_array
+        .map_or(data.len(), |o| o as usize)
+        .min(data.len());
+    let inferred = (end.saturating_sub(start) / count.max(1) as usize) & !3;
+    let stride = get_class_by_id(class)
+        .and_then(|c| c.size)
+        .filter(|&s| s > 0)
+        .unwrap_or(inferred);
+
+    let elements = (0..count.min(MAX_SIBLINGS as u64) as usize)
+        .map(|i| {
+            let element_start = (start + i * stride).min(data.len());
+            let element_end = (element_start + stride).min(data.len());
+            Sibling {
+                label: format!("[{i}]"),
+                tag: None,
+                data: data[element_start..element_end].to_vec(),
+            }
+        })
+        .collect();
+
+    (elements, inferred)
+}
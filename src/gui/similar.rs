@@ -0,0 +1,47 @@
+use itertools::Itertools;
+use quicktag_scanner::TagCache;
+use rayon::prelude::*;
+use tiger_pkg::{package_manager, TagHash};
+
+use crate::crash_context;
+
+const MAX_RESULTS: usize = 256;
+pub const MIN_SIMILARITY: f32 = 0.75;
+
+/// Finds tags with a structural fingerprint similar to the given tag, sorted by similarity
+pub fn find_similar_tags(
+    cache: &TagCache,
+    tag: TagHash,
+    same_class_only: bool,
+) -> Vec<(TagHash, f32)> {
+    let _context =
+        crash_context::push(crash_context::JOB, format!("Finding tags similar to {tag}"));
+    let Some(fingerprint) = cache.hashes.get(&tag).map(|s| &s.fingerprint) else {
+        return vec![];
+    };
+    let reference = package_manager().get_entry(tag).map(|e| e.reference);
+
+    cache
+        .hashes
+        .par_iter()
+        .filter(|(t, scan)| {
+            **t != tag
+                && scan.successful
+                && scan.fingerprint.size_bucket.abs_diff(fingerprint.size_bucket) <= 2
+                // Cheap check before doing the actual comparison
+                && scan.fingerprint.array_classes.len().abs_diff(fingerprint.array_classes.len())
+                    <= fingerprint.array_classes.len().max(1)
+        })
+        .filter(|(t, _)| {
+            !same_class_only || package_manager().get_entry(**t).map(|e| e.reference) == reference
+        })
+        .filter_map(|(t, scan)| {
+            let similarity = fingerprint.similarity(&scan.fingerprint);
+            (similarity >= MIN_SIMILARITY).then_some((*t, similarity))
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .sorted_by(|a, b| b.1.total_cmp(&a.1).then(a.0 .0.cmp(&b.0 .0)))
+        .take(MAX_RESULTS)
+        .collect()
+}
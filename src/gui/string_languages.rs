@@ -0,0 +1,80 @@
+//! Loading string languages on demand when the selection in the settings changes
+//!
+//! Only the selected languages are kept in memory (see [`quicktag_strings::localized::language_status`]). Selecting
+//! a language reads its strings in the background, deselecting one drops them, and the GUI swaps in the new string
+//! map once it's ready.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use eframe::egui::{self, Color32};
+use lazy_static::lazy_static;
+use log::error;
+use parking_lot::Mutex;
+use quicktag_strings::localized::{create_stringmap, language_status, LanguageStatus, StringCache};
+
+use super::TOASTS;
+
+static RELOADING: AtomicBool = AtomicBool::new(false);
+/// Set when the selection changes while a reload is running, so the languages are merged again afterwards
+static RELOAD_AGAIN: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref RELOADED_STRINGS: Mutex<Option<StringCache>> = Mutex::new(None);
+}
+
+/// Rebuilds the string map from the selected languages in the background, reading languages that aren't loaded yet
+pub fn reload_strings(ctx: &egui::Context) {
+    if RELOADING.swap(true, Ordering::Relaxed) {
+        RELOAD_AGAIN.store(true, Ordering::Relaxed);
+        return;
+    }
+
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        loop {
+            RELOAD_AGAIN.store(false, Ordering::Relaxed);
+            match create_stringmap() {
+                Ok(strings) => *RELOADED_STRINGS.lock() = Some(strings),
+                Err(e) => {
+                    error!("Failed to reload strings: {e:?}");
+                    TOASTS
+                        .lock()
+                        .error(format!("Failed to reload strings: {e}"));
+                }
+            }
+
+            if !RELOAD_AGAIN.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+
+        RELOADING.store(false, Ordering::Relaxed);
+        ctx.request_repaint();
+    });
+}
+
+/// Strings rebuilt by [`reload_strings`], once they're done
+pub fn take_reloaded_strings() -> Option<StringCache> {
+    if RELOADING.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    RELOADED_STRINGS.lock().take()
+}
+
+/// Small dot next to a language showing whether its strings are in memory
+pub fn status_indicator(ui: &mut egui::Ui, language: &str) {
+    let (color, text) = match language_status(language) {
+        LanguageStatus::NotLoaded => (Color32::GRAY, "Not loaded".to_string()),
+        LanguageStatus::Loading => {
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_millis(250));
+            (Color32::YELLOW, "Loading...".to_string())
+        }
+        LanguageStatus::Loaded { strings } => (Color32::GREEN, format!("{strings} strings loaded")),
+        LanguageStatus::Failed(e) => (Color32::RED, format!("Failed to load: {e}")),
+    };
+
+    ui.label(egui::RichText::new("●").color(color))
+        .on_hover_text(text);
+}
@@ -10,14 +10,19 @@ use eframe::egui::{self, RichText};
 use itertools::Itertools;
 use quicktag_core::tagtypes::TagType;
 use quicktag_scanner::TagCache;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use tiger_pkg::{package_manager, DestinyVersion, GameVersion, TagHash};
 
+use quicktag_scanner::namespaces::WWISE_NAMESPACE;
 use quicktag_strings::localized::{
-    decode_text, StringCache, StringCacheVec, StringContainer, StringData, StringPart,
+    decode_text, string_hash_language, StringCache, StringCacheVec, StringContainer, StringData,
+    StringPart,
 };
+use quicktag_strings::wwise::WWISE_WORDLIST_PATH;
 
 use super::{common::ResponseExt, tag::format_tag_entry, View, ViewAction};
+use crate::text_index::{self, TextDocument};
+use crate::watchlist;
 
 pub struct StringsView {
     cache: Arc<TagCache>,
@@ -34,10 +39,12 @@ pub struct StringsView {
     variant: StringViewVariant,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub enum StringViewVariant {
     LocalizedStrings,
     RawWordlist,
+    /// Names in a hash namespace, by namespace ID
+    HashNamespace(String),
 }
 
 impl StringsView {
@@ -99,6 +106,14 @@ impl View for StringsView {
             ui.weak("Tip: Additional strings can be added to `local_wordlist.txt`. This requires your tag cache to be regenerated (File > Regenerate Cache).");
         }
 
+        if let StringViewVariant::HashNamespace(namespace) = &self.variant {
+            if namespace == WWISE_NAMESPACE {
+                ui.weak(format!("Tip: Event names are read from `{WWISE_WORDLIST_PATH}`, one name per line. This requires your tag cache to be regenerated (File > Regenerate Cache)."));
+            } else {
+                ui.weak("Tip: Names are read from the wordlist set for this namespace in quicktag.toml. This requires your tag cache to be regenerated (File > Regenerate Cache).");
+            }
+        }
+
         let devstr_regex = regex::Regex::new(r"^str[0-9]*").unwrap();
         egui::SidePanel::left("strings_left_panel")
             .resizable(true)
@@ -137,8 +152,25 @@ impl View for StringsView {
                                 self.string_filter.to_lowercase()
                             };
 
+                            // The index only narrows down the candidates, they're still checked against the search options
+                            let candidates = (self.variant == StringViewVariant::LocalizedStrings)
+                                .then(|| text_index::search(&self.string_filter))
+                                .flatten()
+                                .map(|documents| {
+                                    documents
+                                        .into_iter()
+                                        .filter_map(|d| match d {
+                                            TextDocument::LocalizedString(hash) => Some(hash),
+                                            _ => None,
+                                        })
+                                        .collect::<FxHashSet<u32>>()
+                                });
+
                             self.strings
                                 .iter()
+                                .filter(|(hash, _)| {
+                                    candidates.as_ref().is_none_or(|c| c.contains(hash))
+                                })
                                 .filter(|(_, s)| {
                                     s.iter().any(|s| {
                                         let match_a = if self.case_sensitive {
@@ -188,12 +220,18 @@ impl View for StringsView {
                         self.strings_vec_filtered.len(),
                         |ui, range| {
                             for (hash, strings) in &self.strings_vec_filtered[range] {
+                                let language = (self.variant
+                                    == StringViewVariant::LocalizedStrings)
+                                    .then(|| string_hash_language(*hash))
+                                    .flatten()
+                                    .map(|l| format!(" [{l}]"))
+                                    .unwrap_or_default();
                                 let response = if strings.len() > 1 {
                                     ui.selectable_value(
                                         &mut self.selected_string,
                                         *hash,
                                         format!(
-                                            "'{}' {:08x} ({} collisions)",
+                                            "'{}' {:08x} ({} collisions){language}",
                                             truncate_string_stripped(&strings[0], 192),
                                             hash,
                                             strings.len()
@@ -207,7 +245,7 @@ impl View for StringsView {
                                         &mut self.selected_string,
                                         *hash,
                                         format!(
-                                            "'{}' {:08x}",
+                                            "'{}' {:08x}{language}",
                                             truncate_string_stripped(&strings[0], 192),
                                             hash
                                         ),
@@ -218,11 +256,18 @@ impl View for StringsView {
                                 if response.clicked() {
                                     self.string_selected_entries.clear();
                                     for (tag, _) in self.cache.hashes.iter().filter(|(_, scan)| {
-                                        let hashes = match self.variant {
+                                        let hashes = match &self.variant {
                                             StringViewVariant::LocalizedStrings => {
-                                                &scan.string_hashes
+                                                &scan.string_hashes[..]
                                             }
-                                            StringViewVariant::RawWordlist => &scan.wordlist_hashes,
+                                            StringViewVariant::RawWordlist => {
+                                                &scan.wordlist_hashes[..]
+                                            }
+                                            StringViewVariant::HashNamespace(namespace) => scan
+                                                .namespace_hashes
+                                                .get(namespace)
+                                                .map(Vec::as_slice)
+                                                .unwrap_or_default(),
                                         };
                                         hashes.iter().any(|c| c.hash == *hash)
                                     }) {
@@ -246,6 +291,28 @@ impl View for StringsView {
                                         ui.output_mut(|o| o.copied_text = strings[0].clone());
                                         ui.close_menu();
                                     }
+
+                                    if self.variant == StringViewVariant::LocalizedStrings {
+                                        if watchlist::with(|w| w.is_string_watched(*hash)) {
+                                            if ui
+                                                .selectable_label(false, "Stop watching string")
+                                                .clicked()
+                                            {
+                                                watchlist::with_mut(|w| {
+                                                    w.strings.retain(|s| s.hash != *hash)
+                                                });
+                                                ui.close_menu();
+                                            }
+                                        } else if ui
+                                            .selectable_label(false, "Watch string")
+                                            .clicked()
+                                        {
+                                            watchlist::with_mut(|w| {
+                                                w.watch_string(*hash, &self.strings)
+                                            });
+                                            ui.close_menu();
+                                        }
+                                    }
                                 });
                             }
                         },
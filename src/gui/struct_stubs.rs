@@ -0,0 +1,471 @@
+//! C and Rust (binrw) struct definitions for a class, generated from the class registry and what's known about the
+//! data: enums declared in the schema, tag references, hashes, arrays and bookmarks. Fields that can't be identified
+//! are guessed from their values (float or integer), so the output is a starting point rather than a finished struct
+
+use std::fmt::Write;
+
+use itertools::Itertools;
+use quicktag_core::classes::{get_class_by_id, get_enums, EnumField};
+use quicktag_core::tagtypes::TagType;
+use quicktag_strings::localized::StringCache;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, Endian, TagHash, Version};
+
+use crate::gui::tag::{ExtendedScanResult, ExtendedTagHash};
+
+/// Array elements that are looked at when guessing field types
+const MAX_SAMPLED_INSTANCES: usize = 256;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StubLanguage {
+    C,
+    Rust,
+}
+
+impl StubLanguage {
+    pub const ALL: [StubLanguage; 2] = [StubLanguage::C, StubLanguage::Rust];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StubLanguage::C => "C",
+            StubLanguage::Rust => "Rust (binrw)",
+        }
+    }
+}
+
+/// Instances of a struct in the data of a tag
+pub struct StructSample<'a> {
+    pub tag: TagHash,
+    pub class: u32,
+    pub data: &'a [u8],
+    /// Offsets of every instance, more than one for arrays
+    pub instances: Vec<usize>,
+    pub size: usize,
+    /// Array header offsets in the data with their class, to recognize array fields
+    pub arrays: Vec<(u64, u32)>,
+    /// Names of fields, by offset in the struct
+    pub field_names: FxHashMap<usize, String>,
+}
+
+enum FieldKind {
+    U32,
+    F32,
+    TagHash,
+    TagHash64,
+    /// Count and relative offset of an array of the class
+    Array(u32),
+    Enum(EnumField),
+    Bytes(usize),
+}
+
+struct Field {
+    offset: usize,
+    kind: FieldKind,
+    comment: Option<String>,
+}
+
+impl Field {
+    fn size(&self) -> usize {
+        match &self.kind {
+            FieldKind::U32 | FieldKind::F32 | FieldKind::TagHash => 4,
+            FieldKind::TagHash64 => 8,
+            FieldKind::Array(_) => 16,
+            FieldKind::Enum(e) => e.ty.size(),
+            FieldKind::Bytes(n) => *n,
+        }
+    }
+}
+
+pub fn class_name(class: u32) -> String {
+    get_class_by_id(class)
+        .map(|c| c.name.to_string())
+        .unwrap_or_else(|| format!("s_unk{class:08x}"))
+}
+
+fn looks_like_float(bits: u32) -> bool {
+    let f = f32::from_bits(bits);
+    f.is_normal() && (1e-4..=1e6).contains(&f.abs())
+}
+
+fn read_u32(data: &[u8], offset: usize, endian: Endian) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(match endian {
+        Endian::Big => u32::from_be_bytes(bytes),
+        Endian::Little => u32::from_le_bytes(bytes),
+    })
+}
+
+fn read_u64(data: &[u8], offset: usize, endian: Endian) -> Option<u64> {
+    let bytes: [u8; 8] = data.get(offset..offset + 8)?.try_into().ok()?;
+    Some(match endian {
+        Endian::Big => u64::from_be_bytes(bytes),
+        Endian::Little => u64::from_le_bytes(bytes),
+    })
+}
+
+/// What a reference points to, eg. the class of a tag
+fn describe_reference(tag: TagHash) -> String {
+    let Some(entry) = package_manager().get_entry(tag) else {
+        return "reference".to_string();
+    };
+
+    match TagType::from_type_subtype(entry.file_type, entry.file_subtype) {
+        TagType::Tag | TagType::TagGlobal => {
+            format!("reference to {}", class_name(entry.reference))
+        }
+        tag_type => format!("reference to {tag_type}"),
+    }
+}
+
+fn infer_fields(
+    sample: &StructSample,
+    scan: &ExtendedScanResult,
+    strings: &StringCache,
+) -> Vec<Field> {
+    let endian = package_manager().version.endian();
+    let instances = &sample.instances[..sample.instances.len().min(MAX_SAMPLED_INSTANCES)];
+    let enums: FxHashMap<usize, EnumField> = get_enums(sample.class)
+        .into_iter()
+        .map(|e| (e.offset, e))
+        .collect();
+    let references: FxHashMap<u64, &ExtendedTagHash> = scan
+        .file_hashes
+        .iter()
+        .map(|h| (h.offset, &h.hash))
+        .collect();
+
+    let mut fields = vec![];
+    let mut offset = 0;
+    while offset < sample.size {
+        if let Some(field) = enums.get(&offset) {
+            fields.push(Field {
+                offset,
+                kind: FieldKind::Enum(field.clone()),
+                comment: Some(format!(
+                    "{}: {}",
+                    if field.flags { "flags" } else { "enum" },
+                    field
+                        .values
+                        .iter()
+                        .map(|(v, n)| format!("{v}={n}"))
+                        .join(", ")
+                )),
+            });
+            offset += field.ty.size();
+            continue;
+        }
+
+        // Unaligned leftovers, and the tail of structs that aren't a multiple of 4
+        let next_enum = enums.keys().filter(|&&o| o > offset).min().copied();
+        if offset % 4 != 0 || offset + 4 > sample.size || next_enum.is_some_and(|o| o < offset + 4)
+        {
+            let end = (offset + 1..=sample.size)
+                .find(|&o| o % 4 == 0 || Some(o) == next_enum)
+                .unwrap_or(sample.size);
+            fields.push(Field {
+                offset,
+                kind: FieldKind::Bytes(end - offset),
+                comment: None,
+            });
+            offset = end;
+            continue;
+        }
+
+        let at = |instance: usize| (instance + offset) as u64;
+        let reference = instances.iter().find_map(|&i| references.get(&at(i)));
+        let array = instances.first().and_then(|&i| {
+            let relative = read_u64(sample.data, i + offset + 8, endian)?;
+            let target = (i + offset + 8) as u64 + relative;
+            sample
+                .arrays
+                .iter()
+                .find(|(start, _)| *start == target)
+                .map(|(_, class)| *class)
+        });
+
+        let field = match (array, reference) {
+            (Some(class), _) if offset + 16 <= sample.size => Field {
+                offset,
+                kind: FieldKind::Array(class),
+                comment: Some(format!("array of {} ({class:08X})", class_name(class))),
+            },
+            (_, Some(ExtendedTagHash::Hash64(_))) if offset + 8 <= sample.size => Field {
+                offset,
+                kind: FieldKind::TagHash64,
+                comment: reference.map(|h| describe_reference(h.hash32())),
+            },
+            (_, Some(ExtendedTagHash::Hash32(tag))) => Field {
+                offset,
+                kind: FieldKind::TagHash,
+                comment: Some(describe_reference(*tag)),
+            },
+            _ => {
+                let values = instances
+                    .iter()
+                    .filter_map(|&i| read_u32(sample.data, i + offset, endian))
+                    .collect_vec();
+                let namespace = scan
+                    .namespace_hashes
+                    .iter()
+                    .find(|h| instances.iter().any(|&i| h.offset == at(i)));
+                let string = values.iter().find_map(|v| strings.get(v));
+                let nonzero = values.iter().filter(|&&v| v != 0).collect_vec();
+
+                if let Some(h) = namespace {
+                    Field {
+                        offset,
+                        kind: FieldKind::U32,
+                        comment: Some(format!("{} hash", h.namespace.name)),
+                    }
+                } else if let Some(string) = string {
+                    Field {
+                        offset,
+                        kind: FieldKind::U32,
+                        comment: Some(format!("string hash, eg. '{}'", string[0])),
+                    }
+                } else if !nonzero.is_empty() && nonzero.iter().all(|&&v| looks_like_float(v)) {
+                    Field {
+                        offset,
+                        kind: FieldKind::F32,
+                        comment: None,
+                    }
+                } else {
+                    Field {
+                        offset,
+                        kind: FieldKind::U32,
+                        comment: values.first().map(|v| format!("eg. 0x{v:X}")),
+                    }
+                }
+            }
+        };
+        offset += field.size();
+        fields.push(field);
+    }
+
+    fields
+}
+
+/// `s_static_mesh` -> `SStaticMesh`
+fn rust_type_name(name: &str) -> String {
+    name.split(|c: char| c == '_' || !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+const C_KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "nullptr",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "struct",
+    "switch",
+    "thread_local",
+    "true",
+    "typedef",
+    "typeof",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// A bookmark name turned into a lowercase identifier
+fn identifier(name: &str) -> Option<String> {
+    let name = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    if name.trim_matches('_').is_empty() {
+        None
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(format!("_{name}"))
+    } else {
+        Some(name)
+    }
+}
+
+/// Bookmark names turned into identifiers, `unk<offset>` for fields without one. Names that more than one field has
+/// get the offset appended, and keywords are escaped
+fn field_name(language: StubLanguage, sample: &StructSample, offset: usize) -> String {
+    let Some(name) = sample.field_names.get(&offset).and_then(|n| identifier(n)) else {
+        return format!("unk{offset:x}");
+    };
+
+    let duplicate = sample
+        .field_names
+        .iter()
+        .any(|(&o, n)| o != offset && identifier(n).as_ref() == Some(&name));
+    let name = if duplicate {
+        format!("{name}_{offset:x}")
+    } else {
+        name
+    };
+
+    match language {
+        // These can't be raw identifiers
+        StubLanguage::Rust if ["crate", "self", "super"].contains(&name.as_str()) => {
+            format!("{name}_")
+        }
+        StubLanguage::Rust if RUST_KEYWORDS.contains(&name.as_str()) => format!("r#{name}"),
+        StubLanguage::C if C_KEYWORDS.contains(&name.as_str()) => format!("{name}_"),
+        _ => name,
+    }
+}
+
+/// Generates the struct definition for the sample
+pub fn generate(
+    language: StubLanguage,
+    sample: &StructSample,
+    scan: &ExtendedScanResult,
+    strings: &StringCache,
+) -> String {
+    let fields = infer_fields(sample, scan, strings);
+    let name = class_name(sample.class);
+    let endian = package_manager().version.endian();
+
+    let mut out = String::new();
+    let header = format!(
+        "{name} ({:08X}), 0x{:X} bytes. Generated by quicktag from tag {} ({} instances)",
+        sample.class,
+        sample.size,
+        sample.tag,
+        sample.instances.len()
+    );
+
+    match language {
+        StubLanguage::C => {
+            writeln!(out, "// {header}").ok();
+            writeln!(out, "#pragma pack(push, 1)").ok();
+            writeln!(out, "typedef struct {name} {{").ok();
+            for field in &fields {
+                let field_name = field_name(language, sample, field.offset);
+                let declaration = match &field.kind {
+                    FieldKind::U32 | FieldKind::TagHash => format!("uint32_t {field_name};"),
+                    FieldKind::F32 => format!("float {field_name};"),
+                    FieldKind::TagHash64 => format!("uint64_t {field_name};"),
+                    FieldKind::Array(_) => {
+                        format!("uint64_t {field_name}_count;\n    uint64_t {field_name}_offset;")
+                    }
+                    FieldKind::Enum(e) => format!("uint{}_t {field_name};", e.ty.bits()),
+                    FieldKind::Bytes(n) => format!("uint8_t {field_name}[{n}];"),
+                };
+                let comment = match (&field.kind, &field.comment) {
+                    (FieldKind::TagHash, Some(c)) => format!(" // TagHash, {c}"),
+                    (FieldKind::TagHash64, Some(c)) => format!(" // TagHash64, {c}"),
+                    (_, Some(c)) => format!(" // {c}"),
+                    (_, None) => String::new(),
+                };
+                writeln!(
+                    out,
+                    "    /* 0x{:02X} */ {declaration}{comment}",
+                    field.offset
+                )
+                .ok();
+            }
+            writeln!(out, "}} {name};").ok();
+            writeln!(out, "#pragma pack(pop)").ok();
+            writeln!(
+                out,
+                "_Static_assert(sizeof({name}) == 0x{:X}, \"{name} has the wrong size\");",
+                sample.size
+            )
+            .ok();
+        }
+        StubLanguage::Rust => {
+            writeln!(out, "/// {header}").ok();
+            writeln!(out, "#[derive(BinRead, Debug)]").ok();
+            writeln!(
+                out,
+                "#[br({})]",
+                match endian {
+                    Endian::Big => "big",
+                    Endian::Little => "little",
+                }
+            )
+            .ok();
+            writeln!(out, "pub struct {} {{", rust_type_name(&name)).ok();
+            for field in &fields {
+                let field_name = field_name(language, sample, field.offset);
+                let ty = match &field.kind {
+                    FieldKind::U32 => "u32".to_string(),
+                    FieldKind::F32 => "f32".to_string(),
+                    FieldKind::TagHash => "TagHash".to_string(),
+                    FieldKind::TagHash64 => "TagHash64".to_string(),
+                    FieldKind::Array(_) => "(u64, u64)".to_string(),
+                    FieldKind::Enum(e) => e.ty.name().to_string(),
+                    FieldKind::Bytes(n) => format!("[u8; {n}]"),
+                };
+                let comment = match &field.kind {
+                    FieldKind::Array(_) => field
+                        .comment
+                        .as_ref()
+                        .map(|c| format!("{c}, count and offset relative to the offset field")),
+                    _ => field.comment.clone(),
+                };
+                match comment {
+                    Some(comment) => {
+                        writeln!(out, "    /// 0x{:02X}: {comment}", field.offset).ok()
+                    }
+                    None => writeln!(out, "    /// 0x{:02X}", field.offset).ok(),
+                };
+                writeln!(out, "    pub {field_name}: {ty},").ok();
+            }
+            writeln!(out, "}}").ok();
+        }
+    }
+
+    out
+}
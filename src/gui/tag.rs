@@ -13,12 +13,25 @@ use std::{
 
 use super::{
     common::{
-        open_audio_file_in_default_application, open_tag_in_default_application, tag_context,
-        ResponseExt,
+        open_audio_file_in_default_application, open_tag_in_default_application,
+        register_exported_file, tag_context, ResponseExt,
     },
     View, ViewAction,
 };
+use crate::analyzers;
+use crate::annotations;
+use crate::config::{self, TagViewMode};
+use crate::geometry::GeometryBuffers;
+use crate::gui::geometry::GeometryPreview;
 use crate::gui::hexview::TagHexView;
+use crate::gui::placements::PlacementTable;
+use crate::gui::rig::RigSummary;
+use crate::gui::sibling_diff::{array_elements, SiblingDiffWindow};
+use crate::gui::similar::{find_similar_tags, MIN_SIMILARITY};
+use crate::gui::texture_channels::ChannelViewer;
+use crate::gui::texture_usage::{find_texture_usage, is_texture_header, usage_ui, UsageNode};
+use crate::lan_share;
+use crate::tag_data;
 use crate::util::ui_image_rotated;
 use crate::{texture::cache::TextureCache, texture::Texture};
 use anyhow::Context;
@@ -36,10 +49,12 @@ use egui_extras::syntax_highlighting::CodeTheme;
 use itertools::Itertools;
 use log::error;
 use poll_promise::Promise;
+use quicktag_core::class_mapping::translate_classes;
 use quicktag_core::classes::get_class_by_id;
 use quicktag_core::tagtypes::TagType;
+use quicktag_scanner::namespaces::{hash_namespace, HashNamespace};
 use quicktag_scanner::{read_raw_string_blob, ScanResult, ScannedHash, TagCache};
-use quicktag_strings::localized::{RawStringHashCache, StringCache};
+use quicktag_strings::localized::{string_hash_language, RawStringHashCache, StringCache};
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::fmt::Write;
 use std::hash::{Hash, Hasher};
@@ -47,13 +62,9 @@ use std::rc::Rc;
 use tiger_pkg::{package::UEntryHeader, GameVersion, PackagePlatform, TagHash, TagHash64};
 use tiger_pkg::{package_manager, DestinyVersion, Version};
 
-#[derive(Copy, Clone, PartialEq)]
-enum TagViewMode {
-    Traversal,
-    Hex,
-    HexReferenced,
-    Float,
-    Search,
+/// View configured for the class of a tag, see [`config::TagViewSettings::class_views`]
+fn class_view(entry: &UEntryHeader) -> Option<TagViewMode> {
+    config::with(|c| c.tag_view.class_view(entry.reference))
 }
 
 pub struct TagView {
@@ -69,6 +80,7 @@ pub struct TagView {
 
     /// Used if this tag is a texture header
     texture: anyhow::Result<(Texture, TextureId)>,
+    channel_viewer: ChannelViewer,
 
     tag: TagHash,
     _tag64: Option<TagHash64>,
@@ -91,13 +103,27 @@ pub struct TagView {
     search_package_name_filter: String,
     search_results: Vec<(TagHash, UEntryHeader)>,
 
+    similar_tags: Option<Promise<Vec<(TagHash, f32)>>>,
+    similar_same_class: bool,
+
     render_state: RenderState,
     texture_cache: TextureCache,
     hexview: TagHexView,
     hexview_referenced: Option<TagHexView>,
     mode: TagViewMode,
+    /// Materials/models/maps using this tag, if it's a texture. Built when the usage tab is first opened
+    texture_usage: Option<Vec<UsageNode>>,
+    /// Preview of vertex/index buffers referenced by this tag, if there are any
+    geometry: Option<GeometryPreview>,
+    /// Skeleton/animation summary, if this tag looks like either
+    rig: Option<RigSummary>,
+    /// Transforms and models of a map placement tag
+    placements: Option<PlacementTable>,
 
     decompiled_shader: Result<String, String>,
+    sibling_diff: SiblingDiffWindow,
+    /// What the analyzers reported for this tag, see [`analyzers`]
+    analyzer_findings: Vec<analyzers::Finding>,
 }
 
 #[macro_export]
@@ -121,7 +147,31 @@ impl TagView {
         render_state: RenderState,
         texture_cache: TextureCache,
     ) -> Option<TagView> {
-        let tag_data = package_manager().read_tag(tag).ok()?;
+        let tag_data = tag_data::read_tag(tag).ok()?;
+        Self::from_data(
+            cache,
+            tag_history,
+            string_cache,
+            raw_string_hash_cache,
+            tag,
+            tag_data,
+            render_state,
+            texture_cache,
+        )
+    }
+
+    /// Creates a view for tag data that was read elsewhere, eg. from the host with [`lan_share::read_remote_tag`]
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_data(
+        cache: Arc<TagCache>,
+        tag_history: Rc<RefCell<TagHistory>>,
+        string_cache: Arc<StringCache>,
+        raw_string_hash_cache: Arc<RawStringHashCache>,
+        tag: TagHash,
+        tag_data: Vec<u8>,
+        render_state: RenderState,
+        texture_cache: TextureCache,
+    ) -> Option<TagView> {
         let mut array_offsets = vec![];
         let mut raw_string_offsets = vec![];
         let mut string_hashes = vec![];
@@ -248,7 +298,9 @@ impl TagView {
 
         let tag_entry = package_manager().get_entry(tag)?;
         let tag_type = TagType::from_type_subtype(tag_entry.file_type, tag_entry.file_subtype);
-        let scan = ExtendedScanResult::from_scanresult(cache.hashes.get(&tag).cloned()?);
+        let scan = cache.hashes.get(&tag).cloned()?;
+        tag_data::prefetch(scan.file_hashes.iter().map(|h| h.hash));
+        let scan = ExtendedScanResult::from_scanresult(scan);
 
         let texture = if tag_type.is_texture() && tag_type.is_header() {
             Texture::load(&render_state, tag, true).map(|t| {
@@ -264,18 +316,23 @@ impl TagView {
             Err(anyhow::anyhow!("Tag is not a texture header"))
         };
 
+        let buffers = GeometryBuffers::find(&cache, tag);
+        let geometry =
+            (!buffers.is_empty()).then(|| GeometryPreview::new(tag, buffers, render_state.clone()));
+
+        let rig = RigSummary::new(&cache, &raw_string_hash_cache, tag, &tag_data);
+        let placements = PlacementTable::new(tag, &tag_data);
+
         let hexview_referenced = if matches!(tag_type, TagType::ConstantBuffer { .. }) {
-            package_manager()
-                .read_tag(tag_entry.reference)
+            tag_data::read_tag(TagHash(tag_entry.reference))
                 .ok()
-                .map(TagHexView::new)
+                .map(|d| TagHexView::new(TagHash(tag_entry.reference), d, string_cache.clone()))
         } else {
             None
         };
 
         let decompiled_shader = if tag_type.is_shader() && tag_type.is_header() {
-            package_manager()
-                .read_tag(tag_entry.reference)
+            tag_data::read_tag(TagHash(tag_entry.reference))
                 .ok()
                 .map(|d| decompile_shader(&d))
                 .unwrap_or(Err("Failed to read shader data".to_string()))
@@ -284,9 +341,13 @@ impl TagView {
         };
 
         Some(Self {
-            hexview: TagHexView::new(tag_data.clone()),
+            hexview: TagHexView::new(tag, tag_data.clone(), string_cache.clone()),
             hexview_referenced,
-            mode: TagViewMode::Traversal,
+            mode: class_view(&tag_entry).unwrap_or(TagViewMode::Traversal),
+            texture_usage: None,
+            geometry,
+            rig,
+            placements,
 
             arrays,
             string_hashes,
@@ -298,6 +359,7 @@ impl TagView {
             tag_data,
 
             texture,
+            channel_viewer: ChannelViewer::new(),
 
             scan,
             cache,
@@ -315,6 +377,9 @@ impl TagView {
             search_package_name_filter: String::new(),
             search_results: vec![],
 
+            similar_tags: None,
+            similar_same_class: false,
+
             string_cache,
             raw_string_hash_cache,
             raw_strings,
@@ -322,9 +387,39 @@ impl TagView {
             render_state,
             texture_cache,
             decompiled_shader,
+            analyzer_findings: analyzers::findings(tag),
+            sibling_diff: SiblingDiffWindow::new(),
         })
     }
 
+    /// Opens the elements of an array in the sibling diff window
+    fn compare_array_elements(&mut self, index: usize) {
+        let (offset, array) = &self.arrays[index];
+        let next_array = self
+            .arrays
+            .iter()
+            .map(|(o, _)| *o)
+            .filter(|o| o > offset)
+            .min();
+        let (elements, inferred_size) = array_elements(
+            &self.tag_data,
+            *offset,
+            array.count,
+            array.tagtype,
+            next_array,
+        );
+
+        let class = get_class_by_id(array.tagtype)
+            .map(|c| c.name.to_string())
+            .unwrap_or_else(|| format!("{:08X}", array.tagtype));
+        self.sibling_diff.open_for(
+            format!("{class} @ 0x{offset:X}"),
+            elements,
+            array.tagtype,
+            inferred_size,
+        );
+    }
+
     /// Replaces this view with another tag
     pub fn open_tag(&mut self, tag: TagHash, push_history: bool) {
         if push_history {
@@ -342,7 +437,7 @@ impl TagView {
             tv.traversal_depth_limit = self.traversal_depth_limit;
             tv.traversal_show_strings = self.traversal_show_strings;
             tv.traversal_interactive = self.traversal_interactive;
-            tv.mode = self.mode;
+            tv.mode = class_view(&tv.tag_entry).unwrap_or(self.mode);
             tv.search_tagtype = self.search_tagtype;
             tv.search_reference = self.search_reference;
             tv.search_depth_limit = self.search_depth_limit;
@@ -353,6 +448,56 @@ impl TagView {
         }
     }
 
+    pub fn tag(&self) -> TagHash {
+        self.tag
+    }
+
+    /// Toggle for opening tags of this class in the current view
+    fn class_view_ui(&mut self, ui: &mut egui::Ui) {
+        let class = self.tag_entry.reference;
+        let class_name = get_class_by_id(class)
+            .map(|c| c.name.to_string())
+            .unwrap_or_else(|| format!("{class:08X}"));
+        let is_default = class_view(&self.tag_entry) == Some(self.mode);
+        let hover = if is_default {
+            format!(
+                "Tags of class {class_name} open in the {} view. Click to stop",
+                self.mode.label()
+            )
+        } else {
+            format!(
+                "Open tags of class {class_name} in the {} view",
+                self.mode.label()
+            )
+        };
+
+        if ui
+            .selectable_label(is_default, "📌 Default for class")
+            .on_hover_text(hover)
+            .clicked()
+        {
+            let mode = self.mode;
+            config::with_mut(|c| {
+                c.tag_view
+                    .set_class_view(class, (!is_default).then_some(mode))
+            });
+        }
+    }
+
+    /// Highlights the differences with the corresponding tag in a linked instance in the hex view. Class hashes in the
+    /// linked data are translated first, so instances from before and after Beyond Light can be compared
+    pub fn set_linked_data(&mut self, linked_tag: TagHash, mut data: Vec<u8>) {
+        translate_classes(&mut data, package_manager().version.endian());
+        self.hexview
+            .set_compare_data(format!("{linked_tag} in the linked instance"), data);
+    }
+
+    /// Switches to the hex view, scrolled to the given offset
+    pub fn jump_to_offset(&mut self, offset: u64) {
+        self.mode = TagViewMode::Hex;
+        self.hexview.highlight_offset(offset);
+    }
+
     pub fn traverse_interactive_ui(
         &self,
         ui: &mut egui::Ui,
@@ -571,6 +716,12 @@ impl TagView {
         } else if self.tag_type.is_texture() && self.tag_type.is_header() {
             match &self.texture {
                 Ok((tex, egui_texture)) => {
+                    self.channel_viewer.toolbar_ui(ui);
+                    let texture_id = self
+                        .channel_viewer
+                        .texture_id(ui.ctx(), &self.render_state, tex)
+                        .unwrap_or(*egui_texture);
+
                     let min_dimension = ui.available_size().min_elem();
                     let size = if tex.desc.width > tex.desc.height {
                         vec2(
@@ -586,7 +737,7 @@ impl TagView {
                     let (response, painter) = ui.allocate_painter(size, Sense::hover());
                     ui_image_rotated(
                         &painter,
-                        *egui_texture,
+                        texture_id,
                         response.rect,
                         // Rotate the image if it's a cubemap
                         if tex.desc.array_size == 6 { 90. } else { 0. },
@@ -594,6 +745,8 @@ impl TagView {
                     );
 
                     ui.label(tex.desc.info());
+                    self.channel_viewer
+                        .histogram_ui(ui, &self.render_state, tex);
 
                     if let Some(ref comment) = tex.comment {
                         ui.collapsing("Texture Header", |ui| {
@@ -632,6 +785,22 @@ impl TagView {
         open_new_tag
     }
 
+    pub fn texture_usage_ui(&mut self, ui: &mut egui::Ui) -> Option<TagHash> {
+        let usage = self
+            .texture_usage
+            .get_or_insert_with(|| find_texture_usage(&self.cache, self.tag));
+
+        if usage.is_empty() {
+            ui.label(RichText::new("This texture is not referenced by any tags").italics());
+            return None;
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| usage_ui(ui, usage))
+            .inner
+    }
+
     pub fn floatview_ui(&mut self, ui: &mut egui::Ui) {
         if self.tag_data.len() < 16 {
             ui.label("Tag data too short to display");
@@ -756,6 +925,86 @@ impl TagView {
         result
     }
 
+    pub fn similar_ui(&mut self, ui: &mut egui::Ui) -> Option<TagHash> {
+        ui.label(
+            RichText::new(
+                "Find tags with a similar structure (array classes, element sizes and tag size)",
+            )
+            .italics(),
+        );
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.similar_tags
+                        .as_ref()
+                        .map(|p| p.ready().is_some())
+                        .unwrap_or(true),
+                    egui::Button::new("More like this"),
+                )
+                .clicked()
+            {
+                let cache = self.cache.clone();
+                let tag = self.tag;
+                let same_class_only = self.similar_same_class;
+                self.similar_tags = Some(Promise::spawn_thread("find_similar_tags", move || {
+                    find_similar_tags(&cache, tag, same_class_only)
+                }));
+            }
+
+            ui.checkbox(&mut self.similar_same_class, "Same class only");
+        });
+
+        ui.separator();
+
+        let Some(similar) = self.similar_tags.as_ref()?.ready() else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Comparing fingerprints");
+            });
+            return None;
+        };
+
+        if similar.is_empty() {
+            ui.label(
+                RichText::new(format!(
+                    "No tags with a similarity of at least {:.0}% found",
+                    MIN_SIMILARITY * 100.0
+                ))
+                .italics(),
+            );
+            return None;
+        }
+
+        let mut result = None;
+        egui::ScrollArea::vertical().show_rows(ui, 22.0, similar.len(), |ui, range| {
+            for (tag, similarity) in &similar[range] {
+                let entry = package_manager().get_entry(*tag);
+                let tagtype = entry
+                    .as_ref()
+                    .map(|e| TagType::from_type_subtype(e.file_type, e.file_subtype))
+                    .unwrap_or(TagType::Tag);
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("{:.0}%", similarity * 100.0));
+                    if ui
+                        .selectable_label(
+                            false,
+                            RichText::new(format_tag_entry(*tag, entry.as_ref()))
+                                .color(tagtype.display_color()),
+                        )
+                        .tag_context(*tag)
+                        .clicked()
+                    {
+                        result = Some(*tag);
+                    }
+                });
+            }
+        });
+
+        result
+    }
+
     pub fn dump_traversed_tag_data_recursive(
         tag: &TraversedTag,
         directory: &Path,
@@ -777,13 +1026,14 @@ impl TagView {
         };
 
         let path = directory.join(format!("{}{}.bin", tag.tag, tag_postfix));
-        match package_manager().read_tag(tag.tag) {
+        match tag_data::read_tag(tag.tag) {
             Ok(o) => {
                 let mut file = File::create(&path).with_context(|| {
                     format!("Failed to create tag dump file ({})", path.display())
                 })?;
                 file.write_all(&o)
                     .context("Failed to write tag dump file")?;
+                register_exported_file(tag.tag, &path);
             }
             Err(e) => error!("Failed to dump data for tag {}: {e:?}", tag.tag),
         }
@@ -1022,6 +1272,7 @@ impl View for TagView {
                 });
             });
 
+        let mut compare_array = None;
         if !self.string_hashes.is_empty()
             || !self.raw_strings.is_empty()
             || !self.raw_string_hashes.is_empty()
@@ -1040,7 +1291,7 @@ impl View for TagView {
                                     if self.arrays.is_empty() {
                                         ui.label(RichText::new("No arrays found").italics());
                                     } else {
-                                        for (offset, array) in &self.arrays {
+                                        for (index, (offset, array)) in self.arrays.iter().enumerate() {
                                             let ref_label = get_class_by_id(array.tagtype)
                                                 .map(|c| {
                                                     format!("{} ({:08X})", c.name, array.tagtype)
@@ -1070,7 +1321,18 @@ impl View for TagView {
                                                     )
                                                 }
                                             })
-                                            .clicked();
+                                            .context_menu(|ui| {
+                                                if ui
+                                                    .add_enabled(
+                                                        array.count > 1,
+                                                        egui::Button::new("Compare elements"),
+                                                    )
+                                                    .clicked()
+                                                {
+                                                    compare_array = Some(index);
+                                                    ui.close_menu();
+                                                }
+                                            });
                                         }
                                     }
                                 });
@@ -1085,11 +1347,14 @@ impl View for TagView {
                                     } else {
                                         for (offset, hash) in &self.string_hashes {
                                             if let Some(strings) = self.string_cache.get(hash) {
+                                                let language = string_hash_language(*hash)
+                                                    .map(|l| format!(" [{l}]"))
+                                                    .unwrap_or_default();
                                                 if strings.len() > 1 {
                                                     ui.selectable_label(
                                                         false,
                                                         format!(
-                                                            "'{}' ({} collisions) {:08x} @ 0x{:X}",
+                                                            "'{}' ({} collisions) {:08x} @ 0x{:X}{language}",
                                                             strings[(self
                                                                 .start_time
                                                                 .elapsed()
@@ -1107,7 +1372,7 @@ impl View for TagView {
                                                     ui.selectable_label(
                                                         false,
                                                         format!(
-                                                            "'{}' {:08x} @ 0x{:X}",
+                                                            "'{}' {:08x} @ 0x{:X}{language}",
                                                             strings[0], hash, offset
                                                         ),
                                                     )
@@ -1247,6 +1512,25 @@ impl View for TagView {
                                     }
                                 });
                             });
+
+                        if !self.analyzer_findings.is_empty() {
+                            CollapsingHeader::new(egui::RichText::new("Analyzers").strong())
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    ui.group(|ui| {
+                                        for finding in &self.analyzer_findings {
+                                            ui.strong(&finding.analyzer);
+                                            // Plain strings are shown as-is, anything else as formatted JSON
+                                            let text = match &finding.output {
+                                                serde_json::Value::String(s) => s.clone(),
+                                                output => serde_json::to_string_pretty(output)
+                                                    .unwrap_or_default(),
+                                            };
+                                            ui.monospace(text);
+                                        }
+                                    });
+                                });
+                        }
                     });
                 });
         }
@@ -1264,6 +1548,22 @@ impl View for TagView {
                     );
                 }
                 ui.selectable_value(&mut self.mode, TagViewMode::Search, "Search");
+                ui.selectable_value(&mut self.mode, TagViewMode::Similar, "Similar tags");
+                if is_texture_header(self.tag_type) {
+                    ui.selectable_value(&mut self.mode, TagViewMode::TextureUsage, "Texture usage");
+                }
+                if self.geometry.is_some() {
+                    ui.selectable_value(&mut self.mode, TagViewMode::Geometry, "3D preview");
+                }
+                if let Some(rig) = &self.rig {
+                    ui.selectable_value(&mut self.mode, TagViewMode::Rig, rig.kind.label());
+                }
+                if self.placements.is_some() {
+                    ui.selectable_value(&mut self.mode, TagViewMode::Placements, "Placements");
+                }
+
+                ui.separator();
+                self.class_view_ui(ui);
             });
 
             ui.separator();
@@ -1288,16 +1588,60 @@ impl View for TagView {
                 TagViewMode::Search => {
                     open_new_tag = open_new_tag.or(self.search_ui(ui));
                 }
+                TagViewMode::Similar => {
+                    open_new_tag = open_new_tag.or(self.similar_ui(ui));
+                }
+                TagViewMode::TextureUsage => {
+                    if is_texture_header(self.tag_type) {
+                        open_new_tag = open_new_tag.or(self.texture_usage_ui(ui));
+                    } else {
+                        self.mode = TagViewMode::Traversal;
+                    }
+                }
+                TagViewMode::Geometry => {
+                    if let Some(geometry) = self.geometry.as_mut() {
+                        geometry.show(ui);
+                    } else {
+                        self.mode = TagViewMode::Traversal;
+                    }
+                }
+                TagViewMode::Rig => {
+                    if let Some(rig) = &self.rig {
+                        open_new_tag = open_new_tag.or(rig.show(ui));
+                    } else {
+                        self.mode = TagViewMode::Traversal;
+                    }
+                }
+                TagViewMode::Placements => {
+                    if let Some(placements) = self.placements.as_mut() {
+                        open_new_tag = open_new_tag.or(placements.show(ui));
+                    } else {
+                        self.mode = TagViewMode::Traversal;
+                    }
+                }
             }
         });
 
+        if let Some(index) = compare_array {
+            self.compare_array_elements(index);
+        }
+        let mut action = self.sibling_diff.show(ctx);
+
+        let virtual_tag = self
+            .hexview
+            .take_virtual_tag()
+            .or_else(|| self.hexview_referenced.as_mut()?.take_virtual_tag());
+        if let Some(tag) = virtual_tag {
+            action = Some(ViewAction::OpenVirtualTag(tag));
+        }
+
         ctx.request_repaint_after(Duration::from_secs(1));
 
         if let Some(new_tag) = open_new_tag {
             self.open_tag(new_tag, push_history);
         }
 
-        None
+        action
     }
 }
 
@@ -1351,6 +1695,8 @@ impl Hash for ExtendedTagHash {
 pub struct ExtendedScanResult {
     pub successful: bool,
     pub file_hashes: Vec<ScannedHashWithEntry<ExtendedTagHash>>,
+    /// Hashes of names in a hash namespace, sorted by offset
+    pub namespace_hashes: Vec<ScannedNamespaceHash>,
 
     /// References from other files
     pub references: Vec<(TagHash, Option<UEntryHeader>)>,
@@ -1383,9 +1729,25 @@ impl ExtendedScanResult {
 
         file_hashes_combined.sort_unstable_by_key(|v| v.offset);
 
+        let mut namespace_hashes = vec![];
+        for (id, hashes) in s.namespace_hashes {
+            // Namespaces that were removed since the cache was built are skipped
+            let Some(namespace) = hash_namespace(&id) else {
+                continue;
+            };
+
+            namespace_hashes.extend(hashes.into_iter().map(|h| ScannedNamespaceHash {
+                offset: h.offset,
+                hash: h.hash,
+                namespace: namespace.clone(),
+            }));
+        }
+        namespace_hashes.sort_unstable_by_key(|v| v.offset);
+
         ExtendedScanResult {
             successful: s.successful,
             file_hashes: file_hashes_combined,
+            namespace_hashes,
             references: s
                 .references
                 .into_iter()
@@ -1402,6 +1764,22 @@ pub struct ScannedHashWithEntry<T: Sized> {
     pub entry: Option<UEntryHeader>,
 }
 
+pub struct ScannedNamespaceHash {
+    pub offset: u64,
+    pub hash: u32,
+    pub namespace: Arc<HashNamespace>,
+}
+
+impl ScannedNamespaceHash {
+    /// Names for the hash, or the hash itself if there's no name for it (anymore)
+    pub fn names(&self) -> String {
+        match self.namespace.names.get(&self.hash) {
+            Some(names) => names.join(" / "),
+            None => format!("{:08X}", self.hash),
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq)]
 enum TraversalDirection {
     Up,
@@ -1537,7 +1915,7 @@ fn traverse_tag(
     }
 
     if show_strings {
-        let tag_data = package_manager().read_tag(tag).unwrap();
+        let tag_data = tag_data::read_tag(tag).unwrap();
         let mut raw_strings = vec![];
         let mut raw_string_hashes = vec![];
         for (i, b) in tag_data.chunks_exact(4).enumerate() {
@@ -1680,12 +2058,27 @@ pub fn format_tag_entry(tag: TagHash, entry: Option<&UEntryHeader>) -> String {
             .map(|v| format!("{} ", v.name))
             .unwrap_or_default();
 
+        let (annotation_name, labels) = annotations::with(|a| {
+            a.get(tag)
+                .map(|a| {
+                    (
+                        a.name.as_ref().map(|n| format!("{n} ")).unwrap_or_default(),
+                        if a.labels.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" [{}]", a.labels.join(", "))
+                        },
+                    )
+                })
+                .unwrap_or_default()
+        });
+
         let ref_label = get_class_by_id(entry.reference)
             .map(|c| format!(" ({})", c.name))
             .unwrap_or_default();
 
         format!(
-            "{}{named_tag}{tag} {}{ref_label} ({}+{}, ref {:08X})",
+            "{}{named_tag}{annotation_name}{tag} {}{ref_label} ({}+{}, ref {:08X}){labels}",
             if package_manager().get_tag64_for_tag32(tag).is_some() {
                 "★ "
             } else {
@@ -0,0 +1,124 @@
+//! Copying tags along with their annotations between quicktag instances through the system clipboard
+//!
+//! Copied tags are a JSON object with a `quicktag_clipboard` version field, so they can be told apart from other text.
+//! Pasting them (Ctrl+V while no text field is focused) merges their annotations into the local ones and opens the
+//! first tag
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use tiger_pkg::{package_manager, TagHash, Version};
+
+use super::TOASTS;
+use crate::annotations::{self, TagAnnotation};
+
+const CLIPBOARD_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct ClipboardTags {
+    quicktag_clipboard: u32,
+    /// Game version of the instance the tags were copied from
+    game_version: String,
+    tags: Vec<ClipboardTag>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClipboardTag {
+    /// Hex tag hash
+    tag: String,
+    annotation: Option<TagAnnotation>,
+}
+
+/// Copies the tag and its annotation to the clipboard
+pub fn copy_tag(ui: &egui::Ui, tag: TagHash) {
+    let clipboard = ClipboardTags {
+        quicktag_clipboard: CLIPBOARD_VERSION,
+        game_version: package_manager().version.name().to_string(),
+        tags: vec![ClipboardTag {
+            tag: format!("{:08X}", tag.0),
+            annotation: annotations::with(|a| a.get(tag).cloned()),
+        }],
+    };
+
+    match serde_json::to_string(&clipboard) {
+        Ok(text) => ui.output_mut(|o| o.copied_text = text),
+        Err(e) => log::error!("Failed to serialize tag {tag} for the clipboard: {e:?}"),
+    }
+}
+
+/// Adds the pasted annotation without throwing away anything that's already there
+fn merge_annotation(local: &mut TagAnnotation, pasted: &TagAnnotation) {
+    if local.name.is_none() {
+        local.name.clone_from(&pasted.name);
+    }
+
+    for label in &pasted.labels {
+        if !local.labels.contains(label) {
+            local.labels.push(label.clone());
+        }
+    }
+
+    if !pasted.notes.is_empty() && !local.notes.contains(&pasted.notes) {
+        if !local.notes.is_empty() {
+            local.notes.push_str("\n\n");
+        }
+        local.notes.push_str(&pasted.notes);
+    }
+}
+
+/// Pastes copied tags, returning the first tag so it can be opened
+fn paste(text: &str) -> Option<TagHash> {
+    let clipboard = serde_json::from_str::<ClipboardTags>(text).ok()?;
+    if clipboard.quicktag_clipboard > CLIPBOARD_VERSION {
+        TOASTS
+            .lock()
+            .warning("The tags were copied from a newer version of quicktag");
+        return None;
+    }
+
+    let tags = clipboard
+        .tags
+        .iter()
+        .filter_map(|t| {
+            let tag = TagHash(u32::from_str_radix(&t.tag, 16).ok()?);
+            Some((tag, t.annotation.as_ref()))
+        })
+        .collect::<Vec<_>>();
+
+    annotations::with_mut(
+        &format!("Paste {} tags from {}", tags.len(), clipboard.game_version),
+        |a| {
+            for (tag, annotation) in &tags {
+                if let Some(annotation) = annotation {
+                    merge_annotation(a.entry(*tag), annotation);
+                }
+            }
+        },
+    );
+
+    let (first, _) = tags.first()?;
+    if package_manager().get_entry(*first).is_none() {
+        TOASTS.lock().warning(format!(
+            "Tag {first} (copied from {}) doesn't exist in these packages",
+            clipboard.game_version
+        ));
+        return None;
+    }
+
+    Some(*first)
+}
+
+/// Handles tags pasted with Ctrl+V. Returns the tag to open
+pub fn handle_paste(ctx: &egui::Context) -> Option<TagHash> {
+    if ctx.memory(|m| m.focused().is_some()) {
+        return None;
+    }
+
+    let text = ctx.input(|i| {
+        i.events.iter().find_map(|e| match e {
+            egui::Event::Paste(text) => Some(text.clone()),
+            _ => None,
+        })
+    })?;
+
+    paste(&text)
+}
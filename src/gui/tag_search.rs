@@ -0,0 +1,889 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use eframe::egui::{self, RichText};
+use itertools::Itertools;
+use parking_lot::{Mutex, RwLock};
+use poll_promise::Promise;
+use quicktag_core::{class_mapping::to_current_era, classes::get_class_by_id, tagtypes::TagType};
+use quicktag_scanner::TagCache;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use tiger_pkg::{package::UEntryHeader, package_manager, TagHash};
+
+use super::{
+    bulk_annotate::BulkAnnotateDialog,
+    search_results::{self, ResultsTable},
+    sibling_diff::{Sibling, SiblingDiffWindow, MAX_SIBLINGS},
+    View, ViewAction, TOASTS,
+};
+use crate::annotations;
+use crate::crash_context;
+use crate::saved_queries;
+use crate::tag_data;
+use crate::text_index::{self, TextDocument};
+use crate::util::byte_entropy;
+
+/// Entropy per tag, computed the first time a search needs it
+pub type EntropyCache = Arc<RwLock<FxHashMap<TagHash, f32>>>;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagFilter {
+    /// Case-insensitive part of the package name
+    pub package: String,
+    /// Class reference (hex) or part of the class name
+    pub class: String,
+    /// Case-insensitive text in the raw strings or annotation of the tag, see [`crate::text_index`]
+    pub text: String,
+    #[serde(with = "tag_type_name")]
+    pub tag_type: Option<TagType>,
+    /// Exact (file_type, file_subtype) combination, for types that don't have a [`TagType`]
+    pub raw_type: Option<(u8, u8)>,
+    pub min_size: u32,
+    /// 0 means no limit
+    pub max_size: u32,
+    /// Bits per byte, 0.0..=8.0
+    pub min_entropy: f32,
+    pub max_entropy: f32,
+}
+
+impl Default for TagFilter {
+    fn default() -> Self {
+        Self {
+            package: String::new(),
+            class: String::new(),
+            text: String::new(),
+            tag_type: None,
+            raw_type: None,
+            min_size: 0,
+            max_size: 0,
+            min_entropy: 0.0,
+            max_entropy: 8.0,
+        }
+    }
+}
+
+impl TagFilter {
+    pub fn uses_entropy(&self) -> bool {
+        self.min_entropy > 0.0 || self.max_entropy < 8.0
+    }
+
+    fn matches_entropy(&self, entropy: f32) -> bool {
+        (self.min_entropy..=self.max_entropy).contains(&entropy)
+    }
+}
+
+/// Stores the tag type by name, as [`TagType`] isn't serializable
+mod tag_type_name {
+    use quicktag_core::tagtypes::TagType;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<TagType>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.map(|t| t.to_string()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<TagType>, D::Error> {
+        let name = Option::<String>::deserialize(deserializer)?;
+        Ok(name.and_then(|name| {
+            TagType::all_filterable()
+                .iter()
+                .find(|t| t.to_string() == name)
+                .copied()
+        }))
+    }
+}
+
+/// [`TagFilter`] with the text filters parsed up front, as they're checked for every tag
+struct PreparedFilter<'a> {
+    filter: &'a TagFilter,
+    package: String,
+    class_reference: Option<u32>,
+    class_name: String,
+    text: String,
+}
+
+impl<'a> PreparedFilter<'a> {
+    fn new(filter: &'a TagFilter) -> Self {
+        let class = filter.class.trim();
+        Self {
+            filter,
+            package: filter.package.trim().to_lowercase(),
+            // Classes from before/after Beyond Light are translated to the loaded version
+            class_reference: u32::from_str_radix(class.trim_start_matches("0x"), 16)
+                .ok()
+                .map(to_current_era),
+            class_name: class.to_lowercase(),
+            text: filter.text.trim().to_string(),
+        }
+    }
+
+    fn matches_package(&self, pkg_id: u16) -> bool {
+        self.package.is_empty()
+            || package_manager()
+                .package_paths
+                .get(&pkg_id)
+                .is_some_and(|p| p.filename.to_lowercase().contains(&self.package))
+    }
+
+    /// Checks everything except entropy, which requires reading the tag
+    fn matches_entry(&self, entry: &UEntryHeader) -> bool {
+        let f = self.filter;
+        if entry.file_size < f.min_size || (f.max_size != 0 && entry.file_size > f.max_size) {
+            return false;
+        }
+
+        if f.raw_type
+            .is_some_and(|t| t != (entry.file_type, entry.file_subtype))
+        {
+            return false;
+        }
+
+        if let Some(tag_type) = f.tag_type {
+            if TagType::from_type_subtype(entry.file_type, entry.file_subtype) != tag_type {
+                return false;
+            }
+        }
+
+        if self.class_name.is_empty() {
+            return true;
+        }
+
+        self.class_reference == Some(entry.reference)
+            || get_class_by_id(entry.reference)
+                .is_some_and(|c| c.name.to_lowercase().contains(&self.class_name))
+    }
+}
+
+pub struct TagSearchResult {
+    pub tag: TagHash,
+    /// Named tag or annotation name
+    pub name: Option<String>,
+    pub tag_type: TagType,
+    /// Class reference of the tag
+    pub class: u32,
+    pub size: u32,
+    /// Amount of tags referencing this tag
+    pub refs_in: usize,
+    /// Amount of tags referenced by this tag
+    pub refs_out: usize,
+    pub entropy: Option<f32>,
+}
+
+/// Named tags, overridden by annotation names
+pub fn tag_names() -> FxHashMap<TagHash, String> {
+    let mut names: FxHashMap<TagHash, String> = package_manager()
+        .lookup
+        .named_tags
+        .iter()
+        .map(|t| (t.hash, t.name.clone()))
+        .collect();
+
+    annotations::with(|a| {
+        for (tag, annotation) in &a.tags {
+            if let Some(name) = &annotation.name {
+                names.insert(*tag, name.clone());
+            }
+        }
+    });
+
+    names
+}
+
+fn entry_result(
+    tag: TagHash,
+    entry: &UEntryHeader,
+    cache: &TagCache,
+    names: &FxHashMap<TagHash, String>,
+) -> TagSearchResult {
+    let scan = cache.hashes.get(&tag);
+    TagSearchResult {
+        tag,
+        name: names.get(&tag).cloned(),
+        tag_type: TagType::from_type_subtype(entry.file_type, entry.file_subtype),
+        class: entry.reference,
+        size: entry.file_size,
+        refs_in: scan.map_or(0, |s| s.references.len()),
+        refs_out: scan.map_or(0, |s| s.file_hashes.len() + s.file_hashes64.len()),
+        entropy: None,
+    }
+}
+
+/// Every tag in the packages matching the package filter
+fn candidate_tags(prepared: &PreparedFilter) -> Vec<TagHash> {
+    // Only tags with matching raw strings or annotations can match the text, so those come straight from the index
+    if !prepared.text.is_empty() {
+        return text_index::search(&prepared.text)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|document| match document {
+                TextDocument::RawStrings(tag) | TextDocument::Annotation(tag) => Some(tag),
+                TextDocument::LocalizedString(_) => None,
+            })
+            .unique()
+            .filter(|tag| prepared.matches_package(tag.pkg_id()))
+            .collect_vec();
+    }
+
+    package_manager()
+        .lookup
+        .tag32_entries_by_pkg
+        .iter()
+        .filter(|(pkg_id, _)| prepared.matches_package(**pkg_id))
+        .flat_map(|(&pkg_id, entries)| {
+            (0..entries.len()).map(move |i| TagHash::new(pkg_id, i as u16))
+        })
+        .collect_vec()
+}
+
+/// Every tag matching the filter, without checking entropy
+fn filter_entries(filter: &TagFilter) -> Vec<TagHash> {
+    let prepared = PreparedFilter::new(filter);
+    candidate_tags(&prepared)
+        .into_iter()
+        .filter(|&tag| {
+            package_manager()
+                .get_entry(tag)
+                .is_some_and(|entry| prepared.matches_entry(&entry))
+        })
+        .collect_vec()
+}
+
+fn tag_entropy(tag: TagHash, entropy_cache: &EntropyCache) -> Option<f32> {
+    let cached = entropy_cache.read().get(&tag).copied();
+    if cached.is_some() {
+        return cached;
+    }
+
+    let entropy = byte_entropy(&package_manager().read_tag(tag).ok()?);
+    entropy_cache.write().insert(tag, entropy);
+    Some(entropy)
+}
+
+/// Amount of tags that are checked before their results are handed to the view
+const SEARCH_BATCH_SIZE: usize = 4096;
+
+struct SearchQuery {
+    generation: u64,
+    filter: TagFilter,
+    /// Used for the reference counts
+    cache: Arc<TagCache>,
+}
+
+/// State shared between the search view and the executor thread
+#[derive(Default)]
+struct SearchState {
+    /// Generation of the latest query. Queries with an older generation stop at the next batch
+    generation: AtomicU64,
+    /// Generation of the last query that ran to completion
+    finished: AtomicU64,
+    /// Results that haven't been picked up by the view yet
+    results: Mutex<Vec<TagSearchResult>>,
+    current: AtomicUsize,
+    total: AtomicUsize,
+}
+
+/// Runs tag searches on a background thread, streaming the results to the view as they're found
+///
+/// Every query gets a new generation. Submitting a query (or cancelling) bumps the generation, which makes the
+/// running query stop at its next batch, so changing the filters never waits for an outdated search to finish
+struct SearchExecutor {
+    sender: Sender<SearchQuery>,
+    state: Arc<SearchState>,
+}
+
+impl SearchExecutor {
+    fn new(entropy_cache: EntropyCache) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel::<SearchQuery>();
+        let state = Arc::new(SearchState::default());
+        std::thread::Builder::new()
+            .name("tag_search".to_string())
+            .spawn({
+                let state = state.clone();
+                move || {
+                    while let Ok(mut query) = receiver.recv() {
+                        // Only the latest query matters
+                        while let Ok(newer) = receiver.try_recv() {
+                            query = newer;
+                        }
+
+                        run_query(&query, &state, &entropy_cache);
+                    }
+                }
+            })
+            .expect("Failed to spawn tag search thread");
+
+        Self { sender, state }
+    }
+
+    /// Cancels the running query and starts a new one, returning its generation
+    fn submit(&self, filter: TagFilter, cache: Arc<TagCache>) -> u64 {
+        // Holding the results lock keeps batches of the previous query from being added after they're cleared
+        let mut results = self.state.results.lock();
+        let generation = self.state.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        results.clear();
+        self.state.current.store(0, Ordering::Relaxed);
+        self.state.total.store(0, Ordering::Relaxed);
+        drop(results);
+
+        self.sender
+            .send(SearchQuery {
+                generation,
+                filter,
+                cache,
+            })
+            .ok();
+        generation
+    }
+
+    fn cancel(&self) {
+        self.state.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn is_finished(&self, generation: u64) -> bool {
+        self.state.finished.load(Ordering::Acquire) == generation
+    }
+
+    fn take_results(&self) -> Vec<TagSearchResult> {
+        std::mem::take(&mut *self.state.results.lock())
+    }
+}
+
+/// Checks a single tag against the filter. Entropy is only computed (and cached) when the filter needs it
+fn match_tag(
+    tag: TagHash,
+    prepared: &PreparedFilter,
+    cache: &TagCache,
+    names: &FxHashMap<TagHash, String>,
+    entropy_cache: &EntropyCache,
+) -> Option<TagSearchResult> {
+    let entry = package_manager().get_entry(tag)?;
+    if !prepared.matches_entry(&entry) {
+        return None;
+    }
+
+    let mut r = entry_result(tag, &entry, cache, names);
+    if prepared.filter.uses_entropy() {
+        let entropy = tag_entropy(tag, entropy_cache)?;
+        r.entropy = Some(entropy);
+        if !prepared.filter.matches_entropy(entropy) {
+            return None;
+        }
+    }
+
+    Some(r)
+}
+
+/// Finds every tag matching the filter, streaming the results to the view in batches
+fn run_query(query: &SearchQuery, state: &SearchState, entropy_cache: &EntropyCache) {
+    let _context = crash_context::push(crash_context::JOB, "Searching tags");
+    let is_current = || state.generation.load(Ordering::Relaxed) == query.generation;
+    let prepared = PreparedFilter::new(&query.filter);
+
+    let names = tag_names();
+
+    let candidates = candidate_tags(&prepared);
+    if !is_current() {
+        return;
+    }
+    state.total.store(candidates.len(), Ordering::Relaxed);
+
+    candidates.par_chunks(SEARCH_BATCH_SIZE).for_each(|chunk| {
+        if !is_current() {
+            return;
+        }
+
+        let batch = chunk
+            .iter()
+            .filter_map(|&tag| match_tag(tag, &prepared, &query.cache, &names, entropy_cache))
+            .collect_vec();
+
+        let mut results = state.results.lock();
+        // Checked again while holding the lock, so an outdated query never adds to the results of a new one
+        if is_current() {
+            state.current.fetch_add(chunk.len(), Ordering::Relaxed);
+            results.extend(batch);
+        }
+    });
+
+    if is_current() {
+        state.finished.store(query.generation, Ordering::Release);
+    }
+}
+
+/// Finds every tag matching the filter in one go, for searches that don't need to be streamed or cancelled. Results are
+/// sorted by tag
+pub fn search_tags(
+    filter: &TagFilter,
+    cache: &TagCache,
+    entropy_cache: &EntropyCache,
+) -> Vec<TagSearchResult> {
+    let prepared = PreparedFilter::new(filter);
+    let names = tag_names();
+
+    let mut results = candidate_tags(&prepared)
+        .into_par_iter()
+        .filter_map(|tag| match_tag(tag, &prepared, cache, &names, entropy_cache))
+        .collect::<Vec<_>>();
+    results.sort_by_key(|r| r.tag.0);
+    results
+}
+
+/// Amount of random candidates that are checked against the entropy filter before giving up
+const MAX_RANDOM_ATTEMPTS: usize = 256;
+
+fn random_u64() -> u64 {
+    // RandomState is seeded randomly for every instance, which is plenty for picking a random tag
+    RandomState::new().build_hasher().finish()
+}
+
+/// Picks a random tag matching the filter
+pub fn random_tag(filter: &TagFilter, entropy_cache: &EntropyCache) -> Option<TagHash> {
+    let _context = crash_context::push(crash_context::JOB, "Picking a random tag");
+    let candidates = filter_entries(filter);
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let attempts = if filter.uses_entropy() {
+        MAX_RANDOM_ATTEMPTS
+    } else {
+        1
+    };
+    (0..attempts).find_map(|_| {
+        let tag = candidates[random_u64() as usize % candidates.len()];
+        if !filter.uses_entropy() {
+            return Some(tag);
+        }
+
+        tag_entropy(tag, entropy_cache)
+            .filter(|e| filter.matches_entropy(*e))
+            .map(|_| tag)
+    })
+}
+
+pub struct TagSearchView {
+    filter: TagFilter,
+    entropy_cache: EntropyCache,
+    executor: SearchExecutor,
+    /// Filter of the last submitted query, changing the filters restarts the search
+    query_filter: Option<TagFilter>,
+    /// Generation of the last submitted query
+    generation: u64,
+    cancelled: bool,
+    results: Vec<TagSearchResult>,
+    table: ResultsTable,
+    random_pick: Option<Promise<Option<TagHash>>>,
+    cache: Arc<TagCache>,
+    /// Name for saving the current filters as a query
+    save_name: String,
+    bulk_annotate: BulkAnnotateDialog,
+    sibling_diff: SiblingDiffWindow,
+}
+
+impl TagSearchView {
+    pub fn new() -> Self {
+        let entropy_cache = EntropyCache::default();
+        Self {
+            filter: TagFilter::default(),
+            executor: SearchExecutor::new(entropy_cache.clone()),
+            entropy_cache,
+            query_filter: None,
+            generation: 0,
+            cancelled: false,
+            results: vec![],
+            table: ResultsTable::new(),
+            random_pick: None,
+            cache: Default::default(),
+            save_name: String::new(),
+            bulk_annotate: BulkAnnotateDialog::new(),
+            sibling_diff: SiblingDiffWindow::new(),
+        }
+    }
+
+    pub fn set_cache(&mut self, cache: Arc<TagCache>) {
+        self.cache = cache;
+    }
+
+    /// Opens the first results in the sibling diff window
+    fn compare_results(&mut self) {
+        let siblings: Vec<Sibling> = self
+            .results
+            .iter()
+            .take(MAX_SIBLINGS)
+            .filter_map(|r| {
+                let data = tag_data::read_tag(r.tag)
+                    .map_err(|e| log::warn!("Failed to read {} for comparison: {e}", r.tag))
+                    .ok()?;
+                Some(Sibling {
+                    label: r.name.clone().unwrap_or_else(|| r.tag.to_string()),
+                    tag: Some(r.tag),
+                    data,
+                })
+            })
+            .collect();
+
+        let class = self.results[0].class;
+        // Tags of the same class can have a different size when they end with inline data, only the common part
+        // is compared
+        let inferred_size = siblings.iter().map(|s| s.data.len()).min().unwrap_or(0) & !3;
+        let title = self
+            .results
+            .first()
+            .map(search_results::class_name)
+            .unwrap_or_default();
+        self.sibling_diff
+            .open_for(title, siblings, class, inferred_size);
+    }
+
+    fn start_search(&mut self) {
+        self.generation = self
+            .executor
+            .submit(self.filter.clone(), self.cache.clone());
+        self.query_filter = Some(self.filter.clone());
+        self.cancelled = false;
+        self.results.clear();
+        self.table.needs_sort = true;
+    }
+
+    fn is_running(&self) -> bool {
+        self.query_filter.is_some()
+            && !self.cancelled
+            && !self.executor.is_finished(self.generation)
+    }
+
+    /// Searches for every tag with the given type/subtype combination
+    pub fn search_raw_type(&mut self, file_type: u8, file_subtype: u8) {
+        self.filter = TagFilter {
+            raw_type: Some((file_type, file_subtype)),
+            ..Default::default()
+        };
+        self.start_search();
+    }
+
+    /// Searches with the given filters, eg. from a saved query
+    pub fn search_filter(&mut self, filter: TagFilter) {
+        self.filter = filter;
+        self.start_search();
+    }
+
+    fn saved_queries_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Saved queries", |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.save_name)
+                        .hint_text("Query name")
+                        .desired_width(160.0),
+                );
+                let name = self.save_name.trim();
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                    .on_hover_text(
+                        "Saves the current filters, replacing any query with the same name",
+                    )
+                    .clicked()
+                {
+                    saved_queries::with_mut(|q| q.save(name, self.filter.clone()));
+                    TOASTS.lock().success(format!("Saved query '{name}'"));
+                    self.save_name.clear();
+                }
+            });
+
+            let queries = saved_queries::with(|q| q.queries.clone());
+            if queries.is_empty() {
+                ui.weak("No saved queries");
+                return;
+            }
+
+            ui.separator();
+            for query in queries {
+                ui.horizontal(|ui| {
+                    if ui.button(&query.name).on_hover_text("Load").clicked() {
+                        self.search_filter(query.filter.clone());
+                        ui.close_menu();
+                    }
+
+                    let mut pinned = query.pinned;
+                    if ui
+                        .checkbox(&mut pinned, "📌")
+                        .on_hover_text("Show on the dashboard")
+                        .changed()
+                    {
+                        saved_queries::with_mut(|q| q.set_pinned(&query.name, pinned));
+                    }
+
+                    if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                        saved_queries::with_mut(|q| q.remove(&query.name));
+                    }
+                });
+            }
+        });
+    }
+
+    fn filter_ui(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("tag_search_filters")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Package");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.filter.package)
+                        .hint_text("Part of the package name"),
+                );
+                ui.end_row();
+
+                ui.label("Class");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.filter.class)
+                        .hint_text("Reference hash or class name"),
+                );
+                ui.end_row();
+
+                ui.label("Text");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.filter.text)
+                            .hint_text("Raw strings, names, labels or notes"),
+                    );
+                    if !self.filter.text.trim().is_empty() && !text_index::is_ready() {
+                        ui.weak("Text index is still being built");
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Type");
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("tag_search_type")
+                        .selected_text(match self.filter.tag_type {
+                            Some(t) => RichText::new(t.to_string()).color(t.display_color()),
+                            None => RichText::new("Any"),
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.filter.tag_type, None, "Any");
+                            for t in TagType::all_filterable() {
+                                ui.selectable_value(
+                                    &mut self.filter.tag_type,
+                                    Some(*t),
+                                    RichText::new(t.to_string()).color(t.display_color()),
+                                );
+                            }
+                        });
+
+                    if let Some((file_type, file_subtype)) = self.filter.raw_type {
+                        let tag_type = TagType::from_type_subtype(file_type, file_subtype);
+                        ui.label(
+                            RichText::new(format!("type {file_type}, subtype {file_subtype}"))
+                                .color(tag_type.display_color()),
+                        );
+                        if ui.small_button("✖").on_hover_text("Clear").clicked() {
+                            self.filter.raw_type = None;
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Size");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.filter.min_size)
+                            .speed(16.0)
+                            .suffix(" B"),
+                    );
+                    ui.label("to");
+                    ui.add(
+                        egui::DragValue::new(&mut self.filter.max_size)
+                            .speed(16.0)
+                            .custom_formatter(|v, _| {
+                                if v == 0.0 {
+                                    "∞".to_string()
+                                } else {
+                                    format!("{v} B")
+                                }
+                            }),
+                    )
+                    .on_hover_text("0 means no limit");
+                });
+                ui.end_row();
+
+                ui.label("Entropy");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.filter.min_entropy)
+                            .range(0.0..=8.0)
+                            .speed(0.05)
+                            .max_decimals(2),
+                    );
+                    ui.label("to");
+                    ui.add(
+                        egui::DragValue::new(&mut self.filter.max_entropy)
+                            .range(0.0..=8.0)
+                            .speed(0.05)
+                            .max_decimals(2),
+                    );
+                    ui.label("bits per byte").on_hover_text(
+                        "Compressed or encrypted data is close to 8, mostly empty or repetitive data is close to 0.\nFiltering on entropy reads every matching tag, the results are cached for later searches",
+                    );
+                });
+                ui.end_row();
+            });
+    }
+}
+
+impl View for TagSearchView {
+    fn view(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        self.filter_ui(ui);
+        if self
+            .query_filter
+            .as_ref()
+            .is_some_and(|query| *query != self.filter)
+        {
+            self.start_search();
+        }
+
+        let is_running = self.is_running();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!is_running, egui::Button::new("Search"))
+                .clicked()
+            {
+                self.start_search();
+            }
+
+            if is_running && ui.button("Cancel").clicked() {
+                self.executor.cancel();
+                self.cancelled = true;
+            }
+
+            if ui
+                .add_enabled(
+                    self.random_pick.is_none(),
+                    egui::Button::new("🎲 Random tag"),
+                )
+                .on_hover_text("Opens a random tag matching the filters")
+                .clicked()
+            {
+                let filter = self.filter.clone();
+                let entropy_cache = self.entropy_cache.clone();
+                self.random_pick = Some(Promise::spawn_thread("random_tag", move || {
+                    random_tag(&filter, &entropy_cache)
+                }));
+            }
+
+            if ui.button("Reset filters").clicked() {
+                self.filter = TagFilter::default();
+            }
+
+            self.saved_queries_menu(ui);
+
+            ui.separator();
+            self.table.columns_menu(ui);
+            self.table.group_menu(ui);
+
+            if ui
+                .add_enabled(
+                    !is_running && !self.results.is_empty(),
+                    egui::Button::new("Annotate results..."),
+                )
+                .on_hover_text("Names and/or labels every tag in the results")
+                .clicked()
+            {
+                self.bulk_annotate.open_for(&self.results);
+            }
+
+            let single_class = self.results.iter().map(|r| r.class).all_equal();
+            if ui
+                .add_enabled(
+                    !is_running && self.results.len() > 1 && single_class,
+                    egui::Button::new("Compare results..."),
+                )
+                .on_hover_text(format!(
+                    "Compares the first {MAX_SIBLINGS} results field by field, highlighting the fields that vary"
+                ))
+                .on_disabled_hover_text("Needs at least two results of the same class")
+                .clicked()
+            {
+                self.compare_results();
+            }
+        });
+
+        let compare_action = self.sibling_diff.show(ctx);
+
+        // Restart the search so the results show the new names
+        if self.bulk_annotate.show(ctx) {
+            self.start_search();
+        }
+        ui.separator();
+
+        let mut result = compare_action;
+        if let Some(promise) = self.random_pick.take() {
+            match promise.try_take() {
+                Ok(Some(tag)) => result = Some(ViewAction::OpenTag(tag)),
+                Ok(None) => {
+                    TOASTS.lock().warning("No tags match the filters");
+                }
+                Err(promise) => {
+                    self.random_pick = Some(promise);
+                    ctx.request_repaint();
+                }
+            }
+        }
+
+        if self.query_filter.is_none() {
+            ui.label(RichText::new("No search performed yet").italics());
+            return result;
+        }
+
+        let new_results = self.executor.take_results();
+        if !new_results.is_empty() {
+            self.results.extend(new_results);
+            self.table.needs_sort = true;
+        }
+
+        if is_running {
+            let current = self.executor.state.current.load(Ordering::Relaxed);
+            let total = self.executor.state.total.load(Ordering::Relaxed).max(1);
+            let text = if self.filter.uses_entropy() {
+                format!("Computing entropy {current}/{total}")
+            } else {
+                format!("Searching {current}/{total}")
+            };
+            ui.add(
+                egui::ProgressBar::new(current as f32 / total as f32)
+                    .animate(true)
+                    .text(text),
+            );
+            ctx.request_repaint();
+        }
+
+        // Results arrive in batches from multiple threads, so they're only sorted once the search is done
+        if self.table.needs_sort && !is_running {
+            self.table.sort(&mut self.results);
+        }
+
+        ui.label(format!(
+            "{} tags{}",
+            self.results.len(),
+            if self.cancelled {
+                " (cancelled, results are incomplete)"
+            } else if is_running {
+                " found so far"
+            } else {
+                ""
+            }
+        ));
+
+        if let Some(action) = self.table.show(ui, &self.results) {
+            result = Some(action);
+        }
+
+        result
+    }
+}
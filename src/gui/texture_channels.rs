@@ -0,0 +1,247 @@
+use eframe::egui::{self, pos2, Color32, RichText, Sense, Stroke};
+use eframe::egui_wgpu::RenderState;
+use image::RgbaImage;
+
+use crate::texture::Texture;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ChannelMode {
+    Color,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    /// Tangent space normal, with Z reconstructed from the red and green channels
+    Normal,
+}
+
+impl ChannelMode {
+    fn label(&self) -> &'static str {
+        match self {
+            ChannelMode::Color => "RGBA",
+            ChannelMode::Red => "R",
+            ChannelMode::Green => "G",
+            ChannelMode::Blue => "B",
+            ChannelMode::Alpha => "A",
+            ChannelMode::Normal => "Normal",
+        }
+    }
+}
+
+/// Per-channel value counts of the top mip level
+struct Histogram {
+    bins: [[u32; 256]; 4],
+    min: [u8; 4],
+    max: [u8; 4],
+    mean: [f32; 4],
+}
+
+impl Histogram {
+    fn new(image: &RgbaImage) -> Self {
+        let mut bins = [[0u32; 256]; 4];
+        let mut sum = [0u64; 4];
+        for pixel in image.pixels() {
+            for (c, &v) in pixel.0.iter().enumerate() {
+                bins[c][v as usize] += 1;
+                sum[c] += v as u64;
+            }
+        }
+
+        let pixels = (image.width() as u64 * image.height() as u64).max(1);
+        let first_nonzero = |c: usize| bins[c].iter().position(|&n| n != 0).unwrap_or(0) as u8;
+        let last_nonzero = |c: usize| bins[c].iter().rposition(|&n| n != 0).unwrap_or(0) as u8;
+        Self {
+            min: std::array::from_fn(first_nonzero),
+            max: std::array::from_fn(last_nonzero),
+            mean: std::array::from_fn(|c| sum[c] as f32 / pixels as f32),
+            bins,
+        }
+    }
+}
+
+/// Channel isolation, normal map visualization and histograms for the texture preview in the tag view
+///
+/// Works on a CPU copy of the top mip level with the values as they're stored, which is only captured once
+/// a mode other than the regular color view is used
+pub struct ChannelViewer {
+    mode: ChannelMode,
+    show_histogram: bool,
+    image: Option<Result<RgbaImage, String>>,
+    histogram: Option<Histogram>,
+    /// Texture for the current mode
+    handle: Option<(ChannelMode, egui::TextureHandle)>,
+}
+
+impl ChannelViewer {
+    pub fn new() -> Self {
+        Self {
+            mode: ChannelMode::Color,
+            show_histogram: false,
+            image: None,
+            histogram: None,
+            handle: None,
+        }
+    }
+
+    pub fn toolbar_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Channels");
+            for mode in [
+                ChannelMode::Color,
+                ChannelMode::Red,
+                ChannelMode::Green,
+                ChannelMode::Blue,
+                ChannelMode::Alpha,
+            ] {
+                ui.selectable_value(&mut self.mode, mode, mode.label());
+            }
+            ui.separator();
+            ui.selectable_value(&mut self.mode, ChannelMode::Normal, ChannelMode::Normal.label())
+                .on_hover_text("Visualizes the red and green channels as a tangent space normal map, reconstructing the blue channel (Z)");
+            ui.separator();
+            ui.checkbox(&mut self.show_histogram, "Histogram");
+        });
+
+        if let Some(Err(e)) = &self.image {
+            ui.colored_label(Color32::RED, e);
+        }
+    }
+
+    fn stored_image(&mut self, rs: &RenderState, texture: &Texture) -> Result<&RgbaImage, &str> {
+        self.image
+            .get_or_insert_with(|| {
+                texture
+                    .to_stored_image(rs, 0)
+                    .map_err(|e| format!("Failed to capture texture: {e}"))
+            })
+            .as_ref()
+            .map_err(|e| e.as_str())
+    }
+
+    /// Texture to display instead of the regular texture, `None` when showing the regular colors
+    pub fn texture_id(
+        &mut self,
+        ctx: &egui::Context,
+        rs: &RenderState,
+        texture: &Texture,
+    ) -> Option<egui::TextureId> {
+        let mode = self.mode;
+        if mode == ChannelMode::Color {
+            return None;
+        }
+
+        if let Some((handle_mode, handle)) = &self.handle {
+            if *handle_mode == mode {
+                return Some(handle.id());
+            }
+        }
+
+        let image = self.stored_image(rs, texture).ok()?;
+        let pixels = image
+            .pixels()
+            .map(|p| {
+                let [r, g, b, a] = p.0;
+                match mode {
+                    ChannelMode::Color => Color32::from_rgba_unmultiplied(r, g, b, a),
+                    ChannelMode::Red => Color32::from_gray(r),
+                    ChannelMode::Green => Color32::from_gray(g),
+                    ChannelMode::Blue => Color32::from_gray(b),
+                    ChannelMode::Alpha => Color32::from_gray(a),
+                    ChannelMode::Normal => {
+                        let x = r as f32 / 255.0 * 2.0 - 1.0;
+                        let y = g as f32 / 255.0 * 2.0 - 1.0;
+                        let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+                        let encode = |v: f32| ((v * 0.5 + 0.5) * 255.0).round() as u8;
+                        Color32::from_rgb(encode(x), encode(y), encode(z))
+                    }
+                }
+            })
+            .collect();
+
+        let color_image = egui::ColorImage {
+            size: [image.width() as usize, image.height() as usize],
+            pixels,
+        };
+        let handle = ctx.load_texture(
+            format!("texture_channels_{}", mode.label()),
+            color_image,
+            egui::TextureOptions::LINEAR,
+        );
+        let id = handle.id();
+        self.handle = Some((mode, handle));
+        Some(id)
+    }
+
+    pub fn histogram_ui(&mut self, ui: &mut egui::Ui, rs: &RenderState, texture: &Texture) {
+        if !self.show_histogram {
+            return;
+        }
+
+        if self.histogram.is_none() {
+            let histogram = match self.stored_image(rs, texture) {
+                Ok(image) => Histogram::new(image),
+                Err(e) => {
+                    ui.colored_label(Color32::RED, e);
+                    return;
+                }
+            };
+            self.histogram = Some(histogram);
+        }
+        let Some(histogram) = &self.histogram else {
+            return;
+        };
+
+        let channels = [
+            ("R", Color32::LIGHT_RED),
+            ("G", Color32::LIGHT_GREEN),
+            ("B", Color32::LIGHT_BLUE),
+            ("A", Color32::GRAY),
+        ];
+        // Only draw the isolated channel, if there is one
+        let visible: Vec<usize> = match self.mode {
+            ChannelMode::Red => vec![0],
+            ChannelMode::Green => vec![1],
+            ChannelMode::Blue => vec![2],
+            ChannelMode::Alpha => vec![3],
+            ChannelMode::Normal => vec![0, 1],
+            ChannelMode::Color => vec![0, 1, 2, 3],
+        };
+
+        let (response, painter) = ui.allocate_painter(egui::vec2(512.0, 128.0), Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        let peak = visible
+            .iter()
+            .flat_map(|&c| histogram.bins[c].iter())
+            .copied()
+            .max()
+            .unwrap_or_default()
+            .max(1) as f32;
+        for &c in &visible {
+            let points = histogram.bins[c]
+                .iter()
+                .enumerate()
+                .map(|(i, &n)| {
+                    pos2(
+                        rect.left() + rect.width() * i as f32 / 255.0,
+                        rect.bottom() - rect.height() * n as f32 / peak,
+                    )
+                })
+                .collect();
+            painter.add(egui::Shape::line(points, Stroke::new(1.0, channels[c].1)));
+        }
+
+        for &c in &visible {
+            let (name, color) = channels[c];
+            ui.label(
+                RichText::new(format!(
+                    "{name}: min {} max {} mean {:.1}",
+                    histogram.min[c], histogram.max[c], histogram.mean[c]
+                ))
+                .color(color)
+                .monospace(),
+            );
+        }
+    }
+}
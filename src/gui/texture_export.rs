@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use eframe::egui;
+use eframe::egui_wgpu::RenderState;
+use poll_promise::Promise;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{tag::format_tag_entry, TOASTS};
+use crate::texture::export::{
+    export_textures, ColorSpace, CubemapLayout, TextureExportFormat, TextureExportOptions,
+    TextureExportProgress, TextureExportSummary,
+};
+
+pub struct TextureExportDialog {
+    pub open: bool,
+    render_state: RenderState,
+    tags: Vec<TagHash>,
+    options: TextureExportOptions,
+    out_dir: String,
+    progress: Arc<TextureExportProgress>,
+    job: Option<Promise<TextureExportSummary>>,
+}
+
+impl TextureExportDialog {
+    pub fn new(render_state: RenderState) -> Self {
+        Self {
+            open: false,
+            render_state,
+            tags: vec![],
+            options: Default::default(),
+            out_dir: "textures".to_string(),
+            progress: Default::default(),
+            job: None,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Opens the dialog for the given textures. Ignored while an export is still running
+    pub fn open_for(&mut self, tags: Vec<TagHash>) {
+        if self.is_running() {
+            TOASTS.lock().warning("A texture export is already running");
+            return;
+        }
+
+        self.tags = tags;
+        self.open = true;
+    }
+
+    fn start(&mut self) {
+        self.progress = Default::default();
+        let progress = self.progress.clone();
+        let render_state = self.render_state.clone();
+        let tags = self.tags.clone();
+        let options = self.options;
+        let out_dir = PathBuf::from(self.out_dir.trim());
+        self.job = Some(Promise::spawn_thread("export_textures", move || {
+            export_textures(&render_state, &tags, options, &out_dir, &progress)
+        }));
+    }
+
+    fn poll(&mut self) {
+        let Some(job) = self.job.take() else {
+            return;
+        };
+
+        match job.try_take() {
+            Ok(summary) => {
+                for (tag, e) in &summary.failed {
+                    log::error!("Failed to export texture {tag}: {e}");
+                }
+
+                if summary.failed.is_empty() {
+                    TOASTS.lock().success(format!(
+                        "Exported {} files to {}",
+                        summary.files.len(),
+                        self.out_dir.trim()
+                    ));
+                } else {
+                    TOASTS.lock().warning(format!(
+                        "Exported {} files, {} textures failed (see log)",
+                        summary.files.len(),
+                        summary.failed.len()
+                    ));
+                }
+
+                self.open = false;
+            }
+            Err(job) => self.job = Some(job),
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.poll();
+        if !self.open {
+            return;
+        }
+
+        let is_running = self.is_running();
+        let mut open = self.open;
+        egui::Window::new("Export textures")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let &[tag] = self.tags.as_slice() {
+                    let entry = package_manager().get_entry(tag);
+                    ui.label(format_tag_entry(tag, entry.as_ref()));
+                } else {
+                    ui.label(format!("{} textures", self.tags.len()));
+                }
+                ui.separator();
+
+                ui.add_enabled_ui(!is_running, |ui| {
+                    self.options_ui(ui);
+                });
+                ui.separator();
+
+                if is_running {
+                    let current = self.progress.current.load(Ordering::Relaxed);
+                    let total = self.progress.total.load(Ordering::Relaxed).max(1);
+                    ui.add(
+                        egui::ProgressBar::new(current as f32 / total as f32)
+                            .animate(true)
+                            .text(format!("Exporting {current}/{total}")),
+                    );
+
+                    if ui.button("Cancel").clicked() {
+                        self.progress.cancel.store(true, Ordering::Relaxed);
+                    }
+                    ctx.request_repaint();
+                } else if ui
+                    .add_enabled(!self.tags.is_empty(), egui::Button::new("Export"))
+                    .clicked()
+                {
+                    self.start();
+                }
+            });
+
+        // Keep the dialog around until the export is done, it's the only place that shows the progress
+        self.open = open || is_running;
+    }
+
+    fn options_ui(&mut self, ui: &mut egui::Ui) {
+        let is_dds = self.options.format == TextureExportFormat::Dds;
+        egui::Grid::new("texture_export_options")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Format");
+                ui.horizontal(|ui| {
+                    for format in [
+                        TextureExportFormat::Dds,
+                        TextureExportFormat::Png,
+                        TextureExportFormat::Tga,
+                    ] {
+                        ui.selectable_value(&mut self.options.format, format, format.to_string());
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Mip level");
+                ui.add(egui::DragValue::new(&mut self.options.mip).range(0..=15))
+                    .on_hover_text(if is_dds {
+                        "Fails for textures that don't contain the selected mip level"
+                    } else {
+                        "Smaller mip levels are resampled from the top level"
+                    });
+                ui.end_row();
+
+                ui.label("Cubemap layout");
+                ui.add_enabled_ui(!is_dds, |ui| {
+                    ui.horizontal(|ui| {
+                        for layout in [
+                            CubemapLayout::Cross,
+                            CubemapLayout::Strip,
+                            CubemapLayout::Faces,
+                        ] {
+                            ui.selectable_value(
+                                &mut self.options.cubemap_layout,
+                                layout,
+                                layout.to_string(),
+                            );
+                        }
+                    });
+                });
+                ui.end_row();
+
+                ui.label("Color space");
+                ui.add_enabled_ui(!is_dds, |ui| {
+                    ui.horizontal(|ui| {
+                        for color_space in
+                            [ColorSpace::AsStored, ColorSpace::Srgb, ColorSpace::Linear]
+                        {
+                            ui.selectable_value(
+                                &mut self.options.color_space,
+                                color_space,
+                                color_space.to_string(),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "'As stored' writes sRGB textures as sRGB and everything else (normal maps, masks) unchanged",
+                    );
+                });
+                ui.end_row();
+
+                ui.label("Output directory");
+                ui.text_edit_singleline(&mut self.out_dir);
+                ui.end_row();
+            });
+    }
+}
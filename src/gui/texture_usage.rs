@@ -0,0 +1,181 @@
+use eframe::egui::{self, collapsing_header::CollapsingState, Color32, RichText};
+use itertools::Itertools;
+use quicktag_core::{classes::get_class_by_id, tagtypes::TagType};
+use quicktag_scanner::TagCache;
+use rustc_hash::FxHashSet;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{common::ResponseExt, tag::format_tag_entry};
+
+/// How many levels of references to follow up from the texture (texture <- material <- model <- map)
+const MAX_DEPTH: usize = 4;
+/// Maximum amount of referencing tags shown per node
+const MAX_CHILDREN: usize = 64;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum UsageRole {
+    Material,
+    Model,
+    Map,
+    Other,
+}
+
+impl UsageRole {
+    /// Guesses the role of a tag from the name of its class
+    fn from_tag(tag: TagHash) -> Self {
+        let Some(class) = package_manager()
+            .get_entry(tag)
+            .and_then(|e| get_class_by_id(e.reference))
+        else {
+            return UsageRole::Other;
+        };
+
+        let name = class.name.as_ref();
+        if name.contains("technique") || name.contains("material") || name.contains("texture_tag") {
+            UsageRole::Material
+        } else if name.contains("model")
+            || name.contains("mesh")
+            || name.contains("terrain")
+            || name.contains("decal")
+        {
+            UsageRole::Model
+        } else if name.contains("map")
+            || name.contains("bubble")
+            || name.contains("activity")
+            || name.contains("entity")
+        {
+            UsageRole::Map
+        } else {
+            UsageRole::Other
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            UsageRole::Material => "Material",
+            UsageRole::Model => "Model",
+            UsageRole::Map => "Map/Entity",
+            UsageRole::Other => "Other",
+        }
+    }
+
+    fn color(&self) -> Color32 {
+        match self {
+            UsageRole::Material => Color32::from_rgb(249, 168, 71),
+            UsageRole::Model => Color32::LIGHT_GREEN,
+            UsageRole::Map => Color32::from_rgb(90, 160, 255),
+            UsageRole::Other => Color32::GRAY,
+        }
+    }
+}
+
+pub struct UsageNode {
+    pub tag: TagHash,
+    pub role: UsageRole,
+    pub users: Vec<UsageNode>,
+    /// Amount of referencing tags that were left out because of [`MAX_CHILDREN`]
+    pub truncated: usize,
+}
+
+/// Builds the chain of tags that use the given texture (materials, then the models/maps using those materials)
+pub fn find_texture_usage(cache: &TagCache, texture: TagHash) -> Vec<UsageNode> {
+    let mut visited = FxHashSet::default();
+    visited.insert(texture);
+    let (users, _) = find_users(cache, texture, 0, &mut visited);
+    users
+}
+
+fn find_users(
+    cache: &TagCache,
+    tag: TagHash,
+    depth: usize,
+    visited: &mut FxHashSet<TagHash>,
+) -> (Vec<UsageNode>, usize) {
+    if depth >= MAX_DEPTH {
+        return (vec![], 0);
+    }
+
+    let Some(scan) = cache.hashes.get(&tag) else {
+        return (vec![], 0);
+    };
+
+    let referencers = scan
+        .references
+        .iter()
+        .copied()
+        .unique()
+        .filter(|t| visited.insert(*t))
+        .map(|t| (t, UsageRole::from_tag(t)))
+        // Show the most relevant tags (materials first) before anything else
+        .sorted_by_key(|(t, role)| (*role as u8, t.0))
+        .collect_vec();
+
+    let truncated = referencers.len().saturating_sub(MAX_CHILDREN);
+    let users = referencers
+        .into_iter()
+        .take(MAX_CHILDREN)
+        .map(|(t, role)| {
+            let (users, truncated) = find_users(cache, t, depth + 1, visited);
+            UsageNode {
+                tag: t,
+                role,
+                users,
+                truncated,
+            }
+        })
+        .collect();
+
+    (users, truncated)
+}
+
+pub fn is_texture_header(tag_type: TagType) -> bool {
+    tag_type.is_texture() && tag_type.is_header()
+}
+
+/// Shows a usage chain as a tree. Returns the tag to open, if any was clicked
+pub fn usage_ui(ui: &mut egui::Ui, nodes: &[UsageNode]) -> Option<TagHash> {
+    let mut open_tag = None;
+    for node in nodes {
+        let entry = package_manager().get_entry(node.tag);
+        let label = RichText::new(format!(
+            "[{}] {}",
+            node.role.label(),
+            format_tag_entry(node.tag, entry.as_ref())
+        ))
+        .color(node.role.color());
+
+        let mut header = |ui: &mut egui::Ui| {
+            if ui
+                .selectable_label(false, label.clone())
+                .tag_context(node.tag)
+                .clicked()
+            {
+                open_tag = Some(node.tag);
+            }
+        };
+
+        if node.users.is_empty() {
+            ui.horizontal(|ui| {
+                // Line up with the collapsible nodes
+                ui.add_space(ui.spacing().indent);
+                header(ui);
+            });
+            continue;
+        }
+
+        CollapsingState::load_with_default_open(
+            ui.ctx(),
+            egui::Id::new(("texture_usage_node", node.tag)),
+            node.role == UsageRole::Material,
+        )
+        .show_header(ui, header)
+        .body(|ui| {
+            open_tag = open_tag.or(usage_ui(ui, &node.users));
+            if node.truncated > 0 {
+                ui.weak(format!("...and {} more", node.truncated));
+            }
+        });
+    }
+
+    open_tag
+}
@@ -6,7 +6,10 @@ use tiger_pkg::{manager::PackagePath, package_manager, TagHash};
 use crate::texture::{cache::TextureCache, Texture, TextureDesc};
 use crate::util::ui_image_rotated;
 
-use super::{common::ResponseExt, View, ViewAction};
+use super::{
+    common::{request_texture_export, ResponseExt},
+    View, ViewAction,
+};
 
 const DESC_FILTER_PRESETS: &[(&str, &str)] = &[
     ("(De)buff Icons", "75x75x1 Rgb"),
@@ -230,6 +233,29 @@ impl View for TexturesView {
                 {
                     self.apply_sorting();
                 }
+
+                if ui
+                    .add_enabled(
+                        !self.textures.is_empty(),
+                        egui::Button::new("Export textures..."),
+                    )
+                    .on_hover_text("Exports every texture in the package that matches the filter")
+                    .clicked()
+                {
+                    let filter = self.filter_texdesc.to_lowercase();
+                    request_texture_export(
+                        self.textures
+                            .iter()
+                            .filter(|(_, _, _, desc)| {
+                                filter.is_empty()
+                                    || desc
+                                        .as_ref()
+                                        .is_none_or(|d| d.info().to_lowercase().contains(&filter))
+                            })
+                            .map(|(_, hash, _, _)| *hash)
+                            .collect(),
+                    );
+                }
             });
 
             ui.horizontal(|ui| {
@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+
+use eframe::egui::{self, Color32, RichText};
+use quicktag_core::tagtypes::TagType;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{View, ViewAction};
+use crate::util::format_file_size;
+
+#[derive(Default, Clone, Copy)]
+struct Cell {
+    count: usize,
+    total_size: u64,
+}
+
+/// Tag counts for every file type/subtype combination in the loaded packages
+struct TypeMatrix {
+    cells: FxHashMap<(u8, u8), Cell>,
+    types: BTreeSet<u8>,
+    subtypes: BTreeSet<u8>,
+    max_count: usize,
+}
+
+impl TypeMatrix {
+    fn build() -> Self {
+        let mut cells: FxHashMap<(u8, u8), Cell> = FxHashMap::default();
+        for (pkg_id, entries) in package_manager().lookup.tag32_entries_by_pkg.iter() {
+            for (i, e) in entries.iter().enumerate() {
+                let cell = cells.entry((e.file_type, e.file_subtype)).or_default();
+                cell.count += 1;
+                cell.total_size += package_manager()
+                    .get_entry(TagHash::new(*pkg_id, i as u16))
+                    .map(|e| e.file_size as u64)
+                    .unwrap_or_default();
+            }
+        }
+
+        Self {
+            types: cells.keys().map(|(t, _)| *t).collect(),
+            subtypes: cells.keys().map(|(_, st)| *st).collect(),
+            max_count: cells.values().map(|c| c.count).max().unwrap_or_default(),
+            cells,
+        }
+    }
+}
+
+pub struct TypeMatrixView {
+    matrix: Option<TypeMatrix>,
+}
+
+impl TypeMatrixView {
+    pub fn new() -> Self {
+        Self { matrix: None }
+    }
+}
+
+impl View for TypeMatrixView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        ui.horizontal(|ui| {
+            ui.label("Amount of tags per file type (rows) and subtype (columns). Click a cell to list its tags in the search panel");
+            if ui.button("Refresh").clicked() {
+                self.matrix = None;
+            }
+        });
+        ui.separator();
+
+        let matrix = self.matrix.get_or_insert_with(TypeMatrix::build);
+        let mut result = None;
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                egui::Grid::new("type_matrix")
+                    .striped(true)
+                    .min_col_width(48.0)
+                    .show(ui, |ui| {
+                        ui.strong("type \\ subtype");
+                        for subtype in &matrix.subtypes {
+                            ui.strong(subtype.to_string());
+                        }
+                        ui.end_row();
+
+                        for &file_type in &matrix.types {
+                            ui.strong(file_type.to_string());
+                            for &file_subtype in &matrix.subtypes {
+                                let Some(cell) = matrix.cells.get(&(file_type, file_subtype))
+                                else {
+                                    ui.label("");
+                                    continue;
+                                };
+
+                                let tag_type = TagType::from_type_subtype(file_type, file_subtype);
+                                // Log scale, so rare combinations are still visible next to the millions of regular tags
+                                let intensity = (cell.count as f32).ln_1p()
+                                    / (matrix.max_count as f32).ln_1p().max(1.0);
+                                let color = tag_type.display_color();
+                                let background = Color32::from_rgba_unmultiplied(
+                                    color.r(),
+                                    color.g(),
+                                    color.b(),
+                                    (16.0 + intensity * 96.0) as u8,
+                                );
+
+                                if ui
+                                    .add(egui::Button::new(
+                                        RichText::new(cell.count.to_string()).color(color),
+                                    )
+                                    .fill(background))
+                                    .on_hover_text(format!(
+                                        "{tag_type}\nType {file_type}, subtype {file_subtype}\n{} tags, {} total",
+                                        cell.count,
+                                        format_file_size(cell.total_size as usize)
+                                    ))
+                                    .clicked()
+                                {
+                                    result = Some(ViewAction::SearchRawType {
+                                        file_type,
+                                        file_subtype,
+                                    });
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        result
+    }
+}
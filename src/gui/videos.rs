@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use eframe::egui::{self, RichText};
+use egui_extras::{Column, TableBuilder};
+use itertools::Itertools;
+use log::error;
+use quicktag_scanner::video::{ScannedVideo, VideoContainer};
+use quicktag_scanner::TagCache;
+use tiger_pkg::package_manager;
+
+use super::{common::ResponseExt, View, ViewAction, TOASTS};
+use crate::config;
+
+pub struct VideosView {
+    cache: Arc<TagCache>,
+    container_filter: Option<VideoContainer>,
+    player_command: String,
+}
+
+impl VideosView {
+    pub fn new(cache: Arc<TagCache>) -> Self {
+        Self {
+            cache,
+            container_filter: None,
+            player_command: config::with(|c| c.video_player.clone()).unwrap_or_default(),
+        }
+    }
+
+    fn export_video(video: &ScannedVideo, dir: &Path) -> anyhow::Result<PathBuf> {
+        let data = package_manager().read_tag(video.tag)?;
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.{}", video.tag, video.container.extension()));
+        std::fs::write(&path, data)?;
+        Ok(path)
+    }
+
+    /// Exports the video to a temporary file and opens it with the configured player, or the default application if there is none
+    fn preview_video(video: &ScannedVideo) -> anyhow::Result<()> {
+        let path = Self::export_video(video, &std::env::temp_dir())?;
+        match config::with(|c| c.video_player.clone()) {
+            Some(command) if !command.trim().is_empty() => {
+                let mut parts = command.split_whitespace();
+                let program = parts.next().unwrap();
+                std::process::Command::new(program)
+                    .args(parts)
+                    .arg(&path)
+                    .spawn()?;
+            }
+            _ => {
+                opener::open(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl View for VideosView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let mut result = None;
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Container")
+                .selected_text(
+                    self.container_filter
+                        .map(|c| c.to_string())
+                        .unwrap_or_else(|| "All".to_string()),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.container_filter, None, "All");
+                    for c in [
+                        VideoContainer::Bink1,
+                        VideoContainer::Bink2,
+                        VideoContainer::CriwareUsm,
+                    ] {
+                        ui.selectable_value(&mut self.container_filter, Some(c), c.to_string());
+                    }
+                });
+
+            ui.separator();
+            ui.label("Player command:");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.player_command)
+                    .hint_text("Default application")
+                    .desired_width(192.0),
+            );
+            if response.lost_focus() {
+                let command = self.player_command.trim().to_string();
+                config::with_mut(|c| {
+                    c.video_player = (!command.is_empty()).then_some(command);
+                });
+            }
+        });
+        ui.separator();
+
+        let videos = self
+            .cache
+            .videos
+            .iter()
+            .filter(|v| self.container_filter.map_or(true, |c| v.container == c))
+            .collect_vec();
+
+        if videos.is_empty() {
+            ui.label(RichText::new("No video streams found").italics());
+            return None;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} videos", videos.len()));
+            if ui
+                .button("Export all")
+                .on_hover_text("Exports the raw containers to the videos directory")
+                .clicked()
+            {
+                let mut exported = 0;
+                for v in &videos {
+                    match Self::export_video(v, Path::new("videos")) {
+                        Ok(_) => exported += 1,
+                        Err(e) => error!("Failed to export video {}: {e:?}", v.tag),
+                    }
+                }
+                TOASTS
+                    .lock()
+                    .success(format!("Exported {exported} videos to videos/"));
+            }
+        });
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto().at_least(128.0))
+            .column(Column::auto().at_least(96.0))
+            .column(Column::auto().at_least(96.0))
+            .column(Column::remainder())
+            .min_scrolled_height(0.0)
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Tag");
+                });
+                header.col(|ui| {
+                    ui.strong("Container");
+                });
+                header.col(|ui| {
+                    ui.strong("Size");
+                });
+                header.col(|ui| {
+                    ui.strong("Actions");
+                });
+            })
+            .body(|body| {
+                body.rows(22.0, videos.len(), |mut row| {
+                    let video = videos[row.index()];
+                    row.col(|ui| {
+                        if ui
+                            .selectable_label(false, video.tag.to_string())
+                            .tag_context(video.tag)
+                            .clicked()
+                        {
+                            result = Some(ViewAction::OpenTag(video.tag));
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(video.container.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2} MiB", video.size as f64 / (1024.0 * 1024.0)));
+                    });
+                    row.col(|ui| {
+                        if ui.small_button("▶ Preview").clicked() {
+                            if let Err(e) = Self::preview_video(video) {
+                                error!("Failed to preview video {}: {e:?}", video.tag);
+                                TOASTS.lock().error("Failed to preview video");
+                            }
+                        }
+
+                        if ui.small_button("Export").clicked() {
+                            match Self::export_video(video, Path::new("videos")) {
+                                Ok(path) => {
+                                    TOASTS
+                                        .lock()
+                                        .success(format!("Video exported to {}", path.display()));
+                                }
+                                Err(e) => {
+                                    error!("Failed to export video {}: {e:?}", video.tag);
+                                    TOASTS.lock().error("Failed to export video");
+                                }
+                            }
+                        }
+                    });
+                });
+            });
+
+        result
+    }
+}
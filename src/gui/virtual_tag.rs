@@ -0,0 +1,173 @@
+//! Virtual tags: data that doesn't exist as a tag in the packages, like a decompressed blob or a range of a tag opened
+//! as a sub-view. They're opened in their own window with a hex view, and scanned for tag references like an external
+//! file
+//!
+//! Ranges of a tag are addressed as `hash!offset..len`, with the offset and length in hex (eg. `ABCD8080!120..40`).
+//! Sub-views of sub-views are addressed relative to the tag, so the address always points into the tag data.
+
+use std::fmt::Display;
+use std::ops::Range;
+use std::sync::Arc;
+
+use eframe::egui;
+use quicktag_scanner::{context::ScannerContext, ScannerMode};
+use quicktag_strings::localized::StringCache;
+use tiger_pkg::TagHash;
+
+use crate::crash_context;
+use crate::gui::hexview::TagHexView;
+use crate::gui::tag::ExtendedScanResult;
+use crate::gui::{ViewAction, TOASTS};
+use crate::tag_data;
+
+/// A byte range of a tag
+#[derive(Clone, PartialEq, Debug)]
+pub struct VirtualAddress {
+    pub tag: TagHash,
+    pub range: Range<usize>,
+}
+
+impl VirtualAddress {
+    /// Parses `hash!offset..len`, the offset and length can be prefixed with `0x`
+    pub fn parse(input: &str) -> Option<Self> {
+        let parse_hex = |s: &str| {
+            let s = s.trim();
+            usize::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+        };
+
+        let (tag, range) = input.trim().split_once('!')?;
+        let (offset, len) = range.split_once("..")?;
+        let tag = TagHash(u32::from_str_radix(tag.trim(), 16).ok()?);
+        let (offset, len) = (parse_hex(offset)?, parse_hex(len)?);
+        (len > 0).then_some(Self {
+            tag,
+            range: offset..offset + len,
+        })
+    }
+
+    /// Address of `range` within the data at this address
+    pub fn sub_range(&self, range: Range<usize>) -> Self {
+        Self {
+            tag: self.tag,
+            range: self.range.start + range.start..self.range.start + range.end,
+        }
+    }
+
+    /// Reads the tag and cuts out the range
+    pub fn read(&self) -> anyhow::Result<Vec<u8>> {
+        let data = tag_data::read_tag(self.tag)?;
+        anyhow::ensure!(
+            self.range.end <= data.len(),
+            "{self} is past the end of the tag (0x{:X} bytes)",
+            data.len()
+        );
+        Ok(data[self.range.clone()].to_vec())
+    }
+}
+
+impl Display for VirtualAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:08X}!{:X}..{:X}",
+            self.tag.0,
+            self.range.start,
+            self.range.len()
+        )
+    }
+}
+
+pub struct VirtualTag {
+    /// Where the data came from, eg. `ABCD8080 zlib @ 0x120` or the address of a sub-view
+    pub label: String,
+    /// Set when the data is a range of a tag, rather than derived from one
+    pub address: Option<VirtualAddress>,
+    pub data: Vec<u8>,
+}
+
+impl VirtualTag {
+    pub fn open(address: VirtualAddress) -> anyhow::Result<Self> {
+        Ok(Self {
+            label: address.to_string(),
+            data: address.read()?,
+            address: Some(address),
+        })
+    }
+}
+
+pub struct VirtualTagWindow {
+    pub open: bool,
+    label: String,
+    address: Option<VirtualAddress>,
+    size: usize,
+    scan: ExtendedScanResult,
+    hexview: TagHexView,
+}
+
+impl VirtualTagWindow {
+    pub fn new(
+        tag: VirtualTag,
+        scanner_context: &ScannerContext,
+        strings: Arc<StringCache>,
+    ) -> Self {
+        let _context = crash_context::push(crash_context::JOB, format!("Scanning {}", tag.label));
+        let scan = quicktag_scanner::scan_file(scanner_context, &tag.data, ScannerMode::Tags);
+
+        let mut hexview = TagHexView::new(TagHash::NONE, tag.data, strings);
+        hexview.set_virtual(tag.label.clone(), tag.address.clone());
+        Self {
+            open: true,
+            size: hexview.data_len(),
+            scan: ExtendedScanResult::from_scanresult(scan),
+            hexview,
+            label: tag.label,
+            address: tag.address,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<ViewAction> {
+        let mut action = None;
+        let mut open = self.open;
+        egui::Window::new(&self.label)
+            .id(egui::Id::new("virtual_tag").with(&self.label))
+            .open(&mut open)
+            .default_size([720.0, 560.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.weak(format!(
+                        "{} bytes, {} tag references",
+                        self.size,
+                        self.scan.file_hashes.len()
+                    ));
+                    if let Some(address) = &self.address {
+                        if ui.button(format!("Open {}", address.tag)).clicked() {
+                            action = Some(ViewAction::OpenTagAtOffset(
+                                address.tag,
+                                address.range.start as u64,
+                            ));
+                        }
+                        if ui
+                            .button("Copy address")
+                            .on_hover_text("Paste it in the tag box to open this view again")
+                            .clicked()
+                        {
+                            ui.output_mut(|o| o.copied_text = address.to_string());
+                            TOASTS.lock().success("Address copied to clipboard");
+                        }
+                    }
+                });
+                ui.separator();
+
+                if let Some(tag) = self.hexview.show(ui, &self.scan) {
+                    action = Some(ViewAction::OpenTag(tag));
+                }
+            });
+        self.open = open;
+
+        if let Some(tag) = self.hexview.take_virtual_tag() {
+            action = Some(ViewAction::OpenVirtualTag(tag));
+        }
+
+        action
+    }
+}
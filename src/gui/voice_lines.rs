@@ -0,0 +1,258 @@
+use std::sync::Arc;
+
+use eframe::egui::{self, RichText};
+use egui_extras::{Column, TableBuilder};
+use itertools::Itertools;
+use poll_promise::Promise;
+use quicktag_core::tagtypes::TagType;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::StringCache;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{
+    common::{open_audio_file_in_default_application, ResponseExt},
+    tag::format_tag_entry,
+    View, ViewAction,
+};
+
+/// Maximum distance (in bytes) between a string hash and an audio reference for them to be considered a pair
+const MAX_PAIR_DISTANCE: u64 = 0x40;
+
+#[derive(Clone)]
+pub struct VoiceLine {
+    /// The tag containing both the string hash and the audio reference (usually a dialogue table)
+    pub table: TagHash,
+    pub string_offset: u64,
+    pub string_hash: u32,
+    pub audio_offset: u64,
+    /// Wwise streams reachable from the audio reference
+    pub streams: Vec<TagHash>,
+}
+
+/// Resolves the wwise streams a tag points to, either directly or through a single intermediate tag (eg. a wwise event)
+fn resolve_streams(
+    cache: &TagCache,
+    tag: TagHash,
+    memo: &mut FxHashMap<TagHash, Vec<TagHash>>,
+) -> Vec<TagHash> {
+    if let Some(streams) = memo.get(&tag) {
+        return streams.clone();
+    }
+
+    let is_stream = |t: TagHash| {
+        package_manager()
+            .get_entry(t)
+            .map(|e| TagType::from_type_subtype(e.file_type, e.file_subtype))
+            == Some(TagType::WwiseStream)
+    };
+
+    let streams = if is_stream(tag) {
+        vec![tag]
+    } else if let Some(scan) = cache.hashes.get(&tag) {
+        scan.file_hashes
+            .iter()
+            .map(|h| h.hash)
+            .filter(|&t| is_stream(t))
+            .unique()
+            .collect()
+    } else {
+        vec![]
+    };
+
+    memo.insert(tag, streams.clone());
+    streams
+}
+
+/// Pairs localized string hashes with audio streams referenced close to them in the same tag
+pub fn find_voice_lines(cache: &TagCache, strings: &StringCache) -> Vec<VoiceLine> {
+    let mut memo = FxHashMap::default();
+    let mut lines = vec![];
+
+    for (&table, scan) in cache.hashes.iter() {
+        let localized: Vec<_> = scan
+            .string_hashes
+            .iter()
+            .filter(|s| strings.contains_key(&s.hash))
+            .collect();
+        if localized.is_empty() || scan.file_hashes.is_empty() {
+            continue;
+        }
+
+        let audio_refs: Vec<(u64, Vec<TagHash>)> = scan
+            .file_hashes
+            .iter()
+            .filter(|h| h.offset != u64::MAX)
+            .map(|h| (h.offset, resolve_streams(cache, h.hash, &mut memo)))
+            .filter(|(_, streams)| !streams.is_empty())
+            .collect();
+
+        for s in localized {
+            let closest = audio_refs
+                .iter()
+                .min_by_key(|(offset, _)| offset.abs_diff(s.offset));
+
+            if let Some((audio_offset, streams)) = closest {
+                if audio_offset.abs_diff(s.offset) <= MAX_PAIR_DISTANCE {
+                    lines.push(VoiceLine {
+                        table,
+                        string_offset: s.offset,
+                        string_hash: s.hash,
+                        audio_offset: *audio_offset,
+                        streams: streams.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    lines.sort_by_key(|l| (l.table.0, l.string_offset));
+    lines
+}
+
+pub struct VoiceLinesView {
+    cache: Arc<TagCache>,
+    strings: Arc<StringCache>,
+    lines: Option<Promise<Vec<VoiceLine>>>,
+    filter: String,
+}
+
+impl VoiceLinesView {
+    pub fn new(cache: Arc<TagCache>, strings: Arc<StringCache>) -> Self {
+        Self {
+            cache,
+            strings,
+            lines: None,
+            filter: String::new(),
+        }
+    }
+
+    fn string_text(&self, hash: u32) -> String {
+        self.strings
+            .get(&hash)
+            .and_then(|s| s.first().cloned())
+            .unwrap_or_else(|| format!("{hash:08X}"))
+    }
+}
+
+impl View for VoiceLinesView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let mut result = None;
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.lines.as_ref().map(|p| p.ready().is_some()).unwrap_or(true),
+                    egui::Button::new("Find voice lines"),
+                )
+                .on_hover_text(
+                    "Pairs localized strings with audio streams referenced next to them (eg. in dialogue tables)",
+                )
+                .clicked()
+            {
+                let cache = self.cache.clone();
+                let strings = self.strings.clone();
+                self.lines = Some(Promise::spawn_thread("find_voice_lines", move || {
+                    find_voice_lines(&cache, &strings)
+                }));
+            }
+
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        ui.separator();
+
+        let Some(lines) = self.lines.as_ref() else {
+            ui.label(RichText::new("Voice lines have not been analyzed yet").italics());
+            return None;
+        };
+
+        let Some(lines) = lines.ready() else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Pairing strings with audio");
+            });
+            return None;
+        };
+
+        let filter = self.filter.to_lowercase();
+        let filtered = lines
+            .iter()
+            .filter(|l| {
+                filter.is_empty()
+                    || self
+                        .string_text(l.string_hash)
+                        .to_lowercase()
+                        .contains(&filter)
+            })
+            .collect_vec();
+
+        ui.label(format!("{} voice lines", filtered.len()));
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::remainder().at_least(256.0).clip(true))
+            .column(Column::auto().at_least(160.0))
+            .column(Column::auto().at_least(128.0))
+            .min_scrolled_height(0.0)
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Text");
+                });
+                header.col(|ui| {
+                    ui.strong("Audio");
+                });
+                header.col(|ui| {
+                    ui.strong("Source");
+                });
+            })
+            .body(|body| {
+                body.rows(22.0, filtered.len(), |mut row| {
+                    let line = filtered[row.index()];
+                    row.col(|ui| {
+                        let text = self.string_text(line.string_hash);
+                        ui.selectable_label(false, &text)
+                            .on_hover_text(format!(
+                                "{:08X} @ 0x{:X}",
+                                line.string_hash, line.string_offset
+                            ))
+                            .context_menu(|ui| {
+                                if ui.selectable_label(false, "Copy text").clicked() {
+                                    ui.output_mut(|o| o.copied_text = text.clone());
+                                    ui.close_menu();
+                                }
+                            });
+                    });
+                    row.col(|ui| {
+                        for &stream in &line.streams {
+                            if ui.small_button("▶").clicked() {
+                                open_audio_file_in_default_application(stream, "wem");
+                            }
+                            if ui
+                                .selectable_label(false, stream.to_string())
+                                .tag_context(stream)
+                                .clicked()
+                            {
+                                result = Some(ViewAction::OpenTag(stream));
+                            }
+                        }
+                    });
+                    row.col(|ui| {
+                        let entry = package_manager().get_entry(line.table);
+                        if ui
+                            .selectable_label(
+                                false,
+                                format!("{} @ 0x{:X}", line.table, line.audio_offset),
+                            )
+                            .on_hover_text(format_tag_entry(line.table, entry.as_ref()))
+                            .clicked()
+                        {
+                            result = Some(ViewAction::OpenTag(line.table));
+                        }
+                    });
+                });
+            });
+
+        result
+    }
+}
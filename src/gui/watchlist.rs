@@ -0,0 +1,416 @@
+use std::sync::Arc;
+
+use eframe::egui::{self, collapsing_header::CollapsingState, Color32, RichText};
+use itertools::Itertools;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::StringCache;
+use tiger_pkg::{package_manager, TagHash};
+
+use super::{common::ResponseExt, tag::format_tag_entry, View, ViewAction, TOASTS};
+use crate::watchlist::{self, StringChange, StringWatchReport, TagWatchReport};
+
+pub struct WatchListView {
+    strings: Arc<StringCache>,
+    /// Changes found during the last check, kept until dismissed
+    string_reports: Vec<StringWatchReport>,
+
+    cache: Arc<TagCache>,
+    tag_reports: Vec<TagWatchReport>,
+
+    container_input: String,
+    string_input: String,
+    tag_input: String,
+}
+
+impl WatchListView {
+    pub fn new(strings: Arc<StringCache>) -> Self {
+        Self {
+            strings,
+            string_reports: vec![],
+            cache: Default::default(),
+            tag_reports: vec![],
+            container_input: String::new(),
+            string_input: String::new(),
+            tag_input: String::new(),
+        }
+    }
+
+    /// Replaces the strings that watched strings are compared against, eg. after the packages were reloaded
+    pub fn set_strings(&mut self, strings: Arc<StringCache>) {
+        self.strings = strings;
+    }
+
+    /// Compares watched strings against the current strings. Returns the amount of changed containers/strings
+    pub fn check_strings(&mut self) -> usize {
+        let reports = watchlist::with_mut(|w| w.check_strings(&self.strings));
+        let changed = reports.len();
+        self.string_reports.extend(reports);
+        changed
+    }
+
+    /// Compares watched tags against the (re)loaded cache. Returns the amount of changed tags
+    pub fn check_tags(&mut self, cache: Arc<TagCache>) -> usize {
+        self.cache = cache;
+        let reports = watchlist::with_mut(|w| w.check_tags(&self.cache));
+        let changed = reports.len();
+        self.tag_reports.extend(reports);
+        changed
+    }
+
+    pub fn watch_tag(&self, tag: TagHash) {
+        watchlist::with_mut(|w| w.watch_tag(tag, &self.cache));
+        TOASTS.lock().success(format!("Watching tag {tag}"));
+    }
+
+    pub fn watch_container(&self, tag: TagHash) {
+        watchlist::with_mut(|w| w.watch_container(tag, &self.strings));
+        TOASTS
+            .lock()
+            .success(format!("Watching string container {tag}"));
+    }
+
+    fn string_reports_ui(&mut self, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let mut result = None;
+        ui.horizontal(|ui| {
+            ui.heading("Changed strings");
+            if !self.string_reports.is_empty() && ui.button("Dismiss").clicked() {
+                self.string_reports.clear();
+            }
+        });
+
+        if self.string_reports.is_empty() {
+            ui.label(RichText::new("No watched strings have changed").italics());
+            return None;
+        }
+
+        for (i, report) in self.string_reports.iter().enumerate() {
+            CollapsingState::load_with_default_open(
+                ui.ctx(),
+                egui::Id::new(("string_watch_report", i)),
+                true,
+            )
+            .show_header(ui, |ui| {
+                let response = ui.selectable_label(
+                    false,
+                    format!("{} ({} changes)", report.label, report.changes.len()),
+                );
+                if let Some(tag) = report.container {
+                    if response.tag_context(tag).clicked() {
+                        result = Some(ViewAction::OpenTag(tag));
+                    }
+                }
+            })
+            .body(|ui| {
+                for change in &report.changes {
+                    match change {
+                        StringChange::Added { hash, new } => {
+                            ui.label(
+                                RichText::new(format!("+ {hash:08X}: {new}")).color(Color32::GREEN),
+                            );
+                        }
+                        StringChange::Removed { hash, old } => {
+                            ui.label(
+                                RichText::new(format!("- {hash:08X}: {old}"))
+                                    .color(Color32::LIGHT_RED),
+                            );
+                        }
+                        StringChange::Modified { hash, old, new } => {
+                            ui.label(
+                                RichText::new(format!("~ {hash:08X}: {old}"))
+                                    .color(Color32::LIGHT_RED),
+                            );
+                            ui.label(
+                                RichText::new(format!("  {hash:08X}: {new}")).color(Color32::GREEN),
+                            );
+                        }
+                    }
+                }
+            });
+        }
+
+        result
+    }
+
+    fn tag_reports_ui(&mut self, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let mut result = None;
+        ui.horizontal(|ui| {
+            ui.heading("Changed tags");
+            if !self.tag_reports.is_empty() && ui.button("Dismiss").clicked() {
+                self.tag_reports.clear();
+            }
+        });
+
+        if self.tag_reports.is_empty() {
+            ui.label(RichText::new("No watched tags have changed").italics());
+            return None;
+        }
+
+        for report in &self.tag_reports {
+            CollapsingState::load_with_default_open(
+                ui.ctx(),
+                egui::Id::new(("tag_watch_report", report.tag)),
+                true,
+            )
+            .show_header(ui, |ui| {
+                let entry = package_manager().get_entry(report.tag);
+                if ui
+                    .selectable_label(false, format_tag_entry(report.tag, entry.as_ref()))
+                    .tag_context(report.tag)
+                    .clicked()
+                {
+                    result = Some(ViewAction::OpenTag(report.tag));
+                }
+            })
+            .body(|ui| {
+                if report.content_changed {
+                    ui.label(RichText::new("Content changed").color(Color32::YELLOW));
+                } else {
+                    ui.label("Content unchanged");
+                }
+
+                let delta = report.size_delta();
+                ui.label(format!(
+                    "Size: {} → {} bytes ({delta:+})",
+                    report.old_size, report.new_size
+                ));
+
+                for (label, tags, color) in [
+                    ("+ References", &report.added_references, Color32::GREEN),
+                    (
+                        "- References",
+                        &report.removed_references,
+                        Color32::LIGHT_RED,
+                    ),
+                    (
+                        "+ Referenced by",
+                        &report.added_referenced_by,
+                        Color32::GREEN,
+                    ),
+                    (
+                        "- Referenced by",
+                        &report.removed_referenced_by,
+                        Color32::LIGHT_RED,
+                    ),
+                ] {
+                    for &tag in tags {
+                        let entry = package_manager().get_entry(tag);
+                        if ui
+                            .selectable_label(
+                                false,
+                                RichText::new(format!(
+                                    "{label} {}",
+                                    format_tag_entry(tag, entry.as_ref())
+                                ))
+                                .color(color),
+                            )
+                            .tag_context(tag)
+                            .clicked()
+                        {
+                            result = Some(ViewAction::OpenTag(tag));
+                        }
+                    }
+                }
+            });
+        }
+
+        result
+    }
+
+    fn watched_tags_ui(&mut self, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let mut result = None;
+        ui.heading("Watched tags");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.tag_input)
+                    .hint_text("Tag hash")
+                    .desired_width(96.0),
+            );
+
+            let tag = u32::from_str_radix(self.tag_input.trim(), 16)
+                .ok()
+                .map(TagHash)
+                .filter(|t| package_manager().get_entry(*t).is_some());
+            if ui
+                .add_enabled(tag.is_some(), egui::Button::new("Watch"))
+                .on_disabled_hover_text("Tag does not exist")
+                .clicked()
+            {
+                if let Some(tag) = tag {
+                    self.watch_tag(tag);
+                    self.tag_input.clear();
+                }
+            }
+        });
+
+        let tags = watchlist::with(|w| w.tags.clone());
+        for watched in tags {
+            let tag = TagHash(watched.tag);
+            ui.horizontal(|ui| {
+                if ui
+                    .small_button("🗑")
+                    .on_hover_text("Stop watching")
+                    .clicked()
+                {
+                    watchlist::with_mut(|w| w.tags.retain(|t| t.tag != tag.0));
+                }
+
+                let entry = package_manager().get_entry(tag);
+                if ui
+                    .selectable_label(
+                        false,
+                        format!(
+                            "{} ({} bytes, {} references, {} referenced by)",
+                            format_tag_entry(tag, entry.as_ref()),
+                            watched.size,
+                            watched.references.len(),
+                            watched.referenced_by.len()
+                        ),
+                    )
+                    .tag_context(tag)
+                    .clicked()
+                {
+                    result = Some(ViewAction::OpenTag(tag));
+                }
+            });
+        }
+
+        result
+    }
+
+    fn watched_strings_ui(&mut self, ui: &mut egui::Ui) -> Option<ViewAction> {
+        let mut result = None;
+        ui.heading("Watched string containers");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.container_input)
+                    .hint_text("Container tag")
+                    .desired_width(96.0),
+            );
+
+            let tag = u32::from_str_radix(self.container_input.trim(), 16)
+                .ok()
+                .map(TagHash)
+                .filter(|t| {
+                    package_manager()
+                        .get_entry(*t)
+                        .is_some_and(|e| watchlist::is_string_container(e.reference))
+                });
+            if ui
+                .add_enabled(tag.is_some(), egui::Button::new("Watch"))
+                .on_disabled_hover_text("Not a string container")
+                .clicked()
+            {
+                if let Some(tag) = tag {
+                    self.watch_container(tag);
+                    self.container_input.clear();
+                }
+            }
+        });
+
+        let containers = watchlist::with(|w| {
+            w.string_containers
+                .iter()
+                .map(|c| (TagHash(c.tag), c.snapshot.len()))
+                .collect_vec()
+        });
+        for (tag, string_count) in containers {
+            ui.horizontal(|ui| {
+                if ui
+                    .small_button("🗑")
+                    .on_hover_text("Stop watching")
+                    .clicked()
+                {
+                    watchlist::with_mut(|w| w.string_containers.retain(|c| c.tag != tag.0));
+                }
+
+                let entry = package_manager().get_entry(tag);
+                if ui
+                    .selectable_label(
+                        false,
+                        format!(
+                            "{} ({} strings)",
+                            format_tag_entry(tag, entry.as_ref()),
+                            string_count
+                        ),
+                    )
+                    .tag_context(tag)
+                    .clicked()
+                {
+                    result = Some(ViewAction::OpenTag(tag));
+                }
+            });
+        }
+
+        ui.add_space(8.0);
+        ui.heading("Watched strings");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.string_input)
+                    .hint_text("String hash")
+                    .desired_width(96.0),
+            );
+
+            let hash = u32::from_str_radix(self.string_input.trim(), 16).ok();
+            if ui
+                .add_enabled(hash.is_some(), egui::Button::new("Watch"))
+                .clicked()
+            {
+                if let Some(hash) = hash {
+                    watchlist::with_mut(|w| w.watch_string(hash, &self.strings));
+                    self.string_input.clear();
+                }
+            }
+        });
+
+        let strings = watchlist::with(|w| w.strings.clone());
+        for watched in strings {
+            ui.horizontal(|ui| {
+                if ui
+                    .small_button("🗑")
+                    .on_hover_text("Stop watching")
+                    .clicked()
+                {
+                    watchlist::with_mut(|w| w.strings.retain(|s| s.hash != watched.hash));
+                }
+
+                ui.label(format!(
+                    "{:08X}: {}",
+                    watched.hash,
+                    if watched.snapshot.is_empty() {
+                        "<not found>".to_string()
+                    } else {
+                        watched.snapshot.join(" | ")
+                    }
+                ));
+            });
+        }
+
+        result
+    }
+}
+
+impl View for WatchListView {
+    fn view(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) -> Option<ViewAction> {
+        ui.weak("Watched tags and strings are compared against their last known contents every time the cache is loaded");
+        ui.separator();
+
+        let mut result = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                let tag_report_action = self.tag_reports_ui(ui);
+                ui.separator();
+                let string_report_action = self.string_reports_ui(ui);
+                ui.separator();
+                let watched_tags_action = self.watched_tags_ui(ui);
+                ui.add_space(8.0);
+                let watched_strings_action = self.watched_strings_ui(ui);
+                result = tag_report_action
+                    .or(string_report_action)
+                    .or(watched_tags_action)
+                    .or(watched_strings_action);
+            });
+
+        result
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use anyhow::Context;
+use log::info;
+use quicktag_scanner::{
+    context::ScannerContext, hashes::reannotate_string_hashes, save_tag_cache, TagCache,
+};
+use quicktag_strings::{
+    cracked::{append_cracked_hashes, insert_string, parse_cracked_hashes},
+    localized::StringCache,
+};
+use tiger_pkg::package_manager;
+
+use crate::crash_context;
+
+pub struct HashImport {
+    pub strings: StringCache,
+    pub context: ScannerContext,
+    pub cache: TagCache,
+    /// Amount of strings that weren't known yet
+    pub imported: usize,
+    /// Amount of new string hash matches in existing scan results
+    pub new_matches: usize,
+}
+
+/// Imports a `hash=string` list into the string cache, then adds matches for the new hashes to the existing scan results
+///
+/// Returns None if the list doesn't contain any strings that weren't known yet
+pub fn import_cracked_hashes(
+    path: &Path,
+    strings: &StringCache,
+    cache: &TagCache,
+) -> anyhow::Result<Option<HashImport>> {
+    let _context = crash_context::push(crash_context::JOB, format!("Importing {}", path.display()));
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut strings = strings.clone();
+    let new_hashes: Vec<(u32, String)> = parse_cracked_hashes(&data)
+        .into_iter()
+        .filter(|(hash, string)| insert_string(&mut strings, *hash, string))
+        .collect();
+
+    if new_hashes.is_empty() {
+        return Ok(None);
+    }
+
+    append_cracked_hashes(&new_hashes).context("Failed to save cracked hashes")?;
+    info!(
+        "Imported {} cracked hashes from {}",
+        new_hashes.len(),
+        path.display()
+    );
+
+    // The scanner context reads the string cache again, which now includes the appended hashes
+    let context = ScannerContext::create(&package_manager())?;
+    let mut cache = cache.clone();
+    let hashes: Vec<u32> = new_hashes.iter().map(|(hash, _)| *hash).collect();
+    let new_matches = reannotate_string_hashes(&context, &mut cache, &hashes);
+    if new_matches > 0 {
+        save_tag_cache(&cache);
+    }
+
+    Ok(Some(HashImport {
+        strings,
+        context,
+        cache,
+        imported: new_hashes.len(),
+        new_matches,
+    }))
+}
@@ -0,0 +1,137 @@
+//! Translations of the quicktag UI
+//!
+//! Translations are TOML files in the `locales` directory (in the data directory, see [`crate::config::data_path`]),
+//! named after the language (eg. `locales/de.toml`). Every line maps the English text to its translation:
+//!
+//! ```toml
+//! "Settings" = "Einstellungen"
+//! "{count} watched tags changed" = "{count} beobachtete Tags wurden geändert"
+//! ```
+//!
+//! The English text is the key, so untranslated (missing or empty) strings fall back to English. Placeholders in
+//! braces are filled in after translating, see [`tr_args`]. [`export_template`] writes every string that was shown so
+//! far to a file, as a starting point for a new translation.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use log::{error, info};
+use parking_lot::RwLock;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::config;
+
+/// Language of the strings in the source code
+pub const SOURCE_LANGUAGE: &str = "en";
+
+pub const LOCALES_DIR: &str = "locales";
+
+lazy_static! {
+    static ref LANGUAGE: RwLock<String> = RwLock::new(SOURCE_LANGUAGE.to_string());
+    static ref TRANSLATIONS: RwLock<FxHashMap<String, String>> = RwLock::new(FxHashMap::default());
+    /// Strings that were looked up, for [`export_template`]
+    static ref SEEN: RwLock<FxHashSet<String>> = RwLock::new(FxHashSet::default());
+}
+
+fn locale_path(language: &str) -> PathBuf {
+    config::data_path(LOCALES_DIR).join(format!("{language}.toml"))
+}
+
+fn read_locale(path: &Path) -> anyhow::Result<FxHashMap<String, String>> {
+    let translations: FxHashMap<String, String> = toml::from_str(&std::fs::read_to_string(path)?)?;
+    Ok(translations
+        .into_iter()
+        .filter(|(_, translation)| !translation.is_empty())
+        .collect())
+}
+
+/// Switches the UI to another language. Does nothing if the language is already in use
+pub fn set_language(language: &str) {
+    if *LANGUAGE.read() == language {
+        return;
+    }
+
+    let translations = if language == SOURCE_LANGUAGE {
+        FxHashMap::default()
+    } else {
+        let path = locale_path(language);
+        match read_locale(&path) {
+            Ok(t) => {
+                info!("Loaded {} UI translations from {}", t.len(), path.display());
+                t
+            }
+            Err(e) => {
+                error!(
+                    "Failed to load UI translations from {}: {e:?}",
+                    path.display()
+                );
+                FxHashMap::default()
+            }
+        }
+    };
+
+    *TRANSLATIONS.write() = translations;
+    *LANGUAGE.write() = language.to_string();
+}
+
+/// The source language and every language that has a file in the locales directory
+pub fn available_languages() -> Vec<String> {
+    let mut languages = vec![SOURCE_LANGUAGE.to_string()];
+    if let Ok(dir) = std::fs::read_dir(config::data_path(LOCALES_DIR)) {
+        languages.extend(dir.filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "toml" {
+                return None;
+            }
+
+            Some(path.file_stem()?.to_string_lossy().to_string())
+        }));
+    }
+
+    languages.sort();
+    languages.dedup();
+    languages
+}
+
+/// Translates a UI string
+pub fn tr(text: &str) -> String {
+    if !SEEN.read().contains(text) {
+        SEEN.write().insert(text.to_string());
+    }
+
+    TRANSLATIONS
+        .read()
+        .get(text)
+        .cloned()
+        .unwrap_or_else(|| text.to_string())
+}
+
+/// Translates a UI string, then replaces the `{name}` placeholders in it with the given values
+pub fn tr_args(text: &str, args: &[(&str, &dyn ToString)]) -> String {
+    let mut translated = tr(text);
+    for (name, value) in args {
+        translated = translated.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+
+    translated
+}
+
+/// Writes every string that was shown so far, with the translations of the current language, to `path`. Strings
+/// without a translation are left empty
+pub fn export_template(path: &Path) -> anyhow::Result<usize> {
+    let translations = TRANSLATIONS.read();
+    let template: BTreeMap<String, String> = SEEN
+        .read()
+        .iter()
+        .map(|text| {
+            (
+                text.clone(),
+                translations.get(text).cloned().unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    std::fs::write(path, toml::to_string_pretty(&template)?)?;
+    Ok(template.len())
+}
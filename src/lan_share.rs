@@ -0,0 +1,362 @@
+//! Read-only sharing of the tag cache over the local network
+//!
+//! One instance hosts its cache (see [`ShareSettings`]), other instances started with `--connect <address>` load the
+//! cache from the host instead of scanning their own packages. Tags that aren't in the client's packages are read
+//! from the host as well, so a client only needs a (partial) packages directory for the same game version.
+//!
+//! Two instances can also be linked for comparing game versions side by side (see [`ShareSettings::link`]). Opening a
+//! tag sends an `OPEN` request to the linked instance, which opens the corresponding tag (by 64-bit hash if there is
+//! one) and returns its data for the hex diff.
+//!
+//! The protocol is a single request line per connection (`INFO`, `CACHE`, `TAG <hash>` or `OPEN <hash> [hash64]`),
+//! answered with a status byte, the payload length as a little endian u64 and the payload. Errors carry a UTF-8 message
+//! as their payload. There's no authentication, anyone that can reach the bind address can read the cache and tag data
+//! (and open tags) while hosting is enabled. The host only listens on this machine unless another interface is chosen,
+//! and serves at most [`MAX_CONNECTIONS`] connections at a time
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::Context;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use parking_lot::{Mutex, RwLock};
+use quicktag_scanner::{cache_path, TagCache};
+use serde_json::json;
+use tiger_pkg::{package_manager, TagHash, TagHash64, Version};
+
+use crate::config::{self, ShareSettings};
+use crate::gui::TOASTS;
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+/// Requests are a single short line, anything longer isn't a quicktag client
+const MAX_REQUEST_LENGTH: u64 = 256;
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the host thread checks whether it should stop
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Connections served at the same time, others are closed right away
+const MAX_CONNECTIONS: usize = 8;
+
+struct Host {
+    address: String,
+    port: u16,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref HOST: Mutex<Option<Host>> = Mutex::new(None);
+    /// Address of the host that the cache is loaded from, set with `--connect`
+    static ref REMOTE: RwLock<Option<String>> = RwLock::new(None);
+    /// Tag that a linked instance opened, as (local tag, tag in the linked instance)
+    static ref LINKED_OPEN: Mutex<Option<(TagHash, TagHash)>> = Mutex::new(None);
+}
+
+/// Starts, stops or moves the host to match the settings
+pub fn update_host(settings: &ShareSettings) {
+    let mut host = HOST.lock();
+    let address = settings.bind_address.trim();
+    let target = settings.host.then_some((address, settings.port));
+    if host.as_ref().map(|h| (h.address.as_str(), h.port)) == target {
+        return;
+    }
+
+    if let Some(old) = host.take() {
+        old.stop.store(true, Ordering::Relaxed);
+        old.thread.join().ok();
+        info!(
+            "Stopped sharing the tag cache on {}:{}",
+            old.address, old.port
+        );
+    }
+
+    let Some((address, port)) = target else {
+        return;
+    };
+
+    match start_host(address, port) {
+        Ok(h) => {
+            info!("Sharing the tag cache on {address}:{port}");
+            *host = Some(h);
+        }
+        Err(e) => {
+            error!("Failed to share the tag cache on {address}:{port}: {e}");
+            TOASTS.lock().error(format!(
+                "Failed to share the tag cache on {address}:{port}: {e}"
+            ));
+        }
+    }
+}
+
+fn start_host(address: &str, port: u16) -> std::io::Result<Host> {
+    let listener = TcpListener::bind((address, port))?;
+    listener.set_nonblocking(true)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let connections = Arc::new(AtomicUsize::new(0));
+    let thread = std::thread::Builder::new()
+        .name("cache_share".to_string())
+        .spawn({
+            let stop = stop.clone();
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, peer)) => {
+                            if connections.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                                connections.fetch_sub(1, Ordering::Relaxed);
+                                warn!("Cache share: too many connections, closing the one from {peer}");
+                                continue;
+                            }
+
+                            let connections = connections.clone();
+                            std::thread::spawn(move || {
+                                if let Err(e) = handle_request(stream) {
+                                    warn!("Cache share: request from {peer} failed: {e:?}");
+                                }
+                                connections.fetch_sub(1, Ordering::Relaxed);
+                            });
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            std::thread::sleep(ACCEPT_POLL_INTERVAL)
+                        }
+                        Err(e) => {
+                            error!("Cache share: failed to accept connection: {e}");
+                            std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                        }
+                    }
+                }
+            }
+        })?;
+
+    Ok(Host {
+        address: address.to_string(),
+        port,
+        stop,
+        thread,
+    })
+}
+
+fn write_response(stream: &mut impl Write, status: u8, payload: &[u8]) -> std::io::Result<()> {
+    stream.write_all(&[status])?;
+    stream.write_all(&(payload.len() as u64).to_le_bytes())?;
+    stream.write_all(payload)
+}
+
+fn handle_request(stream: TcpStream) -> anyhow::Result<()> {
+    // Accepted sockets inherit non-blocking mode from the listener on some platforms
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+
+    let mut request = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_LENGTH)).read_line(&mut request)?;
+    let request = request.trim();
+    let (command, argument) = request.split_once(' ').unwrap_or((request, ""));
+
+    let mut stream = BufWriter::new(stream);
+    match command {
+        "INFO" => {
+            let info = json!({
+                "game_version": package_manager().version.name(),
+                "cache_key": package_manager().cache_key().to_string(),
+                "cache_version": TagCache::VERSION,
+            });
+            write_response(&mut stream, STATUS_OK, info.to_string().as_bytes())?;
+        }
+        "CACHE" => match File::open(cache_path()) {
+            Ok(mut file) => {
+                // The cache file is streamed as-is, it's already compressed
+                stream.write_all(&[STATUS_OK])?;
+                stream.write_all(&file.metadata()?.len().to_le_bytes())?;
+                std::io::copy(&mut file, &mut stream)?;
+            }
+            Err(e) => write_response(
+                &mut stream,
+                STATUS_ERROR,
+                format!("The host has no tag cache: {e}").as_bytes(),
+            )?,
+        },
+        "TAG" => match u32::from_str_radix(argument, 16)
+            .map_err(anyhow::Error::from)
+            .and_then(|hash| Ok(package_manager().read_tag(TagHash(hash))?))
+        {
+            Ok(data) => write_response(&mut stream, STATUS_OK, &data)?,
+            Err(e) => write_response(
+                &mut stream,
+                STATUS_ERROR,
+                format!("Failed to read tag {argument}: {e}").as_bytes(),
+            )?,
+        },
+        "OPEN" => match resolve_linked_tag(argument)
+            .and_then(|(tag, remote_tag)| Ok((tag, remote_tag, package_manager().read_tag(tag)?)))
+        {
+            Ok((tag, remote_tag, data)) => {
+                if config::with(|c| c.share.follow_links) {
+                    *LINKED_OPEN.lock() = Some((tag, remote_tag));
+                }
+
+                let mut payload = tag.0.to_le_bytes().to_vec();
+                payload.extend(data);
+                write_response(&mut stream, STATUS_OK, &payload)?
+            }
+            Err(e) => write_response(&mut stream, STATUS_ERROR, e.to_string().as_bytes())?,
+        },
+        _ => write_response(
+            &mut stream,
+            STATUS_ERROR,
+            format!("Unknown request '{command}'").as_bytes(),
+        )?,
+    }
+
+    stream.flush()?;
+    Ok(())
+}
+
+/// Finds the local tag for an `OPEN` request, returning (local tag, tag in the requesting instance)
+///
+/// 64-bit hashes stay the same across game versions, so they're preferred over the 32-bit hash when there is one
+fn resolve_linked_tag(argument: &str) -> anyhow::Result<(TagHash, TagHash)> {
+    let mut parts = argument.split_whitespace();
+    let remote_tag = TagHash(u32::from_str_radix(
+        parts.next().context("Missing tag hash")?,
+        16,
+    )?);
+    let tag64 = parts
+        .next()
+        .map(|h| u64::from_str_radix(h, 16))
+        .transpose()?;
+
+    if let Some(tag) = tag64.and_then(|h| {
+        package_manager()
+            .lookup
+            .tag64_entries
+            .get(&h)
+            .map(|e| e.hash32)
+    }) {
+        return Ok((tag, remote_tag));
+    }
+
+    anyhow::ensure!(
+        package_manager().get_entry(remote_tag).is_some(),
+        "Tag {remote_tag} doesn't exist in the linked instance"
+    );
+    Ok((remote_tag, remote_tag))
+}
+
+/// Whether tags opened by linked instances are opened here, which requires the GUI to check for them regularly
+pub fn is_following_links() -> bool {
+    HOST.lock().is_some() && config::with(|c| c.share.follow_links)
+}
+
+/// Takes the tag that a linked instance opened, as (local tag, tag in the linked instance)
+pub fn take_linked_open() -> Option<(TagHash, TagHash)> {
+    LINKED_OPEN.lock().take()
+}
+
+/// Address of the instance to browse together with, see [`ShareSettings::link`]
+pub fn linked_address() -> Option<String> {
+    config::with(|c| c.share.link.clone()).filter(|a| !a.trim().is_empty())
+}
+
+/// Loads the cache (and missing tags) from the instance at the given address instead of the local packages
+pub fn set_remote(address: String) {
+    *REMOTE.write() = Some(address);
+}
+
+pub fn remote() -> Option<String> {
+    REMOTE.read().clone()
+}
+
+/// Connects to the first address that `address` resolves to that accepts the connection within [`TIMEOUT`]
+fn connect(address: &str) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Address didn't resolve to anything",
+        )
+    }))
+}
+
+fn request(address: &str, request: &str) -> anyhow::Result<Vec<u8>> {
+    let mut stream = connect(address).with_context(|| format!("Failed to connect to {address}"))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.write_all(format!("{request}\n").as_bytes())?;
+
+    let mut header = [0u8; 9];
+    stream.read_exact(&mut header)?;
+    let length = u64::from_le_bytes(header[1..].try_into().unwrap());
+
+    let mut payload = vec![];
+    (&mut stream).take(length).read_to_end(&mut payload)?;
+    anyhow::ensure!(
+        payload.len() as u64 == length,
+        "Connection closed before the response was complete"
+    );
+
+    if header[0] != STATUS_OK {
+        anyhow::bail!("{}", String::from_utf8_lossy(&payload));
+    }
+
+    Ok(payload)
+}
+
+/// Downloads the cache from the host. Runs on the cache loading thread
+pub fn fetch_cache(address: &str) -> anyhow::Result<TagCache> {
+    let info: serde_json::Value = serde_json::from_slice(&request(address, "INFO")?)?;
+    let local_key = package_manager().cache_key().to_string();
+    if info["cache_key"].as_str() != Some(local_key.as_str()) {
+        let game_version = info["game_version"].as_str().unwrap_or("unknown");
+        warn!("The cache shared by {address} ({game_version}) doesn't match the local packages");
+        TOASTS.lock().warning(format!(
+            "The cache shared by {address} is for {game_version}, which doesn't match the local packages"
+        ));
+    }
+
+    info!("Downloading tag cache from {address}");
+    let data = request(address, "CACHE")?;
+    info!("Downloaded tag cache ({} MiB)", data.len() / 1024 / 1024);
+
+    TagCache::decode_compressed(&data)
+}
+
+/// Opens the corresponding tag in the linked instance, returning that tag and its data
+pub fn open_linked(address: &str, tag: TagHash) -> anyhow::Result<(TagHash, Vec<u8>)> {
+    let request_line = match package_manager().get_tag64_for_tag32(tag) {
+        Some(TagHash64(tag64)) => format!("OPEN {:08X} {tag64:016X}", tag.0),
+        None => format!("OPEN {:08X}", tag.0),
+    };
+
+    let mut payload = request(address, &request_line)?;
+    anyhow::ensure!(payload.len() >= 4, "Response is too short");
+    let data = payload.split_off(4);
+    Ok((
+        TagHash(u32::from_le_bytes(payload.try_into().unwrap())),
+        data,
+    ))
+}
+
+/// Reads a tag from the linked instance, without opening it there
+pub fn read_linked_tag(address: &str, tag: TagHash) -> anyhow::Result<Vec<u8>> {
+    request(address, &format!("TAG {:08X}", tag.0))
+}
+
+/// Reads a tag from the host, for tags that aren't in the local packages
+pub fn read_remote_tag(tag: TagHash) -> Option<Vec<u8>> {
+    let address = remote()?;
+    request(&address, &format!("TAG {:08X}", tag.0))
+        .map_err(|e| warn!("Failed to read tag {tag} from {address}: {e:?}"))
+        .ok()
+}
@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+use env_logger::Env;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+/// Amount of log lines kept around for issue reports
+const MAX_RECENT_LINES: usize = 256;
+
+lazy_static! {
+    static ref RECENT_LINES: Mutex<VecDeque<String>> =
+        Mutex::new(VecDeque::with_capacity(MAX_RECENT_LINES));
+}
+
+/// Wraps env_logger, keeping a copy of the most recent log lines
+struct RecordingLogger {
+    inner: env_logger::Logger,
+}
+
+impl log::Log for RecordingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.inner.matches(record) {
+            let mut lines = RECENT_LINES.lock();
+            if lines.len() == MAX_RECENT_LINES {
+                lines.pop_front();
+            }
+
+            lines.push_back(format!(
+                "[{} {}] {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+pub fn init() {
+    let logger = env_logger::Builder::from_env(
+        Env::default().default_filter_or("info,wgpu_core=warn,wgpu_hal=warn,naga=warn"),
+    )
+    .build();
+
+    log::set_max_level(logger.filter());
+    log::set_boxed_logger(Box::new(RecordingLogger { inner: logger }))
+        .expect("Logger already initialized");
+}
+
+/// Returns up to `count` of the most recent log lines, oldest first
+pub fn recent_lines(count: usize) -> Vec<String> {
+    let lines = RECENT_LINES.lock();
+    lines
+        .iter()
+        .skip(lines.len().saturating_sub(count))
+        .cloned()
+        .collect()
+}
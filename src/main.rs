@@ -1,17 +1,44 @@
+mod analyzers;
+mod annotation_merge;
+mod annotations;
+mod backups;
+mod cli;
+mod compression;
+mod config;
+mod crash_context;
+mod dialogs;
+mod geometry;
+mod gltf;
 mod gui;
+mod hash_import;
+mod i18n;
+mod lan_share;
+mod logging;
+mod open_api;
 mod panic_handler;
+mod post_scan_hook;
+mod saved_queries;
+mod session;
+mod shutdown;
+mod tag_data;
+mod tag_identity;
+mod text_index;
 mod texture;
 mod util;
+mod watchdog;
+mod watchlist;
+mod webhook;
 
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use eframe::egui::ViewportBuilder;
 use eframe::egui_wgpu::WgpuConfiguration;
 use eframe::wgpu;
-use env_logger::Env;
 use game_detector::InstalledGame;
-use log::info;
+use log::{info, warn};
+use quicktag_scanner::ScanStatus;
 use tiger_pkg::{package_manager, DestinyVersion, GameVersion, PackageManager, Version};
 
 use crate::gui::QuickTagApp;
@@ -22,9 +49,112 @@ struct Args {
     /// Path to packages directory
     packages_path: Option<String>,
 
-    /// Game version for the specified packages directory
-    #[arg(short, value_enum)]
+    /// Path to the packages directory, same as the positional argument. Meant for shortcuts and launchers
+    #[arg(long, conflicts_with = "packages_path")]
+    pkg_path: Option<String>,
+
+    /// Game version for the specified packages directory (eg. `d2_tfs`). `d1_latest` and `d2_latest` pick the
+    /// latest supported version of either game
+    #[arg(short, long, global = true, value_parser = parse_game_version)]
     version: Option<GameVersion>,
+
+    /// Load the tag cache from a quicktag instance that shares its cache on the network (eg. `192.168.1.10:7331`)
+    /// instead of building one
+    #[arg(long)]
+    connect: Option<String>,
+
+    /// Opens a tag (`<hash>[@offset]` or a `quicktag://` link). The tag is sent to the running instance if there is
+    /// one with the open API enabled, otherwise it's opened once this instance has started
+    #[arg(long)]
+    open: Option<String>,
+
+    /// Shows the given crash log and exits. Used by the panic handler when native dialogs aren't available
+    #[arg(long, hide = true)]
+    crash_dialog: Option<PathBuf>,
+
+    /// Prints information from the tag cache and exits instead of opening the GUI
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
+    #[command(flatten)]
+    headless: cli::HeadlessArgs,
+
+    /// Print the output of a command as JSON, see the README for the format of each command
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Path to the config file. Defaults to `$QUICKTAG_CONFIG`, or `quicktag.toml` in the working directory
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Directory to store tag caches in, overrides `general.cache_dir` from the config file
+    #[arg(long, global = true)]
+    cache_dir: Option<String>,
+
+    /// Directory for annotations and other user data, overrides `general.data_dir` from the config file
+    #[arg(long, global = true)]
+    data_dir: Option<String>,
+
+    /// Amount of threads used for scanning and searching, overrides `general.threads` from the config file
+    #[arg(long, global = true)]
+    threads: Option<u32>,
+
+    /// Name shown in the window title, to tell apart windows for different builds. Overrides `general.profile`
+    #[arg(long)]
+    profile: Option<String>,
+}
+
+/// Versions that can be passed by name besides the ones from tiger-pkg, so shortcuts don't break on new versions
+const VERSION_ALIASES: &[(&str, GameVersion)] = &[
+    (
+        "d1_latest",
+        GameVersion::Destiny(DestinyVersion::DestinyRiseOfIron),
+    ),
+    (
+        "d2_latest",
+        GameVersion::Destiny(DestinyVersion::Destiny2TheFinalShape),
+    ),
+];
+
+fn parse_game_version(version: &str) -> Result<GameVersion, String> {
+    match VERSION_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(version))
+    {
+        Some((_, version)) => Ok(*version),
+        None => GameVersion::from_str(version, true),
+    }
+}
+
+impl Args {
+    /// Config values set by command line flags
+    fn config_overrides(&self) -> Vec<config::Override> {
+        [
+            (
+                "--cache-dir",
+                "general.cache_dir",
+                self.cache_dir.clone().map(toml::Value::from),
+            ),
+            (
+                "--data-dir",
+                "general.data_dir",
+                self.data_dir.clone().map(toml::Value::from),
+            ),
+            (
+                "--threads",
+                "general.threads",
+                self.threads.map(|t| toml::Value::from(t as i64)),
+            ),
+            (
+                "--profile",
+                "general.profile",
+                self.profile.clone().map(toml::Value::from),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(flag, key, value)| Some(config::Override::new(flag, key, value?)))
+        .collect()
+    }
 }
 
 fn main() -> eframe::Result<()> {
@@ -37,42 +167,145 @@ fn main() -> eframe::Result<()> {
 
     let _rt_guard = rt.enter();
 
-    env_logger::Builder::from_env(
-        Env::default().default_filter_or("info,wgpu_core=warn,wgpu_hal=warn,naga=warn"),
-    )
-    .init();
+    logging::init();
     let args = Args::parse();
 
-    let packages_path = if let Some(packages_path) = args.packages_path {
+    if let Some(log_path) = args.crash_dialog {
+        let log = std::fs::read_to_string(&log_path).unwrap_or_default();
+        return dialogs::show_message_window(
+            "QuickTag crashed!",
+            &format!(
+                "A full crash log has been written to {}\n\n{}",
+                log_path.display(),
+                panic_handler::strip_ansi_codes(&log)
+            ),
+        );
+    }
+
+    if let Some(cli::Command::Hash(hash_args)) = &args.command {
+        cli::exit_on_error(cli::print_hash(hash_args, args.json));
+        return Ok(());
+    }
+
+    config::load(args.config.clone(), args.config_overrides());
+    let open_api_settings = config::with(|c| c.open_api.clone());
+    if let Some(target) = args.open.as_deref() {
+        if open_api_settings.enabled && args.command.is_none() && !args.headless.headless {
+            // Only hand the tag over to an instance of the requested version, otherwise the tag would be looked up in the
+            // wrong packages
+            match open_api::send_open(open_api_settings.port, target, args.version) {
+                Ok(()) => {
+                    info!("Opened {target} in the running instance");
+                    return Ok(());
+                }
+                Err(e) => info!("No running instance opened {target}, starting a new one: {e}"),
+            }
+        }
+    }
+
+    let general = config::with(|c| c.general.clone());
+    if general.threads > 0 {
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .num_threads(general.threads as usize)
+            .build_global()
+        {
+            warn!("Failed to set the thread count: {e}");
+        }
+    }
+
+    let packages_path = if let Some(packages_path) = args
+        .pkg_path
+        .or(args.packages_path)
+        .or(general.packages_path)
+    {
         packages_path
     } else if let Some(path) = find_d2_packages_path() {
         let mut path = std::path::PathBuf::from(path);
         path.push("packages");
         path.to_str().unwrap().to_string()
     } else {
-        panic!("Could not find Destiny 2 packages directory");
+        dialogs::fatal_error(
+            "Packages not found",
+            "Could not find a Destiny 2 installation\n\nPass the path to the packages directory as the first argument, or with --pkg-path",
+        );
     };
 
+    // Handles Wine/Proton drive paths and mismatched casing on case-sensitive filesystems
+    let packages_path = util::resolve_user_path(&packages_path)
+        .to_string_lossy()
+        .to_string();
+
+    let version = args.version.or_else(|| {
+        let version = general.game_version.as_deref()?;
+        Some(parse_game_version(version).unwrap_or_else(|e| {
+            dialogs::fatal_error(
+                "Invalid game version",
+                &format!("The game version '{version}' in the config file is invalid: {e}"),
+            )
+        }))
+    });
+
     info!(
         "Initializing package manager for version {:?} at '{}'",
-        args.version, packages_path
+        version, packages_path
     );
-    let pm = PackageManager::new(
-        packages_path,
-        args.version
-            .unwrap_or(GameVersion::Destiny(DestinyVersion::Destiny2TheFinalShape)),
+    let pm = match PackageManager::new(
+        packages_path.clone(),
+        version.unwrap_or(GameVersion::Destiny(DestinyVersion::Destiny2TheFinalShape)),
         None,
-    )
-    .unwrap();
+    ) {
+        Ok(pm) => pm,
+        Err(e) => dialogs::fatal_error(
+            "Failed to load packages",
+            &format!("Failed to initialize the package manager at '{packages_path}'\n\n{e:?}"),
+        ),
+    };
 
     tiger_pkg::initialize(&Arc::new(pm));
 
+    crash_context::set("Game version", package_manager().version.name());
+    crash_context::set("Packages", packages_path);
+    crash_context::add_provider("Scanner", || {
+        match quicktag_scanner::try_scanner_progress()? {
+            ScanStatus::None => None,
+            status => Some(status.to_string()),
+        }
+    });
+
     quicktag_core::classes::initialize_reference_names();
+    analyzers::register();
+    post_scan_hook::register();
+    if let Some(command) = args.command {
+        annotations::load();
+        cli::exit_on_error(cli::run(command, args.json));
+        return Ok(());
+    }
+    if args.headless.headless {
+        annotations::load();
+        cli::exit_on_error(cli::run_headless(args.headless, args.json));
+        return Ok(());
+    }
+
+    lan_share::update_host(&config::with(|c| c.share.clone()));
+    if let Some(address) = args.connect {
+        lan_share::set_remote(address);
+    }
+    open_api::update_listener(&open_api_settings);
+    if let Some(target) = args.open.as_deref() {
+        match open_api::parse_target(target) {
+            Ok((tag, offset)) => open_api::queue_open(tag, offset),
+            Err(e) => warn!("Can't open '{target}': {e}"),
+        }
+    }
+    annotations::load();
+    watchlist::load();
+    saved_queries::load();
+    session::load();
 
     let native_options = eframe::NativeOptions {
         renderer: eframe::Renderer::Wgpu,
         viewport: ViewportBuilder::default()
-            .with_title(format!("Quicktag - {}", package_manager().version.name()))
+            .with_title(gui::profile::window_title())
             .with_icon(
                 eframe::icon_data::from_png_bytes(include_bytes!("../quicktag.png"))
                     .expect("Failed to load icon"),
@@ -0,0 +1,215 @@
+//! Local socket for opening tags from other tools (editors, notebooks, scripts)
+//!
+//! While enabled (see [`OpenApiSettings`]), quicktag listens on `127.0.0.1:<port>`. Clients send one request per line
+//! and get a single response line for each:
+//!
+//! - `PING` is answered with `OK quicktag <game version>`
+//! - `OPEN <tag>[@offset]` opens a tag, optionally with the hex view scrolled to a (hex) offset. The tag is a 32-bit
+//!   or 64-bit hash, and may be a `quicktag://` link. Answered with `OK`
+//!
+//! Failed requests are answered with `ERR <message>`. Starting quicktag with `--open <tag>[@offset]` sends the tag to
+//! an instance that is already running, or opens it once the new instance has started.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::Context;
+use eframe::egui;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use parking_lot::Mutex;
+use tiger_pkg::{package_manager, GameVersion, TagHash, Version};
+
+use crate::config::OpenApiSettings;
+use crate::gui::{parse_tag64, split_tag_offset, TOASTS};
+
+/// Requests are a single short line, anything longer isn't meant for quicktag
+const MAX_REQUEST_LENGTH: usize = 256;
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the listener thread checks whether it should stop
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+struct Listener {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+    /// Tags requested by clients, opened by the GUI on the next frame
+    static ref PENDING_OPENS: Mutex<Vec<(TagHash, Option<u64>)>> = Mutex::new(vec![]);
+    /// Used to wake up the GUI when a request comes in
+    static ref CONTEXT: Mutex<Option<egui::Context>> = Mutex::new(None);
+}
+
+/// Starts, stops or moves the listener to match the settings
+pub fn update_listener(settings: &OpenApiSettings) {
+    let mut listener = LISTENER.lock();
+    let port = settings.enabled.then_some(settings.port);
+    if listener.as_ref().map(|l| l.port) == port {
+        return;
+    }
+
+    if let Some(old) = listener.take() {
+        old.stop.store(true, Ordering::Relaxed);
+        old.thread.join().ok();
+        info!("Stopped listening for open requests on port {}", old.port);
+    }
+
+    let Some(port) = port else {
+        return;
+    };
+
+    match start_listener(port) {
+        Ok(l) => {
+            info!("Listening for open requests on 127.0.0.1:{port}");
+            *listener = Some(l);
+        }
+        Err(e) => {
+            error!("Failed to listen for open requests on port {port}: {e}");
+            TOASTS.lock().error(format!(
+                "Failed to listen for open requests on port {port}: {e}"
+            ));
+        }
+    }
+}
+
+fn start_listener(port: u16) -> std::io::Result<Listener> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    listener.set_nonblocking(true)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = std::thread::Builder::new()
+        .name("open_api".to_string())
+        .spawn({
+            let stop = stop.clone();
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            std::thread::spawn(move || {
+                                if let Err(e) = handle_connection(stream) {
+                                    warn!("Open API: connection failed: {e:?}");
+                                }
+                            });
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            std::thread::sleep(ACCEPT_POLL_INTERVAL)
+                        }
+                        Err(e) => {
+                            error!("Open API: failed to accept connection: {e}");
+                            std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                        }
+                    }
+                }
+            }
+        })?;
+
+    Ok(Listener { port, stop, thread })
+}
+
+/// Handles requests until the client closes the connection, so editors can keep a connection open
+fn handle_connection(stream: TcpStream) -> anyhow::Result<()> {
+    // Accepted sockets inherit non-blocking mode from the listener on some platforms
+    stream.set_nonblocking(false)?;
+
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let request = line?;
+        let response = if request.len() > MAX_REQUEST_LENGTH {
+            "ERR Request is too long".to_string()
+        } else {
+            match handle_request(request.trim()) {
+                Ok(response) => response,
+                Err(e) => format!("ERR {e}"),
+            }
+        };
+        writeln!(writer, "{response}")?;
+    }
+
+    Ok(())
+}
+
+fn handle_request(request: &str) -> anyhow::Result<String> {
+    let (command, argument) = request.split_once(' ').unwrap_or((request, ""));
+    match command.to_uppercase().as_str() {
+        "PING" => Ok(format!("OK quicktag {}", package_manager().version.name())),
+        "OPEN" => {
+            let (tag, offset) = parse_target(argument)?;
+            queue_open(tag, offset);
+            Ok("OK".to_string())
+        }
+        _ => anyhow::bail!("Unknown request '{command}'"),
+    }
+}
+
+/// Parses `<tag>[@offset]`, where the tag is a 32-bit or 64-bit hash
+pub fn parse_target(target: &str) -> anyhow::Result<(TagHash, Option<u64>)> {
+    let (tag, offset) = split_tag_offset(target.trim());
+    let tag = if tag.len() >= 16 {
+        parse_tag64(tag)?
+    } else {
+        TagHash(u32::from_str_radix(tag, 16).context("Invalid tag hash")?)
+    };
+
+    anyhow::ensure!(
+        package_manager().get_entry(tag).is_some(),
+        "Tag {tag} doesn't exist"
+    );
+    Ok((tag, offset))
+}
+
+/// Queues a tag to be opened by the GUI
+pub fn queue_open(tag: TagHash, offset: Option<u64>) {
+    PENDING_OPENS.lock().push((tag, offset));
+    if let Some(ctx) = CONTEXT.lock().as_ref() {
+        ctx.request_repaint();
+    }
+}
+
+/// Takes the oldest tag that was requested to be opened
+pub fn take_pending_open() -> Option<(TagHash, Option<u64>)> {
+    let mut pending = PENDING_OPENS.lock();
+    (!pending.is_empty()).then(|| pending.remove(0))
+}
+
+/// Lets requests wake up the GUI
+pub fn set_context(ctx: egui::Context) {
+    *CONTEXT.lock() = Some(ctx);
+}
+
+/// Sends an `OPEN` request to an instance that's already running. Fails if there's no instance listening on the port,
+/// or if the running instance isn't for the given game version
+pub fn send_open(port: u16, target: &str, version: Option<GameVersion>) -> anyhow::Result<()> {
+    let stream = TcpStream::connect_timeout(&(Ipv4Addr::LOCALHOST, port).into(), TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut request = |request: String| -> anyhow::Result<String> {
+        writeln!(writer, "{request}")?;
+        let mut response = String::new();
+        reader.read_line(&mut response)?;
+        match response.trim().strip_prefix("ERR ") {
+            Some(e) => anyhow::bail!("{e}"),
+            None => Ok(response.trim().to_string()),
+        }
+    };
+
+    if let Some(version) = version {
+        let running = request("PING".to_string())?;
+        let running = running.strip_prefix("OK quicktag ").unwrap_or(&running);
+        anyhow::ensure!(
+            running == version.name(),
+            "The running instance is for {running}, not {}",
+            version.name()
+        );
+    }
+
+    request(format!("OPEN {}", target.trim()))?;
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use std::fs::File;
+use std::path::Path;
 use std::{
     backtrace::{Backtrace, BacktraceStatus},
     io::Write,
@@ -9,6 +10,8 @@ use std::{
 use lazy_static::lazy_static;
 use parking_lot::Mutex;
 
+use crate::{crash_context, dialogs};
+
 lazy_static! {
     static ref PANIC_FILE: Arc<Mutex<Option<File>>> = Arc::new(Mutex::new(None));
     static ref PANIC_LOCK: Arc<Mutex<()>> = Arc::new(Mutex::new(()));
@@ -42,16 +45,19 @@ pub fn install_hook(header: Option<String>) {
 
         // Finally, show a dialog
         let panic_message_stripped = strip_ansi_codes(&format!("{info}"));
-        if let Err(e) = native_dialog::MessageDialog::new()
-            .set_type(native_dialog::MessageType::Error)
-            .set_title("QuickTag crashed!")
-            .set_text(&format!(
+        if let Err(e) = dialogs::show_native_error(
+            "QuickTag crashed!",
+            &format!(
                 "{}\n\nA full crash log has been written to panic.log",
                 panic_message_stripped
-            ))
-            .show_alert()
-        {
-            eprintln!("Failed to show error dialog: {e}")
+            ),
+        ) {
+            eprintln!("Failed to show error dialog: {e}");
+
+            // No native dialogs available (eg. Linux without zenity), let a fresh process show the log instead
+            if let Err(e) = dialogs::spawn_crash_dialog(Path::new("panic.log")) {
+                eprintln!("Failed to launch crash dialog: {e}")
+            }
         }
 
         // Make sure the application exits
@@ -76,6 +82,13 @@ fn write_panic_to_file(info: &PanicHookInfo<'_>, bt: Backtrace) -> std::io::Resu
         writeln!(f, "{}", header)?;
     }
 
+    let context = crash_context::format_context();
+    if !context.is_empty() {
+        writeln!(f, "Context:")?;
+        write!(f, "{context}")?;
+        writeln!(f)?;
+    }
+
     writeln!(f, "{}", info)?;
     if bt.status() == BacktraceStatus::Captured {
         writeln!(f)?;
@@ -0,0 +1,76 @@
+//! Runs a user-configured command after a new tag cache has been built, so automated pipelines can chain off quicktag
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use log::{error, info, warn};
+use quicktag_scanner::TagCache;
+use serde_json::json;
+use tiger_pkg::{package_manager, Version};
+
+use crate::config;
+
+/// Hooks the post-scan command into the scanner. The command is read from the config every time a cache is built
+pub fn register() {
+    quicktag_scanner::add_cache_built_callback(|cache, path| {
+        let (program, args) =
+            config::with(|c| (c.post_scan_hook.clone(), c.post_scan_hook_args.clone()));
+        let Some(program) = program.filter(|program| !program.trim().is_empty()) else {
+            return;
+        };
+
+        if let Err(e) = run(program.trim(), &args, cache, path) {
+            error!("Failed to run post-scan hook '{program}': {e:?}");
+        }
+    });
+}
+
+/// `tags_<key>.cache` -> `tags_<key>.summary.json`
+fn summary_path(cache_path: &Path) -> PathBuf {
+    cache_path.with_extension("summary.json")
+}
+
+fn cache_summary(cache: &TagCache, path: &Path) -> serde_json::Value {
+    let scanned = cache.hashes.values();
+    json!({
+        "cache_path": path,
+        "cache_version": cache.version,
+        "game_version": package_manager().version.name(),
+        "packages_timestamp": cache.timestamp,
+        "tags": cache.hashes.len(),
+        "failed_tags": scanned.clone().filter(|s| !s.successful).count(),
+        "tag_references": scanned.clone().map(|s| s.file_hashes.len() + s.file_hashes64.len()).sum::<usize>(),
+        "string_hashes": scanned.clone().map(|s| s.string_hashes.len()).sum::<usize>(),
+        "wordlist_hashes": scanned.clone().map(|s| s.wordlist_hashes.len()).sum::<usize>(),
+        "namespace_hashes": scanned.clone().map(|s| s.namespace_hashes.values().map(Vec::len).sum::<usize>()).sum::<usize>(),
+        "raw_strings": scanned.map(|s| s.raw_strings.len()).sum::<usize>(),
+        "videos": cache.videos.len(),
+    })
+}
+
+/// Writes the summary next to the cache and runs the program with the cache and summary paths after the configured
+/// arguments. The program isn't waited on, its exit status is only logged
+fn run(program: &str, args: &[String], cache: &TagCache, path: &Path) -> anyhow::Result<()> {
+    let summary_path = summary_path(path);
+    std::fs::write(
+        &summary_path,
+        serde_json::to_string_pretty(&cache_summary(cache, path))?,
+    )
+    .context("Failed to write cache summary")?;
+
+    info!("Running post-scan hook: {program} {args:?}");
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .arg(&summary_path)
+        .spawn()?;
+
+    let program = program.to_string();
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if status.success() => info!("Post-scan hook finished"),
+        Ok(status) => warn!("Post-scan hook '{program}' exited with {status}"),
+        Err(e) => error!("Failed to wait for post-scan hook '{program}': {e}"),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Tag search filters saved by the user. Pinned queries are shown on the dashboard
+
+use lazy_static::lazy_static;
+use log::{error, info};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::gui::TagFilter;
+use crate::shutdown::DirtyFlag;
+
+pub const SAVED_QUERIES_PATH: &str = "saved_queries.json";
+
+pub static DIRTY: DirtyFlag = DirtyFlag::new();
+
+lazy_static! {
+    static ref SAVED_QUERIES: RwLock<SavedQueries> = RwLock::new(SavedQueries::default());
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SavedQueries {
+    pub queries: Vec<SavedQuery>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SavedQuery {
+    pub name: String,
+    pub filter: TagFilter,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl SavedQueries {
+    /// Saves the filter under the given name. An existing query with that name is overwritten, but stays pinned
+    pub fn save(&mut self, name: &str, filter: TagFilter) {
+        if let Some(query) = self.queries.iter_mut().find(|q| q.name == name) {
+            query.filter = filter;
+        } else {
+            self.queries.push(SavedQuery {
+                name: name.to_string(),
+                filter,
+                pinned: false,
+            });
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.queries.retain(|q| q.name != name);
+    }
+
+    pub fn set_pinned(&mut self, name: &str, pinned: bool) {
+        if let Some(query) = self.queries.iter_mut().find(|q| q.name == name) {
+            query.pinned = pinned;
+        }
+    }
+
+    pub fn pinned(&self) -> Vec<SavedQuery> {
+        self.queries.iter().filter(|q| q.pinned).cloned().collect()
+    }
+}
+
+/// Loads the saved queries (if there are any)
+pub fn load() {
+    let path = config::data_path(SAVED_QUERIES_PATH);
+    if !path.exists() {
+        return;
+    }
+
+    match std::fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|s| Ok(serde_json::from_str::<SavedQueries>(&s)?))
+    {
+        Ok(q) => {
+            info!("Loaded saved queries from {}", path.display());
+            *SAVED_QUERIES.write() = q;
+        }
+        Err(e) => {
+            error!("Failed to load saved queries: {e:?}");
+        }
+    }
+}
+
+fn save(queries: &SavedQueries) -> anyhow::Result<()> {
+    std::fs::write(
+        config::data_path(SAVED_QUERIES_PATH),
+        serde_json::to_string_pretty(queries)?,
+    )?;
+    Ok(())
+}
+
+/// Reads the saved queries
+pub fn with<R>(f: impl FnOnce(&SavedQueries) -> R) -> R {
+    f(&SAVED_QUERIES.read())
+}
+
+/// Modifies the saved queries, then saves them
+pub fn with_mut<R>(f: impl FnOnce(&mut SavedQueries) -> R) -> R {
+    let mut queries = SAVED_QUERIES.write();
+    let result = f(&mut queries);
+    save_or_mark_dirty(&queries);
+    result
+}
+
+fn save_or_mark_dirty(queries: &SavedQueries) {
+    DIRTY.record("queries", save(queries));
+}
+
+/// Saves the saved queries as they are now, see [`crate::shutdown::flush`]
+pub fn save_current() {
+    save_or_mark_dirty(&SAVED_QUERIES.read());
+}
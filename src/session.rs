@@ -0,0 +1,100 @@
+//! The tag history of the last session, so quicktag opens the tag that was open when it was closed
+
+use lazy_static::lazy_static;
+use log::{error, info};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tiger_pkg::TagHash;
+
+use crate::config;
+use crate::shutdown::DirtyFlag;
+
+pub const SESSION_PATH: &str = "session.json";
+
+pub static DIRTY: DirtyFlag = DirtyFlag::new();
+
+lazy_static! {
+    static ref SESSION: RwLock<Session> = RwLock::new(Session::default());
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct Session {
+    /// Hex tag hashes of the tag history, oldest first
+    pub history: Vec<String>,
+    /// Index of the open tag in the history
+    pub current: usize,
+}
+
+impl Session {
+    pub fn new(history: impl IntoIterator<Item = TagHash>, current: usize) -> Self {
+        Self {
+            history: history
+                .into_iter()
+                .map(|t| format!("{:08X}", t.0))
+                .collect(),
+            current,
+        }
+    }
+
+    /// The tag history, skipping entries that aren't valid tag hashes
+    pub fn tags(&self) -> Vec<TagHash> {
+        self.history
+            .iter()
+            .filter_map(|h| u32::from_str_radix(h, 16).ok())
+            .map(TagHash)
+            .collect()
+    }
+}
+
+/// Loads the last session (if there is one)
+pub fn load() {
+    let path = config::data_path(SESSION_PATH);
+    if !path.exists() {
+        return;
+    }
+
+    match std::fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|s| Ok(serde_json::from_str::<Session>(&s)?))
+    {
+        Ok(s) => {
+            info!("Loaded session from {}", path.display());
+            *SESSION.write() = s;
+        }
+        Err(e) => {
+            error!("Failed to load session: {e:?}");
+        }
+    }
+}
+
+fn save(session: &Session) -> anyhow::Result<()> {
+    std::fs::write(
+        config::data_path(SESSION_PATH),
+        serde_json::to_string_pretty(session)?,
+    )?;
+    Ok(())
+}
+
+/// The session that was loaded, or the current one once it's been [`set`]
+pub fn get() -> Session {
+    SESSION.read().clone()
+}
+
+/// Replaces the session, then saves it if it changed
+pub fn set(session: Session) {
+    let mut current = SESSION.write();
+    if *current != session {
+        *current = session;
+        save_or_mark_dirty(&current);
+    }
+}
+
+fn save_or_mark_dirty(session: &Session) {
+    DIRTY.record("session", save(session));
+}
+
+/// Saves the session as it is now, see [`crate::shutdown::flush`]
+pub fn save_current() {
+    save_or_mark_dirty(&SESSION.read());
+}
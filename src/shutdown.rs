@@ -0,0 +1,94 @@
+//! Closing quicktag without losing state
+//!
+//! The annotations, watch list, saved queries, session and config are written as soon as they change. Writes that fail
+//! leave them dirty (see [`DirtyFlag`]), [`flush`] tries them once more when the window is closed. Closing the window
+//! while a background job (scan, search, import, export, extract) is still running asks for confirmation first, see
+//! [`running_jobs`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::{error, info};
+use quicktag_scanner::ScanStatus;
+
+use crate::annotations;
+use crate::config;
+use crate::crash_context;
+use crate::saved_queries;
+use crate::session;
+use crate::watchlist;
+
+/// Set when the last save of a file failed, so [`flush`] tries it again on exit
+#[derive(Default)]
+pub struct DirtyFlag(AtomicBool);
+
+impl DirtyFlag {
+    pub const fn new() -> Self {
+        Self(AtomicBool::new(false))
+    }
+
+    /// Records the outcome of a save, logging the error if it failed
+    pub fn record(&self, what: &str, result: anyhow::Result<()>) {
+        self.0.store(result.is_err(), Ordering::Relaxed);
+        if let Err(e) = result {
+            error!("Failed to save {what}: {e:?}");
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Everything that's saved as soon as it changes: name, dirty flag and a function that saves it again
+static DATABASES: &[(&str, &DirtyFlag, fn())] = &[
+    (
+        "annotations",
+        &annotations::DIRTY,
+        annotations::save_current,
+    ),
+    ("watch list", &watchlist::DIRTY, watchlist::save_current),
+    (
+        "saved queries",
+        &saved_queries::DIRTY,
+        saved_queries::save_current,
+    ),
+    ("session", &session::DIRTY, session::save_current),
+    ("settings", &config::DIRTY, config::save_current),
+];
+
+/// Descriptions of the background jobs that would be interrupted by closing quicktag
+pub fn running_jobs() -> Vec<String> {
+    let mut jobs = crash_context::running(crash_context::JOB);
+    // Loading an existing cache can be interrupted safely
+    match quicktag_scanner::try_scanner_progress() {
+        None | Some(ScanStatus::None | ScanStatus::LoadingCache) => {}
+        Some(status) => jobs.insert(0, format!("Building the tag cache ({status})")),
+    }
+
+    jobs
+}
+
+/// Names of the databases with changes that couldn't be saved
+pub fn unsaved() -> Vec<&'static str> {
+    DATABASES
+        .iter()
+        .filter(|(_, dirty, _)| dirty.is_dirty())
+        .map(|(name, _, _)| *name)
+        .collect()
+}
+
+/// Saves everything that's dirty. Called when the window is closed
+pub fn flush() {
+    for (_, dirty, save) in DATABASES {
+        if dirty.is_dirty() {
+            save();
+        }
+    }
+
+    let unsaved = unsaved();
+    if unsaved.is_empty() {
+        info!("Saved all state before exiting");
+    } else {
+        error!("Exiting with unsaved changes to the {}", unsaved.join(", "));
+    }
+}
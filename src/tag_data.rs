@@ -0,0 +1,136 @@
+//! In-memory LRU cache for decompressed tag data, so navigating back and forth between tags doesn't re-read and
+//! re-decompress them from the packages every time
+//!
+//! Only meant for tags that are opened interactively. Views that go over every tag (search, duplicates, stats)
+//! should keep reading from the package manager directly, or they'll push everything else out of the cache
+
+use std::hash::BuildHasherDefault;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use linked_hash_map::LinkedHashMap;
+use parking_lot::Mutex;
+use rustc_hash::FxHasher;
+use tiger_pkg::{package_manager, TagHash};
+
+type TagDataCacheMap = LinkedHashMap<TagHash, Vec<u8>, BuildHasherDefault<FxHasher>>;
+
+#[derive(Default)]
+struct TagDataCache {
+    /// Least recently used tags come first
+    entries: TagDataCacheMap,
+    /// Total size of the cached data in bytes
+    size: usize,
+}
+
+impl TagDataCache {
+    fn truncate(&mut self, capacity: usize) {
+        while self.size > capacity {
+            let Some((_, data)) = self.entries.pop_front() else {
+                break;
+            };
+            self.size -= data.len();
+        }
+    }
+}
+
+lazy_static! {
+    static ref TAG_DATA_CACHE: Mutex<TagDataCache> = Mutex::new(TagDataCache::default());
+}
+
+/// Cache capacity in bytes
+static CAPACITY: AtomicUsize = AtomicUsize::new(256 * 1024 * 1024);
+static PREFETCH_COUNT: AtomicUsize = AtomicUsize::new(16);
+
+/// Sets the cache size in megabytes, 0 disables the cache
+pub fn set_tag_data_cache_size(megabytes: u32) {
+    let capacity = megabytes as usize * 1024 * 1024;
+    CAPACITY.store(capacity, Ordering::Relaxed);
+    TAG_DATA_CACHE.lock().truncate(capacity);
+}
+
+/// Sets how many referenced tags are read ahead when a tag is opened, 0 disables prefetching
+pub fn set_prefetch_count(count: usize) {
+    PREFETCH_COUNT.store(count, Ordering::Relaxed);
+}
+
+/// Reads a tag through the cache. Drop-in replacement for [`tiger_pkg::PackageManager::read_tag`]
+pub fn read_tag(tag: TagHash) -> anyhow::Result<Vec<u8>> {
+    if let Some(data) = TAG_DATA_CACHE.lock().entries.get_refresh(&tag) {
+        return Ok(data.clone());
+    }
+
+    let data = package_manager().read_tag(tag)?;
+    insert(tag, &data);
+
+    Ok(data)
+}
+
+fn insert(tag: TagHash, data: &[u8]) {
+    let capacity = CAPACITY.load(Ordering::Relaxed);
+    // Tags that would take up most of the cache by themselves would evict everything else
+    if data.len() > capacity / 4 {
+        return;
+    }
+
+    let mut cache = TAG_DATA_CACHE.lock();
+    if let Some(old) = cache.entries.insert(tag, data.to_vec()) {
+        cache.size -= old.len();
+    }
+    cache.size += data.len();
+    cache.truncate(capacity);
+}
+
+fn is_cached(tag: TagHash) -> bool {
+    TAG_DATA_CACHE.lock().entries.contains_key(&tag)
+}
+
+/// Reads the first few of the given tags into the cache on a background thread, so following a reference doesn't
+/// have to wait for the packages
+pub fn prefetch(tags: impl IntoIterator<Item = TagHash>) {
+    let count = PREFETCH_COUNT.load(Ordering::Relaxed);
+    if count == 0 || CAPACITY.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+
+    let tags = tags
+        .into_iter()
+        .filter(|tag| tag.is_valid() && !is_cached(*tag))
+        .unique()
+        .take(count)
+        .collect_vec();
+    if tags.is_empty() {
+        return;
+    }
+
+    std::thread::Builder::new()
+        .name("tag_prefetch".to_string())
+        .spawn(move || {
+            for tag in tags {
+                // Might have been opened in the meantime
+                if !is_cached(tag) {
+                    read_tag(tag).ok();
+                }
+            }
+        })
+        .ok();
+}
+
+/// Amount of cached tags and their total size in bytes
+pub fn usage() -> (usize, usize) {
+    let cache = TAG_DATA_CACHE.lock();
+    (cache.entries.len(), cache.size)
+}
+
+/// Cache capacity in bytes
+pub fn capacity() -> usize {
+    CAPACITY.load(Ordering::Relaxed)
+}
+
+/// Drops all cached data, for when the packages have changed
+pub fn clear() {
+    let mut cache = TAG_DATA_CACHE.lock();
+    cache.entries.clear();
+    cache.size = 0;
+}
@@ -0,0 +1,88 @@
+//! Stable tag identity across game versions
+//!
+//! 32-bit tag hashes encode the package and entry index, so they change whenever a patch moves a tag to another
+//! package or reorders a package. Tags with a 64-bit hash keep it across versions, so comparing the 64-bit hash tables
+//! of two builds tells which 32-bit hashes refer to the same content. Tags without a 64-bit hash can't be followed.
+
+use itertools::Itertools;
+use rustc_hash::FxHashMap;
+use tiger_pkg::{package_manager, TagHash};
+
+use crate::{annotations, watchlist};
+
+/// The 64-bit hash table of a single build, mapping 64-bit hashes to 32-bit hashes
+pub struct Hash64Table {
+    tags: FxHashMap<u64, TagHash>,
+}
+
+impl Hash64Table {
+    /// Takes the table of the currently loaded packages
+    pub fn current() -> Self {
+        Self {
+            tags: package_manager()
+                .lookup
+                .tag64_entries
+                .iter()
+                .map(|(hash64, entry)| (*hash64, entry.hash32))
+                .collect(),
+        }
+    }
+}
+
+/// Tags whose 32-bit hash changed between two builds, by their 64-bit hash
+#[derive(Default)]
+pub struct IdentityMap {
+    /// Old 32-bit hash to new 32-bit hash, only for tags that moved
+    moved: FxHashMap<TagHash, TagHash>,
+}
+
+impl IdentityMap {
+    pub fn new(old: &Hash64Table, new: &Hash64Table) -> Self {
+        Self {
+            moved: old
+                .tags
+                .iter()
+                .filter_map(|(hash64, &old_tag)| {
+                    let new_tag = *new.tags.get(hash64)?;
+                    (new_tag != old_tag).then_some((old_tag, new_tag))
+                })
+                .collect(),
+        }
+    }
+
+    /// The hash that a tag of the old build has in the new build
+    pub fn resolve(&self, old: TagHash) -> TagHash {
+        self.moved.get(&old).copied().unwrap_or(old)
+    }
+
+    /// (old, new) hashes of every tag that moved
+    pub fn moved(&self) -> impl Iterator<Item = (TagHash, TagHash)> + '_ {
+        self.moved.iter().map(|(old, new)| (*old, *new))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moved.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.moved.len()
+    }
+}
+
+/// Moves the annotations and watched tags of tags that moved to their new hashes
+pub fn follow_moved_tags(identity: &IdentityMap) {
+    if identity.is_empty() {
+        return;
+    }
+
+    // Everything is taken out first, a moved tag can take over the old hash of another moved tag
+    annotations::with_mut("Follow tags moved by a patch", |a| {
+        let moved = identity
+            .moved()
+            .filter_map(|(old, new)| Some((new, a.tags.remove(&old)?)))
+            .collect_vec();
+        a.tags.extend(moved);
+    });
+
+    watchlist::with_mut(|w| w.remap_tags(identity));
+}
@@ -0,0 +1,223 @@
+//! Trigram index over localized strings, raw strings and tag annotations
+//!
+//! Substring searches only have to check the documents that contain every trigram of the query, instead of going
+//! over every string. The index is built in the background whenever a tag cache is loaded. Annotations are kept up to
+//! date incrementally, changed annotations get a new document and the old one is skipped from then on
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use log::info;
+use parking_lot::RwLock;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::StringCache;
+use rustc_hash::FxHashMap;
+use tiger_pkg::TagHash;
+
+use crate::annotations::{self, TagAnnotation};
+
+/// Separates the parts of a document, so queries don't match across strings
+const SEPARATOR: char = '\0';
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextDocument {
+    /// A localized string hash from the string cache
+    LocalizedString(u32),
+    /// The raw strings found in a tag
+    RawStrings(TagHash),
+    /// The name, labels and notes of a tag
+    Annotation(TagHash),
+}
+
+struct IndexedDocument {
+    document: TextDocument,
+    /// Lowercase text, used to check the candidates
+    text: String,
+    /// Replaced by a newer version of the document
+    removed: bool,
+}
+
+#[derive(Default)]
+pub struct TextIndex {
+    documents: Vec<IndexedDocument>,
+    /// Document IDs for every trigram, in ascending order
+    trigrams: FxHashMap<u64, Vec<u32>>,
+    annotation_documents: FxHashMap<TagHash, u32>,
+    /// [`annotations::generation`] that the annotation documents are up to date with
+    annotations_generation: u64,
+}
+
+lazy_static! {
+    static ref TEXT_INDEX: RwLock<Option<TextIndex>> = RwLock::new(None);
+}
+
+fn trigram_key(c: &[char]) -> u64 {
+    ((c[0] as u64) << 42) | ((c[1] as u64) << 21) | c[2] as u64
+}
+
+fn annotation_text(annotation: &TagAnnotation) -> String {
+    let mut parts = vec![];
+    parts.extend(annotation.name.clone());
+    parts.extend(annotation.labels.iter().cloned());
+    parts.push(annotation.notes.clone());
+    parts.join(&SEPARATOR.to_string())
+}
+
+impl TextIndex {
+    pub fn build(cache: &TagCache, strings: &StringCache) -> Self {
+        let mut index = TextIndex::default();
+        for (&hash, strings) in strings {
+            index.add(
+                TextDocument::LocalizedString(hash),
+                &strings.join(&SEPARATOR.to_string()),
+            );
+        }
+
+        for (&tag, scan) in &cache.hashes {
+            if !scan.raw_strings.is_empty() {
+                let text = scan
+                    .raw_strings
+                    .iter()
+                    .map(|s| s.string.as_str())
+                    .collect::<Vec<_>>()
+                    .join(&SEPARATOR.to_string());
+                index.add(TextDocument::RawStrings(tag), &text);
+            }
+        }
+
+        index.update_annotations();
+        index
+    }
+
+    fn add(&mut self, document: TextDocument, text: &str) -> u32 {
+        let id = self.documents.len() as u32;
+        let text = text.to_lowercase();
+
+        let chars: Vec<char> = text.chars().collect();
+        let mut keys: Vec<u64> = chars.windows(3).map(trigram_key).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        for key in keys {
+            self.trigrams.entry(key).or_default().push(id);
+        }
+
+        self.documents.push(IndexedDocument {
+            document,
+            text,
+            removed: false,
+        });
+
+        id
+    }
+
+    /// Re-indexes annotations that changed since the last update
+    fn update_annotations(&mut self) {
+        let generation = annotations::generation();
+        if generation == self.annotations_generation {
+            return;
+        }
+
+        let annotations = annotations::with(|a| {
+            a.tags
+                .iter()
+                .map(|(tag, annotation)| (*tag, annotation_text(annotation)))
+                .collect::<FxHashMap<_, _>>()
+        });
+
+        for (&tag, &id) in &self.annotation_documents {
+            let document = &mut self.documents[id as usize];
+            if annotations
+                .get(&tag)
+                .is_none_or(|text| text.to_lowercase() != document.text)
+            {
+                document.removed = true;
+            }
+        }
+        self.annotation_documents
+            .retain(|_, id| !self.documents[*id as usize].removed);
+
+        for (tag, text) in annotations {
+            if !self.annotation_documents.contains_key(&tag) && !text.is_empty() {
+                let id = self.add(TextDocument::Annotation(tag), &text);
+                self.annotation_documents.insert(tag, id);
+            }
+        }
+
+        self.annotations_generation = generation;
+    }
+
+    /// Documents that contain the query, ignoring case
+    pub fn search(&self, query: &str) -> Vec<TextDocument> {
+        let query = query.to_lowercase();
+        let chars: Vec<char> = query.chars().collect();
+
+        let matches = |id: &u32| {
+            let document = &self.documents[*id as usize];
+            !document.removed && document.text.contains(&query)
+        };
+
+        // Queries shorter than a trigram have to check every document
+        if chars.len() < 3 {
+            return (0..self.documents.len() as u32)
+                .filter(matches)
+                .map(|id| self.documents[id as usize].document)
+                .collect();
+        }
+
+        let mut postings = vec![];
+        for key in chars.windows(3).map(trigram_key) {
+            let Some(ids) = self.trigrams.get(&key) else {
+                return vec![];
+            };
+            postings.push(ids);
+        }
+        postings.sort_by_key(|ids| ids.len());
+
+        postings[0]
+            .iter()
+            .filter(|id| {
+                postings[1..]
+                    .iter()
+                    .all(|ids| ids.binary_search(id).is_ok())
+            })
+            .filter(|id| matches(id))
+            .map(|&id| self.documents[id as usize].document)
+            .collect()
+    }
+}
+
+/// Builds the index on a background thread
+pub fn rebuild(cache: Arc<TagCache>, strings: Arc<StringCache>) {
+    // An index of the previous cache would miss new strings, searches fall back to a linear scan in the meantime
+    *TEXT_INDEX.write() = None;
+    std::thread::Builder::new()
+        .name("text_index".to_string())
+        .spawn(move || {
+            let start = Instant::now();
+            let index = TextIndex::build(&cache, &strings);
+            info!(
+                "Built text index ({} documents, {} trigrams) in {:.2}s",
+                index.documents.len(),
+                index.trigrams.len(),
+                start.elapsed().as_secs_f32()
+            );
+            *TEXT_INDEX.write() = Some(index);
+        })
+        .expect("Failed to spawn text index thread");
+}
+
+pub fn is_ready() -> bool {
+    TEXT_INDEX.read().is_some()
+}
+
+/// Searches the index, or returns `None` while it's still being built
+pub fn search(query: &str) -> Option<Vec<TextDocument>> {
+    if TEXT_INDEX.read().as_ref()?.annotations_generation != annotations::generation() {
+        if let Some(index) = TEXT_INDEX.write().as_mut() {
+            index.update_annotations();
+        }
+    }
+
+    Some(TEXT_INDEX.read().as_ref()?.search(query))
+}
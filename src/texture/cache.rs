@@ -29,10 +29,13 @@ use tiger_pkg::TagHash;
 
 use linked_hash_map::LinkedHashMap;
 
+use itertools::Itertools;
+
 use eframe::epaint::TextureId;
 
 use super::Texture;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 pub type LoadedTexture = (Arc<Texture>, TextureId);
@@ -181,13 +184,58 @@ impl TextureCache {
         }
     }
 
-    pub(crate) const MAX_TEXTURES: usize = 2048;
     pub(crate) fn truncate(&self) {
+        let max_textures = MAX_TEXTURES.load(Ordering::Relaxed);
         let mut cache = self.cache.write();
-        while cache.len() > Self::MAX_TEXTURES {
+        while cache.len() > max_textures {
             if let Some((_, Either::Left(Some((_, tid))))) = cache.pop_front() {
                 self.render_state.renderer.write().free_texture(&tid);
             }
         }
     }
+
+    /// Frees every loaded texture. Textures that are still shown are loaded again
+    pub fn clear(&self) {
+        let mut cache = self.cache.write();
+        while let Some((_, texture)) = cache.pop_front() {
+            if let Either::Left(Some((_, tid))) = texture {
+                self.render_state.renderer.write().free_texture(&tid);
+            }
+        }
+    }
+
+    /// Amount of loaded textures and an estimate of their size in bytes (the first mip level of each texture)
+    pub fn memory_usage(&self) -> (usize, usize) {
+        let cache = self.cache.read();
+        let textures = cache
+            .values()
+            .filter_map(|t| match t {
+                Either::Left(Some((texture, _))) => Some(texture),
+                _ => None,
+            })
+            .collect_vec();
+
+        let size = textures
+            .iter()
+            .map(|t| {
+                let desc = &t.desc;
+                let (block_width, block_height) = desc.format.block_dimensions();
+                let block_size = desc.format.block_copy_size(None).unwrap_or(4);
+                desc.width.div_ceil(block_width) as usize
+                    * desc.height.div_ceil(block_height) as usize
+                    * block_size as usize
+                    * desc.depth.max(1) as usize
+                    * desc.array_size.max(1) as usize
+            })
+            .sum();
+
+        (textures.len(), size)
+    }
+}
+
+/// Maximum amount of textures kept loaded, the least recently shown ones are freed first
+static MAX_TEXTURES: AtomicUsize = AtomicUsize::new(2048);
+
+pub fn set_texture_cache_count(count: usize) {
+    MAX_TEXTURES.store(count.max(1), Ordering::Relaxed);
 }
@@ -243,6 +243,64 @@ impl DxgiFormat {
         })
     }
 
+    /// Reverse of [`Self::to_wgpu`], used for writing DDS files. Returns `None` for formats without a DXGI equivalent
+    pub fn from_wgpu(format: wgpu::TextureFormat) -> Option<Self> {
+        Some(match format {
+            wgpu::TextureFormat::Rgba32Float => DxgiFormat::R32G32B32A32_FLOAT,
+            wgpu::TextureFormat::Rgba32Uint => DxgiFormat::R32G32B32A32_UINT,
+            wgpu::TextureFormat::Rgba32Sint => DxgiFormat::R32G32B32A32_SINT,
+            wgpu::TextureFormat::Rgba16Float => DxgiFormat::R16G16B16A16_FLOAT,
+            wgpu::TextureFormat::Rgba16Unorm => DxgiFormat::R16G16B16A16_UNORM,
+            wgpu::TextureFormat::Rgba16Uint => DxgiFormat::R16G16B16A16_UINT,
+            wgpu::TextureFormat::Rgba16Snorm => DxgiFormat::R16G16B16A16_SNORM,
+            wgpu::TextureFormat::Rgba16Sint => DxgiFormat::R16G16B16A16_SINT,
+            wgpu::TextureFormat::Rg32Float => DxgiFormat::R32G32_FLOAT,
+            wgpu::TextureFormat::Rgb10a2Unorm => DxgiFormat::R10G10B10A2_UNORM,
+            wgpu::TextureFormat::Rg11b10Float => DxgiFormat::R11G11B10_FLOAT,
+            wgpu::TextureFormat::Rgba8Unorm => DxgiFormat::R8G8B8A8_UNORM,
+            wgpu::TextureFormat::Rgba8UnormSrgb => DxgiFormat::R8G8B8A8_UNORM_SRGB,
+            wgpu::TextureFormat::Rgba8Uint => DxgiFormat::R8G8B8A8_UINT,
+            wgpu::TextureFormat::Rgba8Snorm => DxgiFormat::R8G8B8A8_SNORM,
+            wgpu::TextureFormat::Rgba8Sint => DxgiFormat::R8G8B8A8_SINT,
+            wgpu::TextureFormat::Rg16Float => DxgiFormat::R16G16_FLOAT,
+            wgpu::TextureFormat::Rg16Unorm => DxgiFormat::R16G16_UNORM,
+            wgpu::TextureFormat::Rg16Uint => DxgiFormat::R16G16_UINT,
+            wgpu::TextureFormat::Rg16Snorm => DxgiFormat::R16G16_SNORM,
+            wgpu::TextureFormat::Rg16Sint => DxgiFormat::R16G16_SINT,
+            wgpu::TextureFormat::Depth32Float => DxgiFormat::D32_FLOAT,
+            wgpu::TextureFormat::R32Float => DxgiFormat::R32_FLOAT,
+            wgpu::TextureFormat::R32Uint => DxgiFormat::R32_UINT,
+            wgpu::TextureFormat::R32Sint => DxgiFormat::R32_SINT,
+            wgpu::TextureFormat::R16Float => DxgiFormat::R16_FLOAT,
+            wgpu::TextureFormat::Depth16Unorm => DxgiFormat::D16_UNORM,
+            wgpu::TextureFormat::R16Unorm => DxgiFormat::R16_UNORM,
+            wgpu::TextureFormat::R16Uint => DxgiFormat::R16_UINT,
+            wgpu::TextureFormat::R16Snorm => DxgiFormat::R16_SNORM,
+            wgpu::TextureFormat::R16Sint => DxgiFormat::R16_SINT,
+            wgpu::TextureFormat::Rg8Unorm => DxgiFormat::R8G8_UNORM,
+            wgpu::TextureFormat::R8Unorm => DxgiFormat::R8_UNORM,
+            wgpu::TextureFormat::R8Uint => DxgiFormat::R8_UINT,
+            wgpu::TextureFormat::R8Sint => DxgiFormat::R8_SINT,
+            wgpu::TextureFormat::Bgra8Unorm => DxgiFormat::B8G8R8A8_UNORM,
+            wgpu::TextureFormat::Bgra8UnormSrgb => DxgiFormat::B8G8R8A8_UNORM_SRGB,
+            wgpu::TextureFormat::Bc1RgbaUnorm => DxgiFormat::BC1_UNORM,
+            wgpu::TextureFormat::Bc1RgbaUnormSrgb => DxgiFormat::BC1_UNORM_SRGB,
+            wgpu::TextureFormat::Bc2RgbaUnorm => DxgiFormat::BC2_UNORM,
+            wgpu::TextureFormat::Bc2RgbaUnormSrgb => DxgiFormat::BC2_UNORM_SRGB,
+            wgpu::TextureFormat::Bc3RgbaUnorm => DxgiFormat::BC3_UNORM,
+            wgpu::TextureFormat::Bc3RgbaUnormSrgb => DxgiFormat::BC3_UNORM_SRGB,
+            wgpu::TextureFormat::Bc4RUnorm => DxgiFormat::BC4_UNORM,
+            wgpu::TextureFormat::Bc4RSnorm => DxgiFormat::BC4_SNORM,
+            wgpu::TextureFormat::Bc5RgUnorm => DxgiFormat::BC5_UNORM,
+            wgpu::TextureFormat::Bc5RgSnorm => DxgiFormat::BC5_SNORM,
+            wgpu::TextureFormat::Bc6hRgbUfloat => DxgiFormat::BC6H_UF16,
+            wgpu::TextureFormat::Bc6hRgbFloat => DxgiFormat::BC6H_SF16,
+            wgpu::TextureFormat::Bc7RgbaUnorm => DxgiFormat::BC7_UNORM,
+            wgpu::TextureFormat::Bc7RgbaUnormSrgb => DxgiFormat::BC7_UNORM_SRGB,
+            _ => return None,
+        })
+    }
+
     pub fn bpp(&self) -> usize {
         match self {
             DxgiFormat::R32G32B32A32_TYPELESS
@@ -0,0 +1,418 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use anyhow::Context;
+use eframe::egui_wgpu::RenderState;
+use eframe::wgpu;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImage};
+use rayon::prelude::*;
+use tiger_pkg::TagHash;
+
+use super::dxgi::DxgiFormat;
+use super::{decode_srgb, Texture, TextureDesc, TextureType};
+use crate::crash_context;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum TextureExportFormat {
+    /// Unconverted texture data with a DX10 DDS header
+    Dds,
+    Png,
+    Tga,
+}
+
+impl TextureExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TextureExportFormat::Dds => "dds",
+            TextureExportFormat::Png => "png",
+            TextureExportFormat::Tga => "tga",
+        }
+    }
+}
+
+impl Display for TextureExportFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureExportFormat::Dds => write!(f, "DDS (raw)"),
+            TextureExportFormat::Png => write!(f, "PNG"),
+            TextureExportFormat::Tga => write!(f, "TGA"),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum CubemapLayout {
+    /// Every face as a separate image
+    Faces,
+    /// 4x3 horizontal cross
+    Cross,
+    /// All faces next to each other, in +X -X +Y -Y +Z -Z order
+    Strip,
+}
+
+impl Display for CubemapLayout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CubemapLayout::Faces => write!(f, "Separate faces"),
+            CubemapLayout::Cross => write!(f, "Cross"),
+            CubemapLayout::Strip => write!(f, "Strip"),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ColorSpace {
+    /// Write the values as they're stored in the texture
+    AsStored,
+    /// Treat linear textures as linear color and encode them as sRGB
+    Srgb,
+    /// Decode sRGB textures to linear values
+    Linear,
+}
+
+impl Display for ColorSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorSpace::AsStored => write!(f, "As stored"),
+            ColorSpace::Srgb => write!(f, "sRGB"),
+            ColorSpace::Linear => write!(f, "Linear"),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TextureExportOptions {
+    pub format: TextureExportFormat,
+    pub mip: u32,
+    pub cubemap_layout: CubemapLayout,
+    /// Only applies to converted formats, DDS files always contain the original data
+    pub color_space: ColorSpace,
+}
+
+impl Default for TextureExportOptions {
+    fn default() -> Self {
+        Self {
+            format: TextureExportFormat::Png,
+            mip: 0,
+            cubemap_layout: CubemapLayout::Cross,
+            color_space: ColorSpace::AsStored,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TextureExportProgress {
+    pub current: AtomicUsize,
+    pub total: AtomicUsize,
+    pub cancel: AtomicBool,
+}
+
+#[derive(Default)]
+pub struct TextureExportSummary {
+    pub files: Vec<PathBuf>,
+    pub failed: Vec<(TagHash, String)>,
+}
+
+/// Exports the given textures on the rayon thread pool
+pub fn export_textures(
+    rs: &RenderState,
+    tags: &[TagHash],
+    options: TextureExportOptions,
+    out_dir: &Path,
+    progress: &TextureExportProgress,
+) -> TextureExportSummary {
+    let _context = crash_context::push(crash_context::JOB, "Exporting textures");
+    progress.total.store(tags.len(), Ordering::Relaxed);
+    progress.current.store(0, Ordering::Relaxed);
+
+    if let Err(e) = std::fs::create_dir_all(out_dir) {
+        return TextureExportSummary {
+            files: vec![],
+            failed: tags
+                .iter()
+                .map(|&tag| (tag, format!("Failed to create output directory: {e}")))
+                .collect(),
+        };
+    }
+
+    let results: Vec<_> = tags
+        .par_iter()
+        .map_with(rs.clone(), |rs, &tag| {
+            if progress.cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            let result = export_texture(rs, tag, options, out_dir);
+            progress.current.fetch_add(1, Ordering::Relaxed);
+            Some((tag, result))
+        })
+        .collect();
+
+    let mut summary = TextureExportSummary::default();
+    for (tag, result) in results.into_iter().flatten() {
+        match result {
+            Ok(files) => summary.files.extend(files),
+            Err(e) => summary.failed.push((tag, format!("{e:?}"))),
+        }
+    }
+
+    summary
+}
+
+/// Exports a single texture, returns the paths of the written files
+pub fn export_texture(
+    rs: &RenderState,
+    tag: TagHash,
+    options: TextureExportOptions,
+    out_dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    match options.format {
+        TextureExportFormat::Dds => {
+            let path = out_dir.join(format!("{tag}.dds"));
+            export_dds(tag, options.mip, &path)?;
+            Ok(vec![path])
+        }
+        TextureExportFormat::Png | TextureExportFormat::Tga => {
+            export_images(rs, tag, options, out_dir)
+        }
+    }
+}
+
+/// Size of a single layer of the given mip level, in (width, height, bytes)
+fn mip_level_size(
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    level: u32,
+) -> (u32, u32, usize) {
+    let width = (width >> level).max(1);
+    let height = (height >> level).max(1);
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(4);
+
+    (
+        width,
+        height,
+        (width.div_ceil(block_width) * height.div_ceil(block_height) * block_size) as usize,
+    )
+}
+
+/// Writes the texture data as-is. Mip levels other than the first one are only available if the game stores them
+/// directly after the top level, which is the case for most (but not all) textures with a large buffer
+pub fn export_dds(tag: TagHash, mip: u32, path: &Path) -> anyhow::Result<()> {
+    let (desc, data, _) = Texture::load_raw(tag)?;
+    let format = DxgiFormat::from_wgpu(desc.format)
+        .with_context(|| format!("{:?} can't be stored in a DDS file", desc.format))?;
+
+    let layers = desc.array_size.max(desc.depth).max(1);
+    anyhow::ensure!(
+        mip == 0 || layers == 1,
+        "Mip selection is only supported for single layer textures"
+    );
+
+    let offset: usize = (0..mip)
+        .map(|level| mip_level_size(desc.format, desc.width, desc.height, level).2)
+        .sum();
+    let (width, height, layer_size) = mip_level_size(desc.format, desc.width, desc.height, mip);
+    let level_data = data
+        .get(offset..offset + layer_size * layers as usize)
+        .with_context(|| format!("Mip level {mip} is not present in the texture data"))?;
+
+    let mut f = BufWriter::new(File::create(path)?);
+    write_dds_header(&mut f, &desc, format, width, height, layer_size as u32)?;
+    f.write_all(level_data)?;
+
+    Ok(())
+}
+
+fn write_dds_header(
+    w: &mut impl Write,
+    desc: &TextureDesc,
+    format: DxgiFormat,
+    width: u32,
+    height: u32,
+    linear_size: u32,
+) -> std::io::Result<()> {
+    const DDSD_REQUIRED: u32 = 0x1 | 0x2 | 0x4 | 0x1000; // CAPS | HEIGHT | WIDTH | PIXELFORMAT
+    const DDSD_LINEARSIZE: u32 = 0x80000;
+    const DDSD_DEPTH: u32 = 0x800000;
+    const DDPF_FOURCC: u32 = 0x4;
+    const DDSCAPS_COMPLEX: u32 = 0x8;
+    const DDSCAPS_TEXTURE: u32 = 0x1000;
+    const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xFE00;
+    const DDSCAPS2_VOLUME: u32 = 0x200000;
+
+    let is_cube = desc.kind() == TextureType::TextureCube;
+    let is_volume = desc.kind() == TextureType::Texture3D;
+
+    let mut header = vec![
+        u32::from_le_bytes(*b"DDS "),
+        124,
+        DDSD_REQUIRED | DDSD_LINEARSIZE | if is_volume { DDSD_DEPTH } else { 0 },
+        height,
+        width,
+        linear_size,
+        if is_volume { desc.depth } else { 0 },
+        1, // Mip count
+    ];
+    header.extend([0; 11]);
+
+    // Pixel format, the actual format is stored in the DX10 header
+    header.extend([32, DDPF_FOURCC, u32::from_le_bytes(*b"DX10"), 0, 0, 0, 0, 0]);
+
+    header.extend([
+        DDSCAPS_TEXTURE
+            | if is_cube || is_volume {
+                DDSCAPS_COMPLEX
+            } else {
+                0
+            },
+        if is_cube {
+            DDSCAPS2_CUBEMAP_ALLFACES
+        } else if is_volume {
+            DDSCAPS2_VOLUME
+        } else {
+            0
+        },
+        0,
+        0,
+        0,
+    ]);
+
+    // DX10 header
+    header.extend([
+        u32::from(format),
+        if is_volume { 4 } else { 3 }, // Resource dimension
+        if is_cube { 0x4 } else { 0 }, // TEXTURECUBE
+        if is_cube {
+            desc.array_size / 6
+        } else if is_volume {
+            1
+        } else {
+            desc.array_size.max(1)
+        },
+        0,
+    ]);
+
+    for v in header {
+        w.write_all(&v.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Converts the texture through the GPU, then encodes it with the image crate
+fn export_images(
+    rs: &RenderState,
+    tag: TagHash,
+    options: TextureExportOptions,
+    out_dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let texture = Texture::load(rs, tag, false)?;
+    let images = (0..texture.desc.array_size.max(texture.desc.depth))
+        .map(|layer| {
+            texture
+                .to_image(rs, layer)
+                .map(|image| convert_image(image, &texture.desc, options))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let extension = options.format.extension();
+    let mut files = vec![];
+    if texture.desc.kind() == TextureType::TextureCube && images.len() == 6 {
+        let assembled = match options.cubemap_layout {
+            CubemapLayout::Faces => None,
+            CubemapLayout::Cross => Some(assemble_cubemap(&images)),
+            CubemapLayout::Strip => Some(assemble_cubemap_strip(&images)),
+        };
+
+        if let Some(image) = assembled {
+            let path = out_dir.join(format!("{tag}_cubemap.{extension}"));
+            image.save(&path)?;
+            files.push(path);
+            return Ok(files);
+        }
+    }
+
+    for (layer, image) in images.iter().enumerate() {
+        let path = out_dir.join(format!("{tag}_{layer}.{extension}"));
+        image.save(&path)?;
+        files.push(path);
+    }
+
+    Ok(files)
+}
+
+/// Applies the mip level and color space options to a captured layer
+///
+/// Captures are always sRGB encoded, so linear textures come out gamma-corrected unless converted back.
+/// Only the top mip level is uploaded to the GPU, so smaller levels are resampled from it
+fn convert_image(
+    image: DynamicImage,
+    desc: &TextureDesc,
+    options: TextureExportOptions,
+) -> DynamicImage {
+    let image = if options.mip > 0 {
+        let (width, height, _) = mip_level_size(desc.format, desc.width, desc.height, options.mip);
+        image.resize_exact(width, height, FilterType::Triangle)
+    } else {
+        image
+    };
+
+    let to_linear = match options.color_space {
+        ColorSpace::AsStored => !desc.format.is_srgb(),
+        ColorSpace::Srgb => false,
+        ColorSpace::Linear => true,
+    };
+
+    if !to_linear {
+        return image;
+    }
+
+    let mut rgba = image.into_rgba8();
+    decode_srgb(&mut rgba);
+    DynamicImage::from(rgba)
+}
+
+#[allow(clippy::erasing_op)]
+fn assemble_cubemap(images: &[DynamicImage]) -> DynamicImage {
+    let tile_w = images[0].width();
+    let tile_h = images[0].height();
+
+    let mut cubemap = DynamicImage::new_rgba8(tile_w * 4, tile_h * 3);
+
+    let x_pos = images[0].rotate90();
+    let x_neg = images[1].rotate270();
+    let y_pos = images[2].rotate180();
+    let y_neg = images[3].clone();
+    let z_pos = images[4].rotate90();
+    let z_neg = images[5].rotate90();
+
+    // -- Z+ -- --
+    // Y- X+ Y+ X-
+    // -- Z- -- --
+    let _ = cubemap.copy_from(&z_pos, tile_w, tile_h * 0);
+    let _ = cubemap.copy_from(&y_neg, tile_w * 0, tile_h);
+    let _ = cubemap.copy_from(&x_pos, tile_w, tile_h);
+    let _ = cubemap.copy_from(&y_pos, tile_w * 2, tile_h);
+    let _ = cubemap.copy_from(&x_neg, tile_w * 3, tile_h);
+    let _ = cubemap.copy_from(&z_neg, tile_w, tile_h * 2);
+
+    cubemap
+}
+
+fn assemble_cubemap_strip(images: &[DynamicImage]) -> DynamicImage {
+    let tile_w = images[0].width();
+    let tile_h = images[0].height();
+
+    let mut strip = DynamicImage::new_rgba8(tile_w * images.len() as u32, tile_h);
+    for (i, image) in images.iter().enumerate() {
+        let _ = strip.copy_from(image, tile_w * i as u32, 0);
+    }
+
+    strip
+}
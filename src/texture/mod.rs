@@ -1,6 +1,7 @@
 pub mod cache;
 mod capture;
 mod dxgi;
+pub mod export;
 mod headers_pc;
 mod headers_ps;
 mod headers_xbox;
@@ -17,7 +18,7 @@ use eframe::wgpu::TextureDimension;
 use headers_pc::TextureHeaderPC;
 use headers_ps::{TextureHeaderD2Ps4, TextureHeaderPs3, TextureHeaderRoiPs4};
 use headers_xbox::{TextureHeaderDevAlphaX360, TextureHeaderRoiXbox};
-use image::{DynamicImage, GenericImageView};
+use image::{DynamicImage, GenericImageView, RgbaImage};
 use swizzle::swizzle_ps::{GcmDeswizzler, GcnDeswizzler};
 use swizzle::swizzle_xbox::XenosDetiler;
 use swizzle::Deswizzler;
@@ -574,6 +575,21 @@ impl Texture {
         hash: TagHash,
         premultiply_alpha: bool,
     ) -> anyhow::Result<Texture> {
+        let (desc, data, comment) = Self::load_raw(hash)?;
+        Self::create_texture(
+            rs,
+            hash,
+            TextureDesc {
+                premultiply_alpha,
+                ..desc
+            },
+            data,
+            Some(comment),
+        )
+    }
+
+    /// Loads the unswizzled texture data without uploading it to the GPU
+    pub fn load_raw(hash: TagHash) -> anyhow::Result<(TextureDesc, Vec<u8>, String)> {
         match package_manager().version {
             GameVersion::Destiny(
                 DestinyVersion::DestinyInternalAlpha | DestinyVersion::DestinyTheTakenKing,
@@ -581,37 +597,33 @@ impl Texture {
                 PackagePlatform::X360 => {
                     let (texture, texture_data, comment) =
                         Self::load_data_devalpha_x360(hash, true)?;
-                    Self::create_texture(
-                        rs,
-                        hash,
+                    Ok((
                         TextureDesc {
                             format: texture.format.to_wgpu()?,
                             width: texture.width as u32,
                             height: texture.height as u32,
                             depth: texture.depth as u32,
                             array_size: texture.array_size as u32,
-                            premultiply_alpha,
+                            premultiply_alpha: false,
                         },
                         texture_data,
-                        Some(comment),
-                    )
+                        comment,
+                    ))
                 }
                 PackagePlatform::PS3 => {
                     let (texture, texture_data, comment) = Self::load_data_ps3_ttk(hash, true)?;
-                    Self::create_texture(
-                        rs,
-                        hash,
+                    Ok((
                         TextureDesc {
                             format: texture.format.to_wgpu()?,
                             width: texture.width as u32,
                             height: texture.height as u32,
                             depth: texture.depth as u32,
                             array_size: texture.array_size as u32,
-                            premultiply_alpha,
+                            premultiply_alpha: false,
                         },
                         texture_data,
-                        Some(comment),
-                    )
+                        comment,
+                    ))
                 }
                 _ => anyhow::bail!("Unsupported platform for legacy D1 textures"),
             },
@@ -621,39 +633,35 @@ impl Texture {
                 match package_manager().platform {
                     PackagePlatform::PS4 => {
                         let (texture, texture_data, comment) = Self::load_data_roi_ps4(hash, true)?;
-                        Self::create_texture(
-                            rs,
-                            hash,
+                        Ok((
                             TextureDesc {
                                 format: texture.format.to_wgpu()?,
                                 width: texture.width as u32,
                                 height: texture.height as u32,
                                 depth: texture.depth as u32,
                                 array_size: texture.array_size as u32,
-                                premultiply_alpha,
+                                premultiply_alpha: false,
                             },
                             texture_data,
-                            Some(comment),
-                        )
+                            comment,
+                        ))
                     }
                     PackagePlatform::XboxOne => {
                         // anyhow::bail!("Xbox One textures are not supported yet");
                         let (texture, texture_data, comment) =
                             Self::load_data_roi_xone(hash, true)?;
-                        Self::create_texture(
-                            rs,
-                            hash,
+                        Ok((
                             TextureDesc {
                                 format: texture.format.to_wgpu()?,
                                 width: texture.width as u32,
                                 height: texture.height as u32,
                                 depth: texture.depth as u32,
                                 array_size: texture.array_size as u32,
-                                premultiply_alpha,
+                                premultiply_alpha: false,
                             },
                             texture_data,
-                            Some(comment),
-                        )
+                            comment,
+                        ))
                     }
                     _ => unreachable!("Unsupported platform for RoI textures"),
                 }
@@ -669,20 +677,18 @@ impl Texture {
             )
             | GameVersion::Marathon(MarathonVersion::MarathonAlpha) => {
                 let (texture, texture_data, comment) = Self::load_data_d2(hash, true)?;
-                Self::create_texture(
-                    rs,
-                    hash,
+                Ok((
                     TextureDesc {
                         format: texture.format,
                         width: texture.width as u32,
                         height: texture.height as u32,
                         depth: texture.depth as u32,
                         array_size: texture.array_size as u32,
-                        premultiply_alpha,
+                        premultiply_alpha: false,
                     },
                     texture_data,
-                    Some(comment),
-                )
+                    comment,
+                ))
             }
         }
     }
@@ -823,6 +829,37 @@ impl Texture {
 
         Ok(DynamicImage::from(image).crop(0, 0, self.desc.width, self.desc.height))
     }
+
+    /// Captures a layer with the values as they're stored in the texture
+    ///
+    /// Captures are always sRGB encoded, which skews the values of linear textures (normal maps, masks, etc.)
+    pub fn to_stored_image(&self, rs: &RenderState, layer: u32) -> anyhow::Result<RgbaImage> {
+        let mut image = self.to_image(rs, layer)?.into_rgba8();
+        if !self.desc.format.is_srgb() {
+            decode_srgb(&mut image);
+        }
+
+        Ok(image)
+    }
+}
+
+/// Converts the color channels of an sRGB encoded image to linear values in-place
+pub fn decode_srgb(image: &mut RgbaImage) {
+    let lut: [u8; 256] = std::array::from_fn(|i| {
+        let c = i as f32 / 255.0;
+        let linear = if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        };
+        (linear * 255.0).round() as u8
+    });
+
+    for pixel in image.pixels_mut() {
+        for c in &mut pixel.0[..3] {
+            *c = lut[*c as usize];
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 use eframe::{
     egui::{self, *},
     emath::Rot2,
@@ -36,6 +38,28 @@ pub fn format_file_size(size: usize) -> String {
     }
 }
 
+/// Shannon entropy of the data in bits per byte, ranging from 0.0 (a single repeated byte) to 8.0 (random/compressed data)
+pub fn byte_entropy(data: &[u8]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0usize; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+
+    let len = data.len() as f32;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f32 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
 pub fn ui_image_rotated(
     painter: &egui::Painter,
     texture_id: egui::TextureId,
@@ -60,3 +84,99 @@ pub fn ui_image_rotated(
 
     painter.add(egui::Shape::mesh(mesh));
 }
+
+/// Resolves a user-provided path, translating Wine/Proton drive paths and matching path components case-insensitively when the exact path doesn't exist
+pub fn resolve_user_path(path: &str) -> PathBuf {
+    let path = translate_wine_path(path.trim().trim_matches('"'));
+    if path.exists() {
+        return path;
+    }
+
+    resolve_case_insensitive(&path).unwrap_or(path)
+}
+
+/// Maps DOS drive paths (eg. from a config written inside a Wine/Proton prefix) to the host filesystem
+#[cfg(not(windows))]
+fn translate_wine_path(path: &str) -> PathBuf {
+    let bytes = path.as_bytes();
+    if bytes.len() < 2 || !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' {
+        return PathBuf::from(path);
+    }
+
+    let drive = bytes[0].to_ascii_lowercase() as char;
+    let rest = path[2..].replace('\\', "/");
+    let rest = rest.trim_start_matches('/');
+
+    // Z: is mapped to the root of the host filesystem by default
+    if drive == 'z' {
+        return Path::new("/").join(rest);
+    }
+
+    if let Some(prefix) = wine_prefix() {
+        let dosdevice = prefix.join("dosdevices").join(format!("{drive}:"));
+        if dosdevice.exists() {
+            return dosdevice.join(rest);
+        }
+
+        if drive == 'c' {
+            return prefix.join("drive_c").join(rest);
+        }
+    }
+
+    PathBuf::from(path)
+}
+
+/// Unix paths passed to a Windows build running under Wine are reachable through the Z: drive
+#[cfg(windows)]
+fn translate_wine_path(path: &str) -> PathBuf {
+    if path.starts_with('/') && !Path::new(path).exists() {
+        let z_path = PathBuf::from(format!("Z:{}", path.replace('/', "\\")));
+        if z_path.exists() {
+            return z_path;
+        }
+    }
+
+    PathBuf::from(path)
+}
+
+#[cfg(not(windows))]
+fn wine_prefix() -> Option<PathBuf> {
+    if let Some(prefix) = std::env::var_os("WINEPREFIX") {
+        return Some(prefix.into());
+    }
+
+    // Proton keeps the actual prefix in a pfx subdirectory of the compatdata folder
+    if let Some(compat_data) = std::env::var_os("STEAM_COMPAT_DATA_PATH") {
+        return Some(PathBuf::from(compat_data).join("pfx"));
+    }
+
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".wine"))
+}
+
+/// Finds an existing path that matches the given one when ignoring the case of each component
+fn resolve_case_insensitive(path: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        let candidate = resolved.join(component);
+        if candidate.exists() {
+            resolved = candidate;
+            continue;
+        }
+
+        let name = component.as_os_str().to_str()?;
+        let parent = if resolved.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            resolved.as_path()
+        };
+
+        let matched = std::fs::read_dir(parent).ok()?.flatten().find(|e| {
+            e.file_name()
+                .to_str()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        })?;
+        resolved.push(matched.file_name());
+    }
+
+    Some(resolved)
+}
@@ -0,0 +1,330 @@
+//! Patch watchdog: periodically checks the packages directory for changes, rebuilds the tag cache when the game was
+//! patched and writes a report of what changed (new tags, new strings, tags with a changed structure)
+//!
+//! The watchdog thread only detects changes. Rebuilding goes through the regular cache loading in the GUI, which
+//! takes a [`PatchSnapshot`] of the old cache beforehand and compares it to the new one once it's loaded.
+//! The cache is rebuilt from scratch, so this can take a while on large installations.
+//!
+//! Tags that kept their 64-bit hash but got a new 32-bit hash are reported as moved rather than removed and added,
+//! see [`crate::tag_identity`].
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use eframe::egui;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use log::{error, info};
+use parking_lot::Mutex;
+use quicktag_core::class_mapping::to_current_era;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::{create_stringmap, unload_languages, StringCache};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+use tiger_pkg::{package_manager, PackageManager, TagHash};
+
+use crate::config;
+use crate::tag_data;
+use crate::tag_identity::{Hash64Table, IdentityMap};
+
+/// How often the watchdog thread wakes up to check whether the interval has passed or the settings changed
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// New strings beyond this are only counted in the text report
+const MAX_LISTED_STRINGS: usize = 500;
+
+static PATCH_DETECTED: AtomicBool = AtomicBool::new(false);
+/// Timestamp of the currently loaded cache, see [`TagCache::timestamp`]
+static CACHE_TIMESTAMP: AtomicU64 = AtomicU64::new(u64::MAX);
+
+lazy_static! {
+    /// Strings created for the new packages while rebuilding, picked up by the GUI once the cache is loaded
+    static ref REBUILT_STRINGS: Mutex<Option<StringCache>> = Mutex::new(None);
+}
+
+/// Starts the watchdog thread. It idles while the watchdog is disabled in the settings
+pub fn spawn(ctx: egui::Context) {
+    std::thread::Builder::new()
+        .name("patch_watchdog".to_string())
+        .spawn(move || {
+            let mut last_check = Instant::now();
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+
+                let settings = config::with(|c| c.watchdog.clone());
+                let interval = Duration::from_secs(settings.interval_minutes.max(1) as u64 * 60);
+                if !settings.enabled || last_check.elapsed() < interval {
+                    continue;
+                }
+
+                last_check = Instant::now();
+                let cache_timestamp = CACHE_TIMESTAMP.load(Ordering::Relaxed);
+                if cache_timestamp != u64::MAX
+                    && TagCache::packages_timestamp() > cache_timestamp
+                    && !PATCH_DETECTED.swap(true, Ordering::Relaxed)
+                {
+                    info!("Watchdog: packages directory changed, rebuilding cache");
+                    ctx.request_repaint();
+                }
+            }
+        })
+        .expect("Failed to spawn watchdog thread");
+}
+
+/// Called when a cache has been loaded, changes are detected relative to its timestamp
+pub fn set_cache_timestamp(timestamp: u64) {
+    CACHE_TIMESTAMP.store(timestamp, Ordering::Relaxed);
+}
+
+/// Returns true (once) when the watchdog found that the packages have changed
+pub fn take_patch_detected() -> bool {
+    PATCH_DETECTED.swap(false, Ordering::Relaxed)
+}
+
+/// Re-opens the packages directory and reads the strings of the new packages. Runs on the cache loading thread
+pub fn reload_packages() {
+    let (package_dir, version) = {
+        let pm = package_manager();
+        (pm.package_dir.clone(), pm.version)
+    };
+
+    match PackageManager::new(package_dir, version, None) {
+        Ok(pm) => {
+            tiger_pkg::initialize(&Arc::new(pm));
+            tag_data::clear();
+        }
+        Err(e) => {
+            error!("Watchdog: failed to reload packages, the cache will be rebuilt with the old package list: {e:?}");
+            return;
+        }
+    }
+
+    // The resident languages were read from the old packages
+    unload_languages();
+    match create_stringmap() {
+        Ok(strings) => *REBUILT_STRINGS.lock() = Some(strings),
+        Err(e) => error!("Watchdog: failed to read strings: {e:?}"),
+    }
+}
+
+/// Strings read by [`reload_packages`], if the packages were reloaded
+pub fn take_rebuilt_strings() -> Option<StringCache> {
+    REBUILT_STRINGS.lock().take()
+}
+
+/// The parts of the old cache that are compared against the rebuilt one
+pub struct PatchSnapshot {
+    timestamp: u64,
+    /// Array classes of every tag, in order
+    tag_classes: FxHashMap<TagHash, Vec<u32>>,
+    strings: Arc<StringCache>,
+    /// 64-bit hash table of the packages before they were reloaded
+    hash64: Hash64Table,
+}
+
+impl PatchSnapshot {
+    pub fn new(cache: &TagCache, strings: Arc<StringCache>) -> Self {
+        Self {
+            timestamp: cache.timestamp,
+            tag_classes: cache
+                .hashes
+                .iter()
+                .map(|(tag, scan)| (*tag, scan.fingerprint.array_classes.clone()))
+                .collect(),
+            strings,
+            hash64: Hash64Table::current(),
+        }
+    }
+
+    /// Compares the snapshot to the new cache. Must be called after the packages have been reloaded, as the new 64-bit
+    /// hash table is read from the package manager
+    pub fn report(&self, cache: &TagCache, strings: &StringCache) -> PatchReport {
+        let identity = IdentityMap::new(&self.hash64, &Hash64Table::current());
+
+        // Old tags by the hash they have now. Moved tags go last, they can take over the old hash of another tag.
+        // Classes are translated in case the snapshot was taken on the other side of Beyond Light
+        let tag_classes: FxHashMap<TagHash, Vec<u32>> = self
+            .tag_classes
+            .iter()
+            .sorted_by_key(|(tag, _)| identity.resolve(**tag) != **tag)
+            .map(|(tag, classes)| {
+                (
+                    identity.resolve(*tag),
+                    classes.iter().map(|c| to_current_era(*c)).collect(),
+                )
+            })
+            .collect();
+
+        let mut new_tags = vec![];
+        let mut changed_tags = vec![];
+        for (tag, scan) in &cache.hashes {
+            match tag_classes.get(tag) {
+                None => new_tags.push(*tag),
+                Some(classes) if *classes != scan.fingerprint.array_classes => {
+                    changed_tags.push(*tag)
+                }
+                Some(_) => {}
+            }
+        }
+        new_tags.sort_by_key(|t| t.0);
+        changed_tags.sort_by_key(|t| t.0);
+
+        let removed_tags = self
+            .tag_classes
+            .keys()
+            .filter(|tag| !cache.hashes.contains_key(&identity.resolve(**tag)))
+            .copied()
+            .sorted_by_key(|t| t.0)
+            .collect_vec();
+
+        let moved_tags = identity
+            .moved()
+            .filter(|(_, new)| cache.hashes.contains_key(new))
+            .sorted_by_key(|(old, _)| old.0)
+            .collect_vec();
+
+        let old_classes: FxHashSet<u32> = tag_classes.values().flatten().copied().collect();
+        let new_classes = cache
+            .hashes
+            .values()
+            .flat_map(|scan| scan.fingerprint.array_classes.iter().copied())
+            .filter(|class| !old_classes.contains(class))
+            .unique()
+            .sorted()
+            .collect_vec();
+
+        let new_strings = strings
+            .iter()
+            .flat_map(|(hash, strings)| {
+                let old = self.strings.get(hash);
+                strings
+                    .iter()
+                    .filter(move |s| old.is_none_or(|old| !old.contains(s)))
+                    .map(move |s| (*hash, s.clone()))
+            })
+            .sorted()
+            .collect_vec();
+
+        PatchReport {
+            old_timestamp: self.timestamp,
+            new_timestamp: cache.timestamp,
+            new_tags,
+            removed_tags,
+            changed_tags,
+            moved_tags,
+            new_classes,
+            new_strings,
+            identity,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PatchReport {
+    pub old_timestamp: u64,
+    pub new_timestamp: u64,
+    #[serde(serialize_with = "serialize_tags")]
+    pub new_tags: Vec<TagHash>,
+    #[serde(serialize_with = "serialize_tags")]
+    pub removed_tags: Vec<TagHash>,
+    /// Tags that exist in both caches, but with different array classes
+    #[serde(serialize_with = "serialize_tags")]
+    pub changed_tags: Vec<TagHash>,
+    /// Tags with the same 64-bit hash that got a new 32-bit hash, as (old, new)
+    #[serde(serialize_with = "serialize_moved_tags")]
+    pub moved_tags: Vec<(TagHash, TagHash)>,
+    /// Array classes that didn't appear in any tag before
+    pub new_classes: Vec<u32>,
+    pub new_strings: Vec<(u32, String)>,
+    #[serde(skip)]
+    pub identity: IdentityMap,
+}
+
+fn serialize_tags<S: serde::Serializer>(
+    tags: &[TagHash],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(tags.iter().map(|t| t.to_string()))
+}
+
+fn serialize_moved_tags<S: serde::Serializer>(
+    tags: &[(TagHash, TagHash)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        tags.iter()
+            .map(|(old, new)| (old.to_string(), new.to_string())),
+    )
+}
+
+impl PatchReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} new tags, {} removed tags, {} changed tags, {} moved tags, {} new classes, {} new strings",
+            self.new_tags.len(),
+            self.removed_tags.len(),
+            self.changed_tags.len(),
+            self.moved_tags.len(),
+            self.new_classes.len(),
+            self.new_strings.len()
+        )
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("Patch report\n{}\n", self.summary());
+
+        text += "\nNew classes:\n";
+        for class in &self.new_classes {
+            text += &format!("  {class:08X}\n");
+        }
+
+        text += "\nNew strings:\n";
+        for (hash, string) in self.new_strings.iter().take(MAX_LISTED_STRINGS) {
+            text += &format!("  {hash:08X}: {}\n", string.replace('\n', "\\n"));
+        }
+        if self.new_strings.len() > MAX_LISTED_STRINGS {
+            text += &format!(
+                "  ... and {} more (see the JSON report)\n",
+                self.new_strings.len() - MAX_LISTED_STRINGS
+            );
+        }
+
+        for (label, tags) in [
+            ("New tags", &self.new_tags),
+            ("Removed tags", &self.removed_tags),
+            ("Changed tags", &self.changed_tags),
+        ] {
+            text += &format!("\n{label}:\n");
+            for tag in tags {
+                text += &format!("  {tag}\n");
+            }
+        }
+
+        text += "\nMoved tags:\n";
+        for (old, new) in &self.moved_tags {
+            text += &format!("  {old} -> {new}\n");
+        }
+
+        text
+    }
+
+    /// Writes the report as `patch_<date>.json` and `patch_<date>.txt` to the configured output directory, returns
+    /// the path to the text report
+    pub fn write(&self) -> anyhow::Result<PathBuf> {
+        let dir = config::data_path(config::with(|c| c.watchdog.output_dir.clone()));
+        std::fs::create_dir_all(&dir).context("Failed to create report directory")?;
+
+        let name = format!("patch_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        std::fs::write(
+            dir.join(format!("{name}.json")),
+            serde_json::to_string_pretty(self)?,
+        )?;
+
+        let path = dir.join(format!("{name}.txt"));
+        std::fs::write(&path, self.to_text())?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+
+use lazy_static::lazy_static;
+use log::{error, info};
+use parking_lot::RwLock;
+use quicktag_scanner::TagCache;
+use quicktag_strings::localized::{
+    StringCache, StringContainer, StringContainerD1, StringContainerD1FirstLook,
+};
+use rustc_hash::FxHasher;
+use serde::{Deserialize, Serialize};
+use tiger_pkg::{package_manager, DestinyVersion, GameVersion, TagHash};
+
+use crate::backups;
+use crate::config;
+use crate::shutdown::DirtyFlag;
+use crate::tag_identity::IdentityMap;
+
+pub const WATCHLIST_PATH: &str = "watchlist.json";
+
+lazy_static! {
+    static ref WATCHLIST: RwLock<WatchList> = RwLock::new(WatchList::default());
+}
+
+/// Strings and tags the user wants to be notified about when they change, along with their contents at the last check
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct WatchList {
+    pub string_containers: Vec<WatchedStringContainer>,
+    pub strings: Vec<WatchedString>,
+    pub tags: Vec<WatchedTag>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WatchedStringContainer {
+    #[serde(with = "hex_u32")]
+    pub tag: u32,
+    /// Strings in the container, keyed by string hash
+    pub snapshot: BTreeMap<u32, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WatchedString {
+    #[serde(with = "hex_u32")]
+    pub hash: u32,
+    /// Every string with this hash (there can be collisions)
+    pub snapshot: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WatchedTag {
+    #[serde(with = "hex_u32")]
+    pub tag: u32,
+    /// Hash of the tag data, None if the tag couldn't be read
+    pub content_hash: Option<u64>,
+    pub size: usize,
+    /// Tags referenced by this tag
+    pub references: Vec<u32>,
+    /// Tags referencing this tag
+    pub referenced_by: Vec<u32>,
+}
+
+pub enum StringChange {
+    Added { hash: u32, new: String },
+    Removed { hash: u32, old: String },
+    Modified { hash: u32, old: String, new: String },
+}
+
+/// Changes found for a single watched container or string
+pub struct StringWatchReport {
+    pub label: String,
+    /// The watched container, if any
+    pub container: Option<TagHash>,
+    pub changes: Vec<StringChange>,
+}
+
+/// Changes found for a single watched tag
+pub struct TagWatchReport {
+    pub tag: TagHash,
+    pub content_changed: bool,
+    pub old_size: usize,
+    pub new_size: usize,
+    pub added_references: Vec<TagHash>,
+    pub removed_references: Vec<TagHash>,
+    pub added_referenced_by: Vec<TagHash>,
+    pub removed_referenced_by: Vec<TagHash>,
+}
+
+impl TagWatchReport {
+    pub fn size_delta(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.content_changed
+            || self.old_size != self.new_size
+            || !self.added_references.is_empty()
+            || !self.removed_references.is_empty()
+            || !self.added_referenced_by.is_empty()
+            || !self.removed_referenced_by.is_empty()
+    }
+}
+
+impl WatchList {
+    pub fn is_container_watched(&self, tag: TagHash) -> bool {
+        self.string_containers.iter().any(|c| c.tag == tag.0)
+    }
+
+    pub fn is_string_watched(&self, hash: u32) -> bool {
+        self.strings.iter().any(|s| s.hash == hash)
+    }
+
+    pub fn watch_container(&mut self, tag: TagHash, strings: &StringCache) {
+        if !self.is_container_watched(tag) {
+            self.string_containers.push(WatchedStringContainer {
+                tag: tag.0,
+                snapshot: snapshot_container(tag, strings),
+            });
+        }
+    }
+
+    pub fn watch_string(&mut self, hash: u32, strings: &StringCache) {
+        if !self.is_string_watched(hash) {
+            self.strings.push(WatchedString {
+                hash,
+                snapshot: current_strings(hash, strings),
+            });
+        }
+    }
+
+    pub fn is_tag_watched(&self, tag: TagHash) -> bool {
+        self.tags.iter().any(|t| t.tag == tag.0)
+    }
+
+    pub fn watch_tag(&mut self, tag: TagHash, cache: &TagCache) {
+        if !self.is_tag_watched(tag) {
+            self.tags.push(snapshot_tag(tag, cache));
+        }
+    }
+
+    /// Updates the watched tags and their snapshot references to the hashes they have after a patch, so tags that
+    /// moved aren't reported as changed
+    pub fn remap_tags(&mut self, identity: &IdentityMap) {
+        let remap = |hashes: &mut Vec<u32>| {
+            for hash in hashes.iter_mut() {
+                *hash = identity.resolve(TagHash(*hash)).0;
+            }
+            hashes.sort_unstable();
+            hashes.dedup();
+        };
+
+        for watched in &mut self.tags {
+            watched.tag = identity.resolve(TagHash(watched.tag)).0;
+            remap(&mut watched.references);
+            remap(&mut watched.referenced_by);
+        }
+    }
+
+    /// Compares every watched tag against the current tag data and cache, then updates the snapshots
+    ///
+    /// An empty cache (eg. while it's still loading) doesn't have any references, so it's skipped entirely
+    pub fn check_tags(&mut self, cache: &TagCache) -> Vec<TagWatchReport> {
+        if cache.hashes.is_empty() {
+            return vec![];
+        }
+
+        let mut reports = vec![];
+        for watched in &mut self.tags {
+            let tag = TagHash(watched.tag);
+            let current = snapshot_tag(tag, cache);
+            let (added_references, removed_references) =
+                diff_references(&watched.references, &current.references);
+            let (added_referenced_by, removed_referenced_by) =
+                diff_references(&watched.referenced_by, &current.referenced_by);
+
+            let report = TagWatchReport {
+                tag,
+                content_changed: watched.content_hash != current.content_hash,
+                old_size: watched.size,
+                new_size: current.size,
+                added_references,
+                removed_references,
+                added_referenced_by,
+                removed_referenced_by,
+            };
+            if report.has_changes() {
+                reports.push(report);
+            }
+
+            *watched = current;
+        }
+
+        reports
+    }
+
+    /// Compares every watched string against the current strings, then updates the snapshots
+    pub fn check_strings(&mut self, strings: &StringCache) -> Vec<StringWatchReport> {
+        let mut reports = vec![];
+        for container in &mut self.string_containers {
+            let tag = TagHash(container.tag);
+            let current = snapshot_container(tag, strings);
+            let changes = diff_snapshots(&container.snapshot, &current);
+            if !changes.is_empty() {
+                reports.push(StringWatchReport {
+                    label: format!("String container {tag}"),
+                    container: Some(tag),
+                    changes,
+                });
+            }
+
+            container.snapshot = current;
+        }
+
+        for watched in &mut self.strings {
+            let current = current_strings(watched.hash, strings);
+            if current != watched.snapshot {
+                let old = watched.snapshot.join(" | ");
+                let new = current.join(" | ");
+                let hash = watched.hash;
+                reports.push(StringWatchReport {
+                    label: format!("String {hash:08X}"),
+                    container: None,
+                    changes: vec![match (old.is_empty(), new.is_empty()) {
+                        (true, _) => StringChange::Added { hash, new },
+                        (_, true) => StringChange::Removed { hash, old },
+                        _ => StringChange::Modified { hash, old, new },
+                    }],
+                });
+            }
+
+            watched.snapshot = current;
+        }
+
+        reports
+    }
+}
+
+fn diff_snapshots(old: &BTreeMap<u32, String>, new: &BTreeMap<u32, String>) -> Vec<StringChange> {
+    let mut changes = vec![];
+    for (&hash, old_string) in old {
+        match new.get(&hash) {
+            None => changes.push(StringChange::Removed {
+                hash,
+                old: old_string.clone(),
+            }),
+            Some(new_string) if new_string != old_string => changes.push(StringChange::Modified {
+                hash,
+                old: old_string.clone(),
+                new: new_string.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    for (&hash, new_string) in new {
+        if !old.contains_key(&hash) {
+            changes.push(StringChange::Added {
+                hash,
+                new: new_string.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Returns the (added, removed) tags between two sorted reference lists
+fn diff_references(old: &[u32], new: &[u32]) -> (Vec<TagHash>, Vec<TagHash>) {
+    let added = new
+        .iter()
+        .filter(|t| old.binary_search(t).is_err())
+        .map(|&t| TagHash(t))
+        .collect();
+    let removed = old
+        .iter()
+        .filter(|t| new.binary_search(t).is_err())
+        .map(|&t| TagHash(t))
+        .collect();
+    (added, removed)
+}
+
+fn snapshot_tag(tag: TagHash, cache: &TagCache) -> WatchedTag {
+    let data = package_manager().read_tag(tag).ok();
+    let content_hash = data.as_ref().map(|data| {
+        let mut hasher = FxHasher::default();
+        hasher.write(data);
+        hasher.finish()
+    });
+
+    let (mut references, mut referenced_by) = cache
+        .hashes
+        .get(&tag)
+        .map(|scan| {
+            (
+                scan.file_hashes
+                    .iter()
+                    .map(|h| h.hash.0)
+                    .collect::<Vec<_>>(),
+                scan.references.iter().map(|t| t.0).collect::<Vec<_>>(),
+            )
+        })
+        .unwrap_or_default();
+    references.sort_unstable();
+    references.dedup();
+    referenced_by.sort_unstable();
+    referenced_by.dedup();
+
+    WatchedTag {
+        tag: tag.0,
+        content_hash,
+        size: data.map(|d| d.len()).unwrap_or_default(),
+        references,
+        referenced_by,
+    }
+}
+
+/// Reference IDs of localized string containers across game versions
+pub fn is_string_container(reference: u32) -> bool {
+    matches!(reference, 0x808099EF | 0x80809A88 | 0x8080035A)
+}
+
+fn container_string_hashes(tag: TagHash) -> Option<Vec<u32>> {
+    match package_manager().version {
+        GameVersion::Destiny(DestinyVersion::DestinyTheTakenKing) => package_manager()
+            .read_tag_binrw::<StringContainerD1>(tag)
+            .ok()
+            .map(|c| c.string_hashes.data().to_vec()),
+        GameVersion::Destiny(DestinyVersion::DestinyFirstLookAlpha) => package_manager()
+            .read_tag_binrw::<StringContainerD1FirstLook>(tag)
+            .ok()
+            .map(|c| c.string_hashes.data().to_vec()),
+        GameVersion::Destiny(DestinyVersion::DestinyInternalAlpha) => None,
+        _ => package_manager()
+            .read_tag_binrw::<StringContainer>(tag)
+            .ok()
+            .map(|c| c.string_hashes.data().to_vec()),
+    }
+}
+
+/// Strings for a hash in a stable order, as the string cache doesn't order collisions
+fn current_strings(hash: u32, strings: &StringCache) -> Vec<String> {
+    let mut current = strings.get(&hash).cloned().unwrap_or_default();
+    current.sort();
+    current
+}
+
+fn snapshot_container(tag: TagHash, strings: &StringCache) -> BTreeMap<u32, String> {
+    container_string_hashes(tag)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|hash| (hash, current_strings(hash, strings).join(" | ")))
+        .collect()
+}
+
+/// Loads the watch list (if it exists)
+pub fn load() {
+    let path = config::data_path(WATCHLIST_PATH);
+    if !path.exists() {
+        return;
+    }
+
+    match std::fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|s| Ok(serde_json::from_str::<WatchList>(&s)?))
+    {
+        Ok(w) => {
+            info!("Loaded watch list from {}", path.display());
+            *WATCHLIST.write() = w;
+        }
+        Err(e) => {
+            error!("Failed to load watch list: {e:?}");
+        }
+    }
+}
+
+pub static DIRTY: DirtyFlag = DirtyFlag::new();
+
+fn save(watchlist: &WatchList) -> anyhow::Result<()> {
+    backups::backup_before_write(WATCHLIST_PATH);
+    std::fs::write(
+        config::data_path(WATCHLIST_PATH),
+        serde_json::to_string_pretty(watchlist)?,
+    )?;
+    Ok(())
+}
+
+/// Reads the watch list
+pub fn with<R>(f: impl FnOnce(&WatchList) -> R) -> R {
+    f(&WATCHLIST.read())
+}
+
+/// Modifies the watch list, then saves it
+pub fn with_mut<R>(f: impl FnOnce(&mut WatchList) -> R) -> R {
+    let mut watchlist = WATCHLIST.write();
+    let result = f(&mut watchlist);
+    save_or_mark_dirty(&watchlist);
+    result
+}
+
+fn save_or_mark_dirty(watchlist: &WatchList) {
+    DIRTY.record("watch list", save(watchlist));
+}
+
+/// Saves the watch list as it is now, see [`crate::shutdown::flush`]
+pub fn save_current() {
+    save_or_mark_dirty(&WATCHLIST.read());
+}
+
+/// Stores hashes as hex strings, so the file stays readable
+mod hex_u32 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{value:08X}"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        u32::from_str_radix(&s, 16).map_err(serde::de::Error::custom)
+    }
+}
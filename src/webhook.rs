@@ -0,0 +1,119 @@
+//! Posts patch report summaries to a webhook. Payloads use Discord's embed format, other services that accept
+//! JSON will receive the same payload
+
+use std::path::Path;
+
+use log::{error, info};
+use serde_json::{json, Value};
+use tiger_pkg::{package_manager, Version};
+
+use crate::gui::TOASTS;
+use crate::watchdog::PatchReport;
+
+/// Amount of new strings and tags listed in the message, the full lists are in the written report
+const MAX_LISTED: usize = 10;
+/// Discord rejects field values longer than 1024 characters
+const MAX_FIELD_LENGTH: usize = 1000;
+const EMBED_COLOR: u32 = 0xF2A43A;
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() > max_chars {
+        format!("{}…", s.chars().take(max_chars - 1).collect::<String>())
+    } else {
+        s.to_string()
+    }
+}
+
+/// Lines joined into a code block, cut off before they exceed Discord's field length limit
+fn code_block(lines: impl Iterator<Item = String>) -> String {
+    let mut block = String::new();
+    for line in lines {
+        if block.len() + line.len() + 1 > MAX_FIELD_LENGTH - 8 {
+            break;
+        }
+        block += &line;
+        block.push('\n');
+    }
+
+    format!("```\n{block}```")
+}
+
+/// Discord embed with the counts, the first few new strings and `quicktag://` links to new tags. `title` says what
+/// the report compares, eg. "Patch detected" or "Cache regenerated"
+pub fn patch_report_payload(report: &PatchReport, report_path: &Path, title: &str) -> Value {
+    let mut fields = vec![];
+    for (name, count) in [
+        ("New tags", report.new_tags.len()),
+        ("Removed tags", report.removed_tags.len()),
+        ("Changed tags", report.changed_tags.len()),
+        ("Moved tags", report.moved_tags.len()),
+        ("New classes", report.new_classes.len()),
+        ("New strings", report.new_strings.len()),
+    ] {
+        fields.push(json!({ "name": name, "value": count.to_string(), "inline": true }));
+    }
+
+    let strings = report
+        .new_strings
+        .iter()
+        .filter(|(_, s)| !s.trim().is_empty())
+        .take(MAX_LISTED)
+        .map(|(hash, s)| format!("{hash:08X} {}", truncate(&s.replace('\n', " "), 80)));
+    if !report.new_strings.is_empty() {
+        fields.push(json!({ "name": "Top new strings", "value": code_block(strings) }));
+    }
+
+    if !report.new_classes.is_empty() {
+        fields.push(json!({
+            "name": "New classes",
+            "value": code_block(report.new_classes.iter().take(MAX_LISTED).map(|c| format!("{c:08X}"))),
+        }));
+    }
+
+    if !report.new_tags.is_empty() {
+        let links = report
+            .new_tags
+            .iter()
+            .take(MAX_LISTED)
+            .map(|tag| format!("`quicktag://{tag}`"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        fields.push(json!({ "name": "New tags", "value": truncate(&links, MAX_FIELD_LENGTH) }));
+    }
+
+    json!({
+        "username": "quicktag",
+        "embeds": [{
+            "title": format!("{title} ({})", package_manager().version.name()),
+            "description": format!("{}\nReport: `{}`", report.summary(), report_path.display()),
+            "color": EMBED_COLOR,
+            "fields": fields,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }],
+    })
+}
+
+/// Posts the payload on a background thread, failures are logged and shown as a toast
+pub fn post(url: String, payload: Value) {
+    std::thread::spawn(move || {
+        let result = ureq::post(&url)
+            .set("Content-Type", "application/json")
+            .send_string(&payload.to_string());
+
+        // The URL contains the webhook token, so it's kept out of the error message
+        let result = result.map_err(|e| match e {
+            ureq::Error::Status(status, _) => format!("HTTP status {status}"),
+            ureq::Error::Transport(t) => t.kind().to_string(),
+        });
+
+        match result {
+            Ok(_) => info!("Posted patch report to webhook"),
+            Err(e) => {
+                error!("Failed to post patch report to webhook: {e}");
+                TOASTS
+                    .lock()
+                    .error(format!("Failed to post patch report to webhook: {e}"));
+            }
+        }
+    });
+}